@@ -0,0 +1,260 @@
+use crate::parse::Command;
+use proc_macro2::Span;
+
+/// The command categories of ACL rules such as `+@read`, as listed by `ACL CAT`
+const CATEGORIES: &[&str] = &[
+    "admin",
+    "all",
+    "bitmap",
+    "blocking",
+    "connection",
+    "dangerous",
+    "fast",
+    "geo",
+    "hash",
+    "hyperloglog",
+    "keyspace",
+    "list",
+    "pubsub",
+    "read",
+    "scripting",
+    "set",
+    "slow",
+    "sortedset",
+    "stream",
+    "string",
+    "transaction",
+    "write",
+];
+
+/// What an ACL rule changes, to find rules that undo the ones before them
+#[derive(Clone, Copy, PartialEq)]
+enum Rule {
+    /// `on` or `off`
+    State(bool),
+    /// `nopass`
+    NoPass,
+    /// `>password`, `<password`, `#hash` or `!hash`
+    Password,
+    /// `~pattern`, `%R~pattern` or `allkeys`
+    Keys,
+    /// `&pattern` or `allchannels`
+    Channels,
+    /// `+command`, `-command`, `+@category` or `-@category`
+    Commands,
+    /// `allcommands`, `nocommands`, `+@all` or `-@all`, which replace every command rule
+    AllCommands,
+    /// `(rules)` and `clearselectors`
+    Selectors,
+    /// `reset`, `resetpass`, `resetkeys` and `resetchannels`, which discard the rules of their
+    /// kind, or every rule for `reset`
+    Reset(Option<&'static Rule>),
+}
+
+/// Check the literal rules of `ACL SETUSER` commands, so that typos in rules and categories, and
+/// rules that are undone by a later rule, fail to compile instead of when the user is created.
+/// Rules that are only known at runtime are skipped.
+pub(crate) fn check_acl_setuser(command: &Command) -> syn::Result<()> {
+    let [name, sub, ..] = command.args.as_slice() else {
+        return Ok(());
+    };
+    let is_setuser = name
+        .word()
+        .is_some_and(|name| name.eq_ignore_ascii_case("ACL"))
+        && sub
+            .word()
+            .is_some_and(|sub| sub.eq_ignore_ascii_case("SETUSER"));
+    if !is_setuser {
+        return Ok(());
+    }
+    let rules = command
+        .args
+        .iter()
+        .skip(3)
+        .filter_map(|arg| Some((arg.word()?, arg.span)));
+    check_rules(rules)
+}
+
+fn check_rules(rules: impl Iterator<Item = (String, Span)>) -> syn::Result<()> {
+    let mut seen: Vec<(Rule, String)> = vec![];
+    for (text, span) in rules {
+        let rule = parse_rule(&text, span)?;
+        let earlier = |matches: &dyn Fn(Rule) -> bool| {
+            seen.iter()
+                .find(|(rule, _)| matches(*rule))
+                .map(|(_, text)| text.clone())
+        };
+        let undone = match rule {
+            Rule::Reset(None) => earlier(&|_| true),
+            Rule::Reset(Some(kind)) => {
+                earlier(&|rule| rule == *kind || (*kind == Rule::Password && rule == Rule::NoPass))
+            }
+            Rule::AllCommands => {
+                earlier(&|rule| matches!(rule, Rule::Commands | Rule::AllCommands))
+            }
+            _ => None,
+        };
+        if let Some(undone) = undone {
+            let msg = format!(
+                "`{}` undoes the earlier `{}`, so it has to come before it",
+                text, undone
+            );
+            return Err(syn::Error::new(span, msg));
+        }
+        let conflict = match rule {
+            Rule::State(on) => earlier(&|rule| rule == Rule::State(!on)),
+            Rule::NoPass => earlier(&|rule| rule == Rule::Password),
+            Rule::Password => earlier(&|rule| rule == Rule::NoPass),
+            _ => None,
+        };
+        if let Some(conflict) = conflict {
+            let msg = format!("`{}` contradicts the earlier `{}`", text, conflict);
+            return Err(syn::Error::new(span, msg));
+        }
+        seen.push((rule, text));
+    }
+    Ok(())
+}
+
+fn parse_rule(text: &str, span: Span) -> syn::Result<Rule> {
+    let error = |msg: String| Err(syn::Error::new(span, msg));
+    let lower = text.to_ascii_lowercase();
+    let rule = match lower.as_str() {
+        "on" => Rule::State(true),
+        "off" => Rule::State(false),
+        "nopass" => Rule::NoPass,
+        "allkeys" => Rule::Keys,
+        "allchannels" => Rule::Channels,
+        "allcommands" | "nocommands" | "+@all" | "-@all" => Rule::AllCommands,
+        "clearselectors" => Rule::Selectors,
+        "reset" => Rule::Reset(None),
+        "resetpass" => Rule::Reset(Some(&Rule::Password)),
+        "resetkeys" => Rule::Reset(Some(&Rule::Keys)),
+        "resetchannels" => Rule::Reset(Some(&Rule::Channels)),
+        _ => {
+            let (first, rest) = text.split_at(text.chars().next().map_or(0, char::len_utf8));
+            match first {
+                ">" | "<" => Rule::Password,
+                "#" | "!" => {
+                    if rest.len() != 64 || !rest.chars().all(|c| c.is_ascii_hexdigit()) {
+                        return error(format!(
+                            "`{}` should be followed by the SHA-256 of the password, as 64 hex \
+                             digits",
+                            first
+                        ));
+                    }
+                    Rule::Password
+                }
+                "~" => Rule::Keys,
+                "%" => {
+                    let permissions = rest.split_once('~').map(|(permissions, _)| permissions);
+                    let valid = permissions.is_some_and(|permissions| {
+                        !permissions.is_empty()
+                            && permissions
+                                .chars()
+                                .all(|c| matches!(c, 'R' | 'W' | 'r' | 'w'))
+                    });
+                    if !valid {
+                        return error(format!(
+                            "`{}` isn't a key pattern with permissions; expected `%R~`, `%W~` or \
+                             `%RW~` followed by a pattern",
+                            text
+                        ));
+                    }
+                    Rule::Keys
+                }
+                "&" => Rule::Channels,
+                "(" if text.ends_with(')') => {
+                    let inner = &text[1..text.len() - 1];
+                    check_rules(inner.split_whitespace().map(|rule| (rule.to_owned(), span)))?;
+                    Rule::Selectors
+                }
+                "+" | "-" => match rest.strip_prefix('@') {
+                    Some(category)
+                        if !CATEGORIES.contains(&category.to_ascii_lowercase().as_str()) =>
+                    {
+                        return error(format!(
+                            "`{}` isn't an ACL category; expected one of {}",
+                            category,
+                            CATEGORIES.join(", ")
+                        ));
+                    }
+                    None if rest.is_empty() || rest.starts_with('|') || rest.ends_with('|') => {
+                        return error(format!("`{}` should be followed by a command name", first));
+                    }
+                    _ => Rule::Commands,
+                },
+                _ => {
+                    return error(format!(
+                        "`{}` isn't an ACL rule; expected `on`, `off`, `>password`, `~pattern`, \
+                         `&pattern`, `+command`, `+@category`, or a keyword such as `nopass` or \
+                         `allkeys`",
+                        text
+                    ))
+                }
+            }
+        }
+    };
+    Ok(rule)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse::parse_command;
+    use proc_macro2::TokenStream;
+
+    fn check(input: &str) -> Result<(), String> {
+        let tokens: TokenStream = input.parse().unwrap();
+        check_acl_setuser(&parse_command(tokens).unwrap()).map_err(|err| err.to_string())
+    }
+
+    #[test]
+    fn acl_valid() {
+        let hash = "\"#5e884898da28047151d0e56f8dc6292773603d0d6aabbdd62a11ef721d1542d8\"";
+        for input in [
+            "ACL SETUSER alice on >secret ~cache:* +@read -@dangerous +get",
+            "acl setuser alice reset on nopass allkeys allchannels -@all +@read",
+            "ACL SETUSER bob resetpass >new resetkeys %R~logs:* %RW~tmp:* &news:* +client|list",
+            &format!("ACL SETUSER carol {} off", hash),
+            "ACL SETUSER dave \"(~app:* +get)\" clearselectors",
+            "ACL SETUSER erin on >{password} {..rules} ~x",
+            "ACL SETUSER frank",
+            "ACL DELUSER reset on",
+        ] {
+            assert_eq!(check(input), Ok(()), "{}", input);
+        }
+    }
+
+    #[test]
+    fn acl_errors() {
+        let e = check("ACL SETUSER alice on +@raed").unwrap_err();
+        assert!(e.contains("`raed` isn't an ACL category"), "{}", e);
+        let e = check("ACL SETUSER alice on secret").unwrap_err();
+        assert!(e.contains("`secret` isn't an ACL rule"), "{}", e);
+        let e = check("ACL SETUSER alice on >secret reset").unwrap_err();
+        assert!(e.contains("`reset` undoes the earlier `on`"), "{}", e);
+        let e = check("ACL SETUSER alice ~a:* +get resetkeys").unwrap_err();
+        assert!(e.contains("`resetkeys` undoes the earlier `~a:*`"), "{}", e);
+        let e = check("ACL SETUSER alice +get -@all").unwrap_err();
+        assert!(e.contains("`-@all` undoes the earlier `+get`"), "{}", e);
+        let e = check("ACL SETUSER alice nopass resetpass").unwrap_err();
+        assert!(
+            e.contains("`resetpass` undoes the earlier `nopass`"),
+            "{}",
+            e
+        );
+        let e = check("ACL SETUSER alice on off").unwrap_err();
+        assert!(e.contains("`off` contradicts the earlier `on`"), "{}", e);
+        let e = check("ACL SETUSER alice nopass >secret").unwrap_err();
+        assert!(e.contains("contradicts the earlier `nopass`"), "{}", e);
+        let e = check("ACL SETUSER alice \"#abc\"").unwrap_err();
+        assert!(e.contains("64 hex digits"), "{}", e);
+        let e = check("ACL SETUSER alice %X~logs").unwrap_err();
+        assert!(e.contains("key pattern with permissions"), "{}", e);
+        let e = check("ACL SETUSER alice \"(+@nope)\"").unwrap_err();
+        assert!(e.contains("`nope` isn't an ACL category"), "{}", e);
+        let e = check("ACL SETUSER alice + get").unwrap_err();
+        assert!(e.contains("followed by a command name"), "{}", e);
+    }
+}
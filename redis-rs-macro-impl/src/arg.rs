@@ -0,0 +1,198 @@
+use proc_macro2::{Span, TokenStream};
+use quote::quote;
+use syn::{Data, DeriveInput, Fields, LitStr, Token};
+
+/// How a case writes a variant name, which is in `PascalCase`
+type Case = fn(&str) -> String;
+
+/// The cases of `#[redis(rename_all = "...")]`, named as with serde
+const CASES: &[(&str, Case)] = &[
+    ("lowercase", |name| name.to_ascii_lowercase()),
+    ("UPPERCASE", |name| name.to_ascii_uppercase()),
+    ("PascalCase", |name| name.to_owned()),
+    ("camelCase", |name| {
+        let mut chars = name.chars();
+        chars.next().map_or_else(String::new, |first| {
+            first.to_ascii_lowercase().to_string() + chars.as_str()
+        })
+    }),
+    ("snake_case", |name| separated(name, '_')),
+    ("SCREAMING_SNAKE_CASE", |name| {
+        separated(name, '_').to_ascii_uppercase()
+    }),
+    ("kebab-case", |name| separated(name, '-')),
+    ("SCREAMING-KEBAB-CASE", |name| {
+        separated(name, '-').to_ascii_uppercase()
+    }),
+];
+
+/// Write the words of a `PascalCase` name in lowercase, with `separator` before each capital
+fn separated(name: &str, separator: char) -> String {
+    let mut out = String::with_capacity(name.len() + 4);
+    for (i, c) in name.chars().enumerate() {
+        if i > 0 && c.is_ascii_uppercase() {
+            out.push(separator);
+        }
+        out.push(c.to_ascii_lowercase());
+    }
+    out
+}
+
+/// Parse the `#[redis(rename_all = "...")]` of the enum, or the `#[redis(rename = "...")]` of a
+/// variant
+fn parse_attrs(attrs: &[syn::Attribute], key: &str) -> syn::Result<Option<LitStr>> {
+    let mut value = None;
+    for attr in attrs {
+        if !attr.path().is_ident("redis") {
+            continue;
+        }
+        attr.parse_nested_meta(|meta| {
+            if !meta.path.is_ident(key) {
+                return Err(meta.error(format!("expected `{} = \"...\"`", key)));
+            }
+            meta.input.parse::<Token![=]>()?;
+            value = Some(meta.input.parse()?);
+            Ok(())
+        })?;
+    }
+    Ok(value)
+}
+
+/// Generate a `redis::ToRedisArgs` implementation for an enum of unit variants, writing each
+/// variant as its name, in the case of `#[redis(rename_all)]`, or as its `#[redis(rename)]`
+pub(crate) fn expand_arg(input: TokenStream) -> syn::Result<TokenStream> {
+    let input: DeriveInput = syn::parse2(input)?;
+    let Data::Enum(data) = &input.data else {
+        let msg = "`RedisArg` can only be derived for enums";
+        return Err(syn::Error::new(input.ident.span(), msg));
+    };
+    let case = match parse_attrs(&input.attrs, "rename_all")? {
+        Some(lit) => {
+            let Some((_, case)) = CASES.iter().find(|(name, _)| *name == lit.value()) else {
+                let names: Vec<_> = CASES.iter().map(|(name, _)| *name).collect();
+                let msg = format!(
+                    "unknown case `{}`; expected one of {}",
+                    lit.value(),
+                    names.join(", ")
+                );
+                return Err(syn::Error::new(lit.span(), msg));
+            };
+            *case
+        }
+        None => CASES[2].1,
+    };
+    let mut names: Vec<(String, Span)> = vec![];
+    let mut arms = vec![];
+    for variant in &data.variants {
+        if !matches!(variant.fields, Fields::Unit) {
+            let msg = "`RedisArg` variants can't have fields";
+            return Err(syn::Error::new_spanned(&variant.fields, msg));
+        }
+        let ident = &variant.ident;
+        let name = match parse_attrs(&variant.attrs, "rename")? {
+            Some(lit) => (lit.value(), lit.span()),
+            None => (case(&ident.to_string()), ident.span()),
+        };
+        if let Some((_, earlier)) = names.iter().find(|(earlier, _)| *earlier == name.0) {
+            let msg = format!("`{}` is written the same as an earlier variant", name.0);
+            let mut err = syn::Error::new(name.1, msg);
+            err.combine(syn::Error::new(*earlier, "the earlier variant"));
+            return Err(err);
+        }
+        let text = &name.0;
+        arms.push(quote!(Self::#ident => #text,));
+        names.push(name);
+    }
+
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics redis::ToRedisArgs for #ident #ty_generics #where_clause {
+            fn write_redis_args<W: ?::core::marker::Sized + redis::RedisWrite>(&self, out: &mut W) {
+                let name: &str = match *self {
+                    #(#arms)*
+                };
+                out.write_arg(name.as_bytes())
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expand(input: &str) -> syn::Result<String> {
+        expand_arg(input.parse().unwrap()).map(|out| out.to_string())
+    }
+
+    #[test]
+    fn arg_cases() {
+        let name = "PaymentFailed";
+        let written: Vec<_> = CASES.iter().map(|(_, case)| case(name)).collect();
+        assert_eq!(
+            written,
+            [
+                "paymentfailed",
+                "PAYMENTFAILED",
+                "PaymentFailed",
+                "paymentFailed",
+                "payment_failed",
+                "PAYMENT_FAILED",
+                "payment-failed",
+                "PAYMENT-FAILED",
+            ]
+        );
+    }
+
+    #[test]
+    fn arg_expand() {
+        let output = expand(
+            r#"#[redis(rename_all = "SCREAMING_SNAKE_CASE")]
+            enum OrderStatus {
+                Pending,
+                PaymentFailed,
+                #[redis(rename = "done")]
+                Shipped,
+            }"#,
+        )
+        .unwrap();
+        assert!(
+            output.contains(
+                "match * self { Self :: Pending => \"PENDING\" , Self :: PaymentFailed => \
+                 \"PAYMENT_FAILED\" , Self :: Shipped => \"done\" , }"
+            ),
+            "{}",
+            output
+        );
+        let output = expand("enum Side { Left, Right }").unwrap();
+        assert!(output.contains("Self :: Left => \"Left\""), "{}", output);
+        expand("enum Never {}").unwrap();
+    }
+
+    #[test]
+    fn arg_errors() {
+        let err = |input: &str| expand(input).unwrap_err().to_string();
+        let e = err("struct T;");
+        assert!(e.contains("only be derived for enums"), "{}", e);
+        let e = err("enum T { A(u8) }");
+        assert!(e.contains("can't have fields"), "{}", e);
+        let e = err("#[redis(rename_all = \"upper\")] enum T { A }");
+        assert!(
+            e.contains("unknown case `upper`; expected one of lowercase"),
+            "{}",
+            e
+        );
+        let e = err("#[redis(rename = \"a\")] enum T { A }");
+        assert!(e.contains("expected `rename_all"), "{}", e);
+        let e = err("enum T { #[redis(rename_all = \"a\")] A }");
+        assert!(e.contains("expected `rename = "), "{}", e);
+        let e =
+            err("#[redis(rename_all = \"lowercase\")] enum T { A, #[redis(rename = \"a\")] B }");
+        assert!(
+            e.contains("`a` is written the same as an earlier variant"),
+            "{}",
+            e
+        );
+    }
+}
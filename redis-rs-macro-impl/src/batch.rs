@@ -0,0 +1,73 @@
+use crate::pipe::{expand_entries, parse_entries, scope_entries, split_entries, Entry, Sink};
+use crate::scope::split_scope;
+use proc_macro2::{Ident, Span, TokenStream};
+use quote::quote;
+
+/// Batches have no replies to read, so their commands can't be labeled or ignored
+fn check_batch(entries: &[Entry]) -> syn::Result<()> {
+    for entry in entries {
+        match entry {
+            Entry::Command(command) => {
+                if command.label.is_some() || command.ignore {
+                    let msg = "a batch only builds commands, so they can't have labels or `_ =`";
+                    return Err(syn::Error::new(command.command.args[0].span, msg));
+                }
+            }
+            Entry::For { body, .. } => check_batch(body)?,
+            Entry::If {
+                then, otherwise, ..
+            } => {
+                check_batch(then)?;
+                check_batch(otherwise)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Generate a block that builds the `Vec<redis::Cmd>` for a `redis_batch!` invocation
+pub(crate) fn expand_batch(input: TokenStream) -> syn::Result<TokenStream> {
+    let (scope, input) = split_scope(input)?;
+    let mut entries = parse_entries(split_entries(input), Span::call_site())?;
+    check_batch(&entries)?;
+    scope_entries(&mut entries, &scope)?;
+    let batch = Ident::new("batch", Span::mixed_site());
+    let commands = expand_entries(&entries, Sink::Batch(&batch))?;
+    Ok(quote! {
+        {
+            let mut #batch: ::std::vec::Vec<redis::Cmd> = ::std::vec::Vec::new();
+            #commands
+            #batch
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn batch_expand() {
+        let output = expand_batch(
+            "for (k, v) in &pairs { SET {k} {v} EX 60 }"
+                .parse()
+                .unwrap(),
+        )
+        .unwrap()
+        .to_string();
+        assert!(output.contains("for (k , v) in & pairs {"), "{}", output);
+        assert!(output.contains(". push ("), "{}", output);
+    }
+
+    #[test]
+    fn batch_errors() {
+        let err = |input: &str| {
+            expand_batch(input.parse().unwrap())
+                .unwrap_err()
+                .to_string()
+        };
+        assert!(err("").contains("expected at least one redis command"));
+        assert!(err("a: GET a").contains("can't have labels"));
+        assert!(err("for k in keys { _ = DEL {k} }").contains("can't have labels or `_ =`"));
+    }
+}
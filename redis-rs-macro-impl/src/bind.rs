@@ -0,0 +1,189 @@
+use crate::parse::{Arg, Piece};
+use proc_macro2::{Ident, Span, TokenStream, TokenTree};
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
+use syn::{Expr, Token};
+
+/// A value bound to placeholders after the `;` of the macro input. Named bindings such as the
+/// `key = user_key` in `redis!("GET :key"; key = user_key)` fill `:name` placeholders, while
+/// positional bindings fill `?` placeholders in order. Each value is evaluated once, into `local`,
+/// before the command is built.
+pub(crate) struct Binding {
+    pub(crate) name: Option<Ident>,
+    pub(crate) value: Expr,
+    pub(crate) local: Ident,
+}
+
+impl Binding {
+    pub(crate) fn span(&self) -> Span {
+        match &self.name {
+            Some(name) => name.span(),
+            None => self.value.span(),
+        }
+    }
+
+    fn display_name(&self) -> String {
+        self.name
+            .as_ref()
+            .map_or_else(String::new, Ident::to_string)
+    }
+
+    /// The piece that replaces a placeholder for this binding
+    fn piece(&self) -> Piece {
+        let local = &self.local;
+        Piece::Expr(Box::new(syn::parse_quote!(#local)))
+    }
+}
+
+/// A binding as written in the macro input, either `name = value` or just `value`
+struct BindingInput {
+    name: Option<Ident>,
+    value: Expr,
+}
+
+impl Parse for BindingInput {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut name = None;
+        if input.peek(syn::Ident) && input.peek2(Token![=]) && !input.peek2(Token![==]) {
+            name = Some(input.parse()?);
+            input.parse::<Token![=]>()?;
+        }
+        let value = input.parse()?;
+        Ok(BindingInput { name, value })
+    }
+}
+
+/// Split the macro input at the first top level `;` into the command and its bindings
+pub(crate) fn split_bindings(input: TokenStream) -> (TokenStream, Option<TokenStream>) {
+    let mut tokens = input.into_iter();
+    let command = tokens
+        .by_ref()
+        .take_while(|tt| !matches!(tt, TokenTree::Punct(p) if p.as_char() == ';'))
+        .collect();
+    let rest: Vec<TokenTree> = tokens.collect();
+    if rest.is_empty() {
+        (command, None)
+    } else {
+        (command, Some(rest.into_iter().collect()))
+    }
+}
+
+/// Parse the bindings after the `;`, and replace every placeholder in `args` with its value
+pub(crate) fn bind(args: &mut [Arg], bindings: TokenStream) -> syn::Result<Vec<Binding>> {
+    let parser = Punctuated::<BindingInput, Token![,]>::parse_terminated;
+    let bindings: Vec<Binding> = syn::parse::Parser::parse2(parser, bindings)?
+        .into_iter()
+        .enumerate()
+        .map(|(i, BindingInput { name, value })| {
+            let suffix = name.as_ref().map_or(i.to_string(), Ident::to_string);
+            // Mixed site hygiene keeps the local from clashing with anything at the call site
+            let local = Ident::new(&format!("binding_{}", suffix), Span::mixed_site());
+            Binding { name, value, local }
+        })
+        .collect();
+    let named = bindings.iter().filter(|b| b.name.is_some()).count();
+    if named == 0 {
+        bind_positional(args, &bindings)?;
+    } else if named == bindings.len() {
+        bind_named(args, &bindings)?;
+    } else {
+        let binding = bindings
+            .iter()
+            .find(|b| b.name.is_none())
+            .unwrap_or(&bindings[0]);
+        let msg = "named and positional bindings cannot be mixed";
+        return Err(syn::Error::new(binding.span(), msg));
+    }
+    Ok(bindings)
+}
+
+fn bind_named(args: &mut [Arg], bindings: &[Binding]) -> syn::Result<()> {
+    for (i, binding) in bindings.iter().enumerate() {
+        if bindings[..i].iter().any(|b| b.name == binding.name) {
+            let msg = format!("`{}` is bound more than once", binding.display_name());
+            return Err(syn::Error::new(binding.span(), msg));
+        }
+    }
+    let mut used = vec![false; bindings.len()];
+    visit_placeholders(args, &mut |piece, span| {
+        let Some(name) = named_placeholder(piece) else {
+            return Ok(());
+        };
+        let Some(i) = bindings.iter().position(|b| b.name.as_ref() == Some(&name)) else {
+            let msg = format!(
+                "no binding for placeholder `:{}`; quote the argument to pass it as text",
+                name
+            );
+            return Err(syn::Error::new(span, msg));
+        };
+        used[i] = true;
+        *piece = bindings[i].piece();
+        Ok(())
+    })?;
+    if let Some(i) = used.iter().position(|used| !used) {
+        let name = bindings[i].display_name();
+        let msg = format!(
+            "`{}` is bound but the command has no `:{}` placeholder",
+            name, name
+        );
+        return Err(syn::Error::new(bindings[i].span(), msg));
+    }
+    Ok(())
+}
+
+fn bind_positional(args: &mut [Arg], bindings: &[Binding]) -> syn::Result<()> {
+    let mut count = 0;
+    let mut first_unbound = None;
+    visit_placeholders(args, &mut |piece, span| {
+        if !matches!(piece, Piece::Text(text) if text == "?") {
+            return Ok(());
+        }
+        match bindings.get(count) {
+            Some(binding) => *piece = binding.piece(),
+            None => {
+                first_unbound.get_or_insert(span);
+            }
+        }
+        count += 1;
+        Ok(())
+    })?;
+    if count == bindings.len() {
+        return Ok(());
+    }
+    let msg = format!(
+        "the command has {} `?` placeholder(s), but {} value(s) are bound",
+        count,
+        bindings.len()
+    );
+    let span = match first_unbound {
+        Some(span) => span,
+        None => bindings[count].span(),
+    };
+    Err(syn::Error::new(span, msg))
+}
+
+/// Call `f` on every argument that is a single piece of text, in order, including the arguments
+/// of conditional groups
+fn visit_placeholders(
+    args: &mut [Arg],
+    f: &mut impl FnMut(&mut Piece, Span) -> syn::Result<()>,
+) -> syn::Result<()> {
+    for arg in args {
+        let span = arg.span;
+        match arg.pieces.as_mut_slice() {
+            [piece @ Piece::Text(_)] => f(piece, span)?,
+            [Piece::Conditional { args, .. }] => visit_placeholders(args, f)?,
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// The name of a `:name` placeholder
+fn named_placeholder(piece: &Piece) -> Option<Ident> {
+    match piece {
+        Piece::Text(text) => syn::parse_str::<Ident>(text.strip_prefix(':')?).ok(),
+        _ => None,
+    }
+}
@@ -0,0 +1,215 @@
+use crate::parse::{Arg, Command, Piece};
+use proc_macro2::Span;
+
+/// One argument of a `BITFIELD` command, as far as it can be known at compile time
+enum Word {
+    /// Literal text
+    Text(String),
+    /// A single value that is only known at runtime
+    Value,
+}
+
+impl Word {
+    /// `None` for arguments that expand to an unknown number of values, such as spreads
+    fn of(arg: &Arg) -> Option<Self> {
+        match arg.pieces.as_slice() {
+            [Piece::Text(text)] => Some(Word::Text(text.clone())),
+            [Piece::Str(lit)] => Some(Word::Text(lit.value())),
+            pieces if pieces.iter().all(|piece| piece.standalone_kind().is_none()) => {
+                Some(Word::Value)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Check the sub-operations of `BITFIELD` and `BITFIELD_RO` commands, along with their literal
+/// encodings, offsets, and values, so that malformed commands fail to compile rather than being
+/// rejected by the server. Checking stops at the first argument that could expand to any number
+/// of values, or at a sub-operation that is only known at runtime.
+pub(crate) fn check_bitfield(command: &Command) -> syn::Result<()> {
+    let Some(name) = command.args[0].word() else {
+        return Ok(());
+    };
+    let read_only = match name.to_ascii_uppercase().as_str() {
+        "BITFIELD" => false,
+        "BITFIELD_RO" => true,
+        _ => return Ok(()),
+    };
+    // Skip the key
+    let mut args = command.args.iter().skip(2);
+    while let Some(arg) = args.next() {
+        let Some(Word::Text(op)) = Word::of(arg) else {
+            return Ok(());
+        };
+        let op = op.to_ascii_uppercase();
+        let operands: &[&str] = match op.as_str() {
+            "GET" => &["encoding", "offset"],
+            "SET" | "INCRBY" if !read_only => &["encoding", "offset", "value"],
+            "OVERFLOW" if !read_only => &["overflow"],
+            _ => {
+                let expected = if read_only {
+                    "GET"
+                } else {
+                    "GET, SET, INCRBY, or OVERFLOW"
+                };
+                let msg = format!(
+                    "unknown {} operation `{}`; expected {}",
+                    name.to_ascii_uppercase(),
+                    op,
+                    expected
+                );
+                return Err(syn::Error::new(arg.span, msg));
+            }
+        };
+        for &operand in operands {
+            let Some(next) = args.next() else {
+                let msg = format!("`{}` expects {}", op, describe(operands));
+                return Err(syn::Error::new(arg.span, msg));
+            };
+            match Word::of(next) {
+                Some(Word::Text(text)) => check_operand(operand, &text, next.span)?,
+                Some(Word::Value) => {}
+                None => return Ok(()),
+            }
+        }
+    }
+    Ok(())
+}
+
+fn describe(operands: &[&str]) -> String {
+    match operands {
+        [one] => format!("an {} mode", one),
+        [encoding, offset] => format!("an {} and an {}", encoding, offset),
+        [encoding, offset, value] => format!("an {}, an {}, and a {}", encoding, offset, value),
+        _ => unreachable!("operations have at most three operands"),
+    }
+}
+
+fn check_operand(operand: &str, text: &str, span: Span) -> syn::Result<()> {
+    let valid = match operand {
+        "encoding" => return check_encoding(text, span),
+        "offset" => {
+            let digits = text.strip_prefix('#').unwrap_or(text);
+            !digits.is_empty() && digits.chars().all(|c| c.is_ascii_digit())
+        }
+        "value" => text.parse::<i128>().is_ok(),
+        _ => ["WRAP", "SAT", "FAIL"].contains(&text.to_ascii_uppercase().as_str()),
+    };
+    if valid {
+        return Ok(());
+    }
+    let msg = match operand {
+        "offset" => format!(
+            "invalid BITFIELD offset `{}`; expected a bit offset such as `0`, or a multiple of the \
+             encoding width such as `#1`",
+            text
+        ),
+        "value" => format!("invalid BITFIELD value `{}`; expected an integer", text),
+        _ => format!(
+            "invalid BITFIELD overflow mode `{}`; expected WRAP, SAT, or FAIL",
+            text
+        ),
+    };
+    Err(syn::Error::new(span, msg))
+}
+
+/// Encodings are `i` or `u` followed by a bit width, of up to 64 bits when signed, and 63 bits
+/// when unsigned
+fn check_encoding(text: &str, span: Span) -> syn::Result<()> {
+    let (max, width) = match text.split_at(text.len().min(1)) {
+        ("i", width) => (64, width),
+        ("u", width) => (63, width),
+        _ => (0, ""),
+    };
+    match width.parse::<u32>() {
+        Ok(bits) if width.chars().all(|c| c.is_ascii_digit()) && (1..=max).contains(&bits) => {
+            Ok(())
+        }
+        Ok(_) if width.chars().all(|c| c.is_ascii_digit()) => {
+            let kind = if max == 64 { "signed" } else { "unsigned" };
+            let msg = format!(
+                "invalid BITFIELD encoding `{}`; {} encodings are 1 to {} bits wide",
+                text, kind, max
+            );
+            Err(syn::Error::new(span, msg))
+        }
+        _ => {
+            let msg = format!(
+                "invalid BITFIELD encoding `{}`; expected `i` or `u` followed by a bit width, \
+                 such as `u8` or `i16`",
+                text
+            );
+            Err(syn::Error::new(span, msg))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse::parse_command;
+    use proc_macro2::TokenStream;
+
+    fn check(input: &str) -> Result<(), String> {
+        let tokens: TokenStream = input.parse().unwrap();
+        check_bitfield(&parse_command(tokens).unwrap()).map_err(|err| err.to_string())
+    }
+
+    #[test]
+    fn bitfield_valid() {
+        for input in [
+            "BITFIELD key GET u8 0",
+            "BITFIELD key SET i16 #1 -100 GET u4 0",
+            "BITFIELD key OVERFLOW SAT INCRBY u2 100 1 OVERFLOW fail incrby u2 102 1",
+            "BITFIELD key GET i64 0 GET u63 0 GET i1 0",
+            "BITFIELD key SET u8 {offset} {value}",
+            "BITFIELD key GET u8 0 {..ops}",
+            "BITFIELD_RO key GET u8 0 GET i5 #3",
+            "BITFIELD key",
+        ] {
+            assert_eq!(check(input), Ok(()), "{}", input);
+        }
+    }
+
+    #[test]
+    fn bitfield_invalid() {
+        for (input, msg) in [
+            (
+                "BITFIELD key GETT u8 0",
+                "unknown BITFIELD operation `GETT`",
+            ),
+            (
+                "BITFIELD_RO key SET u8 0 1",
+                "unknown BITFIELD_RO operation `SET`; expected GET",
+            ),
+            ("BITFIELD key GET x8 0", "invalid BITFIELD encoding `x8`"),
+            (
+                "BITFIELD key GET u64 0",
+                "unsigned encodings are 1 to 63 bits wide",
+            ),
+            (
+                "BITFIELD key GET i0 0",
+                "signed encodings are 1 to 64 bits wide",
+            ),
+            ("BITFIELD key GET u8 -1", "invalid BITFIELD offset `-1`"),
+            ("BITFIELD key GET u8 #1x", "invalid BITFIELD offset `#1x`"),
+            ("BITFIELD key SET u8 0 ten", "invalid BITFIELD value `ten`"),
+            (
+                "BITFIELD key OVERFLOW CLAMP",
+                "invalid BITFIELD overflow mode `CLAMP`",
+            ),
+            (
+                "BITFIELD key INCRBY u8 0",
+                "`INCRBY` expects an encoding, an offset, and a value",
+            ),
+            (
+                "BITFIELD key GET",
+                "`GET` expects an encoding and an offset",
+            ),
+        ] {
+            let err = check(input).expect_err(input);
+            assert!(err.contains(msg), "{}: {}", input, err);
+        }
+    }
+}
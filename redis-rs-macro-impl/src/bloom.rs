@@ -0,0 +1,587 @@
+use crate::expand::expand_command;
+use crate::parse::{parse_command, Arg, Piece};
+use crate::typed::split_return;
+use proc_macro2::{Span, TokenStream};
+use quote::quote;
+use syn::Type;
+
+/// A parameter of a RedisBloom command
+#[derive(Clone, Copy)]
+enum Param {
+    /// Anything, such as an item
+    Any,
+    /// A probability or error rate, strictly between 0 and 1, with its name in errors
+    Rate(&'static str),
+    /// A positive integer, such as a capacity, with its name in errors
+    Count(&'static str),
+    /// The decay of `TOPK.RESERVE`, above 0 and at most 1
+    Decay,
+    /// A quantile of `TDIGEST`, from 0 to 1
+    Quantile,
+    /// Any number
+    Float,
+    /// An integer, such as an increment or a rank
+    Int,
+    /// One or more arguments, each of this kind, up to the end of the command
+    Items(&'static Param),
+    /// One or more items, each followed by an argument of this kind
+    Pairs(&'static Param),
+}
+
+/// A RedisBloom command: its parameters after the key, of which the first `required` have to be
+/// given, its options, and the type its reply is read as
+struct Spec {
+    name: &'static str,
+    params: &'static [Param],
+    required: usize,
+    options: &'static [(&'static str, Option<Param>)],
+    reply: &'static str,
+}
+
+const fn spec(
+    name: &'static str,
+    params: &'static [Param],
+    options: &'static [(&'static str, Option<Param>)],
+    reply: &'static str,
+) -> Spec {
+    Spec {
+        name,
+        params,
+        required: params.len(),
+        options,
+        reply,
+    }
+}
+
+const ITEMS: Param = Param::Items(&Param::Any);
+const FLOATS: Param = Param::Items(&Param::Float);
+
+/// The commands whose parameters are checked and whose reply type is known
+const COMMANDS: &[Spec] = &[
+    spec(
+        "BF.RESERVE",
+        &[Param::Rate("the error rate"), Param::Count("the capacity")],
+        &[
+            ("EXPANSION", Some(Param::Count("the expansion"))),
+            ("NONSCALING", None),
+        ],
+        "()",
+    ),
+    spec("BF.ADD", &[Param::Any], &[], "bool"),
+    spec("BF.EXISTS", &[Param::Any], &[], "bool"),
+    spec("BF.MADD", &[ITEMS], &[], "Vec<bool>"),
+    spec("BF.MEXISTS", &[ITEMS], &[], "Vec<bool>"),
+    spec(
+        "BF.INSERT",
+        &[],
+        &[
+            ("CAPACITY", Some(Param::Count("the capacity"))),
+            ("ERROR", Some(Param::Rate("the error rate"))),
+            ("EXPANSION", Some(Param::Count("the expansion"))),
+            ("NOCREATE", None),
+            ("NONSCALING", None),
+            ("ITEMS", Some(ITEMS)),
+        ],
+        "Vec<bool>",
+    ),
+    spec("BF.CARD", &[], &[], "u64"),
+    spec(
+        "CF.RESERVE",
+        &[Param::Count("the capacity")],
+        &[
+            ("BUCKETSIZE", Some(Param::Count("the bucket size"))),
+            (
+                "MAXITERATIONS",
+                Some(Param::Count("the number of iterations")),
+            ),
+            ("EXPANSION", Some(Param::Int)),
+        ],
+        "()",
+    ),
+    spec("CF.ADD", &[Param::Any], &[], "bool"),
+    spec("CF.ADDNX", &[Param::Any], &[], "bool"),
+    spec("CF.EXISTS", &[Param::Any], &[], "bool"),
+    spec("CF.DEL", &[Param::Any], &[], "bool"),
+    spec("CF.COUNT", &[Param::Any], &[], "u64"),
+    spec("CF.MEXISTS", &[ITEMS], &[], "Vec<bool>"),
+    spec(
+        "CF.INSERT",
+        &[],
+        &[
+            ("CAPACITY", Some(Param::Count("the capacity"))),
+            ("NOCREATE", None),
+            ("ITEMS", Some(ITEMS)),
+        ],
+        "Vec<bool>",
+    ),
+    // 1 when added, 0 when the item exists, and -1 when the filter is full
+    spec(
+        "CF.INSERTNX",
+        &[],
+        &[
+            ("CAPACITY", Some(Param::Count("the capacity"))),
+            ("NOCREATE", None),
+            ("ITEMS", Some(ITEMS)),
+        ],
+        "Vec<i64>",
+    ),
+    spec(
+        "CMS.INITBYDIM",
+        &[Param::Count("the width"), Param::Count("the depth")],
+        &[],
+        "()",
+    ),
+    spec(
+        "CMS.INITBYPROB",
+        &[Param::Rate("the error"), Param::Rate("the probability")],
+        &[],
+        "()",
+    ),
+    spec("CMS.INCRBY", &[Param::Pairs(&Param::Int)], &[], "Vec<u64>"),
+    spec("CMS.QUERY", &[ITEMS], &[], "Vec<u64>"),
+    Spec {
+        name: "TOPK.RESERVE",
+        params: &[
+            Param::Count("the number of top items"),
+            Param::Count("the width"),
+            Param::Count("the depth"),
+            Param::Decay,
+        ],
+        required: 1,
+        options: &[],
+        reply: "()",
+    },
+    // The items pushed out of the top list by the added items
+    spec("TOPK.ADD", &[ITEMS], &[], "Vec<Option<String>>"),
+    spec(
+        "TOPK.INCRBY",
+        &[Param::Pairs(&Param::Count("an increment"))],
+        &[],
+        "Vec<Option<String>>",
+    ),
+    spec("TOPK.QUERY", &[ITEMS], &[], "Vec<bool>"),
+    spec("TOPK.COUNT", &[ITEMS], &[], "Vec<u64>"),
+    spec("TOPK.LIST", &[], &[("WITHCOUNT", None)], "Vec<String>"),
+    spec(
+        "TDIGEST.CREATE",
+        &[],
+        &[("COMPRESSION", Some(Param::Count("the compression")))],
+        "()",
+    ),
+    spec("TDIGEST.RESET", &[], &[], "()"),
+    spec("TDIGEST.ADD", &[FLOATS], &[], "()"),
+    spec(
+        "TDIGEST.QUANTILE",
+        &[Param::Items(&Param::Quantile)],
+        &[],
+        "Vec<f64>",
+    ),
+    spec("TDIGEST.CDF", &[FLOATS], &[], "Vec<f64>"),
+    spec("TDIGEST.RANK", &[FLOATS], &[], "Vec<i64>"),
+    spec("TDIGEST.REVRANK", &[FLOATS], &[], "Vec<i64>"),
+    spec(
+        "TDIGEST.BYRANK",
+        &[Param::Items(&Param::Int)],
+        &[],
+        "Vec<f64>",
+    ),
+    spec(
+        "TDIGEST.BYREVRANK",
+        &[Param::Items(&Param::Int)],
+        &[],
+        "Vec<f64>",
+    ),
+    spec("TDIGEST.MIN", &[], &[], "f64"),
+    spec("TDIGEST.MAX", &[], &[], "f64"),
+    spec(
+        "TDIGEST.TRIMMED_MEAN",
+        &[Param::Quantile, Param::Quantile],
+        &[],
+        "f64",
+    ),
+];
+
+/// The prefixes of the RedisBloom commands
+const FAMILIES: &[&str] = &["BF.", "CF.", "CMS.", "TOPK.", "TDIGEST."];
+
+/// Generate a `redis_bloom!` invocation: a RedisBloom command whose literal parameters are checked,
+/// whose substituted numbers have to be `f64`s or `u64`s, and whose reply is read as the type of
+/// the command unless another is given with `-> Type`
+pub(crate) fn expand_bloom(input: TokenStream) -> syn::Result<TokenStream> {
+    let (command, ty) = split_return(input)?;
+    let mut command = parse_command(command)?;
+    let name = command.args[0].word().map(|name| name.to_ascii_uppercase());
+    let family = name
+        .as_deref()
+        .is_some_and(|name| FAMILIES.iter().any(|family| name.starts_with(family)));
+    if !family {
+        let msg = "expected a RedisBloom command, such as `BF.ADD`, `CF.EXISTS`, `CMS.INCRBY`, \
+                   `TOPK.ADD` or `TDIGEST.QUANTILE`";
+        return Err(syn::Error::new(command.args[0].span, msg));
+    }
+    let name = name.unwrap();
+    let spec = COMMANDS.iter().find(|spec| spec.name == name);
+    let ty: Type = match (ty, spec) {
+        (Some(ty), _) => ty,
+        (None, Some(spec)) => syn::parse_str(spec.reply)?,
+        (None, None) => {
+            let msg = format!(
+                "the reply type of `{}` isn't known; give it with `-> Type`",
+                name
+            );
+            return Err(syn::Error::new(command.args[0].span, msg));
+        }
+    };
+    if let Some(spec) = spec {
+        if command.args.len() < 2 {
+            let msg = format!("`{}` expects a key", spec.name);
+            return Err(syn::Error::new(command.args[0].span, msg));
+        }
+        let numbers = check_params(spec, &command.args)?;
+        for (index, ty) in numbers {
+            if let [Piece::Expr(expr)] = command.args[index].pieces.as_mut_slice() {
+                let ty = syn::Ident::new(ty, Span::call_site());
+                **expr = syn::parse_quote!(::core::convert::identity::<#ty>(#expr));
+            }
+        }
+    }
+    let cmd = expand_command(&command)?;
+    Ok(quote!(::redis_rs_macro::TypedCmd::<#ty>::new(#cmd)))
+}
+
+/// Check the parameters and options of a command, returning the arguments that are numbers, by
+/// index, with the type their substitutions have to be
+fn check_params(spec: &Spec, args: &[Arg]) -> syn::Result<Vec<(usize, &'static str)>> {
+    let mut numbers = vec![];
+    let mut i = 2;
+    for (position, param) in spec.params.iter().enumerate() {
+        if let Param::Items(_) | Param::Pairs(_) = param {
+            check_items(spec.name, *param, args[0].span, &args[i..], i, &mut numbers)?;
+            return Ok(numbers);
+        }
+        let Some(arg) = args.get(i) else {
+            if position < spec.required {
+                let names: Vec<_> = spec.params[..spec.required]
+                    .iter()
+                    .map(|param| param.name())
+                    .collect();
+                let names = match names.split_last() {
+                    Some((last, [])) => last.to_string(),
+                    Some((last, rest)) => format!("{} and {}", rest.join(", "), last),
+                    None => unreachable!(),
+                };
+                let msg = format!("`{}` expects {} after the key", spec.name, names);
+                return Err(syn::Error::new(args[0].span, msg));
+            }
+            break;
+        };
+        if standalone(arg) {
+            return Ok(numbers);
+        }
+        check_param(spec.name, *param, arg, i, &mut numbers)?;
+        i += 1;
+    }
+    let mut seen = vec![];
+    while let Some(arg) = args.get(i) {
+        if standalone(arg) {
+            break;
+        }
+        let word = arg.word().unwrap_or_default().to_ascii_uppercase();
+        let Some((option, value)) = spec.options.iter().find(|(option, _)| *option == word) else {
+            let msg = match spec.options.is_empty() {
+                true => format!("`{}` takes no more arguments", spec.name),
+                false => {
+                    let options: Vec<_> = spec.options.iter().map(|(option, _)| *option).collect();
+                    format!(
+                        "unknown `{}` option; expected one of {}",
+                        spec.name,
+                        options.join(", ")
+                    )
+                }
+            };
+            return Err(syn::Error::new(arg.span, msg));
+        };
+        if seen.contains(option) {
+            let msg = format!("`{}` is given twice", option);
+            return Err(syn::Error::new(arg.span, msg));
+        }
+        seen.push(*option);
+        i += 1;
+        match value {
+            None => {}
+            Some(param @ Param::Items(_)) => {
+                check_items(option, *param, arg.span, &args[i..], i, &mut numbers)?;
+                return Ok(numbers);
+            }
+            Some(param) => {
+                let Some(value) = args.get(i) else {
+                    let msg = format!("`{}` expects {}", option, param.name());
+                    return Err(syn::Error::new(arg.span, msg));
+                };
+                check_param(option, *param, value, i, &mut numbers)?;
+                i += 1;
+            }
+        }
+    }
+    if spec.options.iter().any(|(option, _)| *option == "ITEMS") && !seen.contains(&"ITEMS") {
+        if args[i..].iter().any(standalone) {
+            return Ok(numbers);
+        }
+        let msg = format!("`{}` expects `ITEMS` and the items to add", spec.name);
+        return Err(syn::Error::new(args[0].span, msg));
+    }
+    Ok(numbers)
+}
+
+/// Check the items of `Param::Items` or `Param::Pairs`, which run to the end of the command
+fn check_items(
+    name: &str,
+    param: Param,
+    span: Span,
+    items: &[Arg],
+    start: usize,
+    numbers: &mut Vec<(usize, &'static str)>,
+) -> syn::Result<()> {
+    let hidden = items.iter().any(standalone);
+    match param {
+        Param::Items(item) => {
+            if items.is_empty() {
+                let msg = format!("`{}` expects at least one {}", name, item.noun());
+                return Err(syn::Error::new(span, msg));
+            }
+            for (index, arg) in items.iter().enumerate() {
+                if !standalone(arg) {
+                    check_param(name, *item, arg, start + index, numbers)?;
+                }
+            }
+        }
+        Param::Pairs(value) => {
+            if hidden {
+                return Ok(());
+            }
+            if items.is_empty() || items.len() % 2 == 1 {
+                let msg = format!(
+                    "`{}` expects items, each followed by {}",
+                    name,
+                    value.name()
+                );
+                return Err(syn::Error::new(span, msg));
+            }
+            for (index, arg) in items.iter().enumerate().skip(1).step_by(2) {
+                check_param(name, *value, arg, start + index, numbers)?;
+            }
+        }
+        _ => unreachable!(),
+    }
+    Ok(())
+}
+
+/// Check a literal parameter, and record the index of a number
+fn check_param(
+    name: &str,
+    param: Param,
+    arg: &Arg,
+    index: usize,
+    numbers: &mut Vec<(usize, &'static str)>,
+) -> syn::Result<()> {
+    let ty = match param {
+        Param::Any | Param::Items(_) | Param::Pairs(_) => return Ok(()),
+        Param::Rate(_) | Param::Decay | Param::Quantile | Param::Float => "f64",
+        Param::Count(_) => "u64",
+        Param::Int => "i64",
+    };
+    numbers.push((index, ty));
+    let Some(word) = arg.word() else {
+        return Ok(());
+    };
+    let valid = match param {
+        Param::Rate(_) => word.parse::<f64>().is_ok_and(|x| x > 0.0 && x < 1.0),
+        Param::Decay => word.parse::<f64>().is_ok_and(|x| x > 0.0 && x <= 1.0),
+        Param::Quantile => word.parse::<f64>().is_ok_and(|x| (0.0..=1.0).contains(&x)),
+        Param::Float => word.parse::<f64>().is_ok(),
+        Param::Count(_) => word.parse::<u64>().is_ok_and(|x| x > 0),
+        _ => word.parse::<i64>().is_ok(),
+    };
+    if !valid {
+        let msg = format!(
+            "`{}` expects {} as {}, found `{}`",
+            name,
+            param.name(),
+            param.range(),
+            word
+        );
+        return Err(syn::Error::new(arg.span, msg));
+    }
+    Ok(())
+}
+
+impl Param {
+    fn name(self) -> &'static str {
+        match self {
+            Param::Any => "an item",
+            Param::Rate(name) | Param::Count(name) => name,
+            Param::Decay => "the decay",
+            Param::Quantile => "a quantile",
+            Param::Float => "a value",
+            Param::Int => "an integer",
+            Param::Items(param) | Param::Pairs(param) => param.name(),
+        }
+    }
+
+    /// The name without its article
+    fn noun(self) -> &'static str {
+        let name = self.name();
+        name.split_once(' ').map_or(name, |(_, noun)| noun)
+    }
+
+    fn range(self) -> &'static str {
+        match self {
+            Param::Rate(_) => "a number between 0 and 1, exclusive",
+            Param::Decay => "a number above 0 and at most 1",
+            Param::Quantile => "a number from 0 to 1",
+            Param::Float => "a number",
+            Param::Count(_) => "a positive integer",
+            _ => "an integer",
+        }
+    }
+}
+
+/// Arguments that expand into a varying number of arguments
+fn standalone(arg: &Arg) -> bool {
+    arg.pieces
+        .iter()
+        .any(|piece| piece.standalone_kind().is_some())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expand(input: &str) -> syn::Result<String> {
+        expand_bloom(input.parse().unwrap()).map(|out| out.to_string())
+    }
+
+    fn err(input: &str) -> String {
+        expand(input).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn bloom_expand() {
+        let output = expand("BF.RESERVE users 0.001 {capacity} EXPANSION 2 NONSCALING").unwrap();
+        assert!(
+            output.starts_with(":: redis_rs_macro :: TypedCmd :: < () > :: new ("),
+            "{}",
+            output
+        );
+        assert!(
+            output.contains("cmd . arg (:: core :: convert :: identity :: < u64 > (capacity))"),
+            "{}",
+            output
+        );
+        let output = expand("BF.MEXISTS users {..names}").unwrap();
+        assert!(
+            output.contains("TypedCmd :: < Vec < bool > >"),
+            "{}",
+            output
+        );
+        let output = expand("BF.INSERT users ERROR {rate} ITEMS alice {..names}").unwrap();
+        assert!(output.contains("identity :: < f64 > (rate)"), "{}", output);
+        let output = expand("CMS.INCRBY counts page:1 {n} page:2 3").unwrap();
+        assert!(output.contains("identity :: < i64 > (n)"), "{}", output);
+        assert!(output.contains("TypedCmd :: < Vec < u64 > >"), "{}", output);
+        let output = expand("TOPK.RESERVE top 10 2000 7 0.925").unwrap();
+        assert!(output.contains("TypedCmd :: < () >"), "{}", output);
+        let output = expand("TDIGEST.QUANTILE latency 0.5 0.99 {q}").unwrap();
+        assert!(output.contains("TypedCmd :: < Vec < f64 > >"), "{}", output);
+        let output = expand("CF.EXISTS seen {id} -> u8").unwrap();
+        assert!(output.contains("TypedCmd :: < u8 >"), "{}", output);
+        let output = expand("CMS.MERGE dest 2 a b -> ()").unwrap();
+        assert!(output.contains("\"CMS.MERGE\""), "{}", output);
+        expand("TOPK.LIST top WITHCOUNT").unwrap();
+        expand("TDIGEST.CREATE latency COMPRESSION 100").unwrap();
+        expand("CF.INSERTNX seen NOCREATE {..options}").unwrap();
+    }
+
+    #[test]
+    fn bloom_errors() {
+        let e = err("GET k");
+        assert!(e.contains("expected a RedisBloom command"), "{}", e);
+        let e = err("BF.INFO users");
+        assert!(
+            e.contains("the reply type of `BF.INFO` isn't known"),
+            "{}",
+            e
+        );
+        let e = err("BF.ADD");
+        assert!(e.contains("`BF.ADD` expects a key"), "{}", e);
+        let e = err("BF.RESERVE users 0.01");
+        assert!(
+            e.contains("`BF.RESERVE` expects the error rate and the capacity after the key"),
+            "{}",
+            e
+        );
+        let e = err("BF.RESERVE users 1.5 1000");
+        assert!(
+            e.contains(
+                "`BF.RESERVE` expects the error rate as a number between 0 and 1, exclusive, \
+                 found `1.5`"
+            ),
+            "{}",
+            e
+        );
+        let e = err("BF.RESERVE users 0.01 0");
+        assert!(
+            e.contains("the capacity as a positive integer, found `0`"),
+            "{}",
+            e
+        );
+        let e = err("BF.RESERVE users 0.01 1e6");
+        assert!(e.contains("found `1e6`"), "{}", e);
+        let e = err("BF.RESERVE users 0.01 100 EXPAND 2");
+        assert!(
+            e.contains("unknown `BF.RESERVE` option; expected one of EXPANSION"),
+            "{}",
+            e
+        );
+        let e = err("BF.RESERVE users 0.01 100 EXPANSION");
+        assert!(e.contains("`EXPANSION` expects the expansion"), "{}", e);
+        let e = err("BF.RESERVE users 0.01 100 NONSCALING NONSCALING");
+        assert!(e.contains("`NONSCALING` is given twice"), "{}", e);
+        let e = err("BF.ADD users alice bob");
+        assert!(e.contains("`BF.ADD` takes no more arguments"), "{}", e);
+        let e = err("BF.MADD users");
+        assert!(e.contains("`BF.MADD` expects at least one item"), "{}", e);
+        let e = err("BF.INSERT users CAPACITY 100");
+        assert!(e.contains("expects `ITEMS` and the items to add"), "{}", e);
+        let e = err("BF.INSERT users ITEMS");
+        assert!(e.contains("`ITEMS` expects at least one"), "{}", e);
+        let e = err("CMS.INITBYPROB counts 0.001 1");
+        assert!(
+            e.contains("the probability as a number between 0 and 1"),
+            "{}",
+            e
+        );
+        let e = err("CMS.INCRBY counts page:1");
+        assert!(
+            e.contains("expects items, each followed by an integer"),
+            "{}",
+            e
+        );
+        let e = err("CMS.INCRBY counts page:1 many");
+        assert!(e.contains("found `many`"), "{}", e);
+        let e = err("TOPK.RESERVE top 10 2000 7 1.5");
+        assert!(
+            e.contains("the decay as a number above 0 and at most 1"),
+            "{}",
+            e
+        );
+        let e = err("TDIGEST.QUANTILE latency 0.5 99");
+        assert!(
+            e.contains("a quantile as a number from 0 to 1, found `99`"),
+            "{}",
+            e
+        );
+        let e = err("TDIGEST.ADD latency 1.5 fast");
+        assert!(e.contains("a value as a number, found `fast`"), "{}", e);
+    }
+}
@@ -0,0 +1,389 @@
+use crate::lock::expand_key;
+use crate::marker::Marker;
+use proc_macro2::{Span, TokenStream, TokenTree};
+use quote::quote;
+use syn::parse::{Parse, ParseStream};
+use syn::spanned::Spanned;
+use syn::{Block, Expr, FnArg, Ident, ItemFn, LitStr, Pat, ReturnType, Token};
+
+/// The options shared by `#[redis_cached]` and `redis_cache!`, e.g. `key = "user:{id}", ttl = 300`
+#[derive(Default)]
+pub(crate) struct CacheOptions {
+    key: Option<TokenStream>,
+    ttl: Option<Expr>,
+    marker: Option<Marker>,
+}
+
+impl CacheOptions {
+    /// Parse the option named `name`, returning `false` if it isn't a cache option
+    pub(crate) fn parse_option(&mut self, name: &Ident, input: ParseStream) -> syn::Result<bool> {
+        let twice = || syn::Error::new(name.span(), format!("`{}` is given twice", name));
+        match name.to_string().as_str() {
+            "key" => {
+                if self.key.is_some() {
+                    return Err(twice());
+                }
+                input.parse::<Token![=]>()?;
+                let mut key = TokenStream::new();
+                while !input.is_empty() && !input.peek(Token![,]) {
+                    key.extend([input.parse::<TokenTree>()?]);
+                }
+                self.key = Some(key);
+            }
+            "ttl" => {
+                if self.ttl.is_some() {
+                    return Err(twice());
+                }
+                input.parse::<Token![=]>()?;
+                self.ttl = Some(input.parse()?);
+            }
+            _ => match Marker::parse_format(name)? {
+                Some(marker) => {
+                    if self.marker.is_some() {
+                        let msg = "the serialization format is given twice";
+                        return Err(syn::Error::new(name.span(), msg));
+                    }
+                    self.marker = Some(marker);
+                }
+                None => return Ok(false),
+            },
+        }
+        Ok(true)
+    }
+}
+
+/// Generate the cache-aside block of `#[redis_cached]` and `redis_cache!`. It reads the key with
+/// `GET` and evaluates to the deserialized value on a hit; on a miss it evaluates `compute`, a
+/// `Result`, and stores an `Ok` value with `SET` before evaluating to it. A stored value that
+/// doesn't deserialize, such as one written in an older format, is a miss and gets overwritten.
+/// Errors of the `GET` are returned with `?`, and those of the `SET` are ignored, since the value
+/// was computed either way.
+pub(crate) fn expand_cache(
+    con: &TokenStream,
+    options: CacheOptions,
+    compute: TokenStream,
+    asyncness: bool,
+) -> syn::Result<TokenStream> {
+    let CacheOptions { key, ttl, marker } = options;
+    let Some(key) = key else {
+        let msg = "expected `key = ..`, such as `key = \"user:{id}\"`";
+        return Err(syn::Error::new(Span::call_site(), msg));
+    };
+    // A string literal is a format string, so its `{name}` captures variables like `format!`
+    let key = match syn::parse2::<LitStr>(key.clone()) {
+        Ok(lit) => expand_key(quote!({ ::std::format!(#lit) }))?,
+        Err(_) => expand_key(key)?,
+    };
+    let marker = match marker {
+        Some(marker) => marker,
+        None => Marker::default_format("cached values")?,
+    };
+    let wrapper = marker.reply_wrapper();
+    let key_var = Ident::new("key", Span::mixed_site());
+    let cmd = Ident::new("cmd", Span::mixed_site());
+    let cached = Ident::new("cached", Span::mixed_site());
+    let value = Ident::new("value", Span::mixed_site());
+    let result = Ident::new("result", Span::mixed_site());
+    let serialized = marker.expand(&syn::parse_quote!(#value));
+    let expiry = ttl.map(|ttl| quote!(#cmd.arg("EX").arg(#ttl);));
+    let err = Ident::new("err", Span::mixed_site());
+    let query = |ty: TokenStream| match asyncness {
+        true => quote!(redis::Cmd::query_async::<_, #ty>(&#cmd, &mut *#con).await),
+        false => quote!(redis::Cmd::query::<#ty>(&#cmd, &mut *#con)),
+    };
+    let get = query(quote!(::core::option::Option<#wrapper<_>>));
+    let set = query(quote!(()));
+    Ok(quote! {
+        {
+            let #key_var = #key;
+            let mut #cmd = redis::cmd("GET");
+            #cmd.arg(&#key_var);
+            let #cached = match #get {
+                ::core::result::Result::Err(#err)
+                    if #err.kind() == redis::ErrorKind::TypeError =>
+                {
+                    ::core::result::Result::Ok(::core::option::Option::None)
+                }
+                #cached => #cached,
+            }?;
+            match #cached {
+                ::core::option::Option::Some(#wrapper(#value)) => ::core::result::Result::Ok(#value),
+                ::core::option::Option::None => {
+                    #[allow(clippy::redundant_closure_call)]
+                    let #result = #compute;
+                    if let ::core::result::Result::Ok(#value) = &#result {
+                        let mut #cmd = redis::cmd("SET");
+                        #cmd.arg(&#key_var).arg(#serialized);
+                        #expiry
+                        let _ = #set;
+                    }
+                    #result
+                }
+            }
+        }
+    })
+}
+
+/// The arguments of `#[redis_cached(..)]`: the cache options, and `con = <parameter>` to name the
+/// connection parameter when it isn't `con`
+struct CachedArgs {
+    con: Option<Ident>,
+    options: CacheOptions,
+}
+
+impl Parse for CachedArgs {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut con = None;
+        let mut options = CacheOptions::default();
+        while !input.is_empty() {
+            let name: Ident = input.parse()?;
+            if name == "con" {
+                if con.is_some() {
+                    return Err(syn::Error::new(name.span(), "`con` is given twice"));
+                }
+                input.parse::<Token![=]>()?;
+                con = Some(input.parse()?);
+            } else if !options.parse_option(&name, input)? {
+                let msg = "expected `key`, `ttl`, `con`, `json` or `msgpack`";
+                return Err(syn::Error::new(name.span(), msg));
+            }
+            if !input.is_empty() {
+                input.parse::<Token![,]>()?;
+            }
+        }
+        Ok(CachedArgs { con, options })
+    }
+}
+
+/// Generate a `#[redis_cached(..)]` function, whose body only runs when its result isn't cached
+pub(crate) fn expand_cached(args: TokenStream, input: TokenStream) -> syn::Result<TokenStream> {
+    let CachedArgs { con, options } = syn::parse2(args)?;
+    let mut item: ItemFn = syn::parse2(input)?;
+    let ReturnType::Type(_, ret) = &item.sig.output else {
+        let msg = "cached functions return a `Result`, whose error can be converted from \
+                   `redis::RedisError`";
+        return Err(syn::Error::new(item.sig.span(), msg));
+    };
+    let name = con.as_ref().map_or("con".to_string(), Ident::to_string);
+    let con = item
+        .sig
+        .inputs
+        .iter()
+        .find_map(|input| match input {
+            FnArg::Typed(param) => match &*param.pat {
+                Pat::Ident(pat) if pat.ident == name => Some(pat.ident.clone()),
+                _ => None,
+            },
+            FnArg::Receiver(_) => None,
+        })
+        .ok_or_else(|| {
+            let msg = format!(
+                "cached functions take the connection as a parameter named `{}`, or another \
+                 name given with `con = <parameter>`",
+                name
+            );
+            let span = con.as_ref().map_or(item.sig.inputs.span(), Ident::span);
+            syn::Error::new(span, msg)
+        })?;
+    let block = &item.block;
+    // The body is wrapped in a closure or an async block, so that its `return`s and `?`s produce
+    // the result to cache instead of leaving the function
+    let compute = match item.sig.asyncness {
+        Some(_) => quote! {
+            ::redis_rs_macro::__private::cache::output::<#ret, _>(async #block).await
+        },
+        None => quote!((|| -> #ret #block)()),
+    };
+    let cache = expand_cache(
+        &quote!(#con),
+        options,
+        compute,
+        item.sig.asyncness.is_some(),
+    )?;
+    *item.block = syn::parse2::<Block>(cache)?;
+    Ok(quote!(#item))
+}
+
+/// The input of `redis_cache!`, e.g. `con, key = "config:{name}", ttl = 60, || load(name)`
+struct CacheInput {
+    con: Expr,
+    options: CacheOptions,
+    compute: Expr,
+}
+
+impl Parse for CacheInput {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let con = input.parse()?;
+        input.parse::<Token![,]>()?;
+        let mut options = CacheOptions::default();
+        // Options are names followed by `=` or `,`, and the closure comes last
+        while input.peek(Ident) && (input.peek2(Token![=]) || input.peek2(Token![,])) {
+            let name: Ident = input.parse()?;
+            if !options.parse_option(&name, input)? {
+                let msg = "expected `key`, `ttl`, `json` or `msgpack`";
+                return Err(syn::Error::new(name.span(), msg));
+            }
+            input.parse::<Token![,]>()?;
+        }
+        if input.is_empty() {
+            return Err(input.error("expected the closure that computes the value"));
+        }
+        let compute = input.parse()?;
+        input.parse::<Option<Token![,]>>()?;
+        Ok(CacheInput {
+            con,
+            options,
+            compute,
+        })
+    }
+}
+
+/// Generate a `redis_cache!` invocation, which evaluates to the cached value, or calls the closure
+/// and caches its value
+pub(crate) fn expand_cache_expr(input: TokenStream) -> syn::Result<TokenStream> {
+    let CacheInput {
+        con,
+        options,
+        compute,
+    } = syn::parse2(input)?;
+    let con_var = Ident::new("con", Span::mixed_site());
+    let compute = quote! {
+        ::core::result::Result::Ok::<_, redis::RedisError>((#compute)())
+    };
+    let cache = expand_cache(&quote!(#con_var), options, compute, false)?;
+    // The block runs in a closure, so that its `?`s produce the result of the macro
+    Ok(quote! {
+        {
+            let #con_var = &mut *#con;
+            (|| -> redis::RedisResult<_> #cache)()
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expand(args: &str, input: &str) -> syn::Result<String> {
+        expand_cached(args.parse().unwrap(), input.parse().unwrap())
+            .map(|output| output.to_string())
+    }
+
+    #[test]
+    fn cached_expand() {
+        let output = expand(
+            r#"key = "user:{id}", ttl = 300, msgpack"#,
+            "fn load(con: &mut Connection, id: u64) -> RedisResult<User> { fetch(id) }",
+        )
+        .unwrap();
+        assert!(
+            output.contains(r#":: std :: format ! ("user:{id}")"#),
+            "{}",
+            output
+        );
+        assert!(
+            output.contains("match redis :: Cmd :: query :: < :: core :: option :: Option < :: redis_rs_macro :: Msgpack < _ > > > (& cmd , & mut * con) { :: core :: result :: Result :: Err (err) if err . kind () == redis :: ErrorKind :: TypeError"),
+            "{}",
+            output
+        );
+        assert!(
+            output.contains("let result = (| | -> RedisResult < User > { fetch (id) }) () ;"),
+            "{}",
+            output
+        );
+        assert!(
+            output.contains(r#"cmd . arg ("EX") . arg (300) ;"#),
+            "{}",
+            output
+        );
+
+        let output = expand(
+            "key = user:{id}, con = c, msgpack",
+            "async fn load(c: &mut C, id: u64) -> Result<User, E> { fetch(id).await }",
+        )
+        .unwrap();
+        assert!(
+            output.contains(
+                "output :: < Result < User , E > , _ > (async { fetch (id) . await }) . await"
+            ),
+            "{}",
+            output
+        );
+        assert!(
+            output.contains(
+                "let _ = redis :: Cmd :: query_async :: < _ , () > (& cmd , & mut * c) . await ;"
+            ),
+            "{}",
+            output
+        );
+        assert!(!output.contains("\"EX\""), "{}", output);
+    }
+
+    #[test]
+    fn cache_expand() {
+        let output = expand_cache_expr(
+            "&mut con, key = config:{name}, ttl = 60, msgpack, move || load(name)"
+                .parse()
+                .unwrap(),
+        )
+        .unwrap()
+        .to_string();
+        assert!(
+            output
+                .starts_with("{ let con = & mut * & mut con ; (|| -> redis :: RedisResult < _ > {"),
+            "{}",
+            output
+        );
+        assert!(
+            output.contains("Ok :: < _ , redis :: RedisError > ((move | | load (name)) ())"),
+            "{}",
+            output
+        );
+        assert!(output.contains("Msgpack < _ >"), "{}", output);
+        let output = expand_cache_expr(r#"con, key = "a", || 1,"#.parse().unwrap())
+            .unwrap()
+            .to_string();
+        assert!(
+            output.contains(":: redis_rs_macro :: Json < _ >"),
+            "{}",
+            output
+        );
+    }
+
+    #[test]
+    fn cache_errors() {
+        let err = |input: &str| {
+            expand_cache_expr(input.parse().unwrap())
+                .unwrap_err()
+                .to_string()
+        };
+        let e = err("con, ttl = 1, || 1");
+        assert!(e.contains("expected `key = ..`"), "{}", e);
+        let e = err("con, key = a, con = c, || 1");
+        assert!(e.contains("expected `key`, `ttl`, `json`"), "{}", e);
+        let e = err("con, key = a, ttl = 1,");
+        assert!(e.contains("expected the closure"), "{}", e);
+    }
+
+    #[test]
+    fn cached_errors() {
+        let err = |args: &str, input: &str| expand(args, input).unwrap_err().to_string();
+        let f = "fn f(con: &mut C) -> R { g() }";
+        let e = err("ttl = 1, msgpack", f);
+        assert!(e.contains("expected `key = ..`"), "{}", e);
+        let e = err("key = a, ttl = 1, ttl = 2, msgpack", f);
+        assert!(e.contains("given twice"), "{}", e);
+        let e = err("key = a, msgpack, msgpack", f);
+        assert!(e.contains("format is given twice"), "{}", e);
+        let e = err("key = a, expiry = 1", f);
+        assert!(e.contains("expected `key`"), "{}", e);
+        let e = err("key = a b, msgpack", f);
+        assert!(e.contains("single key"), "{}", e);
+        let e = err("key = a, msgpack", "fn f(con: &mut C) { g() }");
+        assert!(e.contains("return a `Result`"), "{}", e);
+        let e = err("key = a, msgpack", "fn f(c: &mut C) -> R { g() }");
+        assert!(e.contains("parameter named `con`"), "{}", e);
+        let e = err("key = a, con = conn, msgpack", f);
+        assert!(e.contains("parameter named `conn`"), "{}", e);
+    }
+}
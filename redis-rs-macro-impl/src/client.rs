@@ -0,0 +1,200 @@
+use crate::parse::{Arg, Command};
+
+/// The filters of `CLIENT KILL`, with what their values are
+const FILTERS: &[(&str, Value)] = &[
+    ("ID", Value::Integer),
+    ("ADDR", Value::Address),
+    ("LADDR", Value::Address),
+    (
+        "TYPE",
+        Value::OneOf(&["normal", "master", "slave", "replica", "pubsub"]),
+    ),
+    ("USER", Value::Any),
+    ("SKIPME", Value::OneOf(&["yes", "no"])),
+    ("MAXAGE", Value::Integer),
+];
+
+/// The kind of value a filter takes
+#[derive(Clone, Copy)]
+enum Value {
+    /// A non-negative integer, such as a client ID
+    Integer,
+    /// An `ip:port` address
+    Address,
+    /// One of the listed words, in any case
+    OneOf(&'static [&'static str]),
+    Any,
+}
+
+impl Value {
+    fn check(self, text: &str) -> Result<(), String> {
+        match self {
+            Value::Integer if text.parse::<u64>().is_err() => {
+                Err("expected a non-negative integer".to_owned())
+            }
+            Value::Address if !is_address(text) => {
+                Err("expected an `ip:port` address, such as `127.0.0.1:6379`".to_owned())
+            }
+            Value::OneOf(words) if !words.iter().any(|word| word.eq_ignore_ascii_case(text)) => {
+                Err(format!("expected one of {}", words.join(", ")))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+fn is_address(text: &str) -> bool {
+    text.rsplit_once(':')
+        .is_some_and(|(host, port)| !host.is_empty() && port.parse::<u16>().is_ok())
+}
+
+/// A literal argument, or `None` for one that is only known at runtime. Arguments that can expand
+/// to any number of values stop the check.
+fn literal(arg: &Arg) -> Option<Option<String>> {
+    if arg
+        .pieces
+        .iter()
+        .any(|piece| piece.standalone_kind().is_some())
+    {
+        return None;
+    }
+    Some(arg.word())
+}
+
+/// Check the filters of `CLIENT KILL` commands, so that unknown filters, filters without a value
+/// or given twice, and literal values of the wrong kind fail to compile rather than on the server.
+/// The older form with a single `ip:port` argument is checked as an address.
+pub(crate) fn check_client_kill(command: &Command) -> syn::Result<()> {
+    let [name, sub, args @ ..] = command.args.as_slice() else {
+        return Ok(());
+    };
+    let is_kill = name
+        .word()
+        .is_some_and(|name| name.eq_ignore_ascii_case("CLIENT"))
+        && sub
+            .word()
+            .is_some_and(|sub| sub.eq_ignore_ascii_case("KILL"));
+    if !is_kill {
+        return Ok(());
+    }
+    if let [addr] = args {
+        let Some(Some(text)) = literal(addr) else {
+            return Ok(());
+        };
+        if is_address(&text) {
+            return Ok(());
+        }
+        if !FILTERS
+            .iter()
+            .any(|(filter, _)| filter.eq_ignore_ascii_case(&text))
+        {
+            let msg = format!(
+                "invalid CLIENT KILL address `{}`; expected an `ip:port` address, or filters such \
+                 as `ID 42`",
+                text
+            );
+            return Err(syn::Error::new(addr.span, msg));
+        }
+    }
+    let mut seen: Vec<&str> = vec![];
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let Some(filter) = literal(arg) else {
+            return Ok(());
+        };
+        // A filter name that is only known at runtime hides what its value is
+        let Some(filter) = filter else {
+            args.next();
+            continue;
+        };
+        let Some((name, value)) = FILTERS
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(&filter))
+        else {
+            let names: Vec<_> = FILTERS.iter().map(|(name, _)| *name).collect();
+            let msg = format!(
+                "unknown CLIENT KILL filter `{}`; expected {}",
+                filter,
+                names.join(", ")
+            );
+            return Err(syn::Error::new(arg.span, msg));
+        };
+        if seen.contains(name) {
+            let msg = format!("the CLIENT KILL filter `{}` is given twice", name);
+            return Err(syn::Error::new(arg.span, msg));
+        }
+        seen.push(name);
+        let Some(next) = args.next() else {
+            let msg = format!("the CLIENT KILL filter `{}` expects a value", name);
+            return Err(syn::Error::new(arg.span, msg));
+        };
+        match literal(next) {
+            Some(Some(text)) => {
+                if let Err(expected) = value.check(&text) {
+                    let msg = format!(
+                        "invalid value `{}` for the CLIENT KILL filter `{}`; {}",
+                        text, name, expected
+                    );
+                    return Err(syn::Error::new(next.span, msg));
+                }
+            }
+            Some(None) => {}
+            None => return Ok(()),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse::parse_command;
+    use proc_macro2::TokenStream;
+
+    fn check(input: &str) -> Result<(), String> {
+        let tokens: TokenStream = input.parse().unwrap();
+        check_client_kill(&parse_command(tokens).unwrap()).map_err(|err| err.to_string())
+    }
+
+    #[test]
+    fn client_kill_valid() {
+        for input in [
+            "CLIENT KILL \"127.0.0.1:6379\"",
+            "CLIENT KILL {addr}",
+            "client kill id 42 type normal skipme no",
+            "CLIENT KILL ADDR \"10.0.0.1:52340\" LADDR \"[::1]:6379\" USER alice MAXAGE 3600",
+            "CLIENT KILL TYPE replica ID {id}",
+            "CLIENT KILL {filter} whatever ID 1",
+            "CLIENT KILL ID 1 {..filters} ID 2",
+            "CLIENT LIST TYPE anything",
+        ] {
+            assert_eq!(check(input), Ok(()), "{}", input);
+        }
+    }
+
+    #[test]
+    fn client_kill_errors() {
+        let e = check("CLIENT KILL localhost").unwrap_err();
+        assert!(
+            e.contains("invalid CLIENT KILL address `localhost`"),
+            "{}",
+            e
+        );
+        let e = check("CLIENT KILL NAME worker").unwrap_err();
+        assert!(e.contains("unknown CLIENT KILL filter `NAME`"), "{}", e);
+        let e = check("CLIENT KILL ID 1 ID 2").unwrap_err();
+        assert!(e.contains("`ID` is given twice"), "{}", e);
+        let e = check("CLIENT KILL USER alice ID").unwrap_err();
+        assert!(e.contains("`ID` expects a value"), "{}", e);
+        let e = check("CLIENT KILL ID -1").unwrap_err();
+        assert!(e.contains("non-negative integer"), "{}", e);
+        let e = check("CLIENT KILL TYPE primary").unwrap_err();
+        assert!(e.contains("expected one of normal, master"), "{}", e);
+        let e = check("CLIENT KILL SKIPME maybe").unwrap_err();
+        assert!(e.contains("expected one of yes, no"), "{}", e);
+        let e = check("CLIENT KILL ADDR \"10.0.0.1\"").unwrap_err();
+        assert!(e.contains("`ip:port` address"), "{}", e);
+        let e = check("CLIENT KILL ID").unwrap_err();
+        assert!(e.contains("`ID` expects a value"), "{}", e);
+    }
+}
@@ -0,0 +1,288 @@
+use crate::parse::Arg;
+
+/// Where the keys of a command are, by position. Positions count the command name as 0.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Keys {
+    /// Keys at positions `first..=last`, every `step` arguments. A negative `last` counts from the
+    /// end, so `-1` is the last argument.
+    Range {
+        first: usize,
+        last: isize,
+        step: usize,
+    },
+    /// A count of keys at `index`, followed by that many keys
+    Count { index: usize },
+    /// The first half of the arguments after the `STREAMS` keyword
+    Streams,
+}
+
+/// Whether a command changes data, as in the `write` and `readonly` flags of `COMMAND INFO`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Access {
+    /// Changes keys, or the whole dataset
+    Write,
+    /// Only reads keys
+    Read,
+    /// Neither, such as connection and server commands
+    Other,
+}
+
+/// What is known about a command
+#[derive(Debug)]
+pub(crate) struct CommandInfo {
+    /// The uppercase name of the command, followed by its subcommand for container commands
+    pub(crate) name: &'static str,
+    /// The number of arguments including the name, or its negation when that is the least number
+    /// of arguments the command takes, as in `COMMAND INFO`
+    pub(crate) arity: i32,
+    pub(crate) access: Access,
+    /// The Redis version that added the command
+    pub(crate) since: &'static str,
+    pub(crate) keys: &'static [Keys],
+}
+
+const fn range(first: usize, last: isize, step: usize) -> Keys {
+    Keys::Range { first, last, step }
+}
+
+const NO_KEYS: &[Keys] = &[];
+const KEY: &[Keys] = &[range(1, 1, 1)];
+const TWO_KEYS: &[Keys] = &[range(1, 2, 1)];
+const ALL_KEYS: &[Keys] = &[range(1, -1, 1)];
+const KEY_VALUE_PAIRS: &[Keys] = &[range(1, -1, 2)];
+/// Blocking commands end with a timeout
+const KEYS_THEN_TIMEOUT: &[Keys] = &[range(1, -2, 1)];
+const SUBCOMMAND_KEY: &[Keys] = &[range(2, 2, 1)];
+const COUNTED_KEYS: &[Keys] = &[Keys::Count { index: 1 }];
+const SCRIPT_KEYS: &[Keys] = &[Keys::Count { index: 2 }];
+const DESTINATION_AND_COUNTED_KEYS: &[Keys] = &[range(1, 1, 1), Keys::Count { index: 2 }];
+
+/// Builds the table from `(name, arity, access, since)` entries, grouped by where their keys are
+macro_rules! commands {
+    ($(
+        $keys:ident => [$(($name:literal, $arity:literal, $access:ident, $since:literal)),* $(,)?],
+    )*) => {
+        &[$($(CommandInfo {
+            name: $name,
+            arity: $arity,
+            access: Access::$access,
+            since: $since,
+            keys: $keys,
+        },)*)*]
+    };
+}
+
+/// The commands the macros know about
+static COMMANDS: &[CommandInfo] = commands! {
+    KEY => [
+        ("APPEND", 3, Write, "2.0.0"), ("BITCOUNT", -2, Read, "2.6.0"),
+        ("BITFIELD", -2, Write, "3.2.0"), ("BITFIELD_RO", -2, Read, "6.0.0"),
+        ("BITPOS", -3, Read, "2.8.7"), ("DECR", 2, Write, "1.0.0"), ("DECRBY", 3, Write, "1.0.0"),
+        ("DUMP", 2, Read, "2.6.0"), ("EXPIRE", -3, Write, "1.0.0"),
+        ("EXPIREAT", -3, Write, "1.2.0"), ("EXPIRETIME", 2, Read, "7.0.0"),
+        ("GEOADD", -5, Write, "3.2.0"), ("GEODIST", -4, Read, "3.2.0"),
+        ("GEOHASH", -2, Read, "3.2.0"), ("GEOPOS", -2, Read, "3.2.0"),
+        ("GEORADIUS", -6, Write, "3.2.0"), ("GEORADIUS_RO", -6, Read, "3.2.10"),
+        ("GEORADIUSBYMEMBER", -5, Write, "3.2.0"), ("GEORADIUSBYMEMBER_RO", -5, Read, "3.2.10"),
+        ("GEOSEARCH", -7, Read, "6.2.0"), ("GET", 2, Read, "1.0.0"), ("GETBIT", 3, Read, "2.2.0"),
+        ("GETDEL", 2, Write, "6.2.0"), ("GETEX", -2, Write, "6.2.0"),
+        ("GETRANGE", 4, Read, "2.4.0"), ("GETSET", 3, Write, "1.0.0"), ("HDEL", -3, Write, "2.0.0"),
+        ("HEXISTS", 3, Read, "2.0.0"), ("HEXPIRE", -6, Write, "7.4.0"), ("HGET", 3, Read, "2.0.0"),
+        ("HGETALL", 2, Read, "2.0.0"), ("HINCRBY", 4, Write, "2.0.0"),
+        ("HINCRBYFLOAT", 4, Write, "2.6.0"), ("HKEYS", 2, Read, "2.0.0"),
+        ("HLEN", 2, Read, "2.0.0"), ("HMGET", -3, Read, "2.0.0"), ("HMSET", -4, Write, "2.0.0"),
+        ("HPEXPIRE", -6, Write, "7.4.0"), ("HRANDFIELD", -2, Read, "6.2.0"),
+        ("HSCAN", -3, Read, "2.8.0"), ("HSET", -4, Write, "2.0.0"), ("HSETNX", 4, Write, "2.0.0"),
+        ("HSTRLEN", 3, Read, "3.2.0"), ("HTTL", -5, Read, "7.4.0"), ("HVALS", 2, Read, "2.0.0"),
+        ("INCR", 2, Write, "1.0.0"), ("INCRBY", 3, Write, "1.0.0"),
+        ("INCRBYFLOAT", 3, Write, "2.6.0"), ("LINDEX", 3, Read, "1.0.0"),
+        ("LINSERT", 5, Write, "2.2.0"), ("LLEN", 2, Read, "1.0.0"), ("LPOP", -2, Write, "1.0.0"),
+        ("LPOS", -3, Read, "6.0.6"), ("LPUSH", -3, Write, "1.0.0"), ("LPUSHX", -3, Write, "2.2.0"),
+        ("LRANGE", 4, Read, "1.0.0"), ("LREM", 4, Write, "1.0.0"), ("LSET", 4, Write, "1.0.0"),
+        ("LTRIM", 4, Write, "1.0.0"), ("PERSIST", 2, Write, "2.2.0"),
+        ("PEXPIRE", -3, Write, "2.6.0"), ("PEXPIREAT", -3, Write, "2.6.0"),
+        ("PEXPIRETIME", 2, Read, "7.0.0"), ("PFADD", -2, Write, "2.8.9"),
+        ("PSETEX", 4, Write, "2.6.0"), ("PTTL", 2, Read, "2.6.0"), ("RESTORE", -4, Write, "2.6.0"),
+        ("RPOP", -2, Write, "1.0.0"), ("RPUSH", -3, Write, "1.0.0"), ("RPUSHX", -3, Write, "2.2.0"),
+        ("SADD", -3, Write, "1.0.0"), ("SCARD", 2, Read, "1.0.0"), ("SET", -3, Write, "1.0.0"),
+        ("SETBIT", 4, Write, "2.2.0"), ("SETEX", 4, Write, "2.0.0"), ("SETNX", 3, Write, "1.0.0"),
+        ("SETRANGE", 4, Write, "2.2.0"), ("SISMEMBER", 3, Read, "1.0.0"),
+        ("SMEMBERS", 2, Read, "1.0.0"), ("SMISMEMBER", -3, Read, "6.2.0"),
+        ("SORT", -2, Write, "1.0.0"), ("SORT_RO", -2, Read, "7.0.0"), ("SPOP", -2, Write, "1.0.0"),
+        ("SRANDMEMBER", -2, Read, "1.0.0"), ("SREM", -3, Write, "1.0.0"),
+        ("SSCAN", -3, Read, "2.8.0"), ("STRLEN", 2, Read, "2.2.0"), ("SUBSTR", 4, Read, "1.0.0"),
+        ("TTL", 2, Read, "1.0.0"), ("TYPE", 2, Read, "1.0.0"), ("XACK", -4, Write, "5.0.0"),
+        ("XADD", -5, Write, "5.0.0"), ("XAUTOCLAIM", -6, Write, "6.2.0"),
+        ("XCLAIM", -6, Write, "5.0.0"), ("XDEL", -3, Write, "5.0.0"), ("XLEN", 2, Read, "5.0.0"),
+        ("XPENDING", -3, Read, "5.0.0"), ("XRANGE", -4, Read, "5.0.0"),
+        ("XREVRANGE", -4, Read, "5.0.0"), ("XSETID", -3, Write, "5.0.0"),
+        ("XTRIM", -4, Write, "5.0.0"), ("ZADD", -4, Write, "1.2.0"), ("ZCARD", 2, Read, "1.2.0"),
+        ("ZCOUNT", 4, Read, "2.0.0"), ("ZINCRBY", 4, Write, "1.2.0"),
+        ("ZLEXCOUNT", 4, Read, "2.8.9"), ("ZMSCORE", -3, Read, "6.2.0"),
+        ("ZPOPMAX", -2, Write, "5.0.0"), ("ZPOPMIN", -2, Write, "5.0.0"),
+        ("ZRANDMEMBER", -2, Read, "6.2.0"), ("ZRANGE", -4, Read, "1.2.0"),
+        ("ZRANGEBYLEX", -4, Read, "2.8.9"), ("ZRANGEBYSCORE", -4, Read, "1.0.5"),
+        ("ZRANK", -3, Read, "2.0.0"), ("ZREM", -3, Write, "1.2.0"),
+        ("ZREMRANGEBYLEX", 4, Write, "2.8.9"), ("ZREMRANGEBYRANK", 4, Write, "2.0.0"),
+        ("ZREMRANGEBYSCORE", 4, Write, "1.2.0"), ("ZREVRANGE", -4, Read, "1.2.0"),
+        ("ZREVRANGEBYLEX", -4, Read, "2.8.9"), ("ZREVRANGEBYSCORE", -4, Read, "2.2.0"),
+        ("ZREVRANK", -3, Read, "2.0.0"), ("ZSCAN", -3, Read, "2.8.0"), ("ZSCORE", 3, Read, "1.2.0"),
+    ],
+    TWO_KEYS => [
+        ("BLMOVE", 6, Write, "6.2.0"), ("BRPOPLPUSH", 4, Write, "2.2.0"),
+        ("COPY", -3, Write, "6.2.0"), ("GEOSEARCHSTORE", -8, Write, "6.2.0"),
+        ("LCS", -3, Read, "7.0.0"), ("LMOVE", 5, Write, "6.2.0"), ("RENAME", 3, Write, "1.0.0"),
+        ("RENAMENX", 3, Write, "1.0.0"), ("RPOPLPUSH", 3, Write, "1.2.0"),
+        ("SMOVE", 4, Write, "1.0.0"), ("ZRANGESTORE", -5, Write, "6.2.0"),
+    ],
+    ALL_KEYS => [
+        ("DEL", -2, Write, "1.0.0"), ("EXISTS", -2, Read, "1.0.0"), ("MGET", -2, Read, "1.0.0"),
+        ("PFCOUNT", -2, Read, "2.8.9"), ("PFMERGE", -2, Write, "2.8.9"),
+        ("SDIFF", -2, Read, "1.0.0"), ("SDIFFSTORE", -3, Write, "1.0.0"),
+        ("SINTER", -2, Read, "1.0.0"), ("SINTERSTORE", -3, Write, "1.0.0"),
+        ("SUNION", -2, Read, "1.0.0"), ("SUNIONSTORE", -3, Write, "1.0.0"),
+        ("TOUCH", -2, Read, "3.2.1"), ("UNLINK", -2, Write, "4.0.0"), ("WATCH", -2, Other, "2.2.0"),
+    ],
+    KEY_VALUE_PAIRS => [
+        ("MSET", -3, Write, "1.0.1"), ("MSETNX", -3, Write, "1.0.1"),
+    ],
+    KEYS_THEN_TIMEOUT => [
+        ("BLPOP", -3, Write, "2.0.0"), ("BRPOP", -3, Write, "2.0.0"),
+        ("BZPOPMAX", -3, Write, "5.0.0"), ("BZPOPMIN", -3, Write, "5.0.0"),
+    ],
+    SUBCOMMAND_KEY => [
+        ("MEMORY USAGE", -3, Read, "4.0.0"), ("OBJECT ENCODING", 3, Read, "2.2.3"),
+        ("OBJECT FREQ", 3, Read, "4.0.0"), ("OBJECT IDLETIME", 3, Read, "2.2.3"),
+        ("OBJECT REFCOUNT", 3, Read, "2.2.3"), ("XGROUP CREATE", -5, Write, "5.0.0"),
+        ("XGROUP CREATECONSUMER", 5, Write, "6.2.0"), ("XGROUP DELCONSUMER", 5, Write, "5.0.0"),
+        ("XGROUP DESTROY", 4, Write, "5.0.0"), ("XGROUP SETID", -5, Write, "5.0.0"),
+        ("XINFO CONSUMERS", 4, Read, "5.0.0"), ("XINFO GROUPS", 3, Read, "5.0.0"),
+        ("XINFO STREAM", -3, Read, "5.0.0"),
+    ],
+    COUNTED_KEYS => [
+        ("LMPOP", -4, Write, "7.0.0"), ("SINTERCARD", -3, Read, "7.0.0"),
+        ("ZDIFF", -3, Read, "6.2.0"), ("ZINTER", -3, Read, "6.2.0"),
+        ("ZINTERCARD", -3, Read, "7.0.0"), ("ZMPOP", -4, Write, "7.0.0"),
+        ("ZUNION", -3, Read, "6.2.0"),
+    ],
+    SCRIPT_KEYS => [
+        ("BLMPOP", -5, Write, "7.0.0"), ("BZMPOP", -5, Write, "7.0.0"),
+        ("EVAL", -3, Other, "2.6.0"), ("EVAL_RO", -3, Read, "7.0.0"),
+        ("EVALSHA", -3, Other, "2.6.0"), ("EVALSHA_RO", -3, Read, "7.0.0"),
+        ("FCALL", -3, Other, "7.0.0"), ("FCALL_RO", -3, Read, "7.0.0"),
+    ],
+    DESTINATION_AND_COUNTED_KEYS => [
+        ("ZDIFFSTORE", -4, Write, "6.2.0"), ("ZINTERSTORE", -4, Write, "2.0.0"),
+        ("ZUNIONSTORE", -4, Write, "2.0.0"),
+    ],
+    NO_KEYS => [
+        ("ACL DELUSER", -3, Other, "6.0.0"), ("ACL LIST", 2, Other, "6.0.0"),
+        ("ACL SETUSER", -3, Other, "6.0.0"), ("ACL WHOAMI", 2, Other, "6.0.0"),
+        ("AUTH", -2, Other, "1.0.0"), ("BGREWRITEAOF", 1, Other, "1.0.0"),
+        ("BGSAVE", -1, Other, "1.0.0"), ("CLIENT GETNAME", 2, Other, "2.6.9"),
+        ("CLIENT ID", 2, Other, "5.0.0"), ("CLIENT KILL", -3, Other, "2.4.0"),
+        ("CLIENT LIST", -2, Other, "2.4.0"), ("CLIENT SETNAME", 3, Other, "2.6.9"),
+        ("CONFIG GET", -3, Other, "2.0.0"), ("CONFIG RESETSTAT", 2, Other, "2.0.0"),
+        ("CONFIG REWRITE", 2, Other, "2.8.0"), ("CONFIG SET", -4, Other, "2.0.0"),
+        ("DBSIZE", 1, Read, "1.0.0"), ("DISCARD", 1, Other, "2.0.0"), ("ECHO", 2, Other, "1.0.0"),
+        ("EXEC", 1, Other, "1.2.0"), ("FLUSHALL", -1, Write, "1.0.0"),
+        ("FLUSHDB", -1, Write, "1.0.0"), ("FUNCTION DELETE", 3, Write, "7.0.0"),
+        ("FUNCTION FLUSH", -2, Write, "7.0.0"), ("FUNCTION LIST", -2, Other, "7.0.0"),
+        ("FUNCTION LOAD", -3, Write, "7.0.0"), ("HELLO", -1, Other, "6.0.0"),
+        ("INFO", -1, Other, "1.0.0"), ("KEYS", 2, Read, "1.0.0"), ("LASTSAVE", 1, Other, "1.0.0"),
+        ("MULTI", 1, Other, "1.2.0"), ("PING", -1, Other, "1.0.0"),
+        ("PSUBSCRIBE", -2, Other, "2.0.0"), ("PUBLISH", 3, Other, "2.0.0"),
+        ("PUNSUBSCRIBE", -1, Other, "2.0.0"), ("QUIT", -1, Other, "1.0.0"),
+        ("RANDOMKEY", 1, Read, "1.0.0"), ("RESET", 1, Other, "6.2.0"), ("ROLE", 1, Other, "2.8.12"),
+        ("SAVE", 1, Other, "1.0.0"), ("SCAN", -2, Read, "2.8.0"),
+        ("SCRIPT EXISTS", -3, Other, "2.6.0"), ("SCRIPT FLUSH", -2, Other, "2.6.0"),
+        ("SCRIPT LOAD", 3, Other, "2.6.0"), ("SELECT", 2, Other, "1.0.0"),
+        ("SLOWLOG GET", -2, Other, "2.2.12"), ("SLOWLOG LEN", 2, Other, "2.2.12"),
+        ("SLOWLOG RESET", 2, Other, "2.2.12"), ("SUBSCRIBE", -2, Other, "2.0.0"),
+        ("SWAPDB", 3, Write, "4.0.0"), ("TIME", 1, Other, "2.6.0"),
+        ("UNSUBSCRIBE", -1, Other, "2.0.0"), ("UNWATCH", 1, Other, "2.2.0"),
+        ("WAIT", 3, Other, "3.0.0"),
+    ],
+
+};
+
+/// `BITOP operation destkey key [key ...]`
+static BITOP: CommandInfo = CommandInfo {
+    name: "BITOP",
+    arity: -4,
+    access: Access::Write,
+    since: "2.6.0",
+    keys: &[range(2, -1, 1)],
+};
+
+/// `XREAD ... STREAMS key [key ...] id [id ...]`
+static STREAM_READS: &[CommandInfo] = &[
+    CommandInfo {
+        name: "XREAD",
+        arity: -4,
+        access: Access::Read,
+        since: "5.0.0",
+        keys: &[Keys::Streams],
+    },
+    CommandInfo {
+        name: "XREADGROUP",
+        arity: -7,
+        access: Access::Write,
+        since: "5.0.0",
+        keys: &[Keys::Streams],
+    },
+];
+
+/// Every command in the table
+pub(crate) fn all() -> impl Iterator<Item = &'static CommandInfo> {
+    COMMANDS
+        .iter()
+        .chain(std::iter::once(&BITOP))
+        .chain(STREAM_READS)
+}
+
+/// Look up a command by its name, and its subcommand for container commands such as `XGROUP`
+pub(crate) fn lookup(args: &[Arg]) -> Option<&'static CommandInfo> {
+    let name = args.first()?.word()?.to_ascii_uppercase();
+    if let Some(sub) = args.get(1).and_then(Arg::word) {
+        let full = format!("{} {}", name, sub.to_ascii_uppercase());
+        if let Some(info) = all().find(|info| info.name == full) {
+            return Some(info);
+        }
+    }
+    all().find(|info| info.name == name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse::parse_command;
+    use proc_macro2::TokenStream;
+
+    fn lookup_(input: &str) -> Option<&'static str> {
+        let tokens: TokenStream = input.parse().unwrap();
+        lookup(&parse_command(tokens).unwrap().args).map(|info| info.name)
+    }
+
+    #[test]
+    fn commands_lookup() {
+        assert_eq!(lookup_("get k"), Some("GET"));
+        assert_eq!(lookup_("XGROUP CREATE s g $"), Some("XGROUP CREATE"));
+        assert_eq!(lookup_("object encoding k"), Some("OBJECT ENCODING"));
+        assert_eq!(lookup_("BITOP AND d a b"), Some("BITOP"));
+        assert_eq!(lookup_("XREAD STREAMS s 0"), Some("XREAD"));
+        assert_eq!(lookup_("PING"), Some("PING"));
+        assert_eq!(lookup_("config set maxmemory 1mb"), Some("CONFIG SET"));
+        assert_eq!(lookup_("MODULE.CMD k"), None);
+        assert_eq!(lookup_("{name} k"), None);
+    }
+
+    #[test]
+    fn commands_unique() {
+        let mut names: Vec<_> = COMMANDS.iter().map(|info| info.name).collect();
+        names.sort_unstable();
+        let len = names.len();
+        names.dedup();
+        assert_eq!(names.len(), len);
+    }
+}
@@ -0,0 +1,244 @@
+use crate::expand::expand_arg_list;
+use crate::parse::{parse_args, Arg, Command, Piece};
+use proc_macro2::{Span, TokenStream, TokenTree};
+use quote::{format_ident, quote};
+use syn::parse::{Parse, ParseStream};
+use syn::{Expr, Token};
+
+/// The options of a consumer, with whether each is required
+const OPTIONS: &[(&str, bool)] = &[
+    ("STREAM", true),
+    ("GROUP", true),
+    ("CONSUMER", true),
+    ("COUNT", false),
+    ("BLOCK", false),
+    ("START", false),
+    ("CLAIM", false),
+    ("MAX_DELIVERIES", false),
+    ("DEAD_LETTER", false),
+];
+
+/// The input of `redis_consume!`, e.g. `con, STREAM orders GROUP workers CONSUMER a, handler`
+struct Consume {
+    con: Expr,
+    spec: TokenStream,
+    handler: Expr,
+}
+
+impl Parse for Consume {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let con = input.parse()?;
+        input.parse::<Token![,]>()?;
+        let mut spec = TokenStream::new();
+        while !input.is_empty() && !input.peek(Token![,]) {
+            spec.extend([input.parse::<TokenTree>()?]);
+        }
+        input.parse::<Token![,]>()?;
+        let handler = input.parse()?;
+        input.parse::<Option<Token![,]>>()?;
+        Ok(Consume { con, spec, handler })
+    }
+}
+
+/// Pair up the options of a consumer with their values
+fn parse_options(args: Vec<Arg>) -> syn::Result<Vec<(&'static str, Arg)>> {
+    let mut options: Vec<(&'static str, Arg)> = vec![];
+    let mut args = args.into_iter();
+    while let Some(keyword) = args.next() {
+        let name = keyword.word().map(|word| word.to_ascii_uppercase());
+        let Some((name, _)) = OPTIONS
+            .iter()
+            .find(|(option, _)| Some(*option) == name.as_deref())
+        else {
+            let names: Vec<_> = OPTIONS.iter().map(|(option, _)| *option).collect();
+            let msg = format!("expected one of the options {}", names.join(", "));
+            return Err(syn::Error::new(keyword.span, msg));
+        };
+        if options.iter().any(|(option, _)| option == name) {
+            let msg = format!("`{}` is given more than once", name);
+            return Err(syn::Error::new(keyword.span, msg));
+        }
+        let value = args.next().filter(|value| {
+            value
+                .pieces
+                .iter()
+                .all(|piece| piece.standalone_kind().is_none())
+        });
+        let Some(value) = value else {
+            let msg = format!("expected a single value after `{}`", name);
+            return Err(syn::Error::new(keyword.span, msg));
+        };
+        options.push((name, value));
+    }
+    for (option, required) in OPTIONS {
+        if *required && !options.iter().any(|(name, _)| name == option) {
+            let msg = format!("the consumer needs a `{}` option", option);
+            return Err(syn::Error::new(Span::call_site(), msg));
+        }
+    }
+    Ok(options)
+}
+
+/// The value of an option that the consumer uses as a number rather than passing it on as an
+/// argument, such as the milliseconds of `CLAIM`, which it also waits between claims
+fn number(arg: &Arg, what: &str) -> syn::Result<TokenStream> {
+    match arg.pieces.as_slice() {
+        [Piece::Text(text)] if text.parse::<u64>().is_ok() => {
+            let number = syn::LitInt::new(text, arg.span);
+            Ok(quote!(#number))
+        }
+        [Piece::Expr(expr)] => Ok(quote!(#expr)),
+        _ => {
+            let msg = format!("expected {}, as a number or `{{expr}}`", what);
+            Err(syn::Error::new(arg.span, msg))
+        }
+    }
+}
+
+fn take_option(options: &mut Vec<(&'static str, Arg)>, option: &str) -> Option<Arg> {
+    let index = options.iter().position(|(name, _)| *name == option)?;
+    Some(options.remove(index).1)
+}
+
+/// An option value passed on as arguments
+fn expand_arg(arg: Arg) -> syn::Result<TokenStream> {
+    let command = Command {
+        args: vec![arg],
+        bindings: vec![],
+    };
+    expand_arg_list(&command, false)
+}
+
+/// Generate the consumer loop of a `redis_consume!` invocation
+pub(crate) fn expand_consume(input: TokenStream) -> syn::Result<TokenStream> {
+    let Consume { con, spec, handler } = syn::parse2(input)?;
+    let Command { args, bindings } = parse_args(spec, 0)?;
+    let mut options = parse_options(args)?;
+    let claim = take_option(&mut options, "CLAIM");
+    let max_deliveries = take_option(&mut options, "MAX_DELIVERIES");
+    let dead_letter = take_option(&mut options, "DEAD_LETTER");
+    let mut value = |option: &str| {
+        let arg = take_option(&mut options, option)?;
+        Some(expand_arg(arg))
+    };
+    let stream = value("STREAM").transpose()?;
+    let group = value("GROUP").transpose()?;
+    let consumer = value("CONSUMER").transpose()?;
+    let settings = ["COUNT", "BLOCK", "START"]
+        .into_iter()
+        .filter_map(|option| {
+            let method = format_ident!("{}", option.to_ascii_lowercase());
+            value(option).map(|value| value.map(|value| quote!(.#method(#value))))
+        })
+        .collect::<syn::Result<Vec<_>>>()?;
+    let dead_letter = match (dead_letter, max_deliveries) {
+        (Some(stream), Some(max)) if claim.is_some() => {
+            let stream = expand_arg(stream)?;
+            let max = number(&max, "the most deliveries")?;
+            Some(quote!(.dead_letter(#stream, #max)))
+        }
+        (Some(_), Some(_)) => {
+            let msg = "dead letters need `CLAIM`, since only claimed entries are delivered again";
+            return Err(syn::Error::new(Span::call_site(), msg));
+        }
+        (Some(arg), None) | (None, Some(arg)) => {
+            let msg = "`DEAD_LETTER` and `MAX_DELIVERIES` are given together";
+            return Err(syn::Error::new(arg.span, msg));
+        }
+        (None, None) => None,
+    };
+    let claim = claim
+        .map(|arg| number(&arg, "the minimum idle time in milliseconds"))
+        .transpose()?
+        .map(|millis| quote!(.claim(#millis)));
+    let bindings = bindings.iter().map(|binding| {
+        let (local, value) = (&binding.local, &binding.value);
+        quote!(let #local = #value;)
+    });
+    Ok(quote! {
+        {
+            #(#bindings)*
+            ::redis_rs_macro::StreamConsumer::new(#stream, #group, #consumer)
+                #(#settings)*
+                #claim
+                #dead_letter
+                .run(#con, #handler)
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expand(input: &str) -> syn::Result<String> {
+        expand_consume(input.parse().unwrap()).map(|output| output.to_string())
+    }
+
+    #[test]
+    fn consume_expand() {
+        let output = expand(
+            "con, STREAM orders GROUP workers CONSUMER worker:{id} COUNT 10, |e| Ok::<_, ()>(())",
+        )
+        .unwrap();
+        assert!(
+            output.contains(":: redis_rs_macro :: StreamConsumer :: new ({"),
+            "{}",
+            output
+        );
+        assert!(output.contains(". count ({"), "{}", output);
+        assert!(!output.contains(". block"), "{}", output);
+        assert!(output.contains(". run (con , | e |"), "{}", output);
+        let output = expand("con, stream s group g consumer c block 0 start 0, h").unwrap();
+        assert!(output.contains(". block ({"), "{}", output);
+        assert!(output.contains(". start ({"), "{}", output);
+        assert!(!output.contains(". claim"), "{}", output);
+        let output = expand("con, STREAM s GROUP g CONSUMER c CLAIM 60000, h").unwrap();
+        assert!(output.contains(". claim (60000) . run"), "{}", output);
+        let output = expand("con, STREAM s GROUP g CONSUMER c CLAIM {idle}, h").unwrap();
+        assert!(output.contains(". claim (idle) . run"), "{}", output);
+        let output = expand(
+            "con, STREAM s GROUP g CONSUMER c CLAIM 1 MAX_DELIVERIES 3 DEAD_LETTER s:dead, h",
+        )
+        .unwrap();
+        assert!(
+            output.contains(". claim (1) . dead_letter ({"),
+            "{}",
+            output
+        );
+        assert!(output.contains("} , 3) . run"), "{}", output);
+    }
+
+    #[test]
+    fn consume_errors() {
+        let err = |input: &str| expand(input).unwrap_err().to_string();
+        let e = err("con, STREAM s GROUP g, h");
+        assert!(e.contains("needs a `CONSUMER` option"), "{}", e);
+        let e = err("con, STREAM s GROUP g CONSUMER c LIMIT 1, h");
+        assert!(e.contains("expected one of the options"), "{}", e);
+        let e = err("con, STREAM s GROUP g CONSUMER c STREAM t, h");
+        assert!(e.contains("`STREAM` is given more than once"), "{}", e);
+        let e = err("con, STREAM s GROUP g CONSUMER, h");
+        assert!(
+            e.contains("expected a single value after `CONSUMER`"),
+            "{}",
+            e
+        );
+        let e = err("con, STREAM {..s} GROUP g CONSUMER c, h");
+        assert!(
+            e.contains("expected a single value after `STREAM`"),
+            "{}",
+            e
+        );
+        let e = err("con, STREAM s GROUP g CONSUMER c CLAIM 1m, h");
+        assert!(e.contains("minimum idle time in milliseconds"), "{}", e);
+        let e = err("con, STREAM s GROUP g CONSUMER c MAX_DELIVERIES 3 DEAD_LETTER d, h");
+        assert!(e.contains("dead letters need `CLAIM`"), "{}", e);
+        let e = err("con, STREAM s GROUP g CONSUMER c CLAIM 1 DEAD_LETTER d, h");
+        assert!(e.contains("are given together"), "{}", e);
+        let e = err("con, STREAM s GROUP g CONSUMER c CLAIM 1 DEAD_LETTER d MAX_DELIVERIES x, h");
+        assert!(e.contains("expected the most deliveries"), "{}", e);
+        let e = err("con, STREAM s GROUP g CONSUMER c");
+        assert!(e.contains("expected `,`"), "{}", e);
+    }
+}
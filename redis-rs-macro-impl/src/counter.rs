@@ -0,0 +1,94 @@
+use crate::lock::expand_key;
+use proc_macro2::{Span, TokenStream, TokenTree};
+use quote::quote;
+use syn::parse::{Parse, ParseStream};
+use syn::{Expr, Ident, Token};
+
+/// The input of `redis_counter!`, e.g. `con, visits:{page}, ttl = 3600, by = 2`
+struct CounterInput {
+    con: Expr,
+    key: TokenStream,
+    ttl: Expr,
+    by: Option<Expr>,
+}
+
+impl Parse for CounterInput {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let con = input.parse()?;
+        input.parse::<Token![,]>()?;
+        let mut key = TokenStream::new();
+        while !input.is_empty() && !input.peek(Token![,]) {
+            key.extend([input.parse::<TokenTree>()?]);
+        }
+        let mut ttl = None;
+        let mut by = None;
+        while input.parse::<Option<Token![,]>>()?.is_some() && !input.is_empty() {
+            let name: Ident = input.parse()?;
+            let value = match name.to_string().as_str() {
+                "ttl" => &mut ttl,
+                "by" => &mut by,
+                _ => return Err(syn::Error::new(name.span(), "expected `ttl` or `by`")),
+            };
+            if value.is_some() {
+                let msg = format!("`{}` is given twice", name);
+                return Err(syn::Error::new(name.span(), msg));
+            }
+            input.parse::<Token![=]>()?;
+            *value = Some(input.parse()?);
+        }
+        if !input.is_empty() {
+            return Err(input.error("expected `,`"));
+        }
+        let Some(ttl) = ttl else {
+            return Err(syn::Error::new(
+                Span::call_site(),
+                "expected `ttl = <seconds>`",
+            ));
+        };
+        Ok(CounterInput { con, key, ttl, by })
+    }
+}
+
+/// Generate a `redis_counter!` invocation, which increments the counter and returns the new count
+pub(crate) fn expand_counter(input: TokenStream) -> syn::Result<TokenStream> {
+    let CounterInput { con, key, ttl, by } = syn::parse2(input)?;
+    let key = expand_key(key)?;
+    let by = by.map_or(quote!(1), |by| quote!(#by));
+    Ok(quote! {
+        ::redis_rs_macro::Counter::new(#key, #ttl).incr(#con, #by)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expand(input: &str) -> syn::Result<String> {
+        expand_counter(input.parse().unwrap()).map(|output| output.to_string())
+    }
+
+    #[test]
+    fn counter_expand() {
+        let output = expand("con, visits:{page}, ttl = 3600").unwrap();
+        assert!(output.ends_with(", 3600) . incr (con , 1)"), "{}", output);
+        let output = expand("&mut con, visits, by = -n, ttl = day,").unwrap();
+        assert!(
+            output.ends_with(", day) . incr (& mut con , - n)"),
+            "{}",
+            output
+        );
+    }
+
+    #[test]
+    fn counter_errors() {
+        let err = |input: &str| expand(input).unwrap_err().to_string();
+        let e = err("con, visits, by = 2");
+        assert!(e.contains("expected `ttl = <seconds>`"), "{}", e);
+        let e = err("con, visits, ttl = 1, ttl = 2");
+        assert!(e.contains("given twice"), "{}", e);
+        let e = err("con, visits, ttl = 1, px = 1");
+        assert!(e.contains("expected `ttl` or `by`"), "{}", e);
+        let e = err("con, a b, ttl = 1");
+        assert!(e.contains("single key"), "{}", e);
+    }
+}
@@ -0,0 +1,171 @@
+use crate::expand::expand_command;
+use crate::parse::parse_command;
+use crate::typed::{expand_typed, split_return};
+use proc_macro2::{TokenStream, TokenTree};
+use quote::quote;
+use syn::parse::{Parse, ParseStream};
+use syn::{Attribute, Ident, Token, Visibility};
+
+/// A function defined by `redis_def!`, e.g. `fn get_user(id: u64) => GET user:{id}`
+struct Def {
+    attrs: Vec<Attribute>,
+    vis: Visibility,
+    name: Ident,
+    params: TokenStream,
+    command: TokenStream,
+}
+
+/// The functions of a `redis_def!` invocation
+struct Defs(Vec<Def>);
+
+impl Parse for Def {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let attrs = input.call(Attribute::parse_outer)?;
+        let vis = input.parse()?;
+        input.parse::<Token![fn]>()?;
+        let name = input.parse()?;
+        let params;
+        syn::parenthesized!(params in input);
+        let params = params.parse()?;
+        input.parse::<Token![=>]>()?;
+        // The command runs to the `;` that starts the next function, since bindings after a `;`
+        // belong to the command
+        let mut command = TokenStream::new();
+        while !at_end(input) {
+            command.extend([input.parse::<TokenTree>()?]);
+        }
+        if command.is_empty() {
+            return Err(input.error("expected a redis command after `=>`"));
+        }
+        Ok(Def {
+            attrs,
+            vis,
+            name,
+            params,
+            command,
+        })
+    }
+}
+
+/// Whether `input` is at the end of a function, which is either the end of the input or a `;`
+/// followed by another function or the end of the input
+fn at_end(input: ParseStream) -> bool {
+    if input.is_empty() {
+        return true;
+    }
+    let fork = input.fork();
+    if fork.parse::<Token![;]>().is_err() {
+        return false;
+    }
+    fork.is_empty() || fork.peek(Token![#]) || fork.peek(Token![pub]) || fork.peek(Token![fn])
+}
+
+impl Parse for Defs {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut defs = vec![];
+        while !input.is_empty() {
+            defs.push(input.parse()?);
+            if !input.is_empty() {
+                input.parse::<Token![;]>()?;
+            }
+        }
+        Ok(Defs(defs))
+    }
+}
+
+/// Generate the functions of a `redis_def!` invocation, each returning the command it names
+pub(crate) fn expand_defs(input: TokenStream) -> syn::Result<TokenStream> {
+    let Defs(defs) = syn::parse2(input)?;
+    let defs = defs
+        .into_iter()
+        .map(|def| {
+            let Def {
+                attrs,
+                vis,
+                name,
+                params,
+                command,
+            } = def;
+            let (command, ty) = split_return(command)?;
+            let cmd = expand_command(&parse_command(command)?)?;
+            let (output, body) = match ty {
+                Some(ty) => (
+                    quote!(::redis_rs_macro::TypedCmd<#ty>),
+                    expand_typed(cmd, &ty),
+                ),
+                None => (quote!(redis::Cmd), cmd),
+            };
+            Ok(quote! {
+                #(#attrs)*
+                #vis fn #name(#params) -> #output {
+                    #body
+                }
+            })
+        })
+        .collect::<syn::Result<Vec<_>>>()?;
+    Ok(quote!(#(#defs)*))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn defs(input: &str) -> syn::Result<Vec<(String, String)>> {
+        let Defs(defs) = syn::parse2(input.parse().unwrap())?;
+        Ok(defs
+            .into_iter()
+            .map(|def| (def.name.to_string(), def.command.to_string()))
+            .collect())
+    }
+
+    #[test]
+    fn def_parse() {
+        assert_eq!(
+            defs("fn get_user(id: u64) => GET user:{id}; fn ping() => PING;").unwrap(),
+            [
+                ("get_user".to_string(), "GET user : { id }".to_string()),
+                ("ping".to_string(), "PING".to_string()),
+            ]
+        );
+        assert_eq!(
+            defs(
+                "/// Docs\npub fn get(k: &str) => \"GET :k\"; k = k;\npub(crate) fn del() => DEL k"
+            )
+            .unwrap(),
+            [
+                ("get".to_string(), "\"GET :k\" ; k = k".to_string()),
+                ("del".to_string(), "DEL k".to_string()),
+            ]
+        );
+        assert!(defs("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn def_errors() {
+        let err = defs("fn get() =>;").unwrap_err().to_string();
+        assert!(err.contains("expected a redis command"), "{}", err);
+        assert!(defs("fn get() GET k").is_err());
+        assert!(defs("get() => GET k").is_err());
+        let err = expand_defs("fn get() => GET k ->".parse().unwrap())
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("expected the reply type"), "{}", err);
+    }
+
+    #[test]
+    fn def_expand() {
+        let output = expand_defs(
+            "pub fn get(id: u64) => GET user:{id} -> String"
+                .parse()
+                .unwrap(),
+        )
+        .unwrap()
+        .to_string();
+        assert!(
+            output
+                .starts_with("pub fn get (id : u64) -> :: redis_rs_macro :: TypedCmd < String > {"),
+            "{}",
+            output
+        );
+    }
+}
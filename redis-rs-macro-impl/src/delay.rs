@@ -0,0 +1,132 @@
+use crate::queue::{parse_defs, QueueDef};
+use proc_macro2::{Span, TokenStream};
+use quote::quote;
+use syn::Ident;
+
+/// Generate the queues of a `redis_delay_queue!` invocation, each a unit struct with a scheduler
+/// and a poller
+pub(crate) fn expand_delay_queue(input: TokenStream) -> syn::Result<TokenStream> {
+    let mut output = TokenStream::new();
+    for def in parse_defs(input, "interval")? {
+        output.extend(expand_def(def));
+    }
+    Ok(output)
+}
+
+fn expand_def(def: QueueDef) -> TokenStream {
+    let QueueDef {
+        attrs,
+        vis,
+        name,
+        job,
+        key,
+        option: interval,
+        marker,
+    } = def;
+    // Workers look for due jobs every second by default
+    let interval = interval.map_or(quote!(1000), |interval| quote!(#interval));
+    let wrapper = marker.reply_wrapper();
+    let job_var = Ident::new("job", Span::mixed_site());
+    let serialized = marker.expand(&syn::parse_quote!(#job_var));
+    quote! {
+        #(#attrs)*
+        #vis struct #name;
+
+        impl #name {
+            /// The queue, with its key
+            pub fn queue() -> ::redis_rs_macro::DelayQueue {
+                ::redis_rs_macro::DelayQueue::new(#key, #interval)
+            }
+
+            /// Schedule a job to run at `run_at` with `ZADD`
+            pub fn schedule(
+                con: &mut dyn redis::ConnectionLike,
+                #job_var: &#job,
+                run_at: impl ::redis_rs_macro::Timestamp,
+            ) -> redis::RedisResult<()> {
+                Self::queue().schedule(con, #serialized, run_at)
+            }
+
+            /// Schedule a job to run after `delay`
+            pub fn schedule_in(
+                con: &mut dyn redis::ConnectionLike,
+                #job_var: &#job,
+                delay: ::std::time::Duration,
+            ) -> redis::RedisResult<()> {
+                Self::schedule(con, #job_var, ::std::time::SystemTime::now() + delay)
+            }
+
+            /// Pop at most `limit` jobs that are due now
+            pub fn pop_due(
+                con: &mut dyn redis::ConnectionLike,
+                limit: usize,
+            ) -> redis::RedisResult<::std::vec::Vec<#job>> {
+                let jobs: ::std::vec::Vec<#wrapper<#job>> = Self::queue().pop_due(con, limit)?;
+                ::core::result::Result::Ok(jobs.into_iter().map(|#wrapper(#job_var)| #job_var).collect())
+            }
+
+            /// Pop due jobs and pass them to `handler`, until it returns
+            /// `Ok(ControlFlow::Break(()))`. Jobs it returns `Err` for are scheduled again.
+            pub fn work<R, E>(
+                con: &mut dyn redis::ConnectionLike,
+                mut handler: impl ::core::ops::FnMut(#job) -> ::core::result::Result<R, E>,
+            ) -> redis::RedisResult<()>
+            where
+                R: ::redis_rs_macro::HandlerResult,
+            {
+                Self::queue().work(con, |#wrapper(#job_var): #wrapper<#job>| handler(#job_var))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expand(input: &str) -> syn::Result<String> {
+        expand_delay_queue(input.parse().unwrap()).map(|output| output.to_string())
+    }
+
+    #[test]
+    fn delay_queue_expand() {
+        let output = expand(
+            "pub struct Reminders(Reminder) = reminders;\n\
+             struct Retries(u64) = retries:{region}, interval = 250, msgpack;",
+        )
+        .unwrap();
+        assert!(
+            output.starts_with("pub struct Reminders ; impl Reminders {"),
+            "{}",
+            output
+        );
+        assert!(output.contains(", 1000) }"), "{}", output);
+        assert!(
+            output.contains("Self :: queue () . schedule (con , :: redis_rs_macro :: __private :: json :: to_string (& (job)) ? , run_at)"),
+            "{}",
+            output
+        );
+        assert!(
+            output.contains("struct Retries ; impl Retries {"),
+            "{}",
+            output
+        );
+        assert!(output.contains(", 250) }"), "{}", output);
+        assert!(
+            output.contains("map (| :: redis_rs_macro :: Msgpack (job) | job)"),
+            "{}",
+            output
+        );
+    }
+
+    #[test]
+    fn delay_queue_errors() {
+        let err = |input: &str| expand(input).unwrap_err().to_string();
+        let e = err("struct Q(Job) = a, visibility = 1;");
+        assert!(e.contains("expected `interval`"), "{}", e);
+        let e = err("struct Q(Job) = a, interval = 1, interval = 2;");
+        assert!(e.contains("`interval` is given twice"), "{}", e);
+        let e = err("struct Q = a;");
+        assert!(e.contains("expected parentheses"), "{}", e);
+    }
+}
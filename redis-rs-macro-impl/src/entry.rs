@@ -0,0 +1,156 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::spanned::Spanned;
+use syn::{Data, DeriveInput, Fields, LitStr, Token};
+
+/// A field of a `#[derive(RedisStreamEntry)]` struct and its name in the stream
+struct Field {
+    ident: syn::Ident,
+    ty: syn::Type,
+    name: LitStr,
+}
+
+/// Generate `ToRedisFields` and `redis::FromRedisValue` implementations for a struct with named
+/// fields, mapping each field to a stream field of the same name, or of its `#[redis(rename)]`
+pub(crate) fn expand_entry(input: TokenStream) -> syn::Result<TokenStream> {
+    let input: DeriveInput = syn::parse2(input)?;
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                let msg = "`RedisStreamEntry` needs a struct with named fields";
+                return Err(syn::Error::new(input.ident.span(), msg));
+            }
+        },
+        _ => {
+            let msg = "`RedisStreamEntry` can only be derived for structs";
+            return Err(syn::Error::new(input.ident.span(), msg));
+        }
+    };
+    let fields = fields
+        .iter()
+        .map(|field| {
+            let ident = field.ident.clone().unwrap();
+            let mut name = LitStr::new(&ident.to_string(), ident.span());
+            for attr in &field.attrs {
+                if attr.path().is_ident("redis") {
+                    name = parse_rename(attr)?;
+                }
+            }
+            Ok(Field {
+                ident,
+                ty: field.ty.clone(),
+                name,
+            })
+        })
+        .collect::<syn::Result<Vec<_>>>()?;
+    for (i, field) in fields.iter().enumerate() {
+        if fields[..i]
+            .iter()
+            .any(|f| f.name.value() == field.name.value())
+        {
+            let msg = format!("the stream field `{}` is used twice", field.name.value());
+            return Err(syn::Error::new(field.name.span(), msg));
+        }
+    }
+
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let mut write_bounds = where_clause
+        .cloned()
+        .unwrap_or_else(|| syn::parse_quote!(where));
+    let mut read_bounds = write_bounds.clone();
+    for Field { ty, .. } in &fields {
+        write_bounds
+            .predicates
+            .push(syn::parse_quote!(#ty: redis::ToRedisArgs));
+        read_bounds
+            .predicates
+            .push(syn::parse_quote!(#ty: redis::FromRedisValue));
+    }
+    let writes = fields.iter().map(|Field { ident, name, .. }| {
+        quote!(::redis_rs_macro::__private::entry::write_field(out, #name, &self.#ident);)
+    });
+    let reads = fields
+        .iter()
+        .map(|Field { ident, name, .. }| quote!(#ident: fields.get(#name)?,));
+    Ok(quote! {
+        impl #impl_generics ::redis_rs_macro::ToRedisFields for #ident #ty_generics #write_bounds {
+            fn write_redis_fields<W: ?::core::marker::Sized + redis::RedisWrite>(&self, out: &mut W) {
+                #(#writes)*
+            }
+        }
+
+        impl #impl_generics redis::FromRedisValue for #ident #ty_generics #read_bounds {
+            fn from_redis_value(value: &redis::Value) -> redis::RedisResult<Self> {
+                let fields = ::redis_rs_macro::__private::entry::EntryFields::new(value)?;
+                ::core::result::Result::Ok(#ident {
+                    #(#reads)*
+                })
+            }
+        }
+    })
+}
+
+/// Parse `#[redis(rename = "name")]`
+fn parse_rename(attr: &syn::Attribute) -> syn::Result<LitStr> {
+    let mut name = None;
+    attr.parse_nested_meta(|meta| {
+        if !meta.path.is_ident("rename") {
+            return Err(meta.error("expected `rename = \"...\"`"));
+        }
+        meta.input.parse::<Token![=]>()?;
+        name = Some(meta.input.parse()?);
+        Ok(())
+    })?;
+    name.ok_or_else(|| syn::Error::new(attr.span(), "expected `rename = \"...\"`"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expand(input: &str) -> syn::Result<String> {
+        expand_entry(input.parse().unwrap()).map(|out| out.to_string())
+    }
+
+    #[test]
+    fn entry_expand() {
+        let output = expand(
+            r#"struct Order {
+                id: u64,
+                #[redis(rename = "customer_name")]
+                customer: String,
+            }"#,
+        )
+        .unwrap();
+        assert!(
+            output.contains("write_field (out , \"id\" , & self . id)"),
+            "{}",
+            output
+        );
+        assert!(
+            output.contains("customer : fields . get (\"customer_name\") ?"),
+            "{}",
+            output
+        );
+        assert!(
+            output.contains("String : redis :: FromRedisValue"),
+            "{}",
+            output
+        );
+    }
+
+    #[test]
+    fn entry_errors() {
+        let err = |input: &str| expand(input).unwrap_err().to_string();
+        let e = err("struct T(u8);");
+        assert!(e.contains("named fields"), "{}", e);
+        let e = err("enum T { A }");
+        assert!(e.contains("only be derived for structs"), "{}", e);
+        let e = err("struct T { #[redis(name = \"a\")] a: u8 }");
+        assert!(e.contains("expected `rename"), "{}", e);
+        let e = err("struct T { a: u8, #[redis(rename = \"a\")] b: u8 }");
+        assert!(e.contains("used twice"), "{}", e);
+    }
+}
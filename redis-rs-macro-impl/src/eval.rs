@@ -0,0 +1,186 @@
+use crate::expand::expand_arg_list;
+use crate::lua;
+use crate::parse::{parse_args, Command};
+use proc_macro2::{Span, TokenStream, TokenTree};
+use quote::quote;
+use syn::parse::{Parse, ParseStream};
+use syn::{Expr, Ident, LitStr, Token};
+
+/// The input of `redis_eval!`, e.g. `keys = [user:{id}], args = [{ttl}], r#"..."#`, optionally
+/// preceded by a connection
+struct Eval {
+    con: Option<Expr>,
+    keys: Command,
+    args: Command,
+    script: LitStr,
+}
+
+impl Parse for Eval {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        // A list name followed by `=`, or the script, means there's no connection
+        let list = (input.peek(Ident) && input.peek2(Token![=])) || input.peek(LitStr);
+        let con = match list || input.is_empty() {
+            true => None,
+            false => {
+                let con = input.parse()?;
+                input.parse::<Token![,]>()?;
+                Some(con)
+            }
+        };
+        let mut keys = None;
+        let mut args = None;
+        while input.peek(Ident) {
+            let name: Ident = input.parse()?;
+            let list = match name.to_string().as_str() {
+                "keys" => &mut keys,
+                "args" => &mut args,
+                _ => return Err(syn::Error::new(name.span(), "expected `keys` or `args`")),
+            };
+            if list.is_some() {
+                let msg = format!("`{}` is given twice", name);
+                return Err(syn::Error::new(name.span(), msg));
+            }
+            input.parse::<Token![=]>()?;
+            let content;
+            syn::bracketed!(content in input);
+            *list = Some(parse_list(content.parse()?)?);
+            input.parse::<Token![,]>()?;
+        }
+        if input.is_empty() {
+            return Err(input.error("expected the Lua script, as a string literal"));
+        }
+        let script = input.parse()?;
+        input.parse::<Option<Token![,]>>()?;
+        if !input.is_empty() {
+            return Err(input.error("unexpected tokens after the script"));
+        }
+        let empty = || Command {
+            args: vec![],
+            bindings: vec![],
+        };
+        Ok(Eval {
+            con,
+            keys: keys.unwrap_or_else(empty),
+            args: args.unwrap_or_else(empty),
+            script,
+        })
+    }
+}
+
+/// Parse a comma separated list of arguments, each written with the argument syntax of `redis!`
+fn parse_list(tokens: TokenStream) -> syn::Result<Command> {
+    let mut list = Command {
+        args: vec![],
+        bindings: vec![],
+    };
+    let mut item = TokenStream::new();
+    let mut tokens = tokens.into_iter().peekable();
+    while let Some(token) = tokens.next() {
+        let comma = matches!(&token, TokenTree::Punct(punct) if punct.as_char() == ',');
+        if !comma {
+            item.extend([token]);
+        }
+        if (comma || tokens.peek().is_none()) && !item.is_empty() {
+            let Command { args, bindings } = parse_args(std::mem::take(&mut item), 0)?;
+            list.args.extend(args);
+            list.bindings.extend(bindings);
+        }
+    }
+    Ok(list)
+}
+
+/// Generate the `EVAL` command of a `redis_eval!` invocation, with the number of keys counted from
+/// the key list. With a connection, the script is called instead, with `EVALSHA` first.
+pub(crate) fn expand_eval(input: TokenStream) -> syn::Result<TokenStream> {
+    let Eval {
+        con,
+        keys,
+        args,
+        script,
+    } = syn::parse2(input)?;
+    if cfg!(feature = "lua-check") {
+        lua::check_literal(&script)?;
+    }
+    let keys = expand_arg_list(&keys, true)?;
+    let args = expand_arg_list(&args, false)?;
+    if let Some(con) = con {
+        let sha1 = sha1_smol::Sha1::from(script.value()).digest().to_string();
+        let call = Ident::new("call", Span::mixed_site());
+        return Ok(quote! {
+            {
+                let mut #call = ::redis_rs_macro::ScriptCmd::new(#script, #sha1);
+                #call.key(#keys).arg(#args);
+                #call.invoke(#con)
+            }
+        });
+    }
+    let local = Ident::new("keys", Span::mixed_site());
+    let cmd = Ident::new("cmd", Span::mixed_site());
+    Ok(quote! {
+        {
+            let #local = #keys;
+            let mut #cmd = redis::cmd("EVAL");
+            #cmd.arg(#script).arg(#local.as_slice().len()).arg(#local).arg(#args);
+            #cmd
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expand(input: &str) -> syn::Result<String> {
+        expand_eval(input.parse().unwrap()).map(|output| output.to_string())
+    }
+
+    #[test]
+    fn eval_expand() {
+        let output = expand(r#"keys = [a, user:{id}], args = [{n}], "return 1""#).unwrap();
+        assert!(output.contains("redis :: cmd (\"EVAL\")"), "{}", output);
+        assert!(output.contains(". arg (\"return 1\")"), "{}", output);
+        assert!(output.contains(". as_slice () . len ()"), "{}", output);
+        let key = match cfg!(feature = "key-prefix") {
+            true => ". arg (:: redis_rs_macro :: __private :: prefix :: key (\"a\"))",
+            false => ". arg (\"a\")",
+        };
+        assert!(output.contains(key), "{}", output);
+        let output = expand(r#"args = [x], r"return ARGV[1]","#).unwrap();
+        assert!(output.contains(". arg (\"x\")"), "{}", output);
+        let output = expand(r#"&mut con, keys = [a], "return 1""#).unwrap();
+        assert!(
+            output.contains(
+                "ScriptCmd :: new (\"return 1\" , \"e0e1f9fabfc9d4800c877a703b823ac0578ff8db\")"
+            ),
+            "{}",
+            output
+        );
+        assert!(output.contains(". invoke (& mut con)"), "{}", output);
+        let output = expand(r#"keys, "return 1""#).unwrap();
+        assert!(output.contains(". invoke (keys)"), "{}", output);
+    }
+
+    #[test]
+    fn eval_list() {
+        let list = |input: &str| parse_list(input.parse().unwrap()).unwrap().args.len();
+        assert_eq!(list(""), 0);
+        assert_eq!(list("a"), 1);
+        assert_eq!(list("a,b"), 2);
+        assert_eq!(list("a, user:{id}, {..more},"), 3);
+    }
+
+    #[test]
+    fn eval_errors() {
+        let err = |input: &str| expand(input).unwrap_err().to_string();
+        let e = err("");
+        assert!(e.contains("expected the Lua script"), "{}", e);
+        let e = err("keys = [a], keys = [b], \"\"");
+        assert!(e.contains("given twice"), "{}", e);
+        let e = err("argv = [a], \"\"");
+        assert!(e.contains("expected `keys` or `args`"), "{}", e);
+        let e = err("\"\" extra");
+        assert!(e.contains("unexpected tokens"), "{}", e);
+        let e = err("keys = [a] \"\"");
+        assert!(e.contains("expected `,`"), "{}", e);
+    }
+}
@@ -0,0 +1,108 @@
+use crate::expand::expand_command;
+use crate::parse::parse_command;
+use crate::typed::split_return;
+use proc_macro2::{Span, TokenStream};
+use quote::quote;
+use syn::parse::{Parse, ParseStream};
+use syn::{Expr, Ident, Token};
+
+/// The input of `redis_exec!`, `redis_async!` and `redis_scan!`, e.g. `con, INCR counter`
+pub(crate) struct Exec {
+    pub(crate) con: Expr,
+    pub(crate) command: TokenStream,
+}
+
+impl Parse for Exec {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let con = input.parse()?;
+        input.parse::<Token![,]>()?;
+        let command = input.parse()?;
+        Ok(Exec { con, command })
+    }
+}
+
+/// Generate the query of a `redis_exec!` invocation, which builds the command and sends it on the
+/// connection right away
+pub(crate) fn expand_exec(input: TokenStream) -> syn::Result<TokenStream> {
+    let Exec { con, command } = syn::parse2(input)?;
+    let (command, ty) = split_return(command)?;
+    let cmd = expand_command(&parse_command(command)?)?;
+    let ty = ty.map(|ty| quote!(::<#ty>));
+    Ok(quote!(redis::Cmd::query #ty (&#cmd, #con)))
+}
+
+/// Generate the future of a `redis_async!` invocation. The command and connection are moved into
+/// the future so that it can be stored or spawned before it is awaited.
+pub(crate) fn expand_async(input: TokenStream) -> syn::Result<TokenStream> {
+    let Exec { con, command } = syn::parse2(input)?;
+    let (command, ty) = split_return(command)?;
+    let cmd = expand_command(&parse_command(command)?)?;
+    let ty = ty.map(|ty| quote!(::<_, #ty>));
+    // Mixed site hygiene keeps the command from shadowing a `cmd` used in the connection
+    let cmd_var = Ident::new("cmd", Span::mixed_site());
+    let con_var = Ident::new("con", Span::mixed_site());
+    Ok(quote! {
+        {
+            let #cmd_var = #cmd;
+            let #con_var = #con;
+            async move { redis::Cmd::query_async #ty (&#cmd_var, #con_var).await }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exec_expand() {
+        let output = expand_exec("con, INCR counter".parse().unwrap())
+            .unwrap()
+            .to_string();
+        assert!(
+            output.starts_with("redis :: Cmd :: query (& {"),
+            "{}",
+            output
+        );
+        assert!(output.ends_with(", con)"), "{}", output);
+        let output = expand_exec("con, GET k -> String".parse().unwrap())
+            .unwrap()
+            .to_string();
+        assert!(
+            output.starts_with("redis :: Cmd :: query :: < String > (& {"),
+            "{}",
+            output
+        );
+        let err = expand_exec("con".parse().unwrap()).unwrap_err().to_string();
+        assert!(err.contains("expected `,`"), "{}", err);
+        let err = expand_exec("con,".parse().unwrap())
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("expected a redis command"), "{}", err);
+    }
+
+    #[test]
+    fn exec_async() {
+        let output = expand_async("&mut con, GET foo".parse().unwrap())
+            .unwrap()
+            .to_string();
+        assert!(output.contains("let con = & mut con ;"), "{}", output);
+        assert!(
+            output.contains("async move { redis :: Cmd :: query_async (& cmd , con) . await }"),
+            "{}",
+            output
+        );
+        let output = expand_async("con, GET foo -> String".parse().unwrap())
+            .unwrap()
+            .to_string();
+        assert!(
+            output.contains("query_async :: < _ , String > (& cmd"),
+            "{}",
+            output
+        );
+        let err = expand_async("con GET foo".parse().unwrap())
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("expected `,`"), "{}", err);
+    }
+}
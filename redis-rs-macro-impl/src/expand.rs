@@ -0,0 +1,308 @@
+use crate::acl;
+use crate::bitfield;
+use crate::client;
+use crate::geo;
+use crate::jsonpath;
+use crate::keys::{key_roles, KeyRole};
+use crate::marker::Marker;
+use crate::parse::{Arg, Command, Piece};
+use crate::slot;
+use crate::time::TimeArg;
+use crate::timeseries;
+use proc_macro2::{Ident, Span, TokenStream};
+use quote::{quote, quote_spanned, ToTokens};
+use syn::spanned::Spanned;
+use syn::{LitByteStr, LitStr};
+
+/// Generate a block that builds the `redis::Cmd` for a parsed command
+pub(crate) fn expand_command(command: &Command) -> syn::Result<TokenStream> {
+    geo::check_coordinates(command)?;
+    bitfield::check_bitfield(command)?;
+    acl::check_acl_setuser(command)?;
+    client::check_client_kill(command)?;
+    jsonpath::check_json_paths(command)?;
+    timeseries::check_timeseries(command)?;
+    // A runtime key prefix changes what is hashed, so keys can only be checked without one
+    if cfg!(feature = "cluster") && !cfg!(feature = "key-prefix") {
+        slot::check_same_slot(command)?;
+    }
+    // Mixed site hygiene keeps the local from shadowing variables used in substitutions
+    let cmd = Ident::new("cmd", Span::mixed_site());
+    let name = expand_name(&command.args[0])?;
+    let roles = if cfg!(feature = "key-prefix") {
+        key_roles(command)
+    } else {
+        vec![KeyRole::None; command.args.len()]
+    };
+    let args = expand_args(
+        &cmd,
+        &command.args[1..],
+        command.args[0].word(),
+        &roles[1..],
+    )?;
+    let bindings = command.bindings.iter().map(|binding| {
+        let (local, value) = (&binding.local, &binding.value);
+        quote!(let #local = #value;)
+    });
+    Ok(quote! {
+        {
+            #(#bindings)*
+            let mut #cmd = redis::cmd(#name);
+            #args
+            #cmd
+        }
+    })
+}
+
+/// Generate a block that builds the `redis_rs_macro::Args` for a parsed argument list. With `keys`
+/// set, every argument is a key, which gets the key prefix.
+pub(crate) fn expand_arg_list(command: &Command, keys: bool) -> syn::Result<TokenStream> {
+    let args = Ident::new("args", Span::mixed_site());
+    let role = match keys && cfg!(feature = "key-prefix") {
+        true => KeyRole::Key,
+        false => KeyRole::None,
+    };
+    let roles = vec![role; command.args.len()];
+    let appended = expand_args(&args, &command.args, None, &roles)?;
+    let bindings = command.bindings.iter().map(|binding| {
+        let (local, value) = (&binding.local, &binding.value);
+        quote!(let #local = #value;)
+    });
+    Ok(quote! {
+        {
+            #(#bindings)*
+            let mut #args = ::redis_rs_macro::Args::new();
+            #appended
+            #args
+        }
+    })
+}
+
+/// The command name is passed to `redis::cmd`, which only accepts a `&str`. Unquoted names are
+/// normalized to uppercase, while quoted names are passed through as written.
+fn expand_name(arg: &Arg) -> syn::Result<TokenStream> {
+    if let Some(kind) = arg.pieces.iter().find_map(Piece::standalone_kind) {
+        let msg = format!("the command name cannot be {}", kind);
+        return Err(syn::Error::new(arg.span, msg));
+    }
+    match arg.pieces.as_slice() {
+        [Piece::Text(text)] => {
+            let name = text.to_uppercase();
+            Ok(quote!(#name))
+        }
+        [Piece::Str(lit)] => Ok(quote!(#lit)),
+        [Piece::ByteStr(_)] => Err(syn::Error::new(
+            arg.span,
+            "the command name cannot be a byte string",
+        )),
+        [Piece::Marked {
+            marker: Marker::Raw,
+            expr,
+        }] => Ok(Marker::Raw.expand(expr)),
+        // A name taken from a value could come from user input, so it has to be asked for
+        [Piece::Expr(expr)] => {
+            let msg = format!(
+                "the command name is a substitution, which can send any command; write \
+                `{{raw {}}}` if it is meant to be dynamic",
+                quote!(#expr)
+            );
+            Err(syn::Error::new(arg.span, msg))
+        }
+        [Piece::Marked { marker, .. }] => {
+            let msg = format!(
+                "the command name cannot be a `{}` substitution",
+                marker.name()
+            );
+            Err(syn::Error::new(arg.span, msg))
+        }
+        _ => Err(syn::Error::new(
+            arg.span,
+            "the command name cannot mix text and expression substitutions",
+        )),
+    }
+}
+
+/// Generate the statements that append `args` to `cmd`. `name` is given when `args` directly
+/// follow the command name, so that time operands can be recognized by their position. `roles`
+/// marks the arguments that get the key prefix.
+fn expand_args(
+    cmd: &Ident,
+    args: &[Arg],
+    name: Option<String>,
+    roles: &[KeyRole],
+) -> syn::Result<TokenStream> {
+    let (mut prev, mut before_prev) = (None, None);
+    args.iter()
+        .zip(roles)
+        .enumerate()
+        .map(|(index, (arg, role))| {
+            let time_arg = name
+                .as_deref()
+                .and_then(|name| TimeArg::of_position(name, index + 1))
+                .or_else(|| {
+                    prev.and_then(Arg::word)
+                        .as_deref()
+                        .and_then(TimeArg::of_option)
+                })
+                .or_else(|| {
+                    before_prev
+                        .and_then(Arg::word)
+                        .as_deref()
+                        .and_then(TimeArg::of_option_value)
+                });
+            (before_prev, prev) = (prev, Some(arg));
+            expand_arg(cmd, arg, time_arg, *role)
+        })
+        .collect()
+}
+
+/// Generate the statements that append an argument to `cmd`. `time_arg` is set when the argument is
+/// a TTL or timestamp, so that durations and times are converted to its unit.
+fn expand_arg(
+    cmd: &Ident,
+    arg: &Arg,
+    time_arg: Option<TimeArg>,
+    role: KeyRole,
+) -> syn::Result<TokenStream> {
+    if role == KeyRole::Unknown {
+        let msg = "cannot tell whether this argument is a key, so the key prefix can't be applied \
+            to it; move spreads and optional arguments after the keys of the command";
+        return Err(syn::Error::new(arg.span, msg));
+    }
+    let key = role != KeyRole::None;
+    if let (true, Some(kind)) = (key, key_kind(arg)) {
+        let msg = format!("keys cannot be {} when the key prefix is enabled", kind);
+        return Err(syn::Error::new(arg.span, msg));
+    }
+    Ok(match arg.pieces.as_slice() {
+        [Piece::Spread(expr)] => {
+            let item = Ident::new("item", Span::mixed_site());
+            let value = match role {
+                KeyRole::Pairs => prefix_pair(&item),
+                _ => prefix(&item, key),
+            };
+            quote! {
+                for #item in #expr {
+                    #cmd.arg(#value);
+                }
+            }
+        }
+        [Piece::Marked { marker, expr }] if marker.is_spread() => {
+            let item = Ident::new("item", Span::mixed_site());
+            let items = marker.expand(expr);
+            quote! {
+                for #item in #items {
+                    #cmd.arg(#item);
+                }
+            }
+        }
+        [Piece::Fields(expr)] => {
+            // Spanned at the value, so that a missing `ToRedisFields` impl points at it
+            let fields = quote_spanned! {expr.span()=>
+                ::redis_rs_macro::__private::fields::fields(&(#expr))
+            };
+            quote!(#cmd.arg(#fields);)
+        }
+        [Piece::Optional { keyword, expr }] => {
+            let item = Ident::new("item", Span::mixed_site());
+            let value = match keyword.as_deref().and_then(TimeArg::of_option) {
+                Some(time_arg) => time_arg.convert(&syn::parse_quote!(#item)),
+                None => prefix(&item, key),
+            };
+            let keyword = keyword.as_ref().map(|keyword| quote!(#cmd.arg(#keyword);));
+            quote! {
+                if let Some(#item) = #expr {
+                    #keyword
+                    #cmd.arg(#value);
+                }
+            }
+        }
+        [Piece::Conditional { cond, args }] => {
+            let args = expand_args(cmd, args, None, &vec![role; args.len()])?;
+            quote! {
+                if #cond {
+                    #args
+                }
+            }
+        }
+        [Piece::Expr(expr)] => {
+            let value = match time_arg {
+                Some(time_arg) => time_arg.convert(expr),
+                None => prefix(expr, key),
+            };
+            quote!(#cmd.arg(#value);)
+        }
+        _ => {
+            let value = prefix(&expand_value(arg), key);
+            quote!(#cmd.arg(#value);)
+        }
+    })
+}
+
+/// Arguments that can't be prefixed as keys
+fn key_kind(arg: &Arg) -> Option<&'static str> {
+    match arg.pieces.as_slice() {
+        [Piece::Fields(_)] => Some("a field spread"),
+        [Piece::Marked { marker, .. }] if marker.is_spread() => Some("a spread"),
+        _ => None,
+    }
+}
+
+/// Wrap the value of a key so that the key prefix is prepended to it
+fn prefix(value: &impl ToTokens, key: bool) -> TokenStream {
+    if key {
+        quote!(::redis_rs_macro::__private::prefix::key(#value))
+    } else {
+        quote!(#value)
+    }
+}
+
+/// Wrap a `(key, value)` pair so that the key prefix is prepended to its key
+fn prefix_pair(item: &Ident) -> TokenStream {
+    quote!(::redis_rs_macro::__private::prefix::pair(#item))
+}
+
+/// Generate the expression passed to `.arg` for an argument with a single value
+fn expand_value(arg: &Arg) -> TokenStream {
+    match arg.pieces.as_slice() {
+        [Piece::Text(text)] => {
+            let lit = LitStr::new(text, arg.span);
+            quote!(#lit)
+        }
+        [Piece::Str(lit)] => quote!(#lit),
+        [Piece::ByteStr(lit)] => quote!(&#lit[..]),
+        [Piece::Expr(expr)] => quote!(#expr),
+        [Piece::Marked { marker, expr }] => marker.expand(expr),
+        // Pieces written next to each other are joined into a single binary argument
+        pieces => {
+            let pieces = pieces.iter().map(|piece| match piece {
+                Piece::Text(text) => {
+                    let lit = LitByteStr::new(text.as_bytes(), arg.span);
+                    quote!(#lit.to_vec())
+                }
+                Piece::Str(lit) => quote!(#lit.as_bytes().to_vec()),
+                Piece::ByteStr(lit) => quote!(#lit.to_vec()),
+                Piece::Expr(expr) => quote!(redis::ToRedisArgs::to_redis_args(&(#expr)).concat()),
+                // Bytes are already binary, so they skip the `ToRedisArgs` conversion
+                Piece::Marked {
+                    marker: Marker::Bytes,
+                    expr,
+                } => {
+                    let value = Marker::Bytes.expand(expr);
+                    quote!(#value.to_vec())
+                }
+                Piece::Marked { marker, expr } => {
+                    let value = marker.expand(expr);
+                    quote!(redis::ToRedisArgs::to_redis_args(&(#value)).concat())
+                }
+                Piece::Spread(_)
+                | Piece::Fields(_)
+                | Piece::Optional { .. }
+                | Piece::Conditional { .. } => {
+                    unreachable!("standalone pieces are always a whole argument")
+                }
+            });
+            quote!([#(#pieces),*].concat())
+        }
+    }
+}
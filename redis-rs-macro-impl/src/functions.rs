@@ -0,0 +1,242 @@
+use crate::lua::{self, Token};
+use crate::script::ScriptFile;
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::spanned::Spanned;
+use syn::{Ident, ItemMod, LitStr};
+
+/// A Redis Functions library, as declared by its source
+#[derive(Debug, PartialEq)]
+struct Library {
+    name: String,
+    functions: Vec<Function>,
+}
+
+/// A function registered with `redis.register_function`
+#[derive(Debug, PartialEq)]
+struct Function {
+    name: String,
+    /// Whether it has the `no-writes` flag, so that it can be called with `FCALL_RO`
+    read_only: bool,
+}
+
+/// Read the library name from the `#!lua name=<library>` line, and the registered functions from
+/// the `redis.register_function` calls
+fn parse_library(source: &str) -> Result<Library, String> {
+    let first = source.lines().next().unwrap_or_default();
+    let name = first
+        .strip_prefix("#!lua")
+        .into_iter()
+        .flat_map(str::split_whitespace)
+        .find_map(|param| param.strip_prefix("name="))
+        .ok_or("the library must start with `#!lua name=<library>`")?;
+    // Blank out the shebang, keeping the offsets of the rest
+    let body = format!("{}{}", " ".repeat(first.len()), &source[first.len()..]);
+    if cfg!(feature = "lua-check") {
+        lua::check(&body).map_err(|err| err.to_string())?;
+    }
+    let tokens: Vec<Token> = lua::tokenize(&body)
+        .map_err(|err| err.to_string())?
+        .into_iter()
+        .map(|(token, _)| token)
+        .collect();
+    let mut functions = vec![];
+    for (i, window) in tokens.windows(3).enumerate() {
+        let [Token::Name(redis), Token::Symbol("."), Token::Name(register)] = window else {
+            continue;
+        };
+        if redis != "redis" || register != "register_function" {
+            continue;
+        }
+        let function = match &tokens[i + 3..] {
+            [Token::Symbol("("), Token::Str(name), ..] | [Token::Str(name), ..] => Function {
+                name: name.clone(),
+                read_only: false,
+            },
+            [Token::Symbol("{"), rest @ ..] => parse_table(rest)?,
+            _ => return Err("expected the name of a registered function".to_string()),
+        };
+        if functions.iter().any(|f: &Function| f.name == function.name) {
+            return Err(format!("`{}` is registered twice", function.name));
+        }
+        functions.push(function);
+    }
+    Ok(Library {
+        name: name.to_string(),
+        functions,
+    })
+}
+
+/// Read the `function_name` and `flags` of a `redis.register_function{..}` table
+fn parse_table(tokens: &[Token]) -> Result<Function, String> {
+    let mut name = None;
+    let mut read_only = false;
+    let mut depth = 0;
+    for (i, token) in tokens.iter().enumerate() {
+        match token {
+            Token::Symbol("{" | "(" | "[") => depth += 1,
+            Token::Symbol("}") if depth == 0 => break,
+            Token::Symbol("}" | ")" | "]") => depth -= 1,
+            Token::Name(key) if depth == 0 => match (key.as_str(), &tokens[i + 1..]) {
+                ("function_name", [Token::Symbol("="), Token::Str(value), ..]) => {
+                    name = Some(value.clone());
+                }
+                ("flags", [Token::Symbol("="), Token::Symbol("{"), flags @ ..]) => {
+                    read_only = flags
+                        .iter()
+                        .take_while(|token| **token != Token::Symbol("}"))
+                        .any(|token| *token == Token::Str("no-writes".to_string()));
+                }
+                _ => {}
+            },
+            _ => {}
+        }
+    }
+    let name = name.ok_or("expected a `function_name` in `redis.register_function{..}`")?;
+    Ok(Function { name, read_only })
+}
+
+/// Fill a `#[redis_functions("path")]` module with the library source, a loader, and a wrapper for
+/// every registered function
+pub(crate) fn expand_functions(args: TokenStream, input: TokenStream) -> syn::Result<TokenStream> {
+    let lit: LitStr = syn::parse2(args).map_err(|err| {
+        syn::Error::new(err.span(), "expected the path of a Redis Functions library")
+    })?;
+    let mut item: ItemMod = syn::parse2(input)?;
+    let Some((_, items)) = &mut item.content else {
+        let msg = "`#[redis_functions]` needs a module with a body, such as `mod orders {}`";
+        return Err(syn::Error::new(item.span(), msg));
+    };
+    let ScriptFile { path, source, .. } = ScriptFile::read(&lit)?;
+    let library = parse_library(&source)
+        .map_err(|msg| syn::Error::new(lit.span(), format!("{} in `{}`", msg, lit.value())))?;
+    let name = &library.name;
+    let source_doc = format!("The source of the `{}` library", name);
+    items.push(syn::parse_quote! {
+        #[doc = #source_doc]
+        pub const SOURCE: &str = ::core::include_str!(#path);
+    });
+    items.push(syn::parse_quote! {
+        /// The name of the library
+        pub const LIBRARY: &str = #name;
+    });
+    items.push(syn::parse_quote! {
+        /// Load the library with `FUNCTION LOAD REPLACE`, replacing an older version of it
+        pub fn load(con: &mut dyn redis::ConnectionLike) -> redis::RedisResult<()> {
+            redis::cmd("FUNCTION")
+                .arg("LOAD")
+                .arg("REPLACE")
+                .arg(SOURCE)
+                .query::<::std::string::String>(con)
+                .map(|_| ())
+        }
+    });
+    for Function { name, read_only } in &library.functions {
+        let ident = syn::parse_str::<Ident>(name).map_err(|_| {
+            let msg = format!("the function `{}` isn't a valid Rust name", name);
+            syn::Error::new(lit.span(), msg)
+        })?;
+        if ident == "load" {
+            let msg = "a function named `load` would shadow the library loader";
+            return Err(syn::Error::new(lit.span(), msg));
+        }
+        let (command, doc) = match read_only {
+            true => ("FCALL_RO", format!("Call `{}` with `FCALL_RO`", name)),
+            false => ("FCALL", format!("Call `{}` with `FCALL`", name)),
+        };
+        let keys = match cfg!(feature = "key-prefix") {
+            true => quote!(::redis_rs_macro::__private::prefix::key(keys)),
+            false => quote!(keys),
+        };
+        items.push(syn::parse_quote! {
+            #[doc = #doc]
+            pub fn #ident<T: redis::FromRedisValue>(
+                con: &mut dyn redis::ConnectionLike,
+                keys: impl redis::ToRedisArgs,
+                args: impl redis::ToRedisArgs,
+            ) -> redis::RedisResult<T> {
+                let keys = redis::ToRedisArgs::to_redis_args(&#keys);
+                redis::cmd(#command)
+                    .arg(#name)
+                    .arg(keys.len())
+                    .arg(keys)
+                    .arg(args)
+                    .query(con)
+            }
+        });
+    }
+    Ok(quote!(#item))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn functions_parse() {
+        let source = r#"#!lua name=orders
+            -- redis.register_function('commented', f)
+            local function add(keys, args) return redis.call('INCRBY', keys[1], args[1]) end
+            redis.register_function('add', add)
+            redis.register_function{
+                function_name = "total",
+                callback = function(keys) return redis.call('GET', keys[1]) end,
+                flags = { 'no-writes' },
+            }
+            redis.register_function{ flags = {}, function_name = [[reset]], callback = add }
+        "#;
+        let library = parse_library(source).unwrap();
+        assert_eq!(library.name, "orders");
+        let functions: Vec<_> = library
+            .functions
+            .iter()
+            .map(|f| (f.name.as_str(), f.read_only))
+            .collect();
+        assert_eq!(
+            functions,
+            [("add", false), ("total", true), ("reset", false)]
+        );
+    }
+
+    #[test]
+    fn functions_parse_errors() {
+        let err = |source: &str| parse_library(source).unwrap_err();
+        assert!(err("redis.register_function('a', f)").contains("#!lua name="));
+        assert!(err("#!lua\n").contains("#!lua name="));
+        let e = err("#!lua name=a\nredis.register_function(f)");
+        assert!(e.contains("expected the name"), "{}", e);
+        let e = err("#!lua name=a\nredis.register_function{callback = f}");
+        assert!(e.contains("function_name"), "{}", e);
+        let e =
+            err("#!lua name=a\nredis.register_function('f', f)\nredis.register_function('f', g)");
+        assert!(e.contains("registered twice"), "{}", e);
+    }
+
+    #[test]
+    fn functions_expand_errors() {
+        let err = |args: TokenStream, input: TokenStream| {
+            expand_functions(args, input).unwrap_err().to_string()
+        };
+        let e = err(
+            quote!(1),
+            quote!(
+                mod m {}
+            ),
+        );
+        assert!(e.contains("expected the path"), "{}", e);
+        let e = err(
+            quote!("Cargo.toml"),
+            quote!(
+                mod m;
+            ),
+        );
+        assert!(e.contains("module with a body"), "{}", e);
+        let e = err(
+            quote!("Cargo.toml"),
+            quote!(
+                mod m {}
+            ),
+        );
+        assert!(e.contains("#!lua name="), "{}", e);
+    }
+}
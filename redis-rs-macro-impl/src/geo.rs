@@ -0,0 +1,208 @@
+use crate::parse::{Arg, Command, Piece};
+use proc_macro2::Span;
+use syn::spanned::Spanned;
+use syn::{Expr, ExprLit, ExprUnary, Lit, UnOp};
+
+/// The furthest north or south that redis can index, in degrees
+const MAX_LATITUDE: f64 = 85.05112878;
+
+/// The part of a coordinate pair a value is in
+#[derive(Clone, Copy, PartialEq)]
+enum Axis {
+    Longitude,
+    Latitude,
+}
+
+impl Axis {
+    fn limit(self) -> f64 {
+        match self {
+            Axis::Longitude => 180.0,
+            Axis::Latitude => MAX_LATITUDE,
+        }
+    }
+}
+
+/// Check that the literal coordinates of geo commands are in range, so that out of range or
+/// swapped coordinates are caught at compile time. Coordinates can be written as two arguments,
+/// or as a `{(longitude, latitude)}` tuple. Checking stops at the first value whose number of
+/// arguments isn't known, such as a variable or a spread.
+pub(crate) fn check_coordinates(command: &Command) -> syn::Result<()> {
+    let Some(name) = command.args[0].word() else {
+        return Ok(());
+    };
+    let args = &command.args[1..];
+    match name.to_ascii_uppercase().as_str() {
+        "GEOADD" => {
+            // GEOADD key [NX | XX] [CH] longitude latitude member ...
+            let start = 1 + args
+                .iter()
+                .skip(1)
+                .take_while(|arg| {
+                    arg.word().is_some_and(|word| {
+                        matches!(word.to_ascii_uppercase().as_str(), "NX" | "XX" | "CH")
+                    })
+                })
+                .count();
+            check_values(args.get(start..).unwrap_or_default(), |index| {
+                match index % 3 {
+                    0 => Some(Axis::Longitude),
+                    1 => Some(Axis::Latitude),
+                    _ => None,
+                }
+            })
+        }
+        // GEORADIUS key longitude latitude radius ...
+        "GEORADIUS" | "GEORADIUS_RO" => check_pair(args.get(1..).unwrap_or_default()),
+        // GEOSEARCH key FROMLONLAT longitude latitude ...
+        _ => {
+            for (index, arg) in args.iter().enumerate() {
+                if arg
+                    .word()
+                    .is_some_and(|word| word.eq_ignore_ascii_case("FROMLONLAT"))
+                {
+                    check_pair(&args[index + 1..])?;
+                }
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Check the longitude and latitude at the start of `args`
+fn check_pair(args: &[Arg]) -> syn::Result<()> {
+    check_values(args, |index| match index {
+        0 => Some(Axis::Longitude),
+        1 => Some(Axis::Latitude),
+        _ => None,
+    })
+}
+
+/// Check every literal value in `args`, where `axis` gives the axis of each value by its index
+fn check_values(args: &[Arg], axis: impl Fn(usize) -> Option<Axis>) -> syn::Result<()> {
+    let mut index = 0;
+    for arg in args {
+        let Some(values) = literal_values(arg) else {
+            return Ok(());
+        };
+        for (value, span) in values {
+            if let (Some(axis), Some(value)) = (axis(index), value) {
+                check_value(axis, value, span)?;
+            }
+            index += 1;
+        }
+    }
+    Ok(())
+}
+
+fn check_value(axis: Axis, value: f64, span: Span) -> syn::Result<()> {
+    if value.abs() <= axis.limit() {
+        return Ok(());
+    }
+    let mut msg = match axis {
+        Axis::Longitude => format!(
+            "longitude {} is out of range, as it must be within ±180",
+            value
+        ),
+        Axis::Latitude => format!(
+            "latitude {} is out of range, as it must be within ±{}",
+            value, MAX_LATITUDE
+        ),
+    };
+    // A latitude that would be a valid longitude usually means the pair was written backwards
+    if axis == Axis::Latitude && value.abs() <= Axis::Longitude.limit() {
+        msg.push_str("; coordinates are written longitude first, so they may be swapped");
+    }
+    Err(syn::Error::new(span, msg))
+}
+
+/// The values an argument is known to expand to, with `None` for values that aren't literal
+/// numbers. Returns `None` when the number of values isn't known.
+fn literal_values(arg: &Arg) -> Option<Vec<(Option<f64>, Span)>> {
+    match arg.pieces.as_slice() {
+        [Piece::Text(text)] => Some(vec![(text.parse().ok(), arg.span)]),
+        [Piece::Str(_) | Piece::ByteStr(_)] => Some(vec![(None, arg.span)]),
+        [Piece::Expr(expr)] => match &**expr {
+            Expr::Tuple(tuple) => Some(
+                tuple
+                    .elems
+                    .iter()
+                    .map(|elem| (literal_number(elem), elem.span()))
+                    .collect(),
+            ),
+            expr => literal_number(expr).map(|value| vec![(Some(value), arg.span)]),
+        },
+        // Joined text is a single argument, like a member name
+        pieces if pieces.iter().all(|piece| piece.standalone_kind().is_none()) => {
+            Some(vec![(None, arg.span)])
+        }
+        _ => None,
+    }
+}
+
+/// The value of a number literal, possibly negated
+fn literal_number(expr: &Expr) -> Option<f64> {
+    match expr {
+        Expr::Lit(ExprLit {
+            lit: Lit::Float(lit),
+            ..
+        }) => lit.base10_parse().ok(),
+        Expr::Lit(ExprLit {
+            lit: Lit::Int(lit), ..
+        }) => lit.base10_parse::<i64>().ok().map(|value| value as f64),
+        Expr::Unary(ExprUnary {
+            op: UnOp::Neg(_),
+            expr,
+            ..
+        }) => literal_number(expr).map(|value| -value),
+        Expr::Paren(paren) => literal_number(&paren.expr),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse::parse_command;
+    use proc_macro2::TokenStream;
+
+    fn check(input: &str) -> Result<(), String> {
+        let tokens: TokenStream = input.parse().unwrap();
+        check_coordinates(&parse_command(tokens).unwrap()).map_err(|err| err.to_string())
+    }
+
+    #[test]
+    fn geo_valid() {
+        for input in [
+            "GEOADD places 13.361389 38.115556 Palermo",
+            "GEOADD places NX CH -180 -85.05 a 180 85.05 b",
+            "GEOADD places {(13.36, 38.11)} Palermo {(-0.12, 51.5)} London",
+            "GEOADD places {lon} 100 m",
+            "GEORADIUS places 15 37 200 km",
+            "GEOSEARCH places FROMLONLAT 15 37 BYRADIUS 200 km",
+            "GET 200 100",
+        ] {
+            assert_eq!(check(input), Ok(()), "{}", input);
+        }
+    }
+
+    #[test]
+    fn geo_invalid() {
+        for (input, msg) in [
+            ("GEOADD places 181 0 m", "longitude 181 is out of range"),
+            ("GEOADD places 0 0 a 0 86 b", "latitude 86 is out of range"),
+            ("GEOADD places 38.1 130.5 Palermo", "may be swapped"),
+            (
+                "GEOADD places XX {(0, -90)} m",
+                "latitude -90 is out of range",
+            ),
+            ("GEORADIUS places 200 0 1 km", "longitude 200"),
+            (
+                "GEOSEARCH places FROMLONLAT 0 -100 BYRADIUS 1 km",
+                "latitude -100",
+            ),
+        ] {
+            let err = check(input).expect_err(input);
+            assert!(err.contains(msg), "{}: {}", input, err);
+        }
+    }
+}
@@ -0,0 +1,215 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Data, DeriveInput, Fields, LitStr, Token};
+
+/// A field of a `#[derive(RedisHash)]` struct, with its name in the hash and how it is read
+struct Field {
+    ident: syn::Ident,
+    ty: syn::Type,
+    name: LitStr,
+    /// `#[redis(skip)]`: never written, and read as its default
+    skip: bool,
+    /// `#[redis(default)]`: read as its default when the hash doesn't have it
+    default: bool,
+}
+
+/// Generate `redis::ToRedisArgs`, `ToRedisFields` and `redis::FromRedisValue` implementations for
+/// a struct with named fields, mapping each field to a hash field of the same name, or of its
+/// `#[redis(rename)]`
+pub(crate) fn expand_hash(input: TokenStream) -> syn::Result<TokenStream> {
+    let input: DeriveInput = syn::parse2(input)?;
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                let msg = "`RedisHash` needs a struct with named fields";
+                return Err(syn::Error::new(input.ident.span(), msg));
+            }
+        },
+        _ => {
+            let msg = "`RedisHash` can only be derived for structs";
+            return Err(syn::Error::new(input.ident.span(), msg));
+        }
+    };
+    let fields = fields
+        .iter()
+        .map(parse_field)
+        .collect::<syn::Result<Vec<_>>>()?;
+    for (i, field) in fields.iter().enumerate() {
+        let used = fields[..i]
+            .iter()
+            .any(|f| !f.skip && f.name.value() == field.name.value());
+        if used && !field.skip {
+            let msg = format!("the hash field `{}` is used twice", field.name.value());
+            return Err(syn::Error::new(field.name.span(), msg));
+        }
+    }
+
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let mut write_bounds = where_clause
+        .cloned()
+        .unwrap_or_else(|| syn::parse_quote!(where));
+    let mut read_bounds = write_bounds.clone();
+    for field in &fields {
+        let ty = &field.ty;
+        if !field.skip {
+            write_bounds
+                .predicates
+                .push(syn::parse_quote!(#ty: redis::ToRedisArgs));
+            read_bounds
+                .predicates
+                .push(syn::parse_quote!(#ty: redis::FromRedisValue));
+        }
+        if field.skip || field.default {
+            read_bounds
+                .predicates
+                .push(syn::parse_quote!(#ty: ::core::default::Default));
+        }
+    }
+    let writes = fields.iter().filter(|field| !field.skip).map(|field| {
+        let Field { ident, name, .. } = field;
+        quote!(::redis_rs_macro::__private::entry::write_field(out, #name, &self.#ident);)
+    });
+    let reads = fields.iter().map(|field| {
+        let Field { ident, name, .. } = field;
+        if field.skip {
+            quote!(#ident: ::core::default::Default::default(),)
+        } else if field.default {
+            quote!(#ident: fields.get_or_default(#name)?,)
+        } else {
+            quote!(#ident: fields.get(#name)?,)
+        }
+    });
+    Ok(quote! {
+        impl #impl_generics ::redis_rs_macro::ToRedisFields for #ident #ty_generics #write_bounds {
+            fn write_redis_fields<W: ?::core::marker::Sized + redis::RedisWrite>(&self, out: &mut W) {
+                #(#writes)*
+            }
+        }
+
+        impl #impl_generics redis::ToRedisArgs for #ident #ty_generics #write_bounds {
+            fn write_redis_args<W: ?::core::marker::Sized + redis::RedisWrite>(&self, out: &mut W) {
+                ::redis_rs_macro::ToRedisFields::write_redis_fields(self, out)
+            }
+
+            fn is_single_arg(&self) -> bool {
+                false
+            }
+        }
+
+        impl #impl_generics redis::FromRedisValue for #ident #ty_generics #read_bounds {
+            fn from_redis_value(value: &redis::Value) -> redis::RedisResult<Self> {
+                let fields = ::redis_rs_macro::__private::entry::EntryFields::new(value)?;
+                ::core::result::Result::Ok(#ident {
+                    #(#reads)*
+                })
+            }
+        }
+    })
+}
+
+/// Parse the `#[redis(rename = "name", skip, default)]` attributes of a field
+fn parse_field(field: &syn::Field) -> syn::Result<Field> {
+    let ident = field.ident.clone().unwrap();
+    let mut parsed = Field {
+        name: LitStr::new(&ident.to_string(), ident.span()),
+        ident,
+        ty: field.ty.clone(),
+        skip: false,
+        default: false,
+    };
+    let mut renamed = false;
+    for attr in &field.attrs {
+        if !attr.path().is_ident("redis") {
+            continue;
+        }
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("rename") {
+                meta.input.parse::<Token![=]>()?;
+                parsed.name = meta.input.parse()?;
+                renamed = true;
+            } else if meta.path.is_ident("skip") {
+                parsed.skip = true;
+            } else if meta.path.is_ident("default") {
+                parsed.default = true;
+            } else {
+                return Err(meta.error("expected `rename = \"...\"`, `skip` or `default`"));
+            }
+            Ok(())
+        })?;
+    }
+    if parsed.skip && renamed {
+        let msg = "a `#[redis(skip)]` field isn't in the hash, so it can't be renamed";
+        return Err(syn::Error::new(parsed.name.span(), msg));
+    }
+    Ok(parsed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expand(input: &str) -> syn::Result<String> {
+        expand_hash(input.parse().unwrap()).map(|out| out.to_string())
+    }
+
+    #[test]
+    fn hash_expand() {
+        let output = expand(
+            r#"struct User {
+                id: u64,
+                #[redis(rename = "display_name")]
+                name: String,
+                #[redis(default)]
+                visits: u32,
+                #[redis(skip)]
+                session: Option<Session>,
+            }"#,
+        )
+        .unwrap();
+        assert!(
+            output.contains("write_field (out , \"id\" , & self . id)"),
+            "{}",
+            output
+        );
+        assert!(!output.contains("\"session\""), "{}", output);
+        assert!(
+            output.contains("name : fields . get (\"display_name\") ?"),
+            "{}",
+            output
+        );
+        assert!(
+            output.contains("visits : fields . get_or_default (\"visits\") ?"),
+            "{}",
+            output
+        );
+        assert!(
+            output.contains("session : :: core :: default :: Default :: default ()"),
+            "{}",
+            output
+        );
+        assert!(
+            output.contains("Option < Session > : :: core :: default :: Default"),
+            "{}",
+            output
+        );
+        assert!(!output.contains("Option < Session > : redis"), "{}", output);
+    }
+
+    #[test]
+    fn hash_errors() {
+        let err = |input: &str| expand(input).unwrap_err().to_string();
+        let e = err("struct T(u8);");
+        assert!(e.contains("named fields"), "{}", e);
+        let e = err("enum T { A }");
+        assert!(e.contains("only be derived for structs"), "{}", e);
+        let e = err("struct T { #[redis(name = \"a\")] a: u8 }");
+        assert!(e.contains("expected `rename"), "{}", e);
+        let e = err("struct T { a: u8, #[redis(rename = \"a\")] b: u8 }");
+        assert!(e.contains("the hash field `a` is used twice"), "{}", e);
+        let e = err("struct T { #[redis(skip, rename = \"b\")] a: u8 }");
+        assert!(e.contains("can't be renamed"), "{}", e);
+        expand("struct T { a: u8, #[redis(skip)] b: u8 }").unwrap();
+    }
+}
@@ -0,0 +1,202 @@
+use crate::lock::expand_key;
+use crate::marker::Marker;
+use proc_macro2::{Span, TokenStream, TokenTree};
+use quote::quote;
+use syn::parse::{Parse, ParseStream};
+use syn::{Block, Expr, Ident, Token};
+
+/// The input of `redis_idempotent!`, e.g. `con, idem:{id}, ttl = 86400, replay, { charge() }`
+struct IdempotentInput {
+    con: Expr,
+    key: TokenStream,
+    ttl: Expr,
+    /// The format that responses are stored in, when they are replayed
+    replay: Option<Marker>,
+    body: Block,
+}
+
+impl Parse for IdempotentInput {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let con = input.parse()?;
+        input.parse::<Token![,]>()?;
+        let mut key = TokenStream::new();
+        while !input.is_empty() && !input.peek(Token![,]) {
+            key.extend([input.parse::<TokenTree>()?]);
+        }
+        input.parse::<Token![,]>()?;
+        let mut ttl = None;
+        let mut replay: Option<(Ident, Option<Marker>)> = None;
+        while input.peek(Ident) {
+            let name: Ident = input.parse()?;
+            if name == "ttl" {
+                if ttl.is_some() {
+                    return Err(syn::Error::new(name.span(), "`ttl` is given twice"));
+                }
+                input.parse::<Token![=]>()?;
+                ttl = Some(input.parse()?);
+            } else if name == "replay" || Marker::parse_format(&name)?.is_some() {
+                if replay.is_some() {
+                    let msg = "`replay` is given twice; a format also replays responses";
+                    return Err(syn::Error::new(name.span(), msg));
+                }
+                let format = Marker::parse_format(&name)?;
+                replay = Some((name, format));
+            } else {
+                let msg = "expected `ttl`, `replay`, `json` or `msgpack`";
+                return Err(syn::Error::new(name.span(), msg));
+            }
+            input.parse::<Token![,]>()?;
+        }
+        let Some(ttl) = ttl else {
+            return Err(syn::Error::new(
+                Span::call_site(),
+                "expected `ttl = <seconds>`",
+            ));
+        };
+        if input.is_empty() {
+            return Err(input.error("expected the block that runs once for the key"));
+        }
+        let body = input.parse()?;
+        input.parse::<Option<Token![,]>>()?;
+        let replay = match replay {
+            Some((_, Some(format))) => Some(format),
+            Some((_, None)) => Some(Marker::default_format("replayed responses")?),
+            None => None,
+        };
+        Ok(IdempotentInput {
+            con,
+            key,
+            ttl,
+            replay,
+            body,
+        })
+    }
+}
+
+/// Generate a `redis_idempotent!` invocation, which claims the key, runs the block if it was
+/// claimed, and stores its response for duplicates when they are replayed
+pub(crate) fn expand_idempotent(input: TokenStream) -> syn::Result<TokenStream> {
+    let IdempotentInput {
+        con,
+        key,
+        ttl,
+        replay,
+        body,
+    } = syn::parse2(input)?;
+    let key = expand_key(key)?;
+    // The connection is reborrowed at each use, since the block may use it too
+    let con_var = quote!(&mut *#con);
+    let idem = Ident::new("idem", Span::mixed_site());
+    let value = Ident::new("value", Span::mixed_site());
+    let result = Ident::new("result", Span::mixed_site());
+    let outcome = quote!(::redis_rs_macro::Idempotent);
+    let ok = quote!(::core::result::Result::Ok);
+    let err = quote!(::core::result::Result::Err);
+    let from = quote!(::core::convert::From::from);
+    let (duplicate, executed) = match replay {
+        Some(marker) => {
+            let wrapper = marker.reply_wrapper();
+            let serialize = match marker {
+                Marker::MsgPack => quote!(::redis_rs_macro::__private::msgpack::to_vec),
+                _ => quote!(::redis_rs_macro::__private::json::to_string),
+            };
+            let duplicate = quote! {
+                match #idem.response::<#wrapper<_>>(#con_var) {
+                    #ok(#value) => #ok(#outcome::Duplicate(#value.map(|#wrapper(#value)| #value))),
+                    #err(#result) => #err(#from(#result)),
+                }
+            };
+            let executed = quote! {
+                match #serialize(&#value).and_then(|#result| #idem.complete(#con_var, #result)) {
+                    #ok(_) => #ok(#outcome::Executed(#value)),
+                    #err(#result) => #err(#from(#result)),
+                }
+            };
+            (duplicate, executed)
+        }
+        None => (
+            quote!(#ok(#outcome::Duplicate(::core::option::Option::None))),
+            quote!(#ok(#outcome::Executed(#value))),
+        ),
+    };
+    Ok(quote! {
+        {
+            let #idem = ::redis_rs_macro::Idempotency::new(#key, #ttl);
+            match #idem.claim(#con_var) {
+                #err(#result) => #err(#from(#result)),
+                #ok(false) => #duplicate,
+                #ok(true) => {
+                    // The block runs in a closure, so that a `?` in it still releases the claim
+                    #[allow(clippy::redundant_closure_call)]
+                    let #result = (|| #body)();
+                    match #result {
+                        #ok(#value) => #executed,
+                        #err(#result) => {
+                            let _ = #idem.release(#con_var);
+                            #err(#result)
+                        }
+                    }
+                }
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expand(input: &str) -> syn::Result<String> {
+        expand_idempotent(input.parse().unwrap()).map(|output| output.to_string())
+    }
+
+    #[test]
+    fn idempotent_expand() {
+        let output = expand("&mut con, idem:{id}, ttl = 86400, { charge(id) }").unwrap();
+        assert!(
+            output.contains(", 86400) ; match idem . claim (& mut * & mut con) {"),
+            "{}",
+            output
+        );
+        assert!(
+            output.contains("Ok (false) => :: core :: result :: Result :: Ok (:: redis_rs_macro :: Idempotent :: Duplicate (:: core :: option :: Option :: None))"),
+            "{}",
+            output
+        );
+        assert!(
+            output.contains("let result = (|| { charge (id) }) () ;"),
+            "{}",
+            output
+        );
+        assert!(!output.contains("complete"), "{}", output);
+
+        let output = expand("con, idem:{id}, replay, ttl = 60, { charge(id) },").unwrap();
+        assert!(
+            output.contains("idem . response :: < :: redis_rs_macro :: Json < _ >>"),
+            "{}",
+            output
+        );
+        assert!(
+            output.contains("json :: to_string (& value) . and_then"),
+            "{}",
+            output
+        );
+        let output = expand("con, idem:{id}, ttl = 60, msgpack, { charge(id) }").unwrap();
+        assert!(output.contains("msgpack :: to_vec (& value)"), "{}", output);
+    }
+
+    #[test]
+    fn idempotent_errors() {
+        let err = |input: &str| expand(input).unwrap_err().to_string();
+        let e = err("con, idem:{id}, { run() }");
+        assert!(e.contains("expected `ttl = <seconds>`"), "{}", e);
+        let e = err("con, idem:{id}, ttl = 1, replay, msgpack, { run() }");
+        assert!(e.contains("`replay` is given twice"), "{}", e);
+        let e = err("con, idem:{id}, ttl = 1, px = 1, { run() }");
+        assert!(e.contains("expected `ttl`, `replay`"), "{}", e);
+        let e = err("con, idem:{id}, ttl = 1,");
+        assert!(e.contains("expected the block"), "{}", e);
+        let e = err("con, a b, ttl = 1, { run() }");
+        assert!(e.contains("single key"), "{}", e);
+    }
+}
@@ -0,0 +1,68 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::Ident;
+
+/// The sections of `INFO` that are read into a type, with the name of the type
+const SECTIONS: &[(&str, &str)] = &[
+    ("memory", "MemoryInfo"),
+    ("replication", "ReplicationInfo"),
+    ("clients", "ClientsInfo"),
+    ("keyspace", "KeyspaceInfo"),
+];
+
+/// Generate a `redis_info!` invocation, an `INFO <section>` command whose reply is read as the type
+/// of the section
+pub(crate) fn expand_info(input: TokenStream) -> syn::Result<TokenStream> {
+    let section: Ident = syn::parse2(input)?;
+    let name = section.to_string().to_ascii_lowercase();
+    let Some((name, ty)) = SECTIONS.iter().find(|(section, _)| *section == name) else {
+        let names: Vec<_> = SECTIONS.iter().map(|(section, _)| *section).collect();
+        let msg = format!(
+            "unknown INFO section `{}`; expected one of {}",
+            section,
+            names.join(", ")
+        );
+        return Err(syn::Error::new(section.span(), msg));
+    };
+    let ty = format_ident!("{}", ty);
+    Ok(quote! {
+        ::redis_rs_macro::TypedCmd::<::redis_rs_macro::#ty>::new(
+            redis::cmd("INFO").arg(#name).clone()
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expand(input: &str) -> syn::Result<String> {
+        expand_info(input.parse().unwrap()).map(|output| output.to_string())
+    }
+
+    #[test]
+    fn info_expand() {
+        assert_eq!(
+            expand("memory").unwrap(),
+            ":: redis_rs_macro :: TypedCmd :: < :: redis_rs_macro :: MemoryInfo > :: new (redis \
+             :: cmd (\"INFO\") . arg (\"memory\") . clone ())"
+        );
+        assert!(expand("Keyspace")
+            .unwrap()
+            .contains("KeyspaceInfo > :: new (redis :: cmd (\"INFO\") . arg (\"keyspace\")"));
+    }
+
+    #[test]
+    fn info_errors() {
+        let e = expand("cpu").unwrap_err().to_string();
+        assert!(
+            e.contains("unknown INFO section `cpu`; expected one of memory, replication"),
+            "{}",
+            e
+        );
+        let e = expand("memory clients").unwrap_err().to_string();
+        assert!(e.contains("unexpected token"), "{}", e);
+        let e = expand("").unwrap_err().to_string();
+        assert!(e.contains("expected identifier"), "{}", e);
+    }
+}
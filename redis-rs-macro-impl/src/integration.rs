@@ -0,0 +1,95 @@
+use proc_macro2::{Span, TokenStream};
+use quote::quote;
+use syn::spanned::Spanned;
+use syn::{FnArg, Ident, ItemFn};
+
+/// Generate a `#[redis_integration_test]` function, a test that starts a throwaway server and
+/// passes a connection to it to the original function
+pub(crate) fn expand_integration_test(
+    args: TokenStream,
+    input: TokenStream,
+) -> syn::Result<TokenStream> {
+    if !args.is_empty() {
+        let msg = "`#[redis_integration_test]` doesn't take arguments";
+        return Err(syn::Error::new(args.span(), msg));
+    }
+    let item: ItemFn = syn::parse2(input)?;
+    if let Some(asyncness) = item.sig.asyncness {
+        let msg = "integration tests can't be async, since they are run by `#[test]`";
+        return Err(syn::Error::new(asyncness.span(), msg));
+    }
+    let single_typed =
+        item.sig.inputs.len() == 1 && matches!(item.sig.inputs.first(), Some(FnArg::Typed(_)));
+    if !single_typed {
+        let msg = "integration tests take the connection as their only parameter, such as \
+                   `con: &mut redis::Connection`";
+        return Err(syn::Error::new(item.sig.inputs.span(), msg));
+    }
+    let ItemFn {
+        attrs,
+        vis,
+        sig,
+        block,
+    } = item;
+    let name = &sig.ident;
+    let output = &sig.output;
+    let server = Ident::new("server", Span::mixed_site());
+    let con = Ident::new("con", Span::mixed_site());
+    Ok(quote! {
+        #[test]
+        #(#attrs)*
+        #vis fn #name() #output {
+            // The test keeps its signature as an inner function, which runs while the server is
+            // alive. The server is killed when it is dropped, even if the test panics.
+            #sig #block
+
+            let #server = ::redis_rs_macro::TestServer::start()
+                .unwrap_or_else(|err| ::core::panic!("couldn't start redis-server: {}", err));
+            let mut #con = #server
+                .connection()
+                .unwrap_or_else(|err| ::core::panic!("couldn't connect to redis-server: {}", err));
+            #name(&mut #con)
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expand(args: &str, input: &str) -> syn::Result<String> {
+        expand_integration_test(args.parse().unwrap(), input.parse().unwrap())
+            .map(|output| output.to_string())
+    }
+
+    #[test]
+    fn integration_test_expand() {
+        let output = expand(
+            "",
+            "#[ignore] fn stores(con: &mut redis::Connection) -> redis::RedisResult<()> { Ok(()) }",
+        )
+        .unwrap();
+        assert!(
+            output.starts_with(
+                "# [test] # [ignore] fn stores () -> redis :: RedisResult < () > { \
+                 fn stores (con : & mut redis :: Connection) -> redis :: RedisResult < () > { Ok (()) }"
+            ),
+            "{}",
+            output
+        );
+        assert!(output.ends_with("stores (& mut con) }"), "{}", output);
+    }
+
+    #[test]
+    fn integration_test_errors() {
+        let err = |args: &str, input: &str| expand(args, input).unwrap_err().to_string();
+        let e = err("", "fn t() {}");
+        assert!(e.contains("as their only parameter"), "{}", e);
+        let e = err("", "fn t(a: &mut redis::Connection, b: u8) {}");
+        assert!(e.contains("as their only parameter"), "{}", e);
+        let e = err("", "async fn t(con: &mut redis::Connection) {}");
+        assert!(e.contains("can't be async"), "{}", e);
+        let e = err("port = 1", "fn t(con: &mut redis::Connection) {}");
+        assert!(e.contains("doesn't take arguments"), "{}", e);
+    }
+}
@@ -0,0 +1,76 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Data, DeriveInput};
+
+/// Generate `json_set` and `json_get` methods for a type that is stored as a RedisJSON document,
+/// serialized with serde
+pub(crate) fn expand_json(input: TokenStream) -> syn::Result<TokenStream> {
+    let input: DeriveInput = syn::parse2(input)?;
+    if let Data::Union(_) = input.data {
+        let msg = "`RedisJson` can't be derived for unions";
+        return Err(syn::Error::new(input.ident.span(), msg));
+    }
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics #ident #ty_generics #where_clause {
+            /// Store the value at `path` of the RedisJSON document at `key` with `JSON.SET`,
+            /// where `$` is the whole document
+            pub fn json_set<C, K>(&self, con: &mut C, key: K, path: &str) -> redis::RedisResult<()>
+            where
+                C: redis::ConnectionLike,
+                K: redis::ToRedisArgs,
+                Self: ::redis_rs_macro::__private::json::Serialize,
+            {
+                ::redis_rs_macro::__private::json::json_set(con, key, path, self)
+            }
+
+            /// Read the value at `path` of the RedisJSON document at `key` with `JSON.GET`, or
+            /// `None` when the key or path doesn't exist
+            pub fn json_get<C, K>(
+                con: &mut C,
+                key: K,
+                path: &str,
+            ) -> redis::RedisResult<::core::option::Option<Self>>
+            where
+                C: redis::ConnectionLike,
+                K: redis::ToRedisArgs,
+                Self: ::redis_rs_macro::__private::json::DeserializeOwned,
+            {
+                ::redis_rs_macro::__private::json::json_get(con, key, path)
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expand(input: &str) -> syn::Result<String> {
+        expand_json(input.parse().unwrap()).map(|out| out.to_string())
+    }
+
+    #[test]
+    fn json_expand() {
+        let output = expand("struct Cart<T> where T: Clone { items: Vec<T> }").unwrap();
+        assert!(
+            output.starts_with("impl < T > Cart < T > where T : Clone {"),
+            "{}",
+            output
+        );
+        assert!(
+            output.contains("__private :: json :: json_set (con , key , path , self)"),
+            "{}",
+            output
+        );
+        assert!(
+            output.contains("Self : :: redis_rs_macro :: __private :: json :: DeserializeOwned"),
+            "{}",
+            output
+        );
+        expand("enum Shape { Circle { radius: f64 } }").unwrap();
+        let e = expand("union U { a: u8 }").unwrap_err().to_string();
+        assert!(e.contains("can't be derived for unions"), "{}", e);
+    }
+}
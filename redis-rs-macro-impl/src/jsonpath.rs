@@ -0,0 +1,362 @@
+use crate::parse::{Arg, Command};
+
+/// Where the paths of a RedisJSON command are, counting from the key
+#[derive(Clone, Copy)]
+enum Paths {
+    /// The argument at this index, if the command has it
+    At(usize),
+    /// `JSON.GET`: every argument after the key and the `INDENT`, `NEWLINE` and `SPACE` options
+    Get,
+    /// `JSON.MGET`: the last argument, after the keys
+    Last,
+    /// `JSON.MSET`: the second argument of each `key path value` triple
+    Triples,
+    /// `JSON.STRAPPEND`: the argument after the key, when it is followed by the value
+    BeforeLast,
+    /// `JSON.DEBUG MEMORY`: the argument after the key
+    Debug,
+}
+
+/// The RedisJSON commands that take paths
+const COMMANDS: &[(&str, Paths)] = &[
+    ("JSON.ARRAPPEND", Paths::At(1)),
+    ("JSON.ARRINDEX", Paths::At(1)),
+    ("JSON.ARRINSERT", Paths::At(1)),
+    ("JSON.ARRLEN", Paths::At(1)),
+    ("JSON.ARRPOP", Paths::At(1)),
+    ("JSON.ARRTRIM", Paths::At(1)),
+    ("JSON.CLEAR", Paths::At(1)),
+    ("JSON.DEBUG", Paths::Debug),
+    ("JSON.DEL", Paths::At(1)),
+    ("JSON.FORGET", Paths::At(1)),
+    ("JSON.GET", Paths::Get),
+    ("JSON.MERGE", Paths::At(1)),
+    ("JSON.MGET", Paths::Last),
+    ("JSON.MSET", Paths::Triples),
+    ("JSON.NUMINCRBY", Paths::At(1)),
+    ("JSON.NUMMULTBY", Paths::At(1)),
+    ("JSON.OBJKEYS", Paths::At(1)),
+    ("JSON.OBJLEN", Paths::At(1)),
+    ("JSON.RESP", Paths::At(1)),
+    ("JSON.SET", Paths::At(1)),
+    ("JSON.STRAPPEND", Paths::BeforeLast),
+    ("JSON.STRLEN", Paths::At(1)),
+    ("JSON.TOGGLE", Paths::At(1)),
+    ("JSON.TYPE", Paths::At(1)),
+];
+
+/// Check the literal paths of RedisJSON commands, so that malformed JSONPaths and legacy paths
+/// fail to compile instead of with a syntax error from the server. Paths that are only known at
+/// runtime are skipped, and so are commands whose paths are hidden by a spread or optional
+/// argument before them.
+pub(crate) fn check_json_paths(command: &Command) -> syn::Result<()> {
+    let [name, args @ ..] = command.args.as_slice() else {
+        return Ok(());
+    };
+    let Some(paths) = name.word().and_then(|name| {
+        COMMANDS
+            .iter()
+            .find(|(command, _)| command.eq_ignore_ascii_case(&name))
+            .map(|(_, paths)| *paths)
+    }) else {
+        return Ok(());
+    };
+    let standalone = args.iter().position(|arg| {
+        arg.pieces
+            .iter()
+            .any(|piece| piece.standalone_kind().is_some())
+    });
+    let known = &args[..standalone.unwrap_or(args.len())];
+    let whole = standalone.is_none();
+    let paths: Vec<&Arg> = match paths {
+        Paths::At(index) => known.get(index).into_iter().collect(),
+        Paths::Get => {
+            let mut paths = vec![];
+            let mut rest = known.iter().skip(1);
+            while let Some(arg) = rest.next() {
+                let option = arg.word().is_some_and(|word| {
+                    ["INDENT", "NEWLINE", "SPACE"]
+                        .iter()
+                        .any(|option| option.eq_ignore_ascii_case(&word))
+                });
+                match option {
+                    true => drop(rest.next()),
+                    false => paths.push(arg),
+                }
+            }
+            paths
+        }
+        Paths::Last if whole && known.len() >= 2 => known.last().into_iter().collect(),
+        Paths::Triples if whole => known.iter().skip(1).step_by(3).collect(),
+        Paths::BeforeLast if whole && known.len() == 3 => vec![&known[1]],
+        Paths::Debug => {
+            let memory = known
+                .first()
+                .and_then(Arg::word)
+                .is_some_and(|sub| sub.eq_ignore_ascii_case("MEMORY"));
+            match memory {
+                true => known.get(2).into_iter().collect(),
+                false => vec![],
+            }
+        }
+        _ => vec![],
+    };
+    for arg in paths {
+        let Some(path) = arg.word() else {
+            continue;
+        };
+        if let Err(reason) = check_path(&path) {
+            let msg = format!("invalid JSON path `{}`: {}", path, reason);
+            return Err(syn::Error::new(arg.span, msg));
+        }
+    }
+    Ok(())
+}
+
+/// Check a JSONPath, which starts with `$`, or a legacy path, which starts with `.` or a member
+/// name and is relative to the root
+fn check_path(path: &str) -> Result<(), String> {
+    let rest = match path.strip_prefix('$') {
+        Some(rest) => rest,
+        None if path == "." => return Ok(()),
+        None if path.starts_with(['.', '[']) => path,
+        None => {
+            check_name(path.split(['.', '[']).next().unwrap_or_default())?;
+            &path[path.find(['.', '[']).unwrap_or(path.len())..]
+        }
+    };
+    let mut rest = rest;
+    while !rest.is_empty() {
+        if let Some(after) = rest.strip_prefix("..") {
+            if after.starts_with('[') {
+                rest = after;
+                continue;
+            }
+            rest = member(after, "`..`")?;
+        } else if let Some(after) = rest.strip_prefix('.') {
+            rest = member(after, "`.`")?;
+        } else if rest.starts_with('[') {
+            let end = closing_bracket(rest)?;
+            check_selectors(&rest[1..end])?;
+            rest = &rest[end + 1..];
+        } else {
+            let found = rest.chars().next().unwrap_or_default();
+            return Err(format!("expected `.`, `..` or `[` before `{}`", found));
+        }
+    }
+    Ok(())
+}
+
+/// Check the member name or `*` after `.` or `..`, and return what follows it
+fn member<'a>(rest: &'a str, after: &str) -> Result<&'a str, String> {
+    let end = rest.find(['.', '[']).unwrap_or(rest.len());
+    let name = &rest[..end];
+    if name.is_empty() {
+        return Err(format!("expected a member name or `*` after {}", after));
+    }
+    if name != "*" {
+        check_name(name)?;
+    }
+    Ok(&rest[end..])
+}
+
+fn check_name(name: &str) -> Result<(), String> {
+    if name.is_empty() {
+        return Err("expected a member name".to_owned());
+    }
+    match name
+        .chars()
+        .find(|c| c.is_whitespace() || "]()'\",*?".contains(*c))
+    {
+        Some(c) => Err(format!(
+            "`{}` can't be part of the member name `{}`; write it in brackets, as `['{}']`",
+            c, name, name
+        )),
+        None => Ok(()),
+    }
+}
+
+/// The index of the `]` that closes the `[` at the start of `rest`, skipping quoted strings and
+/// the parentheses of filters
+fn closing_bracket(rest: &str) -> Result<usize, String> {
+    let mut quote = None;
+    let mut depth = 0usize;
+    let mut chars = rest.char_indices().skip(1);
+    while let Some((i, c)) = chars.next() {
+        match (quote, c) {
+            (Some(_), '\\') => drop(chars.next()),
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '\'' | '"') => quote = Some(c),
+            (None, '(' | '[') => depth += 1,
+            (None, ')' | ']') if depth > 0 => depth -= 1,
+            (None, ']') => return Ok(i),
+            (None, ')') => return Err("unbalanced `)`".to_owned()),
+            _ => {}
+        }
+    }
+    match quote {
+        Some(q) => Err(format!("unclosed `{}`", q)),
+        None => Err("unclosed `[`".to_owned()),
+    }
+}
+
+/// Check the selectors inside brackets: a filter `?(...)`, or a comma-separated list of `*`,
+/// quoted names, indices and `start:end:step` slices
+fn check_selectors(inner: &str) -> Result<(), String> {
+    let inner = inner.trim();
+    if let Some(filter) = inner.strip_prefix('?') {
+        let filter = filter.trim();
+        let valid = filter.starts_with('(') && filter.ends_with(')') && filter.len() > 2;
+        return match valid {
+            true => Ok(()),
+            false => Err("expected a filter expression in parentheses after `?`".to_owned()),
+        };
+    }
+    if inner.is_empty() {
+        return Err("expected a selector inside `[]`".to_owned());
+    }
+    for selector in split_selectors(inner) {
+        let selector = selector.trim();
+        let quoted = selector.len() >= 2
+            && (selector.starts_with('\'') && selector.ends_with('\'')
+                || selector.starts_with('"') && selector.ends_with('"'));
+        if selector == "*" || quoted || is_slice(selector) {
+            continue;
+        }
+        return Err(format!(
+            "`{}` isn't a selector; expected `*`, a quoted name, an index or a `start:end` slice",
+            selector
+        ));
+    }
+    Ok(())
+}
+
+/// Split at the commas that aren't inside quotes
+fn split_selectors(inner: &str) -> Vec<&str> {
+    let mut parts = vec![];
+    let mut quote = None;
+    let mut start = 0;
+    for (i, c) in inner.char_indices() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (None, '\'' | '"') => quote = Some(c),
+            (None, ',') => {
+                parts.push(&inner[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(&inner[start..]);
+    parts
+}
+
+/// An index, such as `-1`, or a slice of up to three optional indices, such as `1:` or `::2`
+fn is_slice(selector: &str) -> bool {
+    let parts: Vec<_> = selector.split(':').collect();
+    let index = |part: &str| {
+        let part = part.trim();
+        let digits = part.strip_prefix('-').unwrap_or(part);
+        !digits.is_empty() && digits.chars().all(|c| c.is_ascii_digit())
+    };
+    match parts.as_slice() {
+        [single] => index(single),
+        parts if parts.len() <= 3 => parts
+            .iter()
+            .all(|part| part.trim().is_empty() || index(part)),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse::parse_command;
+    use proc_macro2::TokenStream;
+
+    fn check(input: &str) -> Result<(), String> {
+        let tokens: TokenStream = input.parse().unwrap();
+        check_json_paths(&parse_command(tokens).unwrap()).map_err(|err| err.to_string())
+    }
+
+    #[test]
+    fn json_path_valid() {
+        for path in [
+            "$",
+            ".",
+            "$.items[0].name",
+            "$..price",
+            "$..*",
+            "$..[0]",
+            "$.store.book[*].author",
+            "$['a b'][\"c\"]",
+            "$.a[-1]",
+            "$.a[0,2,'x']",
+            "$.a[1:3]",
+            "$.a[::2]",
+            "$.a[?(@.price < 10 && @.tags[0] == 'x')]",
+            ".items[0]",
+            "items.name",
+            "[0]",
+            "$.user-name",
+        ] {
+            assert_eq!(check_path(path), Ok(()), "{}", path);
+        }
+    }
+
+    #[test]
+    fn json_path_errors() {
+        for (path, error) in [
+            ("$.items[0.name]", "`0.name` isn't a selector"),
+            ("$.items[0", "unclosed `[`"),
+            ("$.a['b]", "unclosed `'`"),
+            ("$.", "expected a member name or `*` after `.`"),
+            ("$...a", "expected a member name or `*` after `..`"),
+            ("$a", "expected `.`, `..` or `[` before `a`"),
+            ("$.a[]", "expected a selector inside `[]`"),
+            ("$.a[?@.b]", "filter expression in parentheses"),
+            ("$.a[1:2:3:4]", "`1:2:3:4` isn't a selector"),
+            ("$.a)", "`)` can't be part of the member name `a)`"),
+            ("a..", "expected a member name or `*` after `..`"),
+        ] {
+            let e = check_path(path).unwrap_err();
+            assert!(e.contains(error), "{}: {}", path, e);
+        }
+    }
+
+    #[test]
+    fn json_path_commands() {
+        for input in [
+            "JSON.SET doc $ {value}",
+            "JSON.SET doc {path} {value}",
+            "JSON.GET doc INDENT \"\\t\" $.a .b",
+            "JSON.MGET a b $.x",
+            "JSON.MSET a $ 1 b $.x 2",
+            "JSON.STRAPPEND doc \"\\\"x\\\"\"",
+            "JSON.ARRPOP doc",
+            "JSON.DEBUG MEMORY doc $.a",
+            "JSON.MGET {..keys} \"$.x[\"",
+            "GET \"$.[\"",
+        ] {
+            assert_eq!(check(input), Ok(()), "{}", input);
+        }
+        // Unbalanced brackets can only be written in quotes
+        let e = check("JSON.SET doc \"$.a[\" 1").unwrap_err();
+        assert!(
+            e.contains("invalid JSON path `$.a[`: unclosed `[`"),
+            "{}",
+            e
+        );
+        let e = check("json.get doc SPACE \" \" $.a $.b.").unwrap_err();
+        assert!(e.contains("`$.b.`"), "{}", e);
+        let e = check("JSON.MGET a b $x").unwrap_err();
+        assert!(e.contains("`$x`"), "{}", e);
+        let e = check("JSON.MSET a $ 1 b $[] 2").unwrap_err();
+        assert!(e.contains("`$[]`"), "{}", e);
+        let e = check("JSON.STRAPPEND doc $.. x").unwrap_err();
+        assert!(e.contains("`$..`"), "{}", e);
+        let e = check("JSON.DEBUG MEMORY doc $.").unwrap_err();
+        assert!(e.contains("`$.`"), "{}", e);
+    }
+}
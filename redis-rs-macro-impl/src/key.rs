@@ -0,0 +1,221 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::{Expr, LitStr, Token};
+
+/// The environment variable that picks the naming convention of keys
+const CONVENTION_VAR: &str = "REDIS_KEY_CONVENTION";
+/// The environment variable that sets the separator between key segments
+const SEPARATOR_VAR: &str = "REDIS_KEY_SEPARATOR";
+
+/// The naming convention that the literal parts of keys follow
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Convention {
+    /// `user_orders`
+    SnakeCase,
+    /// `user-orders`
+    KebabCase,
+    /// `userOrders`
+    CamelCase,
+    /// Anything without whitespace
+    Any,
+}
+
+impl Convention {
+    fn from_name(name: &str) -> Option<Convention> {
+        match name {
+            "snake_case" => Some(Convention::SnakeCase),
+            "kebab-case" => Some(Convention::KebabCase),
+            "camelCase" => Some(Convention::CamelCase),
+            "any" => Some(Convention::Any),
+            _ => None,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Convention::SnakeCase => "snake_case",
+            Convention::KebabCase => "kebab-case",
+            Convention::CamelCase => "camelCase",
+            Convention::Any => "any",
+        }
+    }
+
+    /// Whether `c` is allowed in a segment, where `first` is set for the start of the segment
+    fn allows(&self, c: char, first: bool) -> bool {
+        match self {
+            Convention::SnakeCase => matches!(c, 'a'..='z' | '0'..='9' | '_'),
+            Convention::KebabCase => matches!(c, 'a'..='z' | '0'..='9' | '-'),
+            Convention::CamelCase if first => c.is_ascii_lowercase(),
+            Convention::CamelCase => c.is_ascii_alphanumeric(),
+            Convention::Any => !c.is_whitespace(),
+        }
+    }
+}
+
+/// How keys are checked, read from the environment when the macro runs
+struct KeyConfig {
+    convention: Convention,
+    separator: String,
+}
+
+impl KeyConfig {
+    fn from_env(span: proc_macro2::Span) -> syn::Result<KeyConfig> {
+        let convention = match std::env::var(CONVENTION_VAR) {
+            Ok(name) => Convention::from_name(&name).ok_or_else(|| {
+                let msg = format!(
+                    "`{}` must be one of `snake_case`, `kebab-case`, `camelCase` or `any`, \
+                    but is `{}`",
+                    CONVENTION_VAR, name
+                );
+                syn::Error::new(span, msg)
+            })?,
+            Err(_) => Convention::SnakeCase,
+        };
+        let separator = std::env::var(SEPARATOR_VAR).unwrap_or_else(|_| ":".to_string());
+        Ok(KeyConfig {
+            convention,
+            separator,
+        })
+    }
+}
+
+/// The input of `redis_key!`, e.g. `"user:{}:orders", id`
+struct KeyFormat {
+    format: LitStr,
+    args: Punctuated<Expr, Token![,]>,
+}
+
+impl Parse for KeyFormat {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let format = input.parse()?;
+        let args = if input.is_empty() {
+            Punctuated::new()
+        } else {
+            input.parse::<Token![,]>()?;
+            Punctuated::parse_terminated(input)?
+        };
+        Ok(KeyFormat { format, args })
+    }
+}
+
+/// Generate the `redis_rs_macro::Key` built by a `redis_key!` invocation
+pub(crate) fn expand_key(input: TokenStream) -> syn::Result<TokenStream> {
+    let KeyFormat { format, args } = syn::parse2(input)?;
+    let config = KeyConfig::from_env(format.span())?;
+    check_key(&format.value(), &config).map_err(|msg| syn::Error::new(format.span(), msg))?;
+    let args = args.iter();
+    Ok(quote! {
+        {
+            // Rebuild when the key convention changes
+            const _: ::core::option::Option<&str> = ::core::option_env!(#CONVENTION_VAR);
+            const _: ::core::option::Option<&str> = ::core::option_env!(#SEPARATOR_VAR);
+            ::redis_rs_macro::__private::key::new(::std::format!(#format #(, #args)*))
+        }
+    })
+}
+
+/// Check the literal parts of a key format against the naming convention. Placeholders may hold
+/// anything, so only the text around them is checked.
+fn check_key(format: &str, config: &KeyConfig) -> Result<(), String> {
+    if format.is_empty() {
+        return Err("keys cannot be empty".to_string());
+    }
+    for segment in format.split(config.separator.as_str()) {
+        let text = literal_text(segment);
+        if text.is_empty() && !segment.contains('{') {
+            let msg = format!(
+                "keys cannot have empty segments, such as a doubled `{}`",
+                config.separator
+            );
+            return Err(msg);
+        }
+        for (index, (c, placeholder_before)) in text.iter().enumerate() {
+            let first = index == 0 && !placeholder_before;
+            if !config.convention.allows(*c, first) {
+                let msg = format!(
+                    "the key segment `{}` doesn't follow the {} naming convention set by `{}`",
+                    segment,
+                    config.convention.name(),
+                    CONVENTION_VAR
+                );
+                return Err(msg);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The literal characters of a segment, each with whether a placeholder came right before it
+fn literal_text(segment: &str) -> Vec<(char, bool)> {
+    let mut text = vec![];
+    let mut chars = segment.chars().peekable();
+    let mut after_placeholder = false;
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                text.push(('{', after_placeholder));
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                text.push(('}', after_placeholder));
+            }
+            '{' => {
+                chars.by_ref().find(|c| *c == '}');
+                after_placeholder = true;
+                continue;
+            }
+            c => text.push((c, after_placeholder)),
+        }
+        after_placeholder = false;
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(format: &str, convention: Convention) -> Result<(), String> {
+        let config = KeyConfig {
+            convention,
+            separator: ":".to_string(),
+        };
+        check_key(format, &config)
+    }
+
+    #[test]
+    fn key_conventions() {
+        assert!(check("user:{}:orders", Convention::SnakeCase).is_ok());
+        assert!(check("user_orders:{id}", Convention::SnakeCase).is_ok());
+        assert!(check("user:{}_{}", Convention::SnakeCase).is_ok());
+        assert!(check("userOrders:{}", Convention::SnakeCase).is_err());
+        assert!(check("user-orders:{}", Convention::KebabCase).is_ok());
+        assert!(check("user_orders", Convention::KebabCase).is_err());
+        assert!(check("userOrders:{}", Convention::CamelCase).is_ok());
+        assert!(check("UserOrders", Convention::CamelCase).is_err());
+        assert!(check("{}Orders", Convention::CamelCase).is_ok());
+        assert!(check("Any.Thing:{}", Convention::Any).is_ok());
+        assert!(check("has space", Convention::Any).is_err());
+    }
+
+    #[test]
+    fn key_segments() {
+        assert!(check("", Convention::SnakeCase)
+            .unwrap_err()
+            .contains("cannot be empty"));
+        assert!(check("user::orders", Convention::SnakeCase)
+            .unwrap_err()
+            .contains("empty segments"));
+        assert!(check("user:{}", Convention::SnakeCase).is_ok());
+        assert!(check("{{user}}", Convention::SnakeCase).is_err());
+        let config = KeyConfig {
+            convention: Convention::SnakeCase,
+            separator: "/".to_string(),
+        };
+        assert!(check_key("user/{}/orders", &config).is_ok());
+        assert!(check_key("user:{}", &config).is_err());
+    }
+}
@@ -0,0 +1,329 @@
+use proc_macro2::{Delimiter, Ident, Span, TokenStream, TokenTree};
+use quote::quote;
+use syn::parse::{Parse, ParseStream, Parser};
+use syn::punctuated::Punctuated;
+use syn::{Expr, Token};
+
+/// The events of keyspace notifications, as named in their `__keyevent@<db>__:<event>` channels
+const EVENTS: &[&str] = &[
+    "del",
+    "expire",
+    "rename_from",
+    "rename_to",
+    "move_from",
+    "move_to",
+    "copy_to",
+    "restore",
+    "persist",
+    "sortstore",
+    "set",
+    "setrange",
+    "incrby",
+    "incrbyfloat",
+    "append",
+    "lpush",
+    "rpush",
+    "lpop",
+    "rpop",
+    "linsert",
+    "lset",
+    "lrem",
+    "ltrim",
+    "sadd",
+    "srem",
+    "spop",
+    "sinterstore",
+    "sunionstore",
+    "sdiffstore",
+    "hset",
+    "hincrby",
+    "hincrbyfloat",
+    "hdel",
+    "zincr",
+    "zadd",
+    "zrem",
+    "zrembyscore",
+    "zrembyrank",
+    "zdiffstore",
+    "zinterstore",
+    "zunionstore",
+    "xadd",
+    "xdel",
+    "xgroup-create",
+    "xgroup-createconsumer",
+    "xgroup-delconsumer",
+    "xgroup-destroy",
+    "xgroup-setid",
+    "xsetid",
+    "xtrim",
+    "expired",
+    "evicted",
+    "keymiss",
+    "new",
+];
+
+/// The classes of `notify-keyspace-events`, as written in `redis_config_notify!`, with their flags
+const CLASSES: &[(&str, char)] = &[
+    ("Keyspace", 'K'),
+    ("Keyevent", 'E'),
+    ("Generic", 'g'),
+    ("String", '$'),
+    ("List", 'l'),
+    ("Set", 's'),
+    ("Hash", 'h'),
+    ("SortedSet", 'z'),
+    ("Expired", 'x'),
+    ("Evicted", 'e'),
+    ("Stream", 't'),
+    ("Module", 'd'),
+    ("KeyMiss", 'm'),
+    ("New", 'n'),
+    ("All", 'A'),
+];
+
+/// The flags that the `A` flag stands for
+const ALL_FLAGS: &str = "g$lshzxetd";
+
+/// The input of `redis_keyevents!`, e.g. `con, 0, [expired, del], |event| ..`
+struct KeyEventsInput {
+    con: Expr,
+    db: Expr,
+    events: Vec<(String, Span)>,
+    handler: Expr,
+}
+
+impl Parse for KeyEventsInput {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let con = input.parse()?;
+        input.parse::<Token![,]>()?;
+        let db = input.parse()?;
+        input.parse::<Token![,]>()?;
+        let events = match input.parse::<TokenTree>()? {
+            TokenTree::Group(group) if group.delimiter() == Delimiter::Bracket => {
+                parse_events(group.stream())?
+            }
+            other => {
+                let msg = "expected a list of events, such as `[expired, del]`";
+                return Err(syn::Error::new(other.span(), msg));
+            }
+        };
+        input.parse::<Token![,]>()?;
+        let handler = input.parse()?;
+        input.parse::<Option<Token![,]>>()?;
+        Ok(KeyEventsInput {
+            con,
+            db,
+            events,
+            handler,
+        })
+    }
+}
+
+/// Read the comma separated event names, which may contain `-` as in `xgroup-create`
+fn parse_events(input: TokenStream) -> syn::Result<Vec<(String, Span)>> {
+    let tokens: Vec<TokenTree> = input.into_iter().collect();
+    let events = tokens
+        .split(|tt| matches!(tt, TokenTree::Punct(p) if p.as_char() == ','))
+        .filter(|tokens| !tokens.is_empty())
+        .map(parse_event)
+        .collect::<syn::Result<Vec<_>>>()?;
+    if events.is_empty() {
+        return Err(syn::Error::new(
+            Span::call_site(),
+            "expected at least one event",
+        ));
+    }
+    Ok(events)
+}
+
+fn parse_event(tokens: &[TokenTree]) -> syn::Result<(String, Span)> {
+    let mut name = String::new();
+    for (index, tt) in tokens.iter().enumerate() {
+        match tt {
+            TokenTree::Ident(ident) => name.push_str(&ident.to_string()),
+            TokenTree::Punct(p) if p.as_char() == '-' && index > 0 => name.push('-'),
+            other => return Err(syn::Error::new(other.span(), "expected an event name")),
+        }
+    }
+    let span = tokens[0].span();
+    if !EVENTS.contains(&name.as_str()) {
+        let msg = format!("`{}` is not an event of keyspace notifications", name);
+        return Err(syn::Error::new(span, msg));
+    }
+    Ok((name, span))
+}
+
+/// The `KeyEventKind` variant of an event, e.g. `XgroupCreate` for `xgroup-create`
+fn variant(name: &str, span: Span) -> Ident {
+    let variant: String = name
+        .split(['-', '_'])
+        .flat_map(|word| {
+            let mut chars = word.chars();
+            chars
+                .next()
+                .map(|c| c.to_ascii_uppercase())
+                .into_iter()
+                .chain(chars)
+        })
+        .collect();
+    Ident::new(&variant, span)
+}
+
+/// Generate a `redis_keyevents!` invocation, which enables the events on the server and passes
+/// them to the handler
+pub(crate) fn expand_keyevents(input: TokenStream) -> syn::Result<TokenStream> {
+    let KeyEventsInput {
+        con,
+        db,
+        events,
+        handler,
+    } = syn::parse2(input)?;
+    let kinds = events.iter().map(|(name, span)| {
+        let variant = variant(name, *span);
+        quote!(::redis_rs_macro::KeyEventKind::#variant)
+    });
+    Ok(quote! {
+        {
+            let events = ::redis_rs_macro::KeyEvents::new(#db, &[#(#kinds),*]);
+            let con: &mut redis::Connection = #con;
+            events
+                .enable(con)
+                .and_then(|()| events.run(con, #handler))
+        }
+    })
+}
+
+/// The `notify-keyspace-events` flags of a list of classes, in the order of the table. Keyevent
+/// events are enabled when neither they nor keyspace events are given, since no events are sent
+/// without one of them.
+fn notify_flags(classes: &[Ident]) -> syn::Result<String> {
+    let mut flags = vec![];
+    for class in classes {
+        let Some((_, flag)) = CLASSES.iter().find(|(name, _)| class == name) else {
+            let names: Vec<_> = CLASSES.iter().map(|(name, _)| *name).collect();
+            let msg = format!(
+                "`{}` is not a class of keyspace notifications, expected one of {}",
+                class,
+                names.join(", ")
+            );
+            return Err(syn::Error::new(class.span(), msg));
+        };
+        if flags.contains(flag) {
+            let msg = format!("`{}` is given twice", class);
+            return Err(syn::Error::new(class.span(), msg));
+        }
+        // `All` stands for every class except key misses and new keys
+        let covered = |flag: &&char| ALL_FLAGS.contains(**flag);
+        let overlap = match flag {
+            'A' => flags.iter().find(covered),
+            _ if flags.contains(&'A') && covered(&flag) => Some(flag),
+            _ => None,
+        };
+        if let Some(overlap) = overlap {
+            let (name, _) = CLASSES.iter().find(|(_, flag)| flag == overlap).unwrap();
+            let msg = format!(
+                "`All` includes every class except `KeyMiss` and `New`, so it can't be given \
+                 with `{}`",
+                name
+            );
+            return Err(syn::Error::new(class.span(), msg));
+        }
+        flags.push(*flag);
+    }
+    if !flags.is_empty() && !flags.contains(&'K') && !flags.contains(&'E') {
+        flags.push('E');
+    }
+    Ok(CLASSES
+        .iter()
+        .map(|(_, flag)| *flag)
+        .filter(|flag| flags.contains(flag))
+        .collect())
+}
+
+/// Generate a `redis_config_notify!` invocation, the `CONFIG SET notify-keyspace-events` command
+/// of the classes
+pub(crate) fn expand_config_notify(input: TokenStream) -> syn::Result<TokenStream> {
+    let classes = Punctuated::<Ident, Token![,]>::parse_terminated.parse2(input)?;
+    let classes: Vec<Ident> = classes.into_iter().collect();
+    let flags = notify_flags(&classes)?;
+    Ok(quote! {
+        redis::cmd("CONFIG")
+            .arg("SET")
+            .arg("notify-keyspace-events")
+            .arg(#flags)
+            .clone()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expand(input: &str) -> syn::Result<String> {
+        expand_keyevents(input.parse().unwrap()).map(|output| output.to_string())
+    }
+
+    #[test]
+    fn keyevents_variants() {
+        let span = Span::call_site();
+        assert_eq!(variant("expired", span), "Expired");
+        assert_eq!(variant("rename_from", span), "RenameFrom");
+        assert_eq!(
+            variant("xgroup-createconsumer", span),
+            "XgroupCreateconsumer"
+        );
+    }
+
+    #[test]
+    fn keyevents_expand() {
+        let output = expand("con, 0, [expired, xgroup-create,], |e| {}").unwrap();
+        assert!(
+            output.contains(
+                "KeyEvents :: new (0 , & [:: redis_rs_macro :: KeyEventKind :: Expired , \
+                 :: redis_rs_macro :: KeyEventKind :: XgroupCreate])"
+            ),
+            "{}",
+            output
+        );
+    }
+
+    #[test]
+    fn keyevents_errors() {
+        let err = expand("con, 0, [expird], |e| {}").unwrap_err().to_string();
+        assert!(err.contains("`expird` is not an event"), "{}", err);
+        let err = expand("con, 0, [], |e| {}").unwrap_err().to_string();
+        assert!(err.contains("at least one event"), "{}", err);
+        let err = expand("con, 0, (del), |e| {}").unwrap_err().to_string();
+        assert!(err.contains("expected a list of events"), "{}", err);
+        let err = expand("con, 0, [\"del\"], |e| {}").unwrap_err().to_string();
+        assert!(err.contains("expected an event name"), "{}", err);
+    }
+
+    fn flags(input: &str) -> syn::Result<String> {
+        let classes = Punctuated::<Ident, Token![,]>::parse_terminated
+            .parse_str(input)
+            .unwrap();
+        notify_flags(&classes.into_iter().collect::<Vec<_>>())
+    }
+
+    #[test]
+    fn config_notify_flags() {
+        assert_eq!(flags("Expired, Evicted, Set").unwrap(), "Esxe");
+        assert_eq!(flags("Keyspace, Generic, String").unwrap(), "Kg$");
+        assert_eq!(flags("All, Keyevent, Keyspace, KeyMiss").unwrap(), "KEmA");
+        assert_eq!(flags("").unwrap(), "");
+    }
+
+    #[test]
+    fn config_notify_errors() {
+        let err = |input: &str| flags(input).unwrap_err().to_string();
+        let e = err("Expired, expired");
+        assert!(e.contains("`expired` is not a class"), "{}", e);
+        let e = err("Set, Set");
+        assert!(e.contains("given twice"), "{}", e);
+        let e = err("All, Hash");
+        assert!(e.contains("can't be given with `Hash`"), "{}", e);
+        let e = err("List, All");
+        assert!(e.contains("can't be given with `List`"), "{}", e);
+    }
+}
@@ -0,0 +1,260 @@
+use crate::commands::{self, Keys};
+use crate::parse::{Arg, Command, Piece};
+
+/// How an argument relates to the keys of its command
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum KeyRole {
+    /// Not a key
+    None,
+    /// A key. For spreads, optional arguments and conditional groups, every argument they expand
+    /// into is a key.
+    Key,
+    /// A spread of `(key, value)` pairs, such as the arguments of `MSET`
+    Pairs,
+    /// Spreads or optional arguments before it make its position unknown
+    Unknown,
+}
+
+/// Where an argument may end up, given the arguments around it
+struct Position {
+    /// The position counted from the command name, if every argument before it is a single one
+    start: Option<usize>,
+    /// The lowest position it can have, since arguments with a varying length may be empty
+    min_start: usize,
+    /// The position counted from the end (`-1` for the last argument), if every argument after it
+    /// is a single one
+    end: Option<isize>,
+    /// The position of a spread over pairs, counted as if earlier spreads were also over pairs
+    pairs_start: Option<usize>,
+    /// The highest position it can have counted from the end
+    max_end: isize,
+    single: bool,
+    spread: bool,
+}
+
+/// Work out which arguments of a command are keys, with one role for every argument including the
+/// command name. Arguments of commands missing from the command table are never keys.
+pub(crate) fn key_roles(command: &Command) -> Vec<KeyRole> {
+    let args = &command.args;
+    let mut roles = vec![KeyRole::None; args.len()];
+    let Some(info) = commands::lookup(args) else {
+        return roles;
+    };
+    let positions = positions(args);
+    for keys in info.keys {
+        for (role, found) in roles.iter_mut().zip(find_keys(*keys, args, &positions)) {
+            if *role == KeyRole::None {
+                *role = found;
+            }
+        }
+    }
+    roles
+}
+
+/// Whether an argument is always a single argument of the command
+pub(crate) fn is_single(arg: &Arg) -> bool {
+    !arg.pieces
+        .iter()
+        .any(|piece| piece.standalone_kind().is_some())
+}
+
+fn is_spread(arg: &Arg) -> bool {
+    matches!(arg.pieces.as_slice(), [Piece::Spread(_)])
+}
+
+fn positions(args: &[Arg]) -> Vec<Position> {
+    let single: Vec<_> = args.iter().map(is_single).collect();
+    (0..args.len())
+        .map(|index| Position {
+            start: single[..index].iter().all(|s| *s).then_some(index),
+            min_start: single[..index].iter().filter(|s| **s).count(),
+            end: single[index + 1..]
+                .iter()
+                .all(|s| *s)
+                .then(|| index as isize - args.len() as isize),
+            pairs_start: args[..index]
+                .iter()
+                .zip(&single)
+                .all(|(arg, single)| *single || is_spread(arg))
+                .then(|| single[..index].iter().filter(|s| **s).count()),
+            max_end: -1 - single[index + 1..].iter().filter(|s| **s).count() as isize,
+            single: single[index],
+            spread: is_spread(&args[index]),
+        })
+        .collect()
+}
+
+fn find_keys(keys: Keys, args: &[Arg], positions: &[Position]) -> Vec<KeyRole> {
+    match keys {
+        Keys::Range { first, last, step } => positions
+            .iter()
+            .map(|position| range_role(position, first, last, step))
+            .collect(),
+        Keys::Count { index } => count_roles(args, positions, index),
+        Keys::Streams => streams_roles(args, positions),
+    }
+}
+
+fn range_role(position: &Position, first: usize, last: isize, step: usize) -> KeyRole {
+    let aligned = |start: usize| (start - first).is_multiple_of(step);
+    if let Ok(last) = usize::try_from(last) {
+        return match position.start {
+            _ if position.min_start > last => KeyRole::None,
+            Some(start) if position.single => {
+                if start >= first && start <= last && aligned(start) {
+                    KeyRole::Key
+                } else {
+                    KeyRole::None
+                }
+            }
+            _ => KeyRole::Unknown,
+        };
+    }
+    let outside = position.start.is_some_and(|start| start < first)
+        || position.end.is_some_and(|end| end > last);
+    if outside && position.single {
+        return KeyRole::None;
+    }
+    if position.min_start < first || position.max_end > last {
+        return KeyRole::Unknown;
+    }
+    match position.start {
+        _ if step == 1 => KeyRole::Key,
+        Some(start) if position.single => {
+            if aligned(start) {
+                KeyRole::Key
+            } else {
+                KeyRole::None
+            }
+        }
+        _ => match position.pairs_start {
+            // Items of a spread over key and value pairs are the pairs themselves
+            Some(start) if aligned(start) && step == 2 && position.spread => KeyRole::Pairs,
+            _ => KeyRole::Unknown,
+        },
+    }
+}
+
+/// Keys that follow a count, such as `EVAL script 2 key1 key2 arg`
+fn count_roles(args: &[Arg], positions: &[Position], index: usize) -> Vec<KeyRole> {
+    let count = args
+        .get(index)
+        .filter(|_| positions[index].start.is_some())
+        .and_then(Arg::word)
+        .and_then(|word| word.parse::<usize>().ok());
+    positions
+        .iter()
+        .map(|position| match (count, position.start) {
+            _ if position.start.is_some_and(|start| start <= index) => KeyRole::None,
+            (Some(count), _) if position.min_start > index + count => KeyRole::None,
+            (Some(count), Some(start)) if position.single => {
+                if start <= index + count {
+                    KeyRole::Key
+                } else {
+                    KeyRole::None
+                }
+            }
+            _ => KeyRole::Unknown,
+        })
+        .collect()
+}
+
+/// Keys that make up the first half of the arguments after `STREAMS`
+fn streams_roles(args: &[Arg], positions: &[Position]) -> Vec<KeyRole> {
+    let streams = args.iter().position(|arg| {
+        arg.word()
+            .is_some_and(|word| word.eq_ignore_ascii_case("STREAMS"))
+    });
+    // Without `STREAMS`, the command has no keys for the server to read either
+    let Some(streams) = streams else {
+        return vec![KeyRole::None; args.len()];
+    };
+    let rest = &positions[streams + 1..];
+    let known = rest.iter().all(|position| position.single) && rest.len().is_multiple_of(2);
+    (0..args.len())
+        .map(|index| match index {
+            _ if index <= streams => KeyRole::None,
+            _ if !known => KeyRole::Unknown,
+            _ if index <= streams + rest.len() / 2 => KeyRole::Key,
+            _ => KeyRole::None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse::parse_command;
+    use proc_macro2::TokenStream;
+    use KeyRole::*;
+
+    fn roles(input: &str) -> Vec<KeyRole> {
+        let tokens: TokenStream = input.parse().unwrap();
+        key_roles(&parse_command(tokens).unwrap())
+    }
+
+    #[test]
+    fn keys_fixed() {
+        assert_eq!(roles("GET k"), [None, Key]);
+        assert_eq!(
+            roles("SET user:{id} {v} EX {ttl?}"),
+            [None, Key, None, None]
+        );
+        assert_eq!(roles("RENAME a b"), [None, Key, Key]);
+        assert_eq!(roles("XGROUP CREATE s g $"), [None, None, Key, None, None]);
+        assert_eq!(roles("PING"), [None]);
+        assert_eq!(roles("{..args} k"), [None, None]);
+    }
+
+    #[test]
+    fn keys_to_end() {
+        assert_eq!(roles("DEL a {..keys} b"), [None, Key, Key, Key]);
+        assert_eq!(roles("DEL a {b?} ?[{c} => d]"), [None, Key, Key, Key]);
+        assert_eq!(roles("BLPOP a {..keys} 0"), [None, Key, Key, None]);
+        assert_eq!(roles("BITOP AND d a b"), [None, None, Key, Key, Key]);
+        assert_eq!(roles("MSET a 1 b 2"), [None, Key, None, Key, None]);
+        assert_eq!(roles("MSET a 1 {..pairs}"), [None, Key, None, Pairs]);
+        assert_eq!(roles("MSET {..a} {..b}"), [None, Pairs, Pairs]);
+    }
+
+    #[test]
+    fn keys_counted() {
+        assert_eq!(
+            roles("EVAL {script} 2 a b c"),
+            [None, None, None, Key, Key, None]
+        );
+        assert_eq!(
+            roles("EVAL {script} 1 a {..args}"),
+            [None, None, None, Key, None]
+        );
+        assert_eq!(
+            roles("ZUNIONSTORE d 2 a b WEIGHTS 1 2"),
+            [None, Key, None, Key, Key, None, None, None]
+        );
+        assert_eq!(roles("EVAL {script} {n} a"), [None, None, None, Unknown]);
+    }
+
+    #[test]
+    fn keys_streams() {
+        assert_eq!(
+            roles("XREAD COUNT 2 STREAMS a b 0 0"),
+            [None, None, None, None, Key, Key, None, None]
+        );
+        assert_eq!(
+            roles("XREAD COUNT {n?} STREAMS a 0"),
+            [None, None, None, Key, None]
+        );
+        assert_eq!(
+            roles("XREAD STREAMS {..keys} {..ids}"),
+            [None, None, Unknown, Unknown]
+        );
+    }
+
+    #[test]
+    fn keys_unknown() {
+        assert_eq!(roles("GET {..k}"), [None, Unknown]);
+        assert_eq!(roles("MSET {..pairs} a 1"), [None, Pairs, Unknown, Unknown]);
+        assert_eq!(roles("MSET a 1 ?[{c} => b 2]"), [None, Key, None, Unknown]);
+        assert_eq!(roles("RENAME {a?} b"), [None, Unknown, Unknown]);
+    }
+}
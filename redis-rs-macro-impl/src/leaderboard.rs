@@ -0,0 +1,214 @@
+use crate::lock::expand_key;
+use crate::parse::parse_args;
+use crate::template::collect_params;
+use proc_macro2::{TokenStream, TokenTree};
+use quote::quote;
+use syn::parse::{ParseStream, Parser};
+use syn::{Attribute, Ident, Token, Type, Visibility};
+
+/// A leaderboard declared in `redis_leaderboard!`, e.g.
+/// `pub struct Scores(String) = leaderboard:{game}, asc;`
+struct LeaderboardDef {
+    attrs: Vec<Attribute>,
+    vis: Visibility,
+    name: Ident,
+    member: Type,
+    key: TokenStream,
+    ascending: bool,
+}
+
+fn parse_def(input: ParseStream) -> syn::Result<LeaderboardDef> {
+    let attrs = input.call(Attribute::parse_outer)?;
+    let vis = input.parse()?;
+    input.parse::<Token![struct]>()?;
+    let name = input.parse()?;
+    let content;
+    syn::parenthesized!(content in input);
+    let member = content.parse()?;
+    input.parse::<Token![=]>()?;
+    let mut key = TokenStream::new();
+    while !input.is_empty() && !input.peek(Token![,]) && !input.peek(Token![;]) {
+        key.extend([input.parse::<TokenTree>()?]);
+    }
+    let mut order: Option<Ident> = None;
+    while input.parse::<Option<Token![,]>>()?.is_some() {
+        let name: Ident = input.parse()?;
+        if name != "asc" && name != "desc" {
+            return Err(syn::Error::new(name.span(), "expected `asc` or `desc`"));
+        }
+        if order.is_some() {
+            return Err(syn::Error::new(name.span(), "the order is given twice"));
+        }
+        order = Some(name);
+    }
+    input.parse::<Token![;]>()?;
+    Ok(LeaderboardDef {
+        attrs,
+        vis,
+        name,
+        member,
+        key,
+        ascending: order.is_some_and(|order| order == "asc"),
+    })
+}
+
+/// Generate the leaderboards of a `redis_leaderboard!` invocation, each a struct that is built
+/// from the substitutions of its key and has typed leaderboard commands
+pub(crate) fn expand_leaderboard(input: TokenStream) -> syn::Result<TokenStream> {
+    let parser = |input: ParseStream| {
+        let mut defs = vec![];
+        while !input.is_empty() {
+            defs.push(parse_def(input)?);
+        }
+        Ok(defs)
+    };
+    let mut output = TokenStream::new();
+    for def in parser.parse2(input)? {
+        output.extend(expand_def(def)?);
+    }
+    Ok(output)
+}
+
+fn expand_def(def: LeaderboardDef) -> syn::Result<TokenStream> {
+    let LeaderboardDef {
+        attrs,
+        vis,
+        name,
+        member,
+        key,
+        ascending,
+    } = def;
+    // The substitutions of the key are the parameters of `new`, as with `redis_template!`
+    let command = parse_args(key.clone(), 0)?;
+    let mut params = vec![];
+    collect_params(&command.args, &mut params)?;
+    let params = params.iter().map(|(name, kind)| {
+        let ty = kind.ty();
+        quote!(#name: #ty)
+    });
+    let key = expand_key(key)?;
+    let order = match ascending {
+        true => quote!(::redis_rs_macro::Order::Ascending),
+        false => quote!(::redis_rs_macro::Order::Descending),
+    };
+    Ok(quote! {
+        #(#attrs)*
+        #vis struct #name(::redis_rs_macro::Leaderboard);
+
+        impl #name {
+            /// The leaderboard at the key built from the given values
+            pub fn new(#(#params),*) -> Self {
+                Self(::redis_rs_macro::Leaderboard::new(#key, #order))
+            }
+
+            /// The untyped leaderboard, with its commands
+            pub fn leaderboard(&self) -> &::redis_rs_macro::Leaderboard {
+                &self.0
+            }
+
+            /// Set the score of a member with `ZADD`
+            pub fn add(
+                &self,
+                con: &mut dyn redis::ConnectionLike,
+                member: &#member,
+                score: f64,
+            ) -> redis::RedisResult<()> {
+                self.0.add(con, member, score)
+            }
+
+            /// The best `n` members with their scores, best first
+            pub fn top(
+                &self,
+                con: &mut dyn redis::ConnectionLike,
+                n: usize,
+            ) -> redis::RedisResult<::std::vec::Vec<(#member, f64)>> {
+                self.0.top(con, n)
+            }
+
+            /// The rank of a member, where the best member is 0, or `None` if it isn't on the
+            /// leaderboard
+            pub fn rank(
+                &self,
+                con: &mut dyn redis::ConnectionLike,
+                member: &#member,
+            ) -> redis::RedisResult<::core::option::Option<u64>> {
+                self.0.rank(con, member)
+            }
+
+            /// The score of a member, or `None` if it isn't on the leaderboard
+            pub fn score(
+                &self,
+                con: &mut dyn redis::ConnectionLike,
+                member: &#member,
+            ) -> redis::RedisResult<::core::option::Option<f64>> {
+                self.0.score(con, member)
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expand(input: &str) -> syn::Result<String> {
+        expand_leaderboard(input.parse().unwrap()).map(|output| output.to_string())
+    }
+
+    #[test]
+    fn leaderboard_expand() {
+        let output = expand(
+            "/// Points\npub struct Points(String) = leaderboard:{game}:{season};\n\
+             struct Times(u64) = times, asc;",
+        )
+        .unwrap();
+        assert!(
+            output.starts_with(
+                "# [doc = \" Points\"] pub struct Points (:: redis_rs_macro :: Leaderboard) ;"
+            ),
+            "{}",
+            output
+        );
+        assert!(
+            output.contains(
+                "pub fn new (game : impl redis :: ToRedisArgs , season : impl redis :: ToRedisArgs) -> Self"
+            ),
+            "{}",
+            output
+        );
+        assert!(
+            output.contains(", :: redis_rs_macro :: Order :: Descending)"),
+            "{}",
+            output
+        );
+        assert!(
+            output.contains("struct Times (:: redis_rs_macro :: Leaderboard) ;"),
+            "{}",
+            output
+        );
+        assert!(output.contains("pub fn new () -> Self"), "{}", output);
+        assert!(
+            output.contains(", :: redis_rs_macro :: Order :: Ascending)"),
+            "{}",
+            output
+        );
+        assert!(
+            output.contains("-> redis :: RedisResult < :: std :: vec :: Vec < (u64 , f64) >>"),
+            "{}",
+            output
+        );
+    }
+
+    #[test]
+    fn leaderboard_errors() {
+        let err = |input: &str| expand(input).unwrap_err().to_string();
+        let e = err("struct B(String) = a b;");
+        assert!(e.contains("single key"), "{}", e);
+        let e = err("struct B(String) = board:{id + 1};");
+        assert!(e.contains("a single parameter name"), "{}", e);
+        let e = err("struct B(String) = board, top;");
+        assert!(e.contains("expected `asc` or `desc`"), "{}", e);
+        let e = err("struct B(String) = board, asc, desc;");
+        assert!(e.contains("order is given twice"), "{}", e);
+    }
+}
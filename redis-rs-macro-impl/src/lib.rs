@@ -0,0 +1,3557 @@
+use proc_macro::TokenStream;
+
+mod acl;
+mod arg;
+mod batch;
+mod bind;
+mod bitfield;
+mod bloom;
+mod cache;
+mod client;
+mod commands;
+mod consume;
+mod counter;
+mod def;
+mod delay;
+mod entry;
+mod eval;
+mod exec;
+mod expand;
+mod functions;
+mod geo;
+mod hash;
+mod idempotency;
+mod info;
+mod integration;
+mod json;
+mod jsonpath;
+mod key;
+mod keyevents;
+mod keys;
+mod leaderboard;
+mod lock;
+mod lua;
+mod marker;
+mod meta;
+mod mock;
+mod namespace;
+mod parse;
+mod pipe;
+mod priority;
+mod publish;
+mod queue;
+mod ratelimit;
+mod readonly;
+mod reply;
+mod retry;
+mod route;
+mod scan;
+mod schema;
+mod scope;
+mod script;
+mod search;
+mod sentinel;
+mod slot;
+mod slowlog;
+mod subscribe;
+mod template;
+mod time;
+mod timeseries;
+mod trait_commands;
+mod transaction;
+mod typed;
+mod url;
+
+/// Generate a redis::cmd object using syntax as if from redis-cli
+///
+/// # Examples
+/// ## Writing a command
+/// The most basic usage of the macro is as seen below.
+/// ```rust
+/// use redis_rs_macro::redis;
+/// redis!(SET my_key my_value 1);
+/// ```
+/// In the above example, my_key, my_value, and 1, are all passed into the .arg function of
+/// redis::cmd as if they were literal strings. The macro evaluates to an owned `redis::Cmd`.
+/// ## Expansion
+/// ```rust
+/// redis::cmd("SET").arg("my_key").arg("my_value").arg("1");
+/// ```
+/// ## Command Names
+/// Command names are case insensitive, and are normalized to uppercase. Quote the name to pass it
+/// through exactly as written, e.g. for case sensitive custom commands.
+/// ```rust
+/// use redis_rs_macro::redis;
+/// redis!(set my_key my_value);
+/// redis!("my.Command" my_key);
+/// ```
+/// ## Expansion
+/// ```rust
+/// redis::cmd("SET").arg("my_key").arg("my_value");
+/// redis::cmd("my.Command").arg("my_key");
+/// ```
+/// ## Dynamic Command Names
+/// A command name taken from a value could send any command if it comes from user input, so it
+/// has to be marked with `raw`. A plain `{expr}` as the command name is a compile error.
+/// ```rust
+/// use redis_rs_macro::redis;
+/// let name = "GET";
+/// redis!({raw name} my_key);
+/// ```
+/// ## Expansion
+/// ```rust
+/// let name = "GET";
+/// redis::cmd(name).arg("my_key");
+/// ```
+/// ## Quoting
+/// If any of the above arguments contain whitespace, but should be treated as a single argument,
+/// use double quotes to capture the entire sequence.
+/// ```rust
+/// use redis_rs_macro::redis;
+/// redis!(SET "my key" my_value 1);
+/// ```
+/// ## Expansion
+/// ```rust
+/// redis::cmd("SET").arg("my key").arg("my_value").arg("1");
+/// ```
+/// ## Numbers
+/// Numbers written with a base prefix, underscores, or a type suffix are normalized to decimal,
+/// so they reach redis with the value they have in Rust. Other numbers are passed as written,
+/// keeping leading zeros.
+/// ```rust
+/// use redis_rs_macro::redis;
+/// redis!(SETBIT my_key 0x1F 1);
+/// redis!(SET my_key 1_000_000);
+/// ```
+/// ## Expansion
+/// ```rust
+/// redis::cmd("SETBIT").arg("my_key").arg("31").arg("1");
+/// redis::cmd("SET").arg("my_key").arg("1000000");
+/// ```
+/// ## Byte Strings
+/// Byte string literals are passed as binary arguments, which is handy for binary fixtures.
+/// ```rust
+/// use redis_rs_macro::redis;
+/// redis!(SET my_key b"\x00\x01\x02");
+/// ```
+/// ## Expansion
+/// ```rust
+/// redis::cmd("SET").arg("my_key").arg(&b"\x00\x01\x02"[..]);
+/// ```
+/// ## Substitution
+/// You can also substitue Rust expressions into .arg or cmd constructor if
+/// you have dynamic data. This is done by enclosing the expression in curly braces.
+/// ```rust
+/// use redis_rs_macro::redis;
+/// let x = 1;
+/// redis!(SET my_key my_value {x});
+/// ```
+/// ## Expansion
+/// ```rust
+/// let x = 1;
+/// redis::cmd("SET").arg("my_key").arg("my_value").arg(x);
+/// ```
+/// ## Format Specs
+/// A substitution can end in a `:` followed by a format spec, which formats the value with
+/// `format!` instead of converting it with `redis::ToRedisArgs`. This controls the precision
+/// of scores and prices, or pads ids, without a separate variable.
+/// ```rust
+/// use redis_rs_macro::redis;
+/// let price = 12.5;
+/// let id = 42;
+/// redis!(ZADD prices {price:.2} order:{id:08});
+/// ```
+/// ## Expansion
+/// ```rust
+/// let price = 12.5;
+/// let id = 42;
+/// redis::cmd("ZADD").arg("prices").arg(format!("{:.2}", price)).arg(format!("order:{:08}", id));
+/// ```
+/// ## Spreading
+/// Prefix a substitution with `..` to append every item of an iterator, slice, or collection as
+/// its own argument. This is useful for variadic commands such as DEL or MGET.
+/// ```rust
+/// use redis_rs_macro::redis;
+/// let keys = vec!["a", "b", "c"];
+/// redis!(DEL {..&keys});
+/// ```
+/// ## Expansion
+/// ```rust
+/// let keys = vec!["a", "b", "c"];
+/// let mut cmd = redis::cmd("DEL");
+/// for item in &keys {
+///     cmd.arg(item);
+/// }
+/// ```
+/// Maps and iterators of `(field, value)` tuples spread into interleaved field and value
+/// arguments, with both halves converted by `redis::ToRedisArgs`. Tuples keep the order of the
+/// iterator, so use a `BTreeMap` or a `Vec` of pairs when the order of the arguments matters.
+/// ```rust
+/// use redis_rs_macro::redis;
+/// use std::collections::BTreeMap;
+/// let fields = BTreeMap::from([("name", "alice"), ("age", "42")]);
+/// redis!(HSET user:1 {..&fields});
+/// ```
+/// ## Expansion
+/// ```rust
+/// redis::cmd("HSET").arg("user:1").arg("age").arg("42").arg("name").arg("alice");
+/// ```
+/// ## Score Spreads
+/// `{..scores pairs}` spreads `(score, member)` pairs into interleaved score and member
+/// arguments, for bulk `ZADD`s. Scores can be any number type, such as `f64`, and a pair with a
+/// member where the score should be fails to compile.
+/// ```rust
+/// use redis_rs_macro::redis;
+/// let board = vec![(1.5, "alice"), (2.0, "bob")];
+/// redis!(ZADD board {..scores board});
+/// ```
+/// ## Expansion
+/// ```rust
+/// let board = vec![(1.5, "alice"), (2.0, "bob")];
+/// let mut cmd = redis::cmd("ZADD");
+/// cmd.arg("board");
+/// for item in board {
+///     cmd.arg(item);
+/// }
+/// ```
+/// ## Field Spreads
+/// `{*expr}` writes a value implementing `redis_rs_macro::ToRedisFields` as alternating field
+/// names and values, which is the shape `HSET` and `XADD` expect. It is implemented for maps, and
+/// can be implemented for your own structs. To dereference a value instead, write `{(*expr)}`.
+/// ```rust
+/// use redis_rs_macro::redis;
+/// use std::collections::BTreeMap;
+/// let user = BTreeMap::from([("name", "alice"), ("role", "admin")]);
+/// redis!(HSET user:1 {*user});
+/// ```
+/// ## Expansion
+/// ```rust
+/// redis::cmd("HSET").arg("user:1").arg("name").arg("alice").arg("role").arg("admin");
+/// ```
+/// ## Optional Arguments
+/// Suffix a substitution with `?` to only append it when the `Option` is `Some`. An uppercase
+/// option name written right before it, such as the `EX` below, is left out along with the value
+/// when it is `None`. To propagate an error with `?` instead, wrap the expression in parentheses
+/// (`{(expr?)}`).
+/// ```rust
+/// use redis_rs_macro::redis;
+/// let ttl: Option<u64> = Some(60);
+/// redis!(SET my_key my_value EX {ttl?});
+/// ```
+/// ## Expansion
+/// ```rust
+/// let ttl: Option<u64> = Some(60);
+/// let mut cmd = redis::cmd("SET");
+/// cmd.arg("my_key").arg("my_value");
+/// if let Some(item) = ttl {
+///     cmd.arg("EX");
+///     cmd.arg(item);
+/// }
+/// ```
+/// ## Conditional Groups
+/// Wrap arguments in `?[{condition} => ...]` to only append them when the condition is true. The
+/// group may contain any arguments, including substitutions and optional arguments.
+/// ```rust
+/// use redis_rs_macro::redis;
+/// let only_new = true;
+/// let keep_ttl = false;
+/// redis!(SET my_key my_value ?[{only_new} => NX] ?[{keep_ttl} => KEEPTTL]);
+/// ```
+/// ## Expansion
+/// ```rust
+/// let only_new = true;
+/// let keep_ttl = false;
+/// let mut cmd = redis::cmd("SET");
+/// cmd.arg("my_key").arg("my_value");
+/// if only_new {
+///     cmd.arg("NX");
+/// }
+/// if keep_ttl {
+///     cmd.arg("KEEPTTL");
+/// }
+/// ```
+/// ## Joining
+/// Text and substitutions written next to each other without any whitespace in between are
+/// joined into a single argument, which is handy for building keys. Substituted values are
+/// converted with `redis::ToRedisArgs` before being joined.
+/// ```rust
+/// use redis_rs_macro::redis;
+/// let id = 42;
+/// redis!(GET user:{id}:name);
+/// ```
+/// ## Expansion
+/// ```rust
+/// let id = 42;
+/// redis::cmd("GET").arg([
+///     b"user:".to_vec(),
+///     redis::ToRedisArgs::to_redis_args(&(id)).concat(),
+///     b":name".to_vec(),
+/// ].concat());
+/// ```
+/// ## Environment Variables
+/// `${NAME}` is replaced with the value of the environment variable `NAME` at compile time, as
+/// with `env!`, which bakes per-deployment values like key prefixes into the binary. A variable
+/// that isn't defined is a compile error.
+/// ```rust
+/// use redis_rs_macro::redis;
+/// redis!(GET ${CARGO_PKG_NAME}:config);
+/// ```
+/// ## Expansion
+/// ```rust
+/// redis::cmd("GET").arg([env!("CARGO_PKG_NAME").as_bytes(), b":config"].concat());
+/// ```
+/// ## Separators
+/// Arguments may optionally be separated by commas, as they would be in a chain of `.arg` calls.
+/// The commas are discarded.
+/// ```rust
+/// use redis_rs_macro::redis;
+/// redis!(SET my_key, my_value);
+/// ```
+/// ## Expansion
+/// ```rust
+/// redis::cmd("SET").arg("my_key").arg("my_value");
+/// ```
+/// ## String Commands
+/// An entire command can also be written as a single string literal, which makes it easy to
+/// paste commands from redis-cli history or configuration files. The contents are split the same
+/// way as regular macro input, and quoted arguments accept the same escapes as redis-cli, along with
+/// `\u{...}` unicode escapes.
+/// ```rust
+/// use redis_rs_macro::redis;
+/// redis!("SET \"my key\" my_value EX 30");
+/// ```
+/// ## Expansion
+/// ```rust
+/// redis::cmd("SET").arg("my key").arg("my_value").arg("EX").arg("30");
+/// ```
+/// ## Named Bindings
+/// Words of the form `:name` are placeholders, which are bound to values after a `;`. This keeps
+/// the command template apart from its data. Every value is evaluated once, in the order it is
+/// bound, and is converted with `redis::ToRedisArgs`. Quote a word such as `":name"` to pass it
+/// as text instead.
+/// ```rust
+/// use redis_rs_macro::redis;
+/// let user_key = "user:1";
+/// redis!("SET :key :val EX :ttl"; key = user_key, val = "payload", ttl = 30);
+/// ```
+/// ## Expansion
+/// ```rust
+/// let user_key = "user:1";
+/// let binding_key = user_key;
+/// let binding_val = "payload";
+/// let binding_ttl = 30;
+/// redis::cmd("SET").arg(binding_key).arg(binding_val).arg("EX").arg(binding_ttl);
+/// ```
+/// ## Positional Bindings
+/// Values bound without a name fill `?` placeholders in order instead. The number of placeholders
+/// and values has to match. Without a `;`, a `?` is passed as text.
+/// ```rust
+/// use redis_rs_macro::redis;
+/// redis!("HSET ? ? ?"; "user:1", "name", "alice");
+/// ```
+/// ## Expansion
+/// ```rust
+/// let binding_0 = "user:1";
+/// let binding_1 = "name";
+/// let binding_2 = "alice";
+/// redis::cmd("HSET").arg(binding_0).arg(binding_1).arg(binding_2);
+/// ```
+/// ## Durations and Timestamps
+/// A `std::time::Duration` substituted as a TTL is converted to the unit redis expects: seconds
+/// after `EX` and in `EXPIRE` and `SETEX`, or milliseconds after `PX` and in `PEXPIRE` and
+/// `PSETEX`. In the same way, a `std::time::SystemTime` is converted to a unix timestamp after
+/// `EXAT` and in `EXPIREAT`, or to one in milliseconds after `PXAT` and in `PEXPIREAT`. The
+/// `chrono` and `time` features add support for `chrono::DateTime` and `time::OffsetDateTime`.
+/// Durations are rounded up to whole seconds or milliseconds, so `EX` of half a second sends `1`
+/// rather than the invalid `0`, and timestamps are truncated. Use the millisecond forms for
+/// sub-second precision.
+/// Any other value is passed through as it is.
+/// ```rust
+/// use redis_rs_macro::redis;
+/// use std::time::{Duration, SystemTime};
+/// redis!(SET my_key my_value EX {Duration::from_secs(30)});
+/// redis!(EXPIREAT my_key {SystemTime::now() + Duration::from_secs(30)});
+/// ```
+/// ## Expansion
+/// ```rust
+/// use std::time::{Duration, SystemTime};
+/// redis::cmd("SET").arg("my_key").arg("my_value").arg("EX").arg(Duration::from_secs(30).as_secs());
+/// let at = SystemTime::now() + Duration::from_secs(30);
+/// redis::cmd("EXPIREAT").arg("my_key").arg(at.duration_since(SystemTime::UNIX_EPOCH).unwrap().as_secs());
+/// ```
+/// ## Coordinates
+/// A `(longitude, latitude)` tuple is passed as two arguments, so coordinates can be kept
+/// together. The literal coordinates of `GEOADD`, `GEORADIUS`, and `FROMLONLAT` are checked at
+/// compile time to be within ±180 degrees of longitude and ±85.05112878 degrees of latitude,
+/// which also catches most pairs written latitude first.
+/// ```rust
+/// use redis_rs_macro::redis;
+/// redis!(GEOADD places {(13.361389, 38.115556)} Palermo);
+/// ```
+/// ## Expansion
+/// ```rust
+/// redis::cmd("GEOADD").arg("places").arg(13.361389).arg(38.115556).arg("Palermo");
+/// ```
+/// ## BITFIELD
+/// The sub-operations of `BITFIELD` and `BITFIELD_RO` are checked at compile time, along with
+/// their literal encodings (`u1` to `u63`, `i1` to `i64`), offsets (`0` or `#0`), values, and
+/// overflow modes, so a malformed command doesn't compile instead of failing on the server.
+/// ```rust
+/// use redis_rs_macro::redis;
+/// redis!(BITFIELD my_key OVERFLOW SAT INCRBY u8 #1 10 GET i16 0);
+/// ```
+/// ## Expansion
+/// ```rust
+/// redis::cmd("BITFIELD").arg("my_key").arg("OVERFLOW").arg("SAT").arg("INCRBY").arg("u8").arg("#1").arg("10").arg("GET").arg("i16").arg("0");
+/// ```
+/// ## ACL Rules
+/// The literal rules of `ACL SETUSER` are checked at compile time: each must be a known rule such
+/// as `on`, `>password`, `~pattern`, `%R~pattern`, `&pattern`, `+command` or `-@category`, with a
+/// category listed by `ACL CAT`. Rules that undo an earlier rule, such as `reset` or `-@all` after
+/// other rules, and rules that contradict each other, such as `on` and `off`, are errors too.
+/// Password hashes and selectors start with `#` and `(`, so they are quoted.
+/// ```rust
+/// use redis_rs_macro::redis;
+/// redis!(ACL SETUSER alice reset on >secret ~cache:* -@all +@read +client|setname);
+/// ```
+/// ## Expansion
+/// ```rust
+/// redis::cmd("ACL").arg("SETUSER").arg("alice").arg("reset").arg("on").arg(">secret").arg("~cache:*").arg("-@all").arg("+@read").arg("+client|setname");
+/// ```
+/// ## CLIENT KILL
+/// The filters of `CLIENT KILL` are checked at compile time: `ID`, `ADDR`, `LADDR`, `TYPE`,
+/// `USER`, `SKIPME` and `MAXAGE`, each given at most once and followed by a value. Literal values
+/// have to be of the right kind, such as an integer for `ID` and `MAXAGE`, an `ip:port` address
+/// for `ADDR`, and `normal`, `master`, `replica` or `pubsub` for `TYPE`.
+/// ```rust
+/// use redis_rs_macro::redis;
+/// let id = 42;
+/// redis!(CLIENT KILL ID {id} TYPE normal SKIPME yes);
+/// ```
+/// ## Expansion
+/// ```rust
+/// let id = 42;
+/// redis::cmd("CLIENT").arg("KILL").arg("ID").arg(id).arg("TYPE").arg("normal").arg("SKIPME").arg("yes");
+/// ```
+/// ## JSON Paths
+/// The literal paths of RedisJSON commands, such as `JSON.GET`, `JSON.SET` and `JSON.ARRAPPEND`,
+/// are checked at compile time. JSONPaths start with `$` and legacy paths with `.` or a member
+/// name, followed by `.name`, `..name`, `.*`, and brackets holding `*`, quoted names, indices,
+/// `start:end:step` slices or a `?(...)` filter. Paths with a space, or unbalanced brackets, have
+/// to be written in quotes.
+/// ```rust
+/// use redis_rs_macro::redis;
+/// redis!(JSON.GET cart:42 $.items[0].name "$..['unit price']");
+/// ```
+/// ## Expansion
+/// ```rust
+/// redis::cmd("JSON.GET").arg("cart:42").arg("$.items[0].name").arg("$..['unit price']");
+/// ```
+/// ## Time Series
+/// The timestamps of RedisTimeSeries commands (those of `TS.ADD`, `TS.MADD`, `TS.DEL`, the ranges
+/// of `TS.RANGE` and `TS.MRANGE`, and `TIMESTAMP`) take a `std::time::SystemTime`, which is
+/// converted to milliseconds, and `RETENTION` and the bucket durations of `AGGREGATION` take a
+/// `std::time::Duration`. Labels can be spread from a map with `LABELS {*labels}`. Literal
+/// aggregators, `REDUCE` reducers and duplicate policies are checked at compile time, along with
+/// bucket durations, `LABELS` pairs and the `label=value` matchers of `FILTER`. The replies of
+/// `TS.RANGE` can be read as `redis_rs_macro::Samples`, a vector of `(SystemTime, f64)`, and those
+/// of `TS.MRANGE` as a `Vec<redis_rs_macro::Series>`.
+/// ```rust
+/// use redis_rs_macro::{redis, Samples};
+/// use std::time::{Duration, SystemTime};
+/// let hour_ago = SystemTime::now() - Duration::from_secs(3600);
+/// redis!(TS.RANGE temp:1 {hour_ago} + AGGREGATION avg {Duration::from_secs(60)} -> Samples);
+/// ```
+/// ## Expansion
+/// ```rust
+/// use redis_rs_macro::{Samples, TypedCmd};
+/// use std::time::{Duration, SystemTime};
+/// let hour_ago = SystemTime::now() - Duration::from_secs(3600);
+/// let from = hour_ago.duration_since(SystemTime::UNIX_EPOCH).unwrap().as_millis() as u64;
+/// TypedCmd::<Samples>::new(redis::cmd("TS.RANGE").arg("temp:1").arg(from).arg("+").arg("AGGREGATION").arg("avg").arg(60000).clone());
+/// ```
+/// ## Bytes
+/// A substitution that starts with `bytes` takes any `AsRef<[u8]>` value, such as a `Vec<u8>` or
+/// a `&[u8]`, and passes it as a single binary argument. This makes binary payloads, like the
+/// ones of `DUMP` and `RESTORE`, explicit at the call site, and keeps them from being mistaken
+/// for a list of numbers.
+/// ```rust
+/// use redis_rs_macro::redis;
+/// let payload: Vec<u8> = vec![0, 159, 146, 150];
+/// redis!(RESTORE my_key 0 {bytes payload});
+/// ```
+/// ## Expansion
+/// ```rust
+/// let payload: Vec<u8> = vec![0, 159, 146, 150];
+/// redis::cmd("RESTORE").arg("my_key").arg("0").arg(AsRef::<[u8]>::as_ref(&payload));
+/// ```
+/// ## Comma Separated Lists
+/// A substitution that starts with `csv` joins the items of an iterator with commas into a
+/// single argument, for options that take comma separated lists.
+/// ```rust
+/// use redis_rs_macro::redis;
+/// let fields = ["name", "email"];
+/// redis!(SET my_key {csv fields});
+/// ```
+/// ## Expansion
+/// ```rust
+/// let fields = ["name", "email"];
+/// redis::cmd("SET").arg("my_key").arg(fields.join(","));
+/// ```
+/// ## JSON
+/// With the `json` feature, a substitution that starts with `json` is serialized with
+/// `serde_json`. Serialization errors are returned with `?` as a `redis::RedisError`, so the
+/// macro has to be used in a function that returns a compatible `Result`.
+/// ```rust
+/// # #[cfg(feature = "json")]
+/// # fn main() -> redis::RedisResult<()> {
+/// use redis_rs_macro::redis;
+/// let user = vec!["alice", "bob"];
+/// redis!(SET users {json user});
+/// # Ok(())
+/// # }
+/// # #[cfg(not(feature = "json"))]
+/// # fn main() {}
+/// ```
+/// ## Expansion
+/// ```rust
+/// # fn main() -> Result<(), serde_json::Error> {
+/// let user = vec!["alice", "bob"];
+/// redis::cmd("SET").arg("users").arg(serde_json::to_string(&user)?);
+/// # Ok(())
+/// # }
+/// ```
+/// ## MessagePack
+/// With the `msgpack` feature, a substitution that starts with `msgpack` is serialized with
+/// `rmp-serde` into a single binary argument. Errors are returned with `?`, as with `json`.
+/// ```rust
+/// # #[cfg(feature = "msgpack")]
+/// # fn main() -> redis::RedisResult<()> {
+/// use redis_rs_macro::redis;
+/// let user = ("alice", 30);
+/// redis!(SET user:1 {msgpack user});
+/// # Ok(())
+/// # }
+/// # #[cfg(not(feature = "msgpack"))]
+/// # fn main() {}
+/// ```
+/// ## Expansion
+/// ```rust
+/// # fn main() -> Result<(), rmp_serde::encode::Error> {
+/// let user = ("alice", 30);
+/// redis::cmd("SET").arg("user:1").arg(rmp_serde::to_vec(&user)?);
+/// # Ok(())
+/// # }
+/// ```
+/// ## Key Prefixes
+/// With the `key-prefix` feature, the keys of known commands get a namespace prefix, taken from
+/// the `REDIS_KEY_PREFIX` environment variable at compile time or set at startup with
+/// `redis_rs_macro::set_key_prefix`. Keys are found by their position in the command, including
+/// the keys of spreads, optional arguments, and `(key, value)` pairs spread into `MSET`, while
+/// commands the macro doesn't know are left as written. A spread or optional argument before the
+/// keys of a command hides their position, which is a compile error.
+/// ```rust
+/// # #[cfg(feature = "key-prefix")]
+/// # fn main() {
+/// use redis_rs_macro::redis;
+/// redis_rs_macro::set_key_prefix("app:").unwrap();
+/// let keys = ["b", "c"];
+/// redis!(DEL a {..keys});
+/// # }
+/// # #[cfg(not(feature = "key-prefix"))]
+/// # fn main() {}
+/// ```
+/// ## Expansion
+/// ```rust
+/// let keys = ["b", "c"];
+/// let mut cmd = redis::cmd("DEL");
+/// cmd.arg("app:a");
+/// for key in keys {
+///     cmd.arg(["app:", key].concat());
+/// }
+/// ```
+/// ## Cluster Slots
+/// With the `cluster` feature, the keys of a known command that are written out in full, or whose
+/// written start holds a hash tag such as `"{user}:"{id}`, must map to the same hash slot, since a
+/// cluster rejects commands whose keys don't with `CROSSSLOT`. Keys with substitutions and no
+/// hash tag are only known at runtime, and aren't checked. The check is skipped along with the
+/// `key-prefix` feature, since the prefix changes what is hashed.
+/// ```rust
+/// use redis_rs_macro::redis;
+/// let id = 42;
+/// redis!(MSET "{user}:name" alice "{user}:age" 30);
+/// redis!(RENAME "{user}:"{id} "{user}:old");
+/// ```
+/// ## Reply Types
+/// A `-> Type` after the command wraps it in a `redis_rs_macro::TypedCmd`, whose `query` method
+/// always reads the reply as that type. This keeps the type next to the command instead of at every
+/// call site. [`redis_exec!`] and [`redis_async!`] take the same annotation.
+/// ```rust
+/// # fn run(con: &mut redis::Connection) -> redis::RedisResult<()> {
+/// use redis_rs_macro::redis;
+/// let id = 42;
+/// let name = redis!(GET user:{id}:name -> Option<String>).query(con)?;
+/// # Ok(())
+/// # }
+/// ```
+/// ## Expansion
+/// ```rust
+/// # fn run(con: &mut redis::Connection) -> redis::RedisResult<()> {
+/// let id = 42;
+/// let name = redis::cmd("GET")
+///     .arg(format!("user:{}:name", id))
+///     .query::<Option<String>>(con)?;
+/// # Ok(())
+/// # }
+/// ```
+/// ## Comments
+/// A `#` at the start of a word comments out the rest of the line, so annotated commands can be
+/// pasted as they are. A `#` directly followed by a number, like the `#1` offsets of `BITFIELD`,
+/// is passed as text instead. The commented text must still be valid Rust tokens, so avoid unbalanced
+/// quotes or apostrophes inside it.
+/// ```rust
+/// use redis_rs_macro::redis;
+/// redis!(
+///     SET my_key my_value # the value to store
+///     EX 60               # expire after a minute
+/// );
+/// ```
+/// ## Expansion
+/// ```rust
+/// redis::cmd("SET").arg("my_key").arg("my_value").arg("EX").arg("60");
+/// ```
+#[proc_macro]
+pub fn redis(tokens: TokenStream) -> TokenStream {
+    typed::split_return(tokens.into())
+        .and_then(|(tokens, ty)| {
+            let cmd = expand::expand_command(&parse::parse_command(tokens)?)?;
+            Ok(match ty {
+                Some(ty) => typed::expand_typed(cmd, &ty),
+                None => cmd,
+            })
+        })
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Build a command with the syntax of [`redis!`] and send it on a connection right away
+///
+/// The first argument is the connection, as a `&mut` to anything that implements
+/// `redis::ConnectionLike`, followed by a comma and the command. The reply is returned as a
+/// `redis::RedisResult` of any type that implements `redis::FromRedisValue`, as with
+/// `redis::Cmd::query`, or of the type given with `-> Type` after the command.
+///
+/// # Examples
+/// ```rust
+/// # fn run(con: &mut redis::Connection) -> redis::RedisResult<()> {
+/// use redis_rs_macro::redis_exec;
+///
+/// let id = 42;
+/// let visits: i64 = redis_exec!(con, INCR user:{id}:visits)?;
+/// # Ok(())
+/// # }
+/// ```
+/// ## Expansion
+/// ```rust
+/// # fn run(con: &mut redis::Connection) -> redis::RedisResult<()> {
+/// let id = 42;
+/// let visits: i64 = redis::cmd("INCR").arg(format!("user:{}:visits", id)).query(con)?;
+/// # Ok(())
+/// # }
+/// ```
+#[proc_macro]
+pub fn redis_exec(tokens: TokenStream) -> TokenStream {
+    exec::expand_exec(tokens.into())
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Build a command with the syntax of [`redis!`] and query it on an async connection
+///
+/// This is the async counterpart of [`redis_exec!`]. The first argument is the connection, as a
+/// `&mut` to anything that implements `redis::aio::ConnectionLike`, followed by a comma and the
+/// command. The macro evaluates to a future of a `redis::RedisResult`, so the `aio` feature of
+/// `redis` has to be enabled, which the `async` feature of this crate does with `tokio-comp`. As
+/// with [`redis_exec!`], a `-> Type` after the command sets the type of the reply. The command and
+/// connection are moved into the future, which can be awaited right away or stored first.
+///
+/// # Examples
+/// ```rust,ignore
+/// use redis_rs_macro::redis_async;
+///
+/// let mut con = client.get_async_connection().await?;
+/// let name: Option<String> = redis_async!(&mut con, GET user:{id}:name).await?;
+/// ```
+/// ## Expansion
+/// ```rust,ignore
+/// let mut con = client.get_async_connection().await?;
+/// let name: Option<String> = {
+///     let cmd = redis::cmd("GET").arg(format!("user:{}:name", id)).to_owned();
+///     let con = &mut con;
+///     async move { redis::Cmd::query_async(&cmd, con).await }
+/// }
+/// .await?;
+/// ```
+#[proc_macro]
+pub fn redis_async(tokens: TokenStream) -> TokenStream {
+    exec::expand_async(tokens.into())
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Query a command with the syntax of [`redis!`], retrying it while it fails with a transient error
+///
+/// The first argument is a `redis_rs_macro::RetryPolicy`, or a reference to one, followed by the
+/// connection and the command as in [`redis_exec!`], including an optional `-> Type` for the
+/// reply. The command is built once and queried until it succeeds, fails with an error that isn't
+/// transient, or has been retried `retries` times, and the macro returns the last
+/// `redis::RedisResult`.
+///
+/// Errors are transient when the connection was refused, dropped or timed out, when the server is
+/// loading its dataset (`LOADING`) or running a script (`BUSY`), or when a cluster asks for a retry
+/// (`TRYAGAIN`, `CLUSTERDOWN`, `MASTERDOWN`). The delay before each retry starts at `base_delay`
+/// and doubles up to `max_delay`, and with `jitter` each delay is picked at random between half of
+/// it and all of it. A command that was sent before its connection dropped may have run, so only
+/// idempotent commands should be retried this way.
+///
+/// # Examples
+/// ```rust
+/// # fn run(con: &mut redis::Connection) -> redis::RedisResult<()> {
+/// use redis_rs_macro::{redis_retry, RetryPolicy};
+/// use std::time::Duration;
+///
+/// let policy = RetryPolicy {
+///     retries: 5,
+///     max_delay: Duration::from_secs(1),
+///     ..RetryPolicy::default()
+/// };
+/// let id = 42;
+/// let name: Option<String> = redis_retry!(policy, con, GET user:{id}:name)?;
+/// # Ok(())
+/// # }
+/// ```
+/// ## Expansion
+/// ```rust
+/// # fn run(con: &mut redis::Connection) -> redis::RedisResult<()> {
+/// # use redis_rs_macro::RetryPolicy;
+/// # let policy = RetryPolicy::default();
+/// let id = 42;
+/// let name: Option<String> = {
+///     let cmd = redis::cmd("GET").arg(format!("user:{}:name", id)).to_owned();
+///     RetryPolicy::run(&policy, || redis::Cmd::query(&cmd, con))
+/// }?;
+/// # Ok(())
+/// # }
+/// ```
+#[proc_macro]
+pub fn redis_retry(tokens: TokenStream) -> TokenStream {
+    retry::expand_retry(tokens.into())
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Query a command with the syntax of [`redis!`] on the current master of a Redis Sentinel service
+///
+/// The first argument is a `&mut redis_rs_macro::Sentinel`, which asks its sentinels for the
+/// address of the master with `SENTINEL GET-MASTER-ADDR-BY-NAME` and keeps a connection to it,
+/// followed by the command as in [`redis_exec!`], including an optional `-> Type` for the reply.
+/// When the command fails because of a failover, with a dropped or refused connection, or with
+/// `READONLY` from a master that became a replica, the master is looked up again and the command
+/// is retried after the delays of the `RetryPolicy` of the `Sentinel`. The other transient errors
+/// of [`redis_retry!`] are retried the same way, and the macro returns the last
+/// `redis::RedisResult`.
+///
+/// # Examples
+/// ```rust
+/// # fn run() -> redis::RedisResult<()> {
+/// use redis_rs_macro::{redis_sentinel, Sentinel};
+///
+/// let mut sentinel = Sentinel::new(
+///     ["redis://10.0.0.1:26379", "redis://10.0.0.2:26379"],
+///     "cache",
+/// )?;
+/// let id = 42;
+/// let visits: u64 = redis_sentinel!(&mut sentinel, INCR user:{id}:visits)?;
+/// # Ok(())
+/// # }
+/// ```
+/// ## Expansion
+/// ```rust
+/// # fn run(sentinel: &mut redis_rs_macro::Sentinel) -> redis::RedisResult<()> {
+/// use redis_rs_macro::Sentinel;
+///
+/// let id = 42;
+/// let visits: u64 = {
+///     let cmd = redis::cmd("INCR").arg(format!("user:{}:visits", id)).to_owned();
+///     Sentinel::query(sentinel, &cmd)
+/// }?;
+/// # Ok(())
+/// # }
+/// ```
+#[proc_macro]
+pub fn redis_sentinel(tokens: TokenStream) -> TokenStream {
+    sentinel::expand_sentinel(tokens.into())
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Query a command on an async connection, retrying it while it fails with a transient error
+///
+/// This is the async counterpart of [`redis_retry!`], and takes the same arguments, with the
+/// connection as a `&mut` to anything that implements `redis::aio::ConnectionLike`. As with
+/// [`redis_async!`], the macro evaluates to a future of a `redis::RedisResult`, into which the
+/// command, the connection and the policy are moved. The delays between attempts don't block the
+/// executor, and don't depend on an async runtime.
+///
+/// # Examples
+/// ```rust,ignore
+/// use redis_rs_macro::{redis_retry_async, RetryPolicy};
+///
+/// let mut con = client.get_multiplexed_async_connection().await?;
+/// let name: Option<String> =
+///     redis_retry_async!(RetryPolicy::default(), &mut con, GET user:{id}:name).await?;
+/// ```
+/// ## Expansion
+/// ```rust,ignore
+/// let mut con = client.get_multiplexed_async_connection().await?;
+/// let name: Option<String> = {
+///     let cmd = redis::cmd("GET").arg(format!("user:{}:name", id)).to_owned();
+///     let con = &mut con;
+///     let policy = RetryPolicy::default();
+///     async move {
+///         let mut retry = 0;
+///         loop {
+///             match redis::Cmd::query_async(&cmd, &mut *con).await {
+///                 Err(err) => match policy.retry_delay(&err, retry) {
+///                     Some(delay) => /* sleep for the delay */,
+///                     None => break Err(err),
+///                 },
+///                 result => break result,
+///             }
+///             retry += 1;
+///         }
+///     }
+/// }
+/// .await?;
+/// ```
+#[proc_macro]
+pub fn redis_retry_async(tokens: TokenStream) -> TokenStream {
+    retry::expand_retry_async(tokens.into())
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Build a `redis_test::MockCmd` from a command written with the syntax of [`redis!`]
+///
+/// The command is followed by `=>` and the reply of the mock, a `Result` of anything that
+/// implements `redis_test::IntoRedisValue` and `redis::RedisError`. Test expectations are then
+/// written the same way as the commands they expect, so the two can't drift apart. The expansion
+/// refers to `redis_test`, so the crate has to depend on `redis-test`, usually as a
+/// dev-dependency.
+///
+/// # Examples
+/// ```rust
+/// use redis_rs_macro::{redis_exec, redis_mock};
+/// use redis_test::MockRedisConnection;
+///
+/// let id = 42;
+/// let mut con = MockRedisConnection::new(vec![
+///     redis_mock!(SET user:{id}:name alice EX 60 => Ok("OK")),
+///     redis_mock!(GET user:{id}:name => Ok("alice")),
+/// ]);
+/// redis_exec!(&mut con, SET user:{id}:name alice EX 60 -> ()).unwrap();
+/// let name: String = redis_exec!(&mut con, GET user:{id}:name).unwrap();
+/// ```
+/// ## Expansion
+/// ```rust
+/// let id = 42;
+/// let expected = redis_test::MockCmd::new(
+///     redis::cmd("GET").arg(format!("user:{}:name", id)),
+///     Ok("alice"),
+/// );
+/// ```
+#[proc_macro]
+pub fn redis_mock(tokens: TokenStream) -> TokenStream {
+    mock::expand_mock(tokens.into())
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Run a test against a throwaway Redis server
+///
+/// The test function takes a connection as its only parameter, as a `&mut redis::Connection` or a
+/// `&mut dyn redis::ConnectionLike`, and can return anything that a `#[test]` can. The attribute
+/// makes it a `#[test]` that starts `redis-server` on a free port, passes it a connection to the
+/// server, and kills the server when the test ends, whether or not it passed. Each test has a
+/// server of its own, so tests can run in parallel without sharing keys.
+///
+/// This requires the `test-server` feature, usually enabled on a dev-dependency. `redis-server` is
+/// looked up on `PATH`, or at the path in the `REDIS_SERVER` environment variable. The server
+/// doesn't save snapshots or an append-only file. Attributes such as `#[ignore]` are kept.
+///
+/// # Examples
+/// ```rust
+/// use redis_rs_macro::{redis_exec, redis_integration_test};
+///
+/// #[redis_integration_test]
+/// fn counts_visits(con: &mut redis::Connection) -> redis::RedisResult<()> {
+///     let visits: i64 = redis_exec!(con, INCR visits)?;
+///     assert_eq!(visits, 1);
+///     Ok(())
+/// }
+/// ```
+/// ## Expansion
+/// ```rust,ignore
+/// #[test]
+/// fn counts_visits() -> redis::RedisResult<()> {
+///     fn counts_visits(con: &mut redis::Connection) -> redis::RedisResult<()> {
+///         let visits: i64 = redis_exec!(con, INCR visits)?;
+///         assert_eq!(visits, 1);
+///         Ok(())
+///     }
+///
+///     let server = redis_rs_macro::TestServer::start()
+///         .unwrap_or_else(|err| panic!("couldn't start redis-server: {}", err));
+///     let mut con = server
+///         .connection()
+///         .unwrap_or_else(|err| panic!("couldn't connect to redis-server: {}", err));
+///     counts_visits(&mut con)
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn redis_integration_test(args: TokenStream, item: TokenStream) -> TokenStream {
+    integration::expand_integration_test(args.into(), item.into())
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Check a connection URL at compile time
+///
+/// The argument is a string literal, which the macro expands to after checking it the way
+/// `redis::IntoConnectionInfo` reads URLs, so a malformed URL in a constant fails the build instead
+/// of the start of the program. These forms are accepted:
+/// - `redis://[[user][:password]@]host[:port][/db]`, where the host can be an IPv6 address in
+///   brackets, and reserved characters in the credentials are percent-encoded
+/// - `rediss://...` for TLS, with the same parts and an optional `#insecure` to skip certificate
+///   checks
+/// - `redis+unix:///path/to/socket` or `unix:///path/to/socket`, with the optional query
+///   parameters `db`, `user` and `pass`
+///
+/// The database is a number, and the port is from 1 to 65535. TLS connections also need the TLS
+/// features of `redis`.
+///
+/// # Examples
+/// ```rust
+/// use redis::IntoConnectionInfo;
+/// use redis_rs_macro::redis_url;
+///
+/// const CACHE_URL: &str = redis_url!("redis://:s%40cret@cache.internal:6380/2");
+/// let info = CACHE_URL.into_connection_info().unwrap();
+/// assert_eq!(info.redis.db, 2);
+/// ```
+/// ## Expansion
+/// ```rust
+/// const CACHE_URL: &str = "redis://:s%40cret@cache.internal:6380/2";
+/// ```
+#[proc_macro]
+pub fn redis_url(tokens: TokenStream) -> TokenStream {
+    url::expand_url(tokens.into())
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Compute the cluster hash slot of a key at compile time
+///
+/// The argument is the key as a string or byte string literal, and the macro expands to its hash
+/// slot as a `u16` literal, so it can be used in constants and patterns. As in a cluster, the slot
+/// is the CRC16 of the key modulo 16384, and only the hash tag is hashed when the key has one: the
+/// text between the first `{` and the next `}`, if it isn't empty.
+///
+/// # Examples
+/// ```rust
+/// use redis_rs_macro::redis_keyslot;
+///
+/// const JOBS_SLOT: u16 = redis_keyslot!("jobs:{eu}:pending");
+/// assert_eq!(JOBS_SLOT, redis_keyslot!("{eu}"));
+/// assert_eq!(redis_keyslot!("foo"), 12182);
+/// ```
+/// ## Expansion
+/// ```rust
+/// const JOBS_SLOT: u16 = 6893u16;
+/// ```
+#[proc_macro]
+pub fn redis_keyslot(tokens: TokenStream) -> TokenStream {
+    slot::expand_keyslot(tokens.into())
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Build a `SLOWLOG GET` command whose reply is read as a list of typed entries
+///
+/// The optional argument is the number of entries to read, as an expression, where `-1` reads the
+/// whole slow log. Without it the server returns its default of 10 entries. The macro evaluates
+/// to a `redis_rs_macro::TypedCmd<Vec<redis_rs_macro::SlowlogEntry>>`, whose `query` returns the
+/// entries newest first, each with its ID, when it ran, how long it took as a `Duration`, the
+/// arguments of the command, and the address and name of the client.
+///
+/// # Examples
+/// ```rust
+/// # fn run(con: &mut redis::Connection) -> redis::RedisResult<()> {
+/// use redis_rs_macro::redis_slowlog;
+///
+/// for entry in redis_slowlog!(25).query(con)? {
+///     println!("{:?} {}", entry.duration, entry.args.join(" "));
+/// }
+/// # Ok(())
+/// # }
+/// ```
+/// ## Expansion
+/// ```rust
+/// # fn run(con: &mut redis::Connection) -> redis::RedisResult<()> {
+/// use redis_rs_macro::{SlowlogEntry, TypedCmd};
+///
+/// let cmd = TypedCmd::<Vec<SlowlogEntry>>::new(redis::cmd("SLOWLOG").arg("GET").arg(25).clone());
+/// for entry in cmd.query(con)? {
+///     println!("{:?} {}", entry.duration, entry.args.join(" "));
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[proc_macro]
+pub fn redis_slowlog(tokens: TokenStream) -> TokenStream {
+    slowlog::expand_slowlog(tokens.into())
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Build an `INFO` command for one section, whose reply is read as a typed struct
+///
+/// The argument is the section: `memory`, `replication`, `clients` or `keyspace`, and other
+/// sections are a compile error. The macro evaluates to a `redis_rs_macro::TypedCmd` of
+/// `redis_rs_macro::MemoryInfo`, `ReplicationInfo`, `ClientsInfo` or `KeyspaceInfo`, whose `query`
+/// parses the `field:value` lines of the reply, with numbers as integers or floats. Fields that
+/// older servers don't send are 0, or `None` when they are optional, and a field that should be a
+/// number but isn't fails with a `TypeError` that names it.
+///
+/// # Examples
+/// ```rust
+/// # fn run(con: &mut redis::Connection) -> redis::RedisResult<()> {
+/// use redis_rs_macro::redis_info;
+///
+/// let memory = redis_info!(memory).query(con)?;
+/// if memory.maxmemory > 0 && memory.used_memory * 10 > memory.maxmemory * 9 {
+///     println!("{} bytes used of {}", memory.used_memory, memory.maxmemory);
+/// }
+/// for (db, keyspace) in redis_info!(keyspace).query(con)?.dbs {
+///     println!("db{}: {} keys, {} with a TTL", db, keyspace.keys, keyspace.expires);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+/// ## Expansion
+/// ```rust
+/// # fn run(con: &mut redis::Connection) -> redis::RedisResult<()> {
+/// use redis_rs_macro::{MemoryInfo, TypedCmd};
+///
+/// let memory = TypedCmd::<MemoryInfo>::new(redis::cmd("INFO").arg("memory").clone()).query(con)?;
+/// # Ok(())
+/// # }
+/// ```
+#[proc_macro]
+pub fn redis_info(tokens: TokenStream) -> TokenStream {
+    info::expand_info(tokens.into())
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Build a RediSearch `FT.SEARCH` or `FT.AGGREGATE` command that is checked at compile time, and
+/// whose results are read as a typed struct
+///
+/// The command is written as with [`redis!`], followed by `-> Type`, the type of each document or
+/// row of the results. The query is a string literal, whose parentheses, brackets, braces and
+/// quotes have to be balanced and whose `@` have to be followed by a field name and `:`. Values
+/// aren't formatted into the query: each `$name` of the query is a parameter, which is bound to the
+/// variable `name` in scope with `PARAMS`, so that the values can't change the query. The values
+/// are borrowed, and each has to be a single argument. `DIALECT 2` is added when the query has
+/// parameters, unless another dialect is given.
+///
+/// The options are checked: unknown options, options given twice, the counts and integer arguments
+/// of options such as `RETURN`, `LIMIT` and `HIGHLIGHT FIELDS`, and, for `FT.SEARCH`, the order of
+/// the options, which is that of the documentation (e.g. `RETURN`, `SUMMARIZE`, `HIGHLIGHT`,
+/// `SORTBY` then `LIMIT`). For `FT.AGGREGATE`, `LOAD`, `TIMEOUT` and the other options of the query
+/// come before the steps of the pipeline, which can be in any order, and `REDUCE` follows a
+/// `GROUPBY`. Checking stops at a spread, or an optional or conditional argument that takes more
+/// than one value, and options that change the layout of the reply (`NOCONTENT`, `WITHSCORES`,
+/// `WITHPAYLOADS` and `WITHSORTKEYS`) can't be written after one.
+///
+/// `FT.SEARCH` evaluates to a `redis_rs_macro::SearchCmd`, whose `query` returns the
+/// `SearchResults`: the total number of matches and the documents, each with its key, the score,
+/// payload and sort key when they were asked for, and its fields read as the type, such as a
+/// [`RedisReply`] or [`RedisHash`] struct. With `NOCONTENT`, the type is usually `()`.
+/// `FT.AGGREGATE` evaluates to a `redis_rs_macro::TypedCmd` of `AggregateResults`, whose rows are
+/// read as the type, along with the cursor of `WITHCURSOR`.
+///
+/// # Examples
+/// ```rust
+/// # fn run(con: &mut redis::Connection) -> redis::RedisResult<()> {
+/// use redis_rs_macro::{redis_search, RedisReply};
+///
+/// #[derive(RedisReply)]
+/// struct Product {
+///     name: String,
+///     price: f64,
+/// }
+///
+/// #[derive(RedisReply)]
+/// struct Brand {
+///     brand: String,
+///     total: f64,
+/// }
+///
+/// let (name, max) = ("laptop", 1000);
+/// let results = redis_search!(
+///     FT.SEARCH idx:products "@name:$name @price:[0 $max]"
+///     RETURN 2 name price SORTBY price ASC LIMIT 0 10 -> Product
+/// )
+/// .query(con)?;
+/// for doc in results.docs {
+///     println!("{}: {} costs {}", doc.id, doc.value.name, doc.value.price);
+/// }
+///
+/// let brands = redis_search!(
+///     FT.AGGREGATE idx:products "*" GROUPBY 1 @brand REDUCE SUM 1 @price AS total -> Brand
+/// )
+/// .query(con)?;
+/// # Ok(())
+/// # }
+/// ```
+/// ## Expansion
+/// ```rust
+/// # struct Product;
+/// # impl redis::FromRedisValue for Product {
+/// #     fn from_redis_value(_: &redis::Value) -> redis::RedisResult<Self> { Ok(Product) }
+/// # }
+/// # fn run(con: &mut redis::Connection) -> redis::RedisResult<()> {
+/// # let (name, max) = ("laptop", 1000);
+/// let results = redis_rs_macro::SearchCmd::<Product>::new(
+///     redis::cmd("FT.SEARCH")
+///         .arg("idx:products")
+///         .arg("@name:$name @price:[0 $max]")
+///         .arg("RETURN").arg(2).arg("name").arg("price")
+///         .arg("SORTBY").arg("price").arg("ASC")
+///         .arg("LIMIT").arg(0).arg(10)
+///         .arg("PARAMS").arg(4).arg("name").arg(&name).arg("max").arg(&max)
+///         .arg("DIALECT").arg(2)
+///         .clone(),
+///     // Which items the reply has for each document
+///     # redis_rs_macro::__private::search::Layout {
+///     #     content: true, scores: false, payloads: false, sort_keys: false,
+///     # },
+/// )
+/// .query(con)?;
+/// # Ok(())
+/// # }
+/// ```
+#[proc_macro]
+pub fn redis_search(tokens: TokenStream) -> TokenStream {
+    search::expand_search(tokens.into())
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Build a RedisBloom command, of a Bloom filter (`BF.*`), cuckoo filter (`CF.*`), count-min
+/// sketch (`CMS.*`), top-k (`TOPK.*`) or t-digest (`TDIGEST.*`), whose reply is typed
+///
+/// The command is written as with [`redis!`], and evaluates to a `redis_rs_macro::TypedCmd` of
+/// the type of its reply, such as `bool` for `BF.ADD` and `CF.EXISTS`, `Vec<bool>` for
+/// `BF.MADD`, `BF.MEXISTS`, `BF.INSERT` and `TOPK.QUERY`, `Vec<u64>` for `CMS.QUERY` and
+/// `Vec<f64>` for `TDIGEST.QUANTILE`. Another type can be given with `-> Type`, which is needed for
+/// commands without a known reply type, such as `BF.INFO`.
+///
+/// The parameters of the commands with a known reply type are checked: error rates and
+/// probabilities have to be between 0 and 1, exclusive, capacities and other sizes have to be
+/// positive integers, quantiles have to be from 0 to 1, and options have to be known and given
+/// once. Substituted error rates, probabilities and quantiles have to be `f64`s, capacities and
+/// sizes `u64`s, and increments `i64`s. Items can be spread with `{..items}`, such as the items
+/// of `BF.MADD` and `BF.MEXISTS`, or the `ITEMS` of `BF.INSERT`.
+///
+/// # Examples
+/// ```rust
+/// # fn run(con: &mut redis::Connection) -> redis::RedisResult<()> {
+/// use redis_rs_macro::redis_bloom;
+///
+/// let capacity: u64 = 100_000;
+/// redis_bloom!(BF.RESERVE seen 0.001 {capacity} NONSCALING).query(con)?;
+/// let names = ["alice", "bob"];
+/// let added: Vec<bool> = redis_bloom!(BF.MADD seen {..names}).query(con)?;
+/// let seen: Vec<bool> = redis_bloom!(BF.MEXISTS seen alice carol).query(con)?;
+/// let p99 = redis_bloom!(TDIGEST.QUANTILE latency 0.99).query(con)?[0];
+/// # Ok(())
+/// # }
+/// ```
+/// ## Expansion
+/// ```rust
+/// # fn run(con: &mut redis::Connection) -> redis::RedisResult<()> {
+/// use redis_rs_macro::TypedCmd;
+///
+/// let names = ["alice", "bob"];
+/// let added = TypedCmd::<Vec<bool>>::new(redis::cmd("BF.MADD").arg("seen").arg(&names).clone())
+///     .query(con)?;
+/// # Ok(())
+/// # }
+/// ```
+#[proc_macro]
+pub fn redis_bloom(tokens: TokenStream) -> TokenStream {
+    bloom::expand_bloom(tokens.into())
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Look up the metadata of a command in the command table at compile time
+///
+/// The argument is the name of the command, and its subcommand for container commands such as
+/// `XGROUP CREATE`, in any case. The macro expands to a `redis_rs_macro::CommandMeta` with the
+/// arity, the write and readonly flags, the positions of the keys, and the Redis version that
+/// added the command, so it can be used in constants. Commands missing from the table are a
+/// compile error.
+///
+/// # Examples
+/// ```rust
+/// use redis_rs_macro::{redis_meta, CommandMeta};
+///
+/// const ZADD: CommandMeta = redis_meta!(ZADD);
+/// assert!(ZADD.write);
+/// assert_eq!((ZADD.arity, ZADD.first_key, ZADD.since), (-4, 1, "1.2.0"));
+/// assert!(redis_meta!(XINFO STREAM).readonly);
+/// ```
+/// ## Expansion
+/// ```rust
+/// const ZADD: redis_rs_macro::CommandMeta = redis_rs_macro::CommandMeta {
+///     name: "ZADD",
+///     arity: -4,
+///     write: true,
+///     readonly: false,
+///     first_key: 1,
+///     last_key: 1,
+///     step: 1,
+///     movable_keys: false,
+///     since: "1.2.0",
+/// };
+/// ```
+#[proc_macro]
+pub fn redis_meta(tokens: TokenStream) -> TokenStream {
+    meta::expand_meta(tokens.into())
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// The metadata of every command in the command table
+///
+/// The macro takes no arguments, and expands to a `&'static [redis_rs_macro::CommandMeta]` with an
+/// entry for each command that [`redis_meta!`] knows about, so tools can list or sample the
+/// commands at runtime.
+///
+/// # Examples
+/// ```rust
+/// use redis_rs_macro::{redis_command_table, redis_meta, CommandMeta};
+///
+/// static COMMANDS: &[CommandMeta] = redis_command_table!();
+/// assert!(COMMANDS.contains(&redis_meta!(ZADD)));
+/// assert!(COMMANDS.iter().any(|meta| meta.name == "XGROUP CREATE"));
+/// ```
+#[proc_macro]
+pub fn redis_command_table(tokens: TokenStream) -> TokenStream {
+    meta::expand_command_table(tokens.into())
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Prepend a namespace to the keys of the commands in a function or module
+///
+/// The argument is a string literal that is written in front of every key of the commands of the
+/// [`redis!`], [`redis_exec!`], [`redis_async!`], [`redis_retry!`], [`redis_retry_async!`],
+/// [`redis_sentinel!`], [`redis_routed!`], [`redis_pipe!`] and [`redis_batch!`] calls inside the item, found from the
+/// key positions of the command table as with the `key-prefix` feature. Literal keys stay
+/// literal, so the namespace is part of the hash slot of `redis_routed!`, and spreads of keys get
+/// it on each of their items. Nested namespaces are joined from the outermost one in, and the
+/// runtime key prefix of the `key-prefix` feature goes in front of them all. A command that isn't
+/// in the table, such as the command of a module, is a compile error, as is a spread or optional
+/// argument that hides which arguments are keys. Writing `@no_namespace` before such a command
+/// sends it as written, so its keys have to be prefixed by hand, as in
+/// `redis!(@no_namespace MODULE.ADD billing:{key} 1)` or a `redis_pipe!` line of the same form.
+///
+/// Calls are found by the name of the macro, so a macro that is renamed on import, or a command
+/// built in another function, doesn't get the namespace.
+///
+/// # Examples
+/// ```rust
+/// use redis_rs_macro::{redis, redis_namespace};
+///
+/// #[redis_namespace("billing:")]
+/// fn charge(user: u64, cents: i64) -> redis::Cmd {
+///     redis!(INCRBY balance:{user} {cents})
+/// }
+///
+/// #[redis_namespace("billing:")]
+/// mod invoices {
+///     #[redis_rs_macro::redis_namespace("2024:")]
+///     pub fn mark_paid(ids: &[u64]) -> redis::Cmd {
+///         redis_rs_macro::redis!(SADD invoices:paid {..ids})
+///     }
+/// }
+///
+/// assert_eq!(
+///     charge(7, 250).get_packed_command(),
+///     redis::cmd("INCRBY").arg("billing:balance:7").arg(250).get_packed_command(),
+/// );
+/// assert_eq!(
+///     invoices::mark_paid(&[1]).get_packed_command(),
+///     redis::cmd("SADD").arg("billing:2024:invoices:paid").arg(1).get_packed_command(),
+/// );
+/// ```
+/// ## Expansion
+/// ```rust
+/// fn charge(user: u64, cents: i64) -> redis::Cmd {
+///     redis::cmd("INCRBY")
+///         .arg(format!("billing:balance:{}", user))
+///         .arg(cents)
+///         .clone()
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn redis_namespace(args: TokenStream, item: TokenStream) -> TokenStream {
+    namespace::expand_namespace(args.into(), item.into())
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Prepend a runtime prefix, such as the id of a tenant, to the keys of the commands in a function
+///
+/// The argument is `prefix = <expr>`, where the expression is anything that implements
+/// `redis::ToRedisArgs` and may use the parameters of the function, including `self`. It goes in
+/// front of the keys of the nested macro calls that [`#[redis_namespace]`](redis_namespace)
+/// supports, found the same way, and is evaluated again for every key it is written in front of,
+/// so it should be cheap to compute. Combined with `#[redis_namespace]`, the outermost attribute's
+/// prefix comes first.
+///
+/// Since every key of a known command gets the prefix, a command can't reach the keys of another
+/// tenant by forgetting to add it. Commands that aren't in the command table are a compile error,
+/// as is a spread or optional argument that hides which arguments are keys; `@no_namespace`
+/// before such a command sends it without the prefix, as with `#[redis_namespace]`.
+///
+/// # Examples
+/// ```rust
+/// use redis_rs_macro::{redis, redis_tenant};
+///
+/// struct Store {
+///     tenant_id: String,
+/// }
+///
+/// impl Store {
+///     #[redis_tenant(prefix = format!("{}:", self.tenant_id))]
+///     fn rename(&self, from: &str, to: &str) -> redis::Cmd {
+///         redis!(RENAME {from} {to})
+///     }
+/// }
+///
+/// let store = Store { tenant_id: "acme".to_string() };
+/// assert_eq!(
+///     store.rename("a", "b").get_packed_command(),
+///     redis::cmd("RENAME").arg("acme:a").arg("acme:b").get_packed_command(),
+/// );
+/// ```
+/// ## Expansion
+/// ```rust
+/// # struct Store {
+/// #     tenant_id: String,
+/// # }
+/// impl Store {
+///     fn rename(&self, from: &str, to: &str) -> redis::Cmd {
+///         let mut cmd = redis::cmd("RENAME");
+///         cmd.arg([format!("{}:", self.tenant_id).as_bytes(), from.as_bytes()].concat());
+///         cmd.arg([format!("{}:", self.tenant_id).as_bytes(), to.as_bytes()].concat());
+///         cmd
+///     }
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn redis_tenant(args: TokenStream, item: TokenStream) -> TokenStream {
+    namespace::expand_tenant(args.into(), item.into())
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Reject write commands in a function or module at compile time
+///
+/// Every command of the nested macro calls that [`#[redis_namespace]`](redis_namespace) supports
+/// is looked up in the command table, and one with the `write` flag, such as `SET` or
+/// `XGROUP CREATE`, is a compile error pointing at its name. This keeps code that runs against
+/// read-only replicas from growing writes. Commands that don't change data, or aren't in the
+/// table, are allowed, and so are scripts, since `EVAL` doesn't say whether it writes.
+///
+/// Calls are found by the name of the macro, so a command built in another function, or with a
+/// macro that is renamed on import, isn't checked.
+///
+/// # Examples
+/// ```rust
+/// use redis_rs_macro::{redis, redis_pipe, redis_readonly};
+///
+/// #[redis_readonly]
+/// fn profile(id: u64) -> redis::Pipeline {
+///     redis_pipe! {
+///         HGETALL user:{id}
+///         SMEMBERS user:{id}:roles
+///         // SET user:{id}:seen 1 would fail to compile
+///     }
+/// }
+/// ```
+/// ## Expansion
+/// ```rust
+/// fn profile(id: u64) -> redis::Pipeline {
+///     let mut pipe = redis::pipe();
+///     pipe.cmd("HGETALL").arg(format!("user:{}", id));
+///     pipe.cmd("SMEMBERS").arg(format!("user:{}:roles", id));
+///     pipe
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn redis_readonly(args: TokenStream, item: TokenStream) -> TokenStream {
+    readonly::expand_readonly(args.into(), item.into())
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Build a command with the syntax of [`redis!`], along with where a cluster client sends it
+///
+/// The macro evaluates to a `(redis::Cmd, redis_rs_macro::Route)` tuple, so cluster clients and
+/// custom routers don't have to find the keys of the command again at runtime. Commands with keys
+/// go to the hash slot of their first key, which is computed at compile time when the key is
+/// written out, or has a hash tag in its written start, and from the built command otherwise.
+/// Commands without keys go to any node, except those over the whole keyspace, such as `KEYS` and
+/// `FLUSHDB`, which go to every primary. A spread or optional argument before the first key hides
+/// its position, which is a compile error.
+///
+/// # Examples
+/// ```rust
+/// use redis_rs_macro::{redis_routed, Route};
+///
+/// let id = 42;
+/// let (cmd, route) = redis_routed!(HSET "{user}:"{id} name alice);
+/// assert_eq!(route, Route::Slot(5474));
+/// let (cmd, route) = redis_routed!(GET user:{id});
+/// assert_eq!(route, Route::for_key(b"user:42"));
+/// ```
+/// ## Expansion
+/// ```rust
+/// use redis_rs_macro::Route;
+///
+/// let id = 42;
+/// let (cmd, route) = (
+///     redis::cmd("HSET").arg(format!("{{user}}:{}", id)).arg("name").arg("alice").clone(),
+///     Route::Slot(5474),
+/// );
+/// let cmd = redis::cmd("GET").arg(format!("user:{}", id)).clone();
+/// let route = Route::for_arg(&cmd, 1);
+/// ```
+#[proc_macro]
+pub fn redis_routed(tokens: TokenStream) -> TokenStream {
+    route::expand_routed(tokens.into())
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Build an `EVAL` command for an inline Lua script
+///
+/// The script is a string literal, usually a raw string, which comes after the optional
+/// `keys = [..]` and `args = [..]` lists. Items of the lists are separated by commas and written
+/// with the argument syntax of [`redis!`], so they can be substitutions or spreads. The number of
+/// keys is counted from the key list when the command is built, which keeps it in sync with the
+/// keys, including those of spreads. With the `key-prefix` feature, the keys get the key prefix.
+/// The macro evaluates to an owned `redis::Cmd`.
+///
+/// Given a connection first, as a `&mut` to anything that implements `redis::ConnectionLike`, the
+/// macro calls the script right away and returns its reply as a `redis::RedisResult`. The script
+/// is sent with `EVALSHA` and its SHA1, which is computed at compile time, and only sent whole with
+/// `EVAL` when the server replies `NOSCRIPT`. This is done by `redis_rs_macro::ScriptCmd`.
+///
+/// With the `lua-check` feature, the script is parsed as Lua 5.1, the version Redis runs, and
+/// syntax errors are reported at compile time with their line and column in the script.
+///
+/// # Examples
+/// ```rust
+/// use redis_rs_macro::redis_eval;
+///
+/// let id = 42;
+/// let ttl = 60;
+/// let cmd = redis_eval!(keys = [user:{id}, counters], args = [{ttl}], r#"
+///     redis.call("EXPIRE", KEYS[1], ARGV[1])
+///     return redis.call("INCR", KEYS[2])
+/// "#);
+///
+/// # fn run(con: &mut redis::Connection) -> redis::RedisResult<()> {
+/// let visits: i64 = redis_eval!(con, keys = [visits], r#"return redis.call("INCR", KEYS[1])"#)?;
+/// # Ok(())
+/// # }
+/// ```
+/// ## Expansion
+/// ```rust
+/// let id = 42;
+/// let ttl = 60;
+/// let keys = vec![format!("user:{}", id), "counters".to_string()];
+/// let mut cmd = redis::cmd("EVAL");
+/// cmd.arg(r#"
+///     redis.call("EXPIRE", KEYS[1], ARGV[1])
+///     return redis.call("INCR", KEYS[2])
+/// "#)
+/// .arg(keys.len())
+/// .arg(keys)
+/// .arg(ttl);
+///
+/// # fn run(con: &mut redis::Connection) -> redis::RedisResult<()> {
+/// let mut call = redis_rs_macro::ScriptCmd::new(
+///     r#"return redis.call("INCR", KEYS[1])"#,
+///     "f793247de6e1e3c553cd42d39c812df499e679e4",
+/// );
+/// call.key("visits");
+/// let visits: i64 = call.invoke(con)?;
+/// # Ok(())
+/// # }
+/// ```
+#[proc_macro]
+pub fn redis_eval(tokens: TokenStream) -> TokenStream {
+    eval::expand_eval(tokens.into())
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Turn a body-less function into one that calls a Lua script file
+///
+/// The argument is the path of the script, relative to the directory of the calling crate's
+/// `Cargo.toml`. The script is embedded with `include_str!`, and its SHA1 is computed at compile
+/// time into a `<NAME>_SHA1` const next to the function, which gives a stable name for the script
+/// in `SCRIPT EXISTS` checks or monitoring.
+///
+/// Parameters marked with `#[key]` are passed as keys, and the others as arguments, in order. The
+/// generated function takes the connection as a `&mut dyn redis::ConnectionLike` before the
+/// parameters, and returns the reply type of the declaration, which is a `redis::RedisResult`.
+/// The script is called with `EVALSHA`, and only sent whole with `EVAL` when the server replies
+/// `NOSCRIPT`, as with [`redis_eval!`]. With the `key-prefix` feature, the keys get the key prefix.
+/// With the `lua-check` feature, syntax errors in the script are reported at compile time.
+///
+/// # Examples
+/// ```rust,ignore
+/// use redis_rs_macro::redis_script;
+///
+/// #[redis_script("scripts/incr_by.lua")]
+/// pub fn incr_by(#[key] counter: &str, amount: i64) -> redis::RedisResult<i64>;
+///
+/// let total = incr_by(&mut con, "visits", 2)?;
+/// println!("{}", INCR_BY_SHA1);
+/// ```
+/// ## Expansion
+/// ```rust,ignore
+/// /// The SHA1 of `scripts/incr_by.lua`
+/// pub const INCR_BY_SHA1: &str = "4f3e5d...";
+///
+/// pub fn incr_by(
+///     con: &mut dyn redis::ConnectionLike,
+///     counter: &str,
+///     amount: i64,
+/// ) -> redis::RedisResult<i64> {
+///     let mut call = redis_rs_macro::ScriptCmd::new(
+///         include_str!("/path/to/crate/scripts/incr_by.lua"),
+///         INCR_BY_SHA1,
+///     );
+///     call.key(&counter);
+///     call.arg(&amount);
+///     call.invoke(con)
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn redis_script(args: TokenStream, item: TokenStream) -> TokenStream {
+    script::expand_script(args.into(), item.into())
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Fill a module with the loader and call wrappers of a Redis Functions library file
+///
+/// The argument is the path of the library, relative to the directory of the calling crate's
+/// `Cargo.toml`. The library is embedded with `include_str!` and read at compile time: its name
+/// comes from the `#!lua name=<library>` line, and its functions from the
+/// `redis.register_function` calls, in both the `('name', callback)` and the
+/// `{function_name = 'name', ..}` forms. The module gets:
+/// - `SOURCE` and `LIBRARY` consts, with the source and name of the library
+/// - `load(con)`, which loads the library with `FUNCTION LOAD REPLACE`
+/// - a function for each registered function, of the same name, taking the connection, the keys
+///   and the arguments, each as anything that implements `redis::ToRedisArgs`. The number of keys
+///   is counted from the keys. Functions with the `no-writes` flag are called with `FCALL_RO`, and
+///   others with `FCALL`. The reply is read as any type that implements `redis::FromRedisValue`.
+///
+/// The connection is a `&mut dyn redis::ConnectionLike`. With the `key-prefix` feature, the keys
+/// get the key prefix. With the `lua-check` feature, syntax errors in the library are reported at
+/// compile time.
+///
+/// # Examples
+/// ```rust,ignore
+/// use redis_rs_macro::redis_functions;
+///
+/// // lua/orders.lua:
+/// // #!lua name=orders
+/// // redis.register_function('add', function(keys, args) ... end)
+/// // redis.register_function{function_name = 'total', callback = total, flags = {'no-writes'}}
+/// #[redis_functions("lua/orders.lua")]
+/// pub mod orders {}
+///
+/// orders::load(&mut con)?;
+/// orders::add::<()>(&mut con, "orders:eu", 3)?;
+/// let total: i64 = orders::total(&mut con, "orders:eu", redis_rs_macro::Args::new())?;
+/// ```
+/// ## Expansion
+/// ```rust,ignore
+/// pub mod orders {
+///     /// The source of the `orders` library
+///     pub const SOURCE: &str = include_str!("/path/to/crate/lua/orders.lua");
+///     /// The name of the library
+///     pub const LIBRARY: &str = "orders";
+///
+///     /// Load the library with `FUNCTION LOAD REPLACE`, replacing an older version of it
+///     pub fn load(con: &mut dyn redis::ConnectionLike) -> redis::RedisResult<()> {
+///         redis::cmd("FUNCTION").arg("LOAD").arg("REPLACE").arg(SOURCE).query::<String>(con).map(|_| ())
+///     }
+///
+///     /// Call `add` with `FCALL`
+///     pub fn add<T: redis::FromRedisValue>(
+///         con: &mut dyn redis::ConnectionLike,
+///         keys: impl redis::ToRedisArgs,
+///         args: impl redis::ToRedisArgs,
+///     ) -> redis::RedisResult<T> {
+///         let keys = keys.to_redis_args();
+///         redis::cmd("FCALL").arg("add").arg(keys.len()).arg(keys).arg(args).query(con)
+///     }
+///
+///     // `total` is called with `FCALL_RO`
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn redis_functions(args: TokenStream, item: TokenStream) -> TokenStream {
+    functions::expand_functions(args.into(), item.into())
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Try to take a distributed lock on a key
+///
+/// The arguments are the connection, as a `&mut` to anything that implements
+/// `redis::ConnectionLike`, the key, written with the argument syntax of [`redis!`], and
+/// `ttl = <milliseconds>`, after which the lock expires if it isn't released. The lock is taken with
+/// `SET key token NX PX ttl`, where the token is random, and is released with a Lua script that
+/// only deletes the key while it still holds the token, so a process whose lock expired can't
+/// release the lock of the next holder.
+///
+/// The macro returns a `redis::RedisResult<Option<redis_rs_macro::LockGuard>>`, which is `None`
+/// when the lock is held elsewhere. The guard dereferences to the connection, so commands can be
+/// sent through it, and releases the lock when dropped, or with `unlock`, which returns whether the
+/// lock was still held. `extend` resets the expiry of a held lock. With the `key-prefix` feature,
+/// the key gets the key prefix.
+///
+/// # Examples
+/// ```rust
+/// # fn run(con: &mut redis::Connection) -> redis::RedisResult<()> {
+/// use redis_rs_macro::{redis, redis_lock};
+///
+/// let id = 42;
+/// if let Some(mut guard) = redis_lock!(con, lock:order:{id}, ttl = 30000)? {
+///     redis!(HSET order:{id} status shipped).query::<()>(&mut *guard)?;
+///     guard.unlock()?;
+/// }
+/// # Ok(())
+/// # }
+/// ```
+/// ## Expansion
+/// ```rust
+/// # fn run(con: &mut redis::Connection) -> redis::RedisResult<()> {
+/// let id = 42;
+/// let guard = redis_rs_macro::Lock::new(format!("lock:order:{}", id), 30000).acquire(con)?;
+/// # Ok(())
+/// # }
+/// ```
+#[proc_macro]
+pub fn redis_lock(tokens: TokenStream) -> TokenStream {
+    lock::expand_lock(tokens.into())
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Run a block while holding a distributed lock, on an async connection
+///
+/// This is the async counterpart of [`redis_lock!`], taking the same arguments followed by a
+/// block. The macro evaluates to a future of a `redis::RedisResult<Option<T>>`: the lock is taken,
+/// the block runs and its value `T` is returned in `Some` once the lock is released. If the lock is
+/// held elsewhere, the block doesn't run and the result is `None`. Since the release is awaited,
+/// the lock is scoped to the block instead of a guard. To hold the lock outside of a block, take
+/// it with `Lock::acquire_async`, with the `async` feature, and await `unlock` on the guard.
+///
+/// The connection expression is evaluated once and borrowed until the lock is released, so write
+/// the body as `|con| body` to run it with the same connection. The body runs in an async block of its own, so a `?` in it, which takes any
+/// error that converts into a `redis::RedisError`, still releases the lock before the error is
+/// returned, and a `return` gives a `redis::RedisResult<T>` for the body. The calling crate needs
+/// the `aio` feature of `redis`.
+///
+/// # Examples
+/// ```rust,ignore
+/// use redis_rs_macro::{redis_async, redis_lock_async};
+///
+/// let mut con = client.get_multiplexed_async_connection().await?;
+/// let shipped = redis_lock_async!(&mut con, lock:order:{id}, ttl = 30000, |con| {
+///     redis_async!(con, HSET order:{id} status shipped).await?
+/// })
+/// .await?;
+/// ```
+/// ## Expansion
+/// ```rust,ignore
+/// let mut con = client.get_multiplexed_async_connection().await?;
+/// let shipped = async {
+///     let con = &mut con;
+///     let lock = redis_rs_macro::Lock::new(format!("lock:order:{}", id), 30000);
+///     let acquired: Option<String> = redis::Cmd::query_async(&lock.acquire_cmd(), &mut *con).await?;
+///     if acquired.is_none() {
+///         return Ok(None);
+///     }
+///     let output: redis::RedisResult<_> = async {
+///         let con = &mut *con;
+///         Ok({ /* .. */ })
+///     }
+///     .await;
+///     let released =
+///         redis::Cmd::query_async::<_, i64>(&lock.release_cmd().eval_cmd(), &mut *con).await;
+///     let output = output?;
+///     released?;
+///     Ok(Some(output))
+/// }
+/// .await?;
+/// ```
+#[proc_macro]
+pub fn redis_lock_async(tokens: TokenStream) -> TokenStream {
+    lock::expand_lock_async(tokens.into())
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Count a request against a rate limit, and return whether it is allowed
+///
+/// The arguments are the connection, as a `&mut` to anything that implements
+/// `redis::ConnectionLike`, the key of the limit, written with the argument syntax of [`redis!`] so
+/// that it can include the identifier being limited, and these options:
+/// - `limit = <requests>` and `window = <milliseconds>`, both required
+/// - `fixed` (the default) counts the requests of consecutive windows in a counter that expires at
+///   the end of each window
+/// - `sliding` counts the requests of the window that ends now, in a sorted set of request times,
+///   so bursts can't pass where two fixed windows meet
+///
+/// Each check runs as one Lua script, so concurrent checks can't both take the last request. The
+/// macro returns a `redis::RedisResult<redis_rs_macro::RateLimitStatus>`, with whether the request
+/// is `allowed`, how many requests are `remaining` in the window, and the `reset` time until the
+/// window resets, or for sliding windows, until the oldest counted request leaves it. With the
+/// `key-prefix` feature, the key gets the key prefix.
+///
+/// # Examples
+/// ```rust
+/// # fn run(con: &mut redis::Connection) -> redis::RedisResult<()> {
+/// use redis_rs_macro::redis_ratelimit;
+///
+/// let user = 42;
+/// let status = redis_ratelimit!(con, ratelimit:api:{user}, limit = 100, window = 60000, sliding)?;
+/// if !status.allowed {
+///     println!("retry in {:?}", status.reset);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+/// ## Expansion
+/// ```rust
+/// # fn run(con: &mut redis::Connection) -> redis::RedisResult<()> {
+/// use redis_rs_macro::{RateLimit, Window};
+///
+/// let user = 42;
+/// let status = RateLimit::new(format!("ratelimit:api:{}", user), 100, 60000, Window::Sliding)
+///     .check(con)?;
+/// # Ok(())
+/// # }
+/// ```
+#[proc_macro]
+pub fn redis_ratelimit(tokens: TokenStream) -> TokenStream {
+    ratelimit::expand_ratelimit(tokens.into())
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Run a block at most once for an idempotency key
+///
+/// The arguments are the connection, as a `&mut` to anything that implements
+/// `redis::ConnectionLike`, the key, written with the argument syntax of [`redis!`], these options,
+/// and the block:
+/// - `ttl = <seconds>`, required, how long the key is remembered
+/// - `replay` stores the response of the block as JSON, which requires the `json` feature, and
+///   returns it for duplicates. `json` or `msgpack` do the same in the given format.
+///
+/// The key is claimed with `SET key claim NX EX ttl`. If it was claimed, the block runs, and the
+/// macro returns `Ok(Idempotent::Executed(value))`. Otherwise the block doesn't run, and the macro
+/// returns `Ok(Idempotent::Duplicate(response))`, where `response` is the stored response of the
+/// run that claimed the key, or `None` while that run hasn't finished, or without `replay`.
+///
+/// The block evaluates to a `Result`, whose error can be converted from `redis::RedisError`. It runs
+/// in a closure, so a `?` in it returns from the block rather than from the enclosing function. When
+/// the block returns `Err`, the claim is released with `DEL` so that the operation can be retried,
+/// and the macro returns the error. With `replay`, the claim is replaced by the response once the
+/// block returns `Ok`. The claim holds a random token, and is only released or replaced while the key
+/// still holds it. With the `key-prefix` feature, the key gets the key prefix.
+///
+/// # Examples
+/// ```rust
+/// # fn charge(order: u64) -> redis::RedisResult<String> { Ok(String::new()) }
+/// # fn run(con: &mut redis::Connection) -> redis::RedisResult<()> {
+/// use redis_rs_macro::{redis_idempotent, Idempotent};
+///
+/// let request = "8f14e45f";
+/// let order = 42;
+/// match redis_idempotent!(con, idem:{request}, ttl = 86400, replay, { charge(order) })? {
+///     Idempotent::Executed(receipt) => println!("charged: {}", receipt),
+///     Idempotent::Duplicate(Some(receipt)) => println!("already charged: {}", receipt),
+///     Idempotent::Duplicate(None) => println!("still being charged"),
+/// }
+/// # Ok(())
+/// # }
+/// ```
+/// ## Expansion
+/// ```rust
+/// # fn charge(order: u64) -> redis::RedisResult<String> { Ok(String::new()) }
+/// # fn run(con: &mut redis::Connection) -> redis::RedisResult<()> {
+/// use redis_rs_macro::{Idempotency, Idempotent, Json};
+///
+/// let request = "8f14e45f";
+/// let order = 42;
+/// let idem = Idempotency::new(format!("idem:{}", request), 86400);
+/// let outcome = if idem.claim(con)? {
+///     match (|| charge(order))() {
+///         Ok(receipt) => {
+///             idem.complete(con, serde_json::to_string(&receipt).unwrap())?;
+///             Ok(Idempotent::Executed(receipt))
+///         }
+///         Err(err) => {
+///             let _ = idem.release(con);
+///             Err(err)
+///         }
+///     }
+/// } else {
+///     let response: Option<Json<String>> = idem.response(con)?;
+///     Ok(Idempotent::Duplicate(response.map(|Json(receipt)| receipt)))
+/// };
+/// # outcome.map(|_| ())
+/// # }
+/// ```
+#[proc_macro]
+pub fn redis_idempotent(tokens: TokenStream) -> TokenStream {
+    idempotency::expand_idempotent(tokens.into())
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Increment a counter that expires a fixed time after its first increment
+///
+/// The arguments are the connection, as a `&mut` to anything that implements
+/// `redis::ConnectionLike`, the key of the counter, written with the argument syntax of [`redis!`],
+/// and these options:
+/// - `ttl = <seconds>`, required, the time after the first increment at which the counter expires
+/// - `by = <n>`, the amount to add, 1 by default
+///
+/// The counter is incremented with `INCRBY` and its expiry is set with `EXPIRE` only when the
+/// increment created it, in one Lua script. Unlike an `INCR` followed by an `EXPIRE`, the counter
+/// can't be left without an expiry when the client fails in between, and later increments don't
+/// push the expiry back. The macro returns a `redis::RedisResult<i64>` with the new count. With the
+/// `key-prefix` feature, the key gets the key prefix.
+///
+/// # Examples
+/// ```rust
+/// # fn run(con: &mut redis::Connection) -> redis::RedisResult<()> {
+/// use redis_rs_macro::redis_counter;
+///
+/// let user = 42;
+/// let attempts = redis_counter!(con, login:attempts:{user}, ttl = 900)?;
+/// if attempts > 5 {
+///     println!("too many attempts");
+/// }
+/// # Ok(())
+/// # }
+/// ```
+/// ## Expansion
+/// ```rust
+/// # fn run(con: &mut redis::Connection) -> redis::RedisResult<()> {
+/// use redis_rs_macro::Counter;
+///
+/// let user = 42;
+/// let attempts = Counter::new(format!("login:attempts:{}", user), 900).incr(con, 1)?;
+/// # Ok(())
+/// # }
+/// ```
+#[proc_macro]
+pub fn redis_counter(tokens: TokenStream) -> TokenStream {
+    counter::expand_counter(tokens.into())
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Declare leaderboards in sorted sets, with typed members and scores
+///
+/// Each leaderboard is written as `struct Name(Member) = key;`, with optional attributes and
+/// visibility, where the key is written with the argument syntax of [`redis!`]. The
+/// substitutions of the key are the parameters of the generated `new` function, in the order
+/// they first appear, as with [`redis_template!`], so one declaration covers a leaderboard per
+/// game or season. The key can be followed by `, desc` (the default), where the highest score is
+/// the top, or `, asc`, where the lowest score is the top.
+///
+/// The generated struct wraps a `redis_rs_macro::Leaderboard`, and has these functions, which
+/// take the connection as a `&mut dyn redis::ConnectionLike`:
+/// - `add(con, &member, score)` sets the score of a member with `ZADD`
+/// - `top(con, n)` returns the best `n` members as `(member, score)` pairs, best first
+/// - `rank(con, &member)` returns the rank of a member, where the best member is 0, or `None`
+/// - `score(con, &member)` returns the score of a member, or `None`
+///
+/// `top` and `rank` use `ZREVRANGE` and `ZREVRANK` for `desc` leaderboards, and `ZRANGE` and
+/// `ZRANK` for `asc` ones. Members are written with `redis::ToRedisArgs` and read with
+/// `redis::FromRedisValue`. With the `key-prefix` feature, the key gets the key prefix.
+///
+/// # Examples
+/// ```rust
+/// # fn run(con: &mut redis::Connection) -> redis::RedisResult<()> {
+/// use redis_rs_macro::redis_leaderboard;
+///
+/// redis_leaderboard! {
+///     /// Points per game, highest first
+///     pub struct Points(String) = leaderboard:{game};
+///     /// Lap times per track, fastest first
+///     struct Laps(String) = laps:{track}, asc;
+/// }
+///
+/// let points = Points::new("chess");
+/// points.add(con, &"alice".to_string(), 1200.0)?;
+/// for (player, score) in points.top(con, 10)? {
+///     println!("{}: {}", player, score);
+/// }
+/// let rank = Laps::new("monza").rank(con, &"bob".to_string())?;
+/// # Ok(())
+/// # }
+/// ```
+/// ## Expansion
+/// ```rust
+/// use redis_rs_macro::{Leaderboard, Order};
+///
+/// /// Points per game, highest first
+/// pub struct Points(Leaderboard);
+///
+/// impl Points {
+///     pub fn new(game: impl redis::ToRedisArgs) -> Self {
+///         Self(Leaderboard::new(
+///             [b"leaderboard:".to_vec(), game.to_redis_args().concat()].concat(),
+///             Order::Descending,
+///         ))
+///     }
+///
+///     pub fn top(
+///         &self,
+///         con: &mut dyn redis::ConnectionLike,
+///         n: usize,
+///     ) -> redis::RedisResult<Vec<(String, f64)>> {
+///         self.0.top(con, n)
+///     }
+///
+///     // ..
+/// }
+/// ```
+#[proc_macro]
+pub fn redis_leaderboard(tokens: TokenStream) -> TokenStream {
+    leaderboard::expand_leaderboard(tokens.into())
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Cache the result of a function in Redis
+///
+/// The cached function reads the key with `GET`, and returns the stored value without running its
+/// body when there is one. Otherwise it runs the body, and stores an `Ok` result with `SET` before
+/// returning it, so that the next call finds it. Errors aren't cached. A stored value that doesn't
+/// deserialize, such as one written in another format by an older version, counts as a miss and
+/// is overwritten.
+///
+/// The arguments are these options:
+/// - `key = <key>`, required. A string literal is a format string, whose `{name}`s are filled in
+///   from the parameters like with `format!`, and anything else is a single key written with the
+///   argument syntax of [`redis!`]. With the `key-prefix` feature, the key gets the key prefix.
+/// - `ttl = <seconds>`, which sets the expiry of stored values with `EX`. Without it they don't
+///   expire.
+/// - `json` (the default, which requires the `json` feature) or `msgpack`, the format of stored
+///   values
+/// - `con = <parameter>`, the connection parameter, which is `con` by default
+///
+/// The connection is a `&mut` to anything that implements `redis::ConnectionLike`, or
+/// `redis::aio::ConnectionLike` for `async fn`s. The function returns a `Result` whose error can
+/// be converted from `redis::RedisError`. Errors of the `GET` are returned with `?`, while a
+/// failing `SET` is ignored, since the result was computed anyway and the next call computes it
+/// again. The body is wrapped in a closure, or an async block, so a `return` in it still stores
+/// the result.
+///
+/// # Examples
+/// ```rust
+/// # fn query_roles(id: u64) -> redis::RedisResult<Vec<String>> { Ok(vec![]) }
+/// use redis_rs_macro::redis_cached;
+///
+/// #[redis_cached(key = "user:{id}:roles", ttl = 300)]
+/// fn user_roles(con: &mut redis::Connection, id: u64) -> redis::RedisResult<Vec<String>> {
+///     query_roles(id)
+/// }
+/// ```
+/// ## Expansion
+/// ```rust
+/// # fn query_roles(id: u64) -> redis::RedisResult<Vec<String>> { Ok(vec![]) }
+/// use redis_rs_macro::Json;
+///
+/// fn user_roles(con: &mut redis::Connection, id: u64) -> redis::RedisResult<Vec<String>> {
+///     let key = format!("user:{id}:roles");
+///     let cached: Option<Json<_>> = match redis::cmd("GET").arg(&key).query(&mut *con) {
+///         Err(err) if err.kind() == redis::ErrorKind::TypeError => Ok(None),
+///         cached => cached,
+///     }?;
+///     match cached {
+///         Some(Json(value)) => Ok(value),
+///         None => {
+///             let result = (|| -> redis::RedisResult<Vec<String>> { query_roles(id) })();
+///             if let Ok(value) = &result {
+///                 let _ = redis::cmd("SET")
+///                     .arg(&key)
+///                     .arg(serde_json::to_string(value).unwrap())
+///                     .arg("EX")
+///                     .arg(300)
+///                     .query::<()>(&mut *con);
+///             }
+///             result
+///         }
+///     }
+/// }
+/// ```
+/// ## Async functions
+/// ```rust,ignore
+/// #[redis_cached(key = "user:{id}:roles", ttl = 300, msgpack)]
+/// async fn user_roles(
+///     con: &mut redis::aio::MultiplexedConnection,
+///     id: u64,
+/// ) -> Result<Vec<String>, AppError> {
+///     query_roles(id).await
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn redis_cached(args: TokenStream, item: TokenStream) -> TokenStream {
+    cache::expand_cached(args.into(), item.into())
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Read a value from the cache, or compute and cache it
+///
+/// This is the expression form of [`#[redis_cached]`](macro@redis_cached), for a single call site.
+/// The arguments are the connection, as a `&mut` to anything that implements
+/// `redis::ConnectionLike`, the `key`, `ttl` and format options of `#[redis_cached]`, and last, the
+/// closure that computes the value on a miss. The macro evaluates to a `redis::RedisResult` of the
+/// value.
+///
+/// # Examples
+/// ```rust
+/// # fn load_config(name: &str) -> Vec<String> { vec![] }
+/// # fn run(con: &mut redis::Connection) -> redis::RedisResult<()> {
+/// use redis_rs_macro::redis_cache;
+///
+/// let name = "features";
+/// let config: Vec<String> = redis_cache!(con, key = "config:{name}", ttl = 60, || load_config(name))?;
+/// # Ok(())
+/// # }
+/// ```
+/// ## Expansion
+/// ```rust
+/// # fn load_config(name: &str) -> Vec<String> { vec![] }
+/// # fn run(con: &mut redis::Connection) -> redis::RedisResult<()> {
+/// use redis_rs_macro::Json;
+///
+/// let name = "features";
+/// let config: Vec<String> = (|| -> redis::RedisResult<_> {
+///     let key = format!("config:{name}");
+///     let cached: Option<Json<_>> = match redis::cmd("GET").arg(&key).query(&mut *con) {
+///         Err(err) if err.kind() == redis::ErrorKind::TypeError => Ok(None),
+///         cached => cached,
+///     }?;
+///     match cached {
+///         Some(Json(value)) => Ok(value),
+///         None => {
+///             let value = load_config(name);
+///             let _ = redis::cmd("SET")
+///                 .arg(&key)
+///                 .arg(serde_json::to_string(&value).unwrap())
+///                 .arg("EX")
+///                 .arg(60)
+///                 .query::<()>(&mut *con);
+///             Ok(value)
+///         }
+///     }
+/// })()?;
+/// # Ok(())
+/// # }
+/// ```
+#[proc_macro]
+pub fn redis_cache(tokens: TokenStream) -> TokenStream {
+    cache::expand_cache_expr(tokens.into())
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Define reliable work queues, each with a producer and a worker loop
+///
+/// Each queue is written as `struct Name(Job) = key, options;`, where `Job` is the type of its
+/// jobs and the key is written with the argument syntax of [`redis!`]. Attributes, doc comments
+/// and visibility are passed on to the struct. The options are:
+/// - `visibility = <milliseconds>`, how long a worker has to handle a job before it is moved back
+///   to the queue, 30000 by default
+/// - `json` (the default, which requires the `json` feature) or `msgpack`, the format of jobs
+///
+/// The struct gets these functions, which take the connection as a
+/// `&mut dyn redis::ConnectionLike`:
+/// - `push(con, &job)` adds a job to the queue with `LPUSH`.
+/// - `work(con, handler)` is the worker loop. It takes the oldest job with `BRPOPLPUSH`, which
+///   moves it to the `<key>:processing` list in the same step, so a job taken by a worker that
+///   crashes is never lost. The job gets a lease in the `<key>:leases` sorted set, and is passed to
+///   the handler. Jobs the handler returns `Ok` for are removed from the processing list, while
+///   jobs it returns `Err` for stay there until their lease expires. It returns a
+///   `redis::RedisResult<()>` once the handler returns `Ok(ControlFlow::Break(()))` or a command
+///   fails.
+/// - `requeue_stale(con)` moves the jobs whose lease expired back to the queue, with a Lua script
+///   so that no job is moved twice, and returns how many were moved. The worker loop runs it when
+///   it starts, and again each time the visibility timeout has passed.
+/// - `queue()` returns the `redis_rs_macro::WorkQueue` with the keys and commands of the queue.
+///
+/// With the `key-prefix` feature, the key gets the key prefix.
+///
+/// # Examples
+/// ```rust
+/// # fn run(con: &mut redis::Connection) -> redis::RedisResult<()> {
+/// use redis_rs_macro::redis_work_queue;
+///
+/// redis_work_queue! {
+///     /// Emails waiting to be sent, as `(address, subject)`
+///     pub struct Emails((String, String)) = queue:emails, visibility = 60000;
+/// }
+///
+/// Emails::push(con, &("alice@example.com".to_string(), "Welcome".to_string()))?;
+/// Emails::work(con, |(address, subject)| {
+///     println!("sending {} to {}", subject, address);
+///     Ok::<(), std::io::Error>(())
+/// })?;
+/// # Ok(())
+/// # }
+/// ```
+/// ## Expansion
+/// ```rust
+/// # use redis_rs_macro::Json;
+/// /// Emails waiting to be sent, as `(address, subject)`
+/// pub struct Emails;
+///
+/// impl Emails {
+///     pub fn queue() -> redis_rs_macro::WorkQueue {
+///         redis_rs_macro::WorkQueue::new("queue:emails", 60000)
+///     }
+///
+///     pub fn push(
+///         con: &mut dyn redis::ConnectionLike,
+///         job: &(String, String),
+///     ) -> redis::RedisResult<()> {
+///         Self::queue().push(con, serde_json::to_string(job).unwrap())
+///     }
+///
+///     pub fn work<R, E>(
+///         con: &mut dyn redis::ConnectionLike,
+///         mut handler: impl FnMut((String, String)) -> Result<R, E>,
+///     ) -> redis::RedisResult<()>
+///     where
+///         R: redis_rs_macro::HandlerResult,
+///     {
+///         Self::queue().work(con, |Json(job): Json<(String, String)>| handler(job))
+///     }
+///
+///     pub fn requeue_stale(con: &mut dyn redis::ConnectionLike) -> redis::RedisResult<usize> {
+///         Self::queue().requeue_stale(con)
+///     }
+/// }
+/// ```
+#[proc_macro]
+pub fn redis_work_queue(tokens: TokenStream) -> TokenStream {
+    queue::expand_work_queue(tokens.into())
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Define delayed-job queues, each with a scheduler and a poller
+///
+/// Each queue is written as `struct Name(Job) = key, options;`, as with [`redis_work_queue!`]. The
+/// jobs are members of the sorted set at the key, scored by the unix time in milliseconds they
+/// run at, so scheduling a job that is already scheduled moves it to the new time. The options
+/// are:
+/// - `interval = <milliseconds>`, how long workers wait between looking for due jobs while none
+///   are due, 1000 by default
+/// - `json` (the default, which requires the `json` feature) or `msgpack`, the format of jobs
+///
+/// The struct gets these functions, which take the connection as a
+/// `&mut dyn redis::ConnectionLike`:
+/// - `schedule(con, &job, run_at)` schedules a job with `ZADD`, to run at a
+///   `redis_rs_macro::Timestamp` such as a `SystemTime`, or a `chrono::DateTime` or
+///   `time::OffsetDateTime` with the `chrono` and `time` features.
+/// - `schedule_in(con, &job, delay)` schedules a job to run after a `Duration`.
+/// - `pop_due(con, limit)` pops at most `limit` due jobs, with a Lua script that runs
+///   `ZRANGEBYSCORE` and `ZREM` together, so that each job is popped by a single poller.
+/// - `work(con, handler)` is the poller loop, which pops due jobs and passes them to the handler.
+///   Jobs it returns `Err` for are scheduled again to run after the poll interval. It returns a
+///   `redis::RedisResult<()>` once the handler returns `Ok(ControlFlow::Break(()))` or a command
+///   fails, scheduling the popped jobs that weren't handled again.
+/// - `queue()` returns the `redis_rs_macro::DelayQueue` with the key and commands of the queue.
+///
+/// With the `key-prefix` feature, the key gets the key prefix.
+///
+/// # Examples
+/// ```rust
+/// # fn run(con: &mut redis::Connection) -> redis::RedisResult<()> {
+/// use redis_rs_macro::redis_delay_queue;
+/// use std::time::Duration;
+///
+/// redis_delay_queue! {
+///     /// Reminders to send, as `(user, message)`
+///     pub struct Reminders((u64, String)) = queue:reminders, interval = 500;
+/// }
+///
+/// Reminders::schedule_in(con, &(42, "Your trial ends soon".to_string()), Duration::from_secs(3600))?;
+/// Reminders::work(con, |(user, message)| {
+///     println!("reminding {}: {}", user, message);
+///     Ok::<(), std::io::Error>(())
+/// })?;
+/// # Ok(())
+/// # }
+/// ```
+/// ## Expansion
+/// ```rust
+/// # use redis_rs_macro::Json;
+/// /// Reminders to send, as `(user, message)`
+/// pub struct Reminders;
+///
+/// impl Reminders {
+///     pub fn queue() -> redis_rs_macro::DelayQueue {
+///         redis_rs_macro::DelayQueue::new("queue:reminders", 500)
+///     }
+///
+///     pub fn schedule(
+///         con: &mut dyn redis::ConnectionLike,
+///         job: &(u64, String),
+///         run_at: impl redis_rs_macro::Timestamp,
+///     ) -> redis::RedisResult<()> {
+///         Self::queue().schedule(con, serde_json::to_string(job).unwrap(), run_at)
+///     }
+///
+///     pub fn schedule_in(
+///         con: &mut dyn redis::ConnectionLike,
+///         job: &(u64, String),
+///         delay: std::time::Duration,
+///     ) -> redis::RedisResult<()> {
+///         Self::schedule(con, job, std::time::SystemTime::now() + delay)
+///     }
+///
+///     pub fn pop_due(
+///         con: &mut dyn redis::ConnectionLike,
+///         limit: usize,
+///     ) -> redis::RedisResult<Vec<(u64, String)>> {
+///         let jobs: Vec<Json<(u64, String)>> = Self::queue().pop_due(con, limit)?;
+///         Ok(jobs.into_iter().map(|Json(job)| job).collect())
+///     }
+///
+///     pub fn work<R, E>(
+///         con: &mut dyn redis::ConnectionLike,
+///         mut handler: impl FnMut((u64, String)) -> Result<R, E>,
+///     ) -> redis::RedisResult<()>
+///     where
+///         R: redis_rs_macro::HandlerResult,
+///     {
+///         Self::queue().work(con, |Json(job): Json<(u64, String)>| handler(job))
+///     }
+/// }
+/// ```
+#[proc_macro]
+pub fn redis_delay_queue(tokens: TokenStream) -> TokenStream {
+    delay::expand_delay_queue(tokens.into())
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Define priority queues, each with typed push and pop functions and a worker loop
+///
+/// Each queue is written as `struct Name(Job) = key, options;`, as with [`redis_work_queue!`]. The
+/// jobs are members of the sorted set at the key, scored by their priority, and the job with the
+/// lowest priority is taken first. Pushing a job that is already queued changes its priority. The
+/// options are:
+/// - `timeout = <seconds>`, how long workers wait for a job at a time, 5 by default
+/// - `json` (the default, which requires the `json` feature) or `msgpack`, the format of jobs
+///
+/// The struct gets these functions, which take the connection as a
+/// `&mut dyn redis::ConnectionLike`:
+/// - `push(con, &job, priority)` adds a job with `ZADD`, with an `f64` priority.
+/// - `pop(con)` pops the job with the lowest priority with `ZPOPMIN`, returning `None` when the
+///   queue is empty.
+/// - `work(con, handler)` is the worker loop, which takes jobs with `BZPOPMIN` and passes them to
+///   the handler. Jobs it returns `Err` for are pushed back with the same priority. It returns a
+///   `redis::RedisResult<()>` once the handler returns `Ok(ControlFlow::Break(()))` or a command
+///   fails.
+/// - `queue()` returns the `redis_rs_macro::PriorityQueue` with the key and commands of the queue.
+///
+/// With the `key-prefix` feature, the key gets the key prefix.
+///
+/// # Examples
+/// ```rust
+/// # fn run(con: &mut redis::Connection) -> redis::RedisResult<()> {
+/// use redis_rs_macro::redis_priority_queue;
+///
+/// redis_priority_queue! {
+///     /// Builds to run, as `(repository, commit)`
+///     pub struct Builds((String, String)) = queue:builds;
+/// }
+///
+/// Builds::push(con, &("api".to_string(), "4f3e5d".to_string()), 1.0)?;
+/// Builds::work(con, |(repository, commit)| {
+///     println!("building {} at {}", repository, commit);
+///     Ok::<(), std::io::Error>(())
+/// })?;
+/// # Ok(())
+/// # }
+/// ```
+/// ## Expansion
+/// ```rust
+/// # use redis_rs_macro::Json;
+/// /// Builds to run, as `(repository, commit)`
+/// pub struct Builds;
+///
+/// impl Builds {
+///     pub fn queue() -> redis_rs_macro::PriorityQueue {
+///         redis_rs_macro::PriorityQueue::new("queue:builds", 5)
+///     }
+///
+///     pub fn push(
+///         con: &mut dyn redis::ConnectionLike,
+///         job: &(String, String),
+///         priority: f64,
+///     ) -> redis::RedisResult<()> {
+///         Self::queue().push(con, serde_json::to_string(job).unwrap(), priority)
+///     }
+///
+///     pub fn pop(con: &mut dyn redis::ConnectionLike) -> redis::RedisResult<Option<(String, String)>> {
+///         let popped: Option<Json<(String, String)>> = Self::queue().pop(con)?;
+///         Ok(popped.map(|Json(job)| job))
+///     }
+///
+///     pub fn work<R, E>(
+///         con: &mut dyn redis::ConnectionLike,
+///         mut handler: impl FnMut((String, String)) -> Result<R, E>,
+///     ) -> redis::RedisResult<()>
+///     where
+///         R: redis_rs_macro::HandlerResult,
+///     {
+///         Self::queue().work(con, |Json(job): Json<(String, String)>| handler(job))
+///     }
+/// }
+/// ```
+#[proc_macro]
+pub fn redis_priority_queue(tokens: TokenStream) -> TokenStream {
+    priority::expand_priority_queue(tokens.into())
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Define functions that build commands with the syntax of [`redis!`]
+///
+/// Each definition is written as `fn name(params) => command`, and definitions are separated by
+/// `;`. The parameters can be used in substitutions, and every function returns a `redis::Cmd`, or
+/// a `redis_rs_macro::TypedCmd` when the command has a `-> Type` annotation. Attributes, doc
+/// comments and visibility are passed on to the functions, so a module of definitions can hold the
+/// whole command surface of an application.
+///
+/// A `;` followed by anything other than another definition starts the bindings of the command, as
+/// with [`redis!`].
+///
+/// # Examples
+/// ```rust
+/// use redis_rs_macro::redis_def;
+///
+/// redis_def! {
+///     /// Read a user
+///     pub fn get_user(id: u64) => GET user:{id} -> Option<String>;
+///     pub fn set_user(id: u64, data: &str) => SET user:{id} {data} EX 3600;
+/// }
+///
+/// let cmd = set_user(42, "alice");
+/// ```
+/// ## Expansion
+/// ```rust
+/// /// Read a user
+/// pub fn get_user(id: u64) -> redis_rs_macro::TypedCmd<Option<String>> {
+///     let mut cmd = redis::cmd("GET");
+///     cmd.arg(format!("user:{}", id));
+///     redis_rs_macro::TypedCmd::new(cmd)
+/// }
+///
+/// pub fn set_user(id: u64, data: &str) -> redis::Cmd {
+///     let mut cmd = redis::cmd("SET");
+///     cmd.arg(format!("user:{}", id)).arg(data).arg("EX").arg(3600);
+///     cmd
+/// }
+/// ```
+#[proc_macro]
+pub fn redis_def(tokens: TokenStream) -> TokenStream {
+    def::expand_defs(tokens.into())
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Implement the methods of a trait with commands, for every connection
+///
+/// Each method without a body takes its command from a `#[redis(...)]` attribute, written with the
+/// syntax of [`redis!`], since a method body has to be valid Rust. The methods take `&mut self`,
+/// which is the connection, and return the reply as a `redis::RedisResult`. The parameters of a
+/// method can be used in substitutions. Methods with a default body are left as they are.
+///
+/// The trait is implemented for every type that implements `redis::ConnectionLike`. With
+/// `#[redis_commands(async)]`, a second trait named after the first with an `Async` suffix is
+/// generated too, whose methods return futures and which is implemented for every
+/// `redis::aio::ConnectionLike`. This needs the `aio` feature of `redis`.
+///
+/// # Examples
+/// ```rust
+/// # fn run(con: &mut redis::Connection) -> redis::RedisResult<()> {
+/// use redis::RedisResult;
+/// use redis_rs_macro::redis_commands;
+///
+/// #[redis_commands]
+/// trait Users {
+///     #[redis(GET user:{id})]
+///     fn get_user(&mut self, id: u64) -> RedisResult<Option<String>>;
+///
+///     #[redis(SET user:{id} {data} EX 3600)]
+///     fn set_user(&mut self, id: u64, data: &str) -> RedisResult<()>;
+/// }
+///
+/// con.set_user(42, "alice")?;
+/// let user = con.get_user(42)?;
+/// # Ok(())
+/// # }
+/// ```
+/// ## Expansion
+/// ```rust
+/// use redis::RedisResult;
+///
+/// trait Users {
+///     fn get_user(&mut self, id: u64) -> RedisResult<Option<String>>;
+///
+///     fn set_user(&mut self, id: u64, data: &str) -> RedisResult<()>;
+/// }
+///
+/// impl<C: redis::ConnectionLike> Users for C {
+///     fn get_user(&mut self, id: u64) -> RedisResult<Option<String>> {
+///         redis::cmd("GET").arg(format!("user:{}", id)).query(self)
+///     }
+///
+///     fn set_user(&mut self, id: u64, data: &str) -> RedisResult<()> {
+///         redis::cmd("SET")
+///             .arg(format!("user:{}", id))
+///             .arg(data)
+///             .arg("EX")
+///             .arg(3600)
+///             .query(self)
+///     }
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn redis_commands(args: TokenStream, item: TokenStream) -> TokenStream {
+    trait_commands::expand_trait(args.into(), item.into())
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Iterate over the items of a `SCAN`, `HSCAN`, `SSCAN` or `ZSCAN` command
+///
+/// The first argument is the connection, as a `&mut` to anything that implements
+/// `redis::ConnectionLike`, followed by a comma and the command without its cursor, which is
+/// inserted by the macro. The result is a lazy `redis_rs_macro::Scan` iterator of
+/// `redis::RedisResult`s, which sends the command again with each cursor the server returns until
+/// the scan is done. A `-> Type` after the command sets the type of the items, such as `String` for
+/// keys and members, or `(String, String)` for the fields and values of `HSCAN`.
+///
+/// The server can return an item more than once; `Scan::unique` skips the repeats.
+///
+/// # Examples
+/// ```rust
+/// # fn run(con: &mut redis::Connection) -> redis::RedisResult<()> {
+/// use redis_rs_macro::redis_scan;
+///
+/// let id = 42;
+/// let keys = redis_scan!(con, SCAN MATCH user:{id}:* COUNT 100 -> String)
+///     .unique()
+///     .collect::<redis::RedisResult<Vec<_>>>()?;
+/// for field in redis_scan!(con, HSCAN user:{id} -> (String, String)) {
+///     let (name, value) = field?;
+/// }
+/// # Ok(())
+/// # }
+/// ```
+/// ## Expansion
+/// ```rust
+/// # fn run(con: &mut redis::Connection) -> redis::RedisResult<()> {
+/// let id = 42;
+/// let keys = redis_rs_macro::Scan::<String, _>::new(con, |cursor: u64| {
+///     let mut cmd = redis::cmd("SCAN");
+///     cmd.arg(cursor)
+///         .arg("MATCH")
+///         .arg(format!("user:{}:*", id))
+///         .arg("COUNT")
+///         .arg(100);
+///     cmd
+/// })
+/// .unique()
+/// .collect::<redis::RedisResult<Vec<_>>>()?;
+/// # Ok(())
+/// # }
+/// ```
+#[proc_macro]
+pub fn redis_scan(tokens: TokenStream) -> TokenStream {
+    scan::expand_scan(tokens.into())
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Stream the items of a `SCAN`, `HSCAN`, `SSCAN` or `ZSCAN` command on an async connection
+///
+/// This is the async counterpart of [`redis_scan!`], taking a `&mut` to anything that implements
+/// `redis::aio::ConnectionLike`. It evaluates to an `impl futures::Stream` of `redis::RedisResult`s,
+/// which queries the next batch once the items before it have been read, so it works with the
+/// combinators of `futures::StreamExt`. The calling crate needs the `futures` crate and the `aio`
+/// feature of `redis`. Unlike `Scan::unique`, the stream doesn't skip repeated items.
+///
+/// # Examples
+/// ```rust,ignore
+/// use futures::StreamExt;
+/// use redis_rs_macro::redis_scan_async;
+///
+/// let mut con = client.get_async_connection().await?;
+/// let mut keys = std::pin::pin!(redis_scan_async!(&mut con, SCAN MATCH user:* -> String));
+/// while let Some(key) = keys.next().await {
+///     println!("{}", key?);
+/// }
+/// ```
+/// ## Expansion
+/// ```rust,ignore
+/// futures::stream::unfold(
+///     (&mut con, |cursor: u64| { /* SCAN {cursor} MATCH user:* */ }, Some(0u64), VecDeque::<String>::new()),
+///     |(con, mut build, mut cursor, mut items)| async move {
+///         // Pop the next item, or query the next batch while the cursor isn't done
+///     },
+/// )
+/// ```
+#[proc_macro]
+pub fn redis_scan_async(tokens: TokenStream) -> TokenStream {
+    scan::expand_scan_async(tokens.into())
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Derive `redis_rs_macro::ToRedisFields` and `redis::FromRedisValue` for a struct that is stored
+/// as the fields of stream entries
+///
+/// Each named field maps to a stream field of the same name, or the name given with
+/// `#[redis(rename = "...")]`. Field values are written with `redis::ToRedisArgs` and read with
+/// `redis::FromRedisValue`. Fields whose value writes no arguments, such as a `None`, are left out,
+/// and fields missing from an entry are read as `None` for `Option`s and are an error otherwise.
+///
+/// The struct can then be spread into `XADD` with `{*expr}`, read from `XRANGE` replies, and
+/// handled by [`redis_consume!`] as a `StreamEntry<Struct>`.
+///
+/// # Examples
+/// ```rust
+/// # fn run(con: &mut redis::Connection) -> redis::RedisResult<()> {
+/// use redis_rs_macro::{redis, RedisStreamEntry};
+///
+/// #[derive(RedisStreamEntry)]
+/// struct Order {
+///     item: String,
+///     #[redis(rename = "qty")]
+///     quantity: u32,
+///     note: Option<String>,
+/// }
+///
+/// let order = Order { item: "book".into(), quantity: 2, note: None };
+/// redis!(XADD orders * {*order}).query::<String>(con)?;
+/// let entries: Vec<(String, Order)> = redis!(XRANGE orders - +).query(con)?;
+/// # Ok(())
+/// # }
+/// ```
+/// ## Expansion
+/// ```rust
+/// # struct Order { item: String, quantity: u32, note: Option<String> }
+/// use redis_rs_macro::ToRedisFields;
+///
+/// impl ToRedisFields for Order {
+///     fn write_redis_fields<W: ?Sized + redis::RedisWrite>(&self, out: &mut W) {
+///         // Writes "item", "qty" and "note" with their values, leaving out empty ones
+///     }
+/// }
+///
+/// impl redis::FromRedisValue for Order {
+///     fn from_redis_value(value: &redis::Value) -> redis::RedisResult<Self> {
+///         // Reads "item", "qty" and "note" from the alternating names and values
+///         # unimplemented!()
+///     }
+/// }
+/// ```
+#[proc_macro_derive(RedisStreamEntry, attributes(redis))]
+pub fn derive_redis_stream_entry(tokens: TokenStream) -> TokenStream {
+    entry::expand_entry(tokens.into())
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Derive `redis::ToRedisArgs`, `redis_rs_macro::ToRedisFields` and `redis::FromRedisValue` for a
+/// struct that is stored as the fields of a hash
+///
+/// Each named field maps to a hash field of the same name, or the name given with
+/// `#[redis(rename = "...")]`. The struct is written as alternating field names and values, so it
+/// can be given to `HSET` as `{user}` or `{*user}`, and is read from the reply of `HGETALL`. As
+/// with [`RedisStreamEntry`](derive@RedisStreamEntry), fields whose value writes no arguments,
+/// such as a `None`, are left out, and fields missing from the hash are read as `None` for
+/// `Option`s and are an error otherwise.
+///
+/// Fields marked `#[redis(default)]` are read as the `Default` of their type when the hash doesn't
+/// have them, for fields added after hashes were written. Fields marked `#[redis(skip)]` aren't
+/// written at all, and are always read as their `Default`.
+///
+/// # Examples
+/// ```rust
+/// # fn run(con: &mut redis::Connection) -> redis::RedisResult<()> {
+/// use redis_rs_macro::{redis, RedisHash};
+///
+/// #[derive(RedisHash)]
+/// struct User {
+///     name: String,
+///     #[redis(rename = "mail")]
+///     email: Option<String>,
+///     #[redis(default)]
+///     visits: u64,
+///     #[redis(skip)]
+///     cached_at: Option<std::time::Instant>,
+/// }
+///
+/// let id = 42;
+/// let user = User { name: "alice".into(), email: None, visits: 0, cached_at: None };
+/// redis!(HSET user:{id} {user}).query::<()>(con)?;
+/// let user: User = redis!(HGETALL user:{id}).query(con)?;
+/// # Ok(())
+/// # }
+/// ```
+/// ## Expansion
+/// ```rust
+/// # struct User { name: String, email: Option<String>, visits: u64, cached_at: Option<std::time::Instant> }
+/// use redis_rs_macro::ToRedisFields;
+///
+/// impl ToRedisFields for User {
+///     fn write_redis_fields<W: ?Sized + redis::RedisWrite>(&self, out: &mut W) {
+///         // Writes "name", "mail" and "visits" with their values, leaving out empty ones
+///     }
+/// }
+///
+/// impl redis::ToRedisArgs for User {
+///     fn write_redis_args<W: ?Sized + redis::RedisWrite>(&self, out: &mut W) {
+///         ToRedisFields::write_redis_fields(self, out)
+///     }
+///
+///     fn is_single_arg(&self) -> bool {
+///         false
+///     }
+/// }
+///
+/// impl redis::FromRedisValue for User {
+///     fn from_redis_value(value: &redis::Value) -> redis::RedisResult<Self> {
+///         // Reads "name", "mail" and "visits" from the alternating names and values, with a
+///         // missing "visits" read as 0, and `cached_at` as `None`
+///         # unimplemented!()
+///     }
+/// }
+/// ```
+#[proc_macro_derive(RedisHash, attributes(redis))]
+pub fn derive_redis_hash(tokens: TokenStream) -> TokenStream {
+    hash::expand_hash(tokens.into())
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Derive `redis::ToRedisArgs` for an enum of unit variants, so that it can be a command argument
+///
+/// Each variant is written as its name, or in the case given with `#[redis(rename_all = "...")]`
+/// on the enum: `lowercase`, `UPPERCASE`, `PascalCase`, `camelCase`, `snake_case`,
+/// `SCREAMING_SNAKE_CASE`, `kebab-case` or `SCREAMING-KEBAB-CASE`, which split the name into words
+/// at each capital as with serde. A variant marked `#[redis(rename = "...")]` is written as the
+/// given name instead. Variants with fields, and two variants that would be written the same, are
+/// compile errors.
+///
+/// # Examples
+/// ```rust
+/// use redis_rs_macro::{redis, RedisArg};
+///
+/// #[derive(RedisArg)]
+/// #[redis(rename_all = "snake_case")]
+/// enum OrderStatus {
+///     Pending,
+///     PaymentFailed,
+///     #[redis(rename = "sent")]
+///     Shipped,
+/// }
+///
+/// let id = 42;
+/// let status = OrderStatus::PaymentFailed;
+/// let cmd = redis!(HSET order:{id} status {status});
+/// assert_eq!(
+///     cmd.get_packed_command(),
+///     redis::cmd("HSET").arg("order:42").arg("status").arg("payment_failed").get_packed_command(),
+/// );
+/// ```
+/// ## Expansion
+/// ```rust
+/// # enum OrderStatus { Pending, PaymentFailed, Shipped }
+/// impl redis::ToRedisArgs for OrderStatus {
+///     fn write_redis_args<W: ?Sized + redis::RedisWrite>(&self, out: &mut W) {
+///         let name: &str = match *self {
+///             Self::Pending => "pending",
+///             Self::PaymentFailed => "payment_failed",
+///             Self::Shipped => "sent",
+///         };
+///         out.write_arg(name.as_bytes())
+///     }
+/// }
+/// ```
+#[proc_macro_derive(RedisArg, attributes(redis))]
+pub fn derive_redis_arg(tokens: TokenStream) -> TokenStream {
+    arg::expand_arg(tokens.into())
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Derive `redis::FromRedisValue` for a struct that is read from an array reply, by field name or
+/// by position
+///
+/// Structs with named fields are read from replies of alternating field names and values, such as
+/// those of `CONFIG GET`, `HGETALL` or `XINFO STREAM`, where each field is read from the value of
+/// the same name, or of the name given with `#[redis(rename = "...")]`. Names that the struct
+/// doesn't have are ignored. Tuple structs, and structs marked `#[redis(positional)]`, are read
+/// from the items of an array reply in order, such as the `[id, fields]` entries of `XRANGE` or the
+/// replies of module commands, and a reply with more items than the struct has fields is an
+/// error.
+///
+/// Fields missing from the reply are read as `None` for `Option`s, and as the `Default` of their
+/// type when they are marked `#[redis(default)]`. Other missing fields are an error, and so are
+/// values of the wrong type, with the name or position of the field in the error.
+///
+/// # Examples
+/// ```rust
+/// # fn run(con: &mut redis::Connection) -> redis::RedisResult<()> {
+/// use redis_rs_macro::{redis, RedisReply};
+///
+/// #[derive(RedisReply)]
+/// struct Limits {
+///     maxmemory: u64,
+///     #[redis(rename = "maxmemory-policy")]
+///     policy: String,
+///     #[redis(default)]
+///     maxclients: u64,
+/// }
+///
+/// #[derive(RedisReply)]
+/// struct Entry(String, Vec<(String, String)>);
+///
+/// let limits: Limits = redis!(CONFIG GET maxmemory maxmemory-policy maxclients).query(con)?;
+/// let entries: Vec<Entry> = redis!(XRANGE events - + COUNT 10).query(con)?;
+/// # Ok(())
+/// # }
+/// ```
+/// ## Expansion
+/// ```rust
+/// # struct Entry(String, Vec<(String, String)>);
+/// impl redis::FromRedisValue for Entry {
+///     fn from_redis_value(value: &redis::Value) -> redis::RedisResult<Self> {
+///         // Checks that `value` is an array of at most 2 items, and reads them as the fields,
+///         // naming the field in the error of a missing or invalid item
+///         # unimplemented!()
+///     }
+/// }
+/// ```
+#[proc_macro_derive(RedisReply, attributes(redis))]
+pub fn derive_redis_reply(tokens: TokenStream) -> TokenStream {
+    reply::expand_reply(tokens.into())
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Derive `json_set` and `json_get` methods for a type that is stored as a RedisJSON document
+///
+/// Requires the `json` feature. The type is serialized and deserialized with serde, so it also
+/// has to derive `serde::Serialize` and `serde::Deserialize`. The generated methods are:
+///
+/// - `value.json_set(con, key, path)`, which stores the value at `path` of the document at `key`
+///   with `JSON.SET` and returns a `redis::RedisResult<()>`. The path `$` replaces the whole
+///   document.
+/// - `Type::json_get(con, key, path)`, which reads the value at `path` with `JSON.GET` and returns
+///   a `redis::RedisResult<Option<Type>>`, which is `None` when the key or path doesn't exist. A
+///   JSONPath starting with `$` can match many values, of which the first is read.
+///
+/// The connection is a `&mut` to anything that implements `redis::ConnectionLike`, and the key is
+/// anything that implements `redis::ToRedisArgs`, such as a [`redis_key!`] key.
+///
+/// # Examples
+/// ```rust
+/// # #[cfg(feature = "json")]
+/// # fn run(con: &mut redis::Connection) -> redis::RedisResult<()> {
+/// use redis_rs_macro::RedisJson;
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(RedisJson, Serialize, Deserialize)]
+/// struct Cart {
+///     items: Vec<String>,
+///     total: f64,
+/// }
+///
+/// let cart = Cart { items: vec!["book".into()], total: 12.5 };
+/// cart.json_set(con, "cart:42", "$")?;
+/// let cart = Cart::json_get(con, "cart:42", "$")?;
+/// # Ok(())
+/// # }
+/// ```
+/// ## Expansion
+/// ```rust
+/// # struct Cart { items: Vec<String>, total: f64 }
+/// impl Cart {
+///     pub fn json_set<C, K>(&self, con: &mut C, key: K, path: &str) -> redis::RedisResult<()>
+///     where
+///         C: redis::ConnectionLike,
+///         K: redis::ToRedisArgs,
+///     {
+///         // JSON.SET key path <serialized self>
+///         # unimplemented!()
+///     }
+///
+///     pub fn json_get<C, K>(con: &mut C, key: K, path: &str) -> redis::RedisResult<Option<Self>>
+///     where
+///         C: redis::ConnectionLike,
+///         K: redis::ToRedisArgs,
+///     {
+///         // JSON.GET key path, deserialized from the first match of a `$` path
+///         # unimplemented!()
+///     }
+/// }
+/// ```
+#[proc_macro_derive(RedisJson)]
+pub fn derive_redis_json(tokens: TokenStream) -> TokenStream {
+    json::expand_json(tokens.into())
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Derive an `ft_create` function that builds the `FT.CREATE` command of a RediSearch index over
+/// the documents of a struct
+///
+/// Each field is a field of the schema, named as the field or by its `#[redis(rename = "...")]`,
+/// so that the same struct can derive [`RedisHash`] to store the documents. Fields marked
+/// `#[redis(skip)]` or `#[search(skip)]` aren't indexed. The kind of a field is given with
+/// `#[search(...)]`, or is inferred from its type: numbers are `NUMERIC`, strings `TEXT` and
+/// booleans `TAG`, and an `Option` is indexed as the type it holds. Fields of other types need a
+/// kind or `skip`.
+///
+/// The options of a field are:
+///
+/// - `text`, `numeric`, `tag` or `geo`: the kind of the field.
+/// - `weight = 2.0`: the weight of a `TEXT` field in the scores of searches, which is positive.
+/// - `nostem`: don't stem the words of a `TEXT` field.
+/// - `separator = ","`: the character between the tags of a `TAG` field.
+/// - `sortable`: the field can be used in `SORTBY`. `GEO` fields can't be sortable.
+/// - `noindex`: the field isn't searched, which is only useful with `sortable`.
+///
+/// On the struct, `#[search(on = "json")]` indexes RedisJSON documents instead of hashes, where
+/// each field is read from the member of the same name with `$.name AS name`, and
+/// `#[search(prefix = "...")]`, which can be given many times, limits the index to keys with one
+/// of the prefixes. Options that don't apply to the kind of their field are compile errors.
+///
+/// # Examples
+/// ```rust
+/// # fn run(con: &mut redis::Connection) -> redis::RedisResult<()> {
+/// use redis_rs_macro::{RedisHash, RedisSearch};
+///
+/// #[derive(RedisHash, RedisSearch)]
+/// #[search(prefix = "product:")]
+/// struct Product {
+///     #[search(weight = 2.0, sortable)]
+///     name: String,
+///     price: f64,
+///     #[search(tag, separator = "|")]
+///     tags: String,
+///     #[redis(skip)]
+///     cached: bool,
+/// }
+///
+/// Product::ft_create("idx:products").query::<()>(con)?;
+/// # Ok(())
+/// # }
+/// ```
+/// ## Expansion
+/// ```rust
+/// # struct Product;
+/// impl Product {
+///     pub fn ft_create(index: &str) -> redis::Cmd {
+///         redis::cmd("FT.CREATE")
+///             .arg(index)
+///             .arg("ON")
+///             .arg("HASH")
+///             .arg("PREFIX").arg(1usize).arg("product:")
+///             .arg("SCHEMA")
+///             .arg("name").arg("TEXT").arg("WEIGHT").arg("2").arg("SORTABLE")
+///             .arg("price").arg("NUMERIC")
+///             .arg("tags").arg("TAG").arg("SEPARATOR").arg("|")
+///             .clone()
+///     }
+/// }
+/// ```
+#[proc_macro_derive(RedisSearch, attributes(redis, search))]
+pub fn derive_redis_search(tokens: TokenStream) -> TokenStream {
+    schema::expand_schema(tokens.into())
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Publish a serialized payload on a channel
+///
+/// The arguments are the connection, as a `&mut` to anything that implements
+/// `redis::ConnectionLike`, the channel, written with the argument syntax of [`redis!`], and the
+/// payload. The payload is serialized as JSON, which needs the `json` feature, unless it starts with
+/// the name of another format: `msgpack` (with the `msgpack` feature), `bytes` or `csv`, as with the
+/// markers of substitutions. The macro returns the number of subscribers that received the message
+/// as a `redis::RedisResult<i64>`. Serialization errors are returned with `?`.
+///
+/// Subscribers read the payloads with `redis_rs_macro::Json<T>` or `redis_rs_macro::Msgpack<T>`,
+/// so both ends share the format.
+///
+/// # Examples
+/// ```rust
+/// # #[cfg(feature = "json")]
+/// # fn run(con: &mut redis::Connection) -> redis::RedisResult<()> {
+/// use redis_rs_macro::redis_publish;
+///
+/// let order = ("book", 2);
+/// let region = "eu";
+/// redis_publish!(con, "orders.created", &order)?;
+/// redis_publish!(con, orders:{region}, msgpack &order)?;
+/// # Ok(())
+/// # }
+/// ```
+/// ## Expansion
+/// ```rust
+/// # fn run(con: &mut redis::Connection) -> redis::RedisResult<()> {
+/// let order = ("book", 2);
+/// let region = "eu";
+/// redis::cmd("PUBLISH")
+///     .arg("orders.created")
+///     .arg(serde_json::to_string(&order).unwrap())
+///     .query::<i64>(con)?;
+/// redis::cmd("PUBLISH")
+///     .arg(format!("orders:{}", region))
+///     .arg(rmp_serde::to_vec(&order).unwrap())
+///     .query::<i64>(con)?;
+/// # Ok(())
+/// # }
+/// ```
+#[proc_macro]
+pub fn redis_publish(tokens: TokenStream) -> TokenStream {
+    publish::expand_publish(tokens.into())
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Consume the entries of a stream as a member of a consumer group
+///
+/// The arguments are the connection, as a `&mut` to anything that implements
+/// `redis::ConnectionLike`, the options of the consumer, and a handler. The options are written
+/// with the argument syntax of [`redis!`], each as a keyword followed by its value:
+///
+/// - `STREAM`, `GROUP` and `CONSUMER` name the stream, the consumer group and the consumer.
+/// - `COUNT` limits how many entries are read at a time.
+/// - `BLOCK` sets how many milliseconds a read waits for new entries, 5000 by default.
+/// - `START` sets where a new group starts, `$` (the end of the stream) by default.
+/// - `CLAIM` sets a minimum idle time in milliseconds, as a number or `{expr}`. Entries that other
+///   consumers left pending for that long, such as those of consumers that crashed, are claimed
+///   with `XAUTOCLAIM` and handled again. This happens when the consumer starts, and again each
+///   time the idle time has passed.
+/// - `MAX_DELIVERIES` and `DEAD_LETTER`, given together with `CLAIM`, move claimed entries that
+///   were delivered more than `MAX_DELIVERIES` times to the `DEAD_LETTER` stream with `XADD`, and
+///   acknowledge them, instead of handling them again.
+///
+/// The group is created with `XGROUP CREATE .. MKSTREAM`, unless it already exists. Then entries
+/// are read with `XREADGROUP` and passed to the handler as `redis_rs_macro::StreamEntry<T>`s, whose
+/// fields are read as any type that implements `redis::FromRedisValue`, such as a `HashMap` or a
+/// struct deriving [`RedisStreamEntry`](derive@RedisStreamEntry). Entries the handler returns `Ok`
+/// for are acknowledged with `XACK`, while entries it returns `Err` for stay pending. The macro
+/// returns a `redis::RedisResult<()>` once the handler returns `Ok(ControlFlow::Break(()))` or a
+/// command fails.
+///
+/// # Examples
+/// ```rust
+/// # fn run(con: &mut redis::Connection) -> redis::RedisResult<()> {
+/// use redis_rs_macro::{redis_consume, StreamEntry};
+/// use std::collections::HashMap;
+///
+/// let id = 1;
+/// redis_consume!(con, STREAM orders GROUP workers CONSUMER worker:{id} COUNT 10 CLAIM 60000,
+///     |entry: StreamEntry<HashMap<String, String>>| {
+///         println!("{}: {:?}", entry.id, entry.fields);
+///         Ok::<(), std::io::Error>(())
+///     },
+/// )?;
+/// # Ok(())
+/// # }
+/// ```
+/// ## Expansion
+/// ```rust
+/// # fn run(con: &mut redis::Connection) -> redis::RedisResult<()> {
+/// use redis_rs_macro::{StreamConsumer, StreamEntry};
+/// use std::collections::HashMap;
+///
+/// let id = 1;
+/// StreamConsumer::new("orders", "workers", format!("worker:{}", id))
+///     .count(10)
+///     .claim(60000)
+///     .run(con, |entry: StreamEntry<HashMap<String, String>>| {
+///         println!("{}: {:?}", entry.id, entry.fields);
+///         Ok::<(), std::io::Error>(())
+///     })?;
+/// # Ok(())
+/// # }
+/// ```
+#[proc_macro]
+pub fn redis_consume(tokens: TokenStream) -> TokenStream {
+    consume::expand_consume(tokens.into())
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Subscribe to channels and pass the payloads of their messages to handlers
+///
+/// The first argument is a `&mut redis::Connection`, followed by a comma and a list of
+/// `channels => handler` routes. The channels are written with the argument syntax of [`redis!`],
+/// so a spread subscribes one handler to many channels. Each handler is a closure taking the
+/// payload as any type that implements `redis::FromRedisValue`, which has to be written out. With
+/// the `json` or `msgpack` feature, payloads are deserialized with serde by taking a
+/// `redis_rs_macro::Json<T>` or `redis_rs_macro::Msgpack<T>`.
+///
+/// The macro subscribes to every channel and passes messages on until a handler returns
+/// `ControlFlow::Break(())`, which returns `Ok(())`, or until receiving a message or reading its
+/// payload fails, which returns the error. Handlers can also return `()` to keep going.
+///
+/// # Examples
+/// ```rust
+/// # fn run(con: &mut redis::Connection) -> redis::RedisResult<()> {
+/// use redis_rs_macro::redis_subscribe;
+/// use std::ops::ControlFlow;
+///
+/// let region = "eu";
+/// let mut total = 0;
+/// redis_subscribe!(con,
+///     orders:{region} => |amount: i64| total += amount,
+///     shutdown => |_: String| ControlFlow::Break(()),
+/// )?;
+/// # Ok(())
+/// # }
+/// ```
+/// ## Expansion
+/// ```rust
+/// # fn run(con: &mut redis::Connection) -> redis::RedisResult<()> {
+/// use std::ops::ControlFlow;
+///
+/// let region = "eu";
+/// let mut total = 0;
+/// redis_rs_macro::Subscriber::new()
+///     .on(format!("orders:{}", region), |amount: i64| total += amount)
+///     .on("shutdown", |_: String| ControlFlow::Break(()))
+///     .run(con)?;
+/// # Ok(())
+/// # }
+/// ```
+/// ## Patterns
+/// A route that starts with `pattern` subscribes to glob-style patterns with `PSUBSCRIBE`. Its
+/// handler takes the name of the channel the message was sent on, followed by the payload, and
+/// gets every message that matched its patterns. A channel that is itself named `pattern` can be
+/// written on its own or quoted.
+/// ```rust
+/// # fn run(con: &mut redis::Connection) -> redis::RedisResult<()> {
+/// use redis_rs_macro::redis_subscribe;
+///
+/// redis_subscribe!(con,
+///     pattern orders:* => |channel: String, amount: i64| println!("{}: {}", channel, amount),
+///     pattern users:* sessions:* => |channel: String, id: u64| println!("{}: {}", channel, id),
+/// )?;
+/// # Ok(())
+/// # }
+/// ```
+/// ## Expansion
+/// ```rust
+/// # fn run(con: &mut redis::Connection) -> redis::RedisResult<()> {
+/// redis_rs_macro::Subscriber::new()
+///     .on_pattern("orders:*", |channel: String, amount: i64| {
+///         println!("{}: {}", channel, amount)
+///     })
+///     .on_pattern(&["users:*", "sessions:*"], |channel: String, id: u64| {
+///         println!("{}: {}", channel, id)
+///     })
+///     .run(con)?;
+/// # Ok(())
+/// # }
+/// ```
+#[proc_macro]
+pub fn redis_subscribe(tokens: TokenStream) -> TokenStream {
+    subscribe::expand_subscribe(tokens.into())
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Subscribe to keyspace notifications of a list of events
+///
+/// The arguments are a `&mut redis::Connection`, the number of the database, the list of events
+/// as named in their `__keyevent@<db>__:<event>` channels, such as `[expired, del, set]`, and a
+/// handler. Event names are checked when compiling. The handler takes every notification as a
+/// `redis_rs_macro::KeyEvent` holding the database, the `KeyEventKind` and the key, and returns
+/// `()` or a `ControlFlow` as with [`redis_subscribe!`].
+///
+/// Before subscribing, the flags the events need are added to the `notify-keyspace-events` setting
+/// of the server with `CONFIG SET`, unless they are already set. On servers that don't allow
+/// `CONFIG`, build a `redis_rs_macro::KeyEvents` and call its `run` method instead.
+///
+/// # Examples
+/// ```rust
+/// # fn run(con: &mut redis::Connection) -> redis::RedisResult<()> {
+/// use redis_rs_macro::{redis_keyevents, KeyEventKind};
+///
+/// redis_keyevents!(con, 0, [expired, del], |event| match event.kind {
+///     KeyEventKind::Expired => println!("{} expired", event.key),
+///     _ => println!("{} was deleted", event.key),
+/// })?;
+/// # Ok(())
+/// # }
+/// ```
+/// ## Expansion
+/// ```rust
+/// # fn run(con: &mut redis::Connection) -> redis::RedisResult<()> {
+/// use redis_rs_macro::{KeyEventKind, KeyEvents};
+///
+/// let events = KeyEvents::new(0, &[KeyEventKind::Expired, KeyEventKind::Del]);
+/// events.enable(con)?;
+/// events.run(con, |event| match event.kind {
+///     KeyEventKind::Expired => println!("{} expired", event.key),
+///     _ => println!("{} was deleted", event.key),
+/// })?;
+/// # Ok(())
+/// # }
+/// ```
+#[proc_macro]
+pub fn redis_keyevents(tokens: TokenStream) -> TokenStream {
+    keyevents::expand_keyevents(tokens.into())
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Build the `CONFIG SET notify-keyspace-events` command for a list of notification classes
+///
+/// The arguments are the classes of keyspace notifications to enable, by name instead of the
+/// single letter flags of the setting: `Keyspace`, `Keyevent`, `Generic`, `String`, `List`,
+/// `Set`, `Hash`, `SortedSet`, `Expired`, `Evicted`, `Stream`, `Module`, `KeyMiss`, `New` and
+/// `All`. Names are checked when compiling, as are classes given twice and classes that `All`
+/// already includes. Since the server sends nothing unless keyspace or keyevent events are on,
+/// `Keyevent` is added when neither is given, which is what [`redis_keyevents!`] listens to. An
+/// empty list turns notifications off.
+///
+/// The macro evaluates to a `redis::Cmd`, and replaces the whole setting. To add to the flags that
+/// are already set, use `redis_rs_macro::KeyEvents::enable`.
+///
+/// # Examples
+/// ```rust
+/// use redis_rs_macro::redis_config_notify;
+///
+/// let cmd = redis_config_notify!(Expired, Evicted, Set);
+/// assert_eq!(
+///     cmd.get_packed_command(),
+///     redis::cmd("CONFIG")
+///         .arg("SET")
+///         .arg("notify-keyspace-events")
+///         .arg("Esxe")
+///         .get_packed_command(),
+/// );
+/// ```
+/// ## Expansion
+/// ```rust
+/// let cmd = redis::cmd("CONFIG")
+///     .arg("SET")
+///     .arg("notify-keyspace-events")
+///     .arg("Esxe")
+///     .clone();
+/// ```
+#[proc_macro]
+pub fn redis_config_notify(tokens: TokenStream) -> TokenStream {
+    keyevents::expand_config_notify(tokens.into())
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Build a list of arguments using the same syntax as [`redis!`], without a command name
+///
+/// The result is a `redis_rs_macro::Args`, which writes every argument in order when it is passed
+/// to `.arg` of a `redis::Cmd` or `redis::Pipeline`. This lets the macro syntax add to commands
+/// that are built by hand. Since the arguments aren't part of a known command, keys don't get the
+/// key prefix.
+///
+/// # Examples
+/// ```rust
+/// use redis_rs_macro::redis_args;
+///
+/// let ttl: Option<u64> = Some(30);
+/// let mut cmd = redis::cmd("SET");
+/// cmd.arg("key").arg("value").arg(redis_args!(EX {ttl?} NX));
+/// ```
+/// ## Expansion
+/// ```rust
+/// let ttl: Option<u64> = Some(30);
+/// let mut cmd = redis::cmd("SET");
+/// cmd.arg("key").arg("value");
+/// if let Some(ttl) = ttl {
+///     cmd.arg("EX").arg(ttl);
+/// }
+/// cmd.arg("NX");
+/// ```
+#[proc_macro]
+pub fn redis_args(tokens: TokenStream) -> TokenStream {
+    parse::parse_args(tokens.into(), 0)
+        .and_then(|command| expand::expand_arg_list(&command, false))
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Build a `redis::Pipeline` from several commands, each written as in [`redis!`]
+///
+/// Commands are separated by `;` or by line breaks, so a command in a pipeline has to fit on one
+/// line. Write `_ =` before a command to leave its reply out of the results, as with
+/// `redis::Pipeline::ignore`. Since `;` ends a command, bindings aren't supported; use `{expr}`
+/// substitutions instead.
+///
+/// Start the pipeline with `atomic` to run its commands in a MULTI/EXEC transaction, as with
+/// `redis::Pipeline::atomic`.
+///
+/// Start the pipeline with `cluster` to build a `redis_rs_macro::ClusterPipeline` instead, which
+/// groups the commands by the hash slot of their first key, as [`redis_routed!`] finds it.
+///
+/// # Examples
+/// ```rust
+/// use redis_rs_macro::redis_pipe;
+///
+/// let id = 42;
+/// let pipe = redis_pipe!(
+///     _ = SET user:{id}:visits 0
+///     INCR user:{id}:visits
+///     GET user:{id}:name; TTL user:{id}:name
+/// );
+/// ```
+/// ## Expansion
+/// ```rust
+/// let id = 42;
+/// let mut pipe = redis::pipe();
+/// pipe.cmd("SET").arg(format!("user:{}:visits", id)).arg(0).ignore();
+/// pipe.cmd("INCR").arg(format!("user:{}:visits", id));
+/// pipe.cmd("GET").arg(format!("user:{}:name", id));
+/// pipe.cmd("TTL").arg(format!("user:{}:name", id));
+/// ```
+/// ## Labeled Results
+/// Commands can be labeled with `name:`, which returns a `redis_rs_macro::LabeledPipeline` whose
+/// `query` reads the replies into a generated struct with one field per label, instead of a
+/// tuple. Every field is converted with `redis::FromRedisValue`, to the type that it is used as.
+/// Once one command has a label, every command that isn't ignored with `_ =` needs one.
+/// ```rust
+/// # fn run(con: &mut redis::Connection) -> redis::RedisResult<()> {
+/// use redis_rs_macro::redis_pipe;
+/// let id = 42;
+/// let results = redis_pipe!(
+///     count: INCR visits
+///     name: GET user:{id}:name
+/// )
+/// .query(con)?;
+/// let count: i64 = results.count;
+/// let name: String = results.name;
+/// # Ok(())
+/// # }
+/// ```
+/// ## Expansion
+/// ```rust
+/// # fn run(con: &mut redis::Connection) -> redis::RedisResult<()> {
+/// let id = 42;
+/// let (count, name): (i64, String) = redis::pipe()
+///     .cmd("INCR").arg("visits")
+///     .cmd("GET").arg(format!("user:{}:name", id))
+///     .query(con)?;
+/// # Ok(())
+/// # }
+/// ```
+/// ## Loops
+/// A `for` loop appends its commands on every iteration, for fetching or updating many keys at
+/// once. Its body is a pipeline of its own, so it can hold several commands and other loops.
+/// Since a loop can run any number of times, its commands can't have labels, and in a labeled
+/// pipeline they have to be ignored with `_ =`.
+/// ```rust
+/// use redis_rs_macro::redis_pipe;
+/// let ids = [1, 2, 3];
+/// redis_pipe!(for id in &ids { GET user:{id}:name });
+/// ```
+/// ## Expansion
+/// ```rust
+/// let ids = [1, 2, 3];
+/// let mut pipe = redis::pipe();
+/// for id in &ids {
+///     pipe.cmd("GET").arg(format!("user:{}:name", id));
+/// }
+/// ```
+/// ## Conditions
+/// An `if` block appends its commands only when its condition holds, and can be followed by
+/// `else if` or `else` blocks on the same line as its closing brace. In a labeled pipeline, the
+/// fields of labeled commands inside an `if` are `Option`s, which are `None` when their branch
+/// isn't taken.
+/// ```rust
+/// # fn run(con: &mut redis::Connection) -> redis::RedisResult<()> {
+/// use redis_rs_macro::redis_pipe;
+/// let fresh = true;
+/// let results = redis_pipe!(
+///     count: INCR visits
+///     if fresh {
+///         first: SET seen 1 NX GET
+///     }
+/// )
+/// .query(con)?;
+/// let count: i64 = results.count;
+/// let first: Option<String> = results.first;
+/// # Ok(())
+/// # }
+/// ```
+/// ## Expansion
+/// ```rust
+/// let fresh = true;
+/// let mut pipe = redis::pipe();
+/// pipe.cmd("INCR").arg("visits");
+/// if fresh {
+///     pipe.cmd("SET").arg("seen").arg(1).arg("NX").arg("GET");
+/// }
+/// ```
+/// ## Atomic Pipelines
+/// ```rust
+/// use redis_rs_macro::redis_pipe;
+/// redis_pipe!(atomic; SET a 1; INCR b);
+/// ```
+/// ## Expansion
+/// ```rust
+/// redis::pipe().atomic().cmd("SET").arg("a").arg(1).cmd("INCR").arg("b");
+/// ```
+/// ## Cluster Pipelines
+/// A cluster node only serves the keys of its own slots, so a pipeline over keys in several slots
+/// is split into one pipeline per slot. Slots of written out keys are computed at compile time,
+/// and those of other keys when the command is added. `query` sends each group with the given
+/// closure, which picks the connection of the node that serves its route, and reads the replies
+/// in the order of the commands. Since the groups are sent separately, cluster pipelines can't be
+/// atomic, and return their replies as a tuple.
+/// ```rust
+/// # fn run(node_of: impl Fn(redis_rs_macro::Route) -> usize, nodes: &mut [redis::Connection]) -> redis::RedisResult<()> {
+/// use redis_rs_macro::redis_pipe;
+/// let ids = [1, 2];
+/// let (a, b, visits): (Option<String>, Option<String>, i64) = redis_pipe!(
+///     cluster
+///     for id in &ids { GET user:{id}:name }
+///     INCR visits
+/// )
+/// .query(|route, pipe| pipe.query(&mut nodes[node_of(route)]))?;
+/// # Ok(())
+/// # }
+/// ```
+/// ## Expansion
+/// ```rust
+/// use redis_rs_macro::{ClusterPipeline, Route};
+/// let ids = [1, 2];
+/// let mut pipe = ClusterPipeline::new();
+/// for id in &ids {
+///     let cmd = redis::cmd("GET").arg(format!("user:{}:name", id)).clone();
+///     let route = Route::for_arg(&cmd, 1);
+///     pipe.add_command(cmd, route);
+/// }
+/// pipe.add_command(redis::cmd("INCR").arg("visits").clone(), Route::Slot(12263));
+/// ```
+#[proc_macro]
+pub fn redis_pipe(tokens: TokenStream) -> TokenStream {
+    pipe::expand_pipeline(tokens.into())
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Build a `Vec<redis::Cmd>` from commands written in the syntax of [`redis_pipe!`]
+///
+/// This is for callers that schedule or shard commands themselves instead of sending them as a
+/// pipeline right away. Loops and `if` blocks work as in a pipeline, but since nothing reads the
+/// replies, commands can't have labels or be ignored with `_ =`.
+///
+/// # Examples
+/// ```rust
+/// use redis_rs_macro::redis_batch;
+///
+/// let pairs = [("a", 1), ("b", 2)];
+/// let cmds: Vec<redis::Cmd> = redis_batch!(for (k, v) in &pairs { SET {k} {v} EX 60 });
+/// ```
+/// ## Expansion
+/// ```rust
+/// let pairs = [("a", 1), ("b", 2)];
+/// let mut cmds = Vec::new();
+/// for (k, v) in &pairs {
+///     cmds.push(redis::cmd("SET").arg(k).arg(v).arg("EX").arg(60).clone());
+/// }
+/// ```
+#[proc_macro]
+pub fn redis_batch(tokens: TokenStream) -> TokenStream {
+    batch::expand_batch(tokens.into())
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Run commands in a transaction that is retried whenever a watched key changes
+///
+/// The macro takes a connection, the keys to `WATCH` in the argument syntax of [`redis_args!`],
+/// an optional `|con| { ... }` block that reads the watched keys, and a body of commands in the
+/// syntax of [`redis_pipe!`]. It expands to a call to `redis::transaction`, which watches the
+/// keys, runs the read block, and runs the body in MULTI/EXEC, starting over when EXEC fails
+/// because a watched key was changed after it was watched. The replies of the commands are
+/// returned as with `redis::Pipeline::query`, in a `redis::RedisResult`.
+///
+/// The read block gets the connection under the name before it, and the commands can use the
+/// variables it defines, so a value can be read and written back without another client changing
+/// it in between. `?` in the read block returns the error from the transaction.
+///
+/// The read block and the substitutions in the body are evaluated again on every attempt.
+///
+/// # Examples
+/// ```rust
+/// # fn run(con: &mut redis::Connection) -> redis::RedisResult<()> {
+/// use redis_rs_macro::{redis, redis_transaction};
+///
+/// let (id, amount) = (42, 10);
+/// let (entries,): (i64,) = redis_transaction!(con, [balance:{id}], |con| {
+///     let balance: i64 = redis!(GET balance:{id}).query(con)?;
+/// }, {
+///     _ = SET balance:{id} {balance - amount}
+///     LPUSH history:{id} {-amount}
+/// })?;
+/// # Ok(())
+/// # }
+/// ```
+/// ## Expansion
+/// ```rust
+/// # fn run(con: &mut redis::Connection) -> redis::RedisResult<()> {
+/// let (id, amount) = (42, 10);
+/// let key = format!("balance:{}", id);
+/// let (entries,): (i64,) = redis::transaction(con, &[&key], |con, pipe| {
+///     let balance: i64 = redis::cmd("GET").arg(&key).query(con)?;
+///     pipe.cmd("SET").arg(&key).arg(balance - amount).ignore()
+///         .cmd("LPUSH").arg(format!("history:{}", id)).arg(-amount)
+///         .query(con)
+/// })?;
+/// # Ok(())
+/// # }
+/// ```
+#[proc_macro]
+pub fn redis_transaction(tokens: TokenStream) -> TokenStream {
+    transaction::expand_transaction(tokens.into())
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Define a reusable command template, using the same syntax as [`redis!`]
+///
+/// The macro generates a unit struct with a `bind` function that takes one parameter for every
+/// substitution in the template, in the order they first appear, and returns the built
+/// `redis::Cmd`. This keeps commands that are built often defined in a single place.
+///
+/// The type of each parameter depends on how it is used:
+/// - `{name}` takes any `impl redis::ToRedisArgs`
+/// - `{..name}` takes an `impl IntoIterator` of `redis::ToRedisArgs` items
+/// - `{*name}` takes a reference to an `impl redis_rs_macro::ToRedisFields`
+/// - `{name?}` takes an `Option` of a `redis::ToRedisArgs` value
+/// - `?[{name} => ...]` takes a `bool`
+///
+/// # Examples
+/// ```rust
+/// use redis_rs_macro::redis_template;
+///
+/// redis_template!(
+///     /// Fetch the name of a user
+///     pub GetUserName = HGET user:{id} name
+/// );
+/// redis_template!(SetUser = SET user:{id} {data} EX {ttl?});
+///
+/// let cmd: redis::Cmd = GetUserName::bind(42);
+/// let cmd: redis::Cmd = SetUser::bind(42, "alice", Some(60));
+/// ```
+#[proc_macro]
+pub fn redis_template(tokens: TokenStream) -> TokenStream {
+    template::expand_template(tokens.into())
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Build a key from a format string, checking its literal parts against the key naming convention
+///
+/// The arguments are formatted as with `format!`, and the result is a `redis_rs_macro::Key`, which
+/// can be used anywhere in [`redis!`] that takes a value. The text outside the placeholders is
+/// split into segments at `:`, and every segment must follow the naming convention, so that key
+/// formats stay consistent across a codebase.
+///
+/// The convention is read from environment variables at compile time, which can be set in the
+/// `[env]` table of `.cargo/config.toml`:
+/// - `REDIS_KEY_CONVENTION` is one of `snake_case` (the default), `kebab-case`, `camelCase` or
+///   `any`, which only rules out whitespace
+/// - `REDIS_KEY_SEPARATOR` replaces `:` as the separator between segments
+///
+/// # Examples
+/// ```rust
+/// use redis_rs_macro::{redis, redis_key};
+///
+/// let id = 42;
+/// let key = redis_key!("user:{}:orders", id);
+/// assert_eq!(key.as_str(), "user:42:orders");
+/// redis!(LPUSH {key} 1001);
+/// ```
+/// ## Expansion
+/// ```rust
+/// let id = 42;
+/// let key = format!("user:{}:orders", id);
+/// ```
+#[proc_macro]
+pub fn redis_key(tokens: TokenStream) -> TokenStream {
+    key::expand_key(tokens.into())
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
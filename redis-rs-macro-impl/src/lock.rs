@@ -0,0 +1,225 @@
+use crate::expand::expand_arg_list;
+use crate::parse::{parse_args, Command};
+use proc_macro2::{Span, TokenStream, TokenTree};
+use quote::quote;
+use syn::parse::{Parse, ParseStream};
+use syn::{Block, Expr, ExprClosure, Ident, Pat, ReturnType, Token};
+
+/// The input of `redis_lock!`, e.g. `con, lock:{id}, ttl = 30000`, and of `redis_lock_async!`,
+/// which is followed by the body that runs while the lock is held
+struct LockInput {
+    con: Expr,
+    key: TokenStream,
+    ttl: Expr,
+    body: Option<LockBody>,
+}
+
+/// The body of `redis_lock_async!`: a block, or `|con| body` to run it with the connection
+struct LockBody {
+    con: Option<Pat>,
+    expr: Expr,
+}
+
+impl Parse for LockInput {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let con = input.parse()?;
+        input.parse::<Token![,]>()?;
+        let mut key = TokenStream::new();
+        while !input.is_empty() && !input.peek(Token![,]) {
+            key.extend([input.parse::<TokenTree>()?]);
+        }
+        input.parse::<Token![,]>()?;
+        let name: Ident = input.parse()?;
+        if name != "ttl" {
+            return Err(syn::Error::new(
+                name.span(),
+                "expected `ttl = <milliseconds>`",
+            ));
+        }
+        input.parse::<Token![=]>()?;
+        let ttl = input.parse()?;
+        let mut body = None;
+        if input.parse::<Option<Token![,]>>()?.is_some() && !input.is_empty() {
+            body = Some(input.parse()?);
+            input.parse::<Option<Token![,]>>()?;
+        }
+        Ok(LockInput {
+            con,
+            key,
+            ttl,
+            body,
+        })
+    }
+}
+
+impl Parse for LockBody {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        if !input.peek(Token![|]) {
+            let block: Block = input.parse()?;
+            return Ok(LockBody {
+                con: None,
+                expr: syn::parse_quote!(#block),
+            });
+        }
+        let closure: ExprClosure = input.parse()?;
+        let plain = closure.lifetimes.is_none()
+            && closure.constness.is_none()
+            && closure.movability.is_none()
+            && closure.asyncness.is_none()
+            && closure.capture.is_none()
+            && matches!(closure.output, ReturnType::Default);
+        if !plain || closure.inputs.len() != 1 {
+            let msg = "expected `|con| body`, which runs the body with the connection";
+            return Err(syn::Error::new_spanned(closure.or1_token, msg));
+        }
+        Ok(LockBody {
+            con: closure.inputs.into_iter().next(),
+            expr: *closure.body,
+        })
+    }
+}
+
+/// Generate the `redis_rs_macro::Args` of a single key, written with the argument syntax of
+/// `redis!`, which gets the key prefix
+pub(crate) fn expand_key(tokens: TokenStream) -> syn::Result<TokenStream> {
+    let Command { mut args, bindings } = parse_args(tokens, 0)?;
+    let single = args.len() == 1 && args[0].pieces.iter().all(|p| p.standalone_kind().is_none());
+    if !single {
+        let span = args.first().map_or(Span::call_site(), |arg| arg.span);
+        return Err(syn::Error::new(span, "expected a single key"));
+    }
+    let key = Command {
+        args: vec![args.remove(0)],
+        bindings,
+    };
+    expand_arg_list(&key, true)
+}
+
+/// Generate a `redis_lock!` invocation, which tries to take the lock and returns its guard
+pub(crate) fn expand_lock(input: TokenStream) -> syn::Result<TokenStream> {
+    let LockInput {
+        con,
+        key,
+        ttl,
+        body,
+    } = syn::parse2(input)?;
+    if let Some(body) = body {
+        let msg = "`redis_lock!` returns a guard instead of running a block; use \
+                   `redis_lock_async!` to run a block";
+        return Err(syn::Error::new_spanned(body.expr, msg));
+    }
+    let key = expand_key(key)?;
+    Ok(quote! {
+        ::redis_rs_macro::Lock::new(#key, #ttl).acquire(#con)
+    })
+}
+
+/// Generate a `redis_lock_async!` invocation, a future that takes the lock, runs the body and
+/// releases the lock. The connection is evaluated once, and the body runs in an async block of its
+/// own, so that the lock is released even when a `?` or `return` leaves the body early.
+pub(crate) fn expand_lock_async(input: TokenStream) -> syn::Result<TokenStream> {
+    let LockInput {
+        con,
+        key,
+        ttl,
+        body,
+    } = syn::parse2(input)?;
+    let Some(LockBody { con: pat, expr }) = body else {
+        let msg = "expected the block that runs while the lock is held, after the `ttl`";
+        return Err(syn::Error::new(Span::call_site(), msg));
+    };
+    let key = expand_key(key)?;
+    // Mixed site hygiene keeps these from shadowing or capturing the names used in the body
+    let con_var = Ident::new("con", Span::mixed_site());
+    let lock = Ident::new("lock", Span::mixed_site());
+    let acquired = Ident::new("acquired", Span::mixed_site());
+    let output = Ident::new("output", Span::mixed_site());
+    let released = Ident::new("released", Span::mixed_site());
+    let bind = pat.map(|pat| quote!(let #pat = &mut *#con_var;));
+    Ok(quote! {
+        async {
+            let #con_var = #con;
+            let #lock = ::redis_rs_macro::Lock::new(#key, #ttl);
+            let #acquired: ::core::option::Option<::std::string::String> =
+                redis::Cmd::query_async(&#lock.acquire_cmd(), &mut *#con_var).await?;
+            if #acquired.is_none() {
+                return redis::RedisResult::Ok(::core::option::Option::None);
+            }
+            let #output: redis::RedisResult<_> = async {
+                #bind
+                redis::RedisResult::Ok(#expr)
+            }
+            .await;
+            let #released =
+                redis::Cmd::query_async::<_, i64>(&#lock.release_cmd().eval_cmd(), &mut *#con_var)
+                    .await;
+            let #output = #output?;
+            #released?;
+            redis::RedisResult::Ok(::core::option::Option::Some(#output))
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lock_expand() {
+        let output = expand_lock(quote!(&mut con, lock:{id}, ttl = 30000))
+            .unwrap()
+            .to_string();
+        assert!(
+            output.starts_with(":: redis_rs_macro :: Lock :: new ({"),
+            "{}",
+            output
+        );
+        assert!(
+            output.ends_with(", 30000) . acquire (& mut con)"),
+            "{}",
+            output
+        );
+        let output = expand_lock_async(quote!(&mut con, jobs, ttl = ttl, { run().await }))
+            .unwrap()
+            .to_string();
+        assert!(
+            output.starts_with("async { let con = & mut con ;"),
+            "{}",
+            output
+        );
+        assert_eq!(output.matches("& mut con").count(), 1, "{}", output);
+        assert!(
+            output.contains("redis :: RedisResult :: Ok ({ run () . await })"),
+            "{}",
+            output
+        );
+        assert!(
+            output.contains("release_cmd () . eval_cmd ()"),
+            "{}",
+            output
+        );
+        let output = expand_lock_async(quote!(&mut con, jobs, ttl = 1, |c| run(c).await))
+            .unwrap()
+            .to_string();
+        assert!(output.contains("let c = & mut * con ;"), "{}", output);
+    }
+
+    #[test]
+    fn lock_errors() {
+        let err = |tokens: TokenStream| expand_lock(tokens).unwrap_err().to_string();
+        let e = err("con, a b, ttl = 1".parse().unwrap());
+        assert!(e.contains("single key"), "{}", e);
+        let e = err(quote!(con, a, px = 1));
+        assert!(e.contains("expected `ttl"), "{}", e);
+        let e = err(quote!(con, a, ttl = 1, {}));
+        assert!(e.contains("use `redis_lock_async!`"), "{}", e);
+        let e = expand_lock_async(quote!(con, a, ttl = 1))
+            .unwrap_err()
+            .to_string();
+        assert!(e.contains("expected the block"), "{}", e);
+        let e = expand_lock_async(quote!(con, a, ttl = 1, |a, b| {}))
+            .unwrap_err()
+            .to_string();
+        assert!(e.contains("expected `|con| body`"), "{}", e);
+    }
+}
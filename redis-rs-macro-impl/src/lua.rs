@@ -0,0 +1,672 @@
+//! A syntax checker for the Lua 5.1 scripts that Redis runs, used with the `lua-check` feature.
+//! It only decides whether a script parses, so it doesn't build a syntax tree.
+
+use std::fmt;
+use syn::LitStr;
+
+/// A syntax error in a script, at a 1-based line and column
+#[derive(Debug, PartialEq)]
+pub(crate) struct LuaError {
+    pub(crate) line: usize,
+    pub(crate) column: usize,
+    /// The byte offset of the error in the script
+    pub(crate) offset: usize,
+    pub(crate) message: String,
+}
+
+impl fmt::Display for LuaError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Lua syntax error at line {}, column {}: {}",
+            self.line, self.column, self.message
+        )
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Token {
+    Name(String),
+    Keyword(&'static str),
+    Symbol(&'static str),
+    Number,
+    /// A string, holding what is written between its quotes or brackets, with escapes left as is
+    Str(String),
+    Eof,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Token::Name(name) => write!(f, "`{}`", name),
+            Token::Keyword(word) | Token::Symbol(word) => write!(f, "`{}`", word),
+            Token::Number => write!(f, "a number"),
+            Token::Str(_) => write!(f, "a string"),
+            Token::Eof => write!(f, "the end of the script"),
+        }
+    }
+}
+
+const KEYWORDS: &[&str] = &[
+    "and", "break", "do", "else", "elseif", "end", "false", "for", "function", "if", "in", "local",
+    "nil", "not", "or", "repeat", "return", "then", "true", "until", "while",
+];
+
+// Longest first, so that `...` isn't read as `..` and `.`
+const SYMBOLS: &[&str] = &[
+    "...", "..", "==", "~=", "<=", ">=", "+", "-", "*", "/", "%", "^", "#", "<", ">", "=", "(",
+    ")", "{", "}", "[", "]", ";", ":", ",", ".",
+];
+
+const BINARY: &[&str] = &[
+    "+", "-", "*", "/", "%", "^", "..", "==", "~=", "<", "<=", ">", ">=", "and", "or",
+];
+
+/// Check that a script is valid Lua 5.1
+pub(crate) fn check(source: &str) -> Result<(), LuaError> {
+    let tokens = tokenize(source)?;
+    let mut parser = Parser {
+        source,
+        tokens,
+        pos: 0,
+        loops: 0,
+    };
+    parser.block()?;
+    match parser.peek() {
+        Token::Eof => Ok(()),
+        _ => Err(parser.unexpected("a statement")),
+    }
+}
+
+/// Check a script written as a string literal, pointing errors at the literal. Raw strings hold
+/// the script as written, so errors in them point at the offending token where the compiler
+/// supports spans inside literals.
+pub(crate) fn check_literal(lit: &LitStr) -> syn::Result<()> {
+    let source = lit.value();
+    let Err(err) = check(&source) else {
+        return Ok(());
+    };
+    let token = lit.token();
+    let text = token.to_string();
+    let span = match text.starts_with('r') {
+        true => {
+            let start = text.find('"').unwrap_or_default() + 1 + err.offset;
+            let end = (start + 1).min(text.len());
+            token.subspan(start..end)
+        }
+        false => None,
+    };
+    Err(syn::Error::new(span.unwrap_or_else(|| lit.span()), err))
+}
+
+/// The position of a byte offset as a line and column
+fn position(source: &str, offset: usize) -> (usize, usize) {
+    let before = &source[..offset];
+    let line = before.matches('\n').count() + 1;
+    let column = before.rsplit('\n').next().unwrap_or("").chars().count() + 1;
+    (line, column)
+}
+
+fn error(source: &str, offset: usize, message: impl Into<String>) -> LuaError {
+    let (line, column) = position(source, offset);
+    LuaError {
+        line,
+        column,
+        offset,
+        message: message.into(),
+    }
+}
+
+/// Split a script into tokens with their byte offsets
+pub(crate) fn tokenize(source: &str) -> Result<Vec<(Token, usize)>, LuaError> {
+    let bytes = source.as_bytes();
+    let mut tokens = vec![];
+    let mut i = 0;
+    while i < bytes.len() {
+        let start = i;
+        let c = bytes[i];
+        if c.is_ascii_whitespace() {
+            i += 1;
+        } else if source[i..].starts_with("--") {
+            i += 2;
+            match long_bracket(bytes, i) {
+                Some(level) => i = skip_long(source, i, level, "comment")?,
+                None => {
+                    while i < bytes.len() && bytes[i] != b'\n' {
+                        i += 1;
+                    }
+                }
+            }
+        } else if c.is_ascii_alphabetic() || c == b'_' {
+            while i < bytes.len() && (bytes[i].is_ascii_alphanumeric() || bytes[i] == b'_') {
+                i += 1;
+            }
+            let word = &source[start..i];
+            tokens.push(match KEYWORDS.iter().find(|k| **k == word) {
+                Some(keyword) => (Token::Keyword(keyword), start),
+                None => (Token::Name(word.to_string()), start),
+            });
+        } else if c.is_ascii_digit()
+            || (c == b'.' && bytes.get(i + 1).is_some_and(u8::is_ascii_digit))
+        {
+            while i < bytes.len() {
+                let b = bytes[i];
+                let sign = (b == b'+' || b == b'-') && matches!(bytes[i - 1], b'e' | b'E');
+                if !(b.is_ascii_alphanumeric() || b == b'.' || b == b'_' || sign) {
+                    break;
+                }
+                i += 1;
+            }
+            let number = &source[start..i];
+            let hex = number
+                .strip_prefix("0x")
+                .or_else(|| number.strip_prefix("0X"))
+                .is_some_and(|digits| u64::from_str_radix(digits, 16).is_ok());
+            if !hex && number.parse::<f64>().is_err() {
+                let msg = format!("malformed number `{}`", number);
+                return Err(error(source, start, msg));
+            }
+            tokens.push((Token::Number, start));
+        } else if c == b'"' || c == b'\'' {
+            i += 1;
+            loop {
+                match bytes.get(i) {
+                    None | Some(b'\n') => {
+                        return Err(error(source, start, "unfinished string"));
+                    }
+                    Some(b'\\') => i += 2,
+                    Some(b) if *b == c => break,
+                    Some(_) => i += 1,
+                }
+            }
+            i += 1;
+            tokens.push((Token::Str(source[start + 1..i - 1].to_string()), start));
+        } else if let Some(level) = long_bracket(bytes, i) {
+            i = skip_long(source, i, level, "string")?;
+            let body = &source[start + level + 2..i - level - 2];
+            tokens.push((Token::Str(body.to_string()), start));
+        } else if let Some(symbol) = SYMBOLS.iter().find(|s| source[i..].starts_with(**s)) {
+            i += symbol.len();
+            tokens.push((Token::Symbol(symbol), start));
+        } else {
+            let c = source[i..].chars().next().unwrap();
+            return Err(error(source, start, format!("unexpected symbol `{}`", c)));
+        }
+    }
+    tokens.push((Token::Eof, source.len()));
+    Ok(tokens)
+}
+
+/// The level of a long bracket such as `[==[` starting at `i`, i.e. the number of `=`
+fn long_bracket(bytes: &[u8], i: usize) -> Option<usize> {
+    if bytes.get(i) != Some(&b'[') {
+        return None;
+    }
+    let level = bytes[i + 1..].iter().take_while(|b| **b == b'=').count();
+    (bytes.get(i + 1 + level) == Some(&b'[')).then_some(level)
+}
+
+/// Skip a long string or comment opened at `i`, returning the offset after its closing bracket
+fn skip_long(source: &str, i: usize, level: usize, what: &str) -> Result<usize, LuaError> {
+    let close = format!("]{}]", "=".repeat(level));
+    let body = i + level + 2;
+    match source[body..].find(&close) {
+        Some(end) => Ok(body + end + close.len()),
+        None => Err(error(source, i, format!("unfinished long {}", what))),
+    }
+}
+
+struct Parser<'a> {
+    source: &'a str,
+    tokens: Vec<(Token, usize)>,
+    pos: usize,
+    /// The number of loops around the current statement in its function, where `break` is allowed
+    loops: usize,
+}
+
+/// What a suffixed expression ends with, which decides whether it can be assigned to or stand as
+/// a statement
+#[derive(Clone, Copy, PartialEq)]
+enum Suffixed {
+    /// A name, field or index, which can be assigned to
+    Var,
+    /// A call, which can be a statement
+    Call,
+    /// A parenthesized expression, such as `(f)`
+    Paren,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> &Token {
+        &self.tokens[self.pos].0
+    }
+
+    fn next(&mut self) -> Token {
+        let token = self.peek().clone();
+        if token != Token::Eof {
+            self.pos += 1;
+        }
+        token
+    }
+
+    fn is(&self, word: &str) -> bool {
+        matches!(self.peek(), Token::Keyword(w) | Token::Symbol(w) if *w == word)
+    }
+
+    fn eat(&mut self, word: &str) -> bool {
+        let is = self.is(word);
+        if is {
+            self.pos += 1;
+        }
+        is
+    }
+
+    fn unexpected(&self, expected: &str) -> LuaError {
+        let (token, offset) = &self.tokens[self.pos];
+        let msg = format!("expected {}, found {}", expected, token);
+        error(self.source, *offset, msg)
+    }
+
+    fn expect(&mut self, word: &str) -> Result<(), LuaError> {
+        match self.eat(word) {
+            true => Ok(()),
+            false => Err(self.unexpected(&format!("`{}`", word))),
+        }
+    }
+
+    fn name(&mut self) -> Result<(), LuaError> {
+        match self.peek() {
+            Token::Name(_) => {
+                self.pos += 1;
+                Ok(())
+            }
+            _ => Err(self.unexpected("a name")),
+        }
+    }
+
+    fn block_end(&self) -> bool {
+        *self.peek() == Token::Eof
+            || ["end", "else", "elseif", "until"]
+                .iter()
+                .any(|w| self.is(w))
+    }
+
+    /// `{stat [';']} [laststat [';']]`
+    fn block(&mut self) -> Result<(), LuaError> {
+        while !self.block_end() {
+            if self.eat("return") {
+                if !self.block_end() && !self.is(";") {
+                    self.exp_list()?;
+                }
+                self.eat(";");
+                return self.last();
+            }
+            if self.is("break") {
+                if self.loops == 0 {
+                    let msg = "`break` outside of a loop";
+                    return Err(error(self.source, self.tokens[self.pos].1, msg));
+                }
+                self.next();
+                self.eat(";");
+                return self.last();
+            }
+            self.statement()?;
+            self.eat(";");
+        }
+        Ok(())
+    }
+
+    /// `return` and `break` end their block
+    fn last(&self) -> Result<(), LuaError> {
+        match self.block_end() {
+            true => Ok(()),
+            false => Err(self.unexpected("the end of the block")),
+        }
+    }
+
+    fn statement(&mut self) -> Result<(), LuaError> {
+        match self.peek().clone() {
+            Token::Keyword("do") => {
+                self.next();
+                self.block()?;
+                self.expect("end")
+            }
+            Token::Keyword("while") => {
+                self.next();
+                self.exp()?;
+                self.expect("do")?;
+                self.loop_block()?;
+                self.expect("end")
+            }
+            Token::Keyword("repeat") => {
+                self.next();
+                self.loop_block()?;
+                self.expect("until")?;
+                self.exp()
+            }
+            Token::Keyword("if") => {
+                self.next();
+                self.exp()?;
+                self.expect("then")?;
+                self.block()?;
+                while self.eat("elseif") {
+                    self.exp()?;
+                    self.expect("then")?;
+                    self.block()?;
+                }
+                if self.eat("else") {
+                    self.block()?;
+                }
+                self.expect("end")
+            }
+            Token::Keyword("for") => {
+                self.next();
+                self.name()?;
+                if self.eat("=") {
+                    self.exp()?;
+                    self.expect(",")?;
+                    self.exp()?;
+                    if self.eat(",") {
+                        self.exp()?;
+                    }
+                } else {
+                    while self.eat(",") {
+                        self.name()?;
+                    }
+                    self.expect("in")?;
+                    self.exp_list()?;
+                }
+                self.expect("do")?;
+                self.loop_block()?;
+                self.expect("end")
+            }
+            Token::Keyword("function") => {
+                self.next();
+                self.name()?;
+                while self.eat(".") {
+                    self.name()?;
+                }
+                if self.eat(":") {
+                    self.name()?;
+                }
+                self.function_body()
+            }
+            Token::Keyword("local") => {
+                self.next();
+                if self.eat("function") {
+                    self.name()?;
+                    return self.function_body();
+                }
+                self.name()?;
+                while self.eat(",") {
+                    self.name()?;
+                }
+                if self.eat("=") {
+                    self.exp_list()?;
+                }
+                Ok(())
+            }
+            _ => self.expression_statement(),
+        }
+    }
+
+    /// The body of a loop, where `break` is allowed
+    fn loop_block(&mut self) -> Result<(), LuaError> {
+        self.loops += 1;
+        let block = self.block();
+        self.loops -= 1;
+        block
+    }
+
+    /// A call, or an assignment such as `a, t[1] = 1, 2`
+    fn expression_statement(&mut self) -> Result<(), LuaError> {
+        let mut start = self.pos;
+        let mut target = self.suffixed_exp()?;
+        if !self.is("=") && !self.is(",") {
+            return match target {
+                Suffixed::Call => Ok(()),
+                _ => Err(error(
+                    self.source,
+                    self.tokens[start].1,
+                    "expected a statement, such as a call or an assignment",
+                )),
+            };
+        }
+        loop {
+            let msg = match target {
+                Suffixed::Var => None,
+                Suffixed::Call => Some("cannot assign to a function call"),
+                Suffixed::Paren => Some("cannot assign to a parenthesized expression"),
+            };
+            if let Some(msg) = msg {
+                return Err(error(self.source, self.tokens[start].1, msg));
+            }
+            if !self.eat(",") {
+                break;
+            }
+            start = self.pos;
+            target = self.suffixed_exp()?;
+        }
+        self.expect("=")?;
+        self.exp_list()
+    }
+
+    /// `'(' [parlist] ')' block end`
+    fn function_body(&mut self) -> Result<(), LuaError> {
+        self.expect("(")?;
+        if !self.is(")") {
+            loop {
+                if self.eat("...") {
+                    break;
+                }
+                self.name()?;
+                if !self.eat(",") {
+                    break;
+                }
+            }
+        }
+        self.expect(")")?;
+        // A `break` can't leave the function for a loop around it
+        let loops = std::mem::take(&mut self.loops);
+        let block = self.block();
+        self.loops = loops;
+        block?;
+        self.expect("end")
+    }
+
+    fn exp_list(&mut self) -> Result<(), LuaError> {
+        self.exp()?;
+        while self.eat(",") {
+            self.exp()?;
+        }
+        Ok(())
+    }
+
+    /// An expression, with operator precedence left out since it doesn't change what parses
+    fn exp(&mut self) -> Result<(), LuaError> {
+        loop {
+            while self.eat("not") || self.eat("-") || self.eat("#") {}
+            self.simple_exp()?;
+            if !BINARY.iter().any(|op| self.is(op)) {
+                return Ok(());
+            }
+            self.next();
+        }
+    }
+
+    fn simple_exp(&mut self) -> Result<(), LuaError> {
+        match self.peek() {
+            Token::Number | Token::Str(_) => {
+                self.next();
+                Ok(())
+            }
+            Token::Keyword("nil" | "true" | "false") | Token::Symbol("...") => {
+                self.next();
+                Ok(())
+            }
+            Token::Keyword("function") => {
+                self.next();
+                self.function_body()
+            }
+            Token::Symbol("{") => self.table(),
+            _ => self.suffixed_exp().map(|_| ()),
+        }
+    }
+
+    /// A name or parenthesized expression, followed by fields, indexes and calls. Returns what
+    /// it ends with.
+    fn suffixed_exp(&mut self) -> Result<Suffixed, LuaError> {
+        let mut last = if self.eat("(") {
+            self.exp()?;
+            self.expect(")")?;
+            Suffixed::Paren
+        } else if let Token::Name(_) = self.peek() {
+            self.next();
+            Suffixed::Var
+        } else {
+            return Err(self.unexpected("an expression"));
+        };
+        loop {
+            if self.eat(".") {
+                self.name()?;
+                last = Suffixed::Var;
+            } else if self.eat("[") {
+                self.exp()?;
+                self.expect("]")?;
+                last = Suffixed::Var;
+            } else if self.eat(":") {
+                self.name()?;
+                self.call_args()?;
+                last = Suffixed::Call;
+            } else if self.is("(") || self.is("{") || matches!(self.peek(), Token::Str(_)) {
+                self.call_args()?;
+                last = Suffixed::Call;
+            } else {
+                return Ok(last);
+            }
+        }
+    }
+
+    /// `'(' [explist] ')'`, a table or a string
+    fn call_args(&mut self) -> Result<(), LuaError> {
+        if matches!(self.peek(), Token::Str(_)) {
+            self.next();
+            return Ok(());
+        }
+        if self.is("{") {
+            return self.table();
+        }
+        self.expect("(")?;
+        if !self.is(")") {
+            self.exp_list()?;
+        }
+        self.expect(")")
+    }
+
+    /// `'{' [field {sep field} [sep]] '}'`
+    fn table(&mut self) -> Result<(), LuaError> {
+        self.expect("{")?;
+        while !self.eat("}") {
+            if self.eat("[") {
+                self.exp()?;
+                self.expect("]")?;
+                self.expect("=")?;
+            } else if matches!(self.peek(), Token::Name(_))
+                && matches!(self.tokens[self.pos + 1].0, Token::Symbol("="))
+            {
+                self.pos += 2;
+            }
+            self.exp()?;
+            if !self.eat(",") && !self.eat(";") {
+                self.expect("}")?;
+                break;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lua_valid() {
+        let scripts = [
+            "",
+            "return 1",
+            "return redis.call('INCRBY', KEYS[1], ARGV[1])",
+            r#"
+            -- Take a token from the bucket
+            local tokens = tonumber(redis.call("GET", KEYS[1]) or ARGV[1])
+            if tokens > 0 then
+                redis.call("SET", KEYS[1], tokens - 1, "PX", ARGV[2])
+                return 1
+            elseif tokens == 0 then return 0 else return -1 end
+            "#,
+            "local t = {1, 2; x = 3, ['y'] = {}, f = function(...) return ... end,}",
+            "for i = 1, #KEYS, 2 do redis.call('DEL', KEYS[i]) end",
+            "for k, v in pairs(t) do t[k] = not v end; while false do break end",
+            "repeat local x = 1 until x == 1",
+            "for i = 1, 2 do if i then break end end; repeat do break end until true",
+            "(t).x, (t)[1] = 1, 2",
+            "local function f(a, b) return a .. b end; function m.n:o() end",
+            "a, b.c, d[1] = 1, 2, 3; f{1}; f'x'; s:len(); (f)()",
+            "--[==[ long\n comment ]==] return [[long\nstring]], 0x1F, 1e-3, .5",
+            "x = 'it\\'s' .. \"q\\\"\"",
+        ];
+        for script in scripts {
+            assert_eq!(check(script), Ok(()), "{}", script);
+        }
+    }
+
+    #[test]
+    fn lua_literal() {
+        let lit: LitStr = syn::parse_str(r##"r#"return "x" +"#"##).unwrap();
+        let e = check_literal(&lit).unwrap_err().to_string();
+        assert!(e.contains("line 1, column 13"), "{}", e);
+        assert!(check_literal(&syn::parse_str(r#""return 1""#).unwrap()).is_ok());
+    }
+
+    #[test]
+    fn lua_errors() {
+        let err = |script: &str| check(script).unwrap_err();
+        let e = err("if x then\n  return 1\n");
+        assert_eq!((e.line, e.column), (3, 1));
+        assert_eq!(e.message, "expected `end`, found the end of the script");
+        let e = err("local x = = 1");
+        assert_eq!((e.line, e.column, e.offset), (1, 11, 10));
+        assert!(e.message.contains("expected an expression"), "{}", e);
+        assert!(err("return 1 x = 2").message.contains("end of the block"));
+        assert!(err("x").message.contains("expected a statement"));
+        assert!(err("f() = 1").message.contains("assign to a function call"));
+        assert!(err("a, t:f() = 1, 2")
+            .message
+            .contains("assign to a function call"));
+        let e = err("(f) = 1");
+        assert_eq!(e.message, "cannot assign to a parenthesized expression");
+        assert!(err("x, (y) = 1, 2")
+            .message
+            .contains("parenthesized expression"));
+        let e = err("if x then break end");
+        assert_eq!(
+            (e.message.as_str(), e.column),
+            ("`break` outside of a loop", 11)
+        );
+        assert!(err("break").message.contains("outside of a loop"));
+        assert!(err("while x do local f = function() break end end")
+            .message
+            .contains("outside of a loop"));
+        assert!(err("x = 'open").message.contains("unfinished string"));
+        assert!(err("x = [[open").message.contains("unfinished long string"));
+        assert!(err("x = 1e").message.contains("malformed number"));
+        assert!(err("x = 1 @ 2").message.contains("unexpected symbol `@`"));
+        assert!(err("end")
+            .message
+            .contains("expected a statement, found `end`"));
+        assert_eq!(
+            err("\n\tx = = 1").to_string(),
+            "Lua syntax error at line 2, column 6: expected an expression, found `=`"
+        );
+    }
+}
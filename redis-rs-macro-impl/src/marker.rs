@@ -0,0 +1,196 @@
+use proc_macro2::{Spacing, Span, TokenStream, TokenTree};
+use quote::{quote, quote_spanned};
+use syn::parse::ParseStream;
+use syn::spanned::Spanned;
+use syn::{Expr, Ident};
+
+/// A keyword at the start of a substitution, such as `json` in `{json user}`, which changes how
+/// the value is converted to arguments
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Marker {
+    /// Serialize the value with `serde_json`
+    Json,
+    /// Serialize the value with `rmp-serde` into a binary argument
+    MsgPack,
+    /// Pass the value as raw bytes
+    Bytes,
+    /// Join the items of the value with commas into a single argument
+    Csv,
+    /// Spread `(score, member)` pairs, written as `{..scores pairs}`
+    Scores,
+    /// Use the value as the command name, which is only allowed when asked for explicitly
+    Raw,
+}
+
+impl Marker {
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Marker::Json => "json",
+            Marker::MsgPack => "msgpack",
+            Marker::Bytes => "bytes",
+            Marker::Csv => "csv",
+            Marker::Scores => "scores",
+            Marker::Raw => "raw",
+        }
+    }
+
+    /// Spread markers come after a `..`, and expand into any number of arguments
+    pub(crate) fn is_spread(self) -> bool {
+        matches!(self, Marker::Scores)
+    }
+
+    /// The feature of redis-rs-macro that the marker needs, and whether it is enabled
+    fn feature(self) -> Option<(&'static str, bool)> {
+        match self {
+            Marker::Json => Some(("json", cfg!(feature = "json"))),
+            Marker::MsgPack => Some(("msgpack", cfg!(feature = "msgpack"))),
+            Marker::Bytes | Marker::Csv | Marker::Scores | Marker::Raw => None,
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "json" => Some(Marker::Json),
+            "msgpack" => Some(Marker::MsgPack),
+            "bytes" => Some(Marker::Bytes),
+            "csv" => Some(Marker::Csv),
+            "scores" => Some(Marker::Scores),
+            "raw" => Some(Marker::Raw),
+            _ => None,
+        }
+    }
+
+    /// Parse a marker at the start of a substitution. A marker is only recognized when whitespace
+    /// separates it from the start of another expression, such as the `(` of `{json (a, b)}` or
+    /// the `&` of `{json &user}`, so `{json}`, `{json.len()}`, `{json(x)}`, `{json?}`,
+    /// `{json:x}` and `{json - 1}` are still plain expressions with a variable named `json`.
+    pub(crate) fn parse(input: ParseStream) -> syn::Result<Option<Self>> {
+        if !input.peek(Ident) || !starts_expr(input) {
+            return Ok(None);
+        }
+        let fork = input.fork();
+        let ident: Ident = fork.parse()?;
+        let Some(marker) = Marker::from_name(&ident.to_string()) else {
+            return Ok(None);
+        };
+        if let Some((feature, false)) = marker.feature() {
+            let msg = format!(
+                "the `{}` marker requires the `{}` feature of redis-rs-macro",
+                marker.name(),
+                feature
+            );
+            return Err(syn::Error::new(ident.span(), msg));
+        }
+        input.parse::<Ident>()?;
+        Ok(Some(marker))
+    }
+
+    /// Parse the serialization format option of a macro that stores values, `json` or `msgpack`,
+    /// returning `None` for other names
+    pub(crate) fn parse_format(name: &Ident) -> syn::Result<Option<Self>> {
+        let marker = match name.to_string().as_str() {
+            "json" => Marker::Json,
+            "msgpack" => Marker::MsgPack,
+            _ => return Ok(None),
+        };
+        if let Some((feature, false)) = marker.feature() {
+            let msg = format!(
+                "`{}` requires the `{}` feature of redis-rs-macro",
+                name, feature
+            );
+            return Err(syn::Error::new(name.span(), msg));
+        }
+        Ok(Some(marker))
+    }
+
+    /// The format of stored values when none is given, which is JSON. `values` names what is
+    /// stored, for the error when the `json` feature is disabled.
+    pub(crate) fn default_format(values: &str) -> syn::Result<Self> {
+        if cfg!(feature = "json") {
+            return Ok(Marker::Json);
+        }
+        let msg = format!(
+            "{} are serialized as JSON by default, which requires the `json` feature of \
+             redis-rs-macro; write `msgpack` to use MessagePack instead",
+            values
+        );
+        Err(syn::Error::new(Span::call_site(), msg))
+    }
+
+    /// The wrapper type that deserializes a reply stored in a format of
+    /// [`Marker::parse_format`]
+    pub(crate) fn reply_wrapper(self) -> TokenStream {
+        match self {
+            Marker::MsgPack => quote!(::redis_rs_macro::Msgpack),
+            _ => quote!(::redis_rs_macro::Json),
+        }
+    }
+
+    /// Generate the expression that converts the value of a marked substitution. For spread
+    /// markers, it evaluates to an iterator of the arguments.
+    pub(crate) fn expand(self, expr: &Expr) -> TokenStream {
+        // The conversion is spanned at the value, so that type errors and a `?` used outside of a
+        // function returning a `Result` point at the substitution
+        match self {
+            Marker::Json => quote_spanned! {expr.span()=>
+                ::redis_rs_macro::__private::json::to_string(&(#expr))?
+            },
+            Marker::MsgPack => quote_spanned! {expr.span()=>
+                ::redis_rs_macro::__private::msgpack::to_vec(&(#expr))?
+            },
+            Marker::Bytes => quote_spanned! {expr.span()=>
+                ::redis_rs_macro::__private::bytes::as_bytes(&(#expr))
+            },
+            Marker::Csv => quote_spanned! {expr.span()=>
+                ::redis_rs_macro::__private::csv::join(#expr)
+            },
+            Marker::Scores => quote_spanned! {expr.span()=>
+                ::redis_rs_macro::__private::scores::scores(#expr)
+            },
+            Marker::Raw => quote_spanned! {expr.span()=> #expr},
+        }
+    }
+}
+
+/// Whether the token after the next one, the one after a marker, starts an expression of its own
+/// rather than continuing an expression that starts with the marker's name
+fn starts_expr(input: ParseStream) -> bool {
+    let Some((marker, cursor)) = input.cursor().token_tree() else {
+        return false;
+    };
+    let Some((next, rest)) = cursor.token_tree() else {
+        return false;
+    };
+    if touching(marker.span(), next.span()) == Some(true) {
+        return false;
+    }
+    match next {
+        TokenTree::Group(_) | TokenTree::Literal(_) => true,
+        TokenTree::Ident(ident) => ident != "as",
+        TokenTree::Punct(punct) => match punct.as_char() {
+            // A unary operator is written against its operand, as in `&user` or `-1`, where the
+            // same operator between spaces is a binary operator on the marker's name. Without
+            // positions, as in the string form, only `&` is taken as unary.
+            '&' | '*' | '-' | '!' => rest
+                .token_tree()
+                .and_then(|(operand, _)| touching(punct.span(), operand.span()))
+                .unwrap_or(punct.as_char() == '&'),
+            // A path such as `::std::mem::take(x)`
+            ':' => {
+                punct.spacing() == Spacing::Joint
+                    && rest.punct().is_some_and(|(p, _)| p.as_char() == ':')
+            }
+            _ => false,
+        },
+    }
+}
+
+/// Whether `second` starts where `first` ends, or `None` when the spans don't have distinct
+/// positions, as for tokens parsed from a string in a macro
+fn touching(first: Span, second: Span) -> Option<bool> {
+    let (end, start) = (first.end(), second.start());
+    if end.line == 0 || (end.line, end.column) > (start.line, start.column) {
+        return None;
+    }
+    Some(end == start)
+}
@@ -0,0 +1,121 @@
+use crate::commands::{all, lookup, Access, CommandInfo, Keys};
+use crate::parse::parse_command;
+use proc_macro2::{Span, TokenStream};
+use quote::quote;
+
+/// Generate a `redis_meta!` invocation, the `redis_rs_macro::CommandMeta` of a command in the
+/// command table
+pub(crate) fn expand_meta(input: TokenStream) -> syn::Result<TokenStream> {
+    let command = parse_command(input)?;
+    let span = command
+        .args
+        .first()
+        .map_or(Span::call_site(), |arg| arg.span);
+    let Some(info) = lookup(&command.args) else {
+        let msg = "the command isn't in the command table; expected its name, and its subcommand \
+                   for container commands such as `XGROUP CREATE`";
+        return Err(syn::Error::new(span, msg));
+    };
+    let words = info.name.split(' ').count();
+    if let Some(extra) = command.args.get(words) {
+        let msg = format!(
+            "expected only the name of `{}`, without arguments",
+            info.name
+        );
+        return Err(syn::Error::new(extra.span, msg));
+    }
+    Ok(meta(info))
+}
+
+/// Generate a `redis_command_table!` invocation, the `redis_rs_macro::CommandMeta` of every command
+/// in the command table
+pub(crate) fn expand_command_table(input: TokenStream) -> syn::Result<TokenStream> {
+    if let Some(extra) = input.into_iter().next() {
+        return Err(syn::Error::new(extra.span(), "expected no arguments"));
+    }
+    let commands = all().map(meta);
+    Ok(quote!(&[#(#commands),*]))
+}
+
+/// The `redis_rs_macro::CommandMeta` of a command in the table
+fn meta(info: &CommandInfo) -> TokenStream {
+    let name = info.name;
+    let arity = info.arity;
+    let since = info.since;
+    let write = info.access == Access::Write;
+    let readonly = info.access == Access::Read;
+    // As in `COMMAND INFO`, the key range is that of the keys at fixed positions
+    let (first_key, last_key, step) = info
+        .keys
+        .iter()
+        .find_map(|keys| match *keys {
+            Keys::Range { first, last, step } => Some((first, last, step)),
+            _ => None,
+        })
+        .unwrap_or((0, 0, 0));
+    let movable_keys = info
+        .keys
+        .iter()
+        .any(|keys| !matches!(keys, Keys::Range { .. }));
+    quote! {
+        ::redis_rs_macro::CommandMeta {
+            name: #name,
+            arity: #arity,
+            write: #write,
+            readonly: #readonly,
+            first_key: #first_key,
+            last_key: #last_key,
+            step: #step,
+            movable_keys: #movable_keys,
+            since: #since,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expand(input: &str) -> syn::Result<String> {
+        expand_meta(input.parse().unwrap()).map(|output| output.to_string())
+    }
+
+    #[test]
+    fn meta_expand() {
+        assert_eq!(
+            expand("zadd").unwrap(),
+            ":: redis_rs_macro :: CommandMeta { name : \"ZADD\" , arity : - 4i32 , write : true , \
+             readonly : false , first_key : 1usize , last_key : 1isize , step : 1usize , \
+             movable_keys : false , since : \"1.2.0\" , }"
+        );
+        let output = expand("XGROUP CREATE").unwrap();
+        assert!(output.contains("first_key : 2usize"), "{}", output);
+        let output = expand("ZUNIONSTORE").unwrap();
+        assert!(output.contains("movable_keys : true"), "{}", output);
+        let output = expand("PING").unwrap();
+        assert!(
+            output.contains("write : false , readonly : false , first_key : 0usize"),
+            "{}",
+            output
+        );
+    }
+
+    #[test]
+    fn meta_errors() {
+        let err = |input: &str| expand(input).unwrap_err().to_string();
+        assert!(err("NOTACOMMAND").contains("isn't in the command table"));
+        assert!(err("{name}").contains("isn't in the command table"));
+        assert!(err("GET k").contains("without arguments"));
+    }
+
+    #[test]
+    fn command_table_expand() {
+        let output = expand_command_table(TokenStream::new())
+            .unwrap()
+            .to_string();
+        assert!(output.starts_with("& [:: redis_rs_macro :: CommandMeta { name : \"APPEND\""));
+        assert!(output.contains("name : \"XREADGROUP\""), "{}", output);
+        let err = expand_command_table("GET".parse().unwrap()).unwrap_err();
+        assert!(err.to_string().contains("expected no arguments"));
+    }
+}
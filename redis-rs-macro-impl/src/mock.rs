@@ -0,0 +1,65 @@
+use crate::expand::expand_command;
+use crate::parse::parse_command;
+use proc_macro2::{Spacing, TokenStream, TokenTree};
+use quote::quote;
+use syn::Expr;
+
+/// Split the input of `redis_mock!` at its top level `=>`, into the command and the reply
+fn split_reply(input: TokenStream) -> syn::Result<(TokenStream, TokenStream)> {
+    let tokens: Vec<TokenTree> = input.into_iter().collect();
+    let arrow = tokens.windows(2).position(|pair| match pair {
+        [TokenTree::Punct(eq), TokenTree::Punct(gt)] => {
+            eq.as_char() == '=' && eq.spacing() == Spacing::Joint && gt.as_char() == '>'
+        }
+        _ => false,
+    });
+    let Some(arrow) = arrow else {
+        let msg = "expected `=>` followed by the reply, such as `GET foo => Ok(\"bar\")`";
+        return Err(syn::Error::new(proc_macro2::Span::call_site(), msg));
+    };
+    let command = tokens[..arrow].iter().cloned().collect();
+    let reply = tokens[arrow + 2..].iter().cloned().collect();
+    Ok((command, reply))
+}
+
+/// Generate a `redis_mock!` invocation, a `redis_test::MockCmd` that expects the command and
+/// replies with the given result
+pub(crate) fn expand_mock(input: TokenStream) -> syn::Result<TokenStream> {
+    let (command, reply) = split_reply(input)?;
+    let reply: Expr = syn::parse2(reply)?;
+    let cmd = expand_command(&parse_command(command)?)?;
+    Ok(quote!(redis_test::MockCmd::new(#cmd, #reply)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expand(input: &str) -> syn::Result<String> {
+        expand_mock(input.parse().unwrap()).map(|output| output.to_string())
+    }
+
+    #[test]
+    fn mock_expand() {
+        let output = expand("SET user:{id} ?[{nx} => NX] => Ok(\"OK\")").unwrap();
+        assert!(
+            output.starts_with("redis_test :: MockCmd :: new ({"),
+            "{}",
+            output
+        );
+        assert!(output.ends_with("} , Ok (\"OK\"))"), "{}", output);
+        // The `=>` of the condition stays in the command
+        assert!(output.contains("\"NX\""), "{}", output);
+    }
+
+    #[test]
+    fn mock_errors() {
+        let err = |input: &str| expand(input).unwrap_err().to_string();
+        let e = err("GET foo");
+        assert!(e.contains("expected `=>`"), "{}", e);
+        let e = err("=> Ok(1)");
+        assert!(e.contains("expected a redis command"), "{}", e);
+        let e = err("GET foo =>");
+        assert!(e.contains("expected an expression"), "{}", e);
+    }
+}
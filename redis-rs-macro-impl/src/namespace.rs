@@ -0,0 +1,260 @@
+use crate::commands;
+use crate::keys::{key_roles, KeyRole};
+use crate::parse::{Arg, Command, Piece};
+use crate::scope::{mark_macros, namespace_marker};
+use proc_macro2::{Ident, Span, TokenStream};
+use quote::quote;
+use syn::parse::{ParseStream, Parser};
+use syn::{Expr, ItemFn, Lit, LitStr, Token};
+
+/// Prepend the namespaces to the keys of a command, the innermost one closest to the key. Literal
+/// namespaces are joined to the keys at compile time, so keys that were literal stay literal.
+/// Commands that aren't in the command table are an error, since their keys can't be found.
+pub(crate) fn apply_namespaces(command: &mut Command, namespaces: &[Expr]) -> syn::Result<()> {
+    if namespaces.is_empty() {
+        return Ok(());
+    }
+    if commands::lookup(&command.args).is_none() {
+        let msg = "this command isn't in the command table, so the namespace can't be applied to \
+            its keys; write `@no_namespace` before the command to send its keys as written";
+        return Err(syn::Error::new(command.args[0].span, msg));
+    }
+    let roles = key_roles(command);
+    for (arg, role) in command.args.iter_mut().zip(roles) {
+        for namespace in namespaces {
+            prefix_arg(arg, role, namespace)?;
+        }
+    }
+    Ok(())
+}
+
+fn prefix_arg(arg: &mut Arg, role: KeyRole, namespace: &Expr) -> syn::Result<()> {
+    let helper = |name: &str| {
+        let name = Ident::new(name, Span::call_site());
+        quote!(::redis_rs_macro::__private::namespace::#name)
+    };
+    match role {
+        KeyRole::None => return Ok(()),
+        KeyRole::Key | KeyRole::Pairs => {}
+        KeyRole::Unknown => {
+            let msg = "cannot tell whether this argument is a key, so the namespace can't be \
+                applied to it; move spreads and optional arguments after the keys of the command";
+            return Err(syn::Error::new(arg.span, msg));
+        }
+    }
+    match arg.pieces.as_mut_slice() {
+        [Piece::Spread(expr)] => {
+            let wrap = match role {
+                KeyRole::Pairs => helper("pairs"),
+                _ => helper("keys"),
+            };
+            **expr = syn::parse_quote!(#wrap(&(#namespace), #expr));
+        }
+        [Piece::Expr(expr)] => {
+            // Wrapped rather than joined, so that a value with several arguments keeps them
+            let wrap = helper("key");
+            **expr = syn::parse_quote!(#wrap(&(#namespace), #expr));
+        }
+        [Piece::Optional { expr, .. }] => {
+            let wrap = helper("key");
+            let key = Ident::new("key", Span::mixed_site());
+            **expr = syn::parse_quote!((#expr).map(|#key| #wrap(&(#namespace), #key)));
+        }
+        [Piece::Conditional { args, .. }] => {
+            for arg in args {
+                prefix_arg(arg, KeyRole::Key, namespace)?;
+            }
+        }
+        [Piece::Fields(_)] => {
+            let msg = "keys cannot be a field spread in a namespace";
+            return Err(syn::Error::new(arg.span, msg));
+        }
+        [Piece::Marked { marker, .. }] if marker.is_spread() => {
+            let msg = "keys cannot be a spread with a marker in a namespace";
+            return Err(syn::Error::new(arg.span, msg));
+        }
+        _ => {
+            // Literal namespaces are joined to the rest of the key at compile time
+            let piece = match namespace {
+                Expr::Lit(syn::ExprLit {
+                    lit: Lit::Str(lit), ..
+                }) => Piece::Str(LitStr::new(&lit.value(), arg.span)),
+                _ => Piece::Expr(Box::new(namespace.clone())),
+            };
+            arg.pieces.insert(0, piece);
+        }
+    }
+    Ok(())
+}
+
+/// Generate a `#[redis_namespace("prefix")]` item, whose nested macro calls get `prefix` in front
+/// of the keys of their commands
+pub(crate) fn expand_namespace(args: TokenStream, item: TokenStream) -> syn::Result<TokenStream> {
+    let prefix: LitStr = syn::parse2(args)?;
+    if prefix.value().is_empty() {
+        return Err(syn::Error::new(prefix.span(), "the namespace is empty"));
+    }
+    let prefix = Expr::Lit(syn::parse_quote!(#prefix));
+    Ok(mark_macros(item, &namespace_marker(&prefix)))
+}
+
+/// Generate a `#[redis_tenant(prefix = expr)]` function, whose nested macro calls get the value of
+/// `expr` in front of the keys of their commands. The expression is evaluated where each key is
+/// written, so it can borrow from the parameters of the function.
+pub(crate) fn expand_tenant(args: TokenStream, item: TokenStream) -> syn::Result<TokenStream> {
+    let parser = |input: ParseStream| {
+        let name: Ident = input.parse()?;
+        if name != "prefix" {
+            return Err(syn::Error::new(name.span(), "expected `prefix = <expr>`"));
+        }
+        input.parse::<Token![=]>()?;
+        input.parse::<Expr>()
+    };
+    let prefix = parser.parse2(args)?;
+    if let Err(err) = syn::parse2::<ItemFn>(item.clone()) {
+        let msg = "`#[redis_tenant]` goes on a function, where its prefix can be evaluated";
+        return Err(syn::Error::new(err.span(), msg));
+    }
+    Ok(mark_macros(item, &namespace_marker(&prefix)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse::parse_command;
+
+    fn render(input: &str) -> syn::Result<Vec<String>> {
+        let command = parse_command(input.parse().unwrap())?;
+        Ok(command
+            .args
+            .iter()
+            .map(|arg| {
+                arg.pieces
+                    .iter()
+                    .map(|piece| match piece {
+                        Piece::Text(text) => text.clone(),
+                        Piece::Str(lit) => lit.value(),
+                        Piece::Spread(expr) => format!("{{..{}}}", quote!(#expr)),
+                        Piece::Optional { expr, .. } => format!("{{{}?}}", quote!(#expr)),
+                        Piece::Expr(expr) => format!("{{{}}}", quote!(#expr)),
+                        _ => "?".to_string(),
+                    })
+                    .collect()
+            })
+            .collect())
+    }
+
+    #[test]
+    fn namespace_apply() {
+        assert_eq!(
+            render("@namespace(\"app:\") SET user:{id} 1").unwrap(),
+            ["SET", "app:user:{id}", "1"]
+        );
+        assert_eq!(
+            render("@namespace(\"b:\") @namespace(\"a:\") MGET x {..keys}").unwrap(),
+            [
+                "MGET",
+                "a:b:x",
+                "{..:: redis_rs_macro :: __private :: namespace :: keys (& (\"a:\") , \
+                 :: redis_rs_macro :: __private :: namespace :: keys (& (\"b:\") , keys))}"
+            ]
+        );
+        assert_eq!(
+            render("@namespace(tenant) MSET {..pairs}").unwrap()[1],
+            "{..:: redis_rs_macro :: __private :: namespace :: pairs (& (tenant) , pairs)}"
+        );
+        assert_eq!(
+            render("@namespace(tenant) GET {key}").unwrap()[1],
+            "{:: redis_rs_macro :: __private :: namespace :: key (& (tenant) , key)}"
+        );
+        assert_eq!(
+            render("@namespace(tenant) PUBLISH chan {msg}").unwrap(),
+            ["PUBLISH", "chan", "{msg}"]
+        );
+        assert_eq!(
+            render("@namespace(\"app:\") @no_namespace MODULE.CMD app:k").unwrap(),
+            ["MODULE.CMD", "app:k"]
+        );
+        assert_eq!(
+            render("@namespace(\"app:\") @no_namespace GET k").unwrap(),
+            ["GET", "k"]
+        );
+    }
+
+    #[test]
+    fn namespace_errors() {
+        let err = |input: &str| render(input).unwrap_err().to_string();
+        let e = err("@namespace(\"app:\") MSET {..pairs} a 1");
+        assert!(e.contains("cannot tell whether"), "{}", e);
+        let e = err("@namespace(\"app:\") DEL {*user}");
+        assert!(e.contains("field spread"), "{}", e);
+        let e = err("@namespace(\"app:\") MODULE.CMD k");
+        assert!(e.contains("isn't in the command table"), "{}", e);
+        let e = err("@namespace(tenant) {name} k");
+        assert!(e.contains("`@no_namespace`"), "{}", e);
+        let e = expand_namespace(
+            quote!(""),
+            quote!(
+                fn f() {}
+            ),
+        )
+        .unwrap_err()
+        .to_string();
+        assert!(e.contains("empty"), "{}", e);
+        let e = expand_namespace(
+            quote!(app),
+            quote!(
+                fn f() {}
+            ),
+        )
+        .unwrap_err()
+        .to_string();
+        assert!(e.contains("expected string literal"), "{}", e);
+    }
+
+    #[test]
+    fn tenant_expand() {
+        let output = expand_tenant(
+            quote!(prefix = self.tenant),
+            quote!(
+                fn f(&self) {
+                    redis!(GET a)
+                }
+            ),
+        )
+        .unwrap()
+        .to_string();
+        assert_eq!(
+            output,
+            "fn f (& self) { redis ! (@ namespace (self . tenant) GET a) }"
+        );
+    }
+
+    #[test]
+    fn tenant_errors() {
+        let err = |args: TokenStream, item: TokenStream| {
+            expand_tenant(args, item).unwrap_err().to_string()
+        };
+        let e = err(
+            quote!(tenant = id),
+            quote!(
+                fn f() {}
+            ),
+        );
+        assert!(e.contains("expected `prefix = <expr>`"), "{}", e);
+        let e = err(
+            quote!(prefix),
+            quote!(
+                fn f() {}
+            ),
+        );
+        assert!(e.contains("expected `=`"), "{}", e);
+        let e = err(
+            quote!(prefix = id),
+            quote!(
+                mod m {}
+            ),
+        );
+        assert!(e.contains("goes on a function"), "{}", e);
+    }
+}
@@ -0,0 +1,997 @@
+use crate::bind::{bind, split_bindings, Binding};
+use crate::marker::Marker;
+use crate::scope::split_scope;
+use proc_macro2::{Delimiter, Group, LineColumn, Spacing, Span, TokenStream, TokenTree};
+use quote::ToTokens;
+use redis_rs_macro_syntax::unescape;
+use syn::parse::{Parse, ParseStream, Parser};
+use syn::{Expr, ExprUnary, Ident, Lit, LitByteStr, LitStr, RangeLimits, Token, UnOp};
+
+/// A redis command parsed from the macro input. The first argument is the command name.
+pub(crate) struct Command {
+    pub(crate) args: Vec<Arg>,
+    pub(crate) bindings: Vec<Binding>,
+}
+
+/// A single redis command argument, made up of one or more pieces that were written next to each
+/// other without any whitespace in between (e.g. `user:{id}`).
+pub(crate) struct Arg {
+    pub(crate) pieces: Vec<Piece>,
+    pub(crate) span: Span,
+}
+
+/// One piece of an argument
+pub(crate) enum Piece {
+    /// Text taken verbatim from the input, e.g. `user:` or `42.2`
+    Text(String),
+    /// A quoted string literal
+    Str(LitStr),
+    /// A byte string literal, e.g. `b"\x00\x01"`
+    ByteStr(LitByteStr),
+    /// A `{expr}` substitution
+    Expr(Box<Expr>),
+    /// A `{marker expr}` substitution, such as `{json user}`
+    Marked { marker: Marker, expr: Box<Expr> },
+    /// A `{..expr}` substitution, where every item of `expr` becomes its own argument
+    Spread(Box<Expr>),
+    /// A `{*expr}` substitution, where `expr` is written as alternating field names and values
+    Fields(Box<Expr>),
+    /// A `{expr?}` substitution, which is only appended when the `Option` is `Some`. An uppercase
+    /// option name written right before it (e.g. `EX {ttl?}`) is dropped along with it.
+    Optional {
+        keyword: Option<String>,
+        expr: Box<Expr>,
+    },
+    /// A `?[{cond} => args...]` group, whose arguments are only appended when `cond` is true
+    Conditional { cond: Box<Expr>, args: Vec<Arg> },
+}
+
+impl Arg {
+    /// The text of an argument that is a single word or string, used to recognize command names
+    /// and option keywords
+    pub(crate) fn word(&self) -> Option<String> {
+        match self.pieces.as_slice() {
+            [Piece::Text(text)] => Some(text.clone()),
+            [Piece::Str(lit)] => Some(lit.value()),
+            _ => None,
+        }
+    }
+}
+
+impl Piece {
+    /// Describes pieces that expand into a varying number of arguments, and so have to make up
+    /// a whole argument on their own
+    pub(crate) fn standalone_kind(&self) -> Option<&'static str> {
+        match self {
+            Piece::Spread(_) => Some("a spread"),
+            Piece::Fields(_) => Some("a field spread"),
+            Piece::Marked { marker, .. } if marker.is_spread() => Some("a spread"),
+            Piece::Optional { .. } => Some("an optional argument"),
+            Piece::Conditional { .. } => Some("a conditional group"),
+            _ => None,
+        }
+    }
+}
+
+/// Parse the input of the `redis!` macro into a command
+pub(crate) fn parse_command(input: TokenStream) -> syn::Result<Command> {
+    let (scope, input) = split_scope(input)?;
+    let mut command = parse_args(input, 1)?;
+    if command.args.is_empty() {
+        return Err(syn::Error::new(
+            Span::call_site(),
+            "expected a redis command",
+        ));
+    }
+    scope.apply(&mut command)?;
+    Ok(command)
+}
+
+/// Parse a list of arguments, as taken by `redis_args!`, or a whole command when `skip` is 1 to
+/// leave the command name out of option names
+pub(crate) fn parse_args(input: TokenStream, skip: usize) -> syn::Result<Command> {
+    let (input, bindings) = split_bindings(input);
+    // A lone string literal holds the entire command, e.g. redis!("SET foo bar")
+    let args = match syn::parse2::<LitStr>(input.clone()) {
+        Ok(lit) => parse_str_command(&lit)?,
+        Err(_) => {
+            let mut walker = Walker::default();
+            walker.walk(input)?;
+            walker.finish_arg();
+            walker.args
+        }
+    };
+    check_raw(&args, skip)?;
+    let mut args = check_args(args, skip)?;
+    let bindings = match bindings {
+        Some(bindings) => bind(&mut args, bindings)?,
+        None => vec![],
+    };
+    Ok(Command { args, bindings })
+}
+
+/// Make sure the `raw` marker is only used for the command name, which comes before `skip`
+fn check_raw(args: &[Arg], skip: usize) -> syn::Result<()> {
+    for arg in args.iter().skip(skip) {
+        for piece in &arg.pieces {
+            match piece {
+                Piece::Marked {
+                    marker: Marker::Raw,
+                    ..
+                } => {
+                    let msg = "the `raw` marker is only for dynamic command names";
+                    return Err(syn::Error::new(arg.span, msg));
+                }
+                Piece::Conditional { args, .. } => check_raw(args, 0)?,
+                _ => {}
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Make sure pieces that must stand alone aren't joined with anything, and attach option names
+/// to the optional arguments that follow them. The first `skip` arguments are never treated as
+/// option names.
+fn check_args(args: Vec<Arg>, skip: usize) -> syn::Result<Vec<Arg>> {
+    for arg in &args {
+        if arg.pieces.len() > 1 {
+            if let Some(kind) = arg.pieces.iter().find_map(Piece::standalone_kind) {
+                let msg = format!(
+                    "{} must be a whole argument, and cannot be joined with other text",
+                    kind
+                );
+                return Err(syn::Error::new(arg.span, msg));
+            }
+        }
+    }
+    Ok(attach_option_keywords(args, skip))
+}
+
+/// Move uppercase option names (e.g. the `EX` in `EX {ttl?}`) into the optional argument that
+/// follows them, so that both are left out when the value is `None`
+fn attach_option_keywords(args: Vec<Arg>, skip: usize) -> Vec<Arg> {
+    let mut output: Vec<Arg> = Vec::with_capacity(args.len());
+    for mut arg in args {
+        if let [Piece::Optional { keyword, .. }] = arg.pieces.as_mut_slice() {
+            if output.len() > skip {
+                if let Some(text) = output.last().and_then(option_keyword) {
+                    *keyword = Some(text.to_string());
+                    output.pop();
+                }
+            }
+        }
+        output.push(arg);
+    }
+    output
+}
+
+/// The text of an argument if it looks like an option name, such as `EX` or `WITHSCORES`
+fn option_keyword(arg: &Arg) -> Option<&str> {
+    match arg.pieces.as_slice() {
+        [Piece::Text(text)]
+            if text.starts_with(|c: char| c.is_ascii_uppercase())
+                && text.chars().all(|c| {
+                    c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_' || c == '-'
+                }) =>
+        {
+            Some(text)
+        }
+        _ => None,
+    }
+}
+
+/// Split the contents of a string literal into arguments using the redis-cli rules
+fn parse_str_command(lit: &LitStr) -> syn::Result<Vec<Arg>> {
+    let span = lit.span();
+    let mut args = vec![];
+    let split = redis_rs_macro_syntax::parse_command(&lit.value());
+    for arg in split.map_err(|err| syn::Error::new(span, err))? {
+        let pieces = if arg.is_quoted {
+            let data = unescape(&arg.data).map_err(|err| syn::Error::new(span, err))?;
+            // Escapes such as `\xff` can leave bytes that aren't UTF-8
+            match String::from_utf8(data) {
+                Ok(data) => vec![Piece::Str(LitStr::new(&data, span))],
+                Err(err) => vec![Piece::ByteStr(LitByteStr::new(err.as_bytes(), span))],
+            }
+        } else if arg.is_braced {
+            vec![str_substitution(&arg.data, span)?]
+        } else if !arg.parts.is_empty() {
+            // Text joined with braces, e.g. user:{id}
+            arg.parts
+                .into_iter()
+                .map(|part| match part.is_braced {
+                    true => str_substitution(&part.data, span),
+                    false => Ok(Piece::Text(part.data)),
+                })
+                .collect::<syn::Result<_>>()?
+        } else {
+            vec![Piece::Text(arg.data)]
+        };
+        args.push(Arg { pieces, span });
+    }
+    Ok(args)
+}
+
+/// Parse the text inside the braces of a string command substitution
+fn str_substitution(data: &str, span: Span) -> syn::Result<Piece> {
+    if data.trim().is_empty() {
+        return Err(empty_substitution(span));
+    }
+    let sub = syn::parse_str(data).map_err(|err| syn::Error::new(span, err))?;
+    substitution(sub, span)
+}
+
+/// The text of a literal that isn't a string. Numbers written with a base prefix, underscores, or
+/// a type suffix are normalized to plain decimal (e.g. `0x1F` becomes `31`), as redis would
+/// otherwise read them as different values. Other numbers keep their text, so leading zeros and
+/// exponents are kept.
+fn number_text(lit: &Lit) -> String {
+    const SUFFIXES: &[&str] = &[
+        "", "u8", "u16", "u32", "u64", "u128", "usize", "i8", "i16", "i32", "i64", "i128", "isize",
+        "f32", "f64",
+    ];
+    let text = lit.to_token_stream().to_string();
+    let (digits, suffix) = match lit {
+        Lit::Int(int) => (int.base10_digits(), int.suffix()),
+        Lit::Float(float) => (float.base10_digits(), float.suffix()),
+        _ => return text,
+    };
+    let prefixed = ["0x", "0o", "0b"]
+        .iter()
+        .any(|prefix| text.starts_with(prefix));
+    let normalize = prefixed || text.contains('_') || !suffix.is_empty();
+    if normalize && SUFFIXES.contains(&suffix) {
+        digits.to_string()
+    } else {
+        text
+    }
+}
+
+fn empty_substitution(span: Span) -> syn::Error {
+    syn::Error::new(
+        span,
+        "empty expression substitution; to pass literal braces, quote them as \"{}\"",
+    )
+}
+
+/// Turn the expression inside a `{}` substitution into a piece. syn parses `{..expr}` as a range
+/// without a start, which marks a spread, and `{expr?}` as a try expression, which marks an
+/// optional argument.
+fn substitution(sub: Substitution, span: Span) -> syn::Result<Piece> {
+    let Substitution {
+        spread,
+        marker,
+        expr,
+        spec,
+    } = sub;
+    match (classify(spread, marker, expr, span)?, spec) {
+        (piece, None) => Ok(piece),
+        // {expr:spec} is formatted with `format!`, e.g. {price:.2}
+        (Piece::Expr(expr), Some(spec)) => {
+            let format = LitStr::new(&format!("{{:{}}}", spec.value()), spec.span());
+            Ok(Piece::Expr(Box::new(
+                syn::parse_quote_spanned!(spec.span()=> ::std::format!(#format, #expr)),
+            )))
+        }
+        (_, Some(spec)) => Err(syn::Error::new(
+            spec.span(),
+            "a format spec can only be used with a plain `{expr:spec}` substitution",
+        )),
+    }
+}
+
+/// Work out the kind of piece a substitution is from its marker and the shape of its expression
+fn classify(spread: bool, marker: Option<Marker>, expr: Expr, span: Span) -> syn::Result<Piece> {
+    if let Some(marker) = marker {
+        if marker.is_spread() && !spread {
+            let msg = format!(
+                "the `{0}` marker spreads its value, so write it as `{{..{0} expr}}`",
+                marker.name()
+            );
+            return Err(syn::Error::new(span, msg));
+        }
+        return match expr {
+            Expr::Range(_) | Expr::Try(_) => {
+                let msg = format!(
+                    "a `{}` substitution cannot also be a spread or an optional argument",
+                    marker.name()
+                );
+                Err(syn::Error::new(span, msg))
+            }
+            expr => Ok(Piece::Marked {
+                marker,
+                expr: Box::new(expr),
+            }),
+        };
+    }
+    match expr {
+        Expr::Range(range)
+            if range.start.is_none() && matches!(range.limits, RangeLimits::HalfOpen(_)) =>
+        {
+            match range.end {
+                Some(end) => Ok(Piece::Spread(end)),
+                None => Err(syn::Error::new(
+                    span,
+                    "expected an expression to spread after `..`",
+                )),
+            }
+        }
+        // {expr?} marks an optional argument. Use {(expr?)} to propagate errors instead.
+        Expr::Try(try_expr) => Ok(Piece::Optional {
+            keyword: None,
+            expr: try_expr.expr,
+        }),
+        // {&expr?} parses as a reference to a try expression, but means an optional reference
+        Expr::Reference(mut reference) if matches!(*reference.expr, Expr::Try(_)) => {
+            let Expr::Try(try_expr) = *reference.expr else {
+                unreachable!()
+            };
+            reference.expr = try_expr.expr;
+            Ok(Piece::Optional {
+                keyword: None,
+                expr: Box::new(Expr::Reference(reference)),
+            })
+        }
+        // {*expr} spreads fields. Use {(*expr)} to dereference instead.
+        Expr::Unary(ExprUnary {
+            op: UnOp::Deref(_),
+            expr,
+            ..
+        }) => Ok(Piece::Fields(expr)),
+        // The parentheses of {(expr?)} and {(*expr)} are only there to tell them apart from an
+        // optional and a field spread
+        Expr::Paren(paren)
+            if matches!(
+                *paren.expr,
+                Expr::Try(_)
+                    | Expr::Unary(ExprUnary {
+                        op: UnOp::Deref(_),
+                        ..
+                    })
+            ) =>
+        {
+            Ok(Piece::Expr(paren.expr))
+        }
+        expr => Ok(Piece::Expr(Box::new(expr))),
+    }
+}
+
+/// The contents of a `{}` substitution
+struct Substitution {
+    /// Whether the marker came after a `..`
+    spread: bool,
+    marker: Option<Marker>,
+    expr: Expr,
+    /// The format spec after a `:`, e.g. `.2` in `{price:.2}`
+    spec: Option<LitStr>,
+}
+
+impl Parse for Substitution {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        // Spread markers come after a `..`, e.g. {..scores pairs}
+        let fork = input.fork();
+        let spread = fork.parse::<Token![..]>().is_ok()
+            && Marker::parse(&fork).is_ok_and(|marker| marker.is_some_and(Marker::is_spread));
+        if spread {
+            input.parse::<Token![..]>()?;
+        }
+        let marker = Marker::parse(input)?;
+        let expr = input.parse()?;
+        let spec = if input.peek(Token![:]) {
+            let colon: Token![:] = input.parse()?;
+            // The spec is made of whatever tokens follow, written without spaces in between
+            let spec = input.step(|cursor| {
+                let mut spec = String::new();
+                let mut rest = *cursor;
+                while let Some((tt, next)) = rest.token_tree() {
+                    spec.push_str(&tt.to_string());
+                    rest = next;
+                }
+                Ok((spec, rest))
+            })?;
+            if spec.is_empty() {
+                return Err(syn::Error::new(
+                    colon.span,
+                    "expected a format spec after `:`",
+                ));
+            }
+            Some(LitStr::new(&spec, colon.span))
+        } else {
+            None
+        };
+        Ok(Substitution {
+            spread,
+            marker,
+            expr,
+            spec,
+        })
+    }
+}
+
+/// Parse the contents of a `{}` group. Parsing the group rather than its inner stream lets errors
+/// at the end of the contents point at the closing brace.
+fn parse_braced<T: Parse>(group: Group) -> syn::Result<T> {
+    let parser = |input: ParseStream| {
+        let content;
+        syn::braced!(content in input);
+        content.parse::<T>()
+    };
+    parser.parse2(TokenTree::Group(group).into())
+}
+
+/// Parse the `{NAME}` part of a `${NAME}` environment variable, which is read at compile time
+/// with `env!`, so that cargo rebuilds the crate when the variable changes
+fn env_var(group: Group) -> syn::Result<Piece> {
+    let name: Ident = syn::parse2(group.stream()).map_err(|_| {
+        syn::Error::new(
+            group.span(),
+            "expected an environment variable name, such as `${REDIS_KEY_PREFIX}`",
+        )
+    })?;
+    let name = LitStr::new(&name.to_string(), name.span());
+    let msg = LitStr::new(
+        &format!(
+            "environment variable `{}` is not defined at compile time",
+            name.value()
+        ),
+        name.span(),
+    );
+    Ok(Piece::Expr(Box::new(
+        syn::parse_quote_spanned!(group.span()=> ::core::env!(#name, #msg)),
+    )))
+}
+
+/// Parse the `[{cond} => args...]` part of a `?[{cond} => args...]` conditional group
+fn parse_conditional(group: Group) -> syn::Result<Piece> {
+    let mut tokens = group.stream().into_iter();
+    let cond = match tokens.next() {
+        Some(TokenTree::Group(cond)) if cond.delimiter() == Delimiter::Brace => {
+            if cond.stream().is_empty() {
+                return Err(syn::Error::new(cond.span(), "expected a condition"));
+            }
+            parse_braced(cond)?
+        }
+        other => {
+            let span = other.map_or(group.span_close(), |tt| tt.span());
+            let msg = "expected a `{condition}` at the start of the conditional group";
+            return Err(syn::Error::new(span, msg));
+        }
+    };
+    match (tokens.next(), tokens.next()) {
+        (Some(TokenTree::Punct(eq)), Some(TokenTree::Punct(gt)))
+            if eq.as_char() == '=' && eq.spacing() == Spacing::Joint && gt.as_char() == '>' => {}
+        (other, _) => {
+            let span = other.map_or(group.span_close(), |tt| tt.span());
+            return Err(syn::Error::new(span, "expected `=>` after the condition"));
+        }
+    }
+    let mut walker = Walker::default();
+    walker.walk(tokens.collect())?;
+    walker.finish_arg();
+    if walker.args.is_empty() {
+        let msg = "expected arguments after `=>`";
+        return Err(syn::Error::new(group.span_close(), msg));
+    }
+    Ok(Piece::Conditional {
+        cond: Box::new(cond),
+        args: check_args(walker.args, 0)?,
+    })
+}
+
+/// Walks the macro input token by token, using span locations to find out which tokens were
+/// written next to each other and so belong to the same argument.
+#[derive(Default)]
+struct Walker {
+    args: Vec<Arg>,
+    current: Option<Arg>,
+    prev_end: Option<LineColumn>,
+    comment_line: Option<usize>,
+}
+
+impl Walker {
+    fn walk(&mut self, tokens: TokenStream) -> syn::Result<()> {
+        let mut tokens = tokens.into_iter().peekable();
+        while let Some(tt) = tokens.next() {
+            let span = tt.span();
+            if self.comment_line == Some(span.start().line) {
+                continue;
+            }
+            self.comment_line = None;
+            match tt {
+                TokenTree::Punct(punct) => {
+                    let glued = self.is_glued(span);
+                    // A `#` directly followed by a number is text, like the `#1` offsets of BITFIELD
+                    let number = matches!(tokens.peek(), Some(TokenTree::Literal(_)))
+                        && tokens
+                            .peek()
+                            .is_some_and(|n| n.span().start() == span.end());
+                    if punct.as_char() == '#' && !glued && !number {
+                        // Comment out the rest of the line, like in a redis-cli script
+                        self.comment_line = Some(span.start().line);
+                        self.finish_arg();
+                    } else if punct.as_char() == '$'
+                        && matches!(tokens.peek(), Some(TokenTree::Group(g)) if g.delimiter() == Delimiter::Brace && g.span().start() == span.end())
+                    {
+                        if let Some(TokenTree::Group(group)) = tokens.next() {
+                            let end = group.span().end();
+                            self.push(env_var(group)?, span);
+                            self.prev_end = Some(end);
+                        }
+                    } else if punct.as_char() == '?'
+                        && !glued
+                        && matches!(tokens.peek(), Some(TokenTree::Group(g)) if g.delimiter() == Delimiter::Bracket)
+                    {
+                        if let Some(TokenTree::Group(group)) = tokens.next() {
+                            let end = group.span().end();
+                            self.push(parse_conditional(group)?, span);
+                            self.prev_end = Some(end);
+                        }
+                    } else if punct.as_char() == ','
+                        && tokens.peek().is_none_or(|n| n.span().start() != span.end())
+                    {
+                        // A comma that isn't glued to the next token separates arguments
+                        self.finish_arg();
+                    } else {
+                        self.push(Piece::Text(punct.as_char().to_string()), span);
+                    }
+                }
+                TokenTree::Ident(ident) => self.push(Piece::Text(ident.to_string()), span),
+                TokenTree::Literal(literal) => match Lit::new(literal.clone()) {
+                    Lit::Str(lit) => self.push(Piece::Str(lit), span),
+                    Lit::ByteStr(lit) => self.push(Piece::ByteStr(lit), span),
+                    lit => self.push(Piece::Text(number_text(&lit)), span),
+                },
+                TokenTree::Group(group) => match group.delimiter() {
+                    Delimiter::Brace => {
+                        if group.stream().is_empty() {
+                            return Err(empty_substitution(span));
+                        }
+                        self.push(substitution(parse_braced(group)?, span)?, span);
+                    }
+                    // Expressions passed in through macro_rules arrive as invisible groups
+                    Delimiter::None => {
+                        self.push(Piece::Expr(Box::new(syn::parse2(group.stream())?)), span)
+                    }
+                    // Other delimiters are plain text, and their contents are split as usual
+                    Delimiter::Parenthesis | Delimiter::Bracket => {
+                        let (open, close) = match group.delimiter() {
+                            Delimiter::Parenthesis => ("(", ")"),
+                            _ => ("[", "]"),
+                        };
+                        self.push(Piece::Text(open.into()), group.span_open());
+                        self.walk(group.stream())?;
+                        if self.comment_line != Some(group.span_close().start().line) {
+                            self.push(Piece::Text(close.into()), group.span_close());
+                        }
+                    }
+                },
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether a token starting at `span` directly follows the previous token
+    fn is_glued(&self, span: Span) -> bool {
+        self.prev_end == Some(span.start())
+    }
+
+    /// Append a piece to the current argument, or start a new argument if there was whitespace
+    /// before it
+    fn push(&mut self, piece: Piece, span: Span) {
+        if !self.is_glued(span) {
+            self.finish_arg();
+        }
+        self.prev_end = Some(span.end());
+        let arg = self.current.get_or_insert_with(|| Arg {
+            pieces: vec![],
+            span,
+        });
+        match (arg.pieces.last_mut(), piece) {
+            (Some(Piece::Text(text)), Piece::Text(next)) => text.push_str(&next),
+            (_, piece) => arg.pieces.push(piece),
+        }
+    }
+
+    fn finish_arg(&mut self) {
+        self.prev_end = None;
+        if let Some(arg) = self.current.take() {
+            self.args.push(arg);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Render parsed arguments back into a string, with substitutions in braces and string
+    /// literals in quotes
+    fn render(input: &str) -> Vec<String> {
+        let tokens: TokenStream = input.parse().unwrap();
+        render_args(&parse_command(tokens).unwrap().args)
+    }
+
+    fn render_args(args: &[Arg]) -> Vec<String> {
+        args.iter()
+            .map(|arg| {
+                arg.pieces
+                    .iter()
+                    .map(|piece| match piece {
+                        Piece::Text(text) => text.clone(),
+                        Piece::Str(lit) => format!("{:?}", lit.value()),
+                        Piece::ByteStr(lit) => format!("b\"{}\"", lit.value().escape_ascii()),
+                        Piece::Expr(expr) => format!("{{{}}}", expr.to_token_stream()),
+                        Piece::Marked { marker, expr } => format!(
+                            "{{{}{} {}}}",
+                            if marker.is_spread() { ".." } else { "" },
+                            marker.name(),
+                            expr.to_token_stream()
+                        ),
+                        Piece::Spread(expr) => format!("{{..{}}}", expr.to_token_stream()),
+                        Piece::Fields(expr) => format!("{{*{}}}", expr.to_token_stream()),
+                        Piece::Optional { keyword, expr } => format!(
+                            "{}{{{}?}}",
+                            keyword
+                                .as_ref()
+                                .map_or(String::new(), |k| format!("{} ", k)),
+                            expr.to_token_stream()
+                        ),
+                        Piece::Conditional { cond, args } => format!(
+                            "?[{{{}}} => {}]",
+                            cond.to_token_stream(),
+                            render_args(args).join(" ")
+                        ),
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn parse_(cases: &[(&str, &[&str])]) {
+        for &(input, expected) in cases {
+            let output = render(input);
+            assert!(
+                expected == output.as_slice(),
+                "Input: {:?}\nExpected: {:?}\nBut found: {:?}",
+                input,
+                expected,
+                output
+            );
+        }
+    }
+
+    fn parse_err(input: &str) -> String {
+        let tokens: TokenStream = input.parse().unwrap();
+        match parse_command(tokens) {
+            Ok(_) => panic!("Input: {:?} should not parse", input),
+            Err(err) => err.to_string(),
+        }
+    }
+
+    #[test]
+    fn parse_words() {
+        parse_(&[
+            ("SET foo bar", &["SET", "foo", "bar"]),
+            ("SET foo-bar 1", &["SET", "foo-bar", "1"]),
+            ("GET user:1:name", &["GET", "user:1:name"]),
+            ("ZADD key -1 +inf", &["ZADD", "key", "-1", "+inf"]),
+            ("SET foo 42.2", &["SET", "foo", "42.2"]),
+            ("SET foo\n\tbar", &["SET", "foo", "bar"]),
+            ("SETBIT key 0x1F 1", &["SETBIT", "key", "31", "1"]),
+            ("SET key 1_000_000", &["SET", "key", "1000000"]),
+            (
+                "SET key 0b101 0o17 1_0.5",
+                &["SET", "key", "5", "15", "10.5"],
+            ),
+            ("SET key 10u64 2.5f32", &["SET", "key", "10", "2.5"]),
+            ("GET user:007", &["GET", "user:007"]),
+            ("SET key 1e5 5min", &["SET", "key", "1e5", "5min"]),
+        ]);
+    }
+
+    #[test]
+    fn parse_quoted() {
+        parse_(&[
+            ("SET \"my key\" 1", &["SET", "\"my key\"", "1"]),
+            ("SET foo \"a\\\"b\"", &["SET", "foo", "\"a\\\"b\""]),
+            ("SET foo r\"raw\"", &["SET", "foo", "\"raw\""]),
+            (
+                "SET foo b\"\\x00\\xff\"",
+                &["SET", "foo", "b\"\\x00\\xff\""],
+            ),
+            ("SET foo k:b\"\\x00\"", &["SET", "foo", "k:b\"\\x00\""]),
+        ]);
+    }
+
+    #[test]
+    fn parse_substitution() {
+        parse_(&[
+            ("SET foo {x}", &["SET", "foo", "{x}"]),
+            ("SET foo {x + 1}", &["SET", "foo", "{x + 1}"]),
+            ("GET user:{id}:name", &["GET", "user:{id}:name"]),
+            ("GET {a}{b}", &["GET", "{a}{b}"]),
+        ]);
+    }
+
+    #[test]
+    fn parse_marked() {
+        parse_(&[
+            ("SET k {json}", &["SET", "k", "{json}"]),
+            ("SET k {json.len()}", &["SET", "k", "{json . len ()}"]),
+            (
+                "RESTORE k 0 {bytes payload}",
+                &["RESTORE", "k", "0", "{bytes payload}"],
+            ),
+            ("SET k:{bytes &id}", &["SET", "k:{bytes & id}"]),
+            (
+                "CLIENT SETINFO LIB-NAME {csv libs}",
+                &["CLIENT", "SETINFO", "LIB-NAME", "{csv libs}"],
+            ),
+            (
+                "ZADD k {..scores pairs}",
+                &["ZADD", "k", "{..scores pairs}"],
+            ),
+            ("ZADD k {..scores}", &["ZADD", "k", "{..scores}"]),
+            ("{raw name} k", &["{raw name}", "k"]),
+            (
+                "SET k {csv [\"a\", \"b\"]}",
+                &["SET", "k", "{csv [\"a\" , \"b\"]}"],
+            ),
+            ("SET k {bytes as u8}", &["SET", "k", "{bytes as u8}"]),
+        ]);
+        // Any expression can follow a marker after whitespace, but a call, an index or a binary
+        // operator on a variable of the same name isn't a marker
+        let marked = |input: &str| {
+            let tokens: TokenStream = input.parse().unwrap();
+            let args = parse_command(tokens).unwrap().args;
+            matches!(args[2].pieces.as_slice(), [Piece::Marked { .. }])
+        };
+        assert!(marked("SET k {bytes [1, 2]}"));
+        assert!(marked("SET k {csv (a, b)}"));
+        assert!(marked("SET k {bytes *buf}"));
+        assert!(marked("SET k {csv ::std::iter::once(1)}"));
+        assert!(!marked("SET k {bytes.len()}"));
+        assert!(!marked("SET k {csv:x}"));
+        assert!(!marked("SET k {bytes + 1}"));
+        assert!(!marked("SET k {bytes - 1}"));
+        assert!(!marked("SET k {csv * 2}"));
+        assert!(!marked("SET k {bytes & mask}"));
+        assert!(!marked("SET k {bytes | mask}"));
+        assert!(!marked("SET k {bytes(x)}"));
+        assert!(!marked("SET k {bytes-1}"));
+        assert!(marked("SET k {bytes -1}"));
+        assert!(marked("SET k {bytes &mask}"));
+        #[cfg(feature = "json")]
+        parse_(&[
+            ("SET k {json user}", &["SET", "k", "{json user}"]),
+            ("SET k {json &user}", &["SET", "k", "{json & user}"]),
+            ("SET k:{json 1}", &["SET", "k:{json 1}"]),
+            ("\"SET k {json user}\"", &["SET", "k", "{json user}"]),
+        ]);
+        #[cfg(feature = "json")]
+        assert!(marked("SET k {json (a, b)}"));
+        #[cfg(feature = "msgpack")]
+        parse_(&[("SET k {msgpack user}", &["SET", "k", "{msgpack user}"])]);
+    }
+
+    #[test]
+    fn parse_format_spec() {
+        parse_(&[
+            (
+                "ZADD board {score:.2} m",
+                &[
+                    "ZADD",
+                    "board",
+                    "{:: std :: format ! (\"{:.2}\" , score)}",
+                    "m",
+                ],
+            ),
+            (
+                "GET id:{id:08}",
+                &["GET", "id:{:: std :: format ! (\"{:08}\" , id)}"],
+            ),
+            (
+                "\"SET k {v:>10}\"",
+                &["SET", "k", "{:: std :: format ! (\"{:>10}\" , v)}"],
+            ),
+            ("GET {a::b}", &["GET", "{a :: b}"]),
+        ]);
+    }
+
+    #[test]
+    fn parse_spread() {
+        parse_(&[
+            ("DEL {..keys}", &["DEL", "{..keys}"]),
+            (
+                "MGET {..ids.iter().map(f)}",
+                &["MGET", "{..ids . iter () . map (f)}"],
+            ),
+            ("\"DEL {..keys}\"", &["DEL", "{..keys}"]),
+        ]);
+    }
+
+    #[test]
+    fn parse_fields() {
+        parse_(&[
+            (
+                "XADD events * {*event}",
+                &["XADD", "events", "*", "{*event}"],
+            ),
+            ("HSET user:1 {*&user}", &["HSET", "user:1", "{*& user}"]),
+            ("SET k {(*count)}", &["SET", "k", "{* count}"]),
+        ]);
+    }
+
+    #[test]
+    fn parse_optional() {
+        parse_(&[
+            ("SET key {v} EX {ttl?}", &["SET", "key", "{v}", "EX {ttl?}"]),
+            ("SET key {v} {ttl?}", &["SET", "key", "{v}", "{ttl?}"]),
+            ("LPUSH list {a?} {b?}", &["LPUSH", "list", "{a?}", "{b?}"]),
+            ("GET {(x?)}", &["GET", "{x ?}"]),
+            ("HGET key {&field?}", &["HGET", "key", "{& field?}"]),
+            ("XADD s MAXLEN {len?}", &["XADD", "s", "MAXLEN {len?}"]),
+            ("INCRBY {k?}", &["INCRBY", "{k?}"]),
+        ]);
+    }
+
+    #[test]
+    fn parse_conditional_() {
+        parse_(&[
+            (
+                "SET k {v} ?[{nx} => NX] ?[{keep} => KEEPTTL]",
+                &["SET", "k", "{v}", "?[{nx} => NX]", "?[{keep} => KEEPTTL]"],
+            ),
+            (
+                "SET k v ?[{a && b} => EX {ttl?} GET]",
+                &["SET", "k", "v", "?[{a && b} => EX {ttl?} GET]"],
+            ),
+            ("SET k ?", &["SET", "k", "?"]),
+        ]);
+    }
+
+    #[test]
+    fn parse_bindings() {
+        parse_(&[
+            (
+                "\"SET :key :val EX :ttl\"; key = k, val = v, ttl = 30",
+                &[
+                    "SET",
+                    "{binding_key}",
+                    "{binding_val}",
+                    "EX",
+                    "{binding_ttl}",
+                ],
+            ),
+            ("GET :key; key = k", &["GET", "{binding_key}"]),
+            ("GET \":key\"", &["GET", "\":key\""]),
+            (
+                "SET k v ?[{c} => GET :x]; x = 1",
+                &["SET", "k", "v", "?[{c} => GET {binding_x}]"],
+            ),
+        ]);
+    }
+
+    #[test]
+    fn parse_groups() {
+        parse_(&[
+            ("GET [x]", &["GET", "[x]"]),
+            ("EVAL (a b)", &["EVAL", "(a", "b)"]),
+        ]);
+    }
+
+    #[test]
+    fn parse_env_vars() {
+        let expected = "{:: core :: env ! (\"PREFIX\" , \"environment variable `PREFIX` is not defined at compile time\")}";
+        parse_(&[
+            ("GET ${PREFIX}", &["GET", expected]),
+            (
+                "GET ${PREFIX}:user",
+                &["GET", &format!("{}:user", expected)],
+            ),
+            ("GET $ {x}", &["GET", "$", "{x}"]),
+        ]);
+    }
+
+    #[test]
+    fn parse_comments() {
+        parse_(&[
+            ("SET foo bar # comment", &["SET", "foo", "bar"]),
+            ("SET foo # first\n bar # second", &["SET", "foo", "bar"]),
+            ("# whole line\nGET foo", &["GET", "foo"]),
+            ("GET key#1", &["GET", "key#1"]),
+            (
+                "BITFIELD k GET u8 #1 # comment",
+                &["BITFIELD", "k", "GET", "u8", "#1"],
+            ),
+            ("SET foo # 1", &["SET", "foo"]),
+        ]);
+    }
+
+    #[test]
+    fn parse_commas() {
+        parse_(&[
+            ("SET foo, bar", &["SET", "foo", "bar"]),
+            ("SET foo , bar ,", &["SET", "foo", "bar"]),
+            ("SET foo,bar", &["SET", "foo,bar"]),
+        ]);
+    }
+
+    #[test]
+    fn parse_str_command_() {
+        parse_(&[
+            ("\"SET foo bar\"", &["SET", "foo", "bar"]),
+            ("\"SET \\\"my key\\\" {x}\"", &["SET", "\"my key\"", "{x}"]),
+        ]);
+    }
+
+    #[test]
+    fn parse_str_command_joined() {
+        for (input, expected) in [
+            ("\"GET user:{id}\"", ["user:", "{id}"]),
+            ("\"GET {id}:x\"", ["{id}", ":x"]),
+        ] {
+            let tokens: TokenStream = input.parse().unwrap();
+            let args = parse_command(tokens).unwrap().args;
+            assert_eq!(args.len(), 2, "{}", input);
+            assert_eq!(render_args(&args[1..]), [expected.concat()], "{}", input);
+            assert!(
+                matches!(args[1].pieces.as_slice(), [_, _]),
+                "{} should be split into text and an expression",
+                input
+            );
+        }
+        assert!(parse_err("\"DEL key:{..keys}\"").contains("a spread must be a whole argument"));
+        assert!(parse_err("\"GET user:{}\"").contains("empty expression substitution"));
+    }
+
+    #[test]
+    fn parse_str_format_spec() {
+        // Only the text inside the braces can be a format spec, never text after them
+        let tokens: TokenStream = "\"GET {id}:x\"".parse().unwrap();
+        let args = parse_command(tokens).unwrap().args;
+        assert!(matches!(&args[1].pieces[0], Piece::Expr(expr) if matches!(**expr, Expr::Path(_))));
+        let tokens: TokenStream = "\"GET a:{id:x}:b\"".parse().unwrap();
+        let args = parse_command(tokens).unwrap().args;
+        assert!(matches!(&args[1].pieces[2], Piece::Text(text) if text == ":b"));
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert!(parse_err("").contains("expected a redis command"));
+        assert!(parse_err("GET {raw k}").contains("only for dynamic command names"));
+        assert!(parse_err("SET k ?[{c} => {raw v}]").contains("only for dynamic command names"));
+        assert!(parse_err("SET key {}").contains("empty expression substitution"));
+        assert!(parse_err("\"SET key {}\"").contains("empty expression substitution"));
+        assert!(parse_err("DEL {..}").contains("expected an expression to spread"));
+        assert!(parse_err("DEL key:{..keys}").contains("a spread must be a whole argument"));
+        assert!(parse_err("GET key:{x?}").contains("an optional argument must be a whole"));
+        assert!(parse_err("GET ${}").contains("expected an environment variable name"));
+        assert!(parse_err("GET ${a b}").contains("expected an environment variable name"));
+        assert!(parse_err("SET k {v:}").contains("expected a format spec after `:`"));
+        assert!(parse_err("DEL {..keys:x}").contains("a format spec can only be used"));
+        assert!(parse_err("ZADD k {scores x}").contains("write it as `{..scores expr}`"));
+        assert!(parse_err("ZADD k m:{..scores x}").contains("a spread must be a whole"));
+        assert!(parse_err("HSET k f:{*x}").contains("a field spread must be a whole"));
+        assert!(parse_err("SET k v ?[NX]").contains("expected a `{condition}`"));
+        assert!(parse_err("SET k v ?[{nx} NX]").contains("expected `=>`"));
+        assert!(parse_err("SET k v ?[{nx} =>]").contains("expected arguments after `=>`"));
+        assert!(parse_err("GET :key; k = 1").contains("no binding for placeholder `:key`"));
+        assert!(parse_err("GET :k; k = 1, x = 2").contains("`x` is bound but"));
+        assert!(parse_err("GET \":k\"; k = 1").contains("`k` is bound but"));
+        assert!(parse_err("GET :k; k = 1, k = 2").contains("`k` is bound more than once"));
+        assert!(parse_err("HSET ? ? ?; k, f").contains("3 `?` placeholder(s), but 2 value(s)"));
+        assert!(parse_err("HSET ?; k, f").contains("1 `?` placeholder(s), but 2 value(s)"));
+        assert!(parse_err("HSET ? :f; k, f = 1").contains("cannot be mixed"));
+        assert!(parse_err("\"SET key \\\"\\\\xZZ\\\"\"").contains("invalid escape sequence"));
+        assert!(parse_err("\"SET key \\\"value\"").contains("unclosed double quote"));
+        assert!(parse_err("SET k {bytes v?}").contains("cannot also be a spread or an optional"));
+        #[cfg(not(feature = "json"))]
+        assert!(parse_err("SET k {json v}").contains("requires the `json` feature"));
+        #[cfg(not(feature = "msgpack"))]
+        assert!(parse_err("SET k {msgpack v}").contains("requires the `msgpack` feature"));
+        #[cfg(feature = "json")]
+        assert!(parse_err("SET k {json v?}").contains("cannot also be a spread or an optional"));
+    }
+}
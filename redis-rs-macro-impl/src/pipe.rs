@@ -0,0 +1,644 @@
+use crate::expand::expand_command;
+use crate::parse::{parse_command, Command};
+use crate::route::expand_routed_command;
+use crate::scope::{split_scope, Scope};
+use proc_macro2::{Delimiter, Ident, Spacing, Span, TokenStream, TokenTree};
+use quote::{format_ident, quote};
+use syn::parse::Parser;
+use syn::{Expr, Pat};
+
+/// An entry of a `redis_pipe!` invocation
+pub(crate) enum Entry {
+    Command(PipeCommand),
+    /// `for pat in expr { ... }`, which appends its commands on every iteration
+    For {
+        pat: Box<Pat>,
+        expr: Box<Expr>,
+        body: Vec<Entry>,
+    },
+    /// `if cond { ... } else { ... }`, which appends the commands of the branch that is taken.
+    /// An `else if` is an `If` on its own in `otherwise`.
+    If {
+        cond: Box<Expr>,
+        then: Vec<Entry>,
+        otherwise: Vec<Entry>,
+    },
+}
+
+/// A label of a labeled pipeline
+struct Label<'a> {
+    name: &'a Ident,
+    /// Labels inside an `if` are `None` when their branch isn't taken
+    conditional: bool,
+}
+
+/// A command of a `redis_pipe!` invocation
+pub(crate) struct PipeCommand {
+    pub(crate) command: Command,
+    /// Written as `_ = ...`, so its reply is left out of the results
+    pub(crate) ignore: bool,
+    /// Written as `name: ...`, so its reply is the `name` field of the results
+    pub(crate) label: Option<Ident>,
+    /// Written after `@no_namespace`, so the namespaces around the pipeline leave its keys alone
+    pub(crate) unscoped: bool,
+}
+
+/// Split the input of `redis_pipe!` into the tokens of each command. Commands end at a top level
+/// `;` or at the end of a line, and comments are dropped so that they can hold either.
+pub(crate) fn split_entries(input: TokenStream) -> Vec<Vec<TokenTree>> {
+    let mut entries = vec![];
+    let mut current: Vec<TokenTree> = vec![];
+    let mut tokens = input.into_iter().peekable();
+    let mut comment_line = None;
+    while let Some(tt) = tokens.next() {
+        let span = tt.span();
+        if comment_line == Some(span.start().line) {
+            continue;
+        }
+        comment_line = None;
+        let prev_end = current.last().map(|prev| prev.span().end());
+        if prev_end.is_some_and(|end| end.line < span.start().line) {
+            entries.push(std::mem::take(&mut current));
+        }
+        match &tt {
+            TokenTree::Punct(punct) if punct.as_char() == ';' => {
+                entries.push(std::mem::take(&mut current));
+                continue;
+            }
+            // The same rule as in commands: a `#` directly followed by a number is text
+            TokenTree::Punct(punct) if punct.as_char() == '#' => {
+                let glued = prev_end == Some(span.start());
+                let number = tokens.peek().is_some_and(|next| {
+                    matches!(next, TokenTree::Literal(_)) && next.span().start() == span.end()
+                });
+                if !glued && !number {
+                    comment_line = Some(span.start().line);
+                    continue;
+                }
+            }
+            _ => {}
+        }
+        current.push(tt);
+    }
+    entries.push(current);
+    entries.retain(|entry| !entry.is_empty());
+    entries
+}
+
+/// Parse the entries of a pipeline, or of the body of a loop
+pub(crate) fn parse_entries(entries: Vec<Vec<TokenTree>>, span: Span) -> syn::Result<Vec<Entry>> {
+    let entries = entries
+        .into_iter()
+        .map(parse_entry)
+        .collect::<syn::Result<Vec<_>>>()?;
+    if entries.is_empty() {
+        return Err(syn::Error::new(span, "expected at least one redis command"));
+    }
+    Ok(entries)
+}
+
+/// Parse a single entry, which is either a loop or a command
+fn parse_entry(tokens: Vec<TokenTree>) -> syn::Result<Entry> {
+    match tokens.first() {
+        Some(TokenTree::Ident(ident)) if ident == "for" => parse_for(tokens),
+        Some(TokenTree::Ident(ident)) if ident == "if" => parse_if(tokens),
+        _ => parse_pipe_command(tokens).map(Entry::Command),
+    }
+}
+
+/// Parse a `for pat in expr { ... }` loop
+fn parse_for(mut tokens: Vec<TokenTree>) -> syn::Result<Entry> {
+    let span = tokens[0].span();
+    let body = match tokens.pop() {
+        Some(TokenTree::Group(group)) if group.delimiter() == Delimiter::Brace => group,
+        _ => {
+            let msg = "expected a `{ ... }` body of commands after the `for` loop";
+            return Err(syn::Error::new(span, msg));
+        }
+    };
+    let header = &tokens[1..];
+    let Some(split) = header
+        .iter()
+        .position(|tt| matches!(tt, TokenTree::Ident(ident) if ident == "in"))
+    else {
+        return Err(syn::Error::new(span, "expected `for pattern in values`"));
+    };
+    let pat =
+        Pat::parse_multi_with_leading_vert.parse2(header[..split].iter().cloned().collect())?;
+    let expr = syn::parse2(header[split + 1..].iter().cloned().collect())?;
+    Ok(Entry::For {
+        pat: Box::new(pat),
+        expr: Box::new(expr),
+        body: parse_entries(split_entries(body.stream()), body.span())?,
+    })
+}
+
+/// Parse an `if cond { ... }` block, with an optional `else { ... }` or `else if ...` after it
+fn parse_if(tokens: Vec<TokenTree>) -> syn::Result<Entry> {
+    let span = tokens[0].span();
+    let Some(body) = tokens.iter().position(
+        |tt| matches!(tt, TokenTree::Group(group) if group.delimiter() == Delimiter::Brace),
+    ) else {
+        let msg = "expected a `{ ... }` body of commands after the `if` condition";
+        return Err(syn::Error::new(span, msg));
+    };
+    let cond = syn::parse2(tokens[1..body].iter().cloned().collect())?;
+    let then = match &tokens[body] {
+        TokenTree::Group(group) => parse_entries(split_entries(group.stream()), group.span())?,
+        _ => unreachable!(),
+    };
+    let otherwise = match &tokens[body + 1..] {
+        [] => vec![],
+        [TokenTree::Ident(ident), TokenTree::Group(group)]
+            if ident == "else" && group.delimiter() == Delimiter::Brace =>
+        {
+            parse_entries(split_entries(group.stream()), group.span())?
+        }
+        [TokenTree::Ident(ident), rest @ ..]
+            if ident == "else"
+                && matches!(rest.first(), Some(TokenTree::Ident(i)) if i == "if") =>
+        {
+            vec![parse_if(rest.to_vec())?]
+        }
+        [tt, ..] => {
+            let msg = "expected `else` and a `{ ... }` body of commands after the `if` block";
+            return Err(syn::Error::new(tt.span(), msg));
+        }
+    };
+    Ok(Entry::If {
+        cond: Box::new(cond),
+        then,
+        otherwise,
+    })
+}
+
+/// Parse a single command, which may start with `_ =` to ignore its reply, or with a `name:`
+/// label
+fn parse_pipe_command(tokens: Vec<TokenTree>) -> syn::Result<PipeCommand> {
+    let (ignore, label) = match tokens.as_slice() {
+        [TokenTree::Ident(ident), TokenTree::Punct(eq), ..]
+            if ident == "_" && eq.as_char() == '=' && eq.spacing() == Spacing::Alone =>
+        {
+            (true, None)
+        }
+        [TokenTree::Ident(ident), TokenTree::Punct(colon), ..]
+            if colon.as_char() == ':' && colon.spacing() == Spacing::Alone =>
+        {
+            (false, Some(ident.clone()))
+        }
+        _ => (false, None),
+    };
+    let skip = if ignore || label.is_some() { 2 } else { 0 };
+    let (scope, tokens) = split_scope(tokens.into_iter().skip(skip).collect())?;
+    let mut command = parse_command(tokens)?;
+    scope.apply(&mut command)?;
+    Ok(PipeCommand {
+        command,
+        ignore,
+        label,
+        unscoped: scope.unscoped,
+    })
+}
+
+/// Apply the scope attributes around a pipeline, such as `#[redis_namespace]`, to its commands,
+/// including those in loops and `if` blocks
+pub(crate) fn scope_entries(entries: &mut [Entry], scope: &Scope) -> syn::Result<()> {
+    for entry in entries {
+        match entry {
+            Entry::Command(command) if command.unscoped => {
+                scope.without_namespaces().apply(&mut command.command)?
+            }
+            Entry::Command(command) => scope.apply(&mut command.command)?,
+            Entry::For { body, .. } => scope_entries(body, scope)?,
+            Entry::If {
+                then, otherwise, ..
+            } => {
+                scope_entries(then, scope)?;
+                scope_entries(otherwise, scope)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Whether any command outside of loops has a label
+pub(crate) fn is_labeled(entries: &[Entry]) -> bool {
+    entries.iter().any(|entry| match entry {
+        Entry::Command(command) => command.label.is_some(),
+        Entry::For { .. } => false,
+        Entry::If {
+            then, otherwise, ..
+        } => is_labeled(then) || is_labeled(otherwise),
+    })
+}
+
+/// The labels of a labeled pipeline, in order, or `None` when no command has a label
+fn labels(entries: &[Entry]) -> syn::Result<Option<Vec<Label<'_>>>> {
+    let labeled = is_labeled(entries);
+    let mut labels = vec![];
+    collect_labels(entries, labeled, false, &mut labels)?;
+    Ok(labeled.then_some(labels))
+}
+
+fn collect_labels<'a>(
+    entries: &'a [Entry],
+    labeled: bool,
+    conditional: bool,
+    labels: &mut Vec<Label<'a>>,
+) -> syn::Result<()> {
+    for entry in entries {
+        let command = match entry {
+            Entry::Command(command) if command.ignore || !labeled => continue,
+            Entry::Command(command) => command,
+            Entry::For { body, .. } => {
+                check_loop_labels(body, labeled)?;
+                continue;
+            }
+            Entry::If {
+                then, otherwise, ..
+            } => {
+                collect_labels(then, labeled, true, labels)?;
+                collect_labels(otherwise, labeled, true, labels)?;
+                continue;
+            }
+        };
+        let Some(label) = &command.label else {
+            let msg = "every command of a labeled pipeline needs a label, or `_ =` to ignore \
+                its reply";
+            return Err(syn::Error::new(command.command.args[0].span, msg));
+        };
+        if labels.iter().any(|existing| existing.name == label) {
+            let msg = format!("`{}` labels more than one command", label);
+            return Err(syn::Error::new(label.span(), msg));
+        }
+        labels.push(Label {
+            name: label,
+            conditional,
+        });
+    }
+    Ok(())
+}
+
+/// The number of labeled commands in a branch, which are skipped when it isn't taken
+fn count_labels(entries: &[Entry]) -> usize {
+    entries
+        .iter()
+        .map(|entry| match entry {
+            Entry::Command(command) => usize::from(command.label.is_some() && !command.ignore),
+            Entry::For { .. } => 0,
+            Entry::If {
+                then, otherwise, ..
+            } => count_labels(then) + count_labels(otherwise),
+        })
+        .sum()
+}
+
+/// Loops run any number of times, so their commands can't have labels, and in a labeled
+/// pipeline their replies have to be ignored
+fn check_loop_labels(body: &[Entry], labeled: bool) -> syn::Result<()> {
+    for entry in body {
+        match entry {
+            Entry::Command(command) => {
+                if let Some(label) = &command.label {
+                    let msg = "commands in a loop can't have labels";
+                    return Err(syn::Error::new(label.span(), msg));
+                }
+                if labeled && !command.ignore {
+                    let msg = "commands in a loop of a labeled pipeline need `_ =` to ignore \
+                        their replies";
+                    return Err(syn::Error::new(command.command.args[0].span, msg));
+                }
+            }
+            Entry::For { body, .. } => check_loop_labels(body, labeled)?,
+            Entry::If {
+                then, otherwise, ..
+            } => {
+                check_loop_labels(then, labeled)?;
+                check_loop_labels(otherwise, labeled)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Generate the struct that holds the replies of a labeled pipeline, with one type parameter for
+/// each field so that their types are inferred from how they are used
+fn expand_results(labels: &[Label], results: &Ident) -> TokenStream {
+    let params: Vec<_> = (0..labels.len()).map(|i| format_ident!("T{}", i)).collect();
+    let types = labels.iter().zip(&params).map(|(label, param)| {
+        if label.conditional {
+            quote!(::core::option::Option<#param>)
+        } else {
+            quote!(#param)
+        }
+    });
+    let labels: Vec<_> = labels.iter().map(|label| label.name).collect();
+    let value = Ident::new("value", Span::mixed_site());
+    quote! {
+        #[derive(Debug)]
+        struct #results<#(#params),*> {
+            #(#labels: #types,)*
+        }
+
+        impl<#(#params: redis::FromRedisValue),*> redis::FromRedisValue for #results<#(#params),*> {
+            fn from_redis_value(#value: &redis::Value) -> redis::RedisResult<Self> {
+                let (#(#labels,)*) = redis::FromRedisValue::from_redis_value(#value)?;
+                Ok(#results { #(#labels),* })
+            }
+        }
+    }
+}
+
+/// Whether the first command is the flag `name`, such as `atomic`, which wraps the pipeline in
+/// MULTI/EXEC
+fn is_flag(tokens: &[TokenTree], name: &str) -> bool {
+    matches!(tokens, [TokenTree::Ident(ident)] if ident == name)
+}
+
+/// Where the commands of a list of entries go
+#[derive(Clone, Copy)]
+pub(crate) enum Sink<'a> {
+    /// Added to the `redis::Pipeline` in `pipe`. In labeled pipelines, `replies` records whether
+    /// each labeled command was added, so that skipped ones read as `None`.
+    Pipeline {
+        pipe: &'a Ident,
+        replies: Option<&'a Ident>,
+    },
+    /// Pushed onto the `Vec<redis::Cmd>` in the variable
+    Batch(&'a Ident),
+    /// Added to the `redis_rs_macro::ClusterPipeline` in the variable, along with its route
+    Cluster(&'a Ident),
+}
+
+/// Generate the statements that append the commands of the entries to `sink`
+pub(crate) fn expand_entries(entries: &[Entry], sink: Sink) -> syn::Result<TokenStream> {
+    let replies = match sink {
+        Sink::Pipeline { replies, .. } => replies,
+        Sink::Batch(_) | Sink::Cluster(_) => None,
+    };
+    entries
+        .iter()
+        .map(|entry| match entry {
+            Entry::Command(command) => {
+                let cmd = || expand_command(&command.command);
+                match sink {
+                    Sink::Pipeline { pipe, replies } => {
+                        let ignore = command.ignore.then(|| quote!(.ignore()));
+                        let reply = replies
+                            .filter(|_| command.label.is_some() && !command.ignore)
+                            .map(|replies| quote!(#replies.push(true);));
+                        let cmd = cmd()?;
+                        Ok(quote!(#pipe.add_command(#cmd)#ignore; #reply))
+                    }
+                    Sink::Batch(batch) => {
+                        let cmd = cmd()?;
+                        Ok(quote!(#batch.push(#cmd);))
+                    }
+                    Sink::Cluster(pipe) => {
+                        let ignore = command.ignore.then(|| quote!(.ignore()));
+                        let routed = expand_routed_command(&command.command)?;
+                        let (cmd, route) = (
+                            Ident::new("cmd", Span::mixed_site()),
+                            Ident::new("route", Span::mixed_site()),
+                        );
+                        Ok(quote! {
+                            let (#cmd, #route) = #routed;
+                            #pipe.add_command(#cmd, #route)#ignore;
+                        })
+                    }
+                }
+            }
+            Entry::For { pat, expr, body } => {
+                let body = expand_entries(body, sink)?;
+                Ok(quote! {
+                    for #pat in #expr {
+                        #body
+                    }
+                })
+            }
+            Entry::If {
+                cond,
+                then,
+                otherwise,
+            } => {
+                let skip = |entries: &[Entry]| {
+                    let count = count_labels(entries);
+                    replies
+                        .filter(|_| count > 0)
+                        .map(|replies| quote!(#replies.extend([false; #count]);))
+                };
+                let (skip_then, skip_otherwise) = (skip(then), skip(otherwise));
+                let then = expand_entries(then, sink)?;
+                let otherwise = expand_entries(otherwise, sink)?;
+                Ok(quote! {
+                    if #cond {
+                        #then
+                        #skip_otherwise
+                    } else {
+                        #skip_then
+                        #otherwise
+                    }
+                })
+            }
+        })
+        .collect()
+}
+
+/// Generate a block that builds the `redis::Pipeline` for a `redis_pipe!` invocation
+pub(crate) fn expand_pipeline(input: TokenStream) -> syn::Result<TokenStream> {
+    let (scope, input) = split_scope(input)?;
+    let mut entries = split_entries(input);
+    let atomic = entries
+        .first()
+        .is_some_and(|first| is_flag(first, "atomic"));
+    if atomic {
+        entries.remove(0);
+    }
+    let cluster = entries
+        .first()
+        .is_some_and(|first| is_flag(first, "cluster"));
+    if cluster {
+        let flag = entries.remove(0);
+        if atomic {
+            let msg = "a cluster pipeline is split across nodes, so it can't be atomic";
+            return Err(syn::Error::new(flag[0].span(), msg));
+        }
+    }
+    let mut entries = parse_entries(entries, Span::call_site())?;
+    scope_entries(&mut entries, &scope)?;
+    if cluster {
+        return expand_cluster_pipeline(entries);
+    }
+    let pipe = Ident::new("pipe", Span::mixed_site());
+    let replies = Ident::new("replies", Span::mixed_site());
+    let labels = labels(&entries)?;
+    let sink = Sink::Pipeline {
+        pipe: &pipe,
+        replies: labels.as_ref().map(|_| &replies),
+    };
+    let commands = expand_entries(&entries, sink)?;
+    let atomic = atomic.then(|| quote!(#pipe.atomic();));
+    let track = labels
+        .is_some()
+        .then(|| quote!(let mut #replies = ::std::vec::Vec::new();));
+    let (results, output) = match labels {
+        Some(labels) => {
+            let name = Ident::new("Results", Span::mixed_site());
+            let holes = labels.iter().map(|_| quote!(_));
+            (
+                expand_results(&labels, &name),
+                quote!(::redis_rs_macro::LabeledPipeline::<#name<#(#holes),*>>::new(
+                    #pipe, #replies
+                )),
+            )
+        }
+        None => (quote!(), quote!(#pipe)),
+    };
+    Ok(quote! {
+        {
+            #results
+            let mut #pipe = redis::pipe();
+            #track
+            #atomic
+            #commands
+            #output
+        }
+    })
+}
+
+/// Generate a block that builds the `redis_rs_macro::ClusterPipeline` for a `redis_pipe!`
+/// invocation in cluster mode
+fn expand_cluster_pipeline(entries: Vec<Entry>) -> syn::Result<TokenStream> {
+    if is_labeled(&entries) {
+        let msg =
+            "cluster pipelines return their replies as a tuple, so commands can't have labels";
+        return Err(syn::Error::new(Span::call_site(), msg));
+    }
+    let pipe = Ident::new("pipe", Span::mixed_site());
+    let commands = expand_entries(&entries, Sink::Cluster(&pipe))?;
+    Ok(quote! {
+        {
+            let mut #pipe = ::redis_rs_macro::ClusterPipeline::new();
+            #commands
+            #pipe
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entries(input: &str) -> Vec<String> {
+        split_entries(input.parse().unwrap())
+            .into_iter()
+            .map(|entry| entry.into_iter().collect::<TokenStream>().to_string())
+            .collect()
+    }
+
+    fn command(input: &str) -> PipeCommand {
+        parse_pipe_command(split_entries(input.parse().unwrap()).remove(0)).unwrap()
+    }
+
+    #[test]
+    fn pipe_split() {
+        assert_eq!(entries("SET a 1; INCR b"), ["SET a 1", "INCR b"]);
+        assert_eq!(entries("SET a 1;\nINCR b;"), ["SET a 1", "INCR b"]);
+        assert_eq!(
+            entries("SET a 1\nINCR b\n\nGET c"),
+            ["SET a 1", "INCR b", "GET c"]
+        );
+        assert_eq!(
+            entries("SET a ?[{x} =>\n NX] # comment; not a command\nGET a"),
+            ["SET a ? [{ x } => NX]", "GET a"]
+        );
+        assert_eq!(entries("BITFIELD k GET u8 #1"), ["BITFIELD k GET u8 # 1"]);
+    }
+
+    #[test]
+    fn pipe_ignore() {
+        let entry = command("_ = SET a 1");
+        assert!(entry.ignore);
+        assert_eq!(entry.command.args.len(), 3);
+        assert!(!command("GET _").ignore);
+    }
+
+    #[test]
+    fn pipe_atomic() {
+        let output = |input: &str| expand_pipeline(input.parse().unwrap()).unwrap().to_string();
+        assert!(output("atomic; SET a 1").contains(". atomic ()"));
+        assert!(output("atomic\nSET a 1").contains(". atomic ()"));
+        assert!(!output("SET a 1; atomic").contains(". atomic ()"));
+        assert!(!output("SET atomic 1").contains(". atomic ()"));
+    }
+
+    #[test]
+    fn pipe_cluster() {
+        let output = |input: &str| expand_pipeline(input.parse().unwrap()).unwrap().to_string();
+        let cluster = output("cluster; _ = SET a 1; for id in &ids { GET user:{id} }");
+        assert!(cluster.contains(":: redis_rs_macro :: ClusterPipeline :: new ()"));
+        assert!(cluster.contains(". add_command (cmd , route) . ignore () ;"));
+        assert!(cluster.contains(":: redis_rs_macro :: Route :: for_arg (& cmd , 1usize)"));
+        assert!(!output("SET cluster 1").contains("ClusterPipeline"));
+    }
+
+    #[test]
+    fn pipe_labels() {
+        let entry = command("count: GET c");
+        assert_eq!(entry.label.unwrap(), "count");
+        assert_eq!(entry.command.args.len(), 2);
+        assert!(command("GET user:{id}").label.is_none());
+        assert!(command("a::b c").label.is_none());
+    }
+
+    #[test]
+    fn pipe_loops() {
+        let output = |input: &str| expand_pipeline(input.parse().unwrap()).unwrap().to_string();
+        assert!(output("for id in &ids { GET user:{id} }").contains("for id in & ids {"));
+        assert!(output("for (k, v) in pairs { SET {k} {v}; EXPIRE {k} 60 }")
+            .contains("for (k , v) in pairs {"));
+        assert!(output("a: GET a; for id in &ids { _ = DEL {id} }").contains("LabeledPipeline"));
+    }
+
+    #[test]
+    fn pipe_conditions() {
+        let output = |input: &str| expand_pipeline(input.parse().unwrap()).unwrap().to_string();
+        assert!(output("if fresh { SET flag 1 }").contains("if fresh {"));
+        assert!(
+            output("if a { GET a } else if b { GET b } else { GET c }").contains("else { if b {")
+        );
+        let labeled = output("x: GET x; if a { y: GET y; _ = SET a 1 } else { z: GET z }");
+        assert!(
+            labeled.contains("y : :: core :: option :: Option < T1 >"),
+            "{}",
+            labeled
+        );
+        assert!(labeled.contains("extend ([false ; 1usize])"), "{}", labeled);
+    }
+
+    #[test]
+    fn pipe_errors() {
+        let err = |input: &str| {
+            expand_pipeline(input.parse().unwrap())
+                .unwrap_err()
+                .to_string()
+        };
+        assert!(err("").contains("expected at least one redis command"));
+        assert!(err("# just a comment").contains("expected at least one redis command"));
+        assert!(err("atomic;").contains("expected at least one redis command"));
+        assert!(err("atomic; cluster; GET a").contains("can't be atomic"));
+        assert!(err("cluster; a: GET a").contains("can't have labels"));
+        assert!(err("GET a; GET {..b}:c").contains("must be a whole argument"));
+        assert!(err("a: GET a; GET b").contains("needs a label"));
+        assert!(err("a: GET a; a: GET b").contains("labels more than one command"));
+        assert!(err("for id in &ids { a: GET {id} }").contains("can't have labels"));
+        assert!(err("a: GET a; for id in &ids { GET {id} }").contains("need `_ =`"));
+        assert!(err("for id in &ids { }").contains("expected at least one redis command"));
+        assert!(err("for id in &ids GET a").contains("expected a `{ ... }` body"));
+        assert!(err("for id { GET a }").contains("expected `for pattern in values`"));
+        assert!(err("if a GET b").contains("expected a `{ ... }` body"));
+        assert!(err("if a { GET b } GET c").contains("expected `else`"));
+        assert!(err("x: GET x; if a { GET y }").contains("needs a label"));
+        assert!(err("if a { y: GET y; for i in b { z: GET {i} } }").contains("can't have labels"));
+    }
+}
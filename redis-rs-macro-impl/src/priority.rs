@@ -0,0 +1,115 @@
+use crate::queue::{parse_defs, QueueDef};
+use proc_macro2::{Span, TokenStream};
+use quote::quote;
+use syn::Ident;
+
+/// Generate the queues of a `redis_priority_queue!` invocation, each a unit struct with typed push
+/// and pop functions and a worker loop
+pub(crate) fn expand_priority_queue(input: TokenStream) -> syn::Result<TokenStream> {
+    let mut output = TokenStream::new();
+    for def in parse_defs(input, "timeout")? {
+        output.extend(expand_def(def));
+    }
+    Ok(output)
+}
+
+fn expand_def(def: QueueDef) -> TokenStream {
+    let QueueDef {
+        attrs,
+        vis,
+        name,
+        job,
+        key,
+        option: timeout,
+        marker,
+    } = def;
+    // Workers wait for jobs five seconds at a time by default
+    let timeout = timeout.map_or(quote!(5), |timeout| quote!(#timeout));
+    let wrapper = marker.reply_wrapper();
+    let job_var = Ident::new("job", Span::mixed_site());
+    let serialized = marker.expand(&syn::parse_quote!(#job_var));
+    quote! {
+        #(#attrs)*
+        #vis struct #name;
+
+        impl #name {
+            /// The queue, with its key
+            pub fn queue() -> ::redis_rs_macro::PriorityQueue {
+                ::redis_rs_macro::PriorityQueue::new(#key, #timeout)
+            }
+
+            /// Add a job with `ZADD`. Jobs with lower priorities are taken first.
+            pub fn push(
+                con: &mut dyn redis::ConnectionLike,
+                #job_var: &#job,
+                priority: f64,
+            ) -> redis::RedisResult<()> {
+                Self::queue().push(con, #serialized, priority)
+            }
+
+            /// Pop the job with the lowest priority with `ZPOPMIN`, without waiting
+            pub fn pop(
+                con: &mut dyn redis::ConnectionLike,
+            ) -> redis::RedisResult<::core::option::Option<#job>> {
+                let popped: ::core::option::Option<#wrapper<#job>> = Self::queue().pop(con)?;
+                ::core::result::Result::Ok(popped.map(|#wrapper(#job_var)| #job_var))
+            }
+
+            /// Take jobs with `BZPOPMIN` and pass them to `handler`, until it returns
+            /// `Ok(ControlFlow::Break(()))`. Jobs it returns `Err` for are pushed back.
+            pub fn work<R, E>(
+                con: &mut dyn redis::ConnectionLike,
+                mut handler: impl ::core::ops::FnMut(#job) -> ::core::result::Result<R, E>,
+            ) -> redis::RedisResult<()>
+            where
+                R: ::redis_rs_macro::HandlerResult,
+            {
+                Self::queue().work(con, |#wrapper(#job_var): #wrapper<#job>| handler(#job_var))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expand(input: &str) -> syn::Result<String> {
+        expand_priority_queue(input.parse().unwrap()).map(|output| output.to_string())
+    }
+
+    #[test]
+    fn priority_queue_expand() {
+        let output = expand(
+            "pub(crate) struct Tasks(Task) = tasks;\n\
+             struct Builds(u64) = builds:{repo}, timeout = 0, msgpack;",
+        )
+        .unwrap();
+        assert!(
+            output.starts_with("pub (crate) struct Tasks ; impl Tasks {"),
+            "{}",
+            output
+        );
+        assert!(output.contains(", 5) }"), "{}", output);
+        assert!(
+            output.contains("Self :: queue () . push (con , :: redis_rs_macro :: __private :: json :: to_string (& (job)) ? , priority)"),
+            "{}",
+            output
+        );
+        assert!(output.contains(", 0) }"), "{}", output);
+        assert!(
+            output.contains("popped . map (| :: redis_rs_macro :: Msgpack (job) | job)"),
+            "{}",
+            output
+        );
+    }
+
+    #[test]
+    fn priority_queue_errors() {
+        let err = |input: &str| expand(input).unwrap_err().to_string();
+        let e = err("struct Q(Job) = a, interval = 1;");
+        assert!(e.contains("expected `timeout`"), "{}", e);
+        let e = err("struct Q(Job) = a, msgpack, msgpack;");
+        assert!(e.contains("format is given twice"), "{}", e);
+    }
+}
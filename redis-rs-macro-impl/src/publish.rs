@@ -0,0 +1,136 @@
+use crate::expand::expand_command;
+use crate::marker::Marker;
+use crate::parse::{parse_args, Arg, Command, Piece};
+use proc_macro2::{Span, TokenStream, TokenTree};
+use quote::quote;
+use syn::parse::{Parse, ParseStream};
+use syn::spanned::Spanned;
+use syn::{Expr, Token};
+
+/// The input of `redis_publish!`, e.g. `con, "orders.created", &order`
+struct Publish {
+    con: Expr,
+    channel: TokenStream,
+    marker: Option<Marker>,
+    payload: Expr,
+}
+
+impl Parse for Publish {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let con = input.parse()?;
+        input.parse::<Token![,]>()?;
+        let mut channel = TokenStream::new();
+        while !input.is_empty() && !input.peek(Token![,]) {
+            channel.extend([input.parse::<TokenTree>()?]);
+        }
+        input.parse::<Token![,]>()?;
+        let marker = Marker::parse(input)?;
+        let payload = input.parse()?;
+        input.parse::<Option<Token![,]>>()?;
+        Ok(Publish {
+            con,
+            channel,
+            marker,
+            payload,
+        })
+    }
+}
+
+/// Generate the `PUBLISH` command of a `redis_publish!` invocation, with the payload serialized as
+/// JSON unless another format is given, and send it
+pub(crate) fn expand_publish(input: TokenStream) -> syn::Result<TokenStream> {
+    let Publish {
+        con,
+        channel,
+        marker,
+        payload,
+    } = syn::parse2(input)?;
+    let Command {
+        args: mut channel,
+        bindings,
+    } = parse_args(channel, 0)?;
+    let channel = match channel.len() {
+        1 if channel[0]
+            .pieces
+            .iter()
+            .all(|p| p.standalone_kind().is_none()) =>
+        {
+            channel.remove(0)
+        }
+        _ => {
+            let span = channel.first().map_or(Span::call_site(), |arg| arg.span);
+            return Err(syn::Error::new(span, "expected a single channel"));
+        }
+    };
+    let marker = match marker {
+        Some(marker @ (Marker::Json | Marker::MsgPack | Marker::Bytes | Marker::Csv)) => marker,
+        Some(marker) => {
+            let msg = format!("the `{}` marker cannot be used for payloads", marker.name());
+            return Err(syn::Error::new(payload.span(), msg));
+        }
+        None if cfg!(feature = "json") => Marker::Json,
+        None => {
+            let msg = "payloads are serialized as JSON by default, which requires the `json` \
+                       feature of redis-rs-macro; write a format such as `bytes payload` instead";
+            return Err(syn::Error::new(payload.span(), msg));
+        }
+    };
+    let name = Arg {
+        pieces: vec![Piece::Text("PUBLISH".to_string())],
+        span: Span::call_site(),
+    };
+    let payload = Arg {
+        span: payload.span(),
+        pieces: vec![Piece::Marked {
+            marker,
+            expr: Box::new(payload),
+        }],
+    };
+    let command = Command {
+        args: vec![name, channel, payload],
+        bindings,
+    };
+    let cmd = expand_command(&command)?;
+    Ok(quote!(redis::Cmd::query::<i64>(&#cmd, #con)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expand(input: &str) -> syn::Result<String> {
+        expand_publish(input.parse().unwrap()).map(|output| output.to_string())
+    }
+
+    #[test]
+    fn publish_expand() {
+        let output = expand("con, \"orders.created\", &order").unwrap();
+        assert!(output.contains("redis :: cmd (\"PUBLISH\")"), "{}", output);
+        assert!(
+            output.contains("json :: to_string (& (& order)) ?"),
+            "{}",
+            output
+        );
+        assert!(output.ends_with(", con)"), "{}", output);
+        let output = expand("con, orders:{region}, msgpack order,").unwrap();
+        assert!(
+            output.contains("msgpack :: to_vec (& (order)) ?"),
+            "{}",
+            output
+        );
+        let output = expand("con, orders, bytes data").unwrap();
+        assert!(output.contains("bytes :: as_bytes"), "{}", output);
+    }
+
+    #[test]
+    fn publish_errors() {
+        let err = expand("con, a b, &order").unwrap_err().to_string();
+        assert!(err.contains("expected a single channel"), "{}", err);
+        let err = expand("con, {..channels}, &order").unwrap_err().to_string();
+        assert!(err.contains("expected a single channel"), "{}", err);
+        let err = expand("con, orders, raw order").unwrap_err().to_string();
+        assert!(err.contains("cannot be used for payloads"), "{}", err);
+        let err = expand("con, orders").unwrap_err().to_string();
+        assert!(err.contains("expected `,`"), "{}", err);
+    }
+}
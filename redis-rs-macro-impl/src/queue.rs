@@ -0,0 +1,197 @@
+use crate::lock::expand_key;
+use crate::marker::Marker;
+use proc_macro2::{Span, TokenStream, TokenTree};
+use quote::quote;
+use syn::parse::{ParseStream, Parser};
+use syn::{Attribute, Expr, Ident, Token, Type, Visibility};
+
+/// A queue declared in `redis_work_queue!` or `redis_delay_queue!`, e.g.
+/// `pub struct Emails(Email) = queue:emails, visibility = 30000;`
+pub(crate) struct QueueDef {
+    pub(crate) attrs: Vec<Attribute>,
+    pub(crate) vis: Visibility,
+    pub(crate) name: Ident,
+    pub(crate) job: Type,
+    /// The `redis_rs_macro::Args` of the key
+    pub(crate) key: TokenStream,
+    /// The value of the numeric option of the queue, such as `visibility`
+    pub(crate) option: Option<Expr>,
+    pub(crate) marker: Marker,
+}
+
+/// Parse the queues of a queue macro, whose only numeric option is named `option`
+pub(crate) fn parse_defs(input: TokenStream, option: &str) -> syn::Result<Vec<QueueDef>> {
+    let parser = |input: ParseStream| {
+        let mut defs = vec![];
+        while !input.is_empty() {
+            defs.push(parse_def(input, option)?);
+        }
+        Ok(defs)
+    };
+    parser.parse2(input)
+}
+
+fn parse_def(input: ParseStream, option: &str) -> syn::Result<QueueDef> {
+    let attrs = input.call(Attribute::parse_outer)?;
+    let vis = input.parse()?;
+    input.parse::<Token![struct]>()?;
+    let name = input.parse()?;
+    let content;
+    syn::parenthesized!(content in input);
+    let job = content.parse()?;
+    input.parse::<Token![=]>()?;
+    let mut key = TokenStream::new();
+    while !input.is_empty() && !input.peek(Token![,]) && !input.peek(Token![;]) {
+        key.extend([input.parse::<TokenTree>()?]);
+    }
+    let mut value = None;
+    let mut marker = None;
+    while input.parse::<Option<Token![,]>>()?.is_some() {
+        let name: Ident = input.parse()?;
+        if name == option {
+            if value.is_some() {
+                let msg = format!("`{}` is given twice", option);
+                return Err(syn::Error::new(name.span(), msg));
+            }
+            input.parse::<Token![=]>()?;
+            value = Some(input.parse()?);
+        } else if let Some(format) = Marker::parse_format(&name)? {
+            if marker.is_some() {
+                let msg = "the serialization format is given twice";
+                return Err(syn::Error::new(name.span(), msg));
+            }
+            marker = Some(format);
+        } else {
+            let msg = format!("expected `{}`, `json` or `msgpack`", option);
+            return Err(syn::Error::new(name.span(), msg));
+        }
+    }
+    input.parse::<Token![;]>()?;
+    let marker = match marker {
+        Some(marker) => marker,
+        None => Marker::default_format("jobs")?,
+    };
+    Ok(QueueDef {
+        attrs,
+        vis,
+        name,
+        job,
+        key: expand_key(key)?,
+        option: value,
+        marker,
+    })
+}
+
+/// Generate the queues of a `redis_work_queue!` invocation, each a unit struct with a producer and
+/// a worker loop
+pub(crate) fn expand_work_queue(input: TokenStream) -> syn::Result<TokenStream> {
+    let mut output = TokenStream::new();
+    for def in parse_defs(input, "visibility")? {
+        output.extend(expand_def(def));
+    }
+    Ok(output)
+}
+
+fn expand_def(def: QueueDef) -> TokenStream {
+    let QueueDef {
+        attrs,
+        vis,
+        name,
+        job,
+        key,
+        option: visibility,
+        marker,
+    } = def;
+    // Jobs that aren't handled within 30 seconds are moved back to the queue by default
+    let visibility = visibility.map_or(quote!(30000), |visibility| quote!(#visibility));
+    let wrapper = marker.reply_wrapper();
+    let job_var = Ident::new("job", Span::mixed_site());
+    let serialized = marker.expand(&syn::parse_quote!(#job_var));
+    quote! {
+        #(#attrs)*
+        #vis struct #name;
+
+        impl #name {
+            /// The queue, with its keys
+            pub fn queue() -> ::redis_rs_macro::WorkQueue {
+                ::redis_rs_macro::WorkQueue::new(#key, #visibility)
+            }
+
+            /// Add a job to the queue with `LPUSH`
+            pub fn push(
+                con: &mut dyn redis::ConnectionLike,
+                #job_var: &#job,
+            ) -> redis::RedisResult<()> {
+                Self::queue().push(con, #serialized)
+            }
+
+            /// Take jobs and pass them to `handler`, until it returns `Ok(ControlFlow::Break(()))`.
+            /// Jobs it returns `Err` for are moved back to the queue once their lease expires.
+            pub fn work<R, E>(
+                con: &mut dyn redis::ConnectionLike,
+                mut handler: impl ::core::ops::FnMut(#job) -> ::core::result::Result<R, E>,
+            ) -> redis::RedisResult<()>
+            where
+                R: ::redis_rs_macro::HandlerResult,
+            {
+                Self::queue().work(con, |#wrapper(#job_var): #wrapper<#job>| handler(#job_var))
+            }
+
+            /// Move jobs whose lease expired back to the queue, returning how many were moved
+            pub fn requeue_stale(con: &mut dyn redis::ConnectionLike) -> redis::RedisResult<usize> {
+                Self::queue().requeue_stale(con)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expand(input: &str) -> syn::Result<String> {
+        expand_work_queue(input.parse().unwrap()).map(|output| output.to_string())
+    }
+
+    #[test]
+    fn work_queue_expand() {
+        let output = expand(
+            "/// Emails\npub struct Emails(Email) = queue:emails;\n\
+             struct Jobs(Vec<u8>) = jobs:{region}, visibility = 5000, msgpack;",
+        )
+        .unwrap();
+        assert!(
+            output.starts_with("# [doc = \" Emails\"] pub struct Emails ; impl Emails {"),
+            "{}",
+            output
+        );
+        assert!(output.contains(", 30000) }"), "{}", output);
+        assert!(
+            output.contains("| :: redis_rs_macro :: Json (job) : :: redis_rs_macro :: Json < Email > | handler (job)"),
+            "{}",
+            output
+        );
+        assert!(output.contains("struct Jobs ; impl Jobs {"), "{}", output);
+        assert!(output.contains(", 5000) }"), "{}", output);
+        assert!(
+            output.contains("Self :: queue () . push (con , :: redis_rs_macro :: __private :: msgpack :: to_vec (& (job)) ?)"),
+            "{}",
+            output
+        );
+    }
+
+    #[test]
+    fn work_queue_errors() {
+        let err = |input: &str| expand(input).unwrap_err().to_string();
+        let e = err("struct Q(Job) = a b;");
+        assert!(e.contains("single key"), "{}", e);
+        let e = err("struct Q(Job) = a, timeout = 1;");
+        assert!(e.contains("expected `visibility`"), "{}", e);
+        let e = err("struct Q(Job) = a, visibility = 1, visibility = 2;");
+        assert!(e.contains("given twice"), "{}", e);
+        let e = err("struct Q(Job) = a, json, msgpack;");
+        assert!(e.contains("format is given twice"), "{}", e);
+        let e = err("struct Q(Job) = a");
+        assert!(e.contains("expected `;`"), "{}", e);
+    }
+}
@@ -0,0 +1,128 @@
+use crate::lock::expand_key;
+use proc_macro2::{Span, TokenStream, TokenTree};
+use quote::quote;
+use syn::parse::{Parse, ParseStream};
+use syn::{Expr, Ident, Token};
+
+/// The input of `redis_ratelimit!`, e.g. `con, api:{user}, limit = 100, window = 60000, sliding`
+struct RateLimitInput {
+    con: Expr,
+    key: TokenStream,
+    limit: Expr,
+    window: Expr,
+    sliding: bool,
+}
+
+impl Parse for RateLimitInput {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let con = input.parse()?;
+        input.parse::<Token![,]>()?;
+        let mut key = TokenStream::new();
+        while !input.is_empty() && !input.peek(Token![,]) {
+            key.extend([input.parse::<TokenTree>()?]);
+        }
+        let mut limit = None;
+        let mut window = None;
+        let mut kind: Option<Ident> = None;
+        while input.parse::<Option<Token![,]>>()?.is_some() && !input.is_empty() {
+            let name: Ident = input.parse()?;
+            let value = match name.to_string().as_str() {
+                "limit" => &mut limit,
+                "window" => &mut window,
+                "fixed" | "sliding" => {
+                    if kind.is_some() {
+                        let msg = "the window is given twice";
+                        return Err(syn::Error::new(name.span(), msg));
+                    }
+                    kind = Some(name);
+                    continue;
+                }
+                _ => {
+                    let msg = "expected `limit`, `window`, `fixed` or `sliding`";
+                    return Err(syn::Error::new(name.span(), msg));
+                }
+            };
+            if value.is_some() {
+                let msg = format!("`{}` is given twice", name);
+                return Err(syn::Error::new(name.span(), msg));
+            }
+            input.parse::<Token![=]>()?;
+            *value = Some(input.parse()?);
+        }
+        if !input.is_empty() {
+            return Err(input.error("expected `,`"));
+        }
+        let missing = |name: &str| {
+            let msg = format!("expected `{} = ..`", name);
+            syn::Error::new(Span::call_site(), msg)
+        };
+        Ok(RateLimitInput {
+            con,
+            key,
+            limit: limit.ok_or_else(|| missing("limit"))?,
+            window: window.ok_or_else(|| missing("window"))?,
+            sliding: kind.is_some_and(|kind| kind == "sliding"),
+        })
+    }
+}
+
+/// Generate a `redis_ratelimit!` invocation, which counts a request and returns whether it is
+/// allowed
+pub(crate) fn expand_ratelimit(input: TokenStream) -> syn::Result<TokenStream> {
+    let RateLimitInput {
+        con,
+        key,
+        limit,
+        window,
+        sliding,
+    } = syn::parse2(input)?;
+    let key = expand_key(key)?;
+    let kind = match sliding {
+        true => quote!(::redis_rs_macro::Window::Sliding),
+        false => quote!(::redis_rs_macro::Window::Fixed),
+    };
+    Ok(quote! {
+        ::redis_rs_macro::RateLimit::new(#key, #limit, #window, #kind).check(#con)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expand(input: &str) -> syn::Result<String> {
+        expand_ratelimit(input.parse().unwrap()).map(|output| output.to_string())
+    }
+
+    #[test]
+    fn ratelimit_expand() {
+        let output = expand("con, api:{user}, limit = 100, window = 60_000").unwrap();
+        assert!(
+            output
+                .ends_with(", 100 , 60_000 , :: redis_rs_macro :: Window :: Fixed) . check (con)"),
+            "{}",
+            output
+        );
+        let output = expand("con, api, sliding, window = w, limit = n * 2,").unwrap();
+        assert!(
+            output.ends_with(", n * 2 , w , :: redis_rs_macro :: Window :: Sliding) . check (con)"),
+            "{}",
+            output
+        );
+    }
+
+    #[test]
+    fn ratelimit_errors() {
+        let err = |input: &str| expand(input).unwrap_err().to_string();
+        let e = err("con, api, limit = 1");
+        assert!(e.contains("expected `window = ..`"), "{}", e);
+        let e = err("con, api, limit = 1, limit = 2, window = 1");
+        assert!(e.contains("given twice"), "{}", e);
+        let e = err("con, api, fixed, sliding, limit = 1, window = 1");
+        assert!(e.contains("window is given twice"), "{}", e);
+        let e = err("con, api, rate = 1");
+        assert!(e.contains("expected `limit`"), "{}", e);
+        let e = err("con, a b, limit = 1, window = 1");
+        assert!(e.contains("single key"), "{}", e);
+    }
+}
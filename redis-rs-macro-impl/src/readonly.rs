@@ -0,0 +1,65 @@
+use crate::commands::{lookup, Access};
+use crate::parse::Command;
+use crate::scope::{mark_macros, readonly_marker};
+use proc_macro2::{Span, TokenStream};
+
+/// Reject a command that writes, according to the command table
+pub(crate) fn check_readonly(command: &Command) -> syn::Result<()> {
+    match lookup(&command.args) {
+        Some(info) if info.access == Access::Write => {
+            let msg = format!(
+                "`{}` is a write command, which can't be used in a `#[redis_readonly]` item",
+                info.name
+            );
+            Err(syn::Error::new(command.args[0].span, msg))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Generate a `#[redis_readonly]` item, whose nested macro calls can't build write commands
+pub(crate) fn expand_readonly(args: TokenStream, item: TokenStream) -> syn::Result<TokenStream> {
+    if !args.is_empty() {
+        let msg = "`#[redis_readonly]` takes no arguments";
+        return Err(syn::Error::new(Span::call_site(), msg));
+    }
+    Ok(mark_macros(item, &readonly_marker()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse::parse_command;
+    use quote::quote;
+
+    fn check(input: &str) -> syn::Result<()> {
+        parse_command(input.parse().unwrap()).map(|_| ())
+    }
+
+    #[test]
+    fn readonly_check() {
+        check("@readonly GET a").unwrap();
+        check("@readonly XINFO STREAM s").unwrap();
+        check("@readonly PING").unwrap();
+        check("@readonly MODULE.CMD k").unwrap();
+        check("SET a 1").unwrap();
+    }
+
+    #[test]
+    fn readonly_errors() {
+        let err = |input: &str| check(input).unwrap_err().to_string();
+        let e = err("@readonly SET a 1");
+        assert!(e.contains("`SET` is a write command"), "{}", e);
+        let e = err("@readonly xgroup create s g $");
+        assert!(e.contains("`XGROUP CREATE` is a write command"), "{}", e);
+        let e = expand_readonly(
+            quote!(strict),
+            quote!(
+                fn f() {}
+            ),
+        )
+        .unwrap_err()
+        .to_string();
+        assert!(e.contains("no arguments"), "{}", e);
+    }
+}
@@ -0,0 +1,226 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Data, DeriveInput, Fields, LitStr, Member, Token};
+
+/// A field of a `#[derive(RedisReply)]` struct, with its name in the reply
+struct Field {
+    member: Member,
+    ty: syn::Type,
+    /// The name of the field in a reply of names and values, and in errors
+    name: LitStr,
+    /// `#[redis(default)]`: read as its default when the reply doesn't have it
+    default: bool,
+}
+
+/// Generate a `redis::FromRedisValue` implementation for a struct, reading tuple structs and
+/// `#[redis(positional)]` structs from the items of an array reply in order, and other structs
+/// from a reply of alternating field names and values
+pub(crate) fn expand_reply(input: TokenStream) -> syn::Result<TokenStream> {
+    let input: DeriveInput = syn::parse2(input)?;
+    let Data::Struct(data) = &input.data else {
+        let msg = "`RedisReply` can only be derived for structs";
+        return Err(syn::Error::new(input.ident.span(), msg));
+    };
+    let mut positional = matches!(data.fields, Fields::Unnamed(_));
+    for attr in &input.attrs {
+        if !attr.path().is_ident("redis") {
+            continue;
+        }
+        attr.parse_nested_meta(|meta| {
+            if !meta.path.is_ident("positional") {
+                return Err(meta.error("expected `positional`"));
+            }
+            positional = true;
+            Ok(())
+        })?;
+    }
+    if matches!(data.fields, Fields::Unit) {
+        let msg = "`RedisReply` needs a struct with fields";
+        return Err(syn::Error::new(input.ident.span(), msg));
+    }
+    let fields = data
+        .fields
+        .iter()
+        .enumerate()
+        .map(|(index, field)| parse_field(index, field, positional))
+        .collect::<syn::Result<Vec<_>>>()?;
+    if !positional {
+        for (i, field) in fields.iter().enumerate() {
+            if fields[..i]
+                .iter()
+                .any(|f| f.name.value() == field.name.value())
+            {
+                let msg = format!("the reply field `{}` is used twice", field.name.value());
+                return Err(syn::Error::new(field.name.span(), msg));
+            }
+        }
+    }
+
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let mut bounds = where_clause
+        .cloned()
+        .unwrap_or_else(|| syn::parse_quote!(where));
+    for Field { ty, default, .. } in &fields {
+        bounds
+            .predicates
+            .push(syn::parse_quote!(#ty: redis::FromRedisValue));
+        if *default {
+            bounds
+                .predicates
+                .push(syn::parse_quote!(#ty: ::core::default::Default));
+        }
+    }
+    let reads = fields.iter().enumerate().map(|(index, field)| {
+        let Field { member, name, .. } = field;
+        let get = match field.default {
+            true => quote!(get_or_default),
+            false => quote!(get),
+        };
+        match positional {
+            true => quote!(#member: items.#get(#index, #name)?,),
+            false => quote!(#member: fields.#get(#name)?,),
+        }
+    });
+    let read = match positional {
+        true => {
+            let len = fields.len();
+            quote! {
+                let items = ::redis_rs_macro::__private::entry::ReplyItems::new(value, #len)?;
+            }
+        }
+        false => quote! {
+            let fields = ::redis_rs_macro::__private::entry::EntryFields::new(value)?;
+        },
+    };
+    Ok(quote! {
+        impl #impl_generics redis::FromRedisValue for #ident #ty_generics #bounds {
+            fn from_redis_value(value: &redis::Value) -> redis::RedisResult<Self> {
+                #read
+                ::core::result::Result::Ok(#ident {
+                    #(#reads)*
+                })
+            }
+        }
+    })
+}
+
+/// Parse the `#[redis(rename = "name", default)]` attributes of a field
+fn parse_field(index: usize, field: &syn::Field, positional: bool) -> syn::Result<Field> {
+    let (member, name) = match &field.ident {
+        Some(ident) => (
+            Member::Named(ident.clone()),
+            LitStr::new(&ident.to_string(), ident.span()),
+        ),
+        None => (
+            Member::Unnamed(index.into()),
+            LitStr::new(&index.to_string(), proc_macro2::Span::call_site()),
+        ),
+    };
+    let mut parsed = Field {
+        member,
+        ty: field.ty.clone(),
+        name,
+        default: false,
+    };
+    for attr in &field.attrs {
+        if !attr.path().is_ident("redis") {
+            continue;
+        }
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("rename") {
+                if positional {
+                    let msg = "fields read by position have no name to rename";
+                    return Err(meta.error(msg));
+                }
+                meta.input.parse::<Token![=]>()?;
+                parsed.name = meta.input.parse()?;
+            } else if meta.path.is_ident("default") {
+                parsed.default = true;
+            } else {
+                return Err(meta.error("expected `rename = \"...\"` or `default`"));
+            }
+            Ok(())
+        })?;
+    }
+    Ok(parsed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expand(input: &str) -> syn::Result<String> {
+        expand_reply(input.parse().unwrap()).map(|out| out.to_string())
+    }
+
+    #[test]
+    fn reply_expand() {
+        let output = expand(
+            r#"struct Config {
+                #[redis(rename = "maxmemory")]
+                max_memory: u64,
+                #[redis(default)]
+                policy: String,
+            }"#,
+        )
+        .unwrap();
+        assert!(
+            output.contains("EntryFields :: new (value) ?"),
+            "{}",
+            output
+        );
+        assert!(
+            output.contains("max_memory : fields . get (\"maxmemory\") ?"),
+            "{}",
+            output
+        );
+        assert!(
+            output.contains("policy : fields . get_or_default (\"policy\") ?"),
+            "{}",
+            output
+        );
+
+        let output = expand("struct Range(u64, #[redis(default)] Option<u64>);").unwrap();
+        assert!(
+            output.contains("ReplyItems :: new (value , 2usize) ?"),
+            "{}",
+            output
+        );
+        assert!(
+            output.contains("0 : items . get (0usize , \"0\") ?"),
+            "{}",
+            output
+        );
+        assert!(
+            output.contains("1 : items . get_or_default (1usize , \"1\") ?"),
+            "{}",
+            output
+        );
+
+        let output =
+            expand("#[redis(positional)] struct Member { name: String, score: f64 }").unwrap();
+        assert!(
+            output.contains("score : items . get (1usize , \"score\") ?"),
+            "{}",
+            output
+        );
+    }
+
+    #[test]
+    fn reply_errors() {
+        let err = |input: &str| expand(input).unwrap_err().to_string();
+        let e = err("enum T { A }");
+        assert!(e.contains("only be derived for structs"), "{}", e);
+        let e = err("struct T;");
+        assert!(e.contains("needs a struct with fields"), "{}", e);
+        let e = err("#[redis(ordered)] struct T { a: u8 }");
+        assert!(e.contains("expected `positional`"), "{}", e);
+        let e = err("#[redis(positional)] struct T { #[redis(rename = \"b\")] a: u8 }");
+        assert!(e.contains("no name to rename"), "{}", e);
+        let e = err("struct T { #[redis(skip)] a: u8 }");
+        assert!(e.contains("expected `rename"), "{}", e);
+        let e = err("struct T { a: u8, #[redis(rename = \"a\")] b: u8 }");
+        assert!(e.contains("the reply field `a` is used twice"), "{}", e);
+    }
+}
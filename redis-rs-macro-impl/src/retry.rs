@@ -0,0 +1,131 @@
+use crate::exec::Exec;
+use crate::expand::expand_command;
+use crate::parse::parse_command;
+use crate::typed::split_return;
+use proc_macro2::{Span, TokenStream};
+use quote::quote;
+use syn::parse::{Parse, ParseStream};
+use syn::{Expr, Ident, Token};
+
+/// The input of `redis_retry!` and `redis_retry_async!`, e.g. `policy, con, GET foo`
+struct RetryInput {
+    policy: Expr,
+    exec: Exec,
+}
+
+impl Parse for RetryInput {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let policy = input.parse()?;
+        input.parse::<Token![,]>()?;
+        let exec = input.parse()?;
+        Ok(RetryInput { policy, exec })
+    }
+}
+
+/// Generate the query of a `redis_retry!` invocation, which sends the command right away and sends
+/// it again while it fails with a transient error
+pub(crate) fn expand_retry(input: TokenStream) -> syn::Result<TokenStream> {
+    let RetryInput {
+        policy,
+        exec: Exec { con, command },
+    } = syn::parse2(input)?;
+    let (command, ty) = split_return(command)?;
+    let cmd = expand_command(&parse_command(command)?)?;
+    let ty = ty.map(|ty| quote!(::<#ty>));
+    let cmd_var = Ident::new("cmd", Span::mixed_site());
+    Ok(quote! {
+        {
+            let #cmd_var = #cmd;
+            ::redis_rs_macro::RetryPolicy::run(&#policy, || redis::Cmd::query #ty (&#cmd_var, #con))
+        }
+    })
+}
+
+/// Generate the future of a `redis_retry_async!` invocation. As with `redis_async!`, the command
+/// and connection are moved into the future, along with the policy.
+pub(crate) fn expand_retry_async(input: TokenStream) -> syn::Result<TokenStream> {
+    let RetryInput {
+        policy,
+        exec: Exec { con, command },
+    } = syn::parse2(input)?;
+    let (command, ty) = split_return(command)?;
+    let cmd = expand_command(&parse_command(command)?)?;
+    let ty = ty.map(|ty| quote!(::<_, #ty>));
+    let cmd_var = Ident::new("cmd", Span::mixed_site());
+    let con_var = Ident::new("con", Span::mixed_site());
+    let policy_var = Ident::new("policy", Span::mixed_site());
+    let retry = Ident::new("retry", Span::mixed_site());
+    let err = Ident::new("err", Span::mixed_site());
+    let delay = Ident::new("delay", Span::mixed_site());
+    let result = Ident::new("result", Span::mixed_site());
+    Ok(quote! {
+        {
+            let #cmd_var = #cmd;
+            let #con_var = #con;
+            let #policy_var = #policy;
+            async move {
+                let mut #retry = 0;
+                loop {
+                    match redis::Cmd::query_async #ty (&#cmd_var, &mut *#con_var).await {
+                        ::core::result::Result::Err(#err) => {
+                            match ::redis_rs_macro::RetryPolicy::retry_delay(&#policy_var, &#err, #retry) {
+                                ::core::option::Option::Some(#delay) => {
+                                    ::redis_rs_macro::__private::retry::sleep(#delay).await
+                                }
+                                ::core::option::Option::None => break ::core::result::Result::Err(#err),
+                            }
+                        }
+                        #result => break #result,
+                    }
+                    #retry += 1;
+                }
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn retry_expand() {
+        let output = expand_retry("policy, &mut con, GET foo -> String".parse().unwrap())
+            .unwrap()
+            .to_string();
+        assert!(
+            output.ends_with(
+                "; :: redis_rs_macro :: RetryPolicy :: run (& policy , || redis :: Cmd :: query :: < String > (& cmd , & mut con)) }"
+            ),
+            "{}",
+            output
+        );
+        let err = expand_retry("policy, con".parse().unwrap())
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("expected `,`"), "{}", err);
+    }
+
+    #[test]
+    fn retry_async_expand() {
+        let output =
+            expand_retry_async("RetryPolicy::default(), &mut con, GET foo".parse().unwrap())
+                .unwrap()
+                .to_string();
+        assert!(output.contains("let con = & mut con ;"), "{}", output);
+        assert!(
+            output.contains("let policy = RetryPolicy :: default () ;"),
+            "{}",
+            output
+        );
+        assert!(
+            output.contains("match redis :: Cmd :: query_async (& cmd , & mut * con) . await {"),
+            "{}",
+            output
+        );
+        let err = expand_retry_async("policy, con,".parse().unwrap())
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("expected a redis command"), "{}", err);
+    }
+}
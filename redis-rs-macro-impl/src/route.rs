@@ -0,0 +1,106 @@
+use crate::expand::expand_command;
+use crate::keys::{is_single, key_roles, KeyRole};
+use crate::parse::{parse_command, Command};
+use crate::slot::literal_slot;
+use proc_macro2::{Ident, Span, TokenStream};
+use quote::quote;
+
+/// Commands without keys that act on the keys of every node
+const ALL_PRIMARIES: &[&str] = &["DBSIZE", "FLUSHALL", "FLUSHDB", "KEYS", "RANDOMKEY", "SCAN"];
+
+/// Generate a `redis_routed!` invocation, the command along with its `redis_rs_macro::Route`
+pub(crate) fn expand_routed(input: TokenStream) -> syn::Result<TokenStream> {
+    expand_routed_command(&parse_command(input)?)
+}
+
+/// Generate a block that evaluates to the `(redis::Cmd, redis_rs_macro::Route)` of a parsed command
+pub(crate) fn expand_routed_command(command: &Command) -> syn::Result<TokenStream> {
+    let cmd = Ident::new("cmd", Span::mixed_site());
+    let route = expand_route(command, &cmd)?;
+    let build = expand_command(command)?;
+    Ok(quote! {
+        {
+            let #cmd = #build;
+            let route = #route;
+            (#cmd, route)
+        }
+    })
+}
+
+/// Generate the route of a command from its first key, which is computed at compile time when the
+/// key is known then, and read from the built command `cmd` otherwise
+fn expand_route(command: &Command, cmd: &Ident) -> syn::Result<TokenStream> {
+    let roles = key_roles(command);
+    let first = roles.iter().position(|role| *role != KeyRole::None);
+    let Some(index) = first else {
+        let name = command.args[0].word().map(|name| name.to_ascii_uppercase());
+        if name.is_some_and(|name| ALL_PRIMARIES.contains(&name.as_str())) {
+            return Ok(quote!(::redis_rs_macro::Route::AllPrimaries));
+        }
+        return Ok(quote!(::redis_rs_macro::Route::Random));
+    };
+    let arg = &command.args[index];
+    if roles[index] == KeyRole::Unknown || !command.args[..index].iter().all(is_single) {
+        let msg = "the position of the first key depends on the spreads or optional arguments \
+                   before it, so the command can't be routed";
+        return Err(syn::Error::new(arg.span, msg));
+    }
+    // A runtime key prefix changes what is hashed, so keys can only be hashed here without one
+    if !cfg!(feature = "key-prefix") {
+        if let Some((slot, _)) = literal_slot(arg) {
+            return Ok(quote!(::redis_rs_macro::Route::Slot(#slot)));
+        }
+    }
+    Ok(quote!(::redis_rs_macro::Route::for_arg(&#cmd, #index)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn route(input: &str) -> syn::Result<String> {
+        let command = parse_command(input.parse().unwrap())?;
+        let cmd = Ident::new("cmd", Span::call_site());
+        expand_route(&command, &cmd).map(|route| route.to_string())
+    }
+
+    #[test]
+    fn route_expand() {
+        assert_eq!(
+            route("PING").unwrap(),
+            ":: redis_rs_macro :: Route :: Random"
+        );
+        assert_eq!(
+            route("keys user:*").unwrap(),
+            ":: redis_rs_macro :: Route :: AllPrimaries"
+        );
+        assert_eq!(
+            route("XREADGROUP GROUP g c STREAMS {s} >").unwrap(),
+            ":: redis_rs_macro :: Route :: for_arg (& cmd , 5usize)"
+        );
+        assert_eq!(
+            route("GET {key}").unwrap(),
+            ":: redis_rs_macro :: Route :: for_arg (& cmd , 1usize)"
+        );
+        if !cfg!(feature = "key-prefix") {
+            assert_eq!(
+                route("GET foo").unwrap(),
+                ":: redis_rs_macro :: Route :: Slot (12182u16)"
+            );
+            assert_eq!(
+                route("SET \"{user}:\"{id} 1").unwrap(),
+                route("GET \"{user}\"").unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn route_errors() {
+        let e = route("XREAD COUNT {count?} STREAMS s 0")
+            .unwrap_err()
+            .to_string();
+        assert!(e.contains("can't be routed"), "{}", e);
+        let e = route("EVAL {script} {n} {..keys}").unwrap_err().to_string();
+        assert!(e.contains("can't be routed"), "{}", e);
+    }
+}
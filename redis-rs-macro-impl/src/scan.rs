@@ -0,0 +1,167 @@
+use crate::exec::Exec;
+use crate::expand::expand_command;
+use crate::parse::{parse_command, Arg, Command, Piece};
+use crate::typed::split_return;
+use proc_macro2::{Ident, Span, TokenStream};
+use quote::quote;
+
+/// The commands that `redis_scan!` takes, with the index the cursor is inserted at
+const SCANS: &[(&str, usize)] = &[("SCAN", 1), ("HSCAN", 2), ("SSCAN", 2), ("ZSCAN", 2)];
+
+/// Generate the iterator of a `redis_scan!` invocation. The command is built by a closure that
+/// takes the cursor, which is inserted where the command expects it.
+pub(crate) fn expand_scan(input: TokenStream) -> syn::Result<TokenStream> {
+    let Scan { con, ty, build } = parse_scan(input)?;
+    Ok(quote! {
+        ::redis_rs_macro::Scan::<#ty, _>::new(#con, #build)
+    })
+}
+
+/// Generate the stream of a `redis_scan_async!` invocation. Since the `Stream` trait comes from
+/// the `futures` crate, the stream is built with the `futures` of the calling crate.
+pub(crate) fn expand_scan_async(input: TokenStream) -> syn::Result<TokenStream> {
+    let Scan { con, ty, build } = parse_scan(input)?;
+    Ok(quote! {
+        ::futures::stream::unfold(
+            (#con, #build, ::core::option::Option::Some(0u64), ::std::collections::VecDeque::<#ty>::new()),
+            |(con, mut build, mut cursor, mut items)| async move {
+                loop {
+                    if let ::core::option::Option::Some(item) = items.pop_front() {
+                        return ::core::option::Option::Some((
+                            ::core::result::Result::Ok(item),
+                            (con, build, cursor, items),
+                        ));
+                    }
+                    // Batches can be empty while the cursor isn't done yet
+                    let next = cursor?;
+                    match redis::Cmd::query_async::<_, (u64, ::std::vec::Vec<#ty>)>(&build(next), con).await {
+                        ::core::result::Result::Ok((next, batch)) => {
+                            cursor = (next != 0).then_some(next);
+                            items = batch.into();
+                        }
+                        ::core::result::Result::Err(err) => {
+                            return ::core::option::Option::Some((
+                                ::core::result::Result::Err(err),
+                                (con, build, ::core::option::Option::None, items),
+                            ));
+                        }
+                    }
+                }
+            },
+        )
+    })
+}
+
+/// The parts of a `redis_scan!` invocation
+struct Scan {
+    con: syn::Expr,
+    /// The type of the items, or `_`
+    ty: TokenStream,
+    /// A closure that builds the command for a cursor
+    build: TokenStream,
+}
+
+fn parse_scan(input: TokenStream) -> syn::Result<Scan> {
+    let Exec { con, command } = syn::parse2(input)?;
+    let (command, ty) = split_return(command)?;
+    let mut command = parse_command(command)?;
+    // Mixed site hygiene keeps the parameter from shadowing variables used in substitutions
+    let cursor = Ident::new("cursor", Span::mixed_site());
+    insert_cursor(&mut command, &cursor)?;
+    let cmd = expand_command(&command)?;
+    Ok(Scan {
+        con,
+        ty: ty.map_or_else(|| quote!(_), |ty| quote!(#ty)),
+        build: quote!(|#cursor: u64| #cmd),
+    })
+}
+
+fn insert_cursor(command: &mut Command, cursor: &Ident) -> syn::Result<()> {
+    let name = &command.args[0];
+    let index = name.word().and_then(|word| {
+        SCANS
+            .iter()
+            .find(|(scan, _)| word.eq_ignore_ascii_case(scan))
+            .map(|(_, index)| *index)
+    });
+    let Some(index) = index else {
+        let msg = "`redis_scan!` takes a `SCAN`, `HSCAN`, `SSCAN` or `ZSCAN` command";
+        return Err(syn::Error::new(name.span, msg));
+    };
+    if index == 2 {
+        let key = command.args.get(1).filter(|key| {
+            key.pieces
+                .iter()
+                .all(|piece| piece.standalone_kind().is_none())
+        });
+        if key.is_none() {
+            let msg = "expected the key to scan, as a single argument";
+            let span = command.args.get(1).map_or(name.span, |key| key.span);
+            return Err(syn::Error::new(span, msg));
+        }
+    }
+    let arg = Arg {
+        pieces: vec![Piece::Expr(Box::new(syn::parse_quote!(#cursor)))],
+        span: Span::call_site(),
+    };
+    command.args.insert(index, arg);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expand(input: &str) -> syn::Result<String> {
+        expand_scan(input.parse().unwrap()).map(|output| output.to_string())
+    }
+
+    #[test]
+    fn scan_expand() {
+        let output = expand("con, SCAN MATCH user:* COUNT 100").unwrap();
+        assert!(
+            output.starts_with(
+                ":: redis_rs_macro :: Scan :: < _ , _ > :: new (con , | cursor : u64 | {"
+            ),
+            "{}",
+            output
+        );
+        let output = expand("&mut con, hscan h -> (String, i64)").unwrap();
+        assert!(
+            output.contains("Scan :: < (String , i64) , _ >"),
+            "{}",
+            output
+        );
+    }
+
+    #[test]
+    fn scan_async() {
+        let output = expand_scan_async("&mut con, SSCAN s -> String".parse().unwrap())
+            .unwrap()
+            .to_string();
+        assert!(
+            output.starts_with(":: futures :: stream :: unfold ((& mut con , | cursor : u64 | {"),
+            "{}",
+            output
+        );
+        assert!(
+            output.contains("query_async :: < _ , (u64 , :: std :: vec :: Vec < String >) >"),
+            "{}",
+            output
+        );
+        let err = expand_scan_async("con, GET k".parse().unwrap())
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("takes a `SCAN`"), "{}", err);
+    }
+
+    #[test]
+    fn scan_errors() {
+        let err = expand("con, KEYS *").unwrap_err().to_string();
+        assert!(err.contains("takes a `SCAN`"), "{}", err);
+        let err = expand("con, HSCAN").unwrap_err().to_string();
+        assert!(err.contains("expected the key"), "{}", err);
+        let err = expand("con, SSCAN {..keys}").unwrap_err().to_string();
+        assert!(err.contains("expected the key"), "{}", err);
+    }
+}
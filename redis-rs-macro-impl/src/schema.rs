@@ -0,0 +1,395 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::spanned::Spanned;
+use syn::{Data, DeriveInput, Fields, Lit, LitStr, Token};
+
+/// The kind of a field in a RediSearch schema
+#[derive(Clone, Copy, PartialEq)]
+enum Kind {
+    Text,
+    Numeric,
+    Tag,
+    Geo,
+}
+
+impl Kind {
+    fn parse(name: &str) -> Option<Kind> {
+        match name {
+            "text" => Some(Kind::Text),
+            "numeric" => Some(Kind::Numeric),
+            "tag" => Some(Kind::Tag),
+            "geo" => Some(Kind::Geo),
+            _ => None,
+        }
+    }
+
+    fn keyword(self) -> &'static str {
+        match self {
+            Kind::Text => "TEXT",
+            Kind::Numeric => "NUMERIC",
+            Kind::Tag => "TAG",
+            Kind::Geo => "GEO",
+        }
+    }
+
+    /// The kind of fields of a type without a `#[search(...)]` kind: numbers are `NUMERIC`, strings
+    /// `TEXT` and booleans `TAG`, and an `Option` is indexed as what it holds
+    fn infer(ty: &syn::Type) -> Option<Kind> {
+        match ty {
+            syn::Type::Reference(reference) => Kind::infer(&reference.elem),
+            syn::Type::Path(path) => {
+                let segment = path.path.segments.last()?;
+                if segment.ident == "Option" {
+                    let syn::PathArguments::AngleBracketed(args) = &segment.arguments else {
+                        return None;
+                    };
+                    return match args.args.first()? {
+                        syn::GenericArgument::Type(ty) => Kind::infer(ty),
+                        _ => None,
+                    };
+                }
+                match segment.ident.to_string().as_str() {
+                    "i8" | "i16" | "i32" | "i64" | "i128" | "isize" | "u8" | "u16" | "u32"
+                    | "u64" | "u128" | "usize" | "f32" | "f64" => Some(Kind::Numeric),
+                    "String" | "str" => Some(Kind::Text),
+                    "bool" => Some(Kind::Tag),
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
+/// A field of the schema, as written after `SCHEMA`
+struct Field {
+    name: LitStr,
+    kind: Kind,
+    weight: Option<String>,
+    separator: Option<LitStr>,
+    sortable: bool,
+    nostem: bool,
+    noindex: bool,
+}
+
+/// Where documents are, from the `#[search(...)]` of the struct
+struct Index {
+    json: bool,
+    prefixes: Vec<LitStr>,
+}
+
+/// Generate an `ft_create` function for a struct, which builds the `FT.CREATE` command of a
+/// RediSearch index over the documents the struct is stored as
+pub(crate) fn expand_schema(input: TokenStream) -> syn::Result<TokenStream> {
+    let input: DeriveInput = syn::parse2(input)?;
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                let msg = "`RedisSearch` needs a struct with named fields";
+                return Err(syn::Error::new(input.ident.span(), msg));
+            }
+        },
+        _ => {
+            let msg = "`RedisSearch` can only be derived for structs";
+            return Err(syn::Error::new(input.ident.span(), msg));
+        }
+    };
+    let index = parse_index(&input.attrs)?;
+    let mut schema = vec![];
+    for field in fields {
+        if let Some(field) = parse_field(field)? {
+            schema.push(field);
+        }
+    }
+    if schema.is_empty() {
+        let msg = "`RedisSearch` needs at least one field to index";
+        return Err(syn::Error::new(input.ident.span(), msg));
+    }
+
+    let on = match index.json {
+        true => "JSON",
+        false => "HASH",
+    };
+    let prefixes = match index.prefixes.len() {
+        0 => quote!(),
+        len => {
+            let prefixes = &index.prefixes;
+            quote!(.arg("PREFIX").arg(#len) #(.arg(#prefixes))*)
+        }
+    };
+    let fields = schema.iter().map(|field| {
+        let name = &field.name;
+        let identifier = match index.json {
+            true => {
+                let path = format!("$.{}", name.value());
+                quote!(.arg(#path).arg("AS").arg(#name))
+            }
+            false => quote!(.arg(#name)),
+        };
+        let kind = field.kind.keyword();
+        let weight = field
+            .weight
+            .as_ref()
+            .map(|weight| quote!(.arg("WEIGHT").arg(#weight)));
+        let separator = field
+            .separator
+            .as_ref()
+            .map(|separator| quote!(.arg("SEPARATOR").arg(#separator)));
+        let nostem = field.nostem.then(|| quote!(.arg("NOSTEM")));
+        let sortable = field.sortable.then(|| quote!(.arg("SORTABLE")));
+        let noindex = field.noindex.then(|| quote!(.arg("NOINDEX")));
+        quote!(#identifier .arg(#kind) #weight #separator #nostem #sortable #noindex)
+    });
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics #ident #ty_generics #where_clause {
+            /// The `FT.CREATE` command of a RediSearch index named `index` over the documents of
+            /// this struct
+            pub fn ft_create(index: &str) -> redis::Cmd {
+                redis::cmd("FT.CREATE")
+                    .arg(index)
+                    .arg("ON")
+                    .arg(#on)
+                    #prefixes
+                    .arg("SCHEMA")
+                    #(#fields)*
+                    .clone()
+            }
+        }
+    })
+}
+
+/// Parse `#[search(on = "hash" | "json", prefix = "...")]`, where `prefix` can be given many times
+fn parse_index(attrs: &[syn::Attribute]) -> syn::Result<Index> {
+    let mut index = Index {
+        json: false,
+        prefixes: vec![],
+    };
+    for attr in attrs {
+        if !attr.path().is_ident("search") {
+            continue;
+        }
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("on") {
+                let on: LitStr = meta.value()?.parse()?;
+                index.json = match on.value().to_ascii_lowercase().as_str() {
+                    "hash" => false,
+                    "json" => true,
+                    _ => return Err(syn::Error::new(on.span(), "expected `hash` or `json`")),
+                };
+            } else if meta.path.is_ident("prefix") {
+                index.prefixes.push(meta.value()?.parse()?);
+            } else {
+                return Err(meta.error("expected `on = \"...\"` or `prefix = \"...\"`"));
+            }
+            Ok(())
+        })?;
+    }
+    Ok(index)
+}
+
+/// Parse a field of the struct, which is named by its `#[redis(rename)]` as with `RedisHash`,
+/// and left out of the schema with `#[redis(skip)]` or `#[search(skip)]`
+fn parse_field(field: &syn::Field) -> syn::Result<Option<Field>> {
+    let ident = field.ident.as_ref().unwrap();
+    let mut name = LitStr::new(&ident.to_string(), ident.span());
+    let mut skip = false;
+    for attr in &field.attrs {
+        if !attr.path().is_ident("redis") {
+            continue;
+        }
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("rename") {
+                name = meta.value()?.parse()?;
+            } else if meta.path.is_ident("skip") {
+                skip = true;
+            } else if meta.input.peek(Token![=]) {
+                // Attributes of the other derives
+                meta.value()?.parse::<syn::Expr>()?;
+            }
+            Ok(())
+        })?;
+    }
+    let mut kind = None;
+    let mut parsed = Field {
+        name,
+        kind: Kind::Text,
+        weight: None,
+        separator: None,
+        sortable: false,
+        nostem: false,
+        noindex: false,
+    };
+    for attr in &field.attrs {
+        if !attr.path().is_ident("search") {
+            continue;
+        }
+        attr.parse_nested_meta(|meta| {
+            let option = meta
+                .path
+                .get_ident()
+                .map(ToString::to_string)
+                .unwrap_or_default();
+            if let Some(new) = Kind::parse(&option) {
+                if kind.is_some() {
+                    return Err(meta.error("a field can only have one kind"));
+                }
+                kind = Some((new, meta.path.span()));
+                return Ok(());
+            }
+            match option.as_str() {
+                "skip" => skip = true,
+                "sortable" => parsed.sortable = true,
+                "nostem" => parsed.nostem = true,
+                "noindex" => parsed.noindex = true,
+                "weight" => {
+                    let weight = match meta.value()?.parse()? {
+                        Lit::Float(lit) => (lit.base10_parse::<f64>()?, lit.span()),
+                        Lit::Int(lit) => (lit.base10_parse::<f64>()?, lit.span()),
+                        lit => return Err(syn::Error::new(lit.span(), "expected a number")),
+                    };
+                    if weight.0 <= 0.0 {
+                        let msg = "the weight of a field has to be positive";
+                        return Err(syn::Error::new(weight.1, msg));
+                    }
+                    parsed.weight = Some(weight.0.to_string());
+                }
+                "separator" => {
+                    let separator: LitStr = meta.value()?.parse()?;
+                    if separator.value().chars().count() != 1 {
+                        let msg = "the separator of a tag is a single character";
+                        return Err(syn::Error::new(separator.span(), msg));
+                    }
+                    parsed.separator = Some(separator);
+                }
+                _ => {
+                    return Err(meta.error(
+                        "expected `text`, `numeric`, `tag`, `geo`, `weight = ...`, \
+                         `separator = \"...\"`, `sortable`, `nostem`, `noindex` or `skip`",
+                    ))
+                }
+            }
+            Ok(())
+        })?;
+    }
+    if skip {
+        return Ok(None);
+    }
+    parsed.kind = match kind {
+        Some((kind, _)) => kind,
+        None => Kind::infer(&field.ty).ok_or_else(|| {
+            let msg = format!(
+                "the schema kind of `{}` can't be inferred from its type; add \
+                 `#[search(text)]`, `#[search(numeric)]`, `#[search(tag)]` or `#[search(geo)]`, \
+                 or `#[search(skip)]` to leave it out of the index",
+                ident
+            );
+            syn::Error::new(field.ty.span(), msg)
+        })?,
+    };
+    let invalid = [
+        (parsed.weight.is_some(), "weight", Kind::Text),
+        (parsed.nostem, "nostem", Kind::Text),
+        (parsed.separator.is_some(), "separator", Kind::Tag),
+    ]
+    .into_iter()
+    .find(|(given, _, kind)| *given && parsed.kind != *kind);
+    if let Some((_, option, kind)) = invalid {
+        let msg = format!(
+            "`{}` only applies to {} fields, and `{}` is {}",
+            option,
+            kind.keyword(),
+            ident,
+            parsed.kind.keyword()
+        );
+        return Err(syn::Error::new(ident.span(), msg));
+    }
+    if parsed.sortable && parsed.kind == Kind::Geo {
+        let msg = "GEO fields can't be sortable";
+        return Err(syn::Error::new(ident.span(), msg));
+    }
+    Ok(Some(parsed))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expand(input: &str) -> syn::Result<String> {
+        expand_schema(input.parse().unwrap()).map(|out| out.to_string())
+    }
+
+    #[test]
+    fn schema_expand() {
+        let output = expand(
+            r#"#[search(prefix = "product:", prefix = "item:")]
+            struct Product {
+                #[search(weight = 2.0, sortable)]
+                name: String,
+                #[redis(rename = "desc", default)]
+                #[search(text, nostem)]
+                description: Option<String>,
+                price: f64,
+                #[search(tag, separator = "|")]
+                tags: Vec<String>,
+                #[search(geo)]
+                location: String,
+                #[redis(skip)]
+                cache: Vec<u8>,
+                #[search(skip)]
+                raw: Vec<u8>,
+            }"#,
+        )
+        .unwrap();
+        let expected = ". arg (\"ON\") . arg (\"HASH\") . arg (\"PREFIX\") . arg (2usize) . arg \
+                        (\"product:\") . arg (\"item:\") . arg (\"SCHEMA\") . arg (\"name\") . arg \
+                        (\"TEXT\") . arg (\"WEIGHT\") . arg (\"2\") . arg (\"SORTABLE\") . arg \
+                        (\"desc\") . arg (\"TEXT\") . arg (\"NOSTEM\") . arg (\"price\") . arg \
+                        (\"NUMERIC\") . arg (\"tags\") . arg (\"TAG\") . arg (\"SEPARATOR\") . arg \
+                        (\"|\") . arg (\"location\") . arg (\"GEO\") . clone ()";
+        assert!(output.contains(expected), "{}", output);
+
+        let output = expand(r#"#[search(on = "json")] struct User { age: u32 }"#).unwrap();
+        assert!(
+            output.contains(
+                ". arg (\"JSON\") . arg (\"SCHEMA\") . arg (\"$.age\") . arg (\"AS\") . arg \
+                 (\"age\") . arg (\"NUMERIC\")"
+            ),
+            "{}",
+            output
+        );
+    }
+
+    #[test]
+    fn schema_errors() {
+        let err = |input: &str| expand(input).unwrap_err().to_string();
+        let e = err("struct T(u8);");
+        assert!(e.contains("named fields"), "{}", e);
+        let e = err("struct T { #[search(skip)] a: u8 }");
+        assert!(e.contains("at least one field"), "{}", e);
+        let e = err("struct T { a: Vec<u8> }");
+        assert!(e.contains("kind of `a` can't be inferred"), "{}", e);
+        let e = err("struct T { #[search(text, tag)] a: String }");
+        assert!(e.contains("only have one kind"), "{}", e);
+        let e = err("struct T { #[search(weight = 2)] a: u32 }");
+        assert!(
+            e.contains("`weight` only applies to TEXT fields, and `a` is NUMERIC"),
+            "{}",
+            e
+        );
+        let e = err("struct T { #[search(separator = \",\")] a: String }");
+        assert!(e.contains("only applies to TAG fields"), "{}", e);
+        let e = err("struct T { #[search(tag, separator = \"ab\")] a: String }");
+        assert!(e.contains("single character"), "{}", e);
+        let e = err("struct T { #[search(weight = 0)] a: String }");
+        assert!(e.contains("has to be positive"), "{}", e);
+        let e = err("struct T { #[search(geo, sortable)] a: String }");
+        assert!(e.contains("can't be sortable"), "{}", e);
+        let e = err("struct T { #[search(fulltext)] a: String }");
+        assert!(e.contains("expected `text`"), "{}", e);
+        let e = err("#[search(on = \"set\")] struct T { a: String }");
+        assert!(e.contains("expected `hash` or `json`"), "{}", e);
+    }
+}
@@ -0,0 +1,198 @@
+use crate::namespace::apply_namespaces;
+use crate::parse::Command;
+use crate::readonly::check_readonly;
+use proc_macro2::{Delimiter, Group, TokenStream, TokenTree};
+use quote::quote;
+use syn::Expr;
+
+/// The macros whose commands get the markers of scope attributes such as `#[redis_namespace]`,
+/// with the number of top level commas before their command
+const SCOPED: &[(&str, usize)] = &[
+    ("redis", 0),
+    ("redis_async", 1),
+    ("redis_batch", 0),
+    ("redis_exec", 1),
+    ("redis_pipe", 0),
+    ("redis_retry", 2),
+    ("redis_retry_async", 2),
+    ("redis_routed", 0),
+    ("redis_sentinel", 1),
+];
+
+/// What the scope attributes around a macro call asked of its commands, passed on as markers at
+/// the start of the command, as in `redis!(@namespace("app:") @readonly GET key)`
+#[derive(Default)]
+pub(crate) struct Scope {
+    /// The namespaces to prepend to keys, from the innermost scope out
+    pub(crate) namespaces: Vec<Expr>,
+    /// Whether the commands are in a `#[redis_readonly]` function
+    pub(crate) readonly: bool,
+    /// Whether the command was written after `@no_namespace`, to send its keys as written
+    pub(crate) unscoped: bool,
+}
+
+impl Scope {
+    /// Check and rewrite a command of the scope
+    pub(crate) fn apply(&self, command: &mut Command) -> syn::Result<()> {
+        if self.readonly {
+            check_readonly(command)?;
+        }
+        if self.unscoped {
+            return Ok(());
+        }
+        apply_namespaces(command, &self.namespaces)
+    }
+
+    /// The scope of a command written after `@no_namespace`, which is only checked
+    pub(crate) fn without_namespaces(&self) -> Scope {
+        Scope {
+            namespaces: vec![],
+            readonly: self.readonly,
+            unscoped: true,
+        }
+    }
+}
+
+/// The marker of a namespace
+pub(crate) fn namespace_marker(prefix: &Expr) -> TokenStream {
+    quote!(@namespace(#prefix))
+}
+
+/// The marker of a `#[redis_readonly]` function
+pub(crate) fn readonly_marker() -> TokenStream {
+    quote!(@readonly)
+}
+
+/// Take the scope markers off the start of a macro input
+pub(crate) fn split_scope(input: TokenStream) -> syn::Result<(Scope, TokenStream)> {
+    let mut tokens: Vec<TokenTree> = input.into_iter().collect();
+    let mut scope = Scope::default();
+    loop {
+        match tokens.as_slice() {
+            [TokenTree::Punct(at), TokenTree::Ident(name), TokenTree::Group(group), ..]
+                if at.as_char() == '@'
+                    && name == "namespace"
+                    && group.delimiter() == Delimiter::Parenthesis =>
+            {
+                scope.namespaces.push(syn::parse2(group.stream())?);
+                tokens.drain(..3);
+            }
+            [TokenTree::Punct(at), TokenTree::Ident(name), ..]
+                if at.as_char() == '@' && name == "readonly" =>
+            {
+                scope.readonly = true;
+                tokens.drain(..2);
+            }
+            [TokenTree::Punct(at), TokenTree::Ident(name), ..]
+                if at.as_char() == '@' && name == "no_namespace" =>
+            {
+                scope.unscoped = true;
+                tokens.drain(..2);
+            }
+            _ => break,
+        }
+    }
+    Ok((scope, tokens.into_iter().collect()))
+}
+
+/// Add a marker to the start of the commands of every nested macro call that supports one, found
+/// by the name of the macro
+pub(crate) fn mark_macros(tokens: TokenStream, marker: &TokenStream) -> TokenStream {
+    let mut tokens: Vec<TokenTree> = tokens.into_iter().collect();
+    for index in 0..tokens.len() {
+        let TokenTree::Group(group) = &tokens[index] else {
+            continue;
+        };
+        let stream = match commas_before(&tokens, index) {
+            Some(commas) => insert_marker(group.stream(), commas, marker),
+            None => mark_macros(group.stream(), marker),
+        };
+        let mut marked = Group::new(group.delimiter(), stream);
+        marked.set_span(group.span());
+        tokens[index] = TokenTree::Group(marked);
+    }
+    tokens.into_iter().collect()
+}
+
+/// The number of commas before the command of the macro called with the group at `index`, if
+/// the group is the input of a macro that supports scopes
+fn commas_before(tokens: &[TokenTree], index: usize) -> Option<usize> {
+    match &tokens[index.checked_sub(2)?..=index] {
+        [TokenTree::Ident(name), TokenTree::Punct(bang), TokenTree::Group(_)]
+            if bang.as_char() == '!' =>
+        {
+            SCOPED
+                .iter()
+                .find(|(scoped, _)| name == scoped)
+                .map(|(_, commas)| *commas)
+        }
+        _ => None,
+    }
+}
+
+/// Insert the marker after the first `commas` top level commas of a macro input
+fn insert_marker(input: TokenStream, commas: usize, marker: &TokenStream) -> TokenStream {
+    let mut tokens: Vec<TokenTree> = input.into_iter().collect();
+    let mut seen = 0;
+    let position = tokens.iter().position(|tt| {
+        if seen == commas {
+            return true;
+        }
+        if matches!(tt, TokenTree::Punct(p) if p.as_char() == ',') {
+            seen += 1;
+        }
+        false
+    });
+    let position = position.unwrap_or(tokens.len());
+    tokens.splice(position..position, marker.clone());
+    tokens.into_iter().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mark(input: &str) -> String {
+        let marker = namespace_marker(&syn::parse_quote!("app:"));
+        mark_macros(input.parse().unwrap(), &marker).to_string()
+    }
+
+    #[test]
+    fn scope_mark() {
+        assert_eq!(
+            mark("fn f() { redis!(GET a); redis_exec!(con, GET b) }"),
+            "fn f () { redis ! (@ namespace (\"app:\") GET a) ; \
+             redis_exec ! (con , @ namespace (\"app:\") GET b) }"
+        );
+        assert_eq!(
+            mark("mod m { fn f() { vec![redis_rs_macro::redis_pipe!(atomic; INCR a)] } }"),
+            "mod m { fn f () { vec ! [redis_rs_macro :: redis_pipe ! (@ namespace (\"app:\") \
+             atomic ; INCR a)] } }"
+        );
+        assert_eq!(
+            mark("fn f() { redis_retry!(policy, con, GET a); println!(\"GET a\") }"),
+            "fn f () { redis_retry ! (policy , con , @ namespace (\"app:\") GET a) ; \
+             println ! (\"GET a\") }"
+        );
+    }
+
+    #[test]
+    fn scope_split() {
+        let (scope, rest) = split_scope(
+            "@readonly @namespace(\"b:\") @namespace(a) GET k"
+                .parse()
+                .unwrap(),
+        )
+        .unwrap();
+        assert!(scope.readonly);
+        assert_eq!(scope.namespaces.len(), 2);
+        assert_eq!(rest.to_string(), "GET k");
+        let (scope, rest) =
+            split_scope("@namespace(a) @no_namespace MODULE.CMD k".parse().unwrap()).unwrap();
+        assert!(scope.unscoped);
+        assert_eq!(rest.to_string(), "MODULE . CMD k");
+        let (scope, rest) = split_scope("@ foo".parse().unwrap()).unwrap();
+        assert!(!scope.readonly && scope.namespaces.is_empty());
+        assert_eq!(rest.to_string(), "@ foo");
+    }
+}
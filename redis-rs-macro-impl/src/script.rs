@@ -0,0 +1,150 @@
+use crate::lua;
+use proc_macro2::{Span, TokenStream};
+use quote::{format_ident, quote};
+use std::path::PathBuf;
+use syn::spanned::Spanned;
+use syn::{FnArg, ForeignItemFn, Ident, LitStr, Pat, ReturnType};
+
+/// A Lua file read from a path relative to the manifest of the calling crate
+pub(crate) struct ScriptFile {
+    /// The absolute path, passed to `include_str!` so that changes to the file trigger a rebuild
+    pub(crate) path: String,
+    pub(crate) source: String,
+    pub(crate) sha1: String,
+}
+
+impl ScriptFile {
+    /// Read the file at a path relative to `CARGO_MANIFEST_DIR`
+    pub(crate) fn read(lit: &LitStr) -> syn::Result<ScriptFile> {
+        let dir = std::env::var("CARGO_MANIFEST_DIR").unwrap_or_default();
+        let path = PathBuf::from(dir).join(lit.value());
+        let source = std::fs::read_to_string(&path).map_err(|err| {
+            let msg = format!("cannot read `{}`: {}", path.display(), err);
+            syn::Error::new(lit.span(), msg)
+        })?;
+        let sha1 = sha1_smol::Sha1::from(&source).digest().to_string();
+        Ok(ScriptFile {
+            path: path.to_string_lossy().into_owned(),
+            source,
+            sha1,
+        })
+    }
+}
+
+/// Generate a `#[redis_script("path")]` function, which calls the script with its `#[key]`
+/// parameters as keys and its other parameters as arguments, and a `<NAME>_SHA1` const
+pub(crate) fn expand_script(args: TokenStream, input: TokenStream) -> syn::Result<TokenStream> {
+    let lit: LitStr = syn::parse2(args)
+        .map_err(|err| syn::Error::new(err.span(), "expected the path of a Lua script"))?;
+    let mut item: ForeignItemFn = syn::parse2(input)?;
+    let ScriptFile { path, source, sha1 } = ScriptFile::read(&lit)?;
+    if cfg!(feature = "lua-check") {
+        lua::check(&source)
+            .map_err(|err| syn::Error::new(lit.span(), format!("{} in `{}`", err, lit.value())))?;
+    }
+    if let ReturnType::Default = item.sig.output {
+        let msg = "script functions return the reply, such as `redis::RedisResult<i64>`";
+        return Err(syn::Error::new(item.sig.span(), msg));
+    }
+    let call = Ident::new("call", Span::mixed_site());
+    let mut calls = vec![];
+    for input in &mut item.sig.inputs {
+        let FnArg::Typed(param) = input else {
+            let msg = "script functions cannot take `self`";
+            return Err(syn::Error::new(input.span(), msg));
+        };
+        let Pat::Ident(pat) = &*param.pat else {
+            let msg = "script function parameters must be plain names";
+            return Err(syn::Error::new(param.pat.span(), msg));
+        };
+        let name = &pat.ident;
+        let len = param.attrs.len();
+        param.attrs.retain(|attr| !attr.path().is_ident("key"));
+        calls.push(match param.attrs.len() < len {
+            true if cfg!(feature = "key-prefix") => {
+                quote!(#call.key(::redis_rs_macro::__private::prefix::key(&#name));)
+            }
+            true => quote!(#call.key(&#name);),
+            false => quote!(#call.arg(&#name);),
+        });
+    }
+    let con = Ident::new("con", Span::mixed_site());
+    item.sig
+        .inputs
+        .insert(0, syn::parse_quote!(#con: &mut dyn redis::ConnectionLike));
+    let const_name = format_ident!("{}_SHA1", item.sig.ident.to_string().to_uppercase());
+    let const_doc = format!("The SHA1 of `{}`", lit.value());
+    let ForeignItemFn {
+        attrs, vis, sig, ..
+    } = &item;
+    Ok(quote! {
+        #[doc = #const_doc]
+        #vis const #const_name: &str = #sha1;
+
+        #(#attrs)*
+        #vis #sig {
+            let mut #call =
+                ::redis_rs_macro::ScriptCmd::new(::core::include_str!(#path), #const_name);
+            #(#calls)*
+            #call.invoke(#con)
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A script that parses, so that the tests pass with the `lua-check` feature too
+    const SCRIPT: &str = "../tests/scripts/incr_by.lua";
+
+    fn expand(path: &str, input: &str) -> syn::Result<String> {
+        let args = format!("{:?}", path).parse().unwrap();
+        expand_script(args, input.parse().unwrap()).map(|output| output.to_string())
+    }
+
+    #[test]
+    fn script_expand() {
+        let output = expand(
+            SCRIPT,
+            "pub fn incr_by(#[key] counter: &str, amount: i64) -> redis::RedisResult<i64>;",
+        )
+        .unwrap();
+        assert!(
+            output.contains("pub const INCR_BY_SHA1 : & str = \""),
+            "{}",
+            output
+        );
+        assert!(!output.contains("# [key]"), "{}", output);
+        assert!(
+            output.contains("fn incr_by (con : & mut dyn redis :: ConnectionLike , counter"),
+            "{}",
+            output
+        );
+        assert!(output.contains("call . arg (& amount)"), "{}", output);
+        assert!(output.contains("ScriptCmd :: new"), "{}", output);
+        assert!(output.contains("include_str ! (\""), "{}", output);
+    }
+
+    #[test]
+    fn script_errors() {
+        let err = |path: &str, input: &str| expand(path, input).unwrap_err().to_string();
+        let path = SCRIPT;
+        let e = err("missing.lua", "fn f() -> u8;");
+        assert!(e.contains("cannot read"), "{}", e);
+        let e = err(path, "fn f();");
+        assert!(e.contains("return the reply"), "{}", e);
+        let e = err(path, "fn f(&self) -> u8;");
+        assert!(e.contains("cannot take `self`"), "{}", e);
+        let e = err(path, "fn f((a, b): (u8, u8)) -> u8;");
+        assert!(e.contains("plain names"), "{}", e);
+        let e = expand_script(
+            quote!(1),
+            quote!(
+                fn f() -> u8;
+            ),
+        )
+        .unwrap_err();
+        assert!(e.to_string().contains("expected the path"), "{}", e);
+    }
+}
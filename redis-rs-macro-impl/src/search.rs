@@ -0,0 +1,742 @@
+use crate::expand::expand_command;
+use crate::parse::{parse_command, Arg, Piece};
+use crate::typed::split_return;
+use proc_macro2::{Span, TokenStream};
+use quote::quote;
+use syn::{Expr, Ident, LitStr};
+
+/// How the arguments after an option are checked
+#[derive(Clone, Copy)]
+enum Shape {
+    /// No arguments
+    Flag,
+    /// A fixed number of arguments
+    Fixed(usize),
+    /// A fixed number of integers
+    Int(usize),
+    /// A count, followed by that many arguments
+    Counted,
+    /// `SORTBY field [ASC | DESC] [WITHCOUNT]` of `FT.SEARCH`
+    SortBy,
+    /// Sub-options, each given at most once, such as the `FIELDS` and `TAGS` of `HIGHLIGHT`
+    Sub(&'static [(&'static str, Shape)]),
+    /// `LOAD *`, or a count of fields
+    Load,
+    /// `SORTBY nargs properties... [MAX n]` of `FT.AGGREGATE`
+    Sort,
+    /// `REDUCE function nargs args... [AS name]`
+    Reduce,
+    /// `APPLY expression AS name`
+    Apply,
+}
+
+/// An option of a search command
+struct Opt {
+    name: &'static str,
+    shape: Shape,
+    /// Options can't come before options of a higher rank
+    rank: u8,
+    /// Whether the option can be given more than once
+    repeat: bool,
+}
+
+const fn opt(name: &'static str, shape: Shape, rank: u8, repeat: bool) -> Opt {
+    Opt {
+        name,
+        shape,
+        rank,
+        repeat,
+    }
+}
+
+/// The options of `FT.SEARCH`, in the order they have to be written
+const SEARCH: &[Opt] = &[
+    opt("NOCONTENT", Shape::Flag, 0, false),
+    opt("VERBATIM", Shape::Flag, 1, false),
+    opt("NOSTOPWORDS", Shape::Flag, 2, false),
+    opt("WITHSCORES", Shape::Flag, 3, false),
+    opt("WITHPAYLOADS", Shape::Flag, 4, false),
+    opt("WITHSORTKEYS", Shape::Flag, 5, false),
+    opt("FILTER", Shape::Fixed(3), 6, true),
+    opt("GEOFILTER", Shape::Fixed(5), 7, true),
+    opt("INKEYS", Shape::Counted, 8, false),
+    opt("INFIELDS", Shape::Counted, 9, false),
+    opt("RETURN", Shape::Counted, 10, false),
+    opt(
+        "SUMMARIZE",
+        Shape::Sub(&[
+            ("FIELDS", Shape::Counted),
+            ("FRAGS", Shape::Int(1)),
+            ("LEN", Shape::Int(1)),
+            ("SEPARATOR", Shape::Fixed(1)),
+        ]),
+        11,
+        false,
+    ),
+    opt(
+        "HIGHLIGHT",
+        Shape::Sub(&[("FIELDS", Shape::Counted), ("TAGS", Shape::Fixed(2))]),
+        12,
+        false,
+    ),
+    opt("SLOP", Shape::Int(1), 13, false),
+    opt("TIMEOUT", Shape::Int(1), 14, false),
+    opt("INORDER", Shape::Flag, 15, false),
+    opt("LANGUAGE", Shape::Fixed(1), 16, false),
+    opt("EXPANDER", Shape::Fixed(1), 17, false),
+    opt("SCORER", Shape::Fixed(1), 18, false),
+    opt("EXPLAINSCORE", Shape::Flag, 19, false),
+    opt("PAYLOAD", Shape::Fixed(1), 20, false),
+    opt("SORTBY", Shape::SortBy, 21, false),
+    opt("LIMIT", Shape::Int(2), 22, false),
+    opt("DIALECT", Shape::Int(1), 23, false),
+];
+
+/// The options of `FT.AGGREGATE`: those of the query, then the steps of the pipeline in any
+/// order, then those of the reply
+const AGGREGATE: &[Opt] = &[
+    opt("VERBATIM", Shape::Flag, 0, false),
+    opt("ADDSCORES", Shape::Flag, 0, false),
+    opt("SCORER", Shape::Fixed(1), 0, false),
+    opt("LOAD", Shape::Load, 0, false),
+    opt("TIMEOUT", Shape::Int(1), 0, false),
+    opt("GROUPBY", Shape::Counted, 1, true),
+    opt("REDUCE", Shape::Reduce, 1, true),
+    opt("SORTBY", Shape::Sort, 1, true),
+    opt("APPLY", Shape::Apply, 1, true),
+    opt("LIMIT", Shape::Int(2), 1, true),
+    opt("FILTER", Shape::Fixed(1), 1, true),
+    opt(
+        "WITHCURSOR",
+        Shape::Sub(&[("COUNT", Shape::Int(1)), ("MAXIDLE", Shape::Int(1))]),
+        2,
+        false,
+    ),
+    opt("DIALECT", Shape::Int(1), 2, false),
+];
+
+/// The options of `FT.SEARCH` that change which items the reply has for each document
+const LAYOUT: &[&str] = &["NOCONTENT", "WITHSCORES", "WITHPAYLOADS", "WITHSORTKEYS"];
+
+/// Generate a `redis_search!` invocation: an `FT.SEARCH` or `FT.AGGREGATE` command whose query and
+/// options are checked, whose `$name` parameters are bound from the variables of the same name,
+/// and whose reply is read as results of the annotated type
+pub(crate) fn expand_search(input: TokenStream) -> syn::Result<TokenStream> {
+    let (command, ty) = split_return(input)?;
+    let Some(ty) = ty else {
+        let msg = "expected the type of the results after the command, e.g. `-> Product`";
+        return Err(syn::Error::new(Span::call_site(), msg));
+    };
+    let mut command = parse_command(command)?;
+    let name = command.args[0].word().map(|name| name.to_ascii_uppercase());
+    let (name, options) = match name.as_deref() {
+        Some("FT.SEARCH") => ("FT.SEARCH", SEARCH),
+        Some("FT.AGGREGATE") => ("FT.AGGREGATE", AGGREGATE),
+        _ => {
+            let msg = "expected `FT.SEARCH` or `FT.AGGREGATE`";
+            return Err(syn::Error::new(command.args[0].span, msg));
+        }
+    };
+    if command.args.len() < 3 {
+        let msg = format!("expected the index and the query after `{}`", name);
+        return Err(syn::Error::new(command.args[0].span, msg));
+    }
+    let query = match command.args[2].pieces.as_slice() {
+        [Piece::Str(query)] => query.clone(),
+        _ => {
+            let msg = "the query has to be a string literal; values are passed as `$name` \
+                       parameters, which are bound from the variables of the same name";
+            return Err(syn::Error::new(command.args[2].span, msg));
+        }
+    };
+    check_query(&query)?;
+    let params = parameters(&query)?;
+    let written = check_options(name, options, &command.args[3..])?;
+
+    let dialect = written.iter().find(|(option, _)| *option == "DIALECT");
+    if let Some((_, index)) = dialect.filter(|(_, index)| command.args[3 + index].word().is_some())
+    {
+        let arg = &command.args[3 + index + 1];
+        let version = arg.word().and_then(|word| word.parse::<u64>().ok());
+        if !params.is_empty() && version.is_some_and(|version| version < 2) {
+            let msg = "parameters need `DIALECT 2` or later";
+            return Err(syn::Error::new(arg.span, msg));
+        }
+    }
+    if !params.is_empty() {
+        let span = query.span();
+        let text = |text: &str| Arg {
+            pieces: vec![Piece::Text(text.to_string())],
+            span,
+        };
+        let mut args = vec![text("PARAMS"), text(&(params.len() * 2).to_string())];
+        for param in &params {
+            args.push(Arg {
+                pieces: vec![Piece::Str(LitStr::new(&param.to_string(), span))],
+                span,
+            });
+            let value: Expr = syn::parse_quote!(&#param);
+            args.push(Arg {
+                pieces: vec![Piece::Expr(Box::new(value))],
+                span,
+            });
+        }
+        let at = match dialect {
+            Some((_, index)) => 3 + index,
+            None => {
+                args.extend([text("DIALECT"), text("2")]);
+                command.args.len()
+            }
+        };
+        command.args.splice(at..at, args);
+    }
+
+    let cmd = expand_command(&command)?;
+    if name == "FT.AGGREGATE" {
+        return Ok(quote! {
+            ::redis_rs_macro::TypedCmd::<::redis_rs_macro::AggregateResults<#ty>>::new(#cmd)
+        });
+    }
+    let has = |option: &str| written.iter().any(|(written, _)| *written == option);
+    if has("EXPLAINSCORE") && !has("WITHSCORES") {
+        let msg = "`EXPLAINSCORE` needs `WITHSCORES`";
+        return Err(syn::Error::new(command.args[0].span, msg));
+    }
+    let content = !has("NOCONTENT");
+    let scores = has("WITHSCORES");
+    let payloads = has("WITHPAYLOADS");
+    let sort_keys = has("WITHSORTKEYS");
+    Ok(quote! {
+        ::redis_rs_macro::SearchCmd::<#ty>::new(
+            #cmd,
+            ::redis_rs_macro::__private::search::Layout {
+                content: #content,
+                scores: #scores,
+                payloads: #payloads,
+                sort_keys: #sort_keys,
+            },
+        )
+    })
+}
+
+/// Check that the parentheses, brackets, braces and quotes of a query are balanced, and that
+/// each `@` is followed by a field name and `:`
+fn check_query(query: &LitStr) -> syn::Result<()> {
+    let text = query.value();
+    let error = |reason: &str| {
+        let msg = format!("invalid query `{}`: {}", text, reason);
+        Err(syn::Error::new(query.span(), msg))
+    };
+    let mut open = vec![];
+    let mut quoted = false;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            _ if quoted => {}
+            '(' | '[' | '{' => open.push(c),
+            ')' | ']' | '}' => {
+                let expected = match c {
+                    ')' => '(',
+                    ']' => '[',
+                    _ => '{',
+                };
+                if open.pop() != Some(expected) {
+                    return error(&format!("unbalanced `{}`", c));
+                }
+            }
+            '@' => {
+                let mut field = String::new();
+                while let Some(&c) = chars.peek() {
+                    if !(c.is_alphanumeric() || "_.$".contains(c)) {
+                        break;
+                    }
+                    field.push(c);
+                    chars.next();
+                }
+                if field.is_empty() || chars.next() != Some(':') {
+                    return error("expected a field name and `:` after `@`");
+                }
+            }
+            _ => {}
+        }
+    }
+    if quoted {
+        return error("unclosed `\"`");
+    }
+    if let Some(c) = open.pop() {
+        return error(&format!("unclosed `{}`", c));
+    }
+    Ok(())
+}
+
+/// The names of the `$name` parameters of a query, in the order they are first used
+fn parameters(query: &LitStr) -> syn::Result<Vec<Ident>> {
+    let text = query.value();
+    let mut params: Vec<Ident> = vec![];
+    let mut rest = text.as_str();
+    while let Some(at) = rest.find('$') {
+        rest = &rest[at + 1..];
+        let len = rest
+            .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+            .unwrap_or(rest.len());
+        let name = &rest[..len];
+        rest = &rest[len..];
+        if !name.starts_with(|c: char| c.is_alphabetic() || c == '_') {
+            continue;
+        }
+        let Ok(mut param) = syn::parse_str::<Ident>(name) else {
+            let msg = format!("the parameter `${}` isn't the name of a variable", name);
+            return Err(syn::Error::new(query.span(), msg));
+        };
+        param.set_span(query.span());
+        if !params.contains(&param) {
+            params.push(param);
+        }
+    }
+    Ok(params)
+}
+
+/// Check the options of a search command, returning those that were written with their index.
+/// Checking stops at a spread, optional or conditional argument that hides what follows it.
+fn check_options(
+    name: &str,
+    options: &'static [Opt],
+    args: &[Arg],
+) -> syn::Result<Vec<(&'static str, usize)>> {
+    let mut written: Vec<(&'static str, usize)> = vec![];
+    let mut i = 0;
+    while i < args.len() {
+        let arg = &args[i];
+        let word = match arg.pieces.as_slice() {
+            // An option written before an optional argument, e.g. `LANGUAGE {language?}`
+            [Piece::Optional {
+                keyword: Some(keyword),
+                ..
+            }] => keyword.clone(),
+            pieces if pieces.iter().any(|piece| piece.standalone_kind().is_some()) => {
+                return hidden(name, &args[i..], written);
+            }
+            _ => match arg.word() {
+                Some(word) => word,
+                None => {
+                    let msg = format!(
+                        "expected an option of `{}`; options are written as words, so that they \
+                         can be checked",
+                        name
+                    );
+                    return Err(syn::Error::new(arg.span, msg));
+                }
+            },
+        };
+        let upper = word.to_ascii_uppercase();
+        if upper == "PARAMS" {
+            let msg = "parameters are bound from the `$name`s of the query, so `PARAMS` isn't \
+                       written";
+            return Err(syn::Error::new(arg.span, msg));
+        }
+        let Some(option) = options.iter().find(|option| option.name == upper) else {
+            let msg = format!("unknown `{}` option `{}`", name, word);
+            return Err(syn::Error::new(arg.span, msg));
+        };
+        if !option.repeat && written.iter().any(|(written, _)| *written == option.name) {
+            let msg = format!("`{}` is given twice", option.name);
+            return Err(syn::Error::new(arg.span, msg));
+        }
+        if let Some((last, _)) = written.last() {
+            let rank = |name| {
+                options
+                    .iter()
+                    .find(|option| option.name == name)
+                    .unwrap()
+                    .rank
+            };
+            if option.rank < rank(*last) {
+                let msg = format!("`{}` has to come before `{}`", option.name, last);
+                return Err(syn::Error::new(arg.span, msg));
+            }
+        }
+        if option.name == "REDUCE"
+            && !matches!(written.last(), Some((last, _)) if ["GROUPBY", "REDUCE"].contains(last))
+        {
+            let msg = "`REDUCE` has to follow `GROUPBY`";
+            return Err(syn::Error::new(arg.span, msg));
+        }
+        written.push((option.name, i));
+        if let [Piece::Optional { .. }] = arg.pieces.as_slice() {
+            if !matches!(option.shape, Shape::Fixed(1) | Shape::Int(1)) {
+                let msg = format!("`{}` takes more than one argument", option.name);
+                return Err(syn::Error::new(arg.span, msg));
+            }
+            i += 1;
+            continue;
+        }
+        match check_shape(option.name, option.shape, arg.span, args, i + 1)? {
+            Some(next) => i = next,
+            None => return hidden(name, &args[i + 1..], written),
+        }
+    }
+    Ok(written)
+}
+
+/// Stop checking at arguments that can't be checked, which can't hold options that change the
+/// layout of the reply of `FT.SEARCH`
+fn hidden(
+    name: &str,
+    args: &[Arg],
+    written: Vec<(&'static str, usize)>,
+) -> syn::Result<Vec<(&'static str, usize)>> {
+    if name == "FT.SEARCH" {
+        if let Some((option, span)) = layout_option(args) {
+            let msg = format!(
+                "`{}` changes the layout of the reply, so it has to be written before any spread, \
+                 optional or conditional argument",
+                option
+            );
+            return Err(syn::Error::new(span, msg));
+        }
+    }
+    Ok(written)
+}
+
+fn layout_option(args: &[Arg]) -> Option<(String, Span)> {
+    args.iter().find_map(|arg| {
+        if let Some(word) = arg.word() {
+            let upper = word.to_ascii_uppercase();
+            return LAYOUT
+                .contains(&upper.as_str())
+                .then_some((upper, arg.span));
+        }
+        arg.pieces.iter().find_map(|piece| match piece {
+            Piece::Conditional { args, .. } => layout_option(args),
+            _ => None,
+        })
+    })
+}
+
+/// Check the arguments of an option, which start at `i`, returning the index after them, or
+/// `None` when they can't be checked
+fn check_shape(
+    option: &str,
+    shape: Shape,
+    span: Span,
+    args: &[Arg],
+    i: usize,
+) -> syn::Result<Option<usize>> {
+    let word = |i: usize| {
+        args.get(i)
+            .and_then(Arg::word)
+            .map(|word| word.to_ascii_uppercase())
+    };
+    let next = match shape {
+        Shape::Flag => i,
+        Shape::Fixed(n) => match take(option, span, args, i, n)? {
+            Some(_) => i + n,
+            None => return Ok(None),
+        },
+        Shape::Int(n) => {
+            let Some(taken) = take(option, span, args, i, n)? else {
+                return Ok(None);
+            };
+            for arg in taken {
+                integer(option, arg)?;
+            }
+            i + n
+        }
+        Shape::Counted => match count(option, span, args, i)? {
+            Some(next) => next,
+            None => return Ok(None),
+        },
+        Shape::SortBy => {
+            if take(option, span, args, i, 1)?.is_none() {
+                return Ok(None);
+            }
+            let mut next = i + 1;
+            if matches!(word(next).as_deref(), Some("ASC" | "DESC")) {
+                next += 1;
+            }
+            if word(next).as_deref() == Some("WITHCOUNT") {
+                next += 1;
+            }
+            next
+        }
+        Shape::Sub(subs) => {
+            let mut next = i;
+            let mut seen = vec![];
+            while let Some((sub, shape)) = word(next)
+                .and_then(|word| subs.iter().find(|(sub, _)| *sub == word))
+                .copied()
+            {
+                if seen.contains(&sub) {
+                    let msg = format!("`{}` is given twice in `{}`", sub, option);
+                    return Err(syn::Error::new(args[next].span, msg));
+                }
+                seen.push(sub);
+                let span = args[next].span;
+                match check_shape(&format!("{} {}", option, sub), shape, span, args, next + 1)? {
+                    Some(after) => next = after,
+                    None => return Ok(None),
+                }
+            }
+            next
+        }
+        Shape::Load if word(i).as_deref() == Some("*") => i + 1,
+        Shape::Load => match count(option, span, args, i)? {
+            Some(next) => next,
+            None => return Ok(None),
+        },
+        Shape::Sort => {
+            let Some(mut next) = count(option, span, args, i)? else {
+                return Ok(None);
+            };
+            if word(next).as_deref() == Some("MAX") {
+                let span = args[next].span;
+                match check_shape("SORTBY MAX", Shape::Int(1), span, args, next + 1)? {
+                    Some(after) => next = after,
+                    None => return Ok(None),
+                }
+            }
+            next
+        }
+        Shape::Reduce => {
+            if take(option, span, args, i, 1)?.is_none() {
+                return Ok(None);
+            }
+            let Some(mut next) = count(option, span, args, i + 1)? else {
+                return Ok(None);
+            };
+            if word(next).as_deref() == Some("AS") {
+                match take("REDUCE AS", args[next].span, args, next + 1, 1)? {
+                    Some(_) => next += 2,
+                    None => return Ok(None),
+                }
+            }
+            next
+        }
+        Shape::Apply => {
+            if take(option, span, args, i, 1)?.is_none() {
+                return Ok(None);
+            }
+            if word(i + 1).as_deref() != Some("AS") || args.get(i + 2).is_none() {
+                let msg = "`APPLY` expects `AS` and a name after the expression";
+                return Err(syn::Error::new(span, msg));
+            }
+            i + 3
+        }
+    };
+    Ok(Some(next))
+}
+
+/// Take the `n` arguments of an option, or `None` when one of them can't be checked
+fn take<'a>(
+    option: &str,
+    span: Span,
+    args: &'a [Arg],
+    i: usize,
+    n: usize,
+) -> syn::Result<Option<&'a [Arg]>> {
+    let Some(taken) = args.get(i..i + n) else {
+        let msg = match n {
+            1 => format!("`{}` expects an argument", option),
+            n => format!("`{}` expects {} arguments", option, n),
+        };
+        return Err(syn::Error::new(span, msg));
+    };
+    let standalone = taken.iter().any(|arg| {
+        arg.pieces
+            .iter()
+            .any(|piece| piece.standalone_kind().is_some())
+    });
+    Ok((!standalone).then_some(taken))
+}
+
+/// Check a count and the arguments it counts, returning the index after them, or `None` when the
+/// count isn't a literal
+fn count(option: &str, span: Span, args: &[Arg], i: usize) -> syn::Result<Option<usize>> {
+    let Some(taken) = take(option, span, args, i, 1)? else {
+        return Ok(None);
+    };
+    let Some(n) = integer(option, &taken[0])? else {
+        return Ok(None);
+    };
+    let n = n as usize;
+    let span = taken[0].span;
+    match take(&format!("{} {}", option, n), span, args, i + 1, n)? {
+        Some(_) => Ok(Some(i + 1 + n)),
+        None => Ok(None),
+    }
+}
+
+/// Check that a literal argument is an integer, returning it
+fn integer(option: &str, arg: &Arg) -> syn::Result<Option<u64>> {
+    let Some(word) = arg.word() else {
+        return Ok(None);
+    };
+    match word.parse() {
+        Ok(n) => Ok(Some(n)),
+        Err(_) => {
+            let msg = format!("`{}` expects an integer, found `{}`", option, word);
+            Err(syn::Error::new(arg.span, msg))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expand(input: &str) -> syn::Result<String> {
+        expand_search(input.parse().unwrap()).map(|out| out.to_string())
+    }
+
+    fn err(input: &str) -> String {
+        expand(input).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn search_expand() {
+        let output = expand(
+            r#"FT.SEARCH idx:products "@name:$name @price:[$min +inf]" WITHSCORES RETURN 2 name price SORTBY price DESC LIMIT 0 10 -> Product"#,
+        )
+        .unwrap();
+        assert!(
+            output.starts_with(":: redis_rs_macro :: SearchCmd :: < Product > :: new ("),
+            "{}",
+            output
+        );
+        assert!(
+            output
+                .contains("content : true , scores : true , payloads : false , sort_keys : false"),
+            "{}",
+            output
+        );
+        assert!(
+            output.contains(
+                ". arg (\"PARAMS\") ; cmd . arg (\"4\") ; cmd . arg (\"name\") ; cmd . arg (& \
+                 name) ; cmd . arg (\"min\") ; cmd . arg (& min) ; cmd . arg (\"DIALECT\") ; cmd \
+                 . arg (\"2\")"
+            ),
+            "{}",
+            output
+        );
+
+        let output =
+            expand(r#"FT.SEARCH idx "@tags:{$tag}" NOCONTENT LIMIT 0 5 DIALECT 3 -> ()"#).unwrap();
+        assert!(output.contains("content : false"), "{}", output);
+        assert!(
+            output.contains(
+                ". arg (\"PARAMS\") ; cmd . arg (\"2\") ; cmd . arg (\"tag\") ; cmd . arg (& tag) \
+                 ; cmd . arg (\"DIALECT\") ; cmd . arg (\"3\")"
+            ),
+            "{}",
+            output
+        );
+
+        let output = expand(
+            r#"FT.AGGREGATE idx "*" LOAD 1 price GROUPBY 1 @brand REDUCE SUM 1 @price AS total SORTBY 2 @total DESC MAX 10 APPLY "@total * 2" AS double WITHCURSOR COUNT 100 -> Brand"#,
+        )
+        .unwrap();
+        assert!(
+            output.starts_with(
+                ":: redis_rs_macro :: TypedCmd :: < :: redis_rs_macro :: AggregateResults < Brand \
+                 >> :: new ("
+            ),
+            "{}",
+            output
+        );
+        assert!(!output.contains("PARAMS"), "{}", output);
+
+        // Arguments after a spread can't be checked
+        expand(r#"FT.SEARCH idx "*" RETURN {..fields} LIMIT x y -> Product"#).unwrap();
+        expand(r#"FT.SEARCH idx "*" LANGUAGE {language?} LIMIT 0 10 -> Product"#).unwrap();
+        let e = err(r#"FT.SEARCH idx "*" RETURN {fields?} -> Product"#);
+        assert!(e.contains("`RETURN` takes more than one argument"), "{}", e);
+    }
+
+    #[test]
+    fn search_errors() {
+        let e = err(r#"FT.SEARCH idx "*""#);
+        assert!(e.contains("expected the type of the results"), "{}", e);
+        let e = err(r#"FT.INFO idx -> Info"#);
+        assert!(
+            e.contains("expected `FT.SEARCH` or `FT.AGGREGATE`"),
+            "{}",
+            e
+        );
+        let e = err(r#"FT.SEARCH idx -> Product"#);
+        assert!(
+            e.contains("expected the index and the query after `FT.SEARCH`"),
+            "{}",
+            e
+        );
+        let e = err(r#"FT.SEARCH idx {query} -> Product"#);
+        assert!(e.contains("the query has to be a string literal"), "{}", e);
+        let e = err(r#"FT.SEARCH idx "@name:(a|b" -> Product"#);
+        assert!(
+            e.contains("invalid query `@name:(a|b`: unclosed `(`"),
+            "{}",
+            e
+        );
+        let e = err(r#"FT.SEARCH idx "@price:[0 10)" -> Product"#);
+        assert!(e.contains("unbalanced `)`"), "{}", e);
+        let e = err(r#"FT.SEARCH idx "@name hello" -> Product"#);
+        assert!(e.contains("a field name and `:` after `@`"), "{}", e);
+        let e = err(r#"FT.SEARCH idx "\"hello" -> Product"#);
+        assert!(e.contains("unclosed `\"`"), "{}", e);
+        let e = err(r#"FT.SEARCH idx "@name:$fn" -> Product"#);
+        assert!(e.contains("`$fn` isn't the name of a variable"), "{}", e);
+        let e = err(r#"FT.SEARCH idx "*" LIMIT 0 10 SORTBY price -> Product"#);
+        assert!(e.contains("`SORTBY` has to come before `LIMIT`"), "{}", e);
+        let e = err(r#"FT.SEARCH idx "*" HIGHLIGHT RETURN 1 name -> Product"#);
+        assert!(
+            e.contains("`RETURN` has to come before `HIGHLIGHT`"),
+            "{}",
+            e
+        );
+        let e = err(r#"FT.SEARCH idx "*" LIMIT 0 -> Product"#);
+        assert!(e.contains("`LIMIT` expects 2 arguments"), "{}", e);
+        let e = err(r#"FT.SEARCH idx "*" LIMIT 0 ten -> Product"#);
+        assert!(
+            e.contains("`LIMIT` expects an integer, found `ten`"),
+            "{}",
+            e
+        );
+        let e = err(r#"FT.SEARCH idx "*" RETURN 3 name price -> Product"#);
+        assert!(e.contains("`RETURN 3` expects 3 arguments"), "{}", e);
+        let e = err(r#"FT.SEARCH idx "*" RETURN name -> Product"#);
+        assert!(
+            e.contains("`RETURN` expects an integer, found `name`"),
+            "{}",
+            e
+        );
+        let e = err(r#"FT.SEARCH idx "*" HIGHLIGHT TAGS "<b>" -> Product"#);
+        assert!(e.contains("`HIGHLIGHT TAGS` expects 2 arguments"), "{}", e);
+        let e = err(r#"FT.SEARCH idx "*" NOCONTENT NOCONTENT -> ()"#);
+        assert!(e.contains("`NOCONTENT` is given twice"), "{}", e);
+        let e = err(r#"FT.SEARCH idx "*" LIMITS 0 10 -> Product"#);
+        assert!(e.contains("unknown `FT.SEARCH` option `LIMITS`"), "{}", e);
+        let e = err(r#"FT.SEARCH idx "*" {option} -> Product"#);
+        assert!(e.contains("options are written as words"), "{}", e);
+        let e = err(r#"FT.SEARCH idx "@a:$a" PARAMS 2 a b -> Product"#);
+        assert!(e.contains("`PARAMS` isn't written"), "{}", e);
+        let e = err(r#"FT.SEARCH idx "@a:$a" DIALECT 1 -> Product"#);
+        assert!(e.contains("parameters need `DIALECT 2` or later"), "{}", e);
+        let e = err(r#"FT.SEARCH idx "*" EXPLAINSCORE -> Product"#);
+        assert!(e.contains("`EXPLAINSCORE` needs `WITHSCORES`"), "{}", e);
+        let e = err(r#"FT.SEARCH idx "*" ?[{scored} => WITHSCORES] -> Product"#);
+        assert!(
+            e.contains("`WITHSCORES` changes the layout of the reply"),
+            "{}",
+            e
+        );
+        let e = err(r#"FT.AGGREGATE idx "*" REDUCE COUNT 0 -> Row"#);
+        assert!(e.contains("`REDUCE` has to follow `GROUPBY`"), "{}", e);
+        let e = err(r#"FT.AGGREGATE idx "*" GROUPBY 1 @a LOAD * -> Row"#);
+        assert!(e.contains("`LOAD` has to come before `GROUPBY`"), "{}", e);
+        let e = err(r#"FT.AGGREGATE idx "*" APPLY "@a * 2" -> Row"#);
+        assert!(e.contains("`APPLY` expects `AS` and a name"), "{}", e);
+    }
+}
@@ -0,0 +1,55 @@
+use crate::exec::Exec;
+use crate::expand::expand_command;
+use crate::parse::parse_command;
+use crate::typed::split_return;
+use proc_macro2::{Span, TokenStream};
+use quote::quote;
+use syn::Ident;
+
+/// Generate the query of a `redis_sentinel!` invocation, which builds the command and sends it to
+/// the current master of the `redis_rs_macro::Sentinel`
+pub(crate) fn expand_sentinel(input: TokenStream) -> syn::Result<TokenStream> {
+    let Exec {
+        con: sentinel,
+        command,
+    } = syn::parse2(input)?;
+    let (command, ty) = split_return(command)?;
+    let cmd = expand_command(&parse_command(command)?)?;
+    let ty = ty.map(|ty| quote!(::<#ty>));
+    let cmd_var = Ident::new("cmd", Span::mixed_site());
+    Ok(quote! {
+        {
+            let #cmd_var = #cmd;
+            ::redis_rs_macro::Sentinel::query #ty (#sentinel, &#cmd_var)
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sentinel_expand() {
+        let output = expand_sentinel("&mut sentinel, INCR counter".parse().unwrap())
+            .unwrap()
+            .to_string();
+        assert!(
+            output.ends_with("redis_rs_macro :: Sentinel :: query (& mut sentinel , & cmd) }"),
+            "{}",
+            output
+        );
+        let output = expand_sentinel("sentinel, GET k -> String".parse().unwrap())
+            .unwrap()
+            .to_string();
+        assert!(
+            output.contains("Sentinel :: query :: < String > (sentinel , & cmd)"),
+            "{}",
+            output
+        );
+        let err = expand_sentinel("sentinel GET k".parse().unwrap())
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("expected `,`"), "{}", err);
+    }
+}
@@ -0,0 +1,184 @@
+use crate::keys::{key_roles, KeyRole};
+use crate::parse::{Arg, Command, Piece};
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::Lit;
+
+/// The number of hash slots of a cluster
+const SLOTS: u16 = 16384;
+
+/// The CRC16 (XMODEM) of `bytes`, which cluster hash slots are computed from
+const fn crc16(bytes: &[u8]) -> u16 {
+    let mut crc: u16 = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        crc ^= (bytes[i] as u16) << 8;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x1021
+            } else {
+                crc << 1
+            };
+            bit += 1;
+        }
+        i += 1;
+    }
+    crc
+}
+
+/// The part of a key that is hashed: the text between the first `{` and the next `}` when it isn't
+/// empty, or else the whole key
+fn hash_tag(key: &[u8]) -> &[u8] {
+    let Some(open) = key.iter().position(|b| *b == b'{') else {
+        return key;
+    };
+    match key[open + 1..].iter().position(|b| *b == b'}') {
+        Some(len) if len > 0 => &key[open + 1..open + 1 + len],
+        _ => key,
+    }
+}
+
+/// The cluster hash slot of a key
+pub(crate) fn key_slot(key: &[u8]) -> u16 {
+    crc16(hash_tag(key)) % SLOTS
+}
+
+/// The hash slot of a key argument, if it is known at compile time: when the whole key is literal,
+/// or when its literal start holds a complete hash tag. Also returns the known text of the key.
+pub(crate) fn literal_slot(arg: &Arg) -> Option<(u16, String)> {
+    let mut known = vec![];
+    let mut complete = true;
+    for piece in &arg.pieces {
+        match piece {
+            Piece::Text(text) => known.extend_from_slice(text.as_bytes()),
+            Piece::Str(lit) => known.extend(lit.value().into_bytes()),
+            Piece::ByteStr(lit) => known.extend(lit.value()),
+            _ => {
+                complete = false;
+                break;
+            }
+        }
+    }
+    let tagged = hash_tag(&known).len() < known.len();
+    (complete || tagged).then(|| {
+        let mut text = String::from_utf8_lossy(&known).into_owned();
+        if !complete {
+            text.push_str("..");
+        }
+        (key_slot(&known), text)
+    })
+}
+
+/// Check that the keys of a command that are known at compile time all map to the same hash slot,
+/// since a cluster rejects commands whose keys don't with `CROSSSLOT`
+pub(crate) fn check_same_slot(command: &Command) -> syn::Result<()> {
+    let roles = key_roles(command);
+    let keys = command
+        .args
+        .iter()
+        .zip(roles)
+        .filter(|(_, role)| *role == KeyRole::Key)
+        .filter_map(|(arg, _)| Some((arg, literal_slot(arg)?)));
+    let mut first: Option<(u16, String)> = None;
+    for (arg, (slot, key)) in keys {
+        match &first {
+            None => first = Some((slot, key)),
+            Some((first_slot, first_key)) if *first_slot != slot => {
+                let msg = format!(
+                    "the keys `{}` and `{}` map to the hash slots {} and {}, which a cluster \
+                     rejects with CROSSSLOT; give them the same hash tag, as in `\"{{user}}:a\"` \
+                     and `\"{{user}}:b\"`, so that only the tag is hashed",
+                    first_key, key, first_slot, slot
+                );
+                return Err(syn::Error::new(arg.span, msg));
+            }
+            Some(_) => {}
+        }
+    }
+    Ok(())
+}
+
+/// Generate a `redis_keyslot!` invocation, the hash slot of the key as a `u16` literal
+pub(crate) fn expand_keyslot(input: TokenStream) -> syn::Result<TokenStream> {
+    let key = match syn::parse2(input)? {
+        Lit::Str(lit) => lit.value().into_bytes(),
+        Lit::ByteStr(lit) => lit.value(),
+        lit => {
+            let msg = "expected the key as a string or byte string literal";
+            return Err(syn::Error::new(lit.span(), msg));
+        }
+    };
+    let slot = key_slot(&key);
+    Ok(quote!(#slot))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slot_crc16() {
+        // The check value of CRC16/XMODEM
+        assert_eq!(crc16(b"123456789"), 0x31c3);
+        assert_eq!(key_slot(b"foo"), 12182);
+        assert_eq!(key_slot(b""), 0);
+    }
+
+    #[test]
+    fn slot_hash_tags() {
+        assert_eq!(
+            key_slot(b"{user1000}.following"),
+            key_slot(b"{user1000}.followers")
+        );
+        assert_eq!(key_slot(b"user:{42}:name"), key_slot(b"42"));
+        // Only the first tag counts, and an empty tag hashes the whole key
+        assert_eq!(key_slot(b"{a}{b}"), key_slot(b"a"));
+        assert_eq!(key_slot(b"{}x"), crc16(b"{}x") % SLOTS);
+        assert_eq!(key_slot(b"a{b"), crc16(b"a{b") % SLOTS);
+        assert_eq!(key_slot(b"{{a}}"), key_slot(b"{a"));
+    }
+
+    fn check(input: &str) -> syn::Result<()> {
+        check_same_slot(&crate::parse::parse_command(input.parse().unwrap()).unwrap())
+    }
+
+    #[test]
+    fn slot_same_slot() {
+        assert!(check("MSET \"{user}:a\" 1 \"{user}:b\" 2").is_ok());
+        assert!(check("SUNIONSTORE \"{tags}:all\" \"{tags}:a\" {..more}").is_ok());
+        assert!(check("EVAL {script} 2 \"{q}:a\" \"{q}:b\" argument").is_ok());
+        // Keys that are only known at runtime aren't checked
+        assert!(check("MSET a 1 {key} 2 user:{id} 3").is_ok());
+        assert!(check("GET foo").is_ok());
+        // Arguments that aren't keys aren't checked either
+        assert!(check("SET foo bar").is_ok());
+        // A literal hash tag decides the slot of a key with substitutions after it
+        assert!(check("RENAME \"{user}:\"{id} \"{user}:old\"").is_ok());
+    }
+
+    #[test]
+    fn slot_cross_slot() {
+        let err = check("MSET foo 1 bar 2").unwrap_err().to_string();
+        assert!(
+            err.contains("the keys `foo` and `bar` map to the hash slots 12182 and 5061"),
+            "{}",
+            err
+        );
+        assert!(err.contains("same hash tag"), "{}", err);
+        let err = check("SUNIONSTORE \"{a}:dest\" \"{a}:x\" \"{b}:\"{id}").unwrap_err();
+        assert!(err.to_string().contains("`{b}:..`"), "{}", err);
+        let err = check("EVAL {script} 2 a b argument").unwrap_err();
+        assert!(err.to_string().contains("`a` and `b`"), "{}", err);
+    }
+
+    #[test]
+    fn keyslot_expand() {
+        let output = expand_keyslot("\"foo\"".parse().unwrap()).unwrap();
+        assert_eq!(output.to_string(), "12182u16");
+        let output = expand_keyslot("b\"foo\"".parse().unwrap()).unwrap();
+        assert_eq!(output.to_string(), "12182u16");
+        let err = expand_keyslot("42".parse().unwrap()).unwrap_err();
+        assert!(err.to_string().contains("string literal"), "{}", err);
+    }
+}
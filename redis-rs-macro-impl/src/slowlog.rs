@@ -0,0 +1,75 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Expr, ExprLit, ExprUnary, Lit, UnOp};
+
+/// Generate a `redis_slowlog!` invocation, a `SLOWLOG GET` command whose reply is read as
+/// `Vec<redis_rs_macro::SlowlogEntry>`
+pub(crate) fn expand_slowlog(input: TokenStream) -> syn::Result<TokenStream> {
+    let count = match input.is_empty() {
+        true => None,
+        false => Some(syn::parse2::<Expr>(input)?),
+    };
+    if let Some(count) = &count {
+        check_count(count)?;
+    }
+    let count = count.map(|count| quote!(.arg(#count)));
+    Ok(quote! {
+        ::redis_rs_macro::TypedCmd::<::std::vec::Vec<::redis_rs_macro::SlowlogEntry>>::new(
+            redis::cmd("SLOWLOG").arg("GET") #count .clone()
+        )
+    })
+}
+
+/// A literal count is at least -1, which asks for every entry
+fn check_count(count: &Expr) -> syn::Result<()> {
+    let negative = match count {
+        Expr::Unary(ExprUnary {
+            op: UnOp::Neg(_),
+            expr,
+            ..
+        }) => match &**expr {
+            Expr::Lit(ExprLit {
+                lit: Lit::Int(lit), ..
+            }) => lit.base10_parse::<u64>()? > 1,
+            _ => false,
+        },
+        _ => false,
+    };
+    if negative {
+        let msg = "the count of entries is at least -1, which reads the whole slow log";
+        return Err(syn::Error::new_spanned(count, msg));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expand(input: &str) -> syn::Result<String> {
+        expand_slowlog(input.parse().unwrap()).map(|output| output.to_string())
+    }
+
+    #[test]
+    fn slowlog_expand() {
+        assert!(
+            expand("")
+                .unwrap()
+                .ends_with("new (redis :: cmd (\"SLOWLOG\") . arg (\"GET\") . clone ())"),
+            "{}",
+            expand("").unwrap()
+        );
+        assert!(expand("n + 1")
+            .unwrap()
+            .contains(". arg (\"GET\") . arg (n + 1) . clone ()"));
+        expand("-1").unwrap();
+    }
+
+    #[test]
+    fn slowlog_errors() {
+        let e = expand("-2").unwrap_err().to_string();
+        assert!(e.contains("at least -1"), "{}", e);
+        let e = expand("10 20").unwrap_err().to_string();
+        assert!(e.contains("unexpected token"), "{}", e);
+    }
+}
@@ -0,0 +1,134 @@
+use crate::expand::expand_arg_list;
+use crate::parse::parse_args;
+use proc_macro2::{TokenStream, TokenTree};
+use quote::quote;
+use syn::parse::{Parse, ParseStream};
+use syn::{Expr, Token};
+
+/// A channel of `redis_subscribe!` with its handler, e.g. `orders => |order: String| ..`, or a
+/// pattern such as `pattern orders:* => |channel: String, order: String| ..`
+struct Route {
+    pattern: bool,
+    channels: TokenStream,
+    handler: Expr,
+}
+
+/// The input of `redis_subscribe!`
+struct Subscribe {
+    con: Expr,
+    routes: Vec<Route>,
+}
+
+impl Parse for Route {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut channels = TokenStream::new();
+        while !input.is_empty() && !input.peek(Token![=>]) {
+            channels.extend([input.parse::<TokenTree>()?]);
+        }
+        if channels.is_empty() {
+            return Err(input.error("expected a channel"));
+        }
+        input.parse::<Token![=>]>()?;
+        let handler = input.parse()?;
+        // A lone `pattern` is a channel of that name
+        let mut tokens = channels.clone().into_iter();
+        let pattern = matches!(tokens.next(), Some(TokenTree::Ident(ident)) if ident == "pattern")
+            && tokens.next().is_some();
+        let channels = match pattern {
+            true => channels.into_iter().skip(1).collect(),
+            false => channels,
+        };
+        Ok(Route {
+            pattern,
+            channels,
+            handler,
+        })
+    }
+}
+
+impl Parse for Subscribe {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let con = input.parse()?;
+        input.parse::<Token![,]>()?;
+        let mut routes = vec![];
+        while !input.is_empty() {
+            routes.push(input.parse()?);
+            if !input.is_empty() {
+                input.parse::<Token![,]>()?;
+            }
+        }
+        if routes.is_empty() {
+            return Err(input.error("expected a channel and its handler"));
+        }
+        Ok(Subscribe { con, routes })
+    }
+}
+
+/// Generate the receive loop of a `redis_subscribe!` invocation
+pub(crate) fn expand_subscribe(input: TokenStream) -> syn::Result<TokenStream> {
+    let Subscribe { con, routes } = syn::parse2(input)?;
+    let routes = routes
+        .into_iter()
+        .map(|route| {
+            let Route {
+                pattern,
+                channels,
+                handler,
+            } = route;
+            let channels = expand_arg_list(&parse_args(channels, 0)?, false)?;
+            Ok(match pattern {
+                true => quote!(.on_pattern(#channels, #handler)),
+                false => quote!(.on(#channels, #handler)),
+            })
+        })
+        .collect::<syn::Result<Vec<_>>>()?;
+    Ok(quote! {
+        ::redis_rs_macro::Subscriber::new()
+            #(#routes)*
+            .run(#con)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expand(input: &str) -> syn::Result<String> {
+        expand_subscribe(input.parse().unwrap()).map(|output| output.to_string())
+    }
+
+    #[test]
+    fn subscribe_expand() {
+        let output =
+            expand("con, orders => |o: String| {}, user:{id} {..more} => handle,").unwrap();
+        assert!(
+            output.starts_with(":: redis_rs_macro :: Subscriber :: new () . on ({"),
+            "{}",
+            output
+        );
+        assert_eq!(output.matches(". on (").count(), 2, "{}", output);
+        assert!(output.ends_with(". run (con)"), "{}", output);
+        let output = expand("con, pattern orders:* => h, pattern => g").unwrap();
+        assert_eq!(output.matches(". on_pattern (").count(), 1, "{}", output);
+        assert!(output.contains("\"orders:*\""), "{}", output);
+        assert!(output.contains("\"pattern\""), "{}", output);
+    }
+
+    #[test]
+    fn subscribe_errors() {
+        let err = expand("con").unwrap_err().to_string();
+        assert!(err.contains("expected `,`"), "{}", err);
+        let err = expand("con,").unwrap_err().to_string();
+        assert!(
+            err.contains("expected a channel and its handler"),
+            "{}",
+            err
+        );
+        let err = expand("con, => |m: String| {}").unwrap_err().to_string();
+        assert!(err.contains("expected a channel"), "{}", err);
+        let err = expand("con, orders |m: String| {}")
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("expected `=>`"), "{}", err);
+    }
+}
@@ -0,0 +1,178 @@
+use crate::expand::expand_command;
+use crate::parse::{parse_command, Arg, Piece};
+use proc_macro2::{Ident, TokenStream};
+use quote::quote;
+use syn::parse::{Parse, ParseStream};
+use syn::{Attribute, Expr, Token, Visibility};
+
+/// The input of `redis_template!`, e.g. `pub GetUser = GET user:{id}`
+struct Template {
+    attrs: Vec<Attribute>,
+    vis: Visibility,
+    name: Ident,
+    command: TokenStream,
+}
+
+impl Parse for Template {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let attrs = input.call(Attribute::parse_outer)?;
+        let vis = input.parse()?;
+        let name = input.parse()?;
+        input.parse::<Token![=]>()?;
+        let command = input.parse()?;
+        Ok(Template {
+            attrs,
+            vis,
+            name,
+            command,
+        })
+    }
+}
+
+/// How a parameter of a template is used, which decides its type in `bind`
+#[derive(PartialEq)]
+pub(crate) enum ParamKind {
+    /// `{name}`
+    Value,
+    /// `{..name}`
+    Spread,
+    /// `{*name}`
+    Fields,
+    /// `{name?}`
+    Optional,
+    /// `?[{name} => ...]`
+    Condition,
+}
+
+impl ParamKind {
+    pub(crate) fn ty(&self) -> TokenStream {
+        match self {
+            ParamKind::Value => quote!(impl redis::ToRedisArgs),
+            ParamKind::Spread => quote!(impl IntoIterator<Item = impl redis::ToRedisArgs>),
+            ParamKind::Fields => quote!(&impl ::redis_rs_macro::ToRedisFields),
+            ParamKind::Optional => quote!(Option<impl redis::ToRedisArgs>),
+            ParamKind::Condition => quote!(bool),
+        }
+    }
+}
+
+/// Generate the struct and `bind` function for a `redis_template!` invocation
+pub(crate) fn expand_template(input: TokenStream) -> syn::Result<TokenStream> {
+    let Template {
+        attrs,
+        vis,
+        name,
+        command,
+    } = syn::parse2(input)?;
+    let command = parse_command(command)?;
+    if let Some(binding) = command.bindings.first() {
+        let msg = "templates take their values as parameters of `bind`, and cannot have bindings";
+        return Err(syn::Error::new(binding.span(), msg));
+    }
+    let mut params = vec![];
+    collect_params(&command.args, &mut params)?;
+    let params = params.iter().map(|(name, kind)| {
+        let ty = kind.ty();
+        quote!(#name: #ty)
+    });
+    let body = expand_command(&command)?;
+    Ok(quote! {
+        #(#attrs)*
+        #vis struct #name;
+
+        impl #name {
+            /// Build the command, substituting the given values into the template
+            #vis fn bind(#(#params),*) -> redis::Cmd {
+                #body
+            }
+        }
+    })
+}
+
+/// Collect the substitutions of a template, in the order they first appear, as parameters
+pub(crate) fn collect_params(
+    args: &[Arg],
+    params: &mut Vec<(Ident, ParamKind)>,
+) -> syn::Result<()> {
+    for arg in args {
+        for piece in &arg.pieces {
+            match piece {
+                Piece::Text(_) | Piece::Str(_) | Piece::ByteStr(_) => {}
+                Piece::Expr(expr) => add_param(expr, ParamKind::Value, params)?,
+                Piece::Marked { marker, .. } => {
+                    let msg = format!("templates cannot use the `{}` marker", marker.name());
+                    return Err(syn::Error::new(arg.span, msg));
+                }
+                Piece::Spread(expr) => add_param(expr, ParamKind::Spread, params)?,
+                Piece::Fields(expr) => add_param(expr, ParamKind::Fields, params)?,
+                Piece::Optional { expr, .. } => add_param(expr, ParamKind::Optional, params)?,
+                Piece::Conditional { cond, args } => {
+                    add_param(cond, ParamKind::Condition, params)?;
+                    collect_params(args, params)?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn add_param(
+    expr: &Expr,
+    kind: ParamKind,
+    params: &mut Vec<(Ident, ParamKind)>,
+) -> syn::Result<()> {
+    let name = match expr {
+        Expr::Path(path) if path.qself.is_none() && path.attrs.is_empty() => path.path.get_ident(),
+        _ => None,
+    };
+    let Some(name) = name else {
+        let msg = "template substitutions must be a single parameter name, such as `{id}`";
+        return Err(syn::Error::new_spanned(expr, msg));
+    };
+    match params.iter().find(|(param, _)| param == name) {
+        Some((_, existing)) if *existing != kind => {
+            let msg = format!("`{}` is used in two different ways in this template", name);
+            Err(syn::Error::new(name.span(), msg))
+        }
+        Some(_) => Ok(()),
+        None => {
+            params.push((name.clone(), kind));
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn template_err(input: &str) -> String {
+        match expand_template(input.parse().unwrap()) {
+            Ok(_) => panic!("Input: {:?} should not expand", input),
+            Err(err) => err.to_string(),
+        }
+    }
+
+    #[test]
+    fn template_params() {
+        let output = expand_template(
+            "GetUser = SET user:{id} {v} {..rest} EX {ttl?} ?[{nx} => NX] {id}"
+                .parse()
+                .unwrap(),
+        )
+        .unwrap()
+        .to_string();
+        let signature =
+            "fn bind (id : impl redis :: ToRedisArgs , v : impl redis :: ToRedisArgs , \
+            rest : impl IntoIterator < Item = impl redis :: ToRedisArgs > , \
+            ttl : Option < impl redis :: ToRedisArgs > , nx : bool)";
+        assert!(output.contains(signature), "{}", output);
+    }
+
+    #[test]
+    fn template_errors() {
+        assert!(template_err("T = GET {id + 1}").contains("a single parameter name"));
+        assert!(template_err("T = GET {id} {..id}").contains("used in two different ways"));
+        assert!(template_err("T = GET :k; k = 1").contains("cannot have bindings"));
+    }
+}
@@ -0,0 +1,147 @@
+use proc_macro2::{Ident, Span, TokenStream};
+use quote::{quote, quote_spanned};
+use syn::spanned::Spanned;
+use syn::Expr;
+
+/// The unit a time operand is sent to redis in
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Unit {
+    Seconds,
+    Milliseconds,
+}
+
+/// An operand that redis interprets as a time
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum TimeArg {
+    /// A relative TTL, which accepts a `std::time::Duration`
+    Ttl(Unit),
+    /// An absolute unix timestamp, which accepts a `std::time::SystemTime`
+    At(Unit),
+}
+
+impl TimeArg {
+    /// The time that follows an option keyword, such as `EX` in `SET k v EX 10`
+    pub(crate) fn of_option(keyword: &str) -> Option<Self> {
+        match keyword.to_ascii_uppercase().as_str() {
+            "EX" => Some(Self::Ttl(Unit::Seconds)),
+            "PX" => Some(Self::Ttl(Unit::Milliseconds)),
+            "EXAT" => Some(Self::At(Unit::Seconds)),
+            "PXAT" => Some(Self::At(Unit::Milliseconds)),
+            // RedisTimeSeries
+            "RETENTION" => Some(Self::Ttl(Unit::Milliseconds)),
+            "TIMESTAMP" => Some(Self::At(Unit::Milliseconds)),
+            _ => None,
+        }
+    }
+
+    /// The time that follows the value of an option, such as the bucket duration after the
+    /// aggregator in `AGGREGATION avg 60000`
+    pub(crate) fn of_option_value(keyword: &str) -> Option<Self> {
+        match keyword.eq_ignore_ascii_case("AGGREGATION") {
+            true => Some(Self::Ttl(Unit::Milliseconds)),
+            false => None,
+        }
+    }
+
+    /// The time at `position` in a command, counting the command name as 0
+    pub(crate) fn of_position(name: &str, position: usize) -> Option<Self> {
+        match (name.to_ascii_uppercase().as_str(), position) {
+            ("EXPIRE" | "SETEX", 2) => Some(Self::Ttl(Unit::Seconds)),
+            ("PEXPIRE" | "PSETEX", 2) => Some(Self::Ttl(Unit::Milliseconds)),
+            ("EXPIREAT", 2) => Some(Self::At(Unit::Seconds)),
+            ("PEXPIREAT", 2) => Some(Self::At(Unit::Milliseconds)),
+            ("TS.ADD", 2) | ("TS.RANGE" | "TS.REVRANGE" | "TS.DEL", 2 | 3) => {
+                Some(Self::At(Unit::Milliseconds))
+            }
+            ("TS.MRANGE" | "TS.MREVRANGE", 1 | 2) => Some(Self::At(Unit::Milliseconds)),
+            // The `key timestamp value` triples of TS.MADD
+            ("TS.MADD", position) if position % 3 == 2 => Some(Self::At(Unit::Milliseconds)),
+            _ => None,
+        }
+    }
+
+    /// Generate an expression that converts `value` to this unit if it is a duration or timestamp,
+    /// and evaluates to `value` unchanged otherwise
+    pub(crate) fn convert(self, value: &Expr) -> TokenStream {
+        let (kind, unit) = match self {
+            Self::Ttl(unit) => ("ttl_kind", unit),
+            Self::At(unit) => ("timestamp_kind", unit),
+        };
+        let method = match unit {
+            Unit::Seconds => "seconds",
+            Unit::Milliseconds => "milliseconds",
+        };
+        let (kind, method) = (
+            Ident::new(kind, Span::call_site()),
+            Ident::new(method, Span::call_site()),
+        );
+        let time = Ident::new("time", Span::mixed_site());
+        // Only the call is spanned at the value, so that type errors point at the substitution
+        let call = quote_spanned!(value.span()=> (&#time).#kind().#method(#time));
+        quote! {
+            {
+                use ::redis_rs_macro::__private::time::{ArgsKind as _, DurationKind as _, TimestampKind as _};
+                let #time = #value;
+                #call
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn time_args() {
+        use TimeArg::{At, Ttl};
+        assert_eq!(TimeArg::of_option("EX"), Some(Ttl(Unit::Seconds)));
+        assert_eq!(TimeArg::of_option("px"), Some(Ttl(Unit::Milliseconds)));
+        assert_eq!(TimeArg::of_option("EXAT"), Some(At(Unit::Seconds)));
+        assert_eq!(TimeArg::of_option("PXAT"), Some(At(Unit::Milliseconds)));
+        assert_eq!(TimeArg::of_option("KEEPTTL"), None);
+        assert_eq!(TimeArg::of_position("expire", 2), Some(Ttl(Unit::Seconds)));
+        assert_eq!(
+            TimeArg::of_position("PSETEX", 2),
+            Some(Ttl(Unit::Milliseconds))
+        );
+        assert_eq!(TimeArg::of_position("EXPIREAT", 2), Some(At(Unit::Seconds)));
+        assert_eq!(
+            TimeArg::of_position("PEXPIREAT", 2),
+            Some(At(Unit::Milliseconds))
+        );
+        assert_eq!(TimeArg::of_position("SETEX", 3), None);
+        assert_eq!(TimeArg::of_position("SET", 2), None);
+        assert_eq!(
+            TimeArg::of_option("RETENTION"),
+            Some(Ttl(Unit::Milliseconds))
+        );
+        assert_eq!(
+            TimeArg::of_option("TIMESTAMP"),
+            Some(At(Unit::Milliseconds))
+        );
+        assert_eq!(
+            TimeArg::of_option_value("aggregation"),
+            Some(Ttl(Unit::Milliseconds))
+        );
+        assert_eq!(TimeArg::of_option_value("RETENTION"), None);
+        assert_eq!(
+            TimeArg::of_position("ts.add", 2),
+            Some(At(Unit::Milliseconds))
+        );
+        assert_eq!(
+            TimeArg::of_position("TS.RANGE", 3),
+            Some(At(Unit::Milliseconds))
+        );
+        assert_eq!(TimeArg::of_position("TS.RANGE", 4), None);
+        assert_eq!(
+            TimeArg::of_position("TS.MRANGE", 1),
+            Some(At(Unit::Milliseconds))
+        );
+        assert_eq!(
+            TimeArg::of_position("TS.MADD", 5),
+            Some(At(Unit::Milliseconds))
+        );
+        assert_eq!(TimeArg::of_position("TS.MADD", 6), None);
+    }
+}
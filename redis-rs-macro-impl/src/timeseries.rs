@@ -0,0 +1,306 @@
+use crate::parse::{Arg, Command, Piece};
+use proc_macro2::Span;
+
+/// The aggregators of `AGGREGATION`
+const AGGREGATORS: &[&str] = &[
+    "avg", "first", "last", "min", "max", "sum", "range", "count", "std.p", "std.s", "var.p",
+    "var.s", "twa",
+];
+
+/// The reducers of the `GROUPBY label REDUCE reducer` of `TS.MRANGE`
+const REDUCERS: &[&str] = &[
+    "avg", "sum", "min", "max", "range", "count", "std.p", "std.s", "var.p", "var.s",
+];
+
+/// The policies of `DUPLICATE_POLICY` and `ON_DUPLICATE`
+const POLICIES: &[&str] = &["block", "first", "last", "min", "max", "sum"];
+
+/// The commands that end with `LABELS label value...`
+const LABELED: &[&str] = &["TS.CREATE", "TS.ALTER", "TS.ADD", "TS.INCRBY", "TS.DECRBY"];
+
+/// The commands that select series with `FILTER` and label matchers
+const FILTERED: &[&str] = &["TS.MRANGE", "TS.MREVRANGE", "TS.MGET"];
+
+/// Check the literal options of RedisTimeSeries commands: the aggregators and bucket durations of
+/// `AGGREGATION`, the reducers of `REDUCE`, duplicate policies, `LABELS` pairs, and the label
+/// matchers of `FILTER` and `TS.QUERYINDEX`
+pub(crate) fn check_timeseries(command: &Command) -> syn::Result<()> {
+    let [name, args @ ..] = command.args.as_slice() else {
+        return Ok(());
+    };
+    let Some(name) = name.word().map(|name| name.to_ascii_uppercase()) else {
+        return Ok(());
+    };
+    if !name.starts_with("TS.") {
+        return Ok(());
+    }
+    if name == "TS.QUERYINDEX" {
+        return check_filters(command.args[0].span, "TS.QUERYINDEX", args);
+    }
+    let mut filtered = false;
+    for (i, arg) in args.iter().enumerate() {
+        let Some(word) = arg.word() else {
+            continue;
+        };
+        let rest = &args[i + 1..];
+        match word.to_ascii_uppercase().as_str() {
+            "AGGREGATION" => check_aggregation(arg.span, rest)?,
+            "REDUCE" if name.ends_with("RANGE") => {
+                check_choice(arg.span, "REDUCE", "reducer", REDUCERS, rest.first())?
+            }
+            "DUPLICATE_POLICY" | "ON_DUPLICATE" => {
+                check_choice(arg.span, &word, "policy", POLICIES, rest.first())?
+            }
+            "LABELS" if LABELED.contains(&name.as_str()) => {
+                return check_labels(arg.span, rest);
+            }
+            "FILTER" if FILTERED.contains(&name.as_str()) => {
+                let end = rest
+                    .iter()
+                    .position(|arg| {
+                        arg.word()
+                            .is_some_and(|w| w.eq_ignore_ascii_case("GROUPBY"))
+                    })
+                    .unwrap_or(rest.len());
+                check_filters(arg.span, "FILTER", &rest[..end])?;
+                filtered = true;
+            }
+            _ => {}
+        }
+    }
+    if FILTERED.contains(&name.as_str()) && !filtered && !args.iter().any(standalone) {
+        let msg = format!("`{}` needs a `FILTER` of label matchers", name);
+        return Err(syn::Error::new(command.args[0].span, msg));
+    }
+    Ok(())
+}
+
+/// Arguments that expand into a varying number of arguments
+fn standalone(arg: &Arg) -> bool {
+    arg.pieces
+        .iter()
+        .any(|piece| piece.standalone_kind().is_some())
+}
+
+/// Check `AGGREGATION aggregator bucketDuration`
+fn check_aggregation(span: Span, rest: &[Arg]) -> syn::Result<()> {
+    if rest.first().is_some_and(standalone) {
+        return Ok(());
+    }
+    let (Some(aggregator), Some(bucket)) = (rest.first(), rest.get(1)) else {
+        let msg = "`AGGREGATION` expects an aggregator and a bucket duration";
+        return Err(syn::Error::new(span, msg));
+    };
+    check_choice(
+        span,
+        "AGGREGATION",
+        "aggregation",
+        AGGREGATORS,
+        Some(aggregator),
+    )?;
+    if let Some(word) = bucket.word() {
+        if !word.parse::<u64>().is_ok_and(|bucket| bucket > 0) {
+            let msg = format!(
+                "the bucket duration has to be a positive number of milliseconds, found `{}`",
+                word
+            );
+            return Err(syn::Error::new(bucket.span, msg));
+        }
+    }
+    Ok(())
+}
+
+/// Check that the literal value of an option is one of `choices`
+fn check_choice(
+    span: Span,
+    option: &str,
+    what: &str,
+    choices: &[&str],
+    value: Option<&Arg>,
+) -> syn::Result<()> {
+    let Some(value) = value else {
+        let msg = format!("`{}` expects a {}", option, what);
+        return Err(syn::Error::new(span, msg));
+    };
+    let Some(word) = value.word() else {
+        return Ok(());
+    };
+    if !choices
+        .iter()
+        .any(|choice| choice.eq_ignore_ascii_case(&word))
+    {
+        let msg = format!(
+            "unknown {} `{}`; expected one of {}",
+            what,
+            word,
+            choices.join(", ")
+        );
+        return Err(syn::Error::new(value.span, msg));
+    }
+    Ok(())
+}
+
+/// Check that `LABELS` is followed by label names and values in pairs. A field spread adds whole
+/// pairs, and other spreads hide the count.
+fn check_labels(span: Span, rest: &[Arg]) -> syn::Result<()> {
+    let mut count = 0;
+    for arg in rest {
+        match arg.pieces.as_slice() {
+            [Piece::Fields(_)] => {}
+            _ if standalone(arg) => return Ok(()),
+            _ => count += 1,
+        }
+    }
+    if rest.is_empty() || count % 2 == 1 {
+        let msg = "`LABELS` expects label names and values in pairs";
+        return Err(syn::Error::new(span, msg));
+    }
+    Ok(())
+}
+
+/// Check the literal label matchers of a filter: `label=value`, `label!=value`, `label=`,
+/// `label!=`, `label=(a,b)` or `label!=(a,b)`, of which at least one has to match a value
+fn check_filters(span: Span, option: &str, filters: &[Arg]) -> syn::Result<()> {
+    if filters.is_empty() {
+        let msg = format!(
+            "`{}` expects label matchers, such as `sensor=temperature`",
+            option
+        );
+        return Err(syn::Error::new(span, msg));
+    }
+    let mut matches = false;
+    for filter in filters {
+        let Some(word) = filter.word() else {
+            // A substitution can be any matcher
+            matches = true;
+            continue;
+        };
+        let Some((label, value)) = word.split_once('=') else {
+            let msg = format!(
+                "invalid filter `{}`; expected `label=value`, `label!=value`, `label=(a,b)` or \
+                 `label!=(a,b)`",
+                word
+            );
+            return Err(syn::Error::new(filter.span, msg));
+        };
+        let (label, negated) = match label.strip_suffix('!') {
+            Some(label) => (label, true),
+            None => (label, false),
+        };
+        if label.is_empty() || label.contains(char::is_whitespace) {
+            let msg = format!("invalid filter `{}`; expected a label before `=`", word);
+            return Err(syn::Error::new(filter.span, msg));
+        }
+        if value.starts_with('(') != value.ends_with(')') {
+            let msg = format!("invalid filter `{}`; unbalanced parentheses", word);
+            return Err(syn::Error::new(filter.span, msg));
+        }
+        matches |= !negated && !value.is_empty();
+    }
+    if !matches {
+        let msg = "filters need at least one `label=value` or `label=(a,b)` matcher";
+        return Err(syn::Error::new(span, msg));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::expand::expand_command;
+    use crate::parse::parse_command;
+
+    fn check(input: &str) -> syn::Result<String> {
+        expand_command(&parse_command(input.parse().unwrap())?).map(|out| out.to_string())
+    }
+
+    fn err(input: &str) -> String {
+        check(input).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn timeseries_valid() {
+        check(
+            "TS.CREATE temp:1 RETENTION 86400000 DUPLICATE_POLICY last LABELS sensor temp room 1",
+        )
+        .unwrap();
+        check("TS.CREATE temp:1 LABELS {*labels}").unwrap();
+        check("TS.CREATE temp:1 LABELS sensor {sensor} {..more}").unwrap();
+        check("TS.ADD temp:1 * 21.5 ON_DUPLICATE SUM").unwrap();
+        check("TS.RANGE temp:1 - + AGGREGATION avg 60000").unwrap();
+        check("TS.RANGE temp:1 - + AGGREGATION {aggregation} {bucket}").unwrap();
+        check("TS.RANGE temp:1 - + AGGREGATION std.p 1000").unwrap();
+        check("TS.MRANGE - + WITHLABELS FILTER sensor=temp room!=(1,2) GROUPBY room REDUCE max")
+            .unwrap();
+        check("TS.MGET FILTER {filter}").unwrap();
+        check("TS.MGET {..filters}").unwrap();
+        check("TS.QUERYINDEX sensor=temp room=").unwrap();
+        check("TS.CREATERULE temp:1 temp:1:avg AGGREGATION twa 3600000").unwrap();
+    }
+
+    #[test]
+    fn timeseries_times() {
+        let output = check("TS.ADD temp:1 {at} 21.5").unwrap();
+        assert!(
+            output.contains("timestamp_kind () . milliseconds"),
+            "{}",
+            output
+        );
+        let output = check("TS.RANGE temp:1 {from} {to} AGGREGATION avg {bucket}").unwrap();
+        assert_eq!(
+            output.matches("timestamp_kind () . milliseconds").count(),
+            2
+        );
+        assert!(output.contains("ttl_kind () . milliseconds"), "{}", output);
+        let output = check("TS.CREATE temp:1 RETENTION {retention}").unwrap();
+        assert!(output.contains("ttl_kind () . milliseconds"), "{}", output);
+        let output = check("TS.MADD a {t1} 1 b {t2} 2").unwrap();
+        assert_eq!(
+            output.matches("timestamp_kind () . milliseconds").count(),
+            2
+        );
+    }
+
+    #[test]
+    fn timeseries_errors() {
+        let e = err("TS.RANGE temp:1 - + AGGREGATION mean 60000");
+        assert!(
+            e.contains("unknown aggregation `mean`; expected one of avg, first, last"),
+            "{}",
+            e
+        );
+        let e = err("TS.RANGE temp:1 - + AGGREGATION avg");
+        assert!(
+            e.contains("expects an aggregator and a bucket duration"),
+            "{}",
+            e
+        );
+        let e = err("TS.RANGE temp:1 - + AGGREGATION avg 0");
+        assert!(
+            e.contains("positive number of milliseconds, found `0`"),
+            "{}",
+            e
+        );
+        let e = err("TS.RANGE temp:1 - + AGGREGATION avg 1m");
+        assert!(e.contains("found `1m`"), "{}", e);
+        let e = err("TS.MRANGE - + FILTER a=b GROUPBY a REDUCE twa");
+        assert!(e.contains("unknown reducer `twa`"), "{}", e);
+        let e = err("TS.CREATE temp:1 DUPLICATE_POLICY replace");
+        assert!(e.contains("unknown policy `replace`"), "{}", e);
+        let e = err("TS.CREATE temp:1 LABELS sensor temp room");
+        assert!(e.contains("label names and values in pairs"), "{}", e);
+        let e = err("TS.CREATE temp:1 LABELS");
+        assert!(e.contains("label names and values in pairs"), "{}", e);
+        let e = err("TS.MRANGE - +");
+        assert!(e.contains("`TS.MRANGE` needs a `FILTER`"), "{}", e);
+        let e = err("TS.MGET FILTER");
+        assert!(e.contains("`FILTER` expects label matchers"), "{}", e);
+        let e = err("TS.MGET FILTER sensor");
+        assert!(e.contains("invalid filter `sensor`"), "{}", e);
+        let e = err("TS.MGET FILTER =temp");
+        assert!(e.contains("expected a label before `=`"), "{}", e);
+        let e = err("TS.MGET FILTER \"room=(1,2\"");
+        assert!(e.contains("unbalanced parentheses"), "{}", e);
+        let e = err("TS.QUERYINDEX sensor!=temp room=");
+        assert!(e.contains("at least one `label=value`"), "{}", e);
+    }
+}
@@ -0,0 +1,202 @@
+use crate::expand::expand_command;
+use crate::parse::parse_command;
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::spanned::Spanned;
+use syn::{FnArg, ItemTrait, ReturnType, Signature, Token, TraitItem, TraitItemFn, Type};
+
+/// A trait method with a `#[redis(...)]` command
+struct Method {
+    attrs: Vec<syn::Attribute>,
+    sig: Signature,
+    reply: Type,
+    cmd: TokenStream,
+}
+
+/// Generate a `#[redis_commands]` trait, with the `#[redis(...)]` attributes of its methods taken
+/// out, and an implementation for every connection. With `async` as the argument, a second trait
+/// named `<Trait>Async` is generated for async connections.
+pub(crate) fn expand_trait(args: TokenStream, input: TokenStream) -> syn::Result<TokenStream> {
+    let with_async = parse_args(args)?;
+    let mut item: ItemTrait = syn::parse2(input)?;
+    if !item.generics.params.is_empty() {
+        let msg = "`#[redis_commands]` traits cannot have generic parameters";
+        return Err(syn::Error::new(item.generics.span(), msg));
+    }
+    let mut methods = vec![];
+    for trait_item in &mut item.items {
+        if let TraitItem::Fn(method) = trait_item {
+            if let Some(method) = take_method(method)? {
+                methods.push(method);
+            }
+        }
+    }
+    let name = &item.ident;
+    let sync_methods = methods.iter().map(|Method { sig, cmd, .. }| {
+        quote! {
+            #sig {
+                redis::Cmd::query(&#cmd, self)
+            }
+        }
+    });
+    let mut output = quote! {
+        #item
+
+        impl<C: redis::ConnectionLike> #name for C {
+            #(#sync_methods)*
+        }
+    };
+    if with_async {
+        output.extend(expand_async(&item, &methods));
+    }
+    Ok(output)
+}
+
+fn parse_args(args: TokenStream) -> syn::Result<bool> {
+    if args.is_empty() {
+        return Ok(false);
+    }
+    syn::parse2::<Token![async]>(args.clone())
+        .map(|_| true)
+        .map_err(|_| syn::Error::new(args.span(), "expected `async` or no arguments"))
+}
+
+/// Take the `#[redis(...)]` command off a method, checking that it can be implemented with it
+fn take_method(method: &mut TraitItemFn) -> syn::Result<Option<Method>> {
+    let position = method
+        .attrs
+        .iter()
+        .position(|attr| attr.path().is_ident("redis"));
+    let Some(position) = position else {
+        if method.default.is_none() {
+            let msg = format!(
+                "`{}` needs a `#[redis(...)]` command or a default body",
+                method.sig.ident
+            );
+            return Err(syn::Error::new(method.sig.ident.span(), msg));
+        }
+        return Ok(None);
+    };
+    let attr = method.attrs.remove(position);
+    if let Some(body) = &method.default {
+        let msg = "methods with a `#[redis(...)]` command cannot have a body";
+        return Err(syn::Error::new(body.span(), msg));
+    }
+    let mutable_self = match method.sig.inputs.first() {
+        Some(FnArg::Receiver(receiver)) => {
+            receiver.reference.is_some() && receiver.mutability.is_some()
+        }
+        _ => false,
+    };
+    if !mutable_self {
+        let msg = "methods with a `#[redis(...)]` command take `&mut self`, the connection";
+        return Err(syn::Error::new(method.sig.span(), msg));
+    }
+    let ReturnType::Type(_, reply) = &method.sig.output else {
+        let msg = "methods with a `#[redis(...)]` command return the reply, such as \
+                   `redis::RedisResult<String>`";
+        return Err(syn::Error::new(method.sig.span(), msg));
+    };
+    let tokens = attr.meta.require_list()?.tokens.clone();
+    let cmd = expand_command(&parse_command(tokens)?)?;
+    Ok(Some(Method {
+        attrs: method.attrs.clone(),
+        sig: method.sig.clone(),
+        reply: (**reply).clone(),
+        cmd,
+    }))
+}
+
+/// Generate the `<Trait>Async` trait, whose methods return futures of the replies
+fn expand_async(item: &ItemTrait, methods: &[Method]) -> TokenStream {
+    let vis = &item.vis;
+    let name = format_ident!("{}Async", item.ident);
+    let sigs: Vec<_> = methods
+        .iter()
+        .map(|method| {
+            let mut sig = method.sig.clone();
+            let reply = &method.reply;
+            sig.output = syn::parse_quote! {
+                -> impl ::core::future::Future<Output = #reply> + ::core::marker::Send
+            };
+            sig
+        })
+        .collect();
+    let decls = methods.iter().zip(&sigs).map(|(method, sig)| {
+        let attrs = &method.attrs;
+        quote!(#(#attrs)* #sig;)
+    });
+    let impls = methods.iter().zip(&sigs).map(|(method, sig)| {
+        let cmd = &method.cmd;
+        quote! {
+            #sig {
+                let cmd = #cmd;
+                async move { redis::Cmd::query_async(&cmd, self).await }
+            }
+        }
+    });
+    let doc = format!("The async methods of [`{}`]", item.ident);
+    quote! {
+        #[doc = #doc]
+        #vis trait #name {
+            #(#decls)*
+        }
+
+        impl<C: redis::aio::ConnectionLike + ::core::marker::Send> #name for C {
+            #(#impls)*
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expand(args: &str, input: &str) -> syn::Result<String> {
+        expand_trait(args.parse().unwrap(), input.parse().unwrap()).map(|out| out.to_string())
+    }
+
+    #[test]
+    fn trait_expand() {
+        let input = "trait Users {
+            #[redis(GET user:{id})]
+            fn get_user(&mut self, id: u64) -> redis::RedisResult<String>;
+            fn other(&self) {}
+        }";
+        let output = expand("", input).unwrap();
+        assert!(!output.contains("# [redis"), "{}", output);
+        assert!(
+            output.contains("impl < C : redis :: ConnectionLike > Users for C"),
+            "{}",
+            output
+        );
+        assert!(output.contains("redis :: Cmd :: query (& {"), "{}", output);
+        assert!(!output.contains("UsersAsync"), "{}", output);
+        let output = expand("async", input).unwrap();
+        assert!(output.contains("trait UsersAsync"), "{}", output);
+        assert!(
+            output.contains("Output = redis :: RedisResult < String >"),
+            "{}",
+            output
+        );
+        let async_trait = output.split("trait UsersAsync").nth(1).unwrap();
+        assert!(!async_trait.contains("fn other"), "{}", async_trait);
+    }
+
+    #[test]
+    fn trait_errors() {
+        let err = |args: &str, input: &str| expand(args, input).unwrap_err().to_string();
+        let e = err("", "trait T { fn f(&mut self) -> u8; }");
+        assert!(e.contains("needs a `#[redis(...)]` command"), "{}", e);
+        let e = err("", "trait T { #[redis(PING)] fn f(&mut self) -> u8 { 0 } }");
+        assert!(e.contains("cannot have a body"), "{}", e);
+        let e = err("", "trait T { #[redis(PING)] fn f(&self) -> u8; }");
+        assert!(e.contains("take `&mut self`"), "{}", e);
+        let e = err("", "trait T { #[redis(PING)] fn f(&mut self); }");
+        assert!(e.contains("return the reply"), "{}", e);
+        let e = err("", "trait T<X> {}");
+        assert!(e.contains("generic parameters"), "{}", e);
+        let e = err("sync", "trait T {}");
+        assert!(e.contains("expected `async`"), "{}", e);
+    }
+}
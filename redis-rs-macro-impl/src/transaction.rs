@@ -0,0 +1,191 @@
+use crate::expand::expand_arg_list;
+use crate::parse::parse_args;
+use crate::pipe::{expand_entries, is_labeled, parse_entries, split_entries, Sink};
+use proc_macro2::{Delimiter, Group, Ident, Span, TokenStream, TokenTree};
+use quote::quote;
+use syn::parse::{Parse, ParseStream};
+use syn::{Expr, ExprClosure, Pat, ReturnType, Stmt, Token};
+
+/// The input of `redis_transaction!`, e.g.
+/// `con, [balance:{id}], |con| { let balance: i64 = ...; }, { SET balance:{id} {balance + 10} }`
+struct Transaction {
+    con: Expr,
+    keys: Group,
+    read: Option<Read>,
+    body: Group,
+}
+
+/// The `|con| { ... }` statements that run with the connection after the keys are watched, and
+/// before the commands are queued
+struct Read {
+    con: Pat,
+    stmts: Vec<Stmt>,
+}
+
+impl Parse for Transaction {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let con = input.parse()?;
+        input.parse::<Token![,]>()?;
+        let keys = parse_group(
+            input,
+            Delimiter::Bracket,
+            "expected the keys to watch, such as `[balance:{id}]`",
+        )?;
+        input.parse::<Token![,]>()?;
+        let mut read = None;
+        if input.peek(Token![|]) {
+            read = Some(input.parse()?);
+            input.parse::<Token![,]>()?;
+        }
+        let body = parse_group(
+            input,
+            Delimiter::Brace,
+            "expected a `{ ... }` body of commands to run in the transaction",
+        )?;
+        input.parse::<Option<Token![,]>>()?;
+        Ok(Transaction {
+            con,
+            keys,
+            read,
+            body,
+        })
+    }
+}
+
+impl Parse for Read {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let closure: ExprClosure = input.parse()?;
+        let plain = closure.lifetimes.is_none()
+            && closure.constness.is_none()
+            && closure.movability.is_none()
+            && closure.asyncness.is_none()
+            && closure.capture.is_none()
+            && matches!(closure.output, ReturnType::Default);
+        if !plain || closure.inputs.len() != 1 {
+            let msg = "expected `|con| { ... }`, which reads the watched keys with the connection";
+            return Err(syn::Error::new_spanned(closure.or1_token, msg));
+        }
+        let span = closure.or2_token.span;
+        let block = match *closure.body {
+            Expr::Block(block) if block.label.is_none() && block.attrs.is_empty() => block.block,
+            _ => {
+                let msg = "expected `|con| { ... }`, a block of statements that read the keys";
+                return Err(syn::Error::new(span, msg));
+            }
+        };
+        Ok(Read {
+            con: closure.inputs.into_iter().next().unwrap(),
+            stmts: block.stmts,
+        })
+    }
+}
+
+fn parse_group(input: ParseStream, delimiter: Delimiter, msg: &str) -> syn::Result<Group> {
+    match input.parse()? {
+        TokenTree::Group(group) if group.delimiter() == delimiter => Ok(group),
+        tt => Err(syn::Error::new(tt.span(), msg)),
+    }
+}
+
+/// Generate the call to `redis::transaction` for a `redis_transaction!` invocation, which watches
+/// the keys and retries the read and the body until no watched key changed before they ran
+pub(crate) fn expand_transaction(input: TokenStream) -> syn::Result<TokenStream> {
+    let Transaction {
+        con,
+        keys,
+        read,
+        body,
+    } = syn::parse2(input)?;
+    let keys = parse_args(keys.stream(), 0)?;
+    if keys.args.is_empty() {
+        let msg = "expected at least one key to watch";
+        return Err(syn::Error::new(Span::call_site(), msg));
+    }
+    let keys = expand_arg_list(&keys, true)?;
+    let entries = parse_entries(split_entries(body.stream()), body.span())?;
+    if is_labeled(&entries) {
+        let msg = "transactions return their replies as a tuple, so commands can't have labels";
+        return Err(syn::Error::new(body.span(), msg));
+    }
+    let (conn, pipe) = (
+        Ident::new("con", Span::mixed_site()),
+        Ident::new("pipe", Span::mixed_site()),
+    );
+    let sink = Sink::Pipeline {
+        pipe: &pipe,
+        replies: None,
+    };
+    let commands = expand_entries(&entries, sink)?;
+    // The read statements share the scope of the commands, so the commands can use what they read
+    let read = read.map(|Read { con, stmts }| {
+        quote! {
+            let #con = &mut *#conn;
+            #(#stmts)*
+        }
+    });
+    Ok(quote! {
+        redis::transaction(#con, &[#keys], |#conn, #pipe| {
+            #read
+            #commands
+            #pipe.query(#conn)
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transaction_err(input: &str) -> String {
+        match expand_transaction(input.parse().unwrap()) {
+            Ok(_) => panic!("Input: {:?} should not expand", input),
+            Err(err) => err.to_string(),
+        }
+    }
+
+    #[test]
+    fn transaction_expand() {
+        let output = expand_transaction(
+            "&mut con, [a b:{id}], { INCR a; _ = SET b 1 }"
+                .parse()
+                .unwrap(),
+        )
+        .unwrap()
+        .to_string();
+        assert!(
+            output.starts_with("redis :: transaction (& mut con , & ["),
+            "{}",
+            output
+        );
+        assert!(output.contains(". ignore ()"), "{}", output);
+    }
+
+    #[test]
+    fn transaction_read() {
+        let output = expand_transaction(
+            "&mut con, [a], |c| { let n: i64 = c.get(\"a\")?; }, { SET a {n + 1} }"
+                .parse()
+                .unwrap(),
+        )
+        .unwrap()
+        .to_string();
+        assert!(
+            output.contains("{ let c = & mut * con ; let n : i64 = c . get (\"a\") ? ;"),
+            "{}",
+            output
+        );
+        assert!(output.ends_with("pipe . query (con) })"), "{}", output);
+    }
+
+    #[test]
+    fn transaction_errors() {
+        assert!(transaction_err("con, a, { GET a }").contains("expected the keys to watch"));
+        assert!(transaction_err("con, [], { GET a }").contains("at least one key"));
+        assert!(transaction_err("con, [a], [GET a]").contains("expected a `{ ... }` body"));
+        assert!(transaction_err("con, [a], { }").contains("expected at least one redis command"));
+        assert!(transaction_err("con, [a], { x: GET a }").contains("can't have labels"));
+        assert!(transaction_err("con, [a], |c, d| {}, { GET a }").contains("`|con| { ... }`"));
+        assert!(transaction_err("con, [a], |c| c, { GET a }").contains("block of statements"));
+        assert!(transaction_err("con, [a], |c| {} { GET a }").contains("expected `,`"));
+    }
+}
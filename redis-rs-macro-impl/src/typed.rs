@@ -0,0 +1,80 @@
+use proc_macro2::{Spacing, TokenStream, TokenTree};
+use quote::quote;
+use syn::Type;
+
+/// Split a `-> Type` reply annotation off the macro input, e.g. the `-> Option<String>` of
+/// `GET user:{id} -> Option<String>`. The annotation goes after the command and before any
+/// bindings, which are kept with the command.
+pub(crate) fn split_return(input: TokenStream) -> syn::Result<(TokenStream, Option<Type>)> {
+    let tokens: Vec<TokenTree> = input.into_iter().collect();
+    let arrow = tokens.windows(2).position(|pair| match pair {
+        [TokenTree::Punct(dash), TokenTree::Punct(gt)] => {
+            dash.as_char() == '-' && dash.spacing() == Spacing::Joint && gt.as_char() == '>'
+        }
+        _ => false,
+    });
+    let Some(arrow) = arrow else {
+        return Ok((tokens.into_iter().collect(), None));
+    };
+    let end = tokens[arrow..]
+        .iter()
+        .position(|tt| matches!(tt, TokenTree::Punct(p) if p.as_char() == ';'))
+        .map_or(tokens.len(), |end| arrow + end);
+    let ty: TokenStream = tokens[arrow + 2..end].iter().cloned().collect();
+    if ty.is_empty() {
+        let span = tokens[arrow].span();
+        return Err(syn::Error::new(span, "expected the reply type after `->`"));
+    }
+    let ty = syn::parse2(ty)?;
+    let command = tokens[..arrow]
+        .iter()
+        .chain(&tokens[end..])
+        .cloned()
+        .collect();
+    Ok((command, Some(ty)))
+}
+
+/// Wrap a built command in a `redis_rs_macro::TypedCmd` that is queried as `ty`
+pub(crate) fn expand_typed(cmd: TokenStream, ty: &Type) -> TokenStream {
+    quote!(::redis_rs_macro::TypedCmd::<#ty>::new(#cmd))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quote::ToTokens;
+
+    fn split(input: &str) -> syn::Result<(String, Option<String>)> {
+        let (command, ty) = split_return(input.parse().unwrap())?;
+        Ok((
+            command.to_string(),
+            ty.map(|ty| ty.into_token_stream().to_string()),
+        ))
+    }
+
+    #[test]
+    fn typed_split() {
+        assert_eq!(split("GET k").unwrap(), ("GET k".to_string(), None));
+        assert_eq!(
+            split("GET user:{id} -> Option<String>").unwrap(),
+            (
+                "GET user : { id }".to_string(),
+                Some("Option < String >".to_string())
+            )
+        );
+        assert_eq!(
+            split("\"GET :k\" -> String; k = key").unwrap(),
+            (
+                "\"GET :k\" ; k = key".to_string(),
+                Some("String".to_string())
+            )
+        );
+        assert_eq!(
+            split("XRANGE s - + -> Vec<(String, i64)>").unwrap().1,
+            Some("Vec < (String , i64) >".to_string())
+        );
+        let err = split("GET k ->").unwrap_err().to_string();
+        assert!(err.contains("expected the reply type"), "{}", err);
+        assert!(split("GET k -> 42").is_err());
+    }
+}
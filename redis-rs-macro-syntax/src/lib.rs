@@ -0,0 +1,518 @@
+//! The grammar of the string commands of `redis-rs-macro`, such as the `"SET :key {value}"` in
+//! `redis!("SET :key {value}")`, as a library. The macros split string commands with the same
+//! rules as `redis-cli`, through [`parse_command`], so linters and editor tooling built on this
+//! crate see the same arguments the macros do.
+//!
+//! ```rust
+//! use redis_rs_macro_syntax::{parse_command, unescape};
+//!
+//! let args = parse_command(r#"SET "user name" {value}"#).unwrap();
+//! assert_eq!(args[1].data, "user name");
+//! assert!(args[1].is_quoted && args[2].is_braced);
+//! assert_eq!(args[2].offset, 16);
+//!
+//! // Braces joined with text split the argument into parts
+//! let args = parse_command("GET user:{id}").unwrap();
+//! assert_eq!(args[1].parts[1].data, "id");
+//! assert!(args[1].parts[1].is_braced);
+//! assert_eq!(unescape("a\\tb\\xff").unwrap(), b"a\tb\xff");
+//! ```
+
+use std::error::Error;
+use std::fmt;
+use std::mem;
+use std::str::CharIndices;
+
+/// State used by the redis command lexer
+enum State {
+    Word,               // Inside unquoted word
+    DoubleQuote,        // Inside double quote
+    SplitMarker,        // Whitespace (Not \r)
+    EscapedDoubleQuote, // Inside double quote after backslash
+    Braced,             // Inside brace
+}
+
+/// A single redis command argument.
+#[derive(Clone, Default, PartialEq, Eq, Debug)]
+#[non_exhaustive]
+pub struct CmdArg {
+    /// The text of the argument, without its quotes or braces. Escape sequences in quoted
+    /// arguments are left as written, to be resolved with [`unescape`].
+    pub data: String,
+    /// Written in "double quotes"
+    pub is_quoted: bool,
+    /// Written in {curly braces}, which the macros read as a Rust expression
+    pub is_braced: bool,
+    /// The byte offset of the start of the argument in the input
+    pub offset: usize,
+    /// The text and braced parts of an unquoted argument that joins text with `{braces}`, such
+    /// as the `user:` and `{id}` of `user:{id}`, whose `data` is then the whole argument as
+    /// written. Empty for other arguments.
+    pub parts: Vec<Part>,
+}
+
+/// A part of an argument that joins text with `{braces}`
+#[derive(Clone, Default, PartialEq, Eq, Debug)]
+#[non_exhaustive]
+pub struct Part {
+    /// The text of the part, without its braces
+    pub data: String,
+    /// Written in {curly braces}
+    pub is_braced: bool,
+    /// The byte offset of the start of the part in the input
+    pub offset: usize,
+}
+
+/// An argument that can't be read, such as a quote that is never closed
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct SyntaxError {
+    message: String,
+    offset: usize,
+}
+
+impl SyntaxError {
+    fn new(message: impl Into<String>, offset: usize) -> SyntaxError {
+        SyntaxError {
+            message: message.into(),
+            offset,
+        }
+    }
+
+    /// What is wrong
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// The byte offset in the input where the problem starts
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+}
+
+impl fmt::Display for SyntaxError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl Error for SyntaxError {}
+
+/// Split an input string by whitespace, except if enclosed by "double quotes" or
+/// {curly braces}
+pub fn parse_command(input: &str) -> Result<Vec<CmdArg>, SyntaxError> {
+    let mut chars = input.char_indices();
+    let mut output: Vec<CmdArg> = vec![];
+    let mut current_word = CmdArg::default();
+    let mut state = State::SplitMarker;
+    loop {
+        let (index, cur) = match chars.next() {
+            Some((index, c)) => (index, Some(c)),
+            None => (input.len(), None),
+        };
+        state = match state {
+            State::Word => match cur {
+                None => {
+                    output.push(finish_word(&mut current_word));
+                    break;
+                }
+                Some('\t') | Some(' ') | Some('\n') => {
+                    output.push(finish_word(&mut current_word));
+                    State::SplitMarker
+                }
+                Some('{') if !current_word.is_quoted => {
+                    start_braced(&mut current_word, index);
+                    State::Braced
+                }
+                Some(c) => {
+                    push_text(&mut current_word, c, index);
+                    State::Word
+                }
+            },
+            State::SplitMarker => {
+                current_word.offset = index;
+                match cur {
+                    Some('\t') | Some(' ') | Some('\n') => State::SplitMarker,
+                    Some('\"') => {
+                        current_word.is_quoted = true;
+                        State::DoubleQuote
+                    }
+                    Some('{') => {
+                        start_braced(&mut current_word, index);
+                        State::Braced
+                    }
+                    Some(c) => {
+                        push_text(&mut current_word, c, index);
+                        State::Word
+                    }
+                    _ => break,
+                }
+            }
+            State::DoubleQuote => match cur {
+                None => {
+                    let msg = "unclosed double quote";
+                    return Err(SyntaxError::new(msg, current_word.offset));
+                }
+                Some('"') => State::Word,
+                Some('\\') => State::EscapedDoubleQuote,
+                Some(c) => {
+                    current_word.data.push(c);
+                    State::DoubleQuote
+                }
+            },
+            State::EscapedDoubleQuote => match cur {
+                None => {
+                    let msg = "unclosed double quote, after a backslash";
+                    return Err(SyntaxError::new(msg, current_word.offset));
+                }
+                Some(cur) => {
+                    current_word.data.push('\\');
+                    current_word.data.push(cur);
+                    State::DoubleQuote
+                }
+            },
+            State::Braced => match cur {
+                None => {
+                    let offset = current_word.parts.last().map_or(0, |part| part.offset);
+                    return Err(SyntaxError::new("unclosed brace", offset));
+                }
+                Some('}') => {
+                    current_word.data.push('}');
+                    State::Word
+                }
+                Some(cur) => {
+                    current_word.data.push(cur);
+                    if let Some(part) = current_word.parts.last_mut() {
+                        part.data.push(cur);
+                    }
+                    State::Braced
+                }
+            },
+        };
+    }
+    Ok(output)
+}
+
+/// Start a `{braced}` part of an unquoted argument at `offset`
+fn start_braced(word: &mut CmdArg, offset: usize) {
+    word.data.push('{');
+    word.parts.push(Part {
+        data: String::new(),
+        is_braced: true,
+        offset,
+    });
+}
+
+/// Add a character of text, outside of braces, to an argument
+fn push_text(word: &mut CmdArg, c: char, offset: usize) {
+    word.data.push(c);
+    if word.is_quoted {
+        return;
+    }
+    match word.parts.last_mut() {
+        Some(part) if !part.is_braced => part.data.push(c),
+        _ => word.parts.push(Part {
+            data: c.to_string(),
+            is_braced: false,
+            offset,
+        }),
+    }
+}
+
+/// Take a finished argument. An argument that is only braced keeps the text inside the braces,
+/// and only arguments that join text with braces keep their parts.
+fn finish_word(word: &mut CmdArg) -> CmdArg {
+    let mut word = mem::take(word);
+    match word.parts.as_mut_slice() {
+        [part] if part.is_braced => {
+            word.data = mem::take(&mut part.data);
+            word.is_braced = true;
+            word.parts.clear();
+        }
+        parts if !parts.iter().any(|part| part.is_braced) => word.parts.clear(),
+        _ => {}
+    }
+    word
+}
+
+/// Resolve the escape sequences redis-cli accepts inside a double quoted argument. The result is
+/// bytes, since a `\xNN` escape can be any byte, as in `"\xff\xfe"`. Offsets of errors are byte
+/// offsets in `data`.
+pub fn unescape(data: &str) -> Result<Vec<u8>, SyntaxError> {
+    let mut output = Vec::with_capacity(data.len());
+    let mut chars = data.char_indices();
+    let push = |output: &mut Vec<u8>, c: char| {
+        output.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes())
+    };
+    while let Some((start, c)) = chars.next() {
+        if c != '\\' {
+            push(&mut output, c);
+            continue;
+        }
+        match chars.next().map(|(_, c)| c) {
+            Some('n') => output.push(b'\n'),
+            Some('r') => output.push(b'\r'),
+            Some('t') => output.push(b'\t'),
+            Some('b') => output.push(8),
+            Some('a') => output.push(7),
+            Some('x') => {
+                let hex: String = chars.by_ref().take(2).map(|(_, c)| c).collect();
+                match u8::from_str_radix(&hex, 16) {
+                    Ok(byte) if hex.len() == 2 => output.push(byte),
+                    _ => {
+                        let msg = format!("invalid escape sequence \"\\x{}\"", hex);
+                        return Err(SyntaxError::new(msg, start));
+                    }
+                }
+            }
+            Some('u') => {
+                let c = unescape_unicode(&mut chars).map_err(|msg| SyntaxError::new(msg, start))?;
+                push(&mut output, c);
+            }
+            Some(c) => push(&mut output, c),
+            None => {
+                let msg = "invalid escape sequence at end of argument";
+                return Err(SyntaxError::new(msg, start));
+            }
+        }
+    }
+    Ok(output)
+}
+
+/// Resolve the `{1F600}` part of a `\u{1F600}` unicode escape
+fn unescape_unicode(chars: &mut CharIndices) -> Result<char, String> {
+    if chars.next().map(|(_, c)| c) != Some('{') {
+        return Err("invalid unicode escape, expected \"\\u{...}\"".into());
+    }
+    let mut hex = String::new();
+    loop {
+        match chars.next().map(|(_, c)| c) {
+            Some('}') => break,
+            Some(c) if c.is_ascii_hexdigit() && hex.len() < 6 => hex.push(c),
+            _ => return Err(format!("invalid unicode escape \"\\u{{{}\"", hex)),
+        }
+    }
+    u32::from_str_radix(&hex, 16)
+        .ok()
+        .and_then(char::from_u32)
+        .ok_or_else(|| format!("invalid unicode escape \"\\u{{{}}}\"", hex))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn split_(cases: &[(&str, &[CmdArg])]) {
+        for &(input, expected) in cases {
+            let output: Vec<CmdArg> = parse_command(input).unwrap();
+            assert!(
+                expected == output.as_slice(),
+                "Input: {:?}\nExpected: {:?}\nBut found: {:?}",
+                input,
+                expected,
+                output
+            );
+        }
+    }
+
+    #[test]
+    fn unescape_() {
+        assert_eq!(unescape("plain").unwrap(), b"plain");
+        assert_eq!(unescape("a\\\"b\\\\c").unwrap(), b"a\"b\\c");
+        assert_eq!(unescape("\\t\\n\\x41").unwrap(), b"\t\nA");
+        assert_eq!(
+            unescape("\\x00\\x7f\\x80\\xFF").unwrap(),
+            b"\x00\x7f\x80\xff"
+        );
+        assert_eq!(unescape("é\\xe9").unwrap(), b"\xc3\xa9\xe9");
+        assert!(unescape("\\xZZ").is_err());
+        assert!(unescape("\\x").is_err());
+        assert_eq!(
+            unescape("\\u{1F600}\\u{e9}").unwrap(),
+            "\u{1F600}\u{e9}".as_bytes()
+        );
+        assert!(unescape("\\u1F600").is_err());
+        assert!(unescape("\\u{}").is_err());
+        assert!(unescape("\\u{D800}").is_err());
+        assert!(unescape("\\u{1F600").is_err());
+    }
+
+    #[test]
+    fn split_errors() {
+        let err = parse_command("GET \"key").unwrap_err();
+        assert_eq!((err.message(), err.offset()), ("unclosed double quote", 4));
+        assert_eq!(parse_command("GET \"key\\").unwrap_err().offset(), 4);
+        assert_eq!(
+            parse_command("a {b").unwrap_err().message(),
+            "unclosed brace"
+        );
+        assert_eq!(unescape("ab\\x4").unwrap_err().offset(), 2);
+    }
+
+    #[test]
+    fn split_empty() {
+        split_(&[("", &[])]);
+    }
+
+    #[test]
+    fn split_leading_ws() {
+        split_(&[
+            (
+                " abcd",
+                &[CmdArg {
+                    data: "abcd".into(),
+                    is_quoted: false,
+                    is_braced: false,
+                    offset: 1,
+                    parts: vec![],
+                }],
+            ),
+            (
+                "\tabcd",
+                &[CmdArg {
+                    data: "abcd".into(),
+                    is_quoted: false,
+                    is_braced: false,
+                    offset: 1,
+                    parts: vec![],
+                }],
+            ),
+            (
+                "\nabcd",
+                &[CmdArg {
+                    data: "abcd".into(),
+                    is_quoted: false,
+                    is_braced: false,
+                    offset: 1,
+                    parts: vec![],
+                }],
+            ),
+            (
+                " \n\tabcd",
+                &[CmdArg {
+                    data: "abcd".into(),
+                    is_quoted: false,
+                    is_braced: false,
+                    offset: 3,
+                    parts: vec![],
+                }],
+            ),
+        ]);
+    }
+
+    #[test]
+    fn split_normal() {
+        split_(&[(
+            "abcd 123",
+            &[
+                CmdArg {
+                    data: "abcd".into(),
+                    is_quoted: false,
+                    is_braced: false,
+                    offset: 0,
+                    parts: vec![],
+                },
+                CmdArg {
+                    data: "123".into(),
+                    is_quoted: false,
+                    is_braced: false,
+                    offset: 5,
+                    parts: vec![],
+                },
+            ],
+        )])
+    }
+
+    #[test]
+    fn split_dquotes() {
+        split_(&[(
+            "\"abcd 123\" abcd",
+            &[
+                CmdArg {
+                    data: "abcd 123".into(),
+                    is_quoted: true,
+                    is_braced: false,
+                    offset: 0,
+                    parts: vec![],
+                },
+                CmdArg {
+                    data: "abcd".into(),
+                    is_quoted: false,
+                    is_braced: false,
+                    offset: 11,
+                    parts: vec![],
+                },
+            ],
+        )]);
+    }
+
+    #[test]
+    fn split_brackets() {
+        split_(&[(
+            "{abcd 123} abcd",
+            &[
+                CmdArg {
+                    data: "abcd 123".into(),
+                    is_quoted: false,
+                    is_braced: true,
+                    offset: 0,
+                    parts: vec![],
+                },
+                CmdArg {
+                    data: "abcd".into(),
+                    is_quoted: false,
+                    is_braced: false,
+                    offset: 11,
+                    parts: vec![],
+                },
+            ],
+        )]);
+    }
+
+    #[test]
+    fn split_joined_brackets() {
+        let part = |data: &str, is_braced, offset| Part {
+            data: data.into(),
+            is_braced,
+            offset,
+        };
+        split_(&[(
+            "GET user:{id} {id}:x a{b c}d",
+            &[
+                CmdArg {
+                    data: "GET".into(),
+                    is_quoted: false,
+                    is_braced: false,
+                    offset: 0,
+                    parts: vec![],
+                },
+                CmdArg {
+                    data: "user:{id}".into(),
+                    is_quoted: false,
+                    is_braced: false,
+                    offset: 4,
+                    parts: vec![part("user:", false, 4), part("id", true, 9)],
+                },
+                CmdArg {
+                    data: "{id}:x".into(),
+                    is_quoted: false,
+                    is_braced: false,
+                    offset: 14,
+                    parts: vec![part("id", true, 14), part(":x", false, 18)],
+                },
+                CmdArg {
+                    data: "a{b c}d".into(),
+                    is_quoted: false,
+                    is_braced: false,
+                    offset: 21,
+                    parts: vec![
+                        part("a", false, 21),
+                        part("b c", true, 22),
+                        part("d", false, 27),
+                    ],
+                },
+            ],
+        )]);
+        let err = parse_command("GET user:{id").unwrap_err();
+        assert_eq!((err.message(), err.offset()), ("unclosed brace", 9));
+    }
+}
@@ -0,0 +1,36 @@
+use redis::{RedisWrite, ToRedisArgs};
+
+/// A list of arguments built by [`redis_args!`](crate::redis_args), which writes every argument in
+/// order when passed to `.arg`
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Args(Vec<Vec<u8>>);
+
+impl Args {
+    /// An empty list of arguments
+    pub fn new() -> Args {
+        Args::default()
+    }
+
+    /// Append an argument, as with `redis::Cmd::arg`
+    pub fn arg<T: ToRedisArgs>(&mut self, arg: T) -> &mut Args {
+        arg.write_redis_args(&mut self.0);
+        self
+    }
+
+    /// The arguments written so far
+    pub fn as_slice(&self) -> &[Vec<u8>] {
+        &self.0
+    }
+}
+
+impl ToRedisArgs for Args {
+    fn write_redis_args<W: ?Sized + RedisWrite>(&self, out: &mut W) {
+        for arg in &self.0 {
+            out.write_arg(arg);
+        }
+    }
+
+    fn is_single_arg(&self) -> bool {
+        false
+    }
+}
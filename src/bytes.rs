@@ -0,0 +1,4 @@
+/// Borrow the value of a `{bytes expr}` substitution as raw bytes
+pub fn as_bytes<T: AsRef<[u8]> + ?Sized>(value: &T) -> &[u8] {
+    value.as_ref()
+}
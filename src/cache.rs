@@ -0,0 +1,8 @@
+use std::future::Future;
+
+/// Pass a future through unchanged, fixing its output type. The body of an async
+/// [`#[redis_cached]`](crate::redis_cached) function is wrapped in an async block, whose `?`s need
+/// the output type to be known.
+pub fn output<T, F: Future<Output = T>>(future: F) -> F {
+    future
+}
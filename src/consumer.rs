@@ -0,0 +1,290 @@
+use crate::subscribe::HandlerResult;
+use crate::Args;
+use redis::{ConnectionLike, FromRedisValue, RedisResult, ToRedisArgs, Value};
+use std::ops::ControlFlow;
+use std::time::{Duration, Instant};
+
+/// An entry read from a stream by [`redis_consume!`](crate::redis_consume)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StreamEntry<T> {
+    /// The ID of the entry, e.g. `1700000000000-0`
+    pub id: String,
+    /// The fields and values of the entry, read from their alternating list
+    pub fields: T,
+}
+
+/// A consumer in a consumer group of a stream, built by [`redis_consume!`](crate::redis_consume)
+#[derive(Clone, Debug)]
+pub struct StreamConsumer {
+    stream: Args,
+    group: Args,
+    consumer: Args,
+    count: Option<Args>,
+    block: Args,
+    start: Args,
+    /// How long entries stay pending before they are claimed from other consumers
+    claim: Option<Duration>,
+    /// The stream that entries delivered too many times are moved to, with the most deliveries
+    dead_letter: Option<(Args, u64)>,
+}
+
+fn args(value: impl ToRedisArgs) -> Args {
+    let mut args = Args::new();
+    args.arg(value);
+    args
+}
+
+impl StreamConsumer {
+    /// A consumer named `consumer` in the group `group` of `stream`. Reads block for up to five
+    /// seconds, and a new group starts at the end of the stream.
+    pub fn new(
+        stream: impl ToRedisArgs,
+        group: impl ToRedisArgs,
+        consumer: impl ToRedisArgs,
+    ) -> StreamConsumer {
+        StreamConsumer {
+            stream: args(stream),
+            group: args(group),
+            consumer: args(consumer),
+            count: None,
+            block: args(5000),
+            start: args("$"),
+            claim: None,
+            dead_letter: None,
+        }
+    }
+
+    /// Read at most `count` entries at a time
+    pub fn count(mut self, count: impl ToRedisArgs) -> Self {
+        self.count = Some(args(count));
+        self
+    }
+
+    /// Wait up to `millis` milliseconds for new entries in each read, or forever for `0`
+    pub fn block(mut self, millis: impl ToRedisArgs) -> Self {
+        self.block = args(millis);
+        self
+    }
+
+    /// Start a group that doesn't exist yet at the entry `id`, such as `0` for the whole stream
+    pub fn start(mut self, id: impl ToRedisArgs) -> Self {
+        self.start = args(id);
+        self
+    }
+
+    /// Claim entries that other consumers left pending for at least `min_idle_ms` milliseconds, such
+    /// as those of consumers that crashed. Pending entries are claimed when the consumer starts,
+    /// and again each time `min_idle_ms` has passed since the last time.
+    pub fn claim(mut self, min_idle_ms: u64) -> Self {
+        self.claim = Some(Duration::from_millis(min_idle_ms));
+        self
+    }
+
+    /// Move claimed entries that were delivered more than `max_deliveries` times to the stream
+    /// `stream` instead of handling them again, and acknowledge them. Only claimed entries are
+    /// delivered again, so this needs [`StreamConsumer::claim`].
+    pub fn dead_letter(mut self, stream: impl ToRedisArgs, max_deliveries: u64) -> Self {
+        self.dead_letter = Some((args(stream), max_deliveries));
+        self
+    }
+
+    /// Create the group, and the stream with it if it is missing. A group that already exists is
+    /// left as it is.
+    pub fn create_group(&self, con: &mut dyn ConnectionLike) -> RedisResult<()> {
+        let created: RedisResult<()> = redis::cmd("XGROUP")
+            .arg("CREATE")
+            .arg(&self.stream)
+            .arg(&self.group)
+            .arg(&self.start)
+            .arg("MKSTREAM")
+            .query(con);
+        match created {
+            Err(err) if err.code() == Some("BUSYGROUP") => Ok(()),
+            created => created,
+        }
+    }
+
+    /// Read the next entries that were never delivered to the group, waiting for them as set by
+    /// [`StreamConsumer::block`]. Entries come with their fields as they were sent.
+    pub fn read(&self, con: &mut dyn ConnectionLike) -> RedisResult<Vec<(String, Value)>> {
+        let mut cmd = redis::cmd("XREADGROUP");
+        cmd.arg("GROUP").arg(&self.group).arg(&self.consumer);
+        if let Some(count) = &self.count {
+            cmd.arg("COUNT").arg(count);
+        }
+        cmd.arg("BLOCK").arg(&self.block);
+        cmd.arg("STREAMS").arg(&self.stream).arg(">");
+        // The reply is nil when the read timed out
+        let streams: Option<Vec<Value>> = cmd.query(con)?;
+        let mut entries = vec![];
+        for stream in streams.into_iter().flatten() {
+            let (_, stream_entries): (Value, Vec<Value>) =
+                FromRedisValue::from_redis_value(&stream)?;
+            entries.extend(read_entries(&stream_entries)?);
+        }
+        Ok(entries)
+    }
+
+    /// Claim a page of the entries that were pending for at least `min_idle`, starting at
+    /// `cursor`. Returns the cursor of the next page, which is `0-0` after the last one.
+    pub fn claim_page(
+        &self,
+        con: &mut dyn ConnectionLike,
+        min_idle: Duration,
+        cursor: &str,
+    ) -> RedisResult<(String, Vec<(String, Value)>)> {
+        let mut cmd = redis::cmd("XAUTOCLAIM");
+        cmd.arg(&self.stream)
+            .arg(&self.group)
+            .arg(&self.consumer)
+            .arg(min_idle.as_millis() as u64)
+            .arg(cursor);
+        if let Some(count) = &self.count {
+            cmd.arg("COUNT").arg(count);
+        }
+        // Redis 7 adds a third item with the IDs of deleted entries, which were already removed
+        // from the pending entries
+        let reply: Vec<Value> = cmd.query(con)?;
+        let (next, entries): (String, Vec<Value>) = match reply.as_slice() {
+            [next, entries, ..] => (
+                FromRedisValue::from_redis_value(next)?,
+                FromRedisValue::from_redis_value(entries)?,
+            ),
+            _ => FromRedisValue::from_redis_value(&Value::Bulk(reply))?,
+        };
+        Ok((next, read_entries(&entries)?))
+    }
+
+    /// Acknowledge an entry, removing it from the pending entries of the group
+    pub fn ack(&self, con: &mut dyn ConnectionLike, id: &str) -> RedisResult<()> {
+        redis::cmd("XACK")
+            .arg(&self.stream)
+            .arg(&self.group)
+            .arg(id)
+            .query(con)
+    }
+
+    /// Create the group and pass every entry to `handler`, acknowledging the entries it returns
+    /// `Ok` for. Entries it returns `Err` for stay pending, to be claimed again when
+    /// [`StreamConsumer::claim`] is set. Runs until the handler returns
+    /// `Ok(ControlFlow::Break(()))`, or until a command or reading the fields of an entry fails.
+    pub fn run<T, R, E>(
+        &self,
+        con: &mut dyn ConnectionLike,
+        mut handler: impl FnMut(StreamEntry<T>) -> Result<R, E>,
+    ) -> RedisResult<()>
+    where
+        T: FromRedisValue,
+        R: HandlerResult,
+    {
+        self.create_group(con)?;
+        let mut claimed_at: Option<Instant> = None;
+        loop {
+            if let Some(min_idle) = self.claim {
+                if claimed_at.is_none_or(|at| at.elapsed() >= min_idle) {
+                    claimed_at = Some(Instant::now());
+                    let mut cursor = "0-0".to_string();
+                    loop {
+                        let (next, entries) = self.claim_page(con, min_idle, &cursor)?;
+                        let entries = self.remove_dead(con, entries)?;
+                        if self.handle(con, entries, &mut handler)?.is_break() {
+                            return Ok(());
+                        }
+                        if next == "0-0" {
+                            break;
+                        }
+                        cursor = next;
+                    }
+                }
+            }
+            let entries = self.read(con)?;
+            if self.handle(con, entries, &mut handler)?.is_break() {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Move the claimed entries that were delivered too many times to the dead letter stream, and
+    /// return the others
+    fn remove_dead(
+        &self,
+        con: &mut dyn ConnectionLike,
+        entries: Vec<(String, Value)>,
+    ) -> RedisResult<Vec<(String, Value)>> {
+        let Some((dead_letter, max_deliveries)) = &self.dead_letter else {
+            return Ok(entries);
+        };
+        let mut alive = vec![];
+        for (id, fields) in entries {
+            // Each pending entry is `[id, consumer, idle time, deliveries]`
+            let pending: Vec<Value> = redis::cmd("XPENDING")
+                .arg(&self.stream)
+                .arg(&self.group)
+                .arg(&id)
+                .arg(&id)
+                .arg(1)
+                .query(con)?;
+            let deliveries = match pending.first() {
+                Some(entry) => {
+                    let (_, _, _, deliveries): (String, String, u64, u64) =
+                        FromRedisValue::from_redis_value(entry)?;
+                    deliveries
+                }
+                None => 0,
+            };
+            if deliveries <= *max_deliveries || fields == Value::Nil {
+                alive.push((id, fields));
+                continue;
+            }
+            let fields: Vec<Vec<u8>> = FromRedisValue::from_redis_value(&fields)?;
+            redis::cmd("XADD")
+                .arg(dead_letter)
+                .arg("*")
+                .arg(fields)
+                .query::<()>(con)?;
+            self.ack(con, &id)?;
+        }
+        Ok(alive)
+    }
+
+    /// Pass entries to the handler, acknowledging those it handled
+    fn handle<T, R, E>(
+        &self,
+        con: &mut dyn ConnectionLike,
+        entries: Vec<(String, Value)>,
+        handler: &mut impl FnMut(StreamEntry<T>) -> Result<R, E>,
+    ) -> RedisResult<ControlFlow<()>>
+    where
+        T: FromRedisValue,
+        R: HandlerResult,
+    {
+        for (id, fields) in entries {
+            // Entries deleted from the stream have no fields left to handle
+            if fields == Value::Nil {
+                self.ack(con, &id)?;
+                continue;
+            }
+            let fields = T::from_redis_value(&fields)?;
+            let Ok(handled) = handler(StreamEntry {
+                id: id.clone(),
+                fields,
+            }) else {
+                continue;
+            };
+            self.ack(con, &id)?;
+            if handled.into_flow().is_break() {
+                return Ok(ControlFlow::Break(()));
+            }
+        }
+        Ok(ControlFlow::Continue(()))
+    }
+}
+
+/// Read the `[id, fields]` pairs of entries. They are read one by one, since a list of pairs would
+/// also be read from a flat list.
+fn read_entries(entries: &[Value]) -> RedisResult<Vec<(String, Value)>> {
+    entries
+        .iter()
+        .map(FromRedisValue::from_redis_value)
+        .collect()
+}
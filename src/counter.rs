@@ -0,0 +1,42 @@
+use crate::{Args, ScriptCmd};
+use redis::{ConnectionLike, RedisResult, ToRedisArgs};
+
+/// Increment the counter, and set its expiry only when the increment created it
+const INCR: &str = r#"local created = redis.call("EXISTS", KEYS[1]) == 0
+local count = redis.call("INCRBY", KEYS[1], ARGV[1])
+if created then
+    redis.call("EXPIRE", KEYS[1], ARGV[2])
+end
+return count"#;
+const INCR_SHA1: &str = "20c6a303e44f095d920fd357445b539dc2ba7a9b";
+
+/// A counter that expires `ttl` seconds after its first increment, built by
+/// [`redis_counter!`](crate::redis_counter). Increments run as one Lua script, so the counter can't
+/// be left without an expiry when a client fails between `INCR` and `EXPIRE`, and later increments
+/// don't push the expiry back.
+#[derive(Clone, Debug)]
+pub struct Counter {
+    key: Args,
+    ttl: u64,
+}
+
+impl Counter {
+    /// The counter at `key`, which expires `ttl` seconds after its first increment
+    pub fn new<K: ToRedisArgs>(key: K, ttl: u64) -> Counter {
+        let mut args = Args::new();
+        args.arg(key);
+        Counter { key: args, ttl }
+    }
+
+    /// The call of the script that adds `by` to the counter, which replies the new count
+    pub fn incr_cmd(&self, by: i64) -> ScriptCmd {
+        let mut call = ScriptCmd::new(INCR, INCR_SHA1);
+        call.key(&self.key).arg(by).arg(self.ttl);
+        call
+    }
+
+    /// Add `by` to the counter, returning the new count
+    pub fn incr(&self, con: &mut dyn ConnectionLike, by: i64) -> RedisResult<i64> {
+        self.incr_cmd(by).invoke(con)
+    }
+}
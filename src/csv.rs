@@ -0,0 +1,19 @@
+use redis::ToRedisArgs;
+
+/// Join the items of a `{csv expr}` substitution with commas into a single argument
+pub fn join<I>(items: I) -> Vec<u8>
+where
+    I: IntoIterator,
+    I::Item: ToRedisArgs,
+{
+    let mut joined = vec![];
+    for item in items {
+        for arg in item.to_redis_args() {
+            if !joined.is_empty() {
+                joined.push(b',');
+            }
+            joined.extend_from_slice(&arg);
+        }
+    }
+    joined
+}
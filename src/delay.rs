@@ -0,0 +1,133 @@
+use crate::subscribe::HandlerResult;
+use crate::time::{millis, Timestamp};
+use crate::{Args, ScriptCmd};
+use redis::{Cmd, ConnectionLike, FromRedisValue, RedisResult, ToRedisArgs, Value};
+use std::time::{Duration, SystemTime};
+
+/// Pop the jobs whose time has come, so that each is popped by one poller only
+const POP_DUE: &str = r#"local jobs = redis.call("ZRANGEBYSCORE", KEYS[1], "-inf", ARGV[1], "LIMIT", 0, ARGV[2])
+if #jobs > 0 then
+    redis.call("ZREM", KEYS[1], unpack(jobs))
+end
+return jobs"#;
+const POP_DUE_SHA1: &str = "062a72ab4233e7023dc51046c34cd128be2e7a3b";
+
+/// How many due jobs are popped at a time
+const BATCH: usize = 100;
+
+/// A queue of jobs that run at a given time, built by
+/// [`redis_delay_queue!`](crate::redis_delay_queue). Jobs are members of the sorted set at `key`,
+/// scored by the unix time in milliseconds they run at, and due jobs are popped with a Lua script
+/// that runs `ZRANGEBYSCORE` and `ZREM` together. Since jobs are set members, scheduling a job
+/// that is already scheduled moves it to the new time.
+#[derive(Clone, Debug)]
+pub struct DelayQueue {
+    key: Args,
+    interval: Duration,
+}
+
+impl DelayQueue {
+    /// The queue at `key`, which workers poll every `interval` milliseconds while no job is due
+    pub fn new<K: ToRedisArgs>(key: K, interval: u64) -> DelayQueue {
+        let mut args = Args::new();
+        args.arg(key);
+        DelayQueue {
+            key: args,
+            interval: Duration::from_millis(interval),
+        }
+    }
+
+    /// The `ZADD` command that schedules a serialized job to run at `run_at`
+    pub fn schedule_cmd<T: ToRedisArgs>(&self, job: T, run_at: impl Timestamp) -> Cmd {
+        let mut cmd = redis::cmd("ZADD");
+        cmd.arg(&self.key)
+            .arg(millis(run_at.since_epoch()))
+            .arg(job);
+        cmd
+    }
+
+    /// The call of the script that pops at most `limit` jobs due at `now`, which replies the jobs
+    pub fn pop_cmd(&self, now: impl Timestamp, limit: usize) -> ScriptCmd {
+        let mut call = ScriptCmd::new(POP_DUE, POP_DUE_SHA1);
+        call.key(&self.key)
+            .arg(millis(now.since_epoch()))
+            .arg(limit);
+        call
+    }
+
+    /// Schedule a serialized job to run at `run_at`
+    pub fn schedule<T: ToRedisArgs>(
+        &self,
+        con: &mut dyn ConnectionLike,
+        job: T,
+        run_at: impl Timestamp,
+    ) -> RedisResult<()> {
+        self.schedule_cmd(job, run_at).query(con)
+    }
+
+    /// Pop at most `limit` jobs that are due now
+    pub fn pop_due<T: FromRedisValue>(
+        &self,
+        con: &mut dyn ConnectionLike,
+        limit: usize,
+    ) -> RedisResult<Vec<T>> {
+        let jobs: Vec<Vec<u8>> = self.pop_cmd(SystemTime::now(), limit).invoke(con)?;
+        jobs.into_iter()
+            .map(|job| T::from_redis_value(&Value::Data(job)))
+            .collect()
+    }
+
+    /// Pop due jobs and pass them to `handler`, waiting for the poll interval while no job is due.
+    /// Jobs it returns `Err` for are scheduled again to run after the poll interval. Runs until the
+    /// handler returns `Ok(ControlFlow::Break(()))`, or until a command or reading a job fails. A
+    /// job that can't be read is dropped, and the rest of the popped jobs are scheduled again to
+    /// run now, as they are when the handler stops.
+    pub fn work<T, R, E>(
+        &self,
+        con: &mut dyn ConnectionLike,
+        mut handler: impl FnMut(T) -> Result<R, E>,
+    ) -> RedisResult<()>
+    where
+        T: FromRedisValue,
+        R: HandlerResult,
+    {
+        loop {
+            let jobs: Vec<Vec<u8>> = self.pop_cmd(SystemTime::now(), BATCH).invoke(con)?;
+            if jobs.is_empty() {
+                std::thread::sleep(self.interval);
+                continue;
+            }
+            let mut jobs = jobs.into_iter();
+            while let Some(job) = jobs.next() {
+                let handled = match T::from_redis_value(&Value::Data(job.clone())) {
+                    Ok(value) => handler(value),
+                    Err(err) => {
+                        self.reschedule(con, jobs)?;
+                        return Err(err);
+                    }
+                };
+                let Ok(handled) = handled else {
+                    let retry_at = SystemTime::now() + self.interval;
+                    self.schedule(con, job, retry_at)?;
+                    continue;
+                };
+                if handled.into_flow().is_break() {
+                    return self.reschedule(con, jobs);
+                }
+            }
+        }
+    }
+
+    /// Schedule popped jobs that weren't handled again, to run now
+    fn reschedule(
+        &self,
+        con: &mut dyn ConnectionLike,
+        jobs: impl Iterator<Item = Vec<u8>>,
+    ) -> RedisResult<()> {
+        let now = SystemTime::now();
+        for job in jobs {
+            self.schedule(con, job, now)?;
+        }
+        Ok(())
+    }
+}
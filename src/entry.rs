@@ -0,0 +1,131 @@
+use redis::{ErrorKind, FromRedisValue, RedisError, RedisResult, RedisWrite, ToRedisArgs, Value};
+
+/// Write a field of a `#[derive(RedisStreamEntry)]` or `#[derive(RedisHash)]` struct. Fields whose
+/// value writes no arguments, such as a `None`, are left out, so that names and values stay paired.
+pub fn write_field<W, T>(out: &mut W, name: &str, value: &T)
+where
+    W: ?Sized + RedisWrite,
+    T: ToRedisArgs,
+{
+    let args = value.to_redis_args();
+    if args.is_empty() {
+        return;
+    }
+    out.write_arg(name.as_bytes());
+    for arg in args {
+        out.write_arg(&arg);
+    }
+}
+
+/// The field names and values of a stream entry or a hash, read by `#[derive(RedisStreamEntry)]`
+/// and `#[derive(RedisHash)]` structs, and by `#[derive(RedisReply)]` structs with named fields
+pub struct EntryFields<'a>(Vec<(&'a [u8], &'a Value)>);
+
+impl<'a> EntryFields<'a> {
+    /// Pair up the alternating field names and values of an entry, as replied by `XRANGE` or
+    /// `XREADGROUP`, or of a hash, as replied by `HGETALL`
+    pub fn new(value: &'a Value) -> RedisResult<EntryFields<'a>> {
+        let items = match value {
+            Value::Bulk(items) if items.len() % 2 == 0 => items,
+            _ => {
+                return Err(RedisError::from((
+                    ErrorKind::TypeError,
+                    "expected alternating field names and values",
+                    format!("{:?}", value),
+                )))
+            }
+        };
+        let mut fields = Vec::with_capacity(items.len() / 2);
+        for pair in items.chunks(2) {
+            let Value::Data(name) = &pair[0] else {
+                return Err(RedisError::from((
+                    ErrorKind::TypeError,
+                    "expected the name of a field",
+                    format!("{:?}", pair[0]),
+                )));
+            };
+            fields.push((name.as_slice(), &pair[1]));
+        }
+        Ok(EntryFields(fields))
+    }
+
+    /// Read the value of a field. A missing field is read from `Nil`, so that `Option` fields
+    /// become `None`, and is an error for other types.
+    pub fn get<T: FromRedisValue>(&self, name: &str) -> RedisResult<T> {
+        let value = self
+            .0
+            .iter()
+            .find(|(field, _)| *field == name.as_bytes())
+            .map(|(_, value)| *value);
+        match value {
+            Some(value) => read_field(value, name),
+            None => T::from_redis_value(&Value::Nil).map_err(|_| {
+                RedisError::from((ErrorKind::TypeError, "missing field", name.to_string()))
+            }),
+        }
+    }
+
+    /// Read the value of a field, or the default of its type when it is missing
+    pub fn get_or_default<T: FromRedisValue + Default>(&self, name: &str) -> RedisResult<T> {
+        match self.0.iter().find(|(field, _)| *field == name.as_bytes()) {
+            Some((_, value)) => read_field(value, name),
+            None => Ok(T::default()),
+        }
+    }
+}
+
+/// The items of an array reply, read by position by `#[derive(RedisReply)]` structs
+pub struct ReplyItems<'a>(&'a [Value]);
+
+impl<'a> ReplyItems<'a> {
+    /// Check that an array reply has at most `len` items, one for each field
+    pub fn new(value: &'a Value, len: usize) -> RedisResult<ReplyItems<'a>> {
+        match value {
+            Value::Bulk(items) if items.len() <= len => Ok(ReplyItems(items)),
+            Value::Bulk(items) => Err(RedisError::from((
+                ErrorKind::TypeError,
+                "too many items in the reply",
+                format!("expected at most {}, got {}", len, items.len()),
+            ))),
+            _ => Err(RedisError::from((
+                ErrorKind::TypeError,
+                "expected an array reply",
+                format!("{:?}", value),
+            ))),
+        }
+    }
+
+    /// Read the item at `index` as the field `name`. A missing item is read from `Nil`, so that
+    /// `Option` fields become `None`, and is an error for other types.
+    pub fn get<T: FromRedisValue>(&self, index: usize, name: &str) -> RedisResult<T> {
+        match self.0.get(index) {
+            Some(value) => read_field(value, name),
+            None => T::from_redis_value(&Value::Nil).map_err(|_| {
+                RedisError::from((ErrorKind::TypeError, "missing field", name.to_string()))
+            }),
+        }
+    }
+
+    /// Read the item at `index` as the field `name`, or the default of its type when it is missing
+    pub fn get_or_default<T: FromRedisValue + Default>(
+        &self,
+        index: usize,
+        name: &str,
+    ) -> RedisResult<T> {
+        match self.0.get(index) {
+            Some(value) => read_field(value, name),
+            None => Ok(T::default()),
+        }
+    }
+}
+
+/// Read the value of a field, naming the field when it has the wrong type
+fn read_field<T: FromRedisValue>(value: &Value, name: &str) -> RedisResult<T> {
+    T::from_redis_value(value).map_err(|err| {
+        RedisError::from((
+            ErrorKind::TypeError,
+            "invalid value of a field",
+            format!("`{}`: {}", name, err),
+        ))
+    })
+}
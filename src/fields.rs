@@ -0,0 +1,86 @@
+use redis::{RedisWrite, ToRedisArgs};
+use std::collections::{BTreeMap, HashMap};
+use std::hash::BuildHasher;
+
+/// A value that can be written as alternating field names and values, which is what commands
+/// like `HSET` and `XADD` expect. Values implementing it can be spread with `{*expr}`.
+///
+/// # Examples
+/// ```rust
+/// use redis::{RedisWrite, ToRedisArgs};
+/// use redis_rs_macro::{redis, ToRedisFields};
+///
+/// struct Event {
+///     kind: &'static str,
+///     user: u64,
+/// }
+///
+/// impl ToRedisFields for Event {
+///     fn write_redis_fields<W: ?Sized + RedisWrite>(&self, out: &mut W) {
+///         out.write_arg(b"kind");
+///         self.kind.write_redis_args(out);
+///         out.write_arg(b"user");
+///         self.user.write_redis_args(out);
+///     }
+/// }
+///
+/// let event = Event { kind: "login", user: 42 };
+/// redis!(XADD events * {*event});
+/// ```
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` cannot be spread into field-value pairs",
+    note = "`{{*expr}}` spreads values implementing `ToRedisFields`; write `{{(*expr)}}` to dereference instead"
+)]
+pub trait ToRedisFields {
+    /// Write every field name followed by its value
+    fn write_redis_fields<W: ?Sized + RedisWrite>(&self, out: &mut W);
+}
+
+impl<T: ToRedisFields + ?Sized> ToRedisFields for &T {
+    fn write_redis_fields<W: ?Sized + RedisWrite>(&self, out: &mut W) {
+        (**self).write_redis_fields(out)
+    }
+}
+
+impl<T: ToRedisFields + ?Sized> ToRedisFields for Box<T> {
+    fn write_redis_fields<W: ?Sized + RedisWrite>(&self, out: &mut W) {
+        (**self).write_redis_fields(out)
+    }
+}
+
+impl<K: ToRedisArgs, V: ToRedisArgs, S: BuildHasher> ToRedisFields for HashMap<K, V, S> {
+    fn write_redis_fields<W: ?Sized + RedisWrite>(&self, out: &mut W) {
+        for (field, value) in self {
+            field.write_redis_args(out);
+            value.write_redis_args(out);
+        }
+    }
+}
+
+impl<K: ToRedisArgs, V: ToRedisArgs> ToRedisFields for BTreeMap<K, V> {
+    fn write_redis_fields<W: ?Sized + RedisWrite>(&self, out: &mut W) {
+        for (field, value) in self {
+            field.write_redis_args(out);
+            value.write_redis_args(out);
+        }
+    }
+}
+
+/// The arguments of a `{*expr}` substitution
+pub struct Fields<'a, T: ?Sized>(&'a T);
+
+/// Wrap the value of a `{*expr}` substitution. Taking the bound here rather than on the
+/// `ToRedisArgs` impl reports a missing `ToRedisFields` impl directly.
+pub fn fields<T: ToRedisFields + ?Sized>(value: &T) -> Fields<'_, T> {
+    Fields(value)
+}
+
+impl<T: ToRedisFields + ?Sized> ToRedisArgs for Fields<'_, T> {
+    fn write_redis_args<W: ?Sized + RedisWrite>(&self, out: &mut W) {
+        self.0.write_redis_fields(out)
+    }
+
+    fn is_single_arg(&self) -> bool {
+        false
+    }
+}
@@ -0,0 +1,401 @@
+use crate::{redis_command_table, CommandMeta};
+use redis::{Cmd, Value};
+
+/// The commands the generator picks from: those of the command table whose keys are all at fixed
+/// positions. The keys of commands such as `EVAL` and `XREAD` depend on their other arguments.
+static COMMANDS: &[CommandMeta] = redis_command_table!();
+
+/// The most arguments added past the least number a command takes
+const MAX_EXTRA_ARGS: u64 = 4;
+
+/// The number of distinct keys, few enough that generated commands touch the same keys
+const KEYS: u64 = 8;
+
+/// Arguments that aren't keys, a mix of numbers and words
+const VALUES: &[&str] = &["0", "1", "-1", "10", "1.5", "value", "field", "a b", ""];
+
+/// Commands that reply with a status
+const STATUS_REPLIES: &[&str] = &[
+    "BGREWRITEAOF",
+    "BGSAVE",
+    "DISCARD",
+    "FLUSHALL",
+    "FLUSHDB",
+    "HMSET",
+    "LSET",
+    "LTRIM",
+    "MSET",
+    "MULTI",
+    "PFMERGE",
+    "PSETEX",
+    "QUIT",
+    "RENAME",
+    "RESET",
+    "RESTORE",
+    "SAVE",
+    "SELECT",
+    "SETEX",
+    "SWAPDB",
+    "TYPE",
+    "UNWATCH",
+    "WATCH",
+    "XSETID",
+    "ACL SETUSER",
+    "AUTH",
+    "CLIENT SETNAME",
+    "CONFIG RESETSTAT",
+    "CONFIG REWRITE",
+    "CONFIG SET",
+    "FUNCTION DELETE",
+    "FUNCTION FLUSH",
+    "SCRIPT FLUSH",
+    "SLOWLOG RESET",
+    "XGROUP CREATE",
+    "XGROUP SETID",
+];
+
+/// Commands that reply with an integer
+const INT_REPLIES: &[&str] = &[
+    "APPEND",
+    "BITCOUNT",
+    "BITOP",
+    "BITPOS",
+    "COPY",
+    "DBSIZE",
+    "DECR",
+    "DECRBY",
+    "DEL",
+    "EXISTS",
+    "EXPIRE",
+    "EXPIREAT",
+    "EXPIRETIME",
+    "GEOADD",
+    "GEOSEARCHSTORE",
+    "GETBIT",
+    "HDEL",
+    "HEXISTS",
+    "HINCRBY",
+    "HLEN",
+    "HSET",
+    "HSETNX",
+    "HSTRLEN",
+    "INCR",
+    "INCRBY",
+    "LASTSAVE",
+    "LINSERT",
+    "LLEN",
+    "LPUSH",
+    "LPUSHX",
+    "LREM",
+    "MSETNX",
+    "PERSIST",
+    "PEXPIRE",
+    "PEXPIREAT",
+    "PEXPIRETIME",
+    "PFADD",
+    "PFCOUNT",
+    "PTTL",
+    "PUBLISH",
+    "RENAMENX",
+    "RPUSH",
+    "RPUSHX",
+    "SADD",
+    "SCARD",
+    "SDIFFSTORE",
+    "SETBIT",
+    "SETNX",
+    "SETRANGE",
+    "SINTERSTORE",
+    "SISMEMBER",
+    "SMOVE",
+    "SREM",
+    "STRLEN",
+    "SUNIONSTORE",
+    "TOUCH",
+    "TTL",
+    "UNLINK",
+    "WAIT",
+    "XACK",
+    "XDEL",
+    "XLEN",
+    "XTRIM",
+    "ZCARD",
+    "ZCOUNT",
+    "ZLEXCOUNT",
+    "ZRANGESTORE",
+    "ZREM",
+    "ZREMRANGEBYLEX",
+    "ZREMRANGEBYRANK",
+    "ZREMRANGEBYSCORE",
+    "ACL DELUSER",
+    "CLIENT ID",
+    "SLOWLOG LEN",
+    "XGROUP CREATECONSUMER",
+    "XGROUP DELCONSUMER",
+    "XGROUP DESTROY",
+];
+
+/// Commands that reply with a bulk string, or nil
+const BULK_REPLIES: &[&str] = &[
+    "BLMOVE",
+    "BRPOPLPUSH",
+    "DUMP",
+    "ECHO",
+    "GEODIST",
+    "GET",
+    "GETDEL",
+    "GETEX",
+    "GETRANGE",
+    "GETSET",
+    "HGET",
+    "HINCRBYFLOAT",
+    "INCRBYFLOAT",
+    "INFO",
+    "LINDEX",
+    "LMOVE",
+    "RANDOMKEY",
+    "RPOPLPUSH",
+    "SUBSTR",
+    "XADD",
+    "ZINCRBY",
+    "ZSCORE",
+    "ACL WHOAMI",
+    "CLIENT GETNAME",
+    "CLIENT LIST",
+    "FUNCTION LOAD",
+    "OBJECT ENCODING",
+    "SCRIPT LOAD",
+];
+
+/// Commands that reply with an array, or nil
+const ARRAY_REPLIES: &[&str] = &[
+    "BITFIELD",
+    "BITFIELD_RO",
+    "BLPOP",
+    "BRPOP",
+    "BZPOPMAX",
+    "BZPOPMIN",
+    "EXEC",
+    "GEOHASH",
+    "GEOPOS",
+    "GEORADIUS",
+    "GEORADIUS_RO",
+    "GEORADIUSBYMEMBER",
+    "GEORADIUSBYMEMBER_RO",
+    "GEOSEARCH",
+    "HELLO",
+    "HEXPIRE",
+    "HGETALL",
+    "HKEYS",
+    "HMGET",
+    "HPEXPIRE",
+    "HSCAN",
+    "HTTL",
+    "HVALS",
+    "KEYS",
+    "LRANGE",
+    "MGET",
+    "PSUBSCRIBE",
+    "PUNSUBSCRIBE",
+    "ROLE",
+    "SCAN",
+    "SDIFF",
+    "SINTER",
+    "SMEMBERS",
+    "SMISMEMBER",
+    "SORT",
+    "SORT_RO",
+    "SSCAN",
+    "SUBSCRIBE",
+    "SUNION",
+    "TIME",
+    "UNSUBSCRIBE",
+    "XAUTOCLAIM",
+    "XCLAIM",
+    "XPENDING",
+    "XRANGE",
+    "XREVRANGE",
+    "ZMSCORE",
+    "ZPOPMAX",
+    "ZPOPMIN",
+    "ZRANGE",
+    "ZRANGEBYLEX",
+    "ZRANGEBYSCORE",
+    "ZREVRANGE",
+    "ZREVRANGEBYLEX",
+    "ZREVRANGEBYSCORE",
+    "ZSCAN",
+    "ACL LIST",
+    "CONFIG GET",
+    "FUNCTION LIST",
+    "SCRIPT EXISTS",
+    "SLOWLOG GET",
+    "XINFO CONSUMERS",
+    "XINFO GROUPS",
+    "XINFO STREAM",
+];
+
+/// The shape of the reply of a command, as in the RESP2 types that `redis::Value` holds
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ReplyShape {
+    /// A status such as `OK`
+    Status,
+    /// An integer
+    Int,
+    /// A bulk string, or nil
+    Bulk,
+    /// An array, or nil as for a blocking command that timed out
+    Array,
+    /// Depends on the arguments, such as `SET` with and without `GET`, or isn't known
+    Any,
+}
+
+impl ReplyShape {
+    /// The shape of the reply of a command in the command table, by its name
+    pub fn of(name: &str) -> ReplyShape {
+        let shapes = [
+            (STATUS_REPLIES, ReplyShape::Status),
+            (INT_REPLIES, ReplyShape::Int),
+            (BULK_REPLIES, ReplyShape::Bulk),
+            (ARRAY_REPLIES, ReplyShape::Array),
+        ];
+        shapes
+            .iter()
+            .find(|(names, _)| names.contains(&name))
+            .map_or(ReplyShape::Any, |(_, shape)| *shape)
+    }
+
+    /// Whether a reply has this shape
+    pub fn matches(&self, value: &Value) -> bool {
+        matches!(
+            (self, value),
+            (ReplyShape::Status, Value::Okay | Value::Status(_))
+                | (ReplyShape::Int, Value::Int(_))
+                | (ReplyShape::Bulk, Value::Data(_) | Value::Nil)
+                | (ReplyShape::Array, Value::Bulk(_) | Value::Nil)
+                | (ReplyShape::Any, _)
+        )
+    }
+
+    /// A reply of this shape, for a mock connection to answer with
+    pub fn example(&self) -> Value {
+        match self {
+            ReplyShape::Status => Value::Okay,
+            ReplyShape::Int => Value::Int(1),
+            ReplyShape::Bulk => Value::Data(b"value".to_vec()),
+            ReplyShape::Array => Value::Bulk(vec![Value::Data(b"value".to_vec())]),
+            ReplyShape::Any => Value::Nil,
+        }
+    }
+}
+
+/// A command made by [`CommandGen`], with the table entry it was made from and the shape of its
+/// reply
+#[derive(Clone)]
+pub struct GeneratedCmd {
+    /// The table entry of the command
+    pub meta: CommandMeta,
+    /// The command, with its name and arguments
+    pub cmd: Cmd,
+    /// The shape a reply to the command has
+    pub reply: ReplyShape,
+}
+
+/// An endless generator of random commands from the command table, with the arity of each
+/// command and its keys where the table puts them. The same seed always gives the same commands,
+/// so a `u64` strategy of proptest or quickcheck can drive it and report failing seeds.
+///
+/// The arguments that aren't keys are random numbers and words, so a server may reject the
+/// values of a generated command; the commands are meant for client-side wrappers and mocks.
+/// Commands whose keys depend on their other arguments, such as `EVAL` and `XREAD`, aren't
+/// generated.
+///
+/// # Examples
+/// ```rust
+/// use redis_rs_macro::CommandGen;
+///
+/// for generated in CommandGen::new(42).take(100) {
+///     assert!(generated.cmd.args_iter().count() >= generated.meta.arity.unsigned_abs() as usize);
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct CommandGen {
+    state: u64,
+}
+
+impl CommandGen {
+    /// A generator whose commands are determined by `seed`
+    pub fn new(seed: u64) -> CommandGen {
+        CommandGen { state: seed }
+    }
+
+    /// The commands the generator picks from
+    pub fn commands() -> impl Iterator<Item = &'static CommandMeta> {
+        COMMANDS.iter().filter(|meta| !meta.movable_keys)
+    }
+
+    /// The next number of the SplitMix64 sequence
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// A number below `n`
+    fn below(&mut self, n: u64) -> u64 {
+        self.next_u64() % n
+    }
+
+    /// Build a random command from its table entry
+    fn build(&mut self, meta: &CommandMeta) -> Cmd {
+        let mut len = meta.arity.unsigned_abs() as usize;
+        if meta.arity < 0 {
+            len += self.below(MAX_EXTRA_ARGS + 1) as usize;
+            // Repeated groups such as the key and value pairs of `MSET` must be whole
+            if meta.last_key < 0 && meta.step > 1 {
+                while !(len - meta.first_key).is_multiple_of(meta.step) {
+                    len += 1;
+                }
+            }
+        }
+        let last_key = if meta.last_key < 0 {
+            len as isize + meta.last_key
+        } else {
+            meta.last_key
+        };
+        let words: Vec<&str> = meta.name.split(' ').collect();
+        let mut cmd = Cmd::new();
+        for word in &words {
+            cmd.arg(*word);
+        }
+        for index in words.len()..len {
+            let is_key = meta.first_key > 0
+                && index >= meta.first_key
+                && index as isize <= last_key
+                && (index - meta.first_key).is_multiple_of(meta.step);
+            if is_key {
+                cmd.arg(format!("key:{}", self.below(KEYS)));
+            } else {
+                cmd.arg(VALUES[self.below(VALUES.len() as u64) as usize]);
+            }
+        }
+        cmd
+    }
+}
+
+impl Iterator for CommandGen {
+    type Item = GeneratedCmd;
+
+    fn next(&mut self) -> Option<GeneratedCmd> {
+        let count = CommandGen::commands().count() as u64;
+        let index = self.below(count) as usize;
+        let meta = *CommandGen::commands().nth(index)?;
+        Some(GeneratedCmd {
+            cmd: self.build(&meta),
+            reply: ReplyShape::of(meta.name),
+            meta,
+        })
+    }
+}
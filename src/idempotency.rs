@@ -0,0 +1,123 @@
+use crate::lock::{token, RELEASE, RELEASE_SHA1};
+use crate::{Args, ScriptCmd};
+use redis::{Cmd, ConnectionLike, FromRedisValue, RedisResult, ToRedisArgs, Value};
+
+/// Replace the claim with the response, only while the key still holds our claim
+const COMPLETE: &str = r#"if redis.call("GET", KEYS[1]) == ARGV[1] then
+    redis.call("SET", KEYS[1], ARGV[2], "EX", ARGV[3])
+    return 1
+end
+return 0"#;
+const COMPLETE_SHA1: &str = "58a07a7fe26768b7a4ab79d62198e9854c535621";
+
+/// The start of the value of a claimed key, which neither JSON nor MessagePack responses start
+/// with, so that a claim is never replayed as a response
+const PENDING: &[u8] = b"\0pending:";
+
+/// The outcome of a block run by [`redis_idempotent!`](crate::redis_idempotent)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Idempotent<T> {
+    /// The key was claimed and the block ran, returning this value
+    Executed(T),
+    /// The key was already claimed, so the block didn't run. Holds the stored response of the run
+    /// that claimed it, when responses are replayed and that run has finished.
+    Duplicate(Option<T>),
+}
+
+impl<T> Idempotent<T> {
+    /// Whether the block didn't run
+    pub fn is_duplicate(&self) -> bool {
+        matches!(self, Idempotent::Duplicate(_))
+    }
+}
+
+/// An idempotency key, claimed with `SET key claim NX EX ttl` before running an operation. Built
+/// by [`redis_idempotent!`](crate::redis_idempotent).
+///
+/// The claim holds a random token, so that completing or releasing it only touches the key while
+/// it still holds this claim, and not a claim taken by another run after this one expired.
+#[derive(Clone, Debug)]
+pub struct Idempotency {
+    key: Args,
+    claim: Vec<u8>,
+    ttl: u64,
+}
+
+impl Idempotency {
+    /// An idempotency key that expires after `ttl` seconds, with a new claim
+    pub fn new<K: ToRedisArgs>(key: K, ttl: u64) -> Idempotency {
+        let mut args = Args::new();
+        args.arg(key);
+        Idempotency {
+            key: args,
+            claim: [PENDING, token().as_bytes()].concat(),
+            ttl,
+        }
+    }
+
+    /// The `SET key claim NX EX ttl` command that claims the key, which replies `OK`, or `nil`
+    /// when it is already claimed
+    pub fn claim_cmd(&self) -> Cmd {
+        let mut cmd = redis::cmd("SET");
+        cmd.arg(&self.key)
+            .arg(&self.claim)
+            .arg("NX")
+            .arg("EX")
+            .arg(self.ttl);
+        cmd
+    }
+
+    /// The call of the script that replaces the claim with a serialized response, which replies 1
+    /// if the claim was still held
+    pub fn complete_cmd<T: ToRedisArgs>(&self, response: T) -> ScriptCmd {
+        let mut call = ScriptCmd::new(COMPLETE, COMPLETE_SHA1);
+        call.key(&self.key)
+            .arg(&self.claim)
+            .arg(response)
+            .arg(self.ttl);
+        call
+    }
+
+    /// The call of the script that deletes the key, so that the operation can be retried, which
+    /// replies 1 if the claim was still held
+    pub fn release_cmd(&self) -> ScriptCmd {
+        let mut call = ScriptCmd::new(RELEASE, RELEASE_SHA1);
+        call.key(&self.key).arg(&self.claim);
+        call
+    }
+
+    /// Claim the key, returning whether it was claimed
+    pub fn claim(&self, con: &mut dyn ConnectionLike) -> RedisResult<bool> {
+        let reply: Option<String> = self.claim_cmd().query(con)?;
+        Ok(reply.is_some())
+    }
+
+    /// Replace the claim with a serialized response, returning whether the claim was still held
+    pub fn complete<T: ToRedisArgs>(
+        &self,
+        con: &mut dyn ConnectionLike,
+        response: T,
+    ) -> RedisResult<bool> {
+        self.complete_cmd(response).invoke(con)
+    }
+
+    /// Delete the key, returning whether the claim was still held
+    pub fn release(&self, con: &mut dyn ConnectionLike) -> RedisResult<bool> {
+        self.release_cmd().invoke(con)
+    }
+
+    /// Read the response stored by the run that claimed the key, or `None` while it is running or
+    /// after the key expired
+    pub fn response<T: FromRedisValue>(
+        &self,
+        con: &mut dyn ConnectionLike,
+    ) -> RedisResult<Option<T>> {
+        let value: Option<Vec<u8>> = redis::cmd("GET").arg(&self.key).query(con)?;
+        match value {
+            Some(value) if !value.starts_with(PENDING) => {
+                T::from_redis_value(&Value::Data(value)).map(Some)
+            }
+            _ => Ok(None),
+        }
+    }
+}
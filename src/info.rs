@@ -0,0 +1,224 @@
+use redis::{ErrorKind, FromRedisValue, RedisError, RedisResult, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::str::FromStr;
+use std::time::Duration;
+
+/// The `field:value` lines of an `INFO` reply, without the `# Section` headers
+struct Fields(HashMap<String, String>);
+
+impl Fields {
+    fn parse(value: &Value) -> RedisResult<Fields> {
+        let text: String = FromRedisValue::from_redis_value(value)?;
+        let fields = text
+            .lines()
+            .filter(|line| !line.starts_with('#'))
+            .filter_map(|line| line.split_once(':'))
+            .map(|(field, value)| (field.to_owned(), value.trim().to_owned()))
+            .collect();
+        Ok(Fields(fields))
+    }
+
+    fn text(&self, field: &str) -> Option<String> {
+        self.0.get(field).cloned()
+    }
+
+    /// A number, or its default when the server doesn't send the field
+    fn number<T: FromStr + Default>(&self, field: &str) -> RedisResult<T> {
+        Ok(self.parsed(field)?.unwrap_or_default())
+    }
+
+    fn parsed<T: FromStr>(&self, field: &str) -> RedisResult<Option<T>> {
+        self.0
+            .get(field)
+            .map(|value| parse_number(field, value))
+            .transpose()
+    }
+}
+
+fn parse_number<T: FromStr>(field: &str, value: &str) -> RedisResult<T> {
+    value.parse().map_err(|_| {
+        RedisError::from((
+            ErrorKind::TypeError,
+            "expected a number in the INFO reply",
+            format!("{}:{}", field, value),
+        ))
+    })
+}
+
+/// The `key=value` pairs of a field such as `db0:keys=1,expires=0,avg_ttl=0`
+fn pairs(value: &str) -> HashMap<&str, &str> {
+    value
+        .split(',')
+        .filter_map(|pair| pair.split_once('='))
+        .collect()
+}
+
+/// The `memory` section of `INFO`, as returned by the query of
+/// [`redis_info!(memory)`](crate::redis_info). Sizes are in bytes.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MemoryInfo {
+    /// The memory allocated by Redis
+    pub used_memory: u64,
+    /// The memory that the operating system sees as used by Redis
+    pub used_memory_rss: u64,
+    /// The most memory that Redis has allocated
+    pub used_memory_peak: u64,
+    /// The memory used by the Lua engine
+    pub used_memory_lua: u64,
+    /// The `maxmemory` setting, where 0 means no limit
+    pub maxmemory: u64,
+    /// The `maxmemory-policy` setting, such as `allkeys-lru`
+    pub maxmemory_policy: Option<String>,
+    /// `used_memory_rss` over `used_memory`
+    pub mem_fragmentation_ratio: f64,
+}
+
+impl FromRedisValue for MemoryInfo {
+    fn from_redis_value(value: &Value) -> RedisResult<MemoryInfo> {
+        let fields = Fields::parse(value)?;
+        Ok(MemoryInfo {
+            used_memory: fields.number("used_memory")?,
+            used_memory_rss: fields.number("used_memory_rss")?,
+            used_memory_peak: fields.number("used_memory_peak")?,
+            used_memory_lua: fields.number("used_memory_lua")?,
+            maxmemory: fields.number("maxmemory")?,
+            maxmemory_policy: fields.text("maxmemory_policy"),
+            mem_fragmentation_ratio: fields.number("mem_fragmentation_ratio")?,
+        })
+    }
+}
+
+/// The `replication` section of `INFO`, as returned by the query of
+/// [`redis_info!(replication)`](crate::redis_info)
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ReplicationInfo {
+    /// `master`, or `slave` for a replica
+    pub role: String,
+    /// The replicas connected to a master
+    pub connected_replicas: u64,
+    /// The replication offset of the server
+    pub master_repl_offset: u64,
+    /// The host of the master of a replica
+    pub master_host: Option<String>,
+    /// The port of the master of a replica
+    pub master_port: Option<u16>,
+    /// Whether a replica is connected to its master
+    pub master_link_up: Option<bool>,
+    /// The replicas of a master, from its `slave0`, `slave1`, ... fields
+    pub replicas: Vec<ReplicaInfo>,
+}
+
+/// A replica in the `replication` section of the `INFO` of its master
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ReplicaInfo {
+    pub ip: String,
+    pub port: u16,
+    /// `online`, or the state of the initial sync, such as `wait_bgsave`
+    pub state: String,
+    /// The replication offset that the replica acknowledged
+    pub offset: u64,
+    /// The seconds since the last acknowledgement of the replica
+    pub lag: u64,
+}
+
+impl ReplicationInfo {
+    /// Whether the server is a master
+    pub fn is_master(&self) -> bool {
+        self.role == "master"
+    }
+}
+
+impl FromRedisValue for ReplicationInfo {
+    fn from_redis_value(value: &Value) -> RedisResult<ReplicationInfo> {
+        let fields = Fields::parse(value)?;
+        let mut replicas = vec![];
+        while let Some(replica) = fields.text(&format!("slave{}", replicas.len())) {
+            let pairs = pairs(&replica);
+            let get = |key: &str| pairs.get(key).copied().unwrap_or_default();
+            let number = |key: &str| parse_number::<u64>(key, pairs.get(key).unwrap_or(&"0"));
+            replicas.push(ReplicaInfo {
+                ip: get("ip").to_owned(),
+                port: parse_number("port", get("port"))?,
+                state: get("state").to_owned(),
+                offset: number("offset")?,
+                lag: number("lag")?,
+            });
+        }
+        Ok(ReplicationInfo {
+            role: fields.text("role").unwrap_or_default(),
+            connected_replicas: fields.number("connected_slaves")?,
+            master_repl_offset: fields.number("master_repl_offset")?,
+            master_host: fields.text("master_host"),
+            master_port: fields.parsed("master_port")?,
+            master_link_up: fields
+                .text("master_link_status")
+                .map(|status| status == "up"),
+            replicas,
+        })
+    }
+}
+
+/// The `clients` section of `INFO`, as returned by the query of
+/// [`redis_info!(clients)`](crate::redis_info)
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ClientsInfo {
+    /// The connected clients, without the connections of replicas
+    pub connected_clients: u64,
+    /// The clients waiting on a blocking command, such as `BLPOP`
+    pub blocked_clients: u64,
+    /// The clients with client side caching turned on
+    pub tracking_clients: u64,
+    /// The `maxclients` setting
+    pub maxclients: u64,
+}
+
+impl FromRedisValue for ClientsInfo {
+    fn from_redis_value(value: &Value) -> RedisResult<ClientsInfo> {
+        let fields = Fields::parse(value)?;
+        Ok(ClientsInfo {
+            connected_clients: fields.number("connected_clients")?,
+            blocked_clients: fields.number("blocked_clients")?,
+            tracking_clients: fields.number("tracking_clients")?,
+            maxclients: fields.number("maxclients")?,
+        })
+    }
+}
+
+/// The `keyspace` section of `INFO`, as returned by the query of
+/// [`redis_info!(keyspace)`](crate::redis_info), by database index. Databases without keys aren't
+/// listed.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct KeyspaceInfo {
+    pub dbs: BTreeMap<u32, DbKeyspace>,
+}
+
+/// A database in the `keyspace` section of `INFO`
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DbKeyspace {
+    pub keys: u64,
+    /// The keys with a TTL
+    pub expires: u64,
+    /// An estimate of the average TTL of the keys with one
+    pub avg_ttl: Duration,
+}
+
+impl FromRedisValue for KeyspaceInfo {
+    fn from_redis_value(value: &Value) -> RedisResult<KeyspaceInfo> {
+        let Fields(fields) = Fields::parse(value)?;
+        let mut dbs = BTreeMap::new();
+        for (field, value) in &fields {
+            let Some(index) = field.strip_prefix("db") else {
+                continue;
+            };
+            let pairs = pairs(value);
+            let number = |key: &str| parse_number::<u64>(key, pairs.get(key).unwrap_or(&"0"));
+            let db = DbKeyspace {
+                keys: number("keys")?,
+                expires: number("expires")?,
+                avg_ttl: Duration::from_millis(number("avg_ttl")?),
+            };
+            dbs.insert(parse_number(field, index)?, db);
+        }
+        Ok(KeyspaceInfo { dbs })
+    }
+}
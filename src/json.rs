@@ -0,0 +1,81 @@
+use redis::{
+    ConnectionLike, ErrorKind, FromRedisValue, RedisError, RedisResult, ToRedisArgs, Value,
+};
+pub use serde::de::DeserializeOwned;
+pub use serde::Serialize;
+
+/// Serialize the value of a `{json expr}` substitution
+pub fn to_string<T: Serialize + ?Sized>(value: &T) -> RedisResult<String> {
+    serde_json::to_string(value).map_err(|err| {
+        RedisError::from((
+            ErrorKind::TypeError,
+            "failed to serialize value as JSON",
+            err.to_string(),
+        ))
+    })
+}
+
+/// Store `value` at `path` of the RedisJSON document at `key`, for `#[derive(RedisJson)]` types
+pub fn json_set<C, K, T>(con: &mut C, key: K, path: &str, value: &T) -> RedisResult<()>
+where
+    C: ConnectionLike,
+    K: ToRedisArgs,
+    T: Serialize + ?Sized,
+{
+    redis::cmd("JSON.SET")
+        .arg(key)
+        .arg(path)
+        .arg(to_string(value)?)
+        .query(con)
+}
+
+/// Read the value at `path` of the RedisJSON document at `key`, for `#[derive(RedisJson)]` types.
+/// A JSONPath starting with `$` replies with an array of the values it matches, of which the first
+/// is read, while a legacy path replies with the value itself.
+pub fn json_get<C, K, T>(con: &mut C, key: K, path: &str) -> RedisResult<Option<T>>
+where
+    C: ConnectionLike,
+    K: ToRedisArgs,
+    T: DeserializeOwned,
+{
+    let reply: Option<Vec<u8>> = redis::cmd("JSON.GET").arg(key).arg(path).query(con)?;
+    let Some(data) = reply else {
+        return Ok(None);
+    };
+    let parse_error = |err: serde_json::Error| {
+        RedisError::from((
+            ErrorKind::TypeError,
+            "failed to deserialize value as JSON",
+            err.to_string(),
+        ))
+    };
+    if path.starts_with('$') {
+        let matches: Vec<T> = serde_json::from_slice(&data).map_err(parse_error)?;
+        return Ok(matches.into_iter().next());
+    }
+    serde_json::from_slice(&data).map(Some).map_err(parse_error)
+}
+
+/// A reply that is read by deserializing JSON, such as a message payload of
+/// [`redis_subscribe!`](crate::redis_subscribe)
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Json<T>(pub T);
+
+impl<T: DeserializeOwned> FromRedisValue for Json<T> {
+    fn from_redis_value(value: &Value) -> RedisResult<Json<T>> {
+        let Value::Data(data) = value else {
+            return Err(RedisError::from((
+                ErrorKind::TypeError,
+                "expected JSON data",
+                format!("{:?}", value),
+            )));
+        };
+        serde_json::from_slice(data).map(Json).map_err(|err| {
+            RedisError::from((
+                ErrorKind::TypeError,
+                "failed to deserialize value as JSON",
+                err.to_string(),
+            ))
+        })
+    }
+}
@@ -0,0 +1,63 @@
+use redis::{RedisWrite, ToRedisArgs};
+use std::fmt;
+use std::ops::Deref;
+
+/// A key built by [`redis_key!`](crate::redis_key), whose literal parts were checked against the
+/// key naming convention at compile time
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Key(String);
+
+impl Key {
+    /// The key as a string slice
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Take the key out as a `String`
+    pub fn into_string(self) -> String {
+        self.0
+    }
+}
+
+/// Wrap the formatted text of a `redis_key!` invocation
+pub fn new(key: String) -> Key {
+    Key(key)
+}
+
+impl Deref for Key {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<str> for Key {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<[u8]> for Key {
+    fn as_ref(&self) -> &[u8] {
+        self.0.as_bytes()
+    }
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl From<Key> for String {
+    fn from(key: Key) -> String {
+        key.0
+    }
+}
+
+impl ToRedisArgs for Key {
+    fn write_redis_args<W: ?Sized + RedisWrite>(&self, out: &mut W) {
+        out.write_arg(self.0.as_bytes());
+    }
+}
@@ -0,0 +1,177 @@
+use crate::subscribe::{HandlerResult, Subscriber};
+use redis::{Connection, ConnectionLike, Msg, RedisResult};
+
+/// The notify-keyspace-events flags that the `A` flag stands for
+const ALL_FLAGS: &str = "g$lshzxetd";
+
+macro_rules! key_events {
+    ($($flag:literal => [$($variant:ident = $name:literal),* $(,)?],)*) => {
+        /// An event of keyspace notifications, named as in the `__keyevent@<db>__:<event>` channels
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+        #[non_exhaustive]
+        pub enum KeyEventKind {
+            $($($variant,)*)*
+        }
+
+        impl KeyEventKind {
+            /// The name of the event in its channel, e.g. `expired`
+            pub fn name(self) -> &'static str {
+                match self {
+                    $($(KeyEventKind::$variant => $name,)*)*
+                }
+            }
+
+            /// The event with the given channel name
+            pub fn from_name(name: &str) -> Option<KeyEventKind> {
+                match name {
+                    $($($name => Some(KeyEventKind::$variant),)*)*
+                    _ => None,
+                }
+            }
+
+            /// The notify-keyspace-events flag of the class of the event
+            fn flag(self) -> char {
+                match self {
+                    $($(KeyEventKind::$variant => $flag,)*)*
+                }
+            }
+        }
+    };
+}
+
+key_events! {
+    'g' => [
+        Del = "del", Expire = "expire", RenameFrom = "rename_from", RenameTo = "rename_to",
+        MoveFrom = "move_from", MoveTo = "move_to", CopyTo = "copy_to", Restore = "restore",
+        Persist = "persist", Sortstore = "sortstore",
+    ],
+    '$' => [
+        Set = "set", Setrange = "setrange", Incrby = "incrby", Incrbyfloat = "incrbyfloat",
+        Append = "append",
+    ],
+    'l' => [
+        Lpush = "lpush", Rpush = "rpush", Lpop = "lpop", Rpop = "rpop", Linsert = "linsert",
+        Lset = "lset", Lrem = "lrem", Ltrim = "ltrim",
+    ],
+    's' => [
+        Sadd = "sadd", Srem = "srem", Spop = "spop", Sinterstore = "sinterstore",
+        Sunionstore = "sunionstore", Sdiffstore = "sdiffstore",
+    ],
+    'h' => [Hset = "hset", Hincrby = "hincrby", Hincrbyfloat = "hincrbyfloat", Hdel = "hdel"],
+    'z' => [
+        Zincr = "zincr", Zadd = "zadd", Zrem = "zrem", Zrembyscore = "zrembyscore",
+        Zrembyrank = "zrembyrank", Zdiffstore = "zdiffstore", Zinterstore = "zinterstore",
+        Zunionstore = "zunionstore",
+    ],
+    't' => [
+        Xadd = "xadd", Xdel = "xdel", XgroupCreate = "xgroup-create",
+        XgroupCreateconsumer = "xgroup-createconsumer", XgroupDelconsumer = "xgroup-delconsumer",
+        XgroupDestroy = "xgroup-destroy", XgroupSetid = "xgroup-setid", Xsetid = "xsetid",
+        Xtrim = "xtrim",
+    ],
+    'x' => [Expired = "expired"],
+    'e' => [Evicted = "evicted"],
+    'm' => [Keymiss = "keymiss"],
+    'n' => [New = "new"],
+}
+
+/// A keyspace notification received by [`redis_keyevents!`](crate::redis_keyevents)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KeyEvent {
+    /// The database of the key
+    pub db: i64,
+    pub kind: KeyEventKind,
+    /// The key the event happened to
+    pub key: String,
+}
+
+/// A subscription to the keyspace notifications of a database, built by
+/// [`redis_keyevents!`](crate::redis_keyevents)
+#[derive(Clone, Debug)]
+pub struct KeyEvents {
+    db: i64,
+    kinds: Vec<KeyEventKind>,
+}
+
+impl KeyEvents {
+    /// Subscribe to the events of `kinds` in the database `db`
+    pub fn new(db: i64, kinds: &[KeyEventKind]) -> KeyEvents {
+        KeyEvents {
+            db,
+            kinds: kinds.to_vec(),
+        }
+    }
+
+    /// The `__keyevent@<db>__:<event>` channels of the events
+    pub fn channels(&self) -> Vec<String> {
+        self.kinds
+            .iter()
+            .map(|kind| format!("__keyevent@{}__:{}", self.db, kind.name()))
+            .collect()
+    }
+
+    /// The notify-keyspace-events flags the server needs to send the events
+    pub fn flags(&self) -> String {
+        let mut flags = String::from("E");
+        for kind in &self.kinds {
+            if !flags.contains(kind.flag()) {
+                flags.push(kind.flag());
+            }
+        }
+        flags
+    }
+
+    /// Add the flags the events need to the notify-keyspace-events setting of the server, keeping
+    /// the flags that are already set. Nothing is sent when every flag is already set.
+    pub fn enable(&self, con: &mut dyn ConnectionLike) -> RedisResult<()> {
+        let config: Vec<String> = redis::cmd("CONFIG")
+            .arg("GET")
+            .arg("notify-keyspace-events")
+            .query(con)?;
+        let current = config.get(1).map_or("", String::as_str);
+        let set = |flag: char| {
+            current.contains(flag) || (current.contains('A') && ALL_FLAGS.contains(flag))
+        };
+        let missing: String = self.flags().chars().filter(|flag| !set(*flag)).collect();
+        if missing.is_empty() {
+            return Ok(());
+        }
+        redis::cmd("CONFIG")
+            .arg("SET")
+            .arg("notify-keyspace-events")
+            .arg(format!("{}{}", current, missing))
+            .query(con)
+    }
+
+    /// A subscription that passes every event to `handler`
+    pub fn subscriber<'a, R: HandlerResult>(
+        &self,
+        mut handler: impl FnMut(KeyEvent) -> R + 'a,
+    ) -> Subscriber<'a> {
+        let db = self.db;
+        let handler = move |msg: &Msg| {
+            let channel = msg.get_channel_name();
+            let kind = channel
+                .rsplit_once(':')
+                .and_then(|(_, name)| KeyEventKind::from_name(name));
+            // Only channels of known events are subscribed to
+            let Some(kind) = kind else {
+                return Ok(std::ops::ControlFlow::Continue(()));
+            };
+            let key = msg.get_payload()?;
+            Ok(handler(KeyEvent { db, kind, key }).into_flow())
+        };
+        let channels = self.channels().into_iter().map(String::into_bytes);
+        Subscriber::new().on_message(channels.collect(), Box::new(handler))
+    }
+
+    /// Pass every event to `handler` until it returns `ControlFlow::Break`, as with
+    /// [`Subscriber::run`]
+    pub fn run<R: HandlerResult>(
+        &self,
+        con: &mut Connection,
+        handler: impl FnMut(KeyEvent) -> R,
+    ) -> RedisResult<()> {
+        self.subscriber(handler).run(con)
+    }
+}
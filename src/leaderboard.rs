@@ -0,0 +1,108 @@
+use crate::Args;
+use redis::{Cmd, ConnectionLike, FromRedisValue, RedisResult, ToRedisArgs};
+
+/// Which end of a [`Leaderboard`] is the top
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Order {
+    /// The highest score is the top, such as for points
+    Descending,
+    /// The lowest score is the top, such as for times
+    Ascending,
+}
+
+/// A leaderboard in a sorted set, built by [`redis_leaderboard!`](crate::redis_leaderboard). Its
+/// commands pick the `REV` variant of `ZRANGE` and `ZRANK` from its order, so that the top and the
+/// ranks always start from the best score.
+#[derive(Clone, Debug)]
+pub struct Leaderboard {
+    key: Args,
+    order: Order,
+}
+
+impl Leaderboard {
+    /// The leaderboard at `key`
+    pub fn new<K: ToRedisArgs>(key: K, order: Order) -> Leaderboard {
+        let mut args = Args::new();
+        args.arg(key);
+        Leaderboard { key: args, order }
+    }
+
+    /// The `ZADD` command that sets the score of a member
+    pub fn add_cmd<M: ToRedisArgs>(&self, member: M, score: f64) -> Cmd {
+        let mut cmd = redis::cmd("ZADD");
+        cmd.arg(&self.key).arg(score).arg(member);
+        cmd
+    }
+
+    /// The `ZREVRANGE` or `ZRANGE` command that replies the best `n` members with their scores,
+    /// best first. `n` must be at least 1, since a range that stops at -1 is the whole set.
+    pub fn top_cmd(&self, n: usize) -> Cmd {
+        let mut cmd = match self.order {
+            Order::Descending => redis::cmd("ZREVRANGE"),
+            Order::Ascending => redis::cmd("ZRANGE"),
+        };
+        cmd.arg(&self.key)
+            .arg(0)
+            .arg(n as i64 - 1)
+            .arg("WITHSCORES");
+        cmd
+    }
+
+    /// The `ZREVRANK` or `ZRANK` command that replies the rank of a member, or `nil` if it isn't on
+    /// the leaderboard
+    pub fn rank_cmd<M: ToRedisArgs>(&self, member: M) -> Cmd {
+        let mut cmd = match self.order {
+            Order::Descending => redis::cmd("ZREVRANK"),
+            Order::Ascending => redis::cmd("ZRANK"),
+        };
+        cmd.arg(&self.key).arg(member);
+        cmd
+    }
+
+    /// The `ZSCORE` command that replies the score of a member
+    pub fn score_cmd<M: ToRedisArgs>(&self, member: M) -> Cmd {
+        let mut cmd = redis::cmd("ZSCORE");
+        cmd.arg(&self.key).arg(member);
+        cmd
+    }
+
+    /// Set the score of a member
+    pub fn add<M: ToRedisArgs>(
+        &self,
+        con: &mut dyn ConnectionLike,
+        member: M,
+        score: f64,
+    ) -> RedisResult<()> {
+        self.add_cmd(member, score).query(con)
+    }
+
+    /// The best `n` members with their scores, best first
+    pub fn top<M: FromRedisValue>(
+        &self,
+        con: &mut dyn ConnectionLike,
+        n: usize,
+    ) -> RedisResult<Vec<(M, f64)>> {
+        if n == 0 {
+            return Ok(vec![]);
+        }
+        self.top_cmd(n).query(con)
+    }
+
+    /// The rank of a member, where the best member is 0, or `None` if it isn't on the leaderboard
+    pub fn rank<M: ToRedisArgs>(
+        &self,
+        con: &mut dyn ConnectionLike,
+        member: M,
+    ) -> RedisResult<Option<u64>> {
+        self.rank_cmd(member).query(con)
+    }
+
+    /// The score of a member, or `None` if it isn't on the leaderboard
+    pub fn score<M: ToRedisArgs>(
+        &self,
+        con: &mut dyn ConnectionLike,
+        member: M,
+    ) -> RedisResult<Option<f64>> {
+        self.score_cmd(member).query(con)
+    }
+}
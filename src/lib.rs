@@ -1,300 +1,173 @@
-use proc_macro::TokenStream;
-use quote::{quote, ToTokens};
-use std::mem;
-use syn::{parse_macro_input, parse_quote, Expr, LitStr};
+//! A macro to make creating Redis commands (from [redis-rs](https://github.com/redis-rs/redis-rs))
+//! more readable.
+//!
+//! See [`redis!`] for the command syntax.
 
-/// State used by the internal redis command lexer
-enum State {
-    Word,               // Inside unquoted word
-    DoubleQuote,        // Inside double quote
-    SplitMarker,        // Whitespace (Not \r)
-    EscapedDoubleQuote, // Inside double quote after backslash
-    Braced,             // Inside brace
-}
+// The macros name this crate by its path, also when used inside it
+extern crate self as redis_rs_macro;
 
-/// A single redis command argument.
-#[derive(Default, PartialEq, Debug)]
-struct CmdArg {
-    data: String,
-    is_quoted: bool,
-    is_braced: bool,
-}
+pub use args::Args;
+pub use consumer::{StreamConsumer, StreamEntry};
+pub use counter::Counter;
+pub use delay::DelayQueue;
+pub use fields::ToRedisFields;
+#[cfg(feature = "generator")]
+pub use generate::{CommandGen, GeneratedCmd, ReplyShape};
+pub use idempotency::{Idempotency, Idempotent};
+pub use info::{ClientsInfo, DbKeyspace, KeyspaceInfo, MemoryInfo, ReplicaInfo, ReplicationInfo};
+#[cfg(feature = "json")]
+pub use json::Json;
+pub use key::Key;
+pub use keyevents::{KeyEvent, KeyEventKind, KeyEvents};
+pub use leaderboard::{Leaderboard, Order};
+#[cfg(feature = "async")]
+pub use lock::AsyncLockGuard;
+pub use lock::{Lock, LockGuard};
+pub use meta::CommandMeta;
+#[cfg(feature = "msgpack")]
+pub use msgpack::Msgpack;
+pub use pipe::{ClusterPipeline, LabeledPipeline};
+#[cfg(feature = "key-prefix")]
+pub use prefix::{key_prefix, set_key_prefix};
+pub use priority::PriorityQueue;
+pub use queue::WorkQueue;
+pub use ratelimit::{RateLimit, RateLimitStatus, Window};
+#[cfg(feature = "record")]
+pub use record::{Recorder, Replay};
+#[cfg(feature = "test-server")]
+pub use redis_rs_macro_impl::redis_integration_test;
+#[cfg(feature = "json")]
+pub use redis_rs_macro_impl::RedisJson;
+pub use redis_rs_macro_impl::{
+    redis, redis_args, redis_async, redis_batch, redis_bloom, redis_cache, redis_cached,
+    redis_command_table, redis_commands, redis_config_notify, redis_consume, redis_counter,
+    redis_def, redis_delay_queue, redis_eval, redis_exec, redis_functions, redis_idempotent,
+    redis_info, redis_key, redis_keyevents, redis_keyslot, redis_leaderboard, redis_lock,
+    redis_lock_async, redis_meta, redis_mock, redis_namespace, redis_pipe, redis_priority_queue,
+    redis_publish, redis_ratelimit, redis_readonly, redis_retry, redis_retry_async, redis_routed,
+    redis_scan, redis_scan_async, redis_script, redis_search, redis_sentinel, redis_slowlog,
+    redis_subscribe, redis_template, redis_tenant, redis_transaction, redis_url, redis_work_queue,
+    RedisArg, RedisHash, RedisReply, RedisSearch, RedisStreamEntry,
+};
+pub use retry::RetryPolicy;
+pub use route::{key_slot, Route};
+pub use scan::Scan;
+pub use script::ScriptCmd;
+pub use search::{AggregateResults, SearchCmd, SearchDoc, SearchResults};
+pub use sentinel::Sentinel;
+#[cfg(feature = "test-server")]
+pub use server::TestServer;
+pub use slowlog::SlowlogEntry;
+pub use subscribe::{HandlerResult, Subscriber};
+pub use time::Timestamp;
+pub use timeseries::{Samples, Series};
+pub use typed::TypedCmd;
 
-/// Split an input string by whitespace, except if enclosed by "double quotes" or
-/// {curly braces}
-fn split_input(input: &str) -> Vec<CmdArg> {
-    let mut chars = input.chars();
-    let mut output: Vec<CmdArg> = vec![];
-    let mut current_word = CmdArg::default();
-    let mut state = State::SplitMarker;
-    loop {
-        let cur = chars.next();
-        state = match state {
-            State::Word => match cur {
-                None => {
-                    output.push(CmdArg {
-                        data: mem::replace(&mut current_word.data, String::new()),
-                        is_quoted: current_word.is_quoted,
-                        is_braced: current_word.is_braced,
-                    });
-                    current_word.is_quoted = false;
-                    current_word.is_braced = false;
-                    break;
-                }
-                Some('\t') | Some(' ') | Some('\n') => {
-                    output.push(CmdArg {
-                        data: mem::replace(&mut current_word.data, String::new()),
-                        is_quoted: current_word.is_quoted,
-                        is_braced: current_word.is_braced,
-                    });
-                    current_word.is_quoted = false;
-                    current_word.is_braced = false;
-                    State::SplitMarker
-                }
-                Some(c) => {
-                    current_word.data.push(c);
-                    State::Word
-                }
-            },
-            State::SplitMarker => match cur {
-                Some('\t') | Some(' ') | Some('\n') => State::SplitMarker,
-                Some('\"') => {
-                    current_word.is_quoted = true;
-                    State::DoubleQuote
-                }
-                Some('{') => {
-                    current_word.is_braced = true;
-                    State::Braced
-                }
-                Some(c) => {
-                    current_word.data.push(c);
-                    State::Word
-                }
-                _ => break,
-            },
-            State::DoubleQuote => match cur {
-                // Shouldn't ever happen. Macro syntax is invalid if there is an unclosed double quote
-                None => panic!("incomplete quoted value"),
-                Some('"') => State::Word,
-                Some('\\') => State::EscapedDoubleQuote,
-                Some(c) => {
-                    current_word.data.push(c);
-                    State::DoubleQuote
-                }
-            },
-            State::EscapedDoubleQuote => match cur {
-                // Shouldn't ever happen. Macro syntax is invalid if there is nothing after the backslash
-                None => panic!("invalid escape sequence"),
-                Some(cur) => {
-                    current_word.data.push('\\');
-                    current_word.data.push(cur);
-                    State::DoubleQuote
-                }
-            },
-            State::Braced => match cur {
-                // Shouldn't ever happen. Macro syntax is invalid if there is an unclosed brace
-                None => panic!("unclosed brace"),
-                Some('}') => State::Word,
-                Some(cur) => {
-                    current_word.data.push(cur);
-                    State::Braced
-                }
-            },
-        };
+mod args;
+mod bytes;
+mod cache;
+mod consumer;
+mod counter;
+mod csv;
+mod delay;
+mod entry;
+mod fields;
+#[cfg(feature = "generator")]
+mod generate;
+mod idempotency;
+mod info;
+#[cfg(feature = "json")]
+mod json;
+mod key;
+mod keyevents;
+mod leaderboard;
+mod lock;
+mod meta;
+#[cfg(feature = "msgpack")]
+mod msgpack;
+mod namespace;
+mod pipe;
+#[cfg(feature = "key-prefix")]
+mod prefix;
+mod priority;
+mod queue;
+mod ratelimit;
+#[cfg(feature = "record")]
+mod record;
+mod retry;
+mod route;
+mod scan;
+mod scores;
+mod script;
+mod search;
+mod sentinel;
+#[cfg(feature = "test-server")]
+mod server;
+mod slowlog;
+mod subscribe;
+mod time;
+mod timeseries;
+mod typed;
+
+/// Runtime support for the code generated by the macros. Not part of the public API.
+#[doc(hidden)]
+pub mod __private {
+    pub mod bytes {
+        pub use crate::bytes::as_bytes;
     }
-    output
-}
 
-/// Generate a redis::cmd object using syntax as if from redis-cli
-///
-/// # Examples
-/// ## Writing a command
-/// The most basic usage of the macro is as seen below.
-/// ```rust
-/// use redis_rs_macro::redis;
-/// redis!(SET my_key my_value 1);
-/// ```
-/// In the above example, my_key, my_value, and 1, are all passed into the .arg function of
-/// redis::cmd as if they were literal strings.
-/// ## Expansion
-/// ```rust
-/// redis::cmd("SET").arg("my_key").arg("my_value").arg("1");
-/// ```
-/// ## Quoting
-/// If any of the above arguments contain whitespace, but should be treated as a single argument,
-/// use whitespace to capture the entire sequence.
-/// ```rust
-/// use redis_rs_macro::redis;
-/// redis!(SET "my key" my_value 1);
-/// ```
-/// ## Expansion
-/// ```rust
-/// redis::cmd("SET").arg("my key").arg("my_value").arg("1");
-/// ```
-/// ## Substitution
-/// You can also substitue Rust expressions into .arg or cmd constructor if
-/// you have dynamic data. This is done by enclosing the expression in curly braces.
-/// ```rust
-/// use redis_rs_macro::redis;
-/// let x = 1;
-/// redis!(SET my_key my_value {x});
-/// ```
-/// ## Expansion
-/// ```rust
-/// let x = 1;
-/// redis::cmd("SET").arg("my_key").arg("my_value").arg(x);
-/// ```
-#[proc_macro]
-pub fn redis(tokens: TokenStream) -> TokenStream {
-    let token_str = tokens.to_string();
-    let split_input = split_input(token_str.as_str());
-    if split_input.is_empty() {
-        return TokenStream::new();
+    pub mod cache {
+        pub use crate::cache::output;
     }
 
-    let mut args: Vec<Expr> = vec![];
-    for arg in split_input.into_iter() {
-        if arg.is_quoted {
-            let data: proc_macro::TokenStream = arg.data.to_token_stream().into();
-            let litstr = parse_macro_input!(data as LitStr);
-            args.push(parse_quote!(#litstr));
-        } else {
-            if arg.is_braced {
-                let expr: Expr = match syn::parse_str::<Expr>(&arg.data) {
-                    Ok(expr) => expr,
-                    Err(err) => {
-                        return TokenStream::from(err.to_compile_error());
-                    }
-                };
-                args.push(expr);
-            } else {
-                let strm = arg.data.to_token_stream();
-                args.push(parse_quote!(#strm));
-            }
-        }
+    pub mod csv {
+        pub use crate::csv::join;
     }
-    let cmd = &args[0];
-    let additional_args = &args[1..];
-    quote! {
-        redis::cmd(#cmd)#(.arg(#additional_args))*
+
+    pub mod entry {
+        pub use crate::entry::{write_field, EntryFields, ReplyItems};
     }
-    .into()
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    pub mod fields {
+        pub use crate::fields::fields;
+    }
+
+    #[cfg(feature = "json")]
+    pub mod json {
+        pub use crate::json::{json_get, json_set, to_string, DeserializeOwned, Serialize};
+    }
+
+    pub mod key {
+        pub use crate::key::new;
+    }
+
+    #[cfg(feature = "msgpack")]
+    pub mod msgpack {
+        pub use crate::msgpack::to_vec;
+    }
 
-    fn split_(cases: &[(&str, &[CmdArg])]) {
-        for &(input, expected) in cases {
-            let output: Vec<CmdArg> = split_input(input);
-            assert!(
-                expected == output.as_slice(),
-                "Input: {:?}\nExpected: {:?}\nBut found: {:?}",
-                input,
-                expected,
-                output
-            );
-        }
+    pub mod namespace {
+        pub use crate::namespace::{key, keys, pairs};
     }
 
-    #[test]
-    fn split_empty() {
-        split_(&[("", &[])]);
+    #[cfg(feature = "key-prefix")]
+    pub mod prefix {
+        pub use crate::prefix::{key, pair};
     }
 
-    #[test]
-    fn split_leading_ws() {
-        split_(&[
-            (
-                " abcd",
-                &[CmdArg {
-                    data: "abcd".into(),
-                    is_quoted: false,
-                    is_braced: false,
-                }],
-            ),
-            (
-                "\tabcd",
-                &[CmdArg {
-                    data: "abcd".into(),
-                    is_quoted: false,
-                    is_braced: false,
-                }],
-            ),
-            (
-                "\nabcd",
-                &[CmdArg {
-                    data: "abcd".into(),
-                    is_quoted: false,
-                    is_braced: false,
-                }],
-            ),
-            (
-                " \n\tabcd",
-                &[CmdArg {
-                    data: "abcd".into(),
-                    is_quoted: false,
-                    is_braced: false,
-                }],
-            ),
-        ]);
+    pub mod retry {
+        pub use crate::retry::sleep;
     }
 
-    #[test]
-    fn split_normal() {
-        split_(&[(
-            "abcd 123",
-            &[
-                CmdArg {
-                    data: "abcd".into(),
-                    is_quoted: false,
-                    is_braced: false,
-                },
-                CmdArg {
-                    data: "123".into(),
-                    is_quoted: false,
-                    is_braced: false,
-                },
-            ],
-        )])
+    pub mod scores {
+        pub use crate::scores::scores;
     }
 
-    #[test]
-    fn split_dquotes() {
-        split_(&[(
-            "\"abcd 123\" abcd",
-            &[
-                CmdArg {
-                    data: "abcd 123".into(),
-                    is_quoted: true,
-                    is_braced: false,
-                },
-                CmdArg {
-                    data: "abcd".into(),
-                    is_quoted: false,
-                    is_braced: false,
-                },
-            ],
-        )]);
+    pub mod search {
+        pub use crate::search::Layout;
     }
 
-    #[test]
-    fn split_brackets() {
-        split_(&[(
-            "{abcd 123} abcd",
-            &[
-                CmdArg {
-                    data: "abcd 123".into(),
-                    is_quoted: false,
-                    is_braced: true,
-                },
-                CmdArg {
-                    data: "abcd".into(),
-                    is_quoted: false,
-                    is_braced: false,
-                },
-            ],
-        )]);
+    pub mod time {
+        pub use crate::time::{ArgsKind, DurationKind, TimestampKind};
     }
 }
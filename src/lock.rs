@@ -0,0 +1,217 @@
+use crate::{Args, ScriptCmd};
+use redis::{Cmd, ConnectionLike, RedisResult, ToRedisArgs};
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::ops::{Deref, DerefMut};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::SystemTime;
+
+/// Delete the lock only while it still holds our token, so that a lock that expired and was taken
+/// by another process isn't released
+pub(crate) const RELEASE: &str = r#"if redis.call("GET", KEYS[1]) == ARGV[1] then
+    return redis.call("DEL", KEYS[1])
+end
+return 0"#;
+pub(crate) const RELEASE_SHA1: &str = "85a5fa5b251b1caa7b666361850696a4e1196346";
+
+/// Reset the expiry of the lock only while it still holds our token
+const EXTEND: &str = r#"if redis.call("GET", KEYS[1]) == ARGV[1] then
+    return redis.call("PEXPIRE", KEYS[1], ARGV[2])
+end
+return 0"#;
+const EXTEND_SHA1: &str = "23dbfcb5978870efe8196112481f310b414a9b21";
+
+/// A lock on a key, taken with `SET key token NX PX ttl` and released with a script that only
+/// deletes the key while it holds the same token. Built by [`redis_lock!`](crate::redis_lock) and
+/// [`redis_lock_async!`](crate::redis_lock_async).
+#[derive(Clone, Debug)]
+pub struct Lock {
+    key: Args,
+    token: String,
+    ttl: u64,
+}
+
+impl Lock {
+    /// A lock on `key` that expires after `ttl` milliseconds, with a new random token
+    pub fn new<K: ToRedisArgs>(key: K, ttl: u64) -> Lock {
+        let mut args = Args::new();
+        args.arg(key);
+        Lock {
+            key: args,
+            token: token(),
+            ttl,
+        }
+    }
+
+    /// The token that identifies this holder of the lock
+    pub fn token(&self) -> &str {
+        &self.token
+    }
+
+    /// The `SET key token NX PX ttl` command that takes the lock, which replies `OK`, or `nil` when
+    /// the lock is held
+    pub fn acquire_cmd(&self) -> Cmd {
+        let mut cmd = redis::cmd("SET");
+        cmd.arg(&self.key)
+            .arg(&self.token)
+            .arg("NX")
+            .arg("PX")
+            .arg(self.ttl);
+        cmd
+    }
+
+    /// The call of the release script, which replies 1 if the lock was released
+    pub fn release_cmd(&self) -> ScriptCmd {
+        let mut call = ScriptCmd::new(RELEASE, RELEASE_SHA1);
+        call.key(&self.key).arg(&self.token);
+        call
+    }
+
+    /// The call of the script that resets the expiry to `ttl` milliseconds, which replies 1 if the
+    /// lock is still held
+    pub fn extend_cmd(&self, ttl: u64) -> ScriptCmd {
+        let mut call = ScriptCmd::new(EXTEND, EXTEND_SHA1);
+        call.key(&self.key).arg(&self.token).arg(ttl);
+        call
+    }
+
+    /// Try to take the lock, returning a guard that releases it when dropped, or `None` if the
+    /// lock is held
+    pub fn acquire<C: ConnectionLike>(self, con: &mut C) -> RedisResult<Option<LockGuard<'_, C>>> {
+        let reply: Option<String> = self.acquire_cmd().query(con)?;
+        Ok(reply.map(|_| LockGuard {
+            con,
+            lock: self,
+            held: true,
+        }))
+    }
+
+    /// Try to take the lock on an async connection, returning a guard to release it with
+    /// [`AsyncLockGuard::unlock`], or `None` if the lock is held
+    #[cfg(feature = "async")]
+    pub async fn acquire_async<C: redis::aio::ConnectionLike>(
+        self,
+        con: &mut C,
+    ) -> RedisResult<Option<AsyncLockGuard<'_, C>>> {
+        let reply: Option<String> = self.acquire_cmd().query_async(con).await?;
+        Ok(reply.map(|_| AsyncLockGuard { con, lock: self }))
+    }
+}
+
+/// A random token, so that each holder of a lock can tell its lock apart from the next holder's.
+/// Rate limits also use it for unique members, and idempotency keys for their claims.
+pub(crate) fn token() -> String {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let nanos = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |time| time.as_nanos());
+    let parts = [
+        nanos as u64,
+        std::process::id().into(),
+        COUNTER.fetch_add(1, Ordering::Relaxed),
+    ];
+    // RandomState is seeded randomly, so the hashes are unpredictable
+    let halves = [RandomState::new(), RandomState::new()].map(|state| {
+        let mut hasher = state.build_hasher();
+        for part in parts {
+            hasher.write_u64(part);
+        }
+        hasher.finish()
+    });
+    format!("{:016x}{:016x}", halves[0], halves[1])
+}
+
+/// A held [`Lock`], which releases it when dropped. The connection is used through the guard while
+/// the lock is held.
+pub struct LockGuard<'a, C: ConnectionLike> {
+    con: &'a mut C,
+    lock: Lock,
+    held: bool,
+}
+
+impl<C: ConnectionLike> LockGuard<'_, C> {
+    /// The held lock
+    pub fn lock(&self) -> &Lock {
+        &self.lock
+    }
+
+    /// Reset the expiry of the lock to `ttl` milliseconds, returning whether it was still held
+    pub fn extend(&mut self, ttl: u64) -> RedisResult<bool> {
+        self.lock.extend_cmd(ttl).invoke(self.con)
+    }
+
+    /// Release the lock, returning whether it was still held. Dropping the guard does the same,
+    /// but ignores errors.
+    pub fn unlock(mut self) -> RedisResult<bool> {
+        self.held = false;
+        self.lock.release_cmd().invoke(self.con)
+    }
+}
+
+impl<C: ConnectionLike> Deref for LockGuard<'_, C> {
+    type Target = C;
+
+    fn deref(&self) -> &C {
+        self.con
+    }
+}
+
+impl<C: ConnectionLike> DerefMut for LockGuard<'_, C> {
+    fn deref_mut(&mut self) -> &mut C {
+        self.con
+    }
+}
+
+impl<C: ConnectionLike> Drop for LockGuard<'_, C> {
+    fn drop(&mut self) {
+        if self.held {
+            let _: RedisResult<bool> = self.lock.release_cmd().invoke(self.con);
+        }
+    }
+}
+
+/// A held [`Lock`] on an async connection. A drop can't await the release, so the lock is released
+/// with [`unlock`](AsyncLockGuard::unlock), and a guard dropped without it leaves the lock to
+/// expire after its ttl. The connection is used through the guard while the lock is held.
+#[cfg(feature = "async")]
+#[must_use = "the lock is held until `unlock` is awaited or it expires"]
+pub struct AsyncLockGuard<'a, C: redis::aio::ConnectionLike> {
+    con: &'a mut C,
+    lock: Lock,
+}
+
+#[cfg(feature = "async")]
+impl<C: redis::aio::ConnectionLike> AsyncLockGuard<'_, C> {
+    /// The held lock
+    pub fn lock(&self) -> &Lock {
+        &self.lock
+    }
+
+    /// Reset the expiry of the lock to `ttl` milliseconds, returning whether it was still held
+    pub async fn extend(&mut self, ttl: u64) -> RedisResult<bool> {
+        let cmd = self.lock.extend_cmd(ttl).eval_cmd();
+        cmd.query_async(self.con).await
+    }
+
+    /// Release the lock, returning whether it was still held
+    pub async fn unlock(self) -> RedisResult<bool> {
+        let cmd = self.lock.release_cmd().eval_cmd();
+        cmd.query_async(self.con).await
+    }
+}
+
+#[cfg(feature = "async")]
+impl<C: redis::aio::ConnectionLike> Deref for AsyncLockGuard<'_, C> {
+    type Target = C;
+
+    fn deref(&self) -> &C {
+        self.con
+    }
+}
+
+#[cfg(feature = "async")]
+impl<C: redis::aio::ConnectionLike> DerefMut for AsyncLockGuard<'_, C> {
+    fn deref_mut(&mut self) -> &mut C {
+        self.con
+    }
+}
@@ -0,0 +1,28 @@
+/// What the command table knows about a command, built by [`redis_meta!`](crate::redis_meta) at
+/// compile time. The fields follow the reply of `COMMAND INFO`, so middleware such as metrics,
+/// routers and ACL checks can read them instead of keeping their own lists.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct CommandMeta {
+    /// The uppercase name, followed by the subcommand for container commands such as
+    /// `XGROUP CREATE`
+    pub name: &'static str,
+    /// The number of arguments including the name, or its negation when that is the least number
+    /// of arguments the command takes
+    pub arity: i32,
+    /// Whether the command changes data
+    pub write: bool,
+    /// Whether the command only reads keys
+    pub readonly: bool,
+    /// The position of the first key, counting the name as 0, or 0 when the command has no keys at
+    /// fixed positions
+    pub first_key: usize,
+    /// The position of the last key, where `-1` is the last argument
+    pub last_key: isize,
+    /// The distance between keys, such as 2 for the key and value pairs of `MSET`
+    pub step: usize,
+    /// Whether some keys are found from the other arguments, such as the keys after the count of
+    /// `EVAL` or the streams of `XREAD`
+    pub movable_keys: bool,
+    /// The Redis version that added the command
+    pub since: &'static str,
+}
@@ -0,0 +1,38 @@
+use redis::{ErrorKind, FromRedisValue, RedisError, RedisResult, Value};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+/// Serialize the value of a `{msgpack expr}` substitution
+pub fn to_vec<T: Serialize + ?Sized>(value: &T) -> RedisResult<Vec<u8>> {
+    rmp_serde::to_vec(value).map_err(|err| {
+        RedisError::from((
+            ErrorKind::TypeError,
+            "failed to serialize value as MessagePack",
+            err.to_string(),
+        ))
+    })
+}
+
+/// A reply that is read by deserializing MessagePack, such as a message payload of
+/// [`redis_subscribe!`](crate::redis_subscribe)
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Msgpack<T>(pub T);
+
+impl<T: DeserializeOwned> FromRedisValue for Msgpack<T> {
+    fn from_redis_value(value: &Value) -> RedisResult<Msgpack<T>> {
+        let Value::Data(data) = value else {
+            return Err(RedisError::from((
+                ErrorKind::TypeError,
+                "expected MessagePack data",
+                format!("{:?}", value),
+            )));
+        };
+        rmp_serde::from_slice(data).map(Msgpack).map_err(|err| {
+            RedisError::from((
+                ErrorKind::TypeError,
+                "failed to deserialize value as MessagePack",
+                err.to_string(),
+            ))
+        })
+    }
+}
@@ -0,0 +1,88 @@
+use redis::{RedisWrite, ToRedisArgs};
+
+/// A key that is written with a namespace of `#[redis_namespace]` in front of it
+pub struct Namespaced<'a, P, T> {
+    namespace: &'a P,
+    key: T,
+}
+
+impl<P: ToRedisArgs, T: ToRedisArgs> ToRedisArgs for Namespaced<'_, P, T> {
+    fn write_redis_args<W: ?Sized + RedisWrite>(&self, out: &mut W) {
+        let namespace = self.namespace.to_redis_args().concat();
+        for arg in self.key.to_redis_args() {
+            out.write_arg(&[namespace.as_slice(), &arg].concat());
+        }
+    }
+
+    fn is_single_arg(&self) -> bool {
+        self.key.is_single_arg()
+    }
+}
+
+/// Put a namespace in front of a key
+pub fn key<P: ToRedisArgs, T: ToRedisArgs>(namespace: &P, key: T) -> Namespaced<'_, P, T> {
+    Namespaced { namespace, key }
+}
+
+/// Put a namespace in front of each key of a spread
+pub fn keys<P: ToRedisArgs, I: IntoIterator>(
+    namespace: &P,
+    keys: I,
+) -> impl Iterator<Item = Namespaced<'_, P, I::Item>>
+where
+    I::Item: ToRedisArgs,
+{
+    keys.into_iter()
+        .map(move |key| Namespaced { namespace, key })
+}
+
+/// A `(key, value)` pair whose key has a namespace in front of it
+type NamespacedPair<'a, P, T> = (
+    Namespaced<'a, P, <T as KeyValuePair>::Key>,
+    <T as KeyValuePair>::Value,
+);
+
+/// Put a namespace in front of the keys of a spread over `(key, value)` pairs
+pub fn pairs<P: ToRedisArgs, I: IntoIterator>(
+    namespace: &P,
+    pairs: I,
+) -> impl Iterator<Item = NamespacedPair<'_, P, I::Item>>
+where
+    I::Item: KeyValuePair,
+{
+    pairs.into_iter().map(move |pair| {
+        let (key, value) = pair.split();
+        (Namespaced { namespace, key }, value)
+    })
+}
+
+/// A `(key, value)` pair, or a reference to one
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` is not a `(key, value)` pair",
+    note = "spreads over the keys and values of `MSET` take `(key, value)` pairs, so that the key \
+            prefix or namespace can be applied to their keys"
+)]
+pub trait KeyValuePair {
+    type Key: ToRedisArgs;
+    type Value: ToRedisArgs;
+
+    fn split(self) -> (Self::Key, Self::Value);
+}
+
+impl<K: ToRedisArgs, V: ToRedisArgs> KeyValuePair for (K, V) {
+    type Key = K;
+    type Value = V;
+
+    fn split(self) -> (K, V) {
+        self
+    }
+}
+
+impl<'a, K: ToRedisArgs, V: ToRedisArgs> KeyValuePair for &'a (K, V) {
+    type Key = &'a K;
+    type Value = &'a V;
+
+    fn split(self) -> (&'a K, &'a V) {
+        (&self.0, &self.1)
+    }
+}
@@ -0,0 +1,120 @@
+use crate::Route;
+use redis::{Cmd, ConnectionLike, ErrorKind, FromRedisValue, Pipeline, RedisResult, Value};
+use std::marker::PhantomData;
+
+/// A pipeline built by [`redis_pipe!`](crate::redis_pipe) with labeled commands, whose replies
+/// are returned as the fields of a struct instead of a tuple
+pub struct LabeledPipeline<T> {
+    pipe: Pipeline,
+    replies: Vec<bool>,
+    results: PhantomData<fn() -> T>,
+}
+
+impl<T> LabeledPipeline<T> {
+    /// Wrap a pipeline whose replies are read into `T`. `replies` has an entry for every labeled
+    /// command, which is `false` when the command was skipped by an `if` block.
+    pub fn new(pipe: Pipeline, replies: Vec<bool>) -> LabeledPipeline<T> {
+        LabeledPipeline {
+            pipe,
+            replies,
+            results: PhantomData,
+        }
+    }
+
+    /// The underlying pipeline
+    pub fn pipeline(&self) -> &Pipeline {
+        &self.pipe
+    }
+
+    /// Take the underlying pipeline out, to query it with a type of your own
+    pub fn into_pipeline(self) -> Pipeline {
+        self.pipe
+    }
+}
+
+impl<T: FromRedisValue> LabeledPipeline<T> {
+    /// Send the pipeline and read the replies into the struct of labeled fields. The fields of
+    /// skipped commands are `None`.
+    pub fn query(&self, con: &mut dyn ConnectionLike) -> RedisResult<T> {
+        let values: Vec<Value> = self.pipe.query(con)?;
+        let mut values = values.into_iter();
+        let replies = self
+            .replies
+            .iter()
+            .map(|sent| match sent {
+                true => values.next().unwrap_or(Value::Nil),
+                false => Value::Nil,
+            })
+            .collect();
+        T::from_redis_value(&Value::Bulk(replies))
+    }
+}
+
+/// A pipeline built by [`redis_pipe!`](crate::redis_pipe) in cluster mode, whose commands are
+/// grouped by their [`Route`], since a cluster node only serves the keys of its own slots. Each
+/// group is sent as a pipeline of its own, and the replies are put back in the order of the
+/// commands.
+#[derive(Clone, Default)]
+pub struct ClusterPipeline {
+    groups: Vec<(Route, Pipeline)>,
+    /// The group of each command, and whether its reply is part of the results
+    commands: Vec<(usize, bool)>,
+}
+
+impl ClusterPipeline {
+    /// An empty pipeline
+    pub fn new() -> ClusterPipeline {
+        ClusterPipeline::default()
+    }
+
+    /// Add a command to the group of its route
+    pub fn add_command(&mut self, cmd: Cmd, route: Route) -> &mut ClusterPipeline {
+        let group = match self.groups.iter().position(|(group, _)| *group == route) {
+            Some(group) => group,
+            None => {
+                self.groups.push((route, redis::pipe()));
+                self.groups.len() - 1
+            }
+        };
+        self.groups[group].1.add_command(cmd);
+        self.commands.push((group, true));
+        self
+    }
+
+    /// Leave the reply of the last command out of the results
+    pub fn ignore(&mut self) -> &mut ClusterPipeline {
+        if let Some((group, reply)) = self.commands.last_mut() {
+            self.groups[*group].1.ignore();
+            *reply = false;
+        }
+        self
+    }
+
+    /// The pipelines of the groups, in the order their first command was added
+    pub fn groups(&self) -> impl Iterator<Item = (Route, &Pipeline)> {
+        self.groups.iter().map(|(route, pipe)| (*route, pipe))
+    }
+
+    /// Send the pipeline of each group with `send`, which picks the connection of the node that
+    /// serves its route, and read the replies into `T` in the order of the commands
+    pub fn query<T: FromRedisValue>(
+        &self,
+        mut send: impl FnMut(Route, &Pipeline) -> RedisResult<Vec<Value>>,
+    ) -> RedisResult<T> {
+        let mut replies = vec![];
+        for (route, pipe) in &self.groups {
+            replies.push(send(*route, pipe)?.into_iter());
+        }
+        let mut values = vec![];
+        for (group, reply) in &self.commands {
+            if *reply {
+                let Some(value) = replies[*group].next() else {
+                    let msg = "a node sent fewer replies than its pipeline had commands";
+                    return Err((ErrorKind::ResponseError, msg).into());
+                };
+                values.push(value);
+            }
+        }
+        T::from_redis_value(&Value::Bulk(values))
+    }
+}
@@ -0,0 +1,60 @@
+use crate::namespace::KeyValuePair;
+use redis::{RedisWrite, ToRedisArgs};
+use std::sync::OnceLock;
+
+static KEY_PREFIX: OnceLock<String> = OnceLock::new();
+
+/// Set the prefix that [`redis!`](crate::redis) prepends to the keys of known commands, replacing
+/// the `REDIS_KEY_PREFIX` environment variable read at compile time.
+///
+/// The prefix can only be set once, before the first command is built. Returns the given prefix
+/// back if a prefix is already in use.
+///
+/// ```
+/// redis_rs_macro::set_key_prefix("app:").unwrap();
+/// let id = 1;
+/// let cmd = redis_rs_macro::redis!(GET user:{id});
+/// assert_eq!(
+///     cmd.get_packed_command(),
+///     redis::cmd("GET").arg("app:user:1").get_packed_command(),
+/// );
+/// ```
+pub fn set_key_prefix(prefix: impl Into<String>) -> Result<(), String> {
+    KEY_PREFIX.set(prefix.into())
+}
+
+/// The prefix that [`redis!`](crate::redis) prepends to the keys of known commands
+pub fn key_prefix() -> &'static str {
+    KEY_PREFIX.get_or_init(|| {
+        option_env!("REDIS_KEY_PREFIX")
+            .unwrap_or_default()
+            .to_owned()
+    })
+}
+
+/// A key that is written with the key prefix in front of it
+pub struct Prefixed<T>(T);
+
+impl<T: ToRedisArgs> ToRedisArgs for Prefixed<T> {
+    fn write_redis_args<W: ?Sized + RedisWrite>(&self, out: &mut W) {
+        let prefix = key_prefix().as_bytes();
+        for arg in self.0.to_redis_args() {
+            out.write_arg(&[prefix, &arg].concat());
+        }
+    }
+
+    fn is_single_arg(&self) -> bool {
+        self.0.is_single_arg()
+    }
+}
+
+/// Prefix a key of a known command
+pub fn key<T: ToRedisArgs>(key: T) -> Prefixed<T> {
+    Prefixed(key)
+}
+
+/// Prefix the key of a `(key, value)` pair in a spread over key and value pairs
+pub fn pair<P: KeyValuePair>(pair: P) -> (Prefixed<P::Key>, P::Value) {
+    let (key, value) = pair.split();
+    (Prefixed(key), value)
+}
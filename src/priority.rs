@@ -0,0 +1,99 @@
+use crate::subscribe::HandlerResult;
+use crate::Args;
+use redis::{Cmd, ConnectionLike, FromRedisValue, RedisResult, ToRedisArgs, Value};
+
+/// A queue that hands out the job with the lowest priority score first, built by
+/// [`redis_priority_queue!`](crate::redis_priority_queue). Jobs are members of the sorted set at
+/// `key`, scored by their priority, and are taken with `BZPOPMIN`. Since jobs are set members,
+/// pushing a job that is already queued changes its priority.
+#[derive(Clone, Debug)]
+pub struct PriorityQueue {
+    key: Args,
+    timeout: u64,
+}
+
+impl PriorityQueue {
+    /// The queue at `key`, whose workers wait up to `timeout` seconds at a time for a job
+    pub fn new<K: ToRedisArgs>(key: K, timeout: u64) -> PriorityQueue {
+        let mut args = Args::new();
+        args.arg(key);
+        PriorityQueue { key: args, timeout }
+    }
+
+    /// The `ZADD` command that adds a serialized job with a priority
+    pub fn push_cmd<T: ToRedisArgs>(&self, job: T, priority: f64) -> Cmd {
+        let mut cmd = redis::cmd("ZADD");
+        cmd.arg(&self.key).arg(priority).arg(job);
+        cmd
+    }
+
+    /// The `ZPOPMIN` command that pops the job with the lowest priority, which replies an empty
+    /// list when the queue is empty
+    pub fn pop_cmd(&self) -> Cmd {
+        let mut cmd = redis::cmd("ZPOPMIN");
+        cmd.arg(&self.key);
+        cmd
+    }
+
+    /// The `BZPOPMIN` command that waits for the job with the lowest priority, which replies `nil`
+    /// if no job arrives within the timeout
+    pub fn take_cmd(&self) -> Cmd {
+        let mut cmd = redis::cmd("BZPOPMIN");
+        cmd.arg(&self.key).arg(self.timeout);
+        cmd
+    }
+
+    /// Add a serialized job with a priority
+    pub fn push<T: ToRedisArgs>(
+        &self,
+        con: &mut dyn ConnectionLike,
+        job: T,
+        priority: f64,
+    ) -> RedisResult<()> {
+        self.push_cmd(job, priority).query(con)
+    }
+
+    /// Pop the job with the lowest priority without waiting
+    pub fn pop<T: FromRedisValue>(&self, con: &mut dyn ConnectionLike) -> RedisResult<Option<T>> {
+        let popped: Vec<(Vec<u8>, f64)> = self.pop_cmd().query(con)?;
+        popped
+            .into_iter()
+            .next()
+            .map(|(job, _)| T::from_redis_value(&Value::Data(job)))
+            .transpose()
+    }
+
+    /// Take jobs, lowest priority first, and pass them to `handler`. Jobs it returns `Err` for, and
+    /// jobs that can't be read, are pushed back with the same priority. Runs until the handler returns
+    /// `Ok(ControlFlow::Break(()))`, or until a command or reading a job fails.
+    pub fn work<T, R, E>(
+        &self,
+        con: &mut dyn ConnectionLike,
+        mut handler: impl FnMut(T) -> Result<R, E>,
+    ) -> RedisResult<()>
+    where
+        T: FromRedisValue,
+        R: HandlerResult,
+    {
+        loop {
+            let taken: Option<(Value, Vec<u8>, f64)> = self.take_cmd().query(con)?;
+            let Some((_, job, priority)) = taken else {
+                continue;
+            };
+            let value = match T::from_redis_value(&Value::Data(job.clone())) {
+                Ok(value) => value,
+                Err(err) => {
+                    self.push(con, job, priority)?;
+                    return Err(err);
+                }
+            };
+            let Ok(handled) = handler(value) else {
+                self.push(con, job, priority)?;
+                continue;
+            };
+            if handled.into_flow().is_break() {
+                return Ok(());
+            }
+        }
+    }
+}
@@ -0,0 +1,167 @@
+use crate::subscribe::HandlerResult;
+use crate::{Args, ScriptCmd};
+use redis::{Cmd, ConnectionLike, FromRedisValue, Pipeline, RedisResult, ToRedisArgs, Value};
+use std::time::{Duration, Instant, SystemTime};
+
+/// Move the items whose lease expired from the processing list back to the queue, at the end that
+/// is taken from next. Items without a lease, taken by a worker that hasn't leased them yet or
+/// that crashed before it could, get a lease instead, so they are moved on a later run.
+const REQUEUE: &str = r#"local now = tonumber(ARGV[1])
+local moved = 0
+for _, item in ipairs(redis.call("LRANGE", KEYS[2], 0, -1)) do
+    local deadline = redis.call("ZSCORE", KEYS[3], item)
+    if not deadline then
+        redis.call("ZADD", KEYS[3], now + tonumber(ARGV[2]), item)
+    elseif tonumber(deadline) <= now then
+        redis.call("LREM", KEYS[2], 1, item)
+        redis.call("ZREM", KEYS[3], item)
+        redis.call("RPUSH", KEYS[1], item)
+        moved = moved + 1
+    end
+end
+return moved"#;
+const REQUEUE_SHA1: &str = "2939b8a43b04832ce0cf8c839effd3bde700388b";
+
+/// How long a worker waits for a job before checking for stale jobs again
+const BLOCK_SECONDS: u64 = 1;
+
+/// A reliable work queue, built by [`redis_work_queue!`](crate::redis_work_queue). Jobs are pushed
+/// to the list at `key`, and workers move them to the `<key>:processing` list with `BRPOPLPUSH`,
+/// so that a job isn't lost when its worker crashes. A taken job gets a lease in the
+/// `<key>:leases` sorted set, and is removed once it is handled. Jobs whose lease expired, because
+/// their worker crashed or failed to handle them, are moved back to the queue.
+#[derive(Clone, Debug)]
+pub struct WorkQueue {
+    key: Args,
+    processing: Args,
+    leases: Args,
+    visibility: Duration,
+}
+
+/// A key with a suffix, such as `<key>:processing`
+fn suffixed(key: &Args, suffix: &str) -> Args {
+    let mut key = key.as_slice().concat();
+    key.extend_from_slice(suffix.as_bytes());
+    let mut args = Args::new();
+    args.arg(key);
+    args
+}
+
+/// The current unix time in milliseconds
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |time| time.as_millis() as u64)
+}
+
+impl WorkQueue {
+    /// The queue at `key`, whose taken jobs are moved back to it when they aren't handled within
+    /// `visibility` milliseconds
+    pub fn new<K: ToRedisArgs>(key: K, visibility: u64) -> WorkQueue {
+        let mut args = Args::new();
+        args.arg(key);
+        WorkQueue {
+            processing: suffixed(&args, ":processing"),
+            leases: suffixed(&args, ":leases"),
+            key: args,
+            visibility: Duration::from_millis(visibility),
+        }
+    }
+
+    /// The `LPUSH` command that adds a serialized job to the queue
+    pub fn push_cmd<T: ToRedisArgs>(&self, job: T) -> Cmd {
+        let mut cmd = redis::cmd("LPUSH");
+        cmd.arg(&self.key).arg(job);
+        cmd
+    }
+
+    /// The `BRPOPLPUSH` command that takes the oldest job, moving it to the processing list, and
+    /// replies `nil` if no job arrives within `timeout` seconds
+    pub fn take_cmd(&self, timeout: u64) -> Cmd {
+        let mut cmd = redis::cmd("BRPOPLPUSH");
+        cmd.arg(&self.key).arg(&self.processing).arg(timeout);
+        cmd
+    }
+
+    /// The `ZADD` command that leases a taken job until the visibility timeout from now
+    pub fn lease_cmd(&self, job: &[u8]) -> Cmd {
+        let deadline = now_ms() + self.visibility.as_millis() as u64;
+        let mut cmd = redis::cmd("ZADD");
+        cmd.arg(&self.leases).arg(deadline).arg(job);
+        cmd
+    }
+
+    /// The transaction that removes a handled job from the processing list and its lease
+    pub fn done_cmd(&self, job: &[u8]) -> Pipeline {
+        let mut pipe = redis::pipe();
+        pipe.atomic()
+            .cmd("LREM")
+            .arg(&self.processing)
+            .arg(1)
+            .arg(job)
+            .ignore()
+            .cmd("ZREM")
+            .arg(&self.leases)
+            .arg(job)
+            .ignore();
+        pipe
+    }
+
+    /// The call of the script that moves jobs whose lease expired back to the queue, which replies
+    /// how many were moved
+    pub fn requeue_cmd(&self) -> ScriptCmd {
+        let mut call = ScriptCmd::new(REQUEUE, REQUEUE_SHA1);
+        call.key(&self.key)
+            .key(&self.processing)
+            .key(&self.leases)
+            .arg(now_ms())
+            .arg(self.visibility.as_millis() as u64);
+        call
+    }
+
+    /// Add a serialized job to the queue
+    pub fn push<T: ToRedisArgs>(&self, con: &mut dyn ConnectionLike, job: T) -> RedisResult<()> {
+        self.push_cmd(job).query(con)
+    }
+
+    /// Move jobs whose lease expired back to the queue, returning how many were moved
+    pub fn requeue_stale(&self, con: &mut dyn ConnectionLike) -> RedisResult<usize> {
+        self.requeue_cmd().invoke(con)
+    }
+
+    /// Take jobs and pass them to `handler`, removing the jobs it returns `Ok` for. Jobs it returns
+    /// `Err` for stay in the processing list until their lease expires, and are then moved back to
+    /// the queue. Stale jobs are moved back when the worker starts, and again each time the
+    /// visibility timeout has passed. Runs until the handler returns `Ok(ControlFlow::Break(()))`,
+    /// or until a command or reading a job fails.
+    pub fn work<T, R, E>(
+        &self,
+        con: &mut dyn ConnectionLike,
+        mut handler: impl FnMut(T) -> Result<R, E>,
+    ) -> RedisResult<()>
+    where
+        T: FromRedisValue,
+        R: HandlerResult,
+    {
+        let mut requeued_at: Option<Instant> = None;
+        loop {
+            if requeued_at.is_none_or(|at| at.elapsed() >= self.visibility) {
+                requeued_at = Some(Instant::now());
+                self.requeue_stale(con)?;
+            }
+            let job: Option<Vec<u8>> = self.take_cmd(BLOCK_SECONDS).query(con)?;
+            let Some(job) = job else {
+                continue;
+            };
+            self.lease_cmd(&job).query::<()>(con)?;
+            let value = T::from_redis_value(&Value::Data(job.clone()))?;
+            let Ok(handled) = handler(value) else {
+                continue;
+            };
+            self.done_cmd(&job).query::<()>(con)?;
+            if handled.into_flow().is_break() {
+                return Ok(());
+            }
+        }
+    }
+}
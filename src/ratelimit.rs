@@ -0,0 +1,121 @@
+use crate::lock::token;
+use crate::{Args, ScriptCmd};
+use redis::{ConnectionLike, RedisResult, ToRedisArgs};
+use std::time::Duration;
+
+/// Count the request in a counter that expires at the end of the window
+const FIXED: &str = r#"local limit = tonumber(ARGV[1])
+local window = tonumber(ARGV[2])
+local count = redis.call("INCR", KEYS[1])
+if count == 1 then
+    redis.call("PEXPIRE", KEYS[1], window)
+end
+local ttl = redis.call("PTTL", KEYS[1])
+if ttl < 0 then
+    redis.call("PEXPIRE", KEYS[1], window)
+    ttl = window
+end
+if count > limit then
+    return {0, 0, ttl}
+end
+return {1, limit - count, ttl}"#;
+const FIXED_SHA1: &str = "dbfd9a575f284402ca43e2aa75e17dea8996a46a";
+
+/// Log the request in a sorted set scored by time, after dropping the requests that are older than
+/// the window. Each request is a member of its own, so the script takes a unique member.
+const SLIDING: &str = r#"local limit = tonumber(ARGV[1])
+local window = tonumber(ARGV[2])
+local time = redis.call("TIME")
+local now = tonumber(time[1]) * 1000 + math.floor(tonumber(time[2]) / 1000)
+redis.call("ZREMRANGEBYSCORE", KEYS[1], "-inf", now - window)
+local count = redis.call("ZCARD", KEYS[1])
+local allowed = 0
+if count < limit then
+    redis.call("ZADD", KEYS[1], now, ARGV[3])
+    redis.call("PEXPIRE", KEYS[1], window)
+    count = count + 1
+    allowed = 1
+end
+local oldest = redis.call("ZRANGE", KEYS[1], 0, 0, "WITHSCORES")
+local reset = window
+if oldest[2] then
+    reset = tonumber(oldest[2]) + window - now
+end
+return {allowed, math.max(limit - count, 0), reset}"#;
+const SLIDING_SHA1: &str = "ffb3b84df7b8c2e62b58fae09f24f02b171ab32b";
+
+/// How a [`RateLimit`] counts requests
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Window {
+    /// Count the requests of consecutive windows that start with their first request
+    Fixed,
+    /// Count the requests of the window that ends now, which doesn't let a burst through where two
+    /// fixed windows meet, but stores every request of the window
+    Sliding,
+}
+
+/// A rate limit of `limit` requests per window on a key, checked by a Lua script so that concurrent
+/// checks can't both take the last request. Built by [`redis_ratelimit!`](crate::redis_ratelimit).
+#[derive(Clone, Debug)]
+pub struct RateLimit {
+    key: Args,
+    limit: u64,
+    window: u64,
+    kind: Window,
+}
+
+/// The result of checking a [`RateLimit`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RateLimitStatus {
+    /// Whether the request is allowed
+    pub allowed: bool,
+    /// The number of requests that are still allowed in the window
+    pub remaining: u64,
+    /// The time until the window resets, or for sliding windows, until the oldest request of the
+    /// window leaves it
+    pub reset: Duration,
+}
+
+impl RateLimit {
+    /// A limit of `limit` requests per `window` milliseconds on `key`
+    pub fn new<K: ToRedisArgs>(key: K, limit: u64, window: u64, kind: Window) -> RateLimit {
+        let mut args = Args::new();
+        args.arg(key);
+        RateLimit {
+            key: args,
+            limit,
+            window,
+            kind,
+        }
+    }
+
+    /// The call of the script that counts a request
+    pub fn check_cmd(&self) -> ScriptCmd {
+        let mut call = match self.kind {
+            Window::Fixed => ScriptCmd::new(FIXED, FIXED_SHA1),
+            Window::Sliding => ScriptCmd::new(SLIDING, SLIDING_SHA1),
+        };
+        call.key(&self.key).arg(self.limit).arg(self.window);
+        if self.kind == Window::Sliding {
+            call.arg(token());
+        }
+        call
+    }
+
+    /// Count a request, returning whether it is allowed
+    pub fn check(&self, con: &mut dyn ConnectionLike) -> RedisResult<RateLimitStatus> {
+        let reply = self.check_cmd().invoke(con)?;
+        Ok(RateLimitStatus::from_reply(reply))
+    }
+}
+
+impl RateLimitStatus {
+    /// Read the `{allowed, remaining, reset}` reply of the script
+    fn from_reply((allowed, remaining, reset): (i64, u64, i64)) -> RateLimitStatus {
+        RateLimitStatus {
+            allowed: allowed == 1,
+            remaining,
+            reset: Duration::from_millis(reset.max(0) as u64),
+        }
+    }
+}
@@ -0,0 +1,234 @@
+use redis::{ConnectionLike, ErrorKind, RedisError, RedisResult, Value};
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::Path;
+
+/// A connection that passes commands on to another connection, and appends each command with its
+/// reply to a file, which [`Replay`] serves back. Errors that didn't come from the server, such as
+/// a dropped connection, aren't recorded.
+///
+/// Each exchange is stored as a RESP array of the packed command, `ok` or `err`, and the reply, so
+/// recordings can be checked into a repository and read in a diff.
+pub struct Recorder<C> {
+    con: C,
+    file: File,
+}
+
+impl<C: ConnectionLike> Recorder<C> {
+    /// Record the exchanges of `con` to the file at `path`, replacing it
+    pub fn create<P: AsRef<Path>>(con: C, path: P) -> io::Result<Recorder<C>> {
+        Ok(Recorder {
+            con,
+            file: File::create(path)?,
+        })
+    }
+
+    /// Take back the wrapped connection
+    pub fn into_inner(self) -> C {
+        self.con
+    }
+
+    /// Append an exchange to the file, each in a single write so that a recording isn't cut in the
+    /// middle of an exchange when a test panics
+    fn record(&mut self, request: &[u8], reply: Result<&Value, &RedisError>) -> RedisResult<()> {
+        let mut out = b"*3\r\n".to_vec();
+        write_value(&mut out, &Value::Data(request.to_vec()));
+        match reply {
+            Ok(value) => {
+                out.extend_from_slice(b"+ok\r\n");
+                write_value(&mut out, value);
+            }
+            Err(err) => {
+                let Some(code) = err.code() else {
+                    return Ok(());
+                };
+                let error = format!("{} {}", code, err.detail().unwrap_or_default());
+                out.extend_from_slice(b"+err\r\n");
+                write_value(&mut out, &Value::Data(error.into_bytes()));
+            }
+        }
+        self.file.write_all(&out)?;
+        Ok(())
+    }
+}
+
+impl<C: ConnectionLike> ConnectionLike for Recorder<C> {
+    fn req_packed_command(&mut self, cmd: &[u8]) -> RedisResult<Value> {
+        let reply = self.con.req_packed_command(cmd);
+        self.record(cmd, reply.as_ref())?;
+        reply
+    }
+
+    fn req_packed_commands(
+        &mut self,
+        cmd: &[u8],
+        offset: usize,
+        count: usize,
+    ) -> RedisResult<Vec<Value>> {
+        let reply = self.con.req_packed_commands(cmd, offset, count);
+        match &reply {
+            Ok(values) => self.record(cmd, Ok(&Value::Bulk(values.clone())))?,
+            Err(err) => self.record(cmd, Err(err))?,
+        }
+        reply
+    }
+
+    fn get_db(&self) -> i64 {
+        self.con.get_db()
+    }
+
+    fn check_connection(&mut self) -> bool {
+        self.con.check_connection()
+    }
+
+    fn is_open(&self) -> bool {
+        self.con.is_open()
+    }
+}
+
+/// A connection that serves the exchanges recorded by a [`Recorder`], in order. Each command has to
+/// match the recorded command, so a test fails when the commands it sends change.
+pub struct Replay {
+    exchanges: VecDeque<(Vec<u8>, RedisResult<Value>)>,
+}
+
+impl Replay {
+    /// Serve the exchanges recorded in the file at `path`
+    pub fn open<P: AsRef<Path>>(path: P) -> RedisResult<Replay> {
+        let recording = std::fs::read(path)?;
+        let mut input = recording.as_slice();
+        let mut exchanges = VecDeque::new();
+        while !input.is_empty() {
+            let exchange = read_value(&mut input).and_then(read_exchange);
+            let Some(exchange) = exchange else {
+                let msg = "the recording is malformed";
+                return Err((ErrorKind::ClientError, msg).into());
+            };
+            exchanges.push_back(exchange);
+        }
+        Ok(Replay { exchanges })
+    }
+
+    /// The number of exchanges that haven't been served yet
+    pub fn remaining(&self) -> usize {
+        self.exchanges.len()
+    }
+
+    fn reply(&mut self, cmd: &[u8]) -> RedisResult<Value> {
+        let Some((request, reply)) = self.exchanges.pop_front() else {
+            let detail = String::from_utf8_lossy(cmd).into_owned();
+            let msg = "the recording has no more commands";
+            return Err((ErrorKind::ClientError, msg, detail).into());
+        };
+        if request != cmd {
+            let detail = format!(
+                "expected {:?}, got {:?}",
+                String::from_utf8_lossy(&request),
+                String::from_utf8_lossy(cmd)
+            );
+            let msg = "the command doesn't match the recording";
+            return Err((ErrorKind::ClientError, msg, detail).into());
+        }
+        reply
+    }
+}
+
+impl ConnectionLike for Replay {
+    fn req_packed_command(&mut self, cmd: &[u8]) -> RedisResult<Value> {
+        self.reply(cmd)
+    }
+
+    fn req_packed_commands(&mut self, cmd: &[u8], _: usize, _: usize) -> RedisResult<Vec<Value>> {
+        match self.reply(cmd)? {
+            Value::Bulk(values) => Ok(values),
+            _ => {
+                let msg = "the recorded reply of a pipeline isn't a list";
+                Err((ErrorKind::ClientError, msg).into())
+            }
+        }
+    }
+
+    fn get_db(&self) -> i64 {
+        0
+    }
+
+    fn check_connection(&mut self) -> bool {
+        true
+    }
+
+    fn is_open(&self) -> bool {
+        true
+    }
+}
+
+/// Read a recorded `[command, ok|err, reply]` exchange
+fn read_exchange(exchange: Value) -> Option<(Vec<u8>, RedisResult<Value>)> {
+    let Value::Bulk(parts) = exchange else {
+        return None;
+    };
+    let [Value::Data(request), Value::Status(tag), reply] = <[Value; 3]>::try_from(parts).ok()?
+    else {
+        return None;
+    };
+    let reply = match (tag.as_str(), reply) {
+        ("ok", reply) => Ok(reply),
+        // The server's error reply, which redis parses back into an error of the same kind
+        ("err", Value::Data(error)) => {
+            let reply = [b"-", error.as_slice(), b"\r\n"].concat();
+            Err(redis::parse_redis_value(&reply).err()?)
+        }
+        _ => return None,
+    };
+    Some((request, reply))
+}
+
+/// Write a value in RESP
+fn write_value(out: &mut Vec<u8>, value: &Value) {
+    match value {
+        Value::Nil => out.extend_from_slice(b"$-1\r\n"),
+        Value::Int(int) => out.extend_from_slice(format!(":{}\r\n", int).as_bytes()),
+        Value::Data(data) => {
+            out.extend_from_slice(format!("${}\r\n", data.len()).as_bytes());
+            out.extend_from_slice(data);
+            out.extend_from_slice(b"\r\n");
+        }
+        Value::Bulk(values) => {
+            out.extend_from_slice(format!("*{}\r\n", values.len()).as_bytes());
+            for value in values {
+                write_value(out, value);
+            }
+        }
+        Value::Status(status) => out.extend_from_slice(format!("+{}\r\n", status).as_bytes()),
+        Value::Okay => out.extend_from_slice(b"+OK\r\n"),
+    }
+}
+
+/// Read a value written by [`write_value`], moving `input` past it
+fn read_value(input: &mut &[u8]) -> Option<Value> {
+    let (&kind, rest) = input.split_first()?;
+    let end = rest.windows(2).position(|pair| pair == b"\r\n")?;
+    let line = std::str::from_utf8(&rest[..end]).ok()?;
+    *input = &rest[end + 2..];
+    match kind {
+        b'+' if line == "OK" => Some(Value::Okay),
+        b'+' => Some(Value::Status(line.to_string())),
+        b':' => line.parse().ok().map(Value::Int),
+        b'$' if line == "-1" => Some(Value::Nil),
+        b'$' => {
+            let len: usize = line.parse().ok()?;
+            if input.get(len..len + 2)? != b"\r\n" {
+                return None;
+            }
+            let data = input[..len].to_vec();
+            *input = &input[len + 2..];
+            Some(Value::Data(data))
+        }
+        b'*' => {
+            let len: usize = line.parse().ok()?;
+            let values = (0..len).map(|_| read_value(input)).collect::<Option<_>>()?;
+            Some(Value::Bulk(values))
+        }
+        _ => None,
+    }
+}
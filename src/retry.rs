@@ -0,0 +1,130 @@
+use redis::{ErrorKind, RedisError, RedisResult};
+use std::collections::hash_map::RandomState;
+use std::future::Future;
+use std::hash::{BuildHasher, Hasher};
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// When and how often [`redis_retry!`](crate::redis_retry) and
+/// [`redis_retry_async!`](crate::redis_retry_async) retry a command that failed with a transient
+/// error. The delay before each retry doubles, starting at `base_delay`, up to `max_delay`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// How many times a command is retried after its first attempt
+    pub retries: u32,
+    /// The delay before the first retry
+    pub base_delay: Duration,
+    /// The longest delay before a retry
+    pub max_delay: Duration,
+    /// Whether each delay is picked at random between half of it and all of it, so that clients
+    /// that failed together don't all retry at the same time
+    pub jitter: bool,
+}
+
+impl Default for RetryPolicy {
+    /// 3 retries, after delays of up to 50ms, 100ms and 200ms
+    fn default() -> RetryPolicy {
+        RetryPolicy {
+            retries: 3,
+            base_delay: Duration::from_millis(50),
+            max_delay: Duration::from_secs(2),
+            jitter: true,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Whether an error is worth retrying: the connection was refused, dropped or timed out, the
+    /// server is loading its dataset (`LOADING`) or running a script (`BUSY`), or a cluster asked
+    /// for a retry (`TRYAGAIN`, `CLUSTERDOWN`, `MASTERDOWN`)
+    pub fn is_transient(err: &RedisError) -> bool {
+        err.is_connection_refusal()
+            || err.is_connection_dropped()
+            || err.is_timeout()
+            || err.code() == Some("BUSY")
+            || matches!(
+                err.kind(),
+                ErrorKind::BusyLoadingError
+                    | ErrorKind::TryAgain
+                    | ErrorKind::ClusterDown
+                    | ErrorKind::MasterDown
+            )
+    }
+
+    /// The delay before retry number `retry`, counting from 0
+    pub fn delay(&self, retry: u32) -> Duration {
+        let factor = 1u32.checked_shl(retry).unwrap_or(u32::MAX);
+        let delay = self.base_delay.saturating_mul(factor).min(self.max_delay);
+        if !self.jitter {
+            return delay;
+        }
+        let half = delay / 2;
+        let spread = (delay - half).as_nanos() as u64;
+        let random = RandomState::new().build_hasher().finish();
+        half + Duration::from_nanos(random % (spread + 1))
+    }
+
+    /// The delay before retrying after `err` failed retry number `retry`, counting the first
+    /// attempt as 0, or `None` if the error isn't transient or the retries are used up
+    pub fn retry_delay(&self, err: &RedisError, retry: u32) -> Option<Duration> {
+        (retry < self.retries && Self::is_transient(err)).then(|| self.delay(retry))
+    }
+
+    /// Run `query`, running it again after a delay while it fails with a transient error
+    pub fn run<T>(&self, mut query: impl FnMut() -> RedisResult<T>) -> RedisResult<T> {
+        let mut retry = 0;
+        loop {
+            match query() {
+                Err(err) => match self.retry_delay(&err, retry) {
+                    Some(delay) => thread::sleep(delay),
+                    None => return Err(err),
+                },
+                result => return result,
+            }
+            retry += 1;
+        }
+    }
+}
+
+/// A future that completes after `delay`. It is woken by a thread of its own, so that async
+/// retries work on any runtime.
+pub fn sleep(delay: Duration) -> Sleep {
+    Sleep {
+        deadline: Instant::now() + delay,
+        waker: None,
+    }
+}
+
+/// The future of [`sleep`]
+pub struct Sleep {
+    deadline: Instant,
+    /// The waker of the last poll, shared with the thread that wakes it
+    waker: Option<Arc<Mutex<Waker>>>,
+}
+
+impl Future for Sleep {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if Instant::now() >= self.deadline {
+            return Poll::Ready(());
+        }
+        match &self.waker {
+            Some(waker) => waker.lock().unwrap().clone_from(cx.waker()),
+            None => {
+                let waker = Arc::new(Mutex::new(cx.waker().clone()));
+                let shared = Arc::clone(&waker);
+                let deadline = self.deadline;
+                thread::spawn(move || {
+                    thread::sleep(deadline.saturating_duration_since(Instant::now()));
+                    shared.lock().unwrap().wake_by_ref();
+                });
+                self.waker = Some(waker);
+            }
+        }
+        Poll::Pending
+    }
+}
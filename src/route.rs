@@ -0,0 +1,67 @@
+use redis::{Arg, Cmd};
+
+/// The number of hash slots of a cluster
+const SLOTS: u16 = 16384;
+
+/// Where a cluster client sends a command, built by [`redis_routed!`](crate::redis_routed) from
+/// the first key of the command and what kind of command it is. This is the information that
+/// `redis::cluster_routing::RoutingInfo` holds, without needing the `cluster` feature of `redis`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Route {
+    /// Any node, for commands without keys
+    Random,
+    /// Every primary, for commands over the whole keyspace such as `KEYS` and `FLUSHDB`
+    AllPrimaries,
+    /// The node that serves a hash slot
+    Slot(u16),
+}
+
+impl Route {
+    /// The route of a command whose first key is `key`
+    pub fn for_key(key: &[u8]) -> Route {
+        Route::Slot(key_slot(key))
+    }
+
+    /// The route of a command whose first key is its argument at `index`, counting the command
+    /// name as 0, or [`Route::Random`] if the command is shorter, as when the keys were an empty
+    /// spread
+    pub fn for_arg(cmd: &Cmd, index: usize) -> Route {
+        match cmd.args_iter().nth(index) {
+            Some(Arg::Simple(key)) => Route::for_key(key),
+            _ => Route::Random,
+        }
+    }
+
+    /// The hash slot of the route, if it goes to a single slot
+    pub fn slot(&self) -> Option<u16> {
+        match self {
+            Route::Slot(slot) => Some(*slot),
+            _ => None,
+        }
+    }
+}
+
+/// The cluster hash slot of a key: the CRC16 (XMODEM) of its hash tag, the text between the first
+/// `{` and the next `}` when it isn't empty, or else of the whole key, modulo 16384
+pub fn key_slot(key: &[u8]) -> u16 {
+    let tag = key
+        .iter()
+        .position(|b| *b == b'{')
+        .and_then(|open| {
+            let len = key[open + 1..].iter().position(|b| *b == b'}')?;
+            (len > 0).then(|| &key[open + 1..open + 1 + len])
+        })
+        .unwrap_or(key);
+    let mut crc: u16 = 0;
+    for byte in tag {
+        crc ^= (*byte as u16) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x1021
+            } else {
+                crc << 1
+            };
+        }
+    }
+    crc % SLOTS
+}
@@ -0,0 +1,68 @@
+use redis::{Cmd, ConnectionLike, FromRedisValue, RedisResult};
+use std::collections::{HashSet, VecDeque};
+use std::hash::Hash;
+use std::marker::PhantomData;
+
+/// An iterator over the items of a `SCAN`, `HSCAN`, `SSCAN` or `ZSCAN` command built by
+/// [`redis_scan!`](crate::redis_scan), which sends the command again with each cursor the server
+/// returns until the cursor is back at `0`
+///
+/// Batches are only requested once the items before them have been read. An error ends the
+/// iteration after it is returned. The server may return an item more than once while the keyspace
+/// changes, which [`Scan::unique`] filters out.
+pub struct Scan<'a, T, F> {
+    con: &'a mut dyn ConnectionLike,
+    cmd: F,
+    /// The cursor of the next batch, which is `None` once the scan is done
+    cursor: Option<u64>,
+    items: VecDeque<T>,
+    reply: PhantomData<fn() -> T>,
+}
+
+impl<'a, T, F: FnMut(u64) -> Cmd> Scan<'a, T, F> {
+    /// Scan with the commands built by `cmd` for each cursor, starting at `0`
+    pub fn new(con: &'a mut dyn ConnectionLike, cmd: F) -> Scan<'a, T, F> {
+        Scan {
+            con,
+            cmd,
+            cursor: Some(0),
+            items: VecDeque::new(),
+            reply: PhantomData,
+        }
+    }
+}
+
+impl<'a, T: FromRedisValue + Hash + Eq + Clone + 'a, F: FnMut(u64) -> Cmd + 'a> Scan<'a, T, F> {
+    /// Skip items that were already returned, keeping every item seen so far in memory
+    pub fn unique(self) -> impl Iterator<Item = RedisResult<T>> + 'a {
+        let mut seen = HashSet::new();
+        self.filter(move |item| match item {
+            Ok(item) => seen.insert(item.clone()),
+            Err(_) => true,
+        })
+    }
+}
+
+impl<T: FromRedisValue, F: FnMut(u64) -> Cmd> Iterator for Scan<'_, T, F> {
+    type Item = RedisResult<T>;
+
+    fn next(&mut self) -> Option<RedisResult<T>> {
+        loop {
+            if let Some(item) = self.items.pop_front() {
+                return Some(Ok(item));
+            }
+            // Batches can be empty while the cursor isn't done yet
+            let cursor = self.cursor?;
+            match (self.cmd)(cursor).query::<(u64, Vec<T>)>(self.con) {
+                Ok((next, items)) => {
+                    self.cursor = (next != 0).then_some(next);
+                    self.items = items.into();
+                }
+                Err(err) => {
+                    self.cursor = None;
+                    return Some(Err(err));
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,42 @@
+use redis::ToRedisArgs;
+
+/// A sorted set score
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` is not a sorted set score",
+    note = "`{{..scores pairs}}` takes `(score, member)` pairs, so check that they aren't swapped"
+)]
+pub trait Score: ToRedisArgs {}
+
+impl Score for f64 {}
+impl Score for f32 {}
+impl Score for i8 {}
+impl Score for i16 {}
+impl Score for i32 {}
+impl Score for i64 {}
+impl Score for isize {}
+impl Score for u8 {}
+impl Score for u16 {}
+impl Score for u32 {}
+impl Score for u64 {}
+impl Score for usize {}
+impl<T: Score> Score for &T {}
+
+/// A `(score, member)` pair, or a reference to one
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` is not a `(score, member)` pair",
+    note = "`{{..scores pairs}}` takes `(score, member)` pairs, so check that they aren't swapped"
+)]
+pub trait ScorePair: ToRedisArgs {}
+
+impl<S: Score, M: ToRedisArgs> ScorePair for (S, M) {}
+impl<S: Score, M: ToRedisArgs> ScorePair for &(S, M) {}
+
+/// The items of a `{..scores pairs}` substitution, each of which writes its score followed by
+/// its member
+pub fn scores<I>(pairs: I) -> I::IntoIter
+where
+    I: IntoIterator,
+    I::Item: ScorePair,
+{
+    pairs.into_iter()
+}
@@ -0,0 +1,75 @@
+use crate::Args;
+use redis::{Cmd, ConnectionLike, ErrorKind, FromRedisValue, RedisResult, ToRedisArgs};
+
+/// A call of a Lua script whose SHA1 was computed at compile time, built by
+/// [`redis_eval!`](crate::redis_eval) and [`#[redis_script]`](crate::redis_script). It is sent
+/// with `EVALSHA`, and sent again with `EVAL` when the server replies `NOSCRIPT`, which also loads
+/// the script so that the next call finds it.
+#[derive(Clone, Debug)]
+pub struct ScriptCmd {
+    source: &'static str,
+    sha1: &'static str,
+    keys: Args,
+    args: Args,
+}
+
+impl ScriptCmd {
+    /// A call of the script with the given source and SHA1, without keys or arguments
+    pub fn new(source: &'static str, sha1: &'static str) -> ScriptCmd {
+        ScriptCmd {
+            source,
+            sha1,
+            keys: Args::new(),
+            args: Args::new(),
+        }
+    }
+
+    /// Append a key
+    pub fn key<T: ToRedisArgs>(&mut self, key: T) -> &mut ScriptCmd {
+        self.keys.arg(key);
+        self
+    }
+
+    /// Append an argument
+    pub fn arg<T: ToRedisArgs>(&mut self, arg: T) -> &mut ScriptCmd {
+        self.args.arg(arg);
+        self
+    }
+
+    /// The source of the script
+    pub fn source(&self) -> &'static str {
+        self.source
+    }
+
+    /// The SHA1 of the script, as a hex string
+    pub fn sha1(&self) -> &'static str {
+        self.sha1
+    }
+
+    /// The `EVALSHA` command of the call
+    pub fn evalsha_cmd(&self) -> Cmd {
+        self.cmd("EVALSHA", self.sha1)
+    }
+
+    /// The `EVAL` command of the call, which sends the whole script
+    pub fn eval_cmd(&self) -> Cmd {
+        self.cmd("EVAL", self.source)
+    }
+
+    fn cmd(&self, name: &str, script: &str) -> Cmd {
+        let mut cmd = redis::cmd(name);
+        cmd.arg(script)
+            .arg(self.keys.as_slice().len())
+            .arg(&self.keys)
+            .arg(&self.args);
+        cmd
+    }
+
+    /// Send the call with `EVALSHA`, falling back to `EVAL` if the script isn't loaded
+    pub fn invoke<T: FromRedisValue>(&self, con: &mut dyn ConnectionLike) -> RedisResult<T> {
+        match self.evalsha_cmd().query(con) {
+            Err(err) if err.kind() == ErrorKind::NoScriptError => self.eval_cmd().query(con),
+            result => result,
+        }
+    }
+}
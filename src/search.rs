@@ -0,0 +1,179 @@
+use redis::{Cmd, ConnectionLike, ErrorKind, FromRedisValue, RedisError, RedisResult, Value};
+use std::marker::PhantomData;
+
+/// Which items follow the ID of each document in an `FT.SEARCH` reply, from the options of the
+/// command
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Layout {
+    /// The fields of the document, unless the command has `NOCONTENT`
+    pub content: bool,
+    /// `WITHSCORES`
+    pub scores: bool,
+    /// `WITHPAYLOADS`
+    pub payloads: bool,
+    /// `WITHSORTKEYS`
+    pub sort_keys: bool,
+}
+
+/// An `FT.SEARCH` command built by [`redis_search!`](crate::redis_search), whose reply is read as
+/// [`SearchResults`] of `T`
+pub struct SearchCmd<T> {
+    cmd: Cmd,
+    layout: Layout,
+    reply: PhantomData<fn() -> T>,
+}
+
+impl<T> SearchCmd<T> {
+    /// Wrap an `FT.SEARCH` command whose reply has the given layout
+    #[doc(hidden)]
+    pub fn new(cmd: Cmd, layout: Layout) -> SearchCmd<T> {
+        SearchCmd {
+            cmd,
+            layout,
+            reply: PhantomData,
+        }
+    }
+
+    /// The underlying command
+    pub fn cmd(&self) -> &Cmd {
+        &self.cmd
+    }
+
+    /// Take the underlying command out, to add it to a pipeline. Its reply can then be read with
+    /// [`SearchCmd::read`].
+    pub fn into_cmd(self) -> Cmd {
+        self.cmd
+    }
+}
+
+impl<T: FromRedisValue> SearchCmd<T> {
+    /// Send the command and read the documents of the reply as `T`
+    pub fn query(&self, con: &mut dyn ConnectionLike) -> RedisResult<SearchResults<T>> {
+        let value: Value = self.cmd.query(con)?;
+        self.read(&value)
+    }
+
+    /// Read the reply of the command, such as one taken from the replies of a pipeline
+    pub fn read(&self, value: &Value) -> RedisResult<SearchResults<T>> {
+        let items = match value {
+            Value::Bulk(items) if !items.is_empty() => items,
+            _ => return Err(invalid("expected the reply of FT.SEARCH", value)),
+        };
+        let total = u64::from_redis_value(&items[0])?;
+        let mut rest = items[1..].iter();
+        let mut next = |what: &str| {
+            rest.next().ok_or_else(|| {
+                RedisError::from((
+                    ErrorKind::TypeError,
+                    "incomplete reply of FT.SEARCH",
+                    format!("missing the {} of a document", what),
+                ))
+            })
+        };
+        let mut docs = vec![];
+        while let Ok(id) = next("ID") {
+            let id = String::from_redis_value(id)?;
+            let score = match self.layout.scores {
+                true => Some(read_score(next("score")?)?),
+                false => None,
+            };
+            let payload = match self.layout.payloads {
+                true => FromRedisValue::from_redis_value(next("payload")?)?,
+                false => None,
+            };
+            let sort_key = match self.layout.sort_keys {
+                true => FromRedisValue::from_redis_value(next("sort key")?)?,
+                false => None,
+            };
+            let value = match self.layout.content {
+                true => T::from_redis_value(next("fields")?)?,
+                false => T::from_redis_value(&Value::Bulk(vec![]))?,
+            };
+            docs.push(SearchDoc {
+                id,
+                score,
+                payload,
+                sort_key,
+                value,
+            });
+        }
+        Ok(SearchResults { total, docs })
+    }
+}
+
+/// The reply of `FT.SEARCH`
+#[derive(Clone, Debug, PartialEq)]
+pub struct SearchResults<T> {
+    /// The number of documents that match the query, which can be more than are returned
+    pub total: u64,
+    /// The documents returned, within the `LIMIT` of the command
+    pub docs: Vec<SearchDoc<T>>,
+}
+
+/// A document of the reply of `FT.SEARCH`
+#[derive(Clone, Debug, PartialEq)]
+pub struct SearchDoc<T> {
+    /// The key of the document
+    pub id: String,
+    /// The score of the document, with `WITHSCORES`
+    pub score: Option<f64>,
+    /// The payload of the document, with `WITHPAYLOADS`, if it has one
+    pub payload: Option<Vec<u8>>,
+    /// The value the document is sorted by, with `WITHSORTKEYS`, if it has one
+    pub sort_key: Option<String>,
+    /// The fields of the document, read from alternating names and values. With `NOCONTENT`, they
+    /// are read from an empty array, as `()` does.
+    pub value: T,
+}
+
+/// The reply of `FT.AGGREGATE`, as read by the query of [`redis_search!`](crate::redis_search)
+#[derive(Clone, Debug, PartialEq)]
+pub struct AggregateResults<T> {
+    /// The number of results reported by the server
+    pub total: u64,
+    /// The rows of the results, each read from alternating names and values
+    pub rows: Vec<T>,
+    /// With `WITHCURSOR`, the cursor to read more rows with `FT.CURSOR READ`, which is 0 once all
+    /// rows have been read
+    pub cursor: Option<u64>,
+}
+
+impl<T: FromRedisValue> FromRedisValue for AggregateResults<T> {
+    fn from_redis_value(value: &Value) -> RedisResult<AggregateResults<T>> {
+        let (items, cursor) = match value {
+            // WITHCURSOR replies with the results and the cursor
+            Value::Bulk(items) if matches!(items.first(), Some(Value::Bulk(_))) => {
+                match items.as_slice() {
+                    [Value::Bulk(results), cursor] => {
+                        (results, Some(u64::from_redis_value(cursor)?))
+                    }
+                    _ => return Err(invalid("expected the reply of FT.AGGREGATE", value)),
+                }
+            }
+            Value::Bulk(items) if !items.is_empty() => (items, None),
+            _ => return Err(invalid("expected the reply of FT.AGGREGATE", value)),
+        };
+        let total = u64::from_redis_value(&items[0])?;
+        let rows = items[1..]
+            .iter()
+            .map(T::from_redis_value)
+            .collect::<RedisResult<_>>()?;
+        Ok(AggregateResults {
+            total,
+            rows,
+            cursor,
+        })
+    }
+}
+
+/// Read a score, which `EXPLAINSCORE` replies with along with its explanation
+fn read_score(value: &Value) -> RedisResult<f64> {
+    match value {
+        Value::Bulk(items) if !items.is_empty() => f64::from_redis_value(&items[0]),
+        value => f64::from_redis_value(value),
+    }
+}
+
+fn invalid(msg: &'static str, value: &Value) -> RedisError {
+    RedisError::from((ErrorKind::TypeError, msg, format!("{:?}", value)))
+}
@@ -0,0 +1,141 @@
+use crate::retry::RetryPolicy;
+use redis::{
+    Client, Cmd, Connection, ConnectionAddr, ConnectionInfo, ErrorKind, FromRedisValue,
+    IntoConnectionInfo, RedisConnectionInfo, RedisError, RedisResult,
+};
+use std::thread;
+
+/// A connection to the master of a service that is monitored by Redis Sentinel, used by
+/// [`redis_sentinel!`](crate::redis_sentinel). This is what the `sentinel` feature of `redis` does,
+/// without the `rand` dependency that it needs.
+///
+/// The master is looked up with `SENTINEL GET-MASTER-ADDR-BY-NAME` on the first sentinel that
+/// answers, which then moves to the front of the list as clients of Sentinel are asked to do. The
+/// connection to the master is kept until a command fails with an error that a failover causes,
+/// after which the master is looked up again and the command is retried, following the
+/// [`RetryPolicy`].
+pub struct Sentinel {
+    sentinels: Vec<ConnectionInfo>,
+    service: String,
+    /// The database and credentials of the master, which don't come from the sentinels
+    redis: RedisConnectionInfo,
+    policy: RetryPolicy,
+    master: Option<Connection>,
+}
+
+impl Sentinel {
+    /// Connect to the master of `service` through any of `sentinels`, given as anything that
+    /// `redis::Client::open` takes, such as `"redis://10.0.0.1:26379"`
+    pub fn new<T: IntoConnectionInfo>(
+        sentinels: impl IntoIterator<Item = T>,
+        service: impl Into<String>,
+    ) -> RedisResult<Sentinel> {
+        let sentinels = sentinels
+            .into_iter()
+            .map(IntoConnectionInfo::into_connection_info)
+            .collect::<RedisResult<Vec<_>>>()?;
+        if sentinels.is_empty() {
+            return Err(RedisError::from((
+                ErrorKind::InvalidClientConfig,
+                "no sentinels were given",
+            )));
+        }
+        Ok(Sentinel {
+            sentinels,
+            service: service.into(),
+            redis: RedisConnectionInfo::default(),
+            policy: RetryPolicy::default(),
+            master: None,
+        })
+    }
+
+    /// Use the database and credentials of `redis` on the master
+    pub fn with_redis(mut self, redis: RedisConnectionInfo) -> Sentinel {
+        self.redis = redis;
+        self
+    }
+
+    /// Retry commands that fail during a failover following `policy`
+    pub fn with_policy(mut self, policy: RetryPolicy) -> Sentinel {
+        self.policy = policy;
+        self
+    }
+
+    /// The name of the service
+    pub fn service(&self) -> &str {
+        &self.service
+    }
+
+    /// Whether an error comes from a failover: the master went away, or it is now a replica that
+    /// rejects writes with `READONLY`, as well as the transient errors of [`RetryPolicy`]
+    pub fn is_failover(err: &RedisError) -> bool {
+        RetryPolicy::is_transient(err) || err.kind() == ErrorKind::ReadOnly
+    }
+
+    /// Ask the sentinels for the address of the current master
+    pub fn master_addr(&mut self) -> RedisResult<(String, u16)> {
+        let mut last_err = None;
+        for index in 0..self.sentinels.len() {
+            let reply = Client::open(self.sentinels[index].clone())
+                .and_then(|client| client.get_connection())
+                .and_then(|mut con| {
+                    redis::cmd("SENTINEL")
+                        .arg("GET-MASTER-ADDR-BY-NAME")
+                        .arg(&self.service)
+                        .query::<Option<(String, u16)>>(&mut con)
+                });
+            match reply {
+                Ok(Some(addr)) => {
+                    self.sentinels[..=index].rotate_right(1);
+                    return Ok(addr);
+                }
+                Ok(None) => {
+                    last_err = Some(RedisError::from((
+                        ErrorKind::MasterDown,
+                        "the sentinel doesn't know the master of the service",
+                        self.service.clone(),
+                    )))
+                }
+                Err(err) => last_err = Some(err),
+            }
+        }
+        Err(last_err.expect("there is at least one sentinel"))
+    }
+
+    /// The connection to the current master, which is looked up and connected to when there is
+    /// none
+    pub fn master(&mut self) -> RedisResult<&mut Connection> {
+        if self.master.is_none() {
+            let (host, port) = self.master_addr()?;
+            let info = ConnectionInfo {
+                addr: ConnectionAddr::Tcp(host, port),
+                redis: self.redis.clone(),
+            };
+            self.master = Some(Client::open(info)?.get_connection()?);
+        }
+        Ok(self
+            .master
+            .as_mut()
+            .expect("the master was just connected to"))
+    }
+
+    /// Send a command to the master, looking the master up again and retrying the command while it
+    /// fails because of a failover
+    pub fn query<T: FromRedisValue>(&mut self, cmd: &Cmd) -> RedisResult<T> {
+        let mut retry = 0;
+        loop {
+            match self.master().and_then(|con| cmd.query(con)) {
+                Err(err) if Self::is_failover(&err) => {
+                    // The next attempt looks the master up again
+                    self.master = None;
+                    if retry >= self.policy.retries {
+                        return Err(err);
+                    }
+                    thread::sleep(self.policy.delay(retry));
+                }
+                result => return result,
+            }
+            retry += 1;
+        }
+    }
+}
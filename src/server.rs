@@ -0,0 +1,97 @@
+use redis::{Client, Connection, RedisResult};
+use std::ffi::OsStr;
+use std::io;
+use std::net::TcpListener;
+use std::process::{Child, Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How long a server has to start accepting connections
+const START_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A throwaway `redis-server` on a free port, which is killed when dropped. Used by
+/// [`#[redis_integration_test]`](crate::redis_integration_test).
+///
+/// The server doesn't save snapshots or an append-only file, so it leaves nothing behind.
+pub struct TestServer {
+    process: Child,
+    port: u16,
+}
+
+impl TestServer {
+    /// Start the `redis-server` found on `PATH`, or the one at the path in the `REDIS_SERVER`
+    /// environment variable, and wait until it accepts connections
+    pub fn start() -> io::Result<TestServer> {
+        match std::env::var_os("REDIS_SERVER") {
+            Some(program) => TestServer::start_program(program),
+            None => TestServer::start_program("redis-server"),
+        }
+    }
+
+    /// Start the server at `program`, and wait until it accepts connections
+    pub fn start_program<P: AsRef<OsStr>>(program: P) -> io::Result<TestServer> {
+        // The OS picks a free port, which is released for the server to bind
+        let port = TcpListener::bind("127.0.0.1:0")?.local_addr()?.port();
+        let process = Command::new(program.as_ref())
+            .args(["--port", &port.to_string(), "--bind", "127.0.0.1"])
+            .args(["--save", "", "--appendonly", "no"])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|err| {
+                let program = program.as_ref().to_string_lossy();
+                io::Error::new(err.kind(), format!("couldn't run {}: {}", program, err))
+            })?;
+        let mut server = TestServer { process, port };
+        server.wait_until_ready()?;
+        Ok(server)
+    }
+
+    fn wait_until_ready(&mut self) -> io::Result<()> {
+        let start = Instant::now();
+        loop {
+            if let Some(status) = self.process.try_wait()? {
+                let msg = format!("redis-server exited with {}", status);
+                return Err(io::Error::other(msg));
+            }
+            let ping = self
+                .connection()
+                .and_then(|mut con| redis::cmd("PING").query::<String>(&mut con));
+            if ping.is_ok() {
+                return Ok(());
+            }
+            if start.elapsed() > START_TIMEOUT {
+                let msg = "redis-server didn't accept connections in time";
+                return Err(io::Error::new(io::ErrorKind::TimedOut, msg));
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    /// The port the server listens on
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    /// The `redis://` URL of the server
+    pub fn url(&self) -> String {
+        format!("redis://127.0.0.1:{}/", self.port)
+    }
+
+    /// A client of the server
+    pub fn client(&self) -> RedisResult<Client> {
+        Client::open(self.url())
+    }
+
+    /// A new connection to the server
+    pub fn connection(&self) -> RedisResult<Connection> {
+        self.client()?.get_connection()
+    }
+}
+
+impl Drop for TestServer {
+    fn drop(&mut self) {
+        let _ = self.process.kill();
+        let _ = self.process.wait();
+    }
+}
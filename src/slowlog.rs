@@ -0,0 +1,57 @@
+use redis::{ErrorKind, FromRedisValue, RedisError, RedisResult, Value};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// An entry of the slow log, as returned by the query of
+/// [`redis_slowlog!`](crate::redis_slowlog)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SlowlogEntry {
+    /// The unique, increasing ID of the entry
+    pub id: u64,
+    /// When the command was run
+    pub timestamp: SystemTime,
+    /// How long the command took to run, without the time spent on I/O
+    pub duration: Duration,
+    /// The command and its arguments, which the server truncates when there are many or they are
+    /// long. Binary arguments are read lossily.
+    pub args: Vec<String>,
+    /// The address of the client, for servers from Redis 4.0
+    pub client_addr: Option<String>,
+    /// The name of the client set with `CLIENT SETNAME`, if it has one
+    pub client_name: Option<String>,
+}
+
+impl FromRedisValue for SlowlogEntry {
+    fn from_redis_value(value: &Value) -> RedisResult<SlowlogEntry> {
+        let items = match value {
+            Value::Bulk(items) if items.len() >= 4 => items,
+            _ => {
+                return Err(RedisError::from((
+                    ErrorKind::TypeError,
+                    "expected a slow log entry",
+                    format!("{:?}", value),
+                )))
+            }
+        };
+        let args: Vec<Vec<u8>> = FromRedisValue::from_redis_value(&items[3])?;
+        let text = |index: usize| -> RedisResult<Option<String>> {
+            match items.get(index) {
+                Some(value) => {
+                    let text: String = FromRedisValue::from_redis_value(value)?;
+                    Ok(Some(text).filter(|text| !text.is_empty()))
+                }
+                None => Ok(None),
+            }
+        };
+        Ok(SlowlogEntry {
+            id: FromRedisValue::from_redis_value(&items[0])?,
+            timestamp: UNIX_EPOCH + Duration::from_secs(u64::from_redis_value(&items[1])?),
+            duration: Duration::from_micros(u64::from_redis_value(&items[2])?),
+            args: args
+                .iter()
+                .map(|arg| String::from_utf8_lossy(arg).into_owned())
+                .collect(),
+            client_addr: text(4)?,
+            client_name: text(5)?,
+        })
+    }
+}
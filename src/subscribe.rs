@@ -0,0 +1,114 @@
+use redis::{Connection, FromRedisValue, Msg, RedisResult, ToRedisArgs};
+use std::ops::ControlFlow;
+
+/// A value returned by the handlers of [`redis_subscribe!`](crate::redis_subscribe), which decides
+/// whether to keep receiving messages
+pub trait HandlerResult {
+    /// `Break` stops receiving messages
+    fn into_flow(self) -> ControlFlow<()>;
+}
+
+impl HandlerResult for () {
+    fn into_flow(self) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+}
+
+impl HandlerResult for ControlFlow<()> {
+    fn into_flow(self) -> ControlFlow<()> {
+        self
+    }
+}
+
+pub(crate) type Handler<'a> = Box<dyn FnMut(&Msg) -> RedisResult<ControlFlow<()>> + 'a>;
+
+/// The channels and patterns of a subscription built by
+/// [`redis_subscribe!`](crate::redis_subscribe), each with the handler its messages are passed to
+#[derive(Default)]
+pub struct Subscriber<'a> {
+    channels: Vec<(Vec<Vec<u8>>, Handler<'a>)>,
+    patterns: Vec<(Vec<Vec<u8>>, Handler<'a>)>,
+}
+
+impl<'a> Subscriber<'a> {
+    /// A subscription without any channels
+    pub fn new() -> Subscriber<'a> {
+        Subscriber::default()
+    }
+
+    /// Pass the payloads of the messages on `channels` to `handler`, read as `T`. Every argument
+    /// of `channels` is its own channel.
+    pub fn on<T, R>(
+        mut self,
+        channels: impl ToRedisArgs,
+        mut handler: impl FnMut(T) -> R + 'a,
+    ) -> Self
+    where
+        T: FromRedisValue,
+        R: HandlerResult,
+    {
+        let handler = move |msg: &Msg| Ok(handler(msg.get_payload()?).into_flow());
+        self.channels
+            .push((channels.to_redis_args(), Box::new(handler)));
+        self
+    }
+
+    /// Pass the messages on `channels` to `handler` as they are
+    pub(crate) fn on_message(mut self, channels: Vec<Vec<u8>>, handler: Handler<'a>) -> Self {
+        self.channels.push((channels, handler));
+        self
+    }
+
+    /// Pass the channel names and payloads of the messages matching `patterns` to `handler`, read
+    /// as `C` and `T`. Every argument of `patterns` is its own glob-style pattern.
+    pub fn on_pattern<C, T, R>(
+        mut self,
+        patterns: impl ToRedisArgs,
+        mut handler: impl FnMut(C, T) -> R + 'a,
+    ) -> Self
+    where
+        C: FromRedisValue,
+        T: FromRedisValue,
+        R: HandlerResult,
+    {
+        let handler =
+            move |msg: &Msg| Ok(handler(msg.get_channel()?, msg.get_payload()?).into_flow());
+        self.patterns
+            .push((patterns.to_redis_args(), Box::new(handler)));
+        self
+    }
+
+    /// Pass a message to the handler of its channel, or of the pattern it matched. Other messages
+    /// are skipped.
+    pub fn dispatch(&mut self, msg: &Msg) -> RedisResult<ControlFlow<()>> {
+        let (routes, name) = match msg.from_pattern() {
+            true => (&mut self.patterns, msg.get_pattern::<Vec<u8>>()?),
+            false => (&mut self.channels, msg.get_channel::<Vec<u8>>()?),
+        };
+        let handler = routes.iter_mut().find(|(names, _)| names.contains(&name));
+        match handler {
+            Some((_, handler)) => handler(msg),
+            None => Ok(ControlFlow::Continue(())),
+        }
+    }
+
+    /// Subscribe to every channel and pattern and pass messages to their handlers, until a handler
+    /// returns `ControlFlow::Break` or receiving or reading a message fails
+    pub fn run(mut self, con: &mut Connection) -> RedisResult<()> {
+        let mut pubsub = con.as_pubsub();
+        let channels: Vec<&Vec<u8>> = self.channels.iter().flat_map(|(c, _)| c).collect();
+        if !channels.is_empty() {
+            pubsub.subscribe(channels)?;
+        }
+        let patterns: Vec<&Vec<u8>> = self.patterns.iter().flat_map(|(p, _)| p).collect();
+        if !patterns.is_empty() {
+            pubsub.psubscribe(patterns)?;
+        }
+        loop {
+            let msg = pubsub.get_message()?;
+            if self.dispatch(&msg)?.is_break() {
+                return Ok(());
+            }
+        }
+    }
+}
@@ -0,0 +1,138 @@
+use redis::ToRedisArgs;
+use std::borrow::Borrow;
+use std::time::{Duration, SystemTime};
+
+// Time arguments are dispatched on their type with autoref specialization. The macro expands a TTL
+// operand to `(&value).ttl_kind().seconds(value)`, and a timestamp to
+// `(&value).timestamp_kind().seconds(value)`. Method resolution picks `DurationKind` or
+// `TimestampKind` without autoref, and only falls back to `ArgsKind` (which needs an extra
+// reference) for everything else.
+
+/// A point in time that can be sent as a unix timestamp
+pub trait Timestamp {
+    /// The time since the unix epoch, or zero for times before it
+    fn since_epoch(&self) -> Duration;
+}
+
+impl<T: Timestamp + ?Sized> Timestamp for &T {
+    fn since_epoch(&self) -> Duration {
+        (**self).since_epoch()
+    }
+}
+
+impl Timestamp for SystemTime {
+    fn since_epoch(&self) -> Duration {
+        self.duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl<Tz: chrono::TimeZone> Timestamp for chrono::DateTime<Tz> {
+    fn since_epoch(&self) -> Duration {
+        u64::try_from(self.timestamp())
+            .map(|secs| Duration::new(secs, self.timestamp_subsec_nanos()))
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(feature = "time")]
+impl Timestamp for ::time::OffsetDateTime {
+    fn since_epoch(&self) -> Duration {
+        (*self - ::time::OffsetDateTime::UNIX_EPOCH)
+            .try_into()
+            .unwrap_or_default()
+    }
+}
+
+/// Converts a `Duration` TTL to the unit of its option
+pub struct DurationTtl;
+
+/// Converts a `Timestamp` to the unit of its option
+pub struct TimestampAt;
+
+/// Passes any other argument through unchanged
+pub struct Args;
+
+pub trait DurationKind {
+    #[inline]
+    fn ttl_kind(&self) -> DurationTtl {
+        DurationTtl
+    }
+}
+
+impl DurationKind for Duration {}
+impl DurationKind for &Duration {}
+
+pub trait TimestampKind {
+    #[inline]
+    fn timestamp_kind(&self) -> TimestampAt {
+        TimestampAt
+    }
+}
+
+impl<T: Timestamp> TimestampKind for T {}
+
+pub trait ArgsKind {
+    #[inline]
+    fn ttl_kind(&self) -> Args {
+        Args
+    }
+
+    #[inline]
+    fn timestamp_kind(&self) -> Args {
+        Args
+    }
+}
+
+impl<T: ToRedisArgs> ArgsKind for &T {}
+
+impl DurationTtl {
+    /// Whole seconds, rounding any fractional part up, so that a sub-second TTL isn't sent as an
+    /// invalid `0` and a key never expires early
+    #[inline]
+    pub fn seconds(self, ttl: impl Borrow<Duration>) -> u64 {
+        let ttl = ttl.borrow();
+        let partial = ttl.subsec_nanos() > 0;
+        ttl.as_secs().saturating_add(partial as u64)
+    }
+
+    /// Whole milliseconds, rounding any fractional part up as [`DurationTtl::seconds`] does, and
+    /// saturating at `u64::MAX`
+    #[inline]
+    pub fn milliseconds(self, ttl: impl Borrow<Duration>) -> u64 {
+        let ttl = ttl.borrow();
+        let partial = ttl.subsec_nanos() % 1_000_000 > 0;
+        millis(*ttl).saturating_add(partial as u64)
+    }
+}
+
+impl TimestampAt {
+    /// Whole seconds since the unix epoch, truncating any fractional part
+    #[inline]
+    pub fn seconds(self, at: impl Timestamp) -> u64 {
+        at.since_epoch().as_secs()
+    }
+
+    /// Whole milliseconds since the unix epoch, saturating at `u64::MAX`
+    #[inline]
+    pub fn milliseconds(self, at: impl Timestamp) -> u64 {
+        millis(at.since_epoch())
+    }
+}
+
+impl Args {
+    #[inline]
+    pub fn seconds<T>(self, value: T) -> T {
+        value
+    }
+
+    #[inline]
+    pub fn milliseconds<T>(self, value: T) -> T {
+        value
+    }
+}
+
+pub(crate) fn millis(duration: Duration) -> u64 {
+    u64::try_from(duration.as_millis()).unwrap_or(u64::MAX)
+}
@@ -0,0 +1,81 @@
+use redis::{ErrorKind, FromRedisValue, RedisError, RedisResult, Value};
+use std::time::{Duration, SystemTime};
+
+/// The samples of a time series, as replied by `TS.RANGE` and `TS.REVRANGE`, with the millisecond
+/// timestamps read as `SystemTime`s
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Samples(pub Vec<(SystemTime, f64)>);
+
+impl FromRedisValue for Samples {
+    fn from_redis_value(value: &Value) -> RedisResult<Samples> {
+        let items = match value {
+            Value::Bulk(items) => items,
+            Value::Nil => return Ok(Samples::default()),
+            _ => return Err(invalid("expected the samples of a time series", value)),
+        };
+        items
+            .iter()
+            .map(sample)
+            .collect::<RedisResult<_>>()
+            .map(Samples)
+    }
+}
+
+impl IntoIterator for Samples {
+    type Item = (SystemTime, f64);
+    type IntoIter = std::vec::IntoIter<(SystemTime, f64)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+/// A time series of the reply of `TS.MRANGE` or `TS.MREVRANGE`
+#[derive(Clone, Debug, PartialEq)]
+pub struct Series {
+    /// The key of the series, or `label=value` for the groups of `GROUPBY`
+    pub key: String,
+    /// The labels of the series, with `WITHLABELS` or `SELECTED_LABELS`
+    pub labels: Vec<(String, String)>,
+    /// The samples of the series in the range
+    pub samples: Samples,
+}
+
+impl FromRedisValue for Series {
+    fn from_redis_value(value: &Value) -> RedisResult<Series> {
+        let [key, labels, samples] = match value {
+            Value::Bulk(items) if items.len() == 3 => [&items[0], &items[1], &items[2]],
+            _ => return Err(invalid("expected a time series", value)),
+        };
+        let labels = match labels {
+            Value::Bulk(labels) => labels
+                .iter()
+                .map(|label| {
+                    // Labels that a series doesn't have are nil with SELECTED_LABELS
+                    let (name, value): (String, Option<String>) =
+                        FromRedisValue::from_redis_value(label)?;
+                    Ok((name, value.unwrap_or_default()))
+                })
+                .collect::<RedisResult<_>>()?,
+            _ => return Err(invalid("expected the labels of a time series", labels)),
+        };
+        Ok(Series {
+            key: String::from_redis_value(key)?,
+            labels,
+            samples: Samples::from_redis_value(samples)?,
+        })
+    }
+}
+
+/// Read a `[timestamp, value]` sample, whose value is sent as a string
+fn sample(value: &Value) -> RedisResult<(SystemTime, f64)> {
+    let (timestamp, sample): (u64, f64) = FromRedisValue::from_redis_value(value)?;
+    Ok((
+        SystemTime::UNIX_EPOCH + Duration::from_millis(timestamp),
+        sample,
+    ))
+}
+
+fn invalid(msg: &'static str, value: &Value) -> RedisError {
+    RedisError::from((ErrorKind::TypeError, msg, format!("{:?}", value)))
+}
@@ -0,0 +1,37 @@
+use redis::{Cmd, ConnectionLike, FromRedisValue, RedisResult};
+use std::marker::PhantomData;
+
+/// A command built by [`redis!`](crate::redis) with a `-> Type` annotation, whose reply is always
+/// read as `T`. For async connections, [`redis_async!`](crate::redis_async) takes the same
+/// annotation.
+pub struct TypedCmd<T> {
+    cmd: Cmd,
+    reply: PhantomData<fn() -> T>,
+}
+
+impl<T> TypedCmd<T> {
+    /// Wrap a command whose reply is read as `T`
+    pub fn new(cmd: Cmd) -> TypedCmd<T> {
+        TypedCmd {
+            cmd,
+            reply: PhantomData,
+        }
+    }
+
+    /// The underlying command
+    pub fn cmd(&self) -> &Cmd {
+        &self.cmd
+    }
+
+    /// Take the underlying command out, to add it to a pipeline or query it with another type
+    pub fn into_cmd(self) -> Cmd {
+        self.cmd
+    }
+}
+
+impl<T: FromRedisValue> TypedCmd<T> {
+    /// Send the command and read the reply as `T`
+    pub fn query(&self, con: &mut dyn ConnectionLike) -> RedisResult<T> {
+        self.cmd.query(con)
+    }
+}
@@ -110,3 +110,56 @@ fn test_base_usage_dollar() {
         Ok(Value::Data(b"$".as_ref().into()))
     );
 }
+
+#[test]
+fn test_base_usage_commas() {
+    let mut conn = MockRedisConnection::new(vec![
+        MockCmd::new(redis::cmd("SET").arg("foo").arg("bar"), Ok("")),
+        MockCmd::new(redis::cmd("GET").arg("foo"), Ok("bar")),
+    ]);
+
+    redis!(SET foo, bar).execute(&mut conn);
+    assert_eq!(
+        redis!(GET foo,).query(&mut conn),
+        Ok(Value::Data(b"bar".as_ref().into()))
+    );
+}
+
+#[test]
+fn test_base_usage_lowercase() {
+    let mut conn = MockRedisConnection::new(vec![
+        MockCmd::new(redis::cmd("SET").arg("foo").arg("bar"), Ok("")),
+        MockCmd::new(redis::cmd("my.Get").arg("foo"), Ok("bar")),
+    ]);
+
+    redis!(set foo bar).execute(&mut conn);
+    assert_eq!(
+        redis!("my.Get" foo).query(&mut conn),
+        Ok(Value::Data(b"bar".as_ref().into()))
+    );
+}
+
+#[test]
+fn test_base_usage_numbers() {
+    let mut conn = MockRedisConnection::new(vec![
+        MockCmd::new(redis::cmd("SETBIT").arg("key").arg("31").arg("1"), Ok("")),
+        MockCmd::new(redis::cmd("SET").arg("key").arg("1000000"), Ok("")),
+        MockCmd::new(redis::cmd("GET").arg("user:007"), Ok("")),
+    ]);
+
+    redis!(SETBIT key 0x1F 1).execute(&mut conn);
+    redis!(SET key 1_000_000).execute(&mut conn);
+    redis!(GET user:007).execute(&mut conn);
+}
+
+#[test]
+fn test_base_usage_raw_name() {
+    let name = "GET";
+    let mut conn = MockRedisConnection::new(vec![
+        MockCmd::new(redis::cmd("GET").arg("foo"), Ok("")),
+        MockCmd::new(redis::cmd("HGET").arg("foo").arg("bar"), Ok("")),
+    ]);
+
+    redis!({raw name} foo).execute(&mut conn);
+    redis!({raw &format!("H{}", name)} foo bar).execute(&mut conn);
+}
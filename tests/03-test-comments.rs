@@ -0,0 +1,24 @@
+use redis_rs_macro::redis;
+use redis_test::{MockCmd, MockRedisConnection};
+
+#[test]
+fn test_comments() {
+    let mut conn = MockRedisConnection::new(vec![
+        MockCmd::new(
+            redis::cmd("SET").arg("foo").arg("bar").arg("EX").arg("60"),
+            Ok(""),
+        ),
+        MockCmd::new(redis::cmd("GET").arg("foo"), Ok("bar")),
+    ]);
+
+    redis!(
+        # store foo for a minute
+        SET foo bar # the value
+        EX 60       # the ttl
+    )
+    .execute(&mut conn);
+    assert_eq!(
+        redis!(GET foo # read it back).query(&mut conn),
+        Ok("bar".to_string())
+    );
+}
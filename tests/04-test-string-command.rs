@@ -0,0 +1,68 @@
+use redis_rs_macro::redis;
+use redis_test::{MockCmd, MockRedisConnection};
+
+#[test]
+fn test_string_command() {
+    let mut conn = MockRedisConnection::new(vec![
+        MockCmd::new(
+            redis::cmd("SET").arg("foo").arg("bar").arg("EX").arg("30"),
+            Ok(""),
+        ),
+        MockCmd::new(redis::cmd("SET").arg("my key").arg("say \"hi\"\n"), Ok("")),
+        MockCmd::new(redis::cmd("PING"), Ok("PONG")),
+    ]);
+
+    redis!("SET foo bar EX 30").execute(&mut conn);
+    redis!("SET \"my key\" \"say \\\"hi\\\"\\n\"").execute(&mut conn);
+    assert_eq!(redis!("PING").query(&mut conn), Ok("PONG".to_string()));
+}
+
+#[test]
+fn test_string_command_expr_sub() {
+    let ttl = 30;
+    let mut conn = MockRedisConnection::new(vec![MockCmd::new(
+        redis::cmd("SET").arg("foo").arg("bar").arg("EX").arg(ttl),
+        Ok(""),
+    )]);
+
+    redis!("SET foo bar EX {ttl}").execute(&mut conn);
+}
+
+#[test]
+fn test_string_command_unicode() {
+    let mut conn = MockRedisConnection::new(vec![
+        MockCmd::new(redis::cmd("SET").arg("foo").arg("😀 é"), Ok("")),
+        MockCmd::new(redis::cmd("SET").arg("bar").arg("😀"), Ok("")),
+    ]);
+
+    redis!("SET foo \"\\u{1F600} \\u{e9}\"").execute(&mut conn);
+    redis!(SET bar "\u{1F600}").execute(&mut conn);
+}
+
+#[test]
+fn test_string_command_joined_sub() {
+    let id = 7;
+    let mut conn = MockRedisConnection::new(vec![
+        MockCmd::new(redis::cmd("GET").arg("user:7"), Ok("")),
+        MockCmd::new(redis::cmd("GET").arg("7:x"), Ok("")),
+    ]);
+
+    redis!("GET user:{id}").execute(&mut conn);
+    redis!("GET {id}:x").execute(&mut conn);
+}
+
+#[test]
+fn test_string_command_hex_bytes() {
+    // As in redis-cli, `\xNN` escapes any byte, including ones that aren't UTF-8
+    assert_eq!(
+        redis!("SET raw \"\\x00\\x7f\\x80\\xff\"").get_packed_command(),
+        redis::cmd("SET")
+            .arg("raw")
+            .arg(&b"\x00\x7f\x80\xff"[..])
+            .get_packed_command()
+    );
+    assert_eq!(
+        redis!("SET k \"\\xc3\\xa9\"").get_packed_command(),
+        redis::cmd("SET").arg("k").arg("é").get_packed_command()
+    );
+}
@@ -0,0 +1,16 @@
+use redis_rs_macro::redis;
+use redis_test::{MockCmd, MockRedisConnection};
+
+#[test]
+fn test_joining() {
+    let id = 42;
+    let mut conn = MockRedisConnection::new(vec![
+        MockCmd::new(redis::cmd("SET").arg("foo-bar").arg("a\"b"), Ok("")),
+        MockCmd::new(redis::cmd("GET").arg("user:42:name"), Ok("")),
+        MockCmd::new(redis::cmd("GET").arg("user:43"), Ok("")),
+    ]);
+
+    redis!(SET foo-bar "a\"b").execute(&mut conn);
+    redis!(GET user:{id}:name).execute(&mut conn);
+    redis!(GET user:{id + 1}).execute(&mut conn);
+}
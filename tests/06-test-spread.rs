@@ -0,0 +1,54 @@
+use redis_rs_macro::redis;
+use redis_test::{MockCmd, MockRedisConnection};
+use std::collections::{BTreeMap, HashMap};
+
+#[test]
+fn test_spread() {
+    let keys = vec!["a", "b", "c"];
+    let ids = [1, 2];
+    let mut conn = MockRedisConnection::new(vec![
+        MockCmd::new(redis::cmd("DEL").arg("a").arg("b").arg("c"), Ok(3)),
+        MockCmd::new(
+            redis::cmd("MGET").arg("user:1").arg("user:2").arg("x"),
+            Ok(""),
+        ),
+        MockCmd::new(redis::cmd("DEL"), Ok(0)),
+    ]);
+
+    assert_eq!(redis!(DEL { ..&keys }).query(&mut conn), Ok(3));
+    redis!(MGET {..ids.iter().map(|id| format!("user:{}", id))} x).execute(&mut conn);
+    assert_eq!(
+        redis!(DEL {
+            ..Vec::<String>::new()
+        })
+        .query(&mut conn),
+        Ok(0)
+    );
+}
+
+#[test]
+fn test_spread_pairs() {
+    let pairs = vec![("name", "alice"), ("age", "42")];
+    let fields = BTreeMap::from([("b", 2), ("a", 1)]);
+    let single = HashMap::from([("field", 1.5)]);
+    let mut conn = MockRedisConnection::new(vec![
+        MockCmd::new(
+            redis::cmd("HSET")
+                .arg("user:1")
+                .arg("name")
+                .arg("alice")
+                .arg("age")
+                .arg("42"),
+            Ok(2),
+        ),
+        MockCmd::new(redis::cmd("MSET").arg("a").arg(1).arg("b").arg(2), Ok("")),
+        MockCmd::new(
+            redis::cmd("HSET").arg("user:2").arg("field").arg(1.5),
+            Ok(1),
+        ),
+    ]);
+
+    assert_eq!(redis!(HSET user:1 {..&pairs}).query(&mut conn), Ok(2));
+    redis!(MSET { ..fields }).execute(&mut conn);
+    assert_eq!(redis!(HSET user:2 {..&single}).query(&mut conn), Ok(1));
+}
@@ -0,0 +1,37 @@
+use redis_rs_macro::redis;
+use redis_test::{MockCmd, MockRedisConnection};
+
+#[test]
+fn test_optional() {
+    let ttl: Option<u64> = Some(30);
+    let no_ttl: Option<u64> = None;
+    let field = Some("name".to_string());
+    let mut conn = MockRedisConnection::new(vec![
+        MockCmd::new(
+            redis::cmd("SET").arg("foo").arg(1).arg("EX").arg(30),
+            Ok(""),
+        ),
+        MockCmd::new(redis::cmd("SET").arg("foo").arg(1), Ok("")),
+        MockCmd::new(redis::cmd("HGET").arg("user").arg("name"), Ok("")),
+    ]);
+
+    redis!(SET foo {1} EX {ttl?}).execute(&mut conn);
+    redis!(SET foo {1} EX {no_ttl?}).execute(&mut conn);
+    redis!(HGET user {&field?}).execute(&mut conn);
+}
+
+#[test]
+fn test_optional_try() {
+    fn value() -> Result<i32, std::num::ParseIntError> {
+        "42".parse()
+    }
+    fn build() -> Result<redis::Cmd, std::num::ParseIntError> {
+        Ok(redis!(SET foo {(value()?)}))
+    }
+
+    let mut conn = MockRedisConnection::new(vec![MockCmd::new(
+        redis::cmd("SET").arg("foo").arg(42),
+        Ok(""),
+    )]);
+    build().unwrap().execute(&mut conn);
+}
@@ -0,0 +1,25 @@
+use redis_rs_macro::redis;
+use redis_test::{MockCmd, MockRedisConnection};
+
+#[test]
+fn test_conditional() {
+    let mut conn = MockRedisConnection::new(vec![
+        MockCmd::new(redis::cmd("SET").arg("foo").arg(1).arg("NX"), Ok("")),
+        MockCmd::new(redis::cmd("SET").arg("foo").arg(1).arg("KEEPTTL"), Ok("")),
+        MockCmd::new(
+            redis::cmd("SET")
+                .arg("foo")
+                .arg(1)
+                .arg("EX")
+                .arg(10)
+                .arg("GET"),
+            Ok(""),
+        ),
+    ]);
+
+    for (nx, keep) in [(true, false), (false, true)] {
+        redis!(SET foo {1} ?[{nx} => NX] ?[{keep} => KEEPTTL]).execute(&mut conn);
+    }
+    let ttl = Some(10);
+    redis!(SET foo {1} ?[{ttl.is_some()} => EX {ttl?} GET]).execute(&mut conn);
+}
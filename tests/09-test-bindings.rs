@@ -0,0 +1,47 @@
+use redis_rs_macro::redis;
+use redis_test::{MockCmd, MockRedisConnection};
+
+#[test]
+fn test_named_bindings() {
+    let user_key = String::from("user:1");
+    let mut calls = 0;
+    let mut next = || {
+        calls += 1;
+        calls
+    };
+    let mut conn = MockRedisConnection::new(vec![
+        MockCmd::new(
+            redis::cmd("SET")
+                .arg("user:1")
+                .arg("payload")
+                .arg("EX")
+                .arg(30),
+            Ok(""),
+        ),
+        MockCmd::new(redis::cmd("MSET").arg("a").arg(1).arg("b").arg(1), Ok("")),
+        MockCmd::new(redis::cmd("GET").arg(":key"), Ok("")),
+    ]);
+
+    redis!("SET :key :val EX :ttl"; key = &user_key, val = "payload", ttl = 30).execute(&mut conn);
+    redis!(MSET a :n b :n; n = next()).execute(&mut conn);
+    redis!(GET ":key").execute(&mut conn);
+    assert_eq!(calls, 1);
+}
+
+#[test]
+fn test_positional_bindings() {
+    let (key, field) = ("user:1", "name");
+    let mut conn = MockRedisConnection::new(vec![
+        MockCmd::new(
+            redis::cmd("HSET").arg("user:1").arg("name").arg("alice"),
+            Ok(1),
+        ),
+        MockCmd::new(redis::cmd("SET").arg("q").arg("?"), Ok("")),
+    ]);
+
+    assert_eq!(
+        redis!("HSET ? ? ?"; key, field, "alice").query(&mut conn),
+        Ok(1)
+    );
+    redis!(SET q ?).execute(&mut conn);
+}
@@ -0,0 +1,39 @@
+use redis_rs_macro::{redis, redis_template};
+use redis_test::{MockCmd, MockRedisConnection};
+
+redis_template!(
+    /// Fetch the name of a user
+    GetUserName = HGET user:{id} name
+);
+redis_template!(SetUser = SET user:{id} {data} EX {ttl?} ?[{nx} => NX]);
+redis_template!(DelAll = DEL { ..keys });
+
+#[test]
+fn test_template() {
+    let mut conn = MockRedisConnection::new(vec![
+        MockCmd::new(redis::cmd("HGET").arg("user:42").arg("name"), Ok("alice")),
+        MockCmd::new(
+            redis::cmd("SET")
+                .arg("user:42")
+                .arg("alice")
+                .arg("EX")
+                .arg(60)
+                .arg("NX"),
+            Ok(""),
+        ),
+        MockCmd::new(redis::cmd("SET").arg("user:7").arg("bob"), Ok("")),
+        MockCmd::new(redis::cmd("DEL").arg("a").arg("b"), Ok(2)),
+    ]);
+
+    assert_eq!(
+        GetUserName::bind(42).query(&mut conn),
+        Ok("alice".to_string())
+    );
+    SetUser::bind(42, "alice", Some(60), true).execute(&mut conn);
+    SetUser::bind(7, String::from("bob"), None::<u64>, false).execute(&mut conn);
+    assert_eq!(DelAll::bind(["a", "b"]).query(&mut conn), Ok(2));
+    assert_eq!(
+        redis!(HGET user:{42} name).get_packed_command(),
+        GetUserName::bind(42).get_packed_command()
+    );
+}
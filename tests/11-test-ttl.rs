@@ -0,0 +1,86 @@
+use redis_rs_macro::redis;
+use redis_test::{MockCmd, MockRedisConnection};
+use std::time::Duration;
+
+#[test]
+fn test_ttl_duration() {
+    let ttl = Duration::from_millis(1500);
+    let mut conn = MockRedisConnection::new(vec![
+        MockCmd::new(redis::cmd("SET").arg("foo").arg(1).arg("EX").arg(2), Ok("")),
+        MockCmd::new(
+            redis::cmd("SET").arg("foo").arg(1).arg("PX").arg(1500),
+            Ok(""),
+        ),
+        MockCmd::new(redis::cmd("EXPIRE").arg("foo").arg(2), Ok("")),
+        MockCmd::new(redis::cmd("PEXPIRE").arg("foo").arg(1500), Ok("")),
+        MockCmd::new(redis::cmd("SETEX").arg("foo").arg(30).arg("bar"), Ok("")),
+        MockCmd::new(redis::cmd("PSETEX").arg("foo").arg(1500).arg("bar"), Ok("")),
+    ]);
+
+    redis!(SET foo {1} EX {ttl}).execute(&mut conn);
+    redis!(SET foo {1} PX {&ttl}).execute(&mut conn);
+    redis!(EXPIRE foo {ttl}).execute(&mut conn);
+    redis!(PEXPIRE foo {ttl}).execute(&mut conn);
+    redis!(SETEX foo {Duration::from_secs(30)} bar).execute(&mut conn);
+    redis!(PSETEX foo {ttl} bar).execute(&mut conn);
+}
+
+#[test]
+fn test_ttl_sub_second() {
+    // Partial seconds and milliseconds are rounded up, as redis rejects an `EX` or `PX` of 0
+    let ttl = Duration::from_millis(500);
+    let mut conn = MockRedisConnection::new(vec![
+        MockCmd::new(redis::cmd("SET").arg("foo").arg(1).arg("EX").arg(1), Ok("")),
+        MockCmd::new(redis::cmd("EXPIRE").arg("foo").arg(1), Ok("")),
+        MockCmd::new(redis::cmd("EXPIRE").arg("foo").arg(0), Ok("")),
+        MockCmd::new(redis::cmd("SET").arg("foo").arg(1).arg("PX").arg(1), Ok("")),
+        MockCmd::new(redis::cmd("PEXPIRE").arg("foo").arg(2), Ok("")),
+        MockCmd::new(redis::cmd("PEXPIRE").arg("foo").arg(5), Ok("")),
+    ]);
+
+    redis!(SET foo {1} EX {ttl}).execute(&mut conn);
+    redis!(EXPIRE foo {Duration::from_nanos(1)}).execute(&mut conn);
+    redis!(EXPIRE foo {Duration::ZERO}).execute(&mut conn);
+    redis!(SET foo {1} PX {Duration::from_micros(300)}).execute(&mut conn);
+    redis!(PEXPIRE foo {Duration::from_micros(1500)}).execute(&mut conn);
+    redis!(PEXPIRE foo {Duration::from_millis(5)}).execute(&mut conn);
+}
+
+#[test]
+fn test_ttl_optional_and_conditional() {
+    let ttl = Some(Duration::from_secs(60));
+    let expire = true;
+    let mut conn = MockRedisConnection::new(vec![
+        MockCmd::new(
+            redis::cmd("SET").arg("foo").arg(1).arg("EX").arg(60),
+            Ok(""),
+        ),
+        MockCmd::new(
+            redis::cmd("SET").arg("foo").arg(1).arg("PX").arg(60000),
+            Ok(""),
+        ),
+    ]);
+
+    redis!(SET foo {1} EX {ttl?}).execute(&mut conn);
+    redis!(SET foo {1} ?[{expire} => PX {Duration::from_secs(60)}]).execute(&mut conn);
+}
+
+#[test]
+fn test_ttl_passthrough() {
+    let secs: u64 = 30;
+    let mut conn = MockRedisConnection::new(vec![
+        MockCmd::new(
+            redis::cmd("SET").arg("foo").arg(1).arg("EX").arg(30),
+            Ok(""),
+        ),
+        MockCmd::new(
+            redis::cmd("SET").arg("foo").arg(1).arg("EX").arg("30"),
+            Ok(""),
+        ),
+        MockCmd::new(redis::cmd("EXPIRE").arg("foo").arg(30), Ok("")),
+    ]);
+
+    redis!(SET foo {1} EX {secs}).execute(&mut conn);
+    redis!(SET foo {1} EX {"30"}).execute(&mut conn);
+    redis!(EXPIRE foo {&secs}).execute(&mut conn);
+}
@@ -0,0 +1,93 @@
+use redis_rs_macro::redis;
+use redis_test::{MockCmd, MockRedisConnection};
+use std::time::{Duration, SystemTime};
+
+#[test]
+fn test_timestamp() {
+    let at = SystemTime::UNIX_EPOCH + Duration::from_millis(1_700_000_000_500);
+    let mut conn = MockRedisConnection::new(vec![
+        MockCmd::new(
+            redis::cmd("EXPIREAT").arg("foo").arg(1_700_000_000u64),
+            Ok(""),
+        ),
+        MockCmd::new(
+            redis::cmd("PEXPIREAT").arg("foo").arg(1_700_000_000_500u64),
+            Ok(""),
+        ),
+        MockCmd::new(
+            redis::cmd("SET")
+                .arg("foo")
+                .arg(1)
+                .arg("EXAT")
+                .arg(1_700_000_000u64),
+            Ok(""),
+        ),
+        MockCmd::new(
+            redis::cmd("GETEX")
+                .arg("foo")
+                .arg("PXAT")
+                .arg(1_700_000_000_500u64),
+            Ok(""),
+        ),
+        MockCmd::new(redis::cmd("EXPIREAT").arg("foo").arg(0), Ok("")),
+    ]);
+
+    redis!(EXPIREAT foo {at}).execute(&mut conn);
+    redis!(PEXPIREAT foo {&at}).execute(&mut conn);
+    redis!(SET foo {1} EXAT {at}).execute(&mut conn);
+    redis!(GETEX foo PXAT {Some(at)?}).execute(&mut conn);
+    redis!(EXPIREAT foo {SystemTime::UNIX_EPOCH - Duration::from_secs(1)}).execute(&mut conn);
+}
+
+#[test]
+fn test_timestamp_passthrough() {
+    let mut conn = MockRedisConnection::new(vec![MockCmd::new(
+        redis::cmd("EXPIREAT").arg("foo").arg(1_700_000_000),
+        Ok(""),
+    )]);
+
+    redis!(EXPIREAT foo {1_700_000_000}).execute(&mut conn);
+}
+
+#[cfg(feature = "chrono")]
+#[test]
+fn test_timestamp_chrono() {
+    use chrono::{DateTime, FixedOffset};
+
+    let at = DateTime::parse_from_rfc3339("2023-11-14T22:13:20.5+00:00").unwrap();
+    let local: DateTime<FixedOffset> = at.with_timezone(&FixedOffset::east_opt(3600).unwrap());
+    let mut conn = MockRedisConnection::new(vec![
+        MockCmd::new(
+            redis::cmd("EXPIREAT").arg("foo").arg(1_700_000_000u64),
+            Ok(""),
+        ),
+        MockCmd::new(
+            redis::cmd("PEXPIREAT").arg("foo").arg(1_700_000_000_500u64),
+            Ok(""),
+        ),
+    ]);
+
+    redis!(EXPIREAT foo {at}).execute(&mut conn);
+    redis!(PEXPIREAT foo {local}).execute(&mut conn);
+}
+
+#[cfg(feature = "time")]
+#[test]
+fn test_timestamp_time() {
+    use time::OffsetDateTime;
+
+    let at = OffsetDateTime::from_unix_timestamp_nanos(1_700_000_000_500_000_000).unwrap();
+    let mut conn = MockRedisConnection::new(vec![
+        MockCmd::new(
+            redis::cmd("EXPIREAT").arg("foo").arg(1_700_000_000u64),
+            Ok(""),
+        ),
+        MockCmd::new(
+            redis::cmd("PEXPIREAT").arg("foo").arg(1_700_000_000_500u64),
+            Ok(""),
+        ),
+    ]);
+
+    redis!(EXPIREAT foo {at}).execute(&mut conn);
+    redis!(PEXPIREAT foo {at}).execute(&mut conn);
+}
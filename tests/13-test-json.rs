@@ -0,0 +1,56 @@
+#![cfg(feature = "json")]
+
+use redis_rs_macro::redis;
+use redis_test::{MockCmd, MockRedisConnection};
+use serde::Serialize;
+
+#[derive(Serialize)]
+struct User {
+    name: &'static str,
+    age: u32,
+}
+
+#[test]
+fn test_json() -> redis::RedisResult<()> {
+    let id = 1;
+    let user = User {
+        name: "alice",
+        age: 30,
+    };
+    let mut conn = MockRedisConnection::new(vec![
+        MockCmd::new(
+            redis::cmd("SET")
+                .arg("cache:user:1")
+                .arg(r#"{"name":"alice","age":30}"#),
+            Ok(""),
+        ),
+        MockCmd::new(
+            redis::cmd("HSET")
+                .arg("users")
+                .arg("1")
+                .arg(r#"{"name":"alice","age":30}"#),
+            Ok(""),
+        ),
+        MockCmd::new(redis::cmd("RPUSH").arg("list").arg("tags=[1,2]"), Ok("")),
+    ]);
+
+    redis!(SET cache:user:{id} {json user}).execute(&mut conn);
+    redis!("HSET users {id} {json &user}").execute(&mut conn);
+    redis!(RPUSH list tags={json &[1, 2]}).execute(&mut conn);
+    Ok(())
+}
+
+#[test]
+fn test_json_error() {
+    use std::collections::HashMap;
+
+    fn build(map: &HashMap<(u8, u8), u8>) -> redis::RedisResult<redis::Cmd> {
+        Ok(redis!(SET key {json map}))
+    }
+
+    let map = HashMap::from([((1, 2), 3)]);
+    let Err(err) = build(&map) else {
+        panic!("maps with non-string keys should not serialize");
+    };
+    assert_eq!(err.kind(), redis::ErrorKind::TypeError);
+}
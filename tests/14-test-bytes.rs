@@ -0,0 +1,46 @@
+use redis_rs_macro::redis;
+use redis_test::{MockCmd, MockRedisConnection};
+
+#[test]
+fn test_bytes() {
+    let payload: Vec<u8> = vec![0, 159, 146, 150];
+    let slice: &[u8] = &payload;
+    let mut conn = MockRedisConnection::new(vec![
+        MockCmd::new(
+            redis::cmd("RESTORE").arg("foo").arg(0).arg(&payload[..]),
+            Ok(""),
+        ),
+        MockCmd::new(redis::cmd("SET").arg("foo").arg(slice), Ok("")),
+        MockCmd::new(
+            redis::cmd("SET")
+                .arg("foo")
+                .arg(&[b'k', b':', 0, 159, 146, 150][..]),
+            Ok(""),
+        ),
+        MockCmd::new(redis::cmd("SET").arg("foo").arg("text"), Ok("")),
+    ]);
+
+    redis!(RESTORE foo 0 {bytes payload}).execute(&mut conn);
+    redis!(SET foo {bytes slice}).execute(&mut conn);
+    redis!(SET foo k:{bytes &payload}).execute(&mut conn);
+    redis!(SET foo {bytes "text"}).execute(&mut conn);
+}
+
+#[test]
+fn test_bytes_variable() {
+    // A variable named like the marker can be used in binary operations
+    let (bytes, csv, mask) = (8, 3, 6);
+    let mut conn = MockRedisConnection::new(vec![
+        MockCmd::new(redis::cmd("SET").arg("n").arg(7), Ok("")),
+        MockCmd::new(redis::cmd("SET").arg("n").arg(6), Ok("")),
+        MockCmd::new(redis::cmd("SET").arg("n").arg(0), Ok("")),
+        MockCmd::new(redis::cmd("SET").arg("n").arg(14), Ok("")),
+        MockCmd::new(redis::cmd("SET").arg("n").arg(6), Ok("")),
+    ]);
+
+    redis!(SET n {bytes - 1}).execute(&mut conn);
+    redis!(SET n {csv * 2}).execute(&mut conn);
+    redis!(SET n {bytes & mask}).execute(&mut conn);
+    redis!(SET n {bytes | mask}).execute(&mut conn);
+    redis!("SET n {bytes - 2}").execute(&mut conn);
+}
@@ -0,0 +1,15 @@
+use redis_rs_macro::redis;
+use redis_test::{MockCmd, MockRedisConnection};
+
+#[test]
+fn test_byte_string() {
+    let mut conn = MockRedisConnection::new(vec![
+        MockCmd::new(redis::cmd("SET").arg("foo").arg(&[0u8, 1, 2][..]), Ok("")),
+        MockCmd::new(redis::cmd("SET").arg("foo").arg(&b"k:\xff"[..]), Ok("")),
+        MockCmd::new(redis::cmd("SET").arg("foo").arg(&b""[..]), Ok("")),
+    ]);
+
+    redis!(SET foo b"\x00\x01\x02").execute(&mut conn);
+    redis!(SET foo k:b"\xff").execute(&mut conn);
+    redis!(SET foo b"").execute(&mut conn);
+}
@@ -0,0 +1,34 @@
+#![cfg(feature = "msgpack")]
+
+use redis_rs_macro::redis;
+use redis_test::{MockCmd, MockRedisConnection};
+use serde::Serialize;
+
+#[derive(Serialize)]
+struct User {
+    name: &'static str,
+    age: u32,
+}
+
+#[test]
+fn test_msgpack() -> redis::RedisResult<()> {
+    let user = User {
+        name: "alice",
+        age: 30,
+    };
+    // A two element array holding a fixstr and a positive fixint
+    let encoded: &[u8] = &[0x92, 0xa5, b'a', b'l', b'i', b'c', b'e', 30];
+    let mut conn = MockRedisConnection::new(vec![
+        MockCmd::new(redis::cmd("SET").arg("user:1").arg(encoded), Ok("")),
+        MockCmd::new(
+            redis::cmd("HSET")
+                .arg("users")
+                .arg([b"1:", encoded].concat()),
+            Ok(""),
+        ),
+    ]);
+
+    redis!(SET user:1 {msgpack user}).execute(&mut conn);
+    redis!(HSET users 1:{msgpack &user}).execute(&mut conn);
+    Ok(())
+}
@@ -0,0 +1,94 @@
+use redis::{RedisWrite, ToRedisArgs};
+use redis_rs_macro::{redis, redis_template, ToRedisFields};
+use redis_test::{MockCmd, MockRedisConnection};
+use std::collections::BTreeMap;
+
+struct Event {
+    kind: &'static str,
+    user: u64,
+}
+
+impl ToRedisFields for Event {
+    fn write_redis_fields<W: ?Sized + RedisWrite>(&self, out: &mut W) {
+        out.write_arg(b"kind");
+        self.kind.write_redis_args(out);
+        out.write_arg(b"user");
+        self.user.write_redis_args(out);
+    }
+}
+
+#[test]
+fn test_fields() {
+    let event = Event {
+        kind: "login",
+        user: 42,
+    };
+    let map = BTreeMap::from([("a", 1), ("b", 2)]);
+    let mut conn = MockRedisConnection::new(vec![
+        MockCmd::new(
+            redis::cmd("XADD")
+                .arg("events")
+                .arg("*")
+                .arg("kind")
+                .arg("login")
+                .arg("user")
+                .arg(42),
+            Ok(""),
+        ),
+        MockCmd::new(
+            redis::cmd("HSET")
+                .arg("user:1")
+                .arg("kind")
+                .arg("login")
+                .arg("user")
+                .arg(42),
+            Ok(""),
+        ),
+        MockCmd::new(
+            redis::cmd("HSET")
+                .arg("map")
+                .arg("a")
+                .arg(1)
+                .arg("b")
+                .arg(2),
+            Ok(""),
+        ),
+    ]);
+
+    redis!(XADD events * {*event}).execute(&mut conn);
+    redis!(HSET user:1 {*&event}).execute(&mut conn);
+    redis!(HSET map {*map}).execute(&mut conn);
+}
+
+#[test]
+fn test_fields_deref() {
+    let count = &5;
+    let mut conn = MockRedisConnection::new(vec![MockCmd::new(
+        redis::cmd("SET").arg("foo").arg(5),
+        Ok(""),
+    )]);
+
+    redis!(SET foo {(*count)}).execute(&mut conn);
+}
+
+#[test]
+fn test_fields_template() {
+    redis_template!(AddEvent = XADD events * {*event});
+
+    let event = Event {
+        kind: "login",
+        user: 42,
+    };
+    let mut conn = MockRedisConnection::new(vec![MockCmd::new(
+        redis::cmd("XADD")
+            .arg("events")
+            .arg("*")
+            .arg("kind")
+            .arg("login")
+            .arg("user")
+            .arg(42),
+        Ok(""),
+    )]);
+
+    AddEvent::bind(&event).execute(&mut conn);
+}
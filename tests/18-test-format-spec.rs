@@ -0,0 +1,33 @@
+use redis_rs_macro::redis;
+use redis_test::{MockCmd, MockRedisConnection};
+
+#[test]
+fn test_format_spec() {
+    let price = 12.5;
+    let id = 42;
+    let mut conn = MockRedisConnection::new(vec![
+        MockCmd::new(
+            redis::cmd("ZADD").arg("prices").arg("12.50").arg("item"),
+            Ok(""),
+        ),
+        MockCmd::new(redis::cmd("GET").arg("order:00000042"), Ok("")),
+        MockCmd::new(redis::cmd("SET").arg("hex").arg("0x2a"), Ok("")),
+    ]);
+
+    redis!(ZADD prices {price:.2} item).execute(&mut conn);
+    redis!(GET order:{id:08}).execute(&mut conn);
+    redis!("SET hex {id:#x}").execute(&mut conn);
+}
+
+#[test]
+fn test_format_spec_joined() {
+    let id = 42;
+    let mut conn = MockRedisConnection::new(vec![
+        MockCmd::new(redis::cmd("GET").arg("42:x"), Ok("")),
+        MockCmd::new(redis::cmd("GET").arg("id:2a:x"), Ok("")),
+    ]);
+
+    // Text after the closing brace is never read as a format spec
+    redis!("GET {id}:x").execute(&mut conn);
+    redis!("GET id:{id:x}:x").execute(&mut conn);
+}
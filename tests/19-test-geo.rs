@@ -0,0 +1,31 @@
+use redis_rs_macro::redis;
+use redis_test::{MockCmd, MockRedisConnection};
+
+#[test]
+fn test_geo_coordinates() {
+    let palermo = (13.361389, 38.115556);
+    let mut conn = MockRedisConnection::new(vec![
+        MockCmd::new(
+            redis::cmd("GEOADD")
+                .arg("places")
+                .arg("13.361389")
+                .arg("38.115556")
+                .arg("Palermo"),
+            Ok(""),
+        ),
+        MockCmd::new(
+            redis::cmd("GEOADD")
+                .arg("places")
+                .arg(13.361389)
+                .arg(38.115556)
+                .arg("Palermo")
+                .arg(-0.1275)
+                .arg(51.507222)
+                .arg("London"),
+            Ok(""),
+        ),
+    ]);
+
+    redis!(GEOADD places 13.361389 38.115556 Palermo).execute(&mut conn);
+    redis!(GEOADD places {palermo} Palermo {(-0.1275, 51.507222)} London).execute(&mut conn);
+}
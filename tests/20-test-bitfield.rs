@@ -0,0 +1,35 @@
+use redis_rs_macro::redis;
+use redis_test::{MockCmd, MockRedisConnection};
+
+#[test]
+fn test_bitfield() {
+    let offset = 8;
+    let mut conn = MockRedisConnection::new(vec![
+        MockCmd::new(
+            redis::cmd("BITFIELD")
+                .arg("key")
+                .arg("SET")
+                .arg("i16")
+                .arg("#1")
+                .arg("-100")
+                .arg("GET")
+                .arg("u4")
+                .arg("0"),
+            Ok(""),
+        ),
+        MockCmd::new(
+            redis::cmd("BITFIELD")
+                .arg("key")
+                .arg("OVERFLOW")
+                .arg("SAT")
+                .arg("INCRBY")
+                .arg("u2")
+                .arg(8)
+                .arg("1"),
+            Ok(""),
+        ),
+    ]);
+
+    redis!(BITFIELD key SET i16 #1 -100 GET u4 0).execute(&mut conn);
+    redis!(BITFIELD key OVERFLOW SAT INCRBY u2 {offset} 1).execute(&mut conn);
+}
@@ -0,0 +1,36 @@
+use redis_rs_macro::redis;
+use redis_test::{MockCmd, MockRedisConnection};
+
+#[test]
+fn test_scores() {
+    let board = vec![(1.5, "alice"), (2.0, "bob")];
+    let ranks = [(1, "carol".to_string())];
+    let mut conn = MockRedisConnection::new(vec![
+        MockCmd::new(
+            redis::cmd("ZADD")
+                .arg("board")
+                .arg(1.5)
+                .arg("alice")
+                .arg(2.0)
+                .arg("bob"),
+            Ok(""),
+        ),
+        MockCmd::new(
+            redis::cmd("ZADD")
+                .arg("board")
+                .arg("NX")
+                .arg(1)
+                .arg("carol"),
+            Ok(""),
+        ),
+        MockCmd::new(
+            redis::cmd("ZADD").arg("board").arg(3.5).arg("alice"),
+            Ok(""),
+        ),
+    ]);
+
+    redis!(ZADD board {..scores &board}).execute(&mut conn);
+    redis!(ZADD board NX {..scores &ranks}).execute(&mut conn);
+    redis!(ZADD board {..scores board.iter().map(|&(score, member)| (score + 2.0, member)).take(1)})
+        .execute(&mut conn);
+}
@@ -0,0 +1,26 @@
+use redis_rs_macro::redis;
+use redis_test::{MockCmd, MockRedisConnection};
+
+#[test]
+fn test_csv() {
+    let libs = vec!["redis-rs", "tokio"];
+    let ids = [1, 2, 3];
+    let empty: Vec<String> = vec![];
+    let mut conn = MockRedisConnection::new(vec![
+        MockCmd::new(
+            redis::cmd("CLIENT")
+                .arg("SETINFO")
+                .arg("LIB-NAME")
+                .arg("redis-rs,tokio"),
+            Ok(""),
+        ),
+        MockCmd::new(redis::cmd("SET").arg("ids").arg("ids=1,2,3"), Ok("")),
+        MockCmd::new(redis::cmd("SET").arg("none").arg(""), Ok("")),
+        MockCmd::new(redis::cmd("SET").arg("tags").arg("a,b"), Ok("")),
+    ]);
+
+    redis!(CLIENT SETINFO LIB-NAME {csv &libs}).execute(&mut conn);
+    redis!(SET ids ids={csv ids}).execute(&mut conn);
+    redis!(SET none {csv empty}).execute(&mut conn);
+    redis!(SET tags {csv ["a", "b"]}).execute(&mut conn);
+}
@@ -0,0 +1,14 @@
+use redis_rs_macro::redis;
+use redis_test::{MockCmd, MockRedisConnection};
+
+#[test]
+fn test_env() {
+    let id = 1;
+    let mut conn = MockRedisConnection::new(vec![
+        MockCmd::new(redis::cmd("GET").arg("redis-rs-macro"), Ok("")),
+        MockCmd::new(redis::cmd("GET").arg("redis-rs-macro:user:1"), Ok("")),
+    ]);
+
+    redis!(GET ${CARGO_PKG_NAME}).execute(&mut conn);
+    redis!(GET ${CARGO_PKG_NAME}:user:{id}).execute(&mut conn);
+}
@@ -0,0 +1,91 @@
+#![cfg(feature = "key-prefix")]
+
+use redis_rs_macro::redis;
+use redis_test::{MockCmd, MockRedisConnection};
+
+fn init() {
+    // Tests share the prefix, which can only be set once
+    let _ = redis_rs_macro::set_key_prefix("app:");
+}
+
+#[test]
+fn test_key_prefix() {
+    init();
+    let id = 1;
+    let ttl: Option<u64> = Some(30);
+    let mut conn = MockRedisConnection::new(vec![
+        MockCmd::new(redis::cmd("GET").arg("app:user:1"), Ok("")),
+        MockCmd::new(
+            redis::cmd("SET")
+                .arg("app:user:1")
+                .arg("v")
+                .arg("EX")
+                .arg(30),
+            Ok(""),
+        ),
+        MockCmd::new(
+            redis::cmd("BITOP").arg("AND").arg("app:d").arg("app:a"),
+            Ok(""),
+        ),
+        MockCmd::new(
+            redis::cmd("EVAL")
+                .arg("return 1")
+                .arg(1)
+                .arg("app:k")
+                .arg("a"),
+            Ok(""),
+        ),
+        MockCmd::new(
+            redis::cmd("XREAD")
+                .arg("STREAMS")
+                .arg("app:s")
+                .arg("app:t")
+                .arg(0)
+                .arg(0),
+            Ok(""),
+        ),
+        MockCmd::new(redis::cmd("PUBLISH").arg("news").arg("hi"), Ok("")),
+    ]);
+
+    redis!(GET user:{id}).execute(&mut conn);
+    redis!(SET user:{id} v EX {ttl?}).execute(&mut conn);
+    redis!(BITOP AND d a).execute(&mut conn);
+    redis!(EVAL "return 1" 1 k a).execute(&mut conn);
+    redis!(XREAD STREAMS s t 0 0).execute(&mut conn);
+    redis!(PUBLISH news hi).execute(&mut conn);
+}
+
+#[test]
+fn test_key_prefix_spreads() {
+    init();
+    let keys = vec!["b", "c"];
+    let extra: Option<&str> = Some("d");
+    let pairs = vec![("x", 1), ("y", 2)];
+    let mut conn = MockRedisConnection::new(vec![
+        MockCmd::new(
+            redis::cmd("DEL")
+                .arg("app:a")
+                .arg("app:b")
+                .arg("app:c")
+                .arg("app:d"),
+            Ok(""),
+        ),
+        MockCmd::new(redis::cmd("BLPOP").arg("app:b").arg("app:c").arg(0), Ok("")),
+        MockCmd::new(
+            redis::cmd("MSET")
+                .arg("app:x")
+                .arg(1)
+                .arg("app:y")
+                .arg(2)
+                .arg("app:x")
+                .arg(1)
+                .arg("app:y")
+                .arg(2),
+            Ok(""),
+        ),
+    ]);
+
+    redis!(DEL a {..&keys} {extra?}).execute(&mut conn);
+    redis!(BLPOP {..&keys} 0).execute(&mut conn);
+    redis!(MSET {..&pairs} {..pairs}).execute(&mut conn);
+}
@@ -0,0 +1,18 @@
+use redis_rs_macro::{redis, redis_key, Key};
+use redis_test::{MockCmd, MockRedisConnection};
+
+#[test]
+fn test_key() {
+    let id = 42;
+    let key: Key = redis_key!("user:{}:orders", id);
+    assert_eq!(key.as_str(), "user:42:orders");
+    assert_eq!(redis_key!("user:{id}:name").to_string(), "user:42:name");
+    assert_eq!(String::from(redis_key!("config")), "config");
+
+    let mut conn = MockRedisConnection::new(vec![
+        MockCmd::new(redis::cmd("LPUSH").arg("user:42:orders").arg(1001), Ok("")),
+        MockCmd::new(redis::cmd("GET").arg("user:42:orders:count"), Ok("")),
+    ]);
+    redis!(LPUSH {&key} 1001).execute(&mut conn);
+    redis!(GET {key}:count).execute(&mut conn);
+}
@@ -0,0 +1,66 @@
+use redis_rs_macro::{redis, redis_args};
+use redis_test::{MockCmd, MockRedisConnection};
+use std::time::Duration;
+
+#[test]
+fn test_args() {
+    let ttl: Option<u64> = Some(30);
+    let no_ttl: Option<u64> = None;
+    let fields = ["a", "b"];
+    let mut conn = MockRedisConnection::new(vec![
+        MockCmd::new(
+            redis::cmd("SET")
+                .arg("key")
+                .arg("value")
+                .arg("EX")
+                .arg(30)
+                .arg("NX"),
+            Ok(""),
+        ),
+        MockCmd::new(redis::cmd("SET").arg("key").arg("value").arg("NX"), Ok("")),
+        MockCmd::new(redis::cmd("HMGET").arg("user:1").arg("a").arg("b"), Ok("")),
+        MockCmd::new(
+            redis::cmd("SET")
+                .arg("key")
+                .arg("value")
+                .arg("PX")
+                .arg(1500),
+            Ok(""),
+        ),
+    ]);
+
+    redis::cmd("SET")
+        .arg("key")
+        .arg("value")
+        .arg(redis_args!(EX {ttl?} NX))
+        .execute(&mut conn);
+    redis::cmd("SET")
+        .arg("key")
+        .arg("value")
+        .arg(redis_args!(EX {no_ttl?} NX))
+        .execute(&mut conn);
+    redis!(HMGET user:1 {redis_args!({..fields})}).execute(&mut conn);
+    let options = redis_args!(PX {Duration::from_millis(1500)});
+    redis::cmd("SET")
+        .arg("key")
+        .arg("value")
+        .arg(options)
+        .execute(&mut conn);
+}
+
+#[test]
+fn test_args_pipeline() {
+    let args = redis_args!(user:1 name);
+    assert_eq!(args.as_slice(), [b"user:1".to_vec(), b"name".to_vec()]);
+    let mut pipe = redis::pipe();
+    pipe.cmd("HGET").arg(&args);
+    assert_eq!(
+        pipe.get_packed_pipeline(),
+        redis::pipe()
+            .cmd("HGET")
+            .arg("user:1")
+            .arg("name")
+            .get_packed_pipeline()
+    );
+    assert!(redis_args!().as_slice().is_empty());
+}
@@ -0,0 +1,247 @@
+use redis_rs_macro::{redis_pipe, Route};
+use redis_test::{MockCmd, MockRedisConnection};
+
+#[test]
+fn test_pipe() {
+    let id = 42;
+    let keys = ["a", "b"];
+    let mut conn = MockRedisConnection::new(vec![MockCmd::with_values(
+        redis::pipe()
+            .cmd("SET")
+            .arg("user:42:visits")
+            .arg(0)
+            .ignore()
+            .cmd("INCR")
+            .arg("user:42:visits")
+            .cmd("DEL")
+            .arg("a")
+            .arg("b")
+            .cmd("GET")
+            .arg("user:42:name"),
+        Ok(vec!["OK", "1", "2", "alice"]),
+    )]);
+
+    let (visits, deleted, name): (i64, i64, String) = redis_pipe!(
+        _ = SET user:{id}:visits 0  # reset the counter
+        INCR user:{id}:visits
+        DEL {..keys}; GET user:{id}:name
+    )
+    .query(&mut conn)
+    .unwrap();
+    assert_eq!((visits, deleted, name.as_str()), (1, 2, "alice"));
+}
+
+#[test]
+fn test_pipe_single_line() {
+    let mut conn = MockRedisConnection::new(vec![MockCmd::with_values(
+        redis::pipe()
+            .cmd("SET")
+            .arg("a")
+            .arg(1)
+            .cmd("INCR")
+            .arg("b"),
+        Ok(vec!["OK", "1"]),
+    )]);
+
+    redis_pipe!(SET a 1; "INCR b";).execute(&mut conn);
+}
+
+#[test]
+fn test_pipe_atomic() {
+    let mut conn = MockRedisConnection::new(vec![MockCmd::with_values(
+        redis::pipe()
+            .atomic()
+            .cmd("SET")
+            .arg("a")
+            .arg(1)
+            .ignore()
+            .cmd("INCR")
+            .arg("b"),
+        Ok(vec![
+            redis::Value::Okay,
+            redis::Value::Status("QUEUED".to_string()),
+            redis::Value::Status("QUEUED".to_string()),
+            redis::Value::Bulk(vec![redis::Value::Okay, redis::Value::Int(1)]),
+        ]),
+    )]);
+
+    let (count,): (i64,) = redis_pipe!(atomic; _ = SET a 1; INCR b)
+        .query(&mut conn)
+        .unwrap();
+    assert_eq!(count, 1);
+}
+
+#[test]
+fn test_pipe_labeled() {
+    let id = 42;
+    let mut conn = MockRedisConnection::new(vec![MockCmd::with_values(
+        redis::pipe()
+            .cmd("INCR")
+            .arg("visits")
+            .cmd("SET")
+            .arg("seen")
+            .arg(1)
+            .ignore()
+            .cmd("GET")
+            .arg("user:42:name"),
+        Ok(vec!["3", "OK", "alice"]),
+    )]);
+
+    let results = redis_pipe!(
+        count: INCR visits
+        _ = SET seen 1
+        name: GET user:{id}:name
+    )
+    .query(&mut conn)
+    .unwrap();
+    let count: i64 = results.count;
+    let name: String = results.name;
+    assert_eq!((count, name.as_str()), (3, "alice"));
+}
+
+#[test]
+fn test_pipe_loop() {
+    let ids = [1, 2];
+    let pairs = vec![("a", 1), ("b", 2)];
+    let mut conn = MockRedisConnection::new(vec![
+        MockCmd::with_values(
+            redis::pipe()
+                .cmd("GET")
+                .arg("user:1:name")
+                .cmd("GET")
+                .arg("user:2:name"),
+            Ok(vec!["alice", "bob"]),
+        ),
+        MockCmd::with_values(
+            redis::pipe()
+                .cmd("INCR")
+                .arg("writes")
+                .cmd("SET")
+                .arg("a")
+                .arg(1)
+                .ignore()
+                .cmd("EXPIRE")
+                .arg("a")
+                .arg(60)
+                .ignore()
+                .cmd("SET")
+                .arg("b")
+                .arg(2)
+                .ignore()
+                .cmd("EXPIRE")
+                .arg("b")
+                .arg(60)
+                .ignore(),
+            Ok(vec!["1", "OK", "1", "OK", "1"]),
+        ),
+    ]);
+
+    let names: Vec<String> = redis_pipe!(for id in &ids { GET user:{id}:name })
+        .query(&mut conn)
+        .unwrap();
+    assert_eq!(names, ["alice", "bob"]);
+    let results = redis_pipe!(
+        writes: INCR writes
+        for (k, v) in &pairs {
+            _ = SET {k} {v}
+            _ = EXPIRE {k} 60
+        }
+    )
+    .query(&mut conn)
+    .unwrap();
+    let writes: i64 = results.writes;
+    assert_eq!(writes, 1);
+}
+
+#[test]
+fn test_pipe_conditions() {
+    let mut conn = MockRedisConnection::new(vec![
+        MockCmd::with_values(
+            redis::pipe()
+                .cmd("INCR")
+                .arg("visits")
+                .cmd("SET")
+                .arg("flag")
+                .arg(1)
+                .ignore(),
+            Ok(vec!["1", "OK"]),
+        ),
+        MockCmd::with_values(
+            redis::pipe().cmd("INCR").arg("visits").cmd("GET").arg("b"),
+            Ok(vec!["2", "bee"]),
+        ),
+    ]);
+
+    for fresh in [true, false] {
+        let results = redis_pipe!(
+            count: INCR visits
+            if fresh {
+                _ = SET flag 1
+            } else if !fresh {
+                name: GET b
+            }
+        )
+        .query(&mut conn)
+        .unwrap();
+        let count: i64 = results.count;
+        let name: Option<String> = results.name;
+        match fresh {
+            true => assert_eq!((count, name), (1, None)),
+            false => assert_eq!((count, name.as_deref()), (2, Some("bee"))),
+        }
+    }
+}
+
+#[test]
+fn test_pipe_cluster() {
+    let ids = [1, 2];
+    let pipe = redis_pipe!(
+        cluster
+        _ = SET "{a}:count" 0
+        for id in &ids { GET "{b}:"{id} }
+        INCR "{a}:count"
+        PING
+    );
+    let routes: Vec<_> = pipe.groups().map(|(route, _)| route).collect();
+    assert_eq!(routes.len(), 3);
+    assert_eq!(routes[2], Route::Random);
+    // The key prefix is part of the keys that are sent
+    if cfg!(feature = "key-prefix") {
+        return;
+    }
+    assert_eq!(routes[0], Route::for_key(b"a"));
+    assert_eq!(routes[1], Route::for_key(b"b"));
+    let mut nodes = [
+        MockRedisConnection::new(vec![MockCmd::with_values(
+            redis::pipe()
+                .cmd("SET")
+                .arg("{a}:count")
+                .arg(0)
+                .ignore()
+                .cmd("INCR")
+                .arg("{a}:count"),
+            Ok(vec!["OK", "1"]),
+        )]),
+        MockRedisConnection::new(vec![MockCmd::with_values(
+            redis::pipe()
+                .cmd("GET")
+                .arg("{b}:1")
+                .cmd("GET")
+                .arg("{b}:2"),
+            Ok(vec!["x", "y"]),
+        )]),
+        MockRedisConnection::new(vec![MockCmd::with_values(
+            redis::pipe().cmd("PING"),
+            Ok(vec!["PONG"]),
+        )]),
+    ];
+    let (first, second, count, pong): (String, String, i64, String) = pipe
+        .query(|route, pipe| {
+            let node = routes.iter().position(|r| *r == route).unwrap();
+            pipe.query(&mut nodes[node])
+        })
+        .unwrap();
+    assert_eq!((first.as_str(), second.as_str()), ("x", "y"));
+    assert_eq!(count, 1);
+    assert_eq!(pong, "PONG");
+}
@@ -0,0 +1,86 @@
+use redis::Value;
+use redis_rs_macro::redis_transaction;
+use redis_test::{MockCmd, MockRedisConnection};
+
+fn queued() -> Value {
+    Value::Status("QUEUED".to_string())
+}
+
+#[test]
+fn test_transaction() {
+    let (id, amount) = (42, 10);
+    let exec = redis::pipe()
+        .atomic()
+        .cmd("INCRBY")
+        .arg("balance:42")
+        .arg(10)
+        .cmd("LPUSH")
+        .arg("history:42")
+        .arg(10)
+        .ignore()
+        .clone();
+    let mut conn = MockRedisConnection::new(vec![
+        MockCmd::new(redis::cmd("WATCH").arg("balance:42"), Ok("OK")),
+        // EXEC fails when a watched key changed, which starts the transaction over
+        MockCmd::with_values(
+            exec.clone(),
+            Ok(vec![Value::Okay, queued(), queued(), Value::Nil]),
+        ),
+        MockCmd::new(redis::cmd("WATCH").arg("balance:42"), Ok("OK")),
+        MockCmd::with_values(
+            exec,
+            Ok(vec![
+                Value::Okay,
+                queued(),
+                queued(),
+                Value::Bulk(vec![Value::Int(110), Value::Int(1)]),
+            ]),
+        ),
+        MockCmd::new(redis::cmd("UNWATCH"), Ok("OK")),
+    ]);
+
+    let (balance,): (i64,) = redis_transaction!(&mut conn, [balance:{id}], {
+        INCRBY balance:{id} {amount}
+        _ = LPUSH history:{id} {amount}
+    })
+    .unwrap();
+    assert_eq!(balance, 110);
+}
+
+#[test]
+fn test_transaction_read() {
+    use redis_rs_macro::redis;
+
+    let (id, amount) = (42, 10);
+    let exec = |balance: i64| {
+        redis::pipe()
+            .atomic()
+            .cmd("SET")
+            .arg("balance:42")
+            .arg(balance)
+            .clone()
+    };
+    let mut conn = MockRedisConnection::new(vec![
+        MockCmd::new(redis::cmd("WATCH").arg("balance:42"), Ok("OK")),
+        MockCmd::new(redis::cmd("GET").arg("balance:42"), Ok("100")),
+        // Another client changed the balance after it was read, so it is read again
+        MockCmd::with_values(exec(90), Ok(vec![Value::Okay, queued(), Value::Nil])),
+        MockCmd::new(redis::cmd("WATCH").arg("balance:42"), Ok("OK")),
+        MockCmd::new(redis::cmd("GET").arg("balance:42"), Ok("50")),
+        MockCmd::with_values(
+            exec(40),
+            Ok(vec![Value::Okay, queued(), Value::Bulk(vec![Value::Okay])]),
+        ),
+        MockCmd::new(redis::cmd("UNWATCH"), Ok("OK")),
+    ]);
+
+    let mut reads = 0;
+    let (set,): (String,) = redis_transaction!(&mut conn, [balance:{id}], |con| {
+        reads += 1;
+        let balance: i64 = redis!(GET balance:{id}).query(con)?;
+    }, {
+        SET balance:{id} {balance - amount}
+    })
+    .unwrap();
+    assert_eq!((set.as_str(), reads), ("OK", 2));
+}
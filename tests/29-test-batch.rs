@@ -0,0 +1,26 @@
+use redis_rs_macro::redis_batch;
+use redis_test::{MockCmd, MockRedisConnection};
+
+#[test]
+fn test_batch() {
+    let pairs = [("a", 1), ("b", 2)];
+    let expire = true;
+    let cmds = redis_batch!(
+        for (k, v) in &pairs {
+            SET {k} {v} EX 60
+        }
+        if expire { PERSIST c }
+        INCR writes
+    );
+    assert_eq!(cmds.len(), 4);
+
+    let mut conn = MockRedisConnection::new(vec![
+        MockCmd::new(redis::cmd("SET").arg("a").arg(1).arg("EX").arg(60), Ok("")),
+        MockCmd::new(redis::cmd("SET").arg("b").arg(2).arg("EX").arg(60), Ok("")),
+        MockCmd::new(redis::cmd("PERSIST").arg("c"), Ok("")),
+        MockCmd::new(redis::cmd("INCR").arg("writes"), Ok("")),
+    ]);
+    for cmd in &cmds {
+        cmd.execute(&mut conn);
+    }
+}
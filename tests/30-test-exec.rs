@@ -0,0 +1,40 @@
+use redis_rs_macro::redis_exec;
+use redis_test::{MockCmd, MockRedisConnection};
+
+mod common;
+
+#[test]
+fn test_exec() {
+    let id = 42;
+    let mut conn = MockRedisConnection::new(vec![
+        MockCmd::new(redis::cmd("INCR").arg("user:42:visits"), Ok("3")),
+        MockCmd::new(redis::cmd("GET").arg("user:42:name"), Ok("alice")),
+    ]);
+
+    let visits: i64 = redis_exec!(&mut conn, INCR user:{id}:visits).unwrap();
+    assert_eq!(visits, 3);
+    let con = &mut conn;
+    let name: Option<String> = redis_exec!(con, GET user:{id}:name).unwrap();
+    assert_eq!(name.as_deref(), Some("alice"));
+}
+
+#[cfg(feature = "async")]
+#[test]
+fn test_async() {
+    use common::AsyncMock;
+    use futures::executor::block_on;
+    use redis_rs_macro::redis_async;
+
+    let id = 42;
+    let mut conn = AsyncMock::new(vec![
+        MockCmd::new(redis::cmd("INCR").arg("user:42:visits"), Ok("3")),
+        MockCmd::new(redis::cmd("GET").arg("user:42:name"), Ok("alice")),
+    ]);
+
+    let visits: i64 = block_on(redis_async!(&mut conn, INCR user:{id}:visits)).unwrap();
+    assert_eq!(visits, 3);
+    // The connection expression can use any name, even the ones of the expansion
+    let cmd = &mut conn;
+    let future = redis_async!(cmd, GET user:{id}:name -> Option<String>);
+    assert_eq!(block_on(future).unwrap().as_deref(), Some("alice"));
+}
@@ -0,0 +1,25 @@
+use redis_rs_macro::{redis, redis_exec};
+use redis_test::{MockCmd, MockRedisConnection};
+
+#[test]
+fn test_typed() {
+    let id = 42;
+    let mut conn = MockRedisConnection::new(vec![
+        MockCmd::new(redis::cmd("GET").arg("user:42:name"), Ok("alice")),
+        MockCmd::new(redis::cmd("INCR").arg("user:42:visits"), Ok("3")),
+        MockCmd::new(redis::cmd("GET").arg("missing"), Ok(redis::Value::Nil)),
+    ]);
+
+    let cmd = redis!(GET user:{id}:name -> Option<String>);
+    assert_eq!(
+        cmd.cmd().get_packed_command(),
+        redis::cmd("GET").arg("user:42:name").get_packed_command()
+    );
+    assert_eq!(cmd.query(&mut conn).unwrap().as_deref(), Some("alice"));
+    let visits = redis_exec!(&mut conn, INCR user:{id}:visits -> i64).unwrap();
+    assert_eq!(visits, 3);
+    let missing = redis!("GET :key" -> Option<String>; key = "missing")
+        .query(&mut conn)
+        .unwrap();
+    assert_eq!(missing, None);
+}
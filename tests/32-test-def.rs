@@ -0,0 +1,40 @@
+use redis_rs_macro::redis_def;
+use redis_test::{MockCmd, MockRedisConnection};
+
+redis_def! {
+    /// Read a user
+    fn get_user(id: u64) => GET user:{id} -> Option<String>;
+    fn set_user(id: u64, data: &str) => SET user:{id} {data} EX 3600;
+    pub(crate) fn visit(id: u64) => "INCR :key"; key = format!("visits:{}", id);
+    fn ping() => PING
+}
+
+#[test]
+fn test_def() {
+    assert_eq!(
+        set_user(42, "alice").get_packed_command(),
+        redis::cmd("SET")
+            .arg("user:42")
+            .arg("alice")
+            .arg("EX")
+            .arg("3600")
+            .get_packed_command()
+    );
+    assert_eq!(
+        visit(42).get_packed_command(),
+        redis::cmd("INCR").arg("visits:42").get_packed_command()
+    );
+    assert_eq!(
+        ping().get_packed_command(),
+        redis::cmd("PING").get_packed_command()
+    );
+
+    let mut conn = MockRedisConnection::new(vec![MockCmd::new(
+        redis::cmd("GET").arg("user:42"),
+        Ok("alice"),
+    )]);
+    assert_eq!(
+        get_user(42).query(&mut conn).unwrap().as_deref(),
+        Some("alice")
+    );
+}
@@ -0,0 +1,71 @@
+use redis::RedisResult;
+use redis_rs_macro::redis_commands;
+use redis_test::{MockCmd, MockRedisConnection};
+
+mod common;
+
+#[redis_commands]
+trait Users {
+    #[redis(GET user:{id})]
+    fn get_user(&mut self, id: u64) -> RedisResult<Option<String>>;
+
+    /// Store a user for an hour
+    #[redis(SET user:{id} {data} EX 3600)]
+    fn set_user(&mut self, id: u64, data: &str) -> RedisResult<()>;
+
+    fn rename_user(&mut self, id: u64, name: &str) -> RedisResult<Option<String>> {
+        self.set_user(id, name)?;
+        self.get_user(id)
+    }
+}
+
+#[test]
+fn test_commands() {
+    let mut conn = MockRedisConnection::new(vec![
+        MockCmd::new(
+            redis::cmd("SET")
+                .arg("user:42")
+                .arg("alice")
+                .arg("EX")
+                .arg("3600"),
+            Ok("OK"),
+        ),
+        MockCmd::new(redis::cmd("GET").arg("user:42"), Ok("alice")),
+        MockCmd::new(redis::cmd("GET").arg("user:7"), Ok(redis::Value::Nil)),
+    ]);
+
+    let user = conn.rename_user(42, "alice").unwrap();
+    assert_eq!(user.as_deref(), Some("alice"));
+    assert_eq!(conn.get_user(7).unwrap(), None);
+}
+
+#[cfg(feature = "async")]
+#[redis_commands(async)]
+trait Sessions {
+    #[redis(GET session:{id})]
+    fn get_session(&mut self, id: &str) -> RedisResult<Option<String>>;
+
+    #[redis(DEL session:{id})]
+    fn end_session(&mut self, id: &str) -> RedisResult<bool>;
+}
+
+#[cfg(feature = "async")]
+#[test]
+fn test_commands_async() {
+    use common::AsyncMock;
+    use futures::executor::block_on;
+
+    let mut conn = AsyncMock::new(vec![
+        MockCmd::new(redis::cmd("GET").arg("session:s1"), Ok("alice")),
+        MockCmd::new(redis::cmd("DEL").arg("session:s1"), Ok(1)),
+        MockCmd::new(redis::cmd("GET").arg("session:s1"), Ok(redis::Value::Nil)),
+        MockCmd::new(redis::cmd("DEL").arg("session:s1"), Ok(0)),
+    ]);
+
+    let user = block_on(SessionsAsync::get_session(&mut conn, "s1")).unwrap();
+    assert_eq!(user.as_deref(), Some("alice"));
+    assert!(block_on(SessionsAsync::end_session(&mut conn, "s1")).unwrap());
+    // The blocking trait is generated as well
+    assert_eq!(Sessions::get_session(&mut conn.0, "s1").unwrap(), None);
+    assert!(!Sessions::end_session(&mut conn.0, "s1").unwrap());
+}
@@ -0,0 +1,102 @@
+use redis::{RedisResult, Value};
+use redis_rs_macro::redis_scan;
+use redis_test::{MockCmd, MockRedisConnection};
+
+mod common;
+
+fn batch(cursor: &str, items: &[&str]) -> Value {
+    let items = items
+        .iter()
+        .map(|item| Value::Data(item.as_bytes().to_vec()));
+    Value::Bulk(vec![
+        Value::Data(cursor.as_bytes().to_vec()),
+        Value::Bulk(items.collect()),
+    ])
+}
+
+#[test]
+fn test_scan() {
+    let id = 42;
+    let scan = |cursor: u64| {
+        redis::cmd("SCAN")
+            .arg(cursor)
+            .arg("MATCH")
+            .arg("user:42:*")
+            .clone()
+    };
+    let mut conn = MockRedisConnection::new(vec![
+        MockCmd::new(scan(0), Ok(batch("7", &["user:42:a", "user:42:b"]))),
+        MockCmd::new(scan(7), Ok(batch("3", &[]))),
+        MockCmd::new(scan(3), Ok(batch("0", &["user:42:a", "user:42:c"]))),
+    ]);
+
+    let keys = redis_scan!(&mut conn, SCAN MATCH user:{id}:* -> String)
+        .unique()
+        .collect::<RedisResult<Vec<_>>>()
+        .unwrap();
+    assert_eq!(keys, ["user:42:a", "user:42:b", "user:42:c"]);
+}
+
+#[test]
+fn test_scan_pairs() {
+    let mut conn = MockRedisConnection::new(vec![
+        MockCmd::new(
+            redis::cmd("HSCAN").arg("h").arg(0).arg("COUNT").arg(10),
+            Ok(batch("5", &["a", "1"])),
+        ),
+        MockCmd::new(
+            redis::cmd("HSCAN").arg("h").arg(5).arg("COUNT").arg(10),
+            Ok(batch("0", &["b", "2"])),
+        ),
+    ]);
+
+    let mut fields = vec![];
+    for field in redis_scan!(&mut conn, HSCAN h COUNT 10) {
+        let (name, value): (String, i64) = field.unwrap();
+        fields.push((name, value));
+    }
+    assert_eq!(fields, [("a".to_string(), 1), ("b".to_string(), 2)]);
+}
+
+#[test]
+fn test_scan_error() {
+    let mut conn = MockRedisConnection::new(vec![MockCmd::new(
+        redis::cmd("SSCAN").arg("s").arg(0),
+        Err::<Value, _>(redis::RedisError::from((
+            redis::ErrorKind::TypeError,
+            "WRONGTYPE",
+        ))),
+    )]);
+
+    let mut members = redis_scan!(&mut conn, SSCAN s -> String);
+    assert!(members.next().unwrap().is_err());
+    assert!(members.next().is_none());
+}
+
+#[cfg(feature = "async")]
+#[test]
+fn test_scan_async() {
+    use common::AsyncMock;
+    use futures::executor::block_on;
+    use futures::TryStreamExt;
+    use redis_rs_macro::redis_scan_async;
+
+    let id = 42;
+    let scan = |cursor: u64| {
+        redis::cmd("SCAN")
+            .arg(cursor)
+            .arg("MATCH")
+            .arg("user:42:*")
+            .clone()
+    };
+    let mut conn = AsyncMock::new(vec![
+        MockCmd::new(scan(0), Ok(batch("7", &["user:42:a"]))),
+        MockCmd::new(scan(7), Ok(batch("3", &[]))),
+        MockCmd::new(scan(3), Ok(batch("0", &["user:42:b"]))),
+    ]);
+
+    let keys: Vec<String> =
+        block_on(redis_scan_async!(&mut conn, SCAN MATCH user:{id}:* -> String).try_collect())
+            .unwrap();
+    assert_eq!(keys, ["user:42:a", "user:42:b"]);
+}
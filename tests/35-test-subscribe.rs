@@ -0,0 +1,116 @@
+use redis::{Msg, Value};
+use redis_rs_macro::{redis_subscribe, Subscriber};
+use std::ops::ControlFlow;
+
+fn message(channel: &str, payload: &str) -> Msg {
+    let value = Value::Bulk(vec![
+        Value::Data(b"message".to_vec()),
+        Value::Data(channel.as_bytes().to_vec()),
+        Value::Data(payload.as_bytes().to_vec()),
+    ]);
+    Msg::from_value(&value).unwrap()
+}
+
+#[test]
+fn test_subscriber() {
+    let mut total = 0;
+    let mut names = vec![];
+    let channels = ["a", "b"];
+    let mut subscriber = Subscriber::new()
+        .on("orders", |amount: i64| total += amount)
+        .on(&channels, |name: String| names.push(name))
+        .on("shutdown", |_: String| ControlFlow::Break(()));
+
+    let flows = [
+        subscriber.dispatch(&message("orders", "3")).unwrap(),
+        subscriber.dispatch(&message("b", "bob")).unwrap(),
+        subscriber.dispatch(&message("other", "x")).unwrap(),
+        subscriber.dispatch(&message("orders", "4")).unwrap(),
+        subscriber.dispatch(&message("shutdown", "")).unwrap(),
+    ];
+    assert!(subscriber.dispatch(&message("orders", "nan")).is_err());
+    drop(subscriber);
+    assert_eq!(flows[..4], [ControlFlow::Continue(()); 4]);
+    assert_eq!(flows[4], ControlFlow::Break(()));
+    assert_eq!(total, 7);
+    assert_eq!(names, ["bob"]);
+}
+
+fn pmessage(pattern: &str, channel: &str, payload: &str) -> Msg {
+    let value = Value::Bulk(vec![
+        Value::Data(b"pmessage".to_vec()),
+        Value::Data(pattern.as_bytes().to_vec()),
+        Value::Data(channel.as_bytes().to_vec()),
+        Value::Data(payload.as_bytes().to_vec()),
+    ]);
+    Msg::from_value(&value).unwrap()
+}
+
+#[test]
+fn test_subscriber_patterns() {
+    let mut orders = vec![];
+    let mut users = vec![];
+    let mut channels = 0;
+    let mut subscriber = Subscriber::new()
+        .on_pattern("orders:*", |channel: String, amount: i64| {
+            orders.push((channel, amount))
+        })
+        .on_pattern(&["users:*", "sessions:*"], |channel: String, id: u64| {
+            users.push((channel, id));
+            ControlFlow::Break(())
+        })
+        .on("orders:*", |_: String| channels += 1);
+
+    let flows = [
+        subscriber.dispatch(&pmessage("orders:*", "orders:eu", "3")),
+        subscriber.dispatch(&pmessage("other:*", "other:a", "x")),
+        subscriber.dispatch(&pmessage("sessions:*", "sessions:1", "7")),
+    ];
+    drop(subscriber);
+    let flows: Vec<_> = flows.into_iter().map(Result::unwrap).collect();
+    assert_eq!(
+        flows,
+        [
+            ControlFlow::Continue(()),
+            ControlFlow::Continue(()),
+            ControlFlow::Break(())
+        ]
+    );
+    assert_eq!(orders, [("orders:eu".to_string(), 3)]);
+    assert_eq!(users, [("sessions:1".to_string(), 7)]);
+    assert_eq!(channels, 0);
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn test_subscriber_json() {
+    use redis_rs_macro::Json;
+
+    #[derive(serde::Deserialize, Debug, PartialEq)]
+    struct Order {
+        id: u64,
+    }
+
+    let mut orders = vec![];
+    let mut subscriber = Subscriber::new().on("orders", |Json(order): Json<Order>| {
+        orders.push(order);
+    });
+    let flow = subscriber.dispatch(&message("orders", r#"{"id": 1}"#));
+    assert_eq!(flow.unwrap(), ControlFlow::Continue(()));
+    assert!(subscriber.dispatch(&message("orders", "{")).is_err());
+    drop(subscriber);
+    assert_eq!(orders, [Order { id: 1 }]);
+}
+
+// Receiving needs a server, so the macro is only checked to compile
+#[allow(dead_code)]
+fn listen(con: &mut redis::Connection, region: &str) -> redis::RedisResult<(i64, i64)> {
+    let mut total = 0;
+    let mut refunds = 0;
+    redis_subscribe!(con,
+        orders:{region} => |amount: i64| total += amount,
+        shutdown => |_: String| ControlFlow::Break(()),
+        pattern refunds:{region}:* => |_: String, amount: i64| refunds += amount,
+    )?;
+    Ok((total, refunds))
+}
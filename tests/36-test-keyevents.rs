@@ -0,0 +1,121 @@
+use redis::{Msg, Value};
+use redis_rs_macro::{redis_config_notify, redis_keyevents, KeyEvent, KeyEventKind, KeyEvents};
+use redis_test::{MockCmd, MockRedisConnection};
+use std::ops::ControlFlow;
+
+fn config_get(value: &str) -> MockCmd {
+    MockCmd::new(
+        redis::cmd("CONFIG")
+            .arg("GET")
+            .arg("notify-keyspace-events"),
+        Ok(Value::Bulk(vec![
+            Value::Data(b"notify-keyspace-events".to_vec()),
+            Value::Data(value.as_bytes().to_vec()),
+        ])),
+    )
+}
+
+#[test]
+fn test_keyevents_channels() {
+    let events = KeyEvents::new(3, &[KeyEventKind::Expired, KeyEventKind::XgroupCreate]);
+    assert_eq!(
+        events.channels(),
+        ["__keyevent@3__:expired", "__keyevent@3__:xgroup-create"]
+    );
+    assert_eq!(events.flags(), "Ext");
+    assert_eq!(
+        KeyEventKind::from_name("rename_from"),
+        Some(KeyEventKind::RenameFrom)
+    );
+}
+
+#[test]
+fn test_keyevents_enable() {
+    let events = KeyEvents::new(0, &[KeyEventKind::Expired, KeyEventKind::Del]);
+    let mut conn = MockRedisConnection::new(vec![
+        config_get("g"),
+        MockCmd::new(
+            redis::cmd("CONFIG")
+                .arg("SET")
+                .arg("notify-keyspace-events")
+                .arg("gEx"),
+            Ok("OK"),
+        ),
+        config_get("AKE"),
+    ]);
+
+    events.enable(&mut conn).unwrap();
+    // `A` covers both classes, so nothing is set
+    events.enable(&mut conn).unwrap();
+}
+
+#[test]
+fn test_keyevents_dispatch() {
+    let events = KeyEvents::new(0, &[KeyEventKind::Expired, KeyEventKind::Del]);
+    let mut received = vec![];
+    let mut subscriber = events.subscriber(|event: KeyEvent| received.push(event));
+    let message = |channel: &str, key: &str| {
+        let value = Value::Bulk(vec![
+            Value::Data(b"message".to_vec()),
+            Value::Data(channel.as_bytes().to_vec()),
+            Value::Data(key.as_bytes().to_vec()),
+        ]);
+        Msg::from_value(&value).unwrap()
+    };
+
+    for channel in ["__keyevent@0__:expired", "__keyevent@0__:del"] {
+        let flow = subscriber.dispatch(&message(channel, "session:1"));
+        assert_eq!(flow.unwrap(), ControlFlow::Continue(()));
+    }
+    drop(subscriber);
+    assert_eq!(
+        received,
+        [
+            KeyEvent {
+                db: 0,
+                kind: KeyEventKind::Expired,
+                key: "session:1".to_string()
+            },
+            KeyEvent {
+                db: 0,
+                kind: KeyEventKind::Del,
+                key: "session:1".to_string()
+            },
+        ]
+    );
+}
+
+// Receiving needs a server, so the macro is only checked to compile
+#[allow(dead_code)]
+fn listen(con: &mut redis::Connection) -> redis::RedisResult<()> {
+    redis_keyevents!(con, 0, [expired, del, xgroup - create], |event| {
+        if event.kind == KeyEventKind::Expired {
+            return ControlFlow::Break(());
+        }
+        ControlFlow::Continue(())
+    })
+}
+
+#[test]
+fn test_config_notify() {
+    let config = |flags: &str| {
+        redis::cmd("CONFIG")
+            .arg("SET")
+            .arg("notify-keyspace-events")
+            .arg(flags)
+            .get_packed_command()
+    };
+    assert_eq!(
+        redis_config_notify!(Expired, Evicted, Set).get_packed_command(),
+        config("Esxe")
+    );
+    assert_eq!(
+        redis_config_notify!(Keyspace, SortedSet, String,).get_packed_command(),
+        config("K$z")
+    );
+    assert_eq!(
+        redis_config_notify!(All, Keyevent, New).get_packed_command(),
+        config("EnA")
+    );
+    assert_eq!(redis_config_notify!().get_packed_command(), config(""));
+}
@@ -0,0 +1,47 @@
+#![cfg(all(feature = "json", feature = "msgpack"))]
+
+use redis::FromRedisValue;
+use redis_rs_macro::{redis_publish, Json, Msgpack};
+use redis_test::{MockCmd, MockRedisConnection};
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct Order {
+    id: u64,
+    item: String,
+}
+
+#[test]
+fn test_publish() {
+    let order = Order {
+        id: 1,
+        item: "book".to_string(),
+    };
+    let region = "eu";
+    let json = serde_json::to_string(&order).unwrap();
+    let msgpack = rmp_serde::to_vec(&order).unwrap();
+    let mut conn = MockRedisConnection::new(vec![
+        MockCmd::new(
+            redis::cmd("PUBLISH").arg("orders.created").arg(&json),
+            Ok(2),
+        ),
+        MockCmd::new(redis::cmd("PUBLISH").arg("orders:eu").arg(&msgpack), Ok(0)),
+        MockCmd::new(redis::cmd("PUBLISH").arg("raw").arg(b"\x00\x01"), Ok(1)),
+    ]);
+
+    let received = (|| -> redis::RedisResult<_> {
+        Ok([
+            redis_publish!(&mut conn, "orders.created", &order)?,
+            redis_publish!(&mut conn, orders:{region}, msgpack &order)?,
+            redis_publish!(&mut conn, raw, bytes b"\x00\x01")?,
+        ])
+    })()
+    .unwrap();
+    assert_eq!(received, [2, 0, 1]);
+
+    // Subscribers read the payloads back in the same format
+    let Json(read) = Json::<Order>::from_redis_value(&redis::Value::Data(json.into())).unwrap();
+    assert_eq!(read, order);
+    let Msgpack(read) = Msgpack::<Order>::from_redis_value(&redis::Value::Data(msgpack)).unwrap();
+    assert_eq!(read, order);
+}
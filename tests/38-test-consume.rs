@@ -0,0 +1,262 @@
+use redis::Value;
+use redis_rs_macro::{redis_consume, StreamEntry};
+use redis_test::{MockCmd, MockRedisConnection};
+use std::collections::HashMap;
+use std::ops::ControlFlow;
+
+mod common;
+
+use common::data;
+
+fn entry(id: &str, fields: &[&str]) -> Value {
+    Value::Bulk(vec![
+        data(id),
+        Value::Bulk(fields.iter().map(data).collect()),
+    ])
+}
+
+fn read(entries: Vec<Value>) -> MockCmd {
+    MockCmd::new(
+        redis::cmd("XREADGROUP")
+            .arg("GROUP")
+            .arg("workers")
+            .arg("worker:1")
+            .arg("COUNT")
+            .arg(10)
+            .arg("BLOCK")
+            .arg(5000)
+            .arg("STREAMS")
+            .arg("orders")
+            .arg(">"),
+        Ok(match entries.is_empty() {
+            true => Value::Nil,
+            false => Value::Bulk(vec![Value::Bulk(vec![
+                data("orders"),
+                Value::Bulk(entries),
+            ])]),
+        }),
+    )
+}
+
+fn ack(id: &str) -> MockCmd {
+    MockCmd::new(
+        redis::cmd("XACK").arg("orders").arg("workers").arg(id),
+        Ok(1),
+    )
+}
+
+#[test]
+fn test_consume() {
+    let busy = redis::parse_redis_value(b"-BUSYGROUP Consumer Group name already exists\r\n");
+    let mut conn = MockRedisConnection::new(vec![
+        MockCmd::new(
+            redis::cmd("XGROUP")
+                .arg("CREATE")
+                .arg("orders")
+                .arg("workers")
+                .arg("$")
+                .arg("MKSTREAM"),
+            busy,
+        ),
+        read(vec![]),
+        read(vec![
+            entry("1-0", &["item", "book"]),
+            entry("2-0", &["item", "bad"]),
+            Value::Bulk(vec![data("3-0"), Value::Nil]),
+        ]),
+        ack("1-0"),
+        ack("3-0"),
+        read(vec![entry("4-0", &["item", "pen"])]),
+        ack("4-0"),
+    ]);
+
+    let id = 1;
+    let mut items = vec![];
+    redis_consume!(&mut conn, STREAM orders GROUP workers CONSUMER worker:{id} COUNT 10,
+        |entry: StreamEntry<HashMap<String, String>>| {
+            let item = entry.fields["item"].clone();
+            if item == "bad" {
+                return Err("bad item");
+            }
+            items.push(item);
+            Ok(match items.len() {
+                2 => ControlFlow::Break(()),
+                _ => ControlFlow::Continue(()),
+            })
+        },
+    )
+    .unwrap();
+    assert_eq!(items, ["book", "pen"]);
+}
+
+#[test]
+fn test_consume_create() {
+    let mut conn = MockRedisConnection::new(vec![
+        MockCmd::new(
+            redis::cmd("XGROUP")
+                .arg("CREATE")
+                .arg("orders")
+                .arg("workers")
+                .arg("0")
+                .arg("MKSTREAM"),
+            Ok("OK"),
+        ),
+        MockCmd::new(
+            redis::cmd("XREADGROUP")
+                .arg("GROUP")
+                .arg("workers")
+                .arg("a")
+                .arg("BLOCK")
+                .arg("0")
+                .arg("STREAMS")
+                .arg("orders")
+                .arg(">"),
+            Ok(Value::Bulk(vec![Value::Bulk(vec![
+                data("orders"),
+                Value::Bulk(vec![entry("1-0", &["n", "1"])]),
+            ])])),
+        ),
+        ack("1-0"),
+    ]);
+
+    redis_consume!(&mut conn, STREAM orders GROUP workers CONSUMER a BLOCK 0 START 0,
+        |entry: StreamEntry<Vec<(String, i64)>>| {
+            assert_eq!(entry.fields, [("n".to_string(), 1)]);
+            Ok::<_, ()>(ControlFlow::Break(()))
+        },
+    )
+    .unwrap();
+}
+
+#[test]
+fn test_consume_claim() {
+    let claim = |cursor: &str, next: &str, entries: Vec<Value>| {
+        MockCmd::new(
+            redis::cmd("XAUTOCLAIM")
+                .arg("orders")
+                .arg("workers")
+                .arg("a")
+                .arg(60000)
+                .arg(cursor),
+            Ok(Value::Bulk(vec![
+                data(next),
+                Value::Bulk(entries),
+                Value::Bulk(vec![]),
+            ])),
+        )
+    };
+    let mut conn = MockRedisConnection::new(vec![
+        MockCmd::new(
+            redis::cmd("XGROUP")
+                .arg("CREATE")
+                .arg("orders")
+                .arg("workers")
+                .arg("$")
+                .arg("MKSTREAM"),
+            Ok("OK"),
+        ),
+        claim("0-0", "5-0", vec![entry("1-0", &["item", "lost"])]),
+        ack("1-0"),
+        claim("5-0", "0-0", vec![entry("5-0", &["item", "stale"])]),
+        ack("5-0"),
+        MockCmd::new(
+            redis::cmd("XREADGROUP")
+                .arg("GROUP")
+                .arg("workers")
+                .arg("a")
+                .arg("BLOCK")
+                .arg(5000)
+                .arg("STREAMS")
+                .arg("orders")
+                .arg(">"),
+            Ok(Value::Bulk(vec![Value::Bulk(vec![
+                data("orders"),
+                Value::Bulk(vec![entry("6-0", &["item", "new"])]),
+            ])])),
+        ),
+        ack("6-0"),
+    ]);
+
+    let idle = 60000;
+    let mut items = vec![];
+    redis_consume!(&mut conn, STREAM orders GROUP workers CONSUMER a CLAIM {idle},
+        |entry: StreamEntry<HashMap<String, String>>| {
+            items.push(entry.fields["item"].clone());
+            Ok::<_, ()>(match items.len() {
+                3 => ControlFlow::Break(()),
+                _ => ControlFlow::Continue(()),
+            })
+        },
+    )
+    .unwrap();
+    assert_eq!(items, ["lost", "stale", "new"]);
+}
+
+#[test]
+fn test_consume_dead_letter() {
+    let pending = |id: &str, deliveries: i64| {
+        MockCmd::new(
+            redis::cmd("XPENDING")
+                .arg("orders")
+                .arg("workers")
+                .arg(id)
+                .arg(id)
+                .arg(1),
+            Ok(Value::Bulk(vec![Value::Bulk(vec![
+                data(id),
+                data("b"),
+                Value::Int(70000),
+                Value::Int(deliveries),
+            ])])),
+        )
+    };
+    let mut conn = MockRedisConnection::new(vec![
+        MockCmd::new(
+            redis::cmd("XGROUP")
+                .arg("CREATE")
+                .arg("orders")
+                .arg("workers")
+                .arg("$")
+                .arg("MKSTREAM"),
+            Ok("OK"),
+        ),
+        MockCmd::new(
+            redis::cmd("XAUTOCLAIM")
+                .arg("orders")
+                .arg("workers")
+                .arg("a")
+                .arg(60000)
+                .arg("0-0"),
+            Ok(Value::Bulk(vec![
+                data("0-0"),
+                Value::Bulk(vec![
+                    entry("1-0", &["item", "poison"]),
+                    entry("2-0", &["item", "retry"]),
+                ]),
+            ])),
+        ),
+        pending("1-0", 4),
+        MockCmd::new(
+            redis::cmd("XADD")
+                .arg("orders:dead")
+                .arg("*")
+                .arg("item")
+                .arg("poison"),
+            Ok("9-0"),
+        ),
+        ack("1-0"),
+        pending("2-0", 2),
+        ack("2-0"),
+    ]);
+
+    let mut items = vec![];
+    redis_consume!(&mut conn,
+        STREAM orders GROUP workers CONSUMER a CLAIM 60000 MAX_DELIVERIES 3 DEAD_LETTER orders:dead,
+        |entry: StreamEntry<HashMap<String, String>>| {
+            items.push(entry.fields["item"].clone());
+            Ok::<_, ()>(ControlFlow::Break(()))
+        },
+    )
+    .unwrap();
+    assert_eq!(items, ["retry"]);
+}
@@ -0,0 +1,129 @@
+use redis::{FromRedisValue, Value};
+use redis_rs_macro::{redis, redis_consume, RedisStreamEntry, StreamEntry};
+use redis_test::{MockCmd, MockRedisConnection};
+use std::ops::ControlFlow;
+
+mod common;
+
+use common::data;
+
+#[derive(RedisStreamEntry, Debug, PartialEq)]
+struct Order {
+    item: String,
+    #[redis(rename = "qty")]
+    quantity: u32,
+    note: Option<String>,
+}
+
+fn fields(fields: &[&str]) -> Value {
+    Value::Bulk(fields.iter().map(data).collect())
+}
+
+#[test]
+fn test_stream_entry_write() {
+    let order = Order {
+        item: "book".into(),
+        quantity: 2,
+        note: None,
+    };
+    assert_eq!(
+        redis!(XADD orders * {*order}).get_packed_command(),
+        redis::cmd("XADD")
+            .arg("orders")
+            .arg("*")
+            .arg("item")
+            .arg("book")
+            .arg("qty")
+            .arg(2)
+            .get_packed_command()
+    );
+    let order = Order {
+        note: Some("gift".into()),
+        ..order
+    };
+    assert_eq!(
+        redis!(XADD orders * {*order}).get_packed_command(),
+        redis::cmd("XADD")
+            .arg("orders")
+            .arg("*")
+            .arg("item")
+            .arg("book")
+            .arg("qty")
+            .arg(2)
+            .arg("note")
+            .arg("gift")
+            .get_packed_command()
+    );
+}
+
+#[test]
+fn test_stream_entry_read() {
+    let order = Order::from_redis_value(&fields(&["qty", "3", "item", "pen"])).unwrap();
+    assert_eq!(
+        order,
+        Order {
+            item: "pen".into(),
+            quantity: 3,
+            note: None,
+        }
+    );
+    let order = Order::from_redis_value(&fields(&["item", "pen", "qty", "1", "note", "x"]));
+    assert_eq!(order.unwrap().note.as_deref(), Some("x"));
+    let err = Order::from_redis_value(&fields(&["item", "pen"])).unwrap_err();
+    assert!(err.to_string().contains("qty"), "{}", err);
+    assert!(Order::from_redis_value(&fields(&["item", "pen", "qty"])).is_err());
+    assert!(Order::from_redis_value(&fields(&["item", "pen", "qty", "many"])).is_err());
+}
+
+#[test]
+fn test_stream_entry_consume() {
+    let mut conn = MockRedisConnection::new(vec![
+        MockCmd::new(
+            redis::cmd("XGROUP")
+                .arg("CREATE")
+                .arg("orders")
+                .arg("workers")
+                .arg("$")
+                .arg("MKSTREAM"),
+            Ok("OK"),
+        ),
+        MockCmd::new(
+            redis::cmd("XREADGROUP")
+                .arg("GROUP")
+                .arg("workers")
+                .arg("a")
+                .arg("BLOCK")
+                .arg(5000)
+                .arg("STREAMS")
+                .arg("orders")
+                .arg(">"),
+            Ok(Value::Bulk(vec![Value::Bulk(vec![
+                data("orders"),
+                Value::Bulk(vec![Value::Bulk(vec![
+                    data("1-0"),
+                    fields(&["item", "book", "qty", "2"]),
+                ])]),
+            ])])),
+        ),
+        MockCmd::new(
+            redis::cmd("XACK").arg("orders").arg("workers").arg("1-0"),
+            Ok(1),
+        ),
+    ]);
+    let mut orders = vec![];
+    redis_consume!(&mut conn, STREAM orders GROUP workers CONSUMER a,
+        |entry: StreamEntry<Order>| {
+            orders.push(entry.fields);
+            Ok::<_, ()>(ControlFlow::Break(()))
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        orders,
+        [Order {
+            item: "book".into(),
+            quantity: 2,
+            note: None,
+        }]
+    );
+}
@@ -0,0 +1,50 @@
+use redis_rs_macro::redis_eval;
+use redis_test::{MockCmd, MockRedisConnection};
+
+#[test]
+fn test_eval() {
+    let id = 42;
+    let ttl = 60;
+    let more = vec!["b", "c"];
+    let script = r#"return redis.call("INCR", KEYS[1])"#;
+    let mut conn = MockRedisConnection::new(vec![
+        MockCmd::new(
+            redis::cmd("EVAL")
+                .arg(script)
+                .arg(2)
+                .arg("user:42")
+                .arg("counters")
+                .arg(60),
+            Ok(1),
+        ),
+        MockCmd::new(
+            redis::cmd("EVAL")
+                .arg(script)
+                .arg(3)
+                .arg("a")
+                .arg("b")
+                .arg("c")
+                .arg("x"),
+            Ok(2),
+        ),
+        MockCmd::new(redis::cmd("EVAL").arg("return 3").arg(0), Ok(3)),
+        MockCmd::new(
+            redis::cmd("EVAL").arg("return ARGV[1]").arg(0).arg("y"),
+            Ok(4),
+        ),
+    ]);
+
+    let replies: Vec<i64> = vec![
+        redis_eval!(keys = [user:{id}, counters], args = [{ttl}], r#"return redis.call("INCR", KEYS[1])"#)
+            .query(&mut conn)
+            .unwrap(),
+        redis_eval!(keys = [a, {..more}], args = [x], r#"return redis.call("INCR", KEYS[1])"#)
+            .query(&mut conn)
+            .unwrap(),
+        redis_eval!("return 3").query(&mut conn).unwrap(),
+        redis_eval!(args = [y], "return ARGV[1]",)
+            .query(&mut conn)
+            .unwrap(),
+    ];
+    assert_eq!(replies, [1, 2, 3, 4]);
+}
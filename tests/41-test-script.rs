@@ -0,0 +1,75 @@
+use redis_rs_macro::{redis_eval, redis_script, ScriptCmd};
+use redis_test::{MockCmd, MockRedisConnection};
+
+/// Add to a counter
+#[redis_script("tests/scripts/incr_by.lua")]
+fn incr_by(#[key] counter: &str, amount: i64) -> redis::RedisResult<i64>;
+
+#[test]
+fn test_script() {
+    assert_eq!(INCR_BY_SHA1, "2e3edaf76cfcc844648b8435f69d3eca829a5ebb");
+    let source = include_str!("scripts/incr_by.lua");
+    let evalsha = || {
+        redis::cmd("EVALSHA")
+            .arg(INCR_BY_SHA1)
+            .arg(1)
+            .arg("visits")
+            .arg(2)
+            .clone()
+    };
+    let noscript = redis::parse_redis_value(b"-NOSCRIPT No matching script\r\n").unwrap_err();
+    let mut conn = MockRedisConnection::new(vec![
+        MockCmd::new(evalsha(), Ok(2)),
+        MockCmd::new(evalsha(), Err::<i64, _>(noscript)),
+        MockCmd::new(
+            redis::cmd("EVAL").arg(source).arg(1).arg("visits").arg(2),
+            Ok(4),
+        ),
+    ]);
+    assert_eq!(incr_by(&mut conn, "visits", 2).unwrap(), 2);
+    assert_eq!(incr_by(&mut conn, "visits", 2).unwrap(), 4);
+}
+
+#[test]
+fn test_script_cmd() {
+    let mut call = ScriptCmd::new("return 1", "e0e1f9fabfc9d4800c877a703b823ac0578ff8db");
+    call.key("a").key(&["b", "c"]).arg(1);
+    assert_eq!(
+        call.eval_cmd().get_packed_command(),
+        redis::cmd("EVAL")
+            .arg("return 1")
+            .arg(3)
+            .arg("a")
+            .arg("b")
+            .arg("c")
+            .arg(1)
+            .get_packed_command()
+    );
+    let other = redis::parse_redis_value(b"-ERR other\r\n").unwrap_err();
+    let mut conn =
+        MockRedisConnection::new(vec![MockCmd::new(call.evalsha_cmd(), Err::<i64, _>(other))]);
+    assert!(call.invoke::<i64>(&mut conn).is_err());
+}
+
+#[test]
+fn test_eval_invoke() {
+    let id = 7;
+    let script = r#"return redis.call("INCR", KEYS[1])"#;
+    let noscript = redis::parse_redis_value(b"-NOSCRIPT No matching script\r\n").unwrap_err();
+    let evalsha = redis::cmd("EVALSHA")
+        .arg("f793247de6e1e3c553cd42d39c812df499e679e4")
+        .arg(1)
+        .arg("visits:7")
+        .clone();
+    let mut conn = MockRedisConnection::new(vec![
+        MockCmd::new(evalsha.clone(), Err::<i64, _>(noscript)),
+        MockCmd::new(redis::cmd("EVAL").arg(script).arg(1).arg("visits:7"), Ok(1)),
+        MockCmd::new(evalsha, Ok(2)),
+    ]);
+    for expected in [1, 2] {
+        let visits: i64 =
+            redis_eval!(&mut conn, keys = [visits:{id}], r#"return redis.call("INCR", KEYS[1])"#)
+                .unwrap();
+        assert_eq!(visits, expected);
+    }
+}
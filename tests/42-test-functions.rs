@@ -0,0 +1,42 @@
+use redis_rs_macro::{redis_functions, Args};
+use redis_test::{MockCmd, MockRedisConnection};
+
+#[redis_functions("tests/scripts/orders.lua")]
+mod orders {}
+
+#[test]
+fn test_functions() {
+    assert_eq!(orders::LIBRARY, "orders");
+    assert!(orders::SOURCE.starts_with("#!lua name=orders"));
+    let mut conn = MockRedisConnection::new(vec![
+        MockCmd::new(
+            redis::cmd("FUNCTION")
+                .arg("LOAD")
+                .arg("REPLACE")
+                .arg(orders::SOURCE),
+            Ok("orders"),
+        ),
+        MockCmd::new(
+            redis::cmd("FCALL")
+                .arg("add")
+                .arg(1)
+                .arg("orders:eu")
+                .arg(3),
+            Ok(3),
+        ),
+        MockCmd::new(
+            redis::cmd("FCALL_RO")
+                .arg("total")
+                .arg(2)
+                .arg("orders:eu")
+                .arg("orders:us"),
+            Ok(5),
+        ),
+    ]);
+    orders::load(&mut conn).unwrap();
+    let added: i64 = orders::add(&mut conn, "orders:eu", 3).unwrap();
+    assert_eq!(added, 3);
+    let total: i64 =
+        orders::total(&mut conn, &["orders:eu", "orders:us"][..], Args::new()).unwrap();
+    assert_eq!(total, 5);
+}
@@ -0,0 +1,165 @@
+use redis::Value;
+use redis_rs_macro::{redis, redis_lock, Lock};
+use redis_test::{MockCmd, MockRedisConnection};
+
+mod common;
+
+use common::StubConnection;
+
+#[test]
+fn test_lock_scripts() {
+    let lock = Lock::new("jobs", 1000);
+    for call in [lock.release_cmd(), lock.extend_cmd(1000)] {
+        assert_eq!(redis::Script::new(call.source()).get_hash(), call.sha1());
+    }
+    assert_eq!(lock.token().len(), 32);
+    assert_ne!(lock.token(), Lock::new("jobs", 1000).token());
+}
+
+#[test]
+fn test_lock() {
+    let id = 42;
+    let lock = Lock::new("lock:order:42", 30000);
+    let token = lock.token().to_string();
+    let set = |token: &str| {
+        redis::cmd("SET")
+            .arg("lock:order:42")
+            .arg(token)
+            .arg("NX")
+            .arg("PX")
+            .arg(30000)
+            .clone()
+    };
+    let release = lock.release_cmd();
+    let mut conn = MockRedisConnection::new(vec![
+        MockCmd::new(set(&token), Ok(Value::Okay)),
+        MockCmd::new(
+            redis::cmd("HSET")
+                .arg("order:42")
+                .arg("status")
+                .arg("shipped"),
+            Ok(1),
+        ),
+        MockCmd::new(lock.extend_cmd(5000).evalsha_cmd(), Ok(1)),
+        MockCmd::new(release.evalsha_cmd(), Ok(1)),
+        MockCmd::new(set(&token), Ok(Value::Nil)),
+        MockCmd::new(set(&token), Ok(Value::Okay)),
+        MockCmd::new(release.evalsha_cmd(), Ok(0)),
+    ]);
+
+    let mut guard = lock.clone().acquire(&mut conn).unwrap().unwrap();
+    redis!(HSET order:{id} status shipped)
+        .query::<()>(&mut *guard)
+        .unwrap();
+    assert!(guard.extend(5000).unwrap());
+    drop(guard);
+    assert!(lock.clone().acquire(&mut conn).unwrap().is_none());
+    let guard = lock.acquire(&mut conn).unwrap().unwrap();
+    assert!(!guard.unlock().unwrap());
+}
+
+#[test]
+fn test_lock_macro() {
+    let id = 7;
+    let mut conn = StubConnection::new(vec![Value::Okay, Value::Int(1)]);
+    let guard = redis_lock!(&mut conn, lock:{id}, ttl = 100)
+        .unwrap()
+        .unwrap();
+    let lock = guard.lock().clone();
+    drop(guard);
+    assert_eq!(
+        conn.sent,
+        [
+            redis::cmd("SET")
+                .arg("lock:7")
+                .arg(lock.token())
+                .arg("NX")
+                .arg("PX")
+                .arg(100)
+                .get_packed_command(),
+            lock.release_cmd().evalsha_cmd().get_packed_command(),
+        ]
+    );
+}
+
+#[cfg(feature = "async")]
+#[test]
+fn test_lock_async() {
+    use common::AsyncMock;
+    use futures::executor::block_on;
+    use redis::RedisResult;
+    use redis_rs_macro::{redis_async, redis_lock_async};
+
+    let id = 7;
+    let (ok, one) = (Value::Okay, Value::Int(1));
+    let replies = vec![ok.clone(), one.clone(), one.clone(), ok, one];
+    let mut conn = AsyncMock(StubConnection::new(replies));
+    let mut evaluated = 0;
+    // The body can use the names of the expansion
+    let output = "shipped";
+    let future = redis_lock_async!({ evaluated += 1; &mut conn }, lock:{id}, ttl = 100, |con| {
+        redis_async!(con, HSET order:{id} status {output} -> i64).await?
+    });
+    assert_eq!(block_on(future).unwrap(), Some(1));
+    // The connection is evaluated once for the lock, the body and the release
+    assert_eq!(evaluated, 1);
+    assert_eq!(conn.0.sent.len(), 3);
+    assert_eq!(
+        conn.0.sent[1],
+        redis::cmd("HSET")
+            .arg("order:7")
+            .arg("status")
+            .arg("shipped")
+            .get_packed_command()
+    );
+
+    // The lock is released when `?` leaves the body early
+    let err = block_on(redis_lock_async!(&mut conn, lock:{id}, ttl = 100, {
+        let failed: RedisResult<()> = Err((redis::ErrorKind::TypeError, "failed").into());
+        failed?;
+        1
+    }))
+    .unwrap_err();
+    assert_eq!(err.kind(), redis::ErrorKind::TypeError);
+    assert_eq!(conn.0.sent.len(), 5);
+    let release = String::from_utf8_lossy(&conn.0.sent[4]).into_owned();
+    assert!(release.contains("EVAL"), "{}", release);
+}
+
+#[cfg(feature = "async")]
+#[test]
+fn test_lock_guard_async() {
+    use common::AsyncMock;
+    use futures::executor::block_on;
+
+    let lock = Lock::new("lock:order:42", 30000);
+    let replies = vec![Value::Okay, Value::Int(1), Value::Int(1), Value::Nil];
+    let mut conn = AsyncMock(StubConnection::new(replies));
+    block_on(async {
+        let mut guard = lock
+            .clone()
+            .acquire_async(&mut conn)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(guard.lock().token(), lock.token());
+        assert!(guard.extend(5000).await.unwrap());
+        assert!(guard.unlock().await.unwrap());
+        // The lock is held elsewhere
+        assert!(lock
+            .clone()
+            .acquire_async(&mut conn)
+            .await
+            .unwrap()
+            .is_none());
+    });
+    assert_eq!(
+        conn.0.sent,
+        [
+            lock.acquire_cmd().get_packed_command(),
+            lock.extend_cmd(5000).eval_cmd().get_packed_command(),
+            lock.release_cmd().eval_cmd().get_packed_command(),
+            lock.acquire_cmd().get_packed_command(),
+        ]
+    );
+}
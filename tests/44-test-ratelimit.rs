@@ -0,0 +1,60 @@
+use redis::Value;
+use redis_rs_macro::{redis_ratelimit, RateLimit, RateLimitStatus, Window};
+use redis_test::{MockCmd, MockRedisConnection};
+use std::time::Duration;
+
+fn reply(allowed: i64, remaining: i64, reset: i64) -> Value {
+    Value::Bulk(vec![
+        Value::Int(allowed),
+        Value::Int(remaining),
+        Value::Int(reset),
+    ])
+}
+
+#[test]
+fn test_ratelimit_scripts() {
+    for kind in [Window::Fixed, Window::Sliding] {
+        let call = RateLimit::new("api", 10, 1000, kind).check_cmd();
+        assert_eq!(redis::Script::new(call.source()).get_hash(), call.sha1());
+    }
+}
+
+#[test]
+fn test_ratelimit_fixed() {
+    let user = 42;
+    let call = RateLimit::new("api:42", 2, 60000, Window::Fixed).check_cmd();
+    let mut conn = MockRedisConnection::new(vec![
+        MockCmd::new(call.evalsha_cmd(), Ok(reply(1, 1, 60000))),
+        MockCmd::new(call.evalsha_cmd(), Ok(reply(0, 0, 59000))),
+    ]);
+    let status = redis_ratelimit!(&mut conn, api:{user}, limit = 2, window = 60000).unwrap();
+    assert_eq!(
+        status,
+        RateLimitStatus {
+            allowed: true,
+            remaining: 1,
+            reset: Duration::from_secs(60),
+        }
+    );
+    let status = redis_ratelimit!(&mut conn, api:{user}, limit = 2, window = 60000, fixed).unwrap();
+    assert!(!status.allowed);
+    assert_eq!(status.reset, Duration::from_secs(59));
+}
+
+#[test]
+fn test_ratelimit_sliding() {
+    let limit = RateLimit::new("api", 5, 1000, Window::Sliding);
+    let (a, b) = (limit.check_cmd(), limit.check_cmd());
+    // Each request is logged under a unique member
+    assert_eq!(a.sha1(), b.sha1());
+    assert_ne!(
+        a.evalsha_cmd().get_packed_command(),
+        b.evalsha_cmd().get_packed_command()
+    );
+    let packed = String::from_utf8(a.evalsha_cmd().get_packed_command()).unwrap();
+    assert!(
+        packed.contains("\r\napi\r\n$1\r\n5\r\n$4\r\n1000\r\n$32\r\n"),
+        "{:?}",
+        packed
+    );
+}
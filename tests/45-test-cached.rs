@@ -0,0 +1,219 @@
+#![cfg(all(feature = "json", feature = "msgpack"))]
+
+use redis::{ConnectionLike, RedisError, RedisResult, Value};
+use redis_rs_macro::{redis_cache, redis_cached};
+use redis_test::{MockCmd, MockRedisConnection};
+use serde::{Deserialize, Serialize};
+use std::cell::Cell;
+
+mod common;
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct User {
+    id: u64,
+    name: String,
+}
+
+thread_local! {
+    static LOADS: Cell<u32> = const { Cell::new(0) };
+}
+
+fn loads() -> u32 {
+    LOADS.with(Cell::get)
+}
+
+#[redis_cached(key = "user:{id}", ttl = 300)]
+fn load_user(con: &mut MockRedisConnection, id: u64) -> RedisResult<User> {
+    LOADS.with(|loads| loads.set(loads.get() + 1));
+    Ok(User {
+        id,
+        name: "alice".to_string(),
+    })
+}
+
+#[test]
+fn test_cached() {
+    let json = serde_json::to_string(&User {
+        id: 7,
+        name: "alice".to_string(),
+    })
+    .unwrap();
+    let mut con = MockRedisConnection::new(vec![
+        MockCmd::new(redis::cmd("GET").arg("user:7"), Ok(Value::Nil)),
+        MockCmd::new(
+            redis::cmd("SET")
+                .arg("user:7")
+                .arg(&json)
+                .arg("EX")
+                .arg(300),
+            Ok("OK"),
+        ),
+        MockCmd::new(redis::cmd("GET").arg("user:7"), Ok(json.as_str())),
+    ]);
+    let before = loads();
+    let user = load_user(&mut con, 7).unwrap();
+    assert_eq!(user.name, "alice");
+    assert_eq!(loads(), before + 1);
+    assert_eq!(load_user(&mut con, 7).unwrap(), user);
+    assert_eq!(loads(), before + 1);
+}
+
+#[derive(Debug)]
+enum AppError {
+    Redis(RedisError),
+    NotFound,
+}
+
+impl From<RedisError> for AppError {
+    fn from(err: RedisError) -> AppError {
+        AppError::Redis(err)
+    }
+}
+
+#[redis_cached(key = scores:{board}, con = redis, msgpack)]
+fn top_scores(redis: &mut dyn ConnectionLike, board: &str) -> Result<Vec<u32>, AppError> {
+    if board.is_empty() {
+        return Err(AppError::NotFound);
+    }
+    if board == "daily" {
+        return Ok(vec![9, 8]);
+    }
+    Ok(vec![])
+}
+
+#[test]
+fn test_cached_options() {
+    let msgpack = rmp_serde::to_vec(&vec![9u32, 8]).unwrap();
+    let mut con = MockRedisConnection::new(vec![
+        MockCmd::new(redis::cmd("GET").arg("scores:daily"), Ok(Value::Nil)),
+        MockCmd::new(
+            redis::cmd("SET").arg("scores:daily").arg(&msgpack),
+            Ok("OK"),
+        ),
+        // Errors aren't stored
+        MockCmd::new(redis::cmd("GET").arg("scores:"), Ok(Value::Nil)),
+        MockCmd::new(redis::cmd("GET").arg("scores:daily"), Ok(msgpack.clone())),
+    ]);
+    assert_eq!(top_scores(&mut con, "daily").unwrap(), [9, 8]);
+    assert!(matches!(top_scores(&mut con, ""), Err(AppError::NotFound)));
+    assert_eq!(top_scores(&mut con, "daily").unwrap(), [9, 8]);
+}
+
+#[test]
+fn test_cached_errors() {
+    let msgpack = rmp_serde::to_vec(&Vec::<u32>::new()).unwrap();
+    let mut con = MockRedisConnection::new(vec![
+        // A value in another format is recomputed and overwritten
+        MockCmd::new(redis::cmd("GET").arg("scores:weekly"), Ok("not msgpack")),
+        MockCmd::new(
+            redis::cmd("SET").arg("scores:weekly").arg(&msgpack),
+            Ok("OK"),
+        ),
+        // A failing SET still returns the computed value
+        MockCmd::new(redis::cmd("GET").arg("scores:daily"), Ok(Value::Nil)),
+        MockCmd::new(
+            redis::cmd("SET")
+                .arg("scores:daily")
+                .arg(rmp_serde::to_vec(&vec![9u32, 8]).unwrap()),
+            Err::<Value, _>(RedisError::from((redis::ErrorKind::ReadOnly, "read only"))),
+        ),
+        // Errors of the GET are returned
+        MockCmd::new(
+            redis::cmd("GET").arg("scores:monthly"),
+            Err::<Value, _>(RedisError::from((redis::ErrorKind::IoError, "closed"))),
+        ),
+    ]);
+    assert_eq!(top_scores(&mut con, "weekly").unwrap(), Vec::<u32>::new());
+    assert_eq!(top_scores(&mut con, "daily").unwrap(), [9, 8]);
+    let Err(AppError::Redis(err)) = top_scores(&mut con, "monthly") else {
+        panic!("expected the error of the GET");
+    };
+    assert_eq!(err.kind(), redis::ErrorKind::IoError);
+}
+
+#[test]
+fn test_cache() {
+    let json = serde_json::to_string(&["dark", "beta"]).unwrap();
+    let mut con = MockRedisConnection::new(vec![
+        MockCmd::new(redis::cmd("GET").arg("config:features"), Ok(Value::Nil)),
+        MockCmd::new(
+            redis::cmd("SET")
+                .arg("config:features")
+                .arg(&json)
+                .arg("EX")
+                .arg(60),
+            Ok("OK"),
+        ),
+        MockCmd::new(redis::cmd("GET").arg("config:features"), Ok(json.as_str())),
+        MockCmd::new(redis::cmd("GET").arg("config:limits"), Ok(Value::Nil)),
+        MockCmd::new(
+            redis::cmd("SET")
+                .arg("config:limits")
+                .arg(rmp_serde::to_vec(&3u32).unwrap()),
+            Ok("OK"),
+        ),
+    ]);
+    let name = "features";
+    let mut computed = 0;
+    let config: Vec<String> = redis_cache!(&mut con, key = "config:{name}", ttl = 60, || {
+        computed += 1;
+        vec!["dark".to_string(), "beta".to_string()]
+    })
+    .unwrap();
+    assert_eq!(config, ["dark", "beta"]);
+    let cached: Vec<String> =
+        redis_cache!(&mut con, key = "config:{name}", ttl = 60, || unreachable!()).unwrap();
+    assert_eq!(cached, config);
+    assert_eq!(computed, 1);
+    let limit: u32 = redis_cache!(&mut con, key = config:limits, msgpack, || 3).unwrap();
+    assert_eq!(limit, 3);
+}
+
+#[cfg(feature = "async")]
+#[redis_cached(key = "user:{id}", ttl = 300)]
+async fn load_user_async(con: &mut common::AsyncMock, id: u64) -> RedisResult<User> {
+    if id == 0 {
+        // Returns from the body are cached like its result
+        return Ok(User {
+            id,
+            name: "root".to_string(),
+        });
+    }
+    Ok(User {
+        id,
+        name: "alice".to_string(),
+    })
+}
+
+#[cfg(feature = "async")]
+#[test]
+fn test_cached_async() {
+    use futures::executor::block_on;
+
+    let json = |id, name: &str| {
+        serde_json::to_string(&User {
+            id,
+            name: name.to_string(),
+        })
+        .unwrap()
+    };
+    let set = |id, name| {
+        redis::cmd("SET")
+            .arg(format!("user:{}", id))
+            .arg(json(id, name))
+            .arg("EX")
+            .arg(300)
+            .clone()
+    };
+    let mut con = common::AsyncMock::new(vec![
+        MockCmd::new(redis::cmd("GET").arg("user:7"), Ok(Value::Nil)),
+        MockCmd::new(set(7, "alice"), Ok("OK")),
+        MockCmd::new(redis::cmd("GET").arg("user:0"), Ok(Value::Nil)),
+        MockCmd::new(set(0, "root"), Ok("OK")),
+        MockCmd::new(redis::cmd("GET").arg("user:7"), Ok(json(7, "alice"))),
+    ]);
+    let user = block_on(load_user_async(&mut con, 7)).unwrap();
+    assert_eq!(user.name, "alice");
+    assert_eq!(block_on(load_user_async(&mut con, 0)).unwrap().name, "root");
+    assert_eq!(block_on(load_user_async(&mut con, 7)).unwrap(), user);
+}
@@ -0,0 +1,117 @@
+#![cfg(all(feature = "json", feature = "msgpack"))]
+
+use redis::Value;
+use redis_rs_macro::{redis_work_queue, WorkQueue};
+use std::ops::ControlFlow;
+
+mod common;
+
+use common::{data, StubConnection};
+
+redis_work_queue! {
+    /// Emails waiting to be sent, as `(address, subject)`
+    pub struct Emails((String, String)) = queue:emails;
+    struct Counts(u32) = queue:{name()}, visibility = 5000, msgpack;
+}
+
+fn name() -> &'static str {
+    "counts"
+}
+
+#[test]
+fn test_work_queue_script() {
+    let call = WorkQueue::new("jobs", 1000).requeue_cmd();
+    assert_eq!(redis::Script::new(call.source()).get_hash(), call.sha1());
+    let cmd = call.evalsha_cmd().get_packed_command();
+    let keys = b"$4\r\njobs\r\n$15\r\njobs:processing\r\n$11\r\njobs:leases\r\n";
+    assert!(cmd.windows(keys.len()).any(|w| w == keys));
+}
+
+#[test]
+fn test_work_queue_push() {
+    let job = ("alice@example.com".to_string(), "Welcome".to_string());
+    let mut con = StubConnection::new(vec![Value::Int(1), Value::Int(2)]);
+    Emails::push(&mut con, &job).unwrap();
+    Counts::push(&mut con, &7).unwrap();
+    let json = serde_json::to_string(&job).unwrap();
+    assert_eq!(
+        con.sent,
+        [
+            redis::cmd("LPUSH")
+                .arg("queue:emails")
+                .arg(json)
+                .get_packed_command(),
+            redis::cmd("LPUSH")
+                .arg("queue:counts")
+                .arg(rmp_serde::to_vec(&7).unwrap())
+                .get_packed_command(),
+        ]
+    );
+}
+
+#[test]
+fn test_work_queue_work() {
+    let job = serde_json::to_vec(&("bob@example.com", "Hello")).unwrap();
+    let mut con = StubConnection::new(vec![
+        // Requeue
+        Value::Int(0),
+        // Nothing arrives
+        Value::Nil,
+        data(&job),
+        Value::Int(1),
+        // The same job again, after it failed
+        data(&job),
+        Value::Int(1),
+        Value::Bulk(vec![Value::Int(1), Value::Int(1)]),
+    ]);
+    let mut attempts = 0;
+    Emails::work(&mut con, |(address, subject)| {
+        assert_eq!(
+            (address.as_str(), subject.as_str()),
+            ("bob@example.com", "Hello")
+        );
+        attempts += 1;
+        match attempts {
+            1 => Err("smtp down"),
+            _ => Ok(ControlFlow::Break(())),
+        }
+    })
+    .unwrap();
+    assert_eq!(attempts, 2);
+    assert_eq!(
+        con.names(),
+        [
+            "EVALSHA",
+            "BRPOPLPUSH",
+            "BRPOPLPUSH",
+            "ZADD",
+            "BRPOPLPUSH",
+            "ZADD",
+            "MULTI"
+        ]
+    );
+    assert_eq!(
+        con.sent[1],
+        redis::cmd("BRPOPLPUSH")
+            .arg("queue:emails")
+            .arg("queue:emails:processing")
+            .arg(1)
+            .get_packed_command()
+    );
+    let done = &con.sent[6];
+    let lrem = redis::cmd("LREM")
+        .arg("queue:emails:processing")
+        .arg(1)
+        .arg(&job)
+        .get_packed_command();
+    assert!(done.windows(lrem.len()).any(|w| w == lrem));
+    assert!(done.ends_with(b"EXEC\r\n"));
+}
+
+#[test]
+fn test_work_queue_errors() {
+    let mut con = StubConnection::new(vec![Value::Int(0), data(b"\xc1"), Value::Int(1)]);
+    let result = Counts::work(&mut con, |_| Ok::<(), ()>(()));
+    assert_eq!(result.unwrap_err().kind(), redis::ErrorKind::TypeError);
+    assert_eq!(con.names(), ["EVALSHA", "BRPOPLPUSH", "ZADD"]);
+}
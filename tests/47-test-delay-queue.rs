@@ -0,0 +1,112 @@
+#![cfg(all(feature = "json", feature = "msgpack"))]
+
+use redis::Value;
+use redis_rs_macro::{redis_delay_queue, DelayQueue};
+use std::ops::ControlFlow;
+use std::time::{Duration, SystemTime};
+
+mod common;
+
+use common::{data, StubConnection};
+
+redis_delay_queue! {
+    /// Reminders to send, as `(user, message)`
+    pub struct Reminders((u64, String)) = queue:reminders;
+    struct Retries(u32) = retries:{region()}, interval = 10, msgpack;
+}
+
+fn region() -> &'static str {
+    "eu"
+}
+
+fn reminder(user: u64) -> Vec<u8> {
+    serde_json::to_vec(&(user, "hi")).unwrap()
+}
+
+fn contains(cmd: &[u8], part: &[u8]) -> bool {
+    cmd.windows(part.len()).any(|w| w == part)
+}
+
+#[test]
+fn test_delay_queue_script() {
+    let call = DelayQueue::new("jobs", 1000).pop_cmd(SystemTime::UNIX_EPOCH, 10);
+    assert_eq!(redis::Script::new(call.source()).get_hash(), call.sha1());
+}
+
+#[test]
+fn test_delay_queue_schedule() {
+    let run_at = SystemTime::UNIX_EPOCH + Duration::from_millis(1_700_000_000_123);
+    let mut con = StubConnection::new(vec![Value::Int(1), Value::Int(1), Value::Int(1)]);
+    Reminders::schedule(&mut con, &(1, "hi".to_string()), run_at).unwrap();
+    Retries::schedule(&mut con, &3, run_at).unwrap();
+    Reminders::schedule_in(&mut con, &(2, "hi".to_string()), Duration::from_secs(60)).unwrap();
+    assert_eq!(
+        con.sent[..2],
+        [
+            redis::cmd("ZADD")
+                .arg("queue:reminders")
+                .arg(1_700_000_000_123u64)
+                .arg(reminder(1))
+                .get_packed_command(),
+            redis::cmd("ZADD")
+                .arg("retries:eu")
+                .arg(1_700_000_000_123u64)
+                .arg(rmp_serde::to_vec(&3).unwrap())
+                .get_packed_command(),
+        ]
+    );
+    assert!(contains(&con.sent[2], &reminder(2)));
+}
+
+#[test]
+fn test_delay_queue_pop_due() {
+    let mut con = StubConnection::new(vec![Value::Bulk(vec![
+        Value::Data(reminder(1)),
+        Value::Data(reminder(2)),
+    ])]);
+    let due = Reminders::pop_due(&mut con, 10).unwrap();
+    assert_eq!(due, [(1, "hi".to_string()), (2, "hi".to_string())]);
+    assert!(contains(&con.sent[0], b"$15\r\nqueue:reminders\r\n"));
+}
+
+#[test]
+fn test_delay_queue_work() {
+    let mut con = StubConnection::new(vec![
+        Value::Bulk(vec![
+            Value::Data(reminder(1)),
+            Value::Data(reminder(2)),
+            Value::Data(reminder(3)),
+        ]),
+        // The failed job is scheduled again
+        Value::Int(1),
+        // The job left when the handler stops is scheduled again
+        Value::Int(1),
+    ]);
+    let mut handled = vec![];
+    Reminders::work(&mut con, |(user, _)| {
+        handled.push(user);
+        match user {
+            1 => Err("unreachable"),
+            _ => Ok(ControlFlow::Break(())),
+        }
+    })
+    .unwrap();
+    assert_eq!(handled, [1, 2]);
+    assert_eq!(con.names(), ["EVALSHA", "ZADD", "ZADD"]);
+    assert!(contains(&con.sent[1], &reminder(1)));
+    assert!(contains(&con.sent[2], &reminder(3)));
+}
+
+#[test]
+fn test_delay_queue_errors() {
+    let mut con = StubConnection::new(vec![
+        // Nothing is due yet
+        Value::Bulk(vec![]),
+        Value::Bulk(vec![data(b"\xc1"), data(b"\x05")]),
+        Value::Int(1),
+    ]);
+    let result = Retries::work(&mut con, |_| Ok::<(), ()>(()));
+    assert_eq!(result.unwrap_err().kind(), redis::ErrorKind::TypeError);
+    assert_eq!(con.names(), ["EVALSHA", "EVALSHA", "ZADD"]);
+    assert!(contains(&con.sent[2], b"$1\r\n\x05\r\n"));
+}
@@ -0,0 +1,110 @@
+#![cfg(all(feature = "json", feature = "msgpack"))]
+
+use redis::Value;
+use redis_rs_macro::redis_priority_queue;
+use redis_test::{MockCmd, MockRedisConnection};
+use std::ops::ControlFlow;
+
+mod common;
+
+use common::data;
+
+redis_priority_queue! {
+    /// Builds to run, as `(repository, commit)`
+    pub struct Builds((String, String)) = queue:builds;
+    struct Retries(u32) = retries:{region()}, timeout = 0, msgpack;
+}
+
+fn region() -> &'static str {
+    "eu"
+}
+
+fn build(repository: &str) -> Vec<u8> {
+    serde_json::to_vec(&(repository, "4f3e5d")).unwrap()
+}
+
+#[test]
+fn test_priority_queue_push_pop() {
+    let msgpack = rmp_serde::to_vec(&7u32).unwrap();
+    let mut con = MockRedisConnection::new(vec![
+        MockCmd::new(
+            redis::cmd("ZADD")
+                .arg("queue:builds")
+                .arg(1.5)
+                .arg(build("api")),
+            Ok(1),
+        ),
+        MockCmd::new(
+            redis::cmd("ZADD").arg("retries:eu").arg(0.0).arg(&msgpack),
+            Ok(1),
+        ),
+        MockCmd::new(
+            redis::cmd("ZPOPMIN").arg("queue:builds"),
+            Ok(Value::Bulk(vec![data(build("api")), data(b"1.5")])),
+        ),
+        MockCmd::new(
+            redis::cmd("ZPOPMIN").arg("queue:builds"),
+            Ok(Value::Bulk(vec![])),
+        ),
+    ]);
+    let job = ("api".to_string(), "4f3e5d".to_string());
+    Builds::push(&mut con, &job, 1.5).unwrap();
+    Retries::push(&mut con, &7, 0.0).unwrap();
+    assert_eq!(Builds::pop(&mut con).unwrap(), Some(job));
+    assert_eq!(Builds::pop(&mut con).unwrap(), None);
+}
+
+#[test]
+fn test_priority_queue_work() {
+    let take = || redis::cmd("BZPOPMIN").arg("queue:builds").arg(5).clone();
+    let taken = |repository: &str, priority: &[u8]| {
+        Ok(Value::Bulk(vec![
+            data(b"queue:builds"),
+            data(build(repository)),
+            data(priority),
+        ]))
+    };
+    let mut con = MockRedisConnection::new(vec![
+        MockCmd::new(take(), Ok(Value::Nil)),
+        MockCmd::new(take(), taken("api", b"1")),
+        // The failed job is pushed back with its priority
+        MockCmd::new(
+            redis::cmd("ZADD")
+                .arg("queue:builds")
+                .arg(1.0)
+                .arg(build("api")),
+            Ok(1),
+        ),
+        MockCmd::new(take(), taken("web", b"2.5")),
+    ]);
+    let mut handled = vec![];
+    Builds::work(&mut con, |(repository, _)| {
+        handled.push(repository.clone());
+        match repository.as_str() {
+            "api" => Err("compiler crashed"),
+            _ => Ok(ControlFlow::Break(())),
+        }
+    })
+    .unwrap();
+    assert_eq!(handled, ["api", "web"]);
+}
+
+#[test]
+fn test_priority_queue_errors() {
+    let mut con = MockRedisConnection::new(vec![
+        MockCmd::new(
+            redis::cmd("BZPOPMIN").arg("retries:eu").arg(0),
+            Ok(Value::Bulk(vec![
+                data(b"retries:eu"),
+                data(b"\xc1"),
+                data(b"3"),
+            ])),
+        ),
+        MockCmd::new(
+            redis::cmd("ZADD").arg("retries:eu").arg(3.0).arg(b"\xc1"),
+            Ok(1),
+        ),
+    ]);
+    let result = Retries::work(&mut con, |_| Ok::<(), ()>(()));
+    assert_eq!(result.unwrap_err().kind(), redis::ErrorKind::TypeError);
+}
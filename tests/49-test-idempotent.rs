@@ -0,0 +1,131 @@
+#![cfg(all(feature = "json", feature = "msgpack"))]
+
+use redis::{RedisError, Value};
+use redis_rs_macro::{redis_idempotent, Idempotency, Idempotent};
+use redis_test::MockRedisConnection;
+
+mod common;
+
+use common::StubConnection;
+
+fn ok() -> Value {
+    Value::Okay
+}
+
+#[test]
+fn test_idempotency_commands() {
+    let idem = Idempotency::new("idem:1", 60);
+    let call = idem.complete_cmd("done");
+    assert_eq!(redis::Script::new(call.source()).get_hash(), call.sha1());
+    let cmd = idem.claim_cmd().get_packed_command();
+    assert!(cmd.starts_with(b"*6\r\n$3\r\nSET\r\n$6\r\nidem:1\r\n"));
+    assert!(cmd.ends_with(b"$2\r\nNX\r\n$2\r\nEX\r\n$2\r\n60\r\n"));
+    // Each run claims the key with its own token
+    let other = Idempotency::new("idem:1", 60)
+        .claim_cmd()
+        .get_packed_command();
+    assert_ne!(cmd, other);
+}
+
+#[test]
+fn test_idempotent() {
+    let mut con = StubConnection::new(vec![ok(), Value::Nil]);
+    let request = "a1";
+    let mut runs = 0;
+    let mut outcomes = vec![];
+    for _ in 0..2 {
+        let outcome = redis_idempotent!(&mut con, idem:{request}, ttl = 86400, {
+            runs += 1;
+            Ok::<_, RedisError>(runs)
+        });
+        outcomes.push(outcome.unwrap());
+    }
+    assert_eq!(runs, 1);
+    assert_eq!(
+        outcomes,
+        [Idempotent::Executed(1), Idempotent::Duplicate(None)]
+    );
+    assert!(outcomes[1].is_duplicate());
+    assert_eq!(con.names(), ["SET", "SET"]);
+    assert!(con.sent_contains(0, b"$7\r\nidem:a1\r\n"));
+    assert!(con.sent_contains(0, b"$5\r\n86400\r\n"));
+}
+
+#[test]
+fn test_idempotent_replay() {
+    let response = serde_json::to_string(&("paid", 42)).unwrap();
+    let mut con = StubConnection::new(vec![
+        ok(),
+        Value::Int(1),
+        Value::Nil,
+        Value::Data(response.clone().into_bytes()),
+        // Still being run by the first caller
+        Value::Nil,
+        Value::Data(b"\0pending:abc".to_vec()),
+    ]);
+    let charge = |con: &mut StubConnection| {
+        redis_idempotent!(con, idem:order:7, ttl = 60, replay, {
+            Ok::<_, RedisError>(("paid".to_string(), 42))
+        })
+    };
+    assert_eq!(
+        charge(&mut con).unwrap(),
+        Idempotent::Executed(("paid".to_string(), 42))
+    );
+    assert_eq!(
+        charge(&mut con).unwrap(),
+        Idempotent::Duplicate(Some(("paid".to_string(), 42)))
+    );
+    assert_eq!(charge(&mut con).unwrap(), Idempotent::Duplicate(None));
+    assert_eq!(con.names(), ["SET", "EVALSHA", "SET", "GET", "SET", "GET"]);
+    assert!(con.sent_contains(1, response.as_bytes()));
+}
+
+#[test]
+fn test_idempotent_msgpack() {
+    let response = rmp_serde::to_vec(&vec![1u8, 2]).unwrap();
+    let mut con = StubConnection::new(vec![Value::Nil, Value::Data(response)]);
+    let outcome: Idempotent<Vec<u8>> =
+        redis_idempotent!(&mut con, idem:batch, ttl = 60, msgpack, { Ok::<_, RedisError>(vec![]) })
+            .unwrap();
+    assert_eq!(outcome, Idempotent::Duplicate(Some(vec![1, 2])));
+}
+
+#[derive(Debug)]
+enum AppError {
+    Redis(RedisError),
+    Declined,
+}
+
+impl From<RedisError> for AppError {
+    fn from(err: RedisError) -> AppError {
+        AppError::Redis(err)
+    }
+}
+
+fn declined() -> Result<(), AppError> {
+    Err(AppError::Declined)
+}
+
+#[test]
+fn test_idempotent_release() {
+    let mut con = StubConnection::new(vec![ok(), Value::Int(0), Value::Int(1)]);
+    let outcome = redis_idempotent!(&mut con, idem:card, ttl = 60, replay, {
+        // The block uses the connection too
+        redis::cmd("INCR").arg("attempts").query::<i64>(&mut con)?;
+        declined()?;
+        Ok(1)
+    });
+    assert!(matches!(outcome, Err(AppError::Declined)));
+    assert_eq!(con.names(), ["SET", "INCR", "EVALSHA"]);
+    assert!(con.sent_contains(2, b"$9\r\nidem:card\r\n"));
+
+    // Errors of the claim are converted
+    let mut con = MockRedisConnection::new(vec![]);
+    let outcome: Result<Idempotent<()>, AppError> =
+        redis_idempotent!(&mut con, idem:card, ttl = 60, { Ok(()) });
+    let Err(AppError::Redis(err)) = outcome else {
+        panic!("expected the error of the claim");
+    };
+    assert_eq!(err.kind(), redis::ErrorKind::ClientError);
+}
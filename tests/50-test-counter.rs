@@ -0,0 +1,35 @@
+use redis::Value;
+use redis_rs_macro::{redis_counter, Counter};
+use redis_test::{MockCmd, MockRedisConnection};
+
+#[test]
+fn test_counter_script() {
+    let call = Counter::new("visits", 60).incr_cmd(1);
+    assert_eq!(redis::Script::new(call.source()).get_hash(), call.sha1());
+    let packed = String::from_utf8(call.evalsha_cmd().get_packed_command()).unwrap();
+    assert!(
+        packed.ends_with("$1\r\n1\r\n$6\r\nvisits\r\n$1\r\n1\r\n$2\r\n60\r\n"),
+        "{:?}",
+        packed
+    );
+}
+
+#[test]
+fn test_counter() {
+    let page = "home";
+    let one = Counter::new("visits:home", 3600).incr_cmd(1);
+    let many = Counter::new("visits:home", 3600).incr_cmd(5);
+    let mut conn = MockRedisConnection::new(vec![
+        MockCmd::new(one.evalsha_cmd(), Ok(Value::Int(1))),
+        MockCmd::new(many.evalsha_cmd(), Ok(Value::Int(6))),
+    ]);
+    assert_eq!(
+        redis_counter!(&mut conn, visits:{page}, ttl = 3600).unwrap(),
+        1
+    );
+    let batch = 5;
+    assert_eq!(
+        redis_counter!(&mut conn, visits:{page}, ttl = 3600, by = batch).unwrap(),
+        6
+    );
+}
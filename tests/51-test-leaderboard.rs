@@ -0,0 +1,111 @@
+use redis::Value;
+use redis_rs_macro::{redis_leaderboard, Leaderboard, Order};
+use redis_test::{MockCmd, MockRedisConnection};
+
+mod common;
+
+use common::data;
+
+redis_leaderboard! {
+    /// Points per game and season, highest first
+    pub struct Points(String) = leaderboard:{game}:{season};
+    struct Laps(u32) = laps, asc;
+}
+
+#[test]
+fn test_leaderboard_commands() {
+    let board = Leaderboard::new("board", Order::Descending);
+    assert_eq!(
+        board.top_cmd(3).get_packed_command(),
+        redis::cmd("ZREVRANGE")
+            .arg("board")
+            .arg(0)
+            .arg(2)
+            .arg("WITHSCORES")
+            .get_packed_command()
+    );
+    let board = Leaderboard::new("board", Order::Ascending);
+    assert_eq!(
+        board.rank_cmd("alice").get_packed_command(),
+        redis::cmd("ZRANK")
+            .arg("board")
+            .arg("alice")
+            .get_packed_command()
+    );
+}
+
+#[test]
+fn test_leaderboard() {
+    let mut con = MockRedisConnection::new(vec![
+        MockCmd::new(
+            redis::cmd("ZADD")
+                .arg("leaderboard:chess:2024")
+                .arg(1200.5)
+                .arg("alice"),
+            Ok(Value::Int(1)),
+        ),
+        MockCmd::new(
+            redis::cmd("ZREVRANGE")
+                .arg("leaderboard:chess:2024")
+                .arg(0)
+                .arg(1)
+                .arg("WITHSCORES"),
+            Ok(Value::Bulk(vec![
+                data("alice"),
+                data("1200.5"),
+                data("bob"),
+                data("900"),
+            ])),
+        ),
+        MockCmd::new(
+            redis::cmd("ZREVRANK")
+                .arg("leaderboard:chess:2024")
+                .arg("bob"),
+            Ok(Value::Int(1)),
+        ),
+        MockCmd::new(
+            redis::cmd("ZREVRANK")
+                .arg("leaderboard:chess:2024")
+                .arg("carol"),
+            Ok(Value::Nil),
+        ),
+        MockCmd::new(
+            redis::cmd("ZSCORE")
+                .arg("leaderboard:chess:2024")
+                .arg("bob"),
+            Ok(data("900")),
+        ),
+    ]);
+    let points = Points::new("chess", 2024);
+    points.add(&mut con, &"alice".to_string(), 1200.5).unwrap();
+    assert_eq!(
+        points.top(&mut con, 2).unwrap(),
+        [("alice".to_string(), 1200.5), ("bob".to_string(), 900.0)]
+    );
+    // No command is sent for an empty top
+    assert_eq!(points.top(&mut con, 0).unwrap(), []);
+    assert_eq!(points.rank(&mut con, &"bob".to_string()).unwrap(), Some(1));
+    assert_eq!(points.rank(&mut con, &"carol".to_string()).unwrap(), None);
+    assert_eq!(
+        points.score(&mut con, &"bob".to_string()).unwrap(),
+        Some(900.0)
+    );
+}
+
+#[test]
+fn test_leaderboard_ascending() {
+    let mut con = MockRedisConnection::new(vec![
+        MockCmd::new(
+            redis::cmd("ZRANGE")
+                .arg("laps")
+                .arg(0)
+                .arg(0)
+                .arg("WITHSCORES"),
+            Ok(Value::Bulk(vec![data("7"), data("81.2")])),
+        ),
+        MockCmd::new(redis::cmd("ZRANK").arg("laps").arg(7), Ok(Value::Int(0))),
+    ]);
+    let laps = Laps::new();
+    assert_eq!(laps.top(&mut con, 1).unwrap(), [(7, 81.2)]);
+    assert_eq!(laps.rank(&mut con, &7).unwrap(), Some(0));
+}
@@ -0,0 +1,167 @@
+use redis::{ErrorKind, RedisError};
+use redis_rs_macro::{redis_retry, RetryPolicy};
+use redis_test::{MockCmd, MockRedisConnection};
+use std::future::Future;
+use std::io;
+use std::pin::pin;
+use std::sync::Arc;
+use std::task::{Context, Wake};
+use std::thread::{self, Thread};
+use std::time::{Duration, Instant};
+
+mod common;
+
+fn dropped() -> RedisError {
+    io::Error::new(io::ErrorKind::ConnectionReset, "connection reset").into()
+}
+
+fn loading() -> RedisError {
+    (ErrorKind::BusyLoadingError, "loading").into()
+}
+
+/// Retries right away, so that the tests don't sleep
+fn no_delay(retries: u32) -> RetryPolicy {
+    RetryPolicy {
+        retries,
+        base_delay: Duration::ZERO,
+        ..RetryPolicy::default()
+    }
+}
+
+#[test]
+fn test_retry_transient() {
+    assert!(RetryPolicy::is_transient(&dropped()));
+    assert!(RetryPolicy::is_transient(&loading()));
+    let busy = redis::parse_redis_value(b"-BUSY Redis is busy running a script\r\n").unwrap_err();
+    assert!(RetryPolicy::is_transient(&busy));
+    let wrong_type = (ErrorKind::TypeError, "wrong type").into();
+    assert!(!RetryPolicy::is_transient(&wrong_type));
+}
+
+#[test]
+fn test_retry_delay() {
+    let policy = RetryPolicy {
+        retries: 10,
+        base_delay: Duration::from_millis(100),
+        max_delay: Duration::from_millis(500),
+        jitter: false,
+    };
+    let delays: Vec<_> = (0..5)
+        .map(|retry| policy.delay(retry).as_millis())
+        .collect();
+    assert_eq!(delays, [100, 200, 400, 500, 500]);
+    assert_eq!(policy.delay(100), Duration::from_millis(500));
+    let jittered = RetryPolicy {
+        jitter: true,
+        ..policy
+    };
+    for retry in 0..5 {
+        let delay = jittered.delay(retry);
+        assert!(delay >= policy.delay(retry) / 2 && delay <= policy.delay(retry));
+    }
+    assert_eq!(
+        policy.retry_delay(&dropped(), 9),
+        Some(Duration::from_millis(500))
+    );
+    assert_eq!(policy.retry_delay(&dropped(), 10), None);
+    let wrong_type = (ErrorKind::TypeError, "wrong type").into();
+    assert_eq!(policy.retry_delay(&wrong_type, 0), None);
+}
+
+#[test]
+fn test_retry() {
+    let id = 42;
+    let mut con = MockRedisConnection::new(vec![
+        MockCmd::new(
+            redis::cmd("GET").arg("user:42"),
+            Err::<redis::Value, _>(dropped()),
+        ),
+        MockCmd::new(
+            redis::cmd("GET").arg("user:42"),
+            Err::<redis::Value, _>(loading()),
+        ),
+        MockCmd::new(redis::cmd("GET").arg("user:42"), Ok("alice")),
+    ]);
+    let name = redis_retry!(no_delay(3), &mut con, GET user:{id} -> String).unwrap();
+    assert_eq!(name, "alice");
+}
+
+#[test]
+fn test_retry_gives_up() {
+    let policy = no_delay(1);
+    let mut con = MockRedisConnection::new(vec![
+        MockCmd::new(
+            redis::cmd("GET").arg("a"),
+            Err::<redis::Value, _>(loading()),
+        ),
+        MockCmd::new(
+            redis::cmd("GET").arg("a"),
+            Err::<redis::Value, _>(loading()),
+        ),
+        MockCmd::new(
+            redis::cmd("GET").arg("b"),
+            Err::<redis::Value, _>((ErrorKind::TypeError, "wrong type").into()),
+        ),
+    ]);
+    let err = redis_retry!(&policy, &mut con, GET a -> String).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::BusyLoadingError);
+    // Errors that aren't transient aren't retried
+    let err = redis_retry!(&policy, &mut con, GET b -> String).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::TypeError);
+}
+
+/// Wakes the thread that runs a future
+struct Unpark(Thread);
+
+impl Wake for Unpark {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+#[test]
+fn test_retry_sleep() {
+    let waker = Arc::new(Unpark(thread::current())).into();
+    let mut cx = Context::from_waker(&waker);
+    let start = Instant::now();
+    let mut sleep = pin!(redis_rs_macro::__private::retry::sleep(
+        Duration::from_millis(20)
+    ));
+    while sleep.as_mut().poll(&mut cx).is_pending() {
+        thread::park();
+    }
+    assert!(start.elapsed() >= Duration::from_millis(20));
+}
+
+#[cfg(feature = "async")]
+#[test]
+fn test_retry_async() {
+    use common::AsyncMock;
+    use futures::executor::block_on;
+    use redis_rs_macro::redis_retry_async;
+
+    let policy = no_delay(2);
+    let mut con = AsyncMock::new(vec![
+        MockCmd::new(
+            redis::cmd("GET").arg("a"),
+            Err::<redis::Value, _>(dropped()),
+        ),
+        MockCmd::new(redis::cmd("GET").arg("a"), Ok("1")),
+        MockCmd::new(
+            redis::cmd("GET").arg("b"),
+            Err::<redis::Value, _>(loading()),
+        ),
+        MockCmd::new(
+            redis::cmd("GET").arg("b"),
+            Err::<redis::Value, _>(loading()),
+        ),
+        MockCmd::new(
+            redis::cmd("GET").arg("b"),
+            Err::<redis::Value, _>(loading()),
+        ),
+    ]);
+    let value = block_on(redis_retry_async!(&policy, &mut con, GET a -> i64)).unwrap();
+    assert_eq!(value, 1);
+    let err = block_on(redis_retry_async!(&policy, &mut con, GET b -> i64)).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::BusyLoadingError);
+}
@@ -0,0 +1,54 @@
+use redis::{ErrorKind, RedisResult};
+use redis_rs_macro::{redis, redis_exec, redis_mock};
+use redis_test::{MockCmd, MockRedisConnection};
+
+#[test]
+fn test_mock() {
+    let id = 42;
+    let tags = ["a", "b"];
+    let nx = true;
+    let mut con = MockRedisConnection::new(vec![
+        redis_mock!(SET user:{id} alice ?[{nx} => NX] => Ok("OK")),
+        redis_mock!(SADD user:{id}:tags {..tags} => Ok(2)),
+        redis_mock!(GET user:{id} => Ok("alice")),
+    ]);
+    redis_exec!(&mut con, SET user:{id} alice ?[{nx} => NX] -> ()).unwrap();
+    let added: i64 = redis_exec!(&mut con, SADD user:{id}:tags {..tags}).unwrap();
+    assert_eq!(added, 2);
+    let name: String = redis!(GET user:{id}).query(&mut con).unwrap();
+    assert_eq!(name, "alice");
+}
+
+#[test]
+fn test_mock_matches_handwritten() {
+    let id = 7;
+    let mut con = MockRedisConnection::new(vec![redis_mock!(
+        HSET user:{id} name "Alice Smith" => Ok(1)
+    )]);
+    let handwritten = MockCmd::new(
+        redis::cmd("HSET")
+            .arg("user:7")
+            .arg("name")
+            .arg("Alice Smith"),
+        Ok(1),
+    );
+    let mut expected = MockRedisConnection::new(vec![handwritten]);
+    let cmd = redis::cmd("HSET")
+        .arg("user:7")
+        .arg("name")
+        .arg("Alice Smith")
+        .to_owned();
+    assert_eq!(
+        cmd.query::<i64>(&mut con).unwrap(),
+        cmd.query::<i64>(&mut expected).unwrap()
+    );
+}
+
+#[test]
+fn test_mock_error() {
+    let mut con = MockRedisConnection::new(vec![redis_mock!(
+        INCR counter => Err::<i64, _>((ErrorKind::TypeError, "not an integer").into())
+    )]);
+    let result: RedisResult<i64> = redis_exec!(&mut con, INCR counter);
+    assert_eq!(result.unwrap_err().kind(), ErrorKind::TypeError);
+}
@@ -0,0 +1,116 @@
+#![cfg(feature = "record")]
+
+use redis::{ErrorKind, RedisResult, Value};
+use redis_rs_macro::{redis, redis_exec, redis_mock, Recorder, Replay};
+use redis_test::MockRedisConnection;
+use std::path::PathBuf;
+
+/// A recording file that is removed at the end of the test
+struct TempFile(PathBuf);
+
+impl TempFile {
+    fn new(name: &str) -> TempFile {
+        let name = format!("redis-rs-macro-{}-{}.resp", name, std::process::id());
+        TempFile(std::env::temp_dir().join(name))
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+/// The commands of the test, which run the same against the recorder and the replay
+fn run(con: &mut dyn redis::ConnectionLike) -> RedisResult<()> {
+    let id = 42;
+    redis_exec!(con, SET user:{id} "Alice Smith" -> ())?;
+    let name: Option<String> = redis_exec!(con, GET user:{id})?;
+    assert_eq!(name.as_deref(), Some("Alice Smith"));
+    let missing: Option<String> = redis_exec!(con, GET user:{7})?;
+    assert_eq!(missing, None);
+    let (len, tags): (i64, Vec<String>) = redis::pipe()
+        .add_command(redis!(RPUSH tags a b))
+        .add_command(redis!(LRANGE tags 0 -1))
+        .query(con)?;
+    assert_eq!(len, 2);
+    assert_eq!(tags, ["a", "b"]);
+    let err = redis_exec!(con, INCR user:{id} -> i64).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::ResponseError);
+    assert_eq!(
+        err.detail(),
+        Some("value is not an integer or out of range")
+    );
+    Ok(())
+}
+
+#[test]
+fn test_record_replay() {
+    let file = TempFile::new("replay");
+    let id = 42;
+    let mock = MockRedisConnection::new(vec![
+        redis_mock!(SET user:{id} "Alice Smith" => Ok("OK")),
+        redis_mock!(GET user:{id} => Ok("Alice Smith")),
+        redis_mock!(GET user:{7} => Ok(Value::Nil)),
+        redis_mock!(INCR user:{id} => Err::<i64, _>(redis::parse_redis_value(
+            b"-ERR value is not an integer or out of range\r\n"
+        ).unwrap_err())),
+    ]);
+    // The mock doesn't take pipelines, so the recorder wraps one that replies to both
+    let mut recorder = Recorder::create(Pipelined(mock), &file.0).unwrap();
+    run(&mut recorder).unwrap();
+    drop(recorder);
+
+    let mut replay = Replay::open(&file.0).unwrap();
+    assert_eq!(replay.remaining(), 5);
+    run(&mut replay).unwrap();
+    assert_eq!(replay.remaining(), 0);
+    let err = redis_exec!(&mut replay, GET user:{id} -> String).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::ClientError);
+}
+
+#[test]
+fn test_replay_mismatch() {
+    let file = TempFile::new("mismatch");
+    let request = redis!(GET a).get_packed_command();
+    let mut recording = format!("*3\r\n${}\r\n", request.len()).into_bytes();
+    recording.extend_from_slice(&request);
+    recording.extend_from_slice(b"\r\n+ok\r\n$1\r\n1\r\n");
+    std::fs::write(&file.0, &recording).unwrap();
+    let mut replay = Replay::open(&file.0).unwrap();
+    let err = redis_exec!(&mut replay, GET b -> String).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::ClientError);
+    assert!(err.to_string().contains("doesn't match"), "{}", err);
+
+    std::fs::write(&file.0, &recording[..recording.len() - 4]).unwrap();
+    let Err(err) = Replay::open(&file.0) else {
+        panic!("expected a malformed recording");
+    };
+    assert!(err.to_string().contains("malformed"), "{}", err);
+}
+
+/// Replies to pipelines with `[2, ["a", "b"]]`, and passes other commands to the mock
+struct Pipelined(MockRedisConnection);
+
+impl redis::ConnectionLike for Pipelined {
+    fn req_packed_command(&mut self, cmd: &[u8]) -> RedisResult<Value> {
+        self.0.req_packed_command(cmd)
+    }
+
+    fn req_packed_commands(&mut self, _: &[u8], _: usize, _: usize) -> RedisResult<Vec<Value>> {
+        let tags = vec![Value::Data(b"a".to_vec()), Value::Data(b"b".to_vec())];
+        Ok(vec![Value::Int(2), Value::Bulk(tags)])
+    }
+
+    fn get_db(&self) -> i64 {
+        0
+    }
+
+    fn check_connection(&mut self) -> bool {
+        true
+    }
+
+    fn is_open(&self) -> bool {
+        true
+    }
+}
@@ -0,0 +1,34 @@
+#![cfg(feature = "test-server")]
+
+use redis_rs_macro::{redis_exec, redis_integration_test, TestServer};
+
+#[test]
+fn test_server_missing() {
+    let Err(err) = TestServer::start_program("/nonexistent/redis-server") else {
+        panic!("expected the server not to start");
+    };
+    assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+    assert!(
+        err.to_string().contains("/nonexistent/redis-server"),
+        "{}",
+        err
+    );
+}
+
+#[redis_integration_test]
+#[ignore = "needs redis-server"]
+fn test_integration(con: &mut redis::Connection) -> redis::RedisResult<()> {
+    let id = 42;
+    redis_exec!(con, SET user:{id} alice -> ())?;
+    let name: String = redis_exec!(con, GET user:{id})?;
+    assert_eq!(name, "alice");
+    Ok(())
+}
+
+#[redis_integration_test]
+#[ignore = "needs redis-server"]
+fn test_integration_isolated(con: &mut dyn redis::ConnectionLike) {
+    // Each test has a server of its own, so the key of the other test isn't there
+    let name: Option<String> = redis_exec!(con, GET user:42).unwrap();
+    assert_eq!(name, None);
+}
@@ -0,0 +1,22 @@
+use redis_rs_macro::redis_keyslot;
+
+const EU_SLOT: u16 = redis_keyslot!("jobs:{eu}:pending");
+
+/// The shard of a slot, matched against slots known at build time
+fn shard(slot: u16) -> &'static str {
+    match slot {
+        EU_SLOT => "eu",
+        redis_keyslot!("jobs:{us}:pending") => "us",
+        _ => "other",
+    }
+}
+
+#[test]
+fn test_keyslot() {
+    assert_eq!(EU_SLOT, 6893);
+    assert_eq!(redis_keyslot!("{eu}"), EU_SLOT);
+    assert_eq!(redis_keyslot!(b"jobs:{eu}:done"), EU_SLOT);
+    assert_eq!(redis_keyslot!("123456789"), 0x31c3);
+    assert_eq!(shard(redis_keyslot!("{us}")), "us");
+    assert_eq!(shard(redis_keyslot!("{ap}")), "other");
+}
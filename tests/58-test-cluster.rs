@@ -0,0 +1,43 @@
+#![cfg(all(feature = "cluster", not(feature = "key-prefix")))]
+
+use redis_rs_macro::{redis, redis_keyslot};
+
+#[test]
+fn test_cluster_same_slot() {
+    let id = 42;
+    let cmd = redis!(MSET "{user}:name" alice "{user}:age" 30);
+    assert_eq!(
+        cmd.get_packed_command(),
+        redis::cmd("MSET")
+            .arg("{user}:name")
+            .arg("alice")
+            .arg("{user}:age")
+            .arg(30)
+            .get_packed_command()
+    );
+    redis!(SUNIONSTORE "{tags}:all" "{tags}:"{id} "{tags}:new");
+    // Keys that are only known at runtime aren't checked
+    let (a, b) = ("a", "b");
+    redis!(RENAME {a} {b});
+    assert_eq!(redis_keyslot!("{user}:name"), redis_keyslot!("{user}:age"));
+}
+
+#[test]
+fn test_cluster_bindings() {
+    let mut calls = 0;
+    let mut next = || {
+        calls += 1;
+        calls
+    };
+    let cmd = redis!(MSET "{m}:a" :n "{m}:b" :n; n = next());
+    assert_eq!(
+        cmd.get_packed_command(),
+        redis::cmd("MSET")
+            .arg("{m}:a")
+            .arg(1)
+            .arg("{m}:b")
+            .arg(1)
+            .get_packed_command()
+    );
+    assert_eq!(calls, 1);
+}
@@ -0,0 +1,59 @@
+use redis_rs_macro::{key_slot, redis_keyslot, redis_routed, Route};
+
+#[test]
+fn test_routed_literal_keys() {
+    let id = 42;
+    let (cmd, route) = redis_routed!(HSET "{user}:"{id} name alice);
+    assert_eq!(
+        cmd.get_packed_command(),
+        redis::cmd("HSET")
+            .arg("{user}:42")
+            .arg("name")
+            .arg("alice")
+            .get_packed_command()
+    );
+    if cfg!(feature = "key-prefix") {
+        return;
+    }
+    assert_eq!(route, Route::Slot(redis_keyslot!("{user}")));
+    let (_, route) = redis_routed!(GET foo);
+    assert_eq!(route, Route::Slot(12182));
+    assert_eq!(route.slot(), Some(12182));
+}
+
+#[test]
+fn test_routed_runtime_keys() {
+    let id = 42;
+    let (cmd, route) = redis_routed!(GET user:{id});
+    assert_eq!(route, Route::for_arg(&cmd, 1));
+    if !cfg!(feature = "key-prefix") {
+        assert_eq!(route, Route::for_key(b"user:42"));
+    }
+    let keys: Vec<String> = vec![];
+    let (_, route) = redis_routed!(DEL { ..keys });
+    assert_eq!(route, Route::Random);
+    let keys = ["{a}1", "{a}2"];
+    let (_, route) = redis_routed!(MGET { ..keys });
+    if !cfg!(feature = "key-prefix") {
+        assert_eq!(route, Route::Slot(key_slot(b"a")));
+    }
+    let (_, route) = redis_routed!(XREADGROUP GROUP g c STREAMS {keys[0]} ">");
+    assert!(route.slot().is_some());
+}
+
+#[test]
+fn test_routed_keyless() {
+    let (_, route) = redis_routed!(PING);
+    assert_eq!(route, Route::Random);
+    let (_, route) = redis_routed!(KEYS "user:*");
+    assert_eq!(route, Route::AllPrimaries);
+    assert_eq!(route.slot(), None);
+}
+
+#[test]
+fn test_key_slot() {
+    assert_eq!(key_slot(b"foo"), redis_keyslot!("foo"));
+    assert_eq!(key_slot(b"{}x"), redis_keyslot!("{}x"));
+    assert_eq!(key_slot(b"a{b}c"), key_slot(b"b"));
+    assert_eq!(key_slot(b""), 0);
+}
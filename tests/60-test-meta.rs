@@ -0,0 +1,46 @@
+use redis_rs_macro::{redis_meta, CommandMeta};
+
+const GET: CommandMeta = redis_meta!(GET);
+
+#[test]
+fn test_meta() {
+    assert_eq!(
+        GET,
+        CommandMeta {
+            name: "GET",
+            arity: 2,
+            write: false,
+            readonly: true,
+            first_key: 1,
+            last_key: 1,
+            step: 1,
+            movable_keys: false,
+            since: "1.0.0",
+        }
+    );
+    let mset = redis_meta!(mset);
+    assert_eq!((mset.first_key, mset.last_key, mset.step), (1, -1, 2));
+    assert!(mset.write);
+    let blpop = redis_meta!(BLPOP);
+    assert_eq!(blpop.last_key, -2);
+}
+
+#[test]
+fn test_meta_subcommands() {
+    let create = redis_meta!(XGROUP CREATE);
+    assert_eq!(create.name, "XGROUP CREATE");
+    assert_eq!((create.arity, create.first_key), (-5, 2));
+    let config = redis_meta!(CONFIG SET);
+    assert!(!config.write && !config.readonly);
+    assert_eq!(config.first_key, 0);
+}
+
+#[test]
+fn test_meta_movable_keys() {
+    let eval = redis_meta!(EVAL);
+    assert!(eval.movable_keys);
+    assert_eq!((eval.first_key, eval.last_key, eval.step), (0, 0, 0));
+    let xread = redis_meta!(XREAD);
+    assert!(xread.movable_keys && xread.readonly);
+    assert!(redis_meta!(ZINTERSTORE).movable_keys);
+}
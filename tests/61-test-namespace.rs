@@ -0,0 +1,137 @@
+use redis_rs_macro::{redis, redis_batch, redis_exec, redis_namespace, redis_pipe, redis_routed};
+
+mod common;
+
+use common::StubConnection;
+
+#[redis_namespace("billing:")]
+fn balance(user: u64) -> redis::Cmd {
+    redis!(INCRBY balance:{user} 10)
+}
+
+#[redis_namespace("billing:")]
+mod invoices {
+    use redis_rs_macro::{redis, redis_namespace};
+
+    pub fn get(key: &[u8]) -> redis::Cmd {
+        redis!(GET { key })
+    }
+
+    #[redis_namespace("2024:")]
+    pub fn paid(ids: &[&str], last: Option<&str>) -> redis::Cmd {
+        redis!(MGET paid {..ids} {last?})
+    }
+
+    pub fn publish(message: &str) -> redis::Cmd {
+        redis!(PUBLISH invoices {message})
+    }
+}
+
+#[test]
+fn test_namespace_keys() {
+    assert_eq!(
+        balance(7).get_packed_command(),
+        redis::cmd("INCRBY")
+            .arg("billing:balance:7")
+            .arg(10)
+            .get_packed_command()
+    );
+    assert_eq!(
+        invoices::get(b"\xff").get_packed_command(),
+        redis::cmd("GET")
+            .arg(&b"billing:\xff"[..])
+            .get_packed_command()
+    );
+    assert_eq!(
+        invoices::paid(&["1", "2"], Some("3")).get_packed_command(),
+        redis::cmd("MGET")
+            .arg("billing:2024:paid")
+            .arg("billing:2024:1")
+            .arg("billing:2024:2")
+            .arg("billing:2024:3")
+            .get_packed_command()
+    );
+    // Channels aren't keys
+    assert_eq!(
+        invoices::publish("hi").get_packed_command(),
+        redis::cmd("PUBLISH")
+            .arg("invoices")
+            .arg("hi")
+            .get_packed_command()
+    );
+}
+
+#[test]
+#[redis_namespace("jobs:")]
+fn test_namespace_pipe() {
+    let pairs = [("a", 1), ("b", 2)];
+    let ids = [1, 2];
+    let pipe = redis_pipe! {
+        MSET {..pairs}
+        for id in ids {
+            DEL job:{id}
+        }
+    };
+    let mut expected = redis::pipe();
+    expected
+        .cmd("MSET")
+        .arg("jobs:a")
+        .arg(1)
+        .arg("jobs:b")
+        .arg(2)
+        .cmd("DEL")
+        .arg("jobs:job:1")
+        .cmd("DEL")
+        .arg("jobs:job:2");
+    assert_eq!(pipe.get_packed_pipeline(), expected.get_packed_pipeline());
+    let batch = redis_batch! {
+        SET a 1
+    };
+    assert_eq!(
+        batch[0].get_packed_command(),
+        redis::cmd("SET").arg("jobs:a").arg(1).get_packed_command()
+    );
+}
+
+#[test]
+#[redis_namespace("jobs:")]
+fn test_namespace_exec() {
+    let mut con = StubConnection::default();
+    let _: () = redis_exec!(&mut con, SET a 1).unwrap();
+    assert_eq!(
+        con.sent,
+        [redis::cmd("SET").arg("jobs:a").arg(1).get_packed_command()]
+    );
+    let (cmd, route) = redis_routed!(GET a);
+    assert_eq!(route, redis_rs_macro::Route::for_key(b"jobs:a"));
+    assert_eq!(
+        cmd.get_packed_command(),
+        redis::cmd("GET").arg("jobs:a").get_packed_command()
+    );
+}
+
+#[test]
+#[redis_namespace("jobs:")]
+fn test_namespace_opt_out() {
+    // The keys of commands that aren't in the command table are prefixed by hand
+    assert_eq!(
+        redis!(@no_namespace MODULE.ADD jobs:a 1).get_packed_command(),
+        redis::cmd("MODULE.ADD")
+            .arg("jobs:a")
+            .arg(1)
+            .get_packed_command()
+    );
+    let pipe = redis_pipe! {
+        SET a 1
+        @no_namespace MODULE.ADD jobs:a 1
+    };
+    let mut expected = redis::pipe();
+    expected
+        .cmd("SET")
+        .arg("jobs:a")
+        .arg(1)
+        .cmd("MODULE.ADD")
+        .arg("jobs:a")
+        .arg(1);
+    assert_eq!(pipe.get_packed_pipeline(), expected.get_packed_pipeline());
+}
@@ -0,0 +1,67 @@
+use redis_rs_macro::{redis, redis_namespace, redis_pipe, redis_routed, redis_tenant, Route};
+
+struct Store {
+    tenant_id: u32,
+}
+
+impl Store {
+    #[redis_tenant(prefix = format!("t{}:", self.tenant_id))]
+    fn copy(&self, from: &str, to: &str) -> redis::Cmd {
+        redis!(COPY {from} {to} REPLACE)
+    }
+
+    #[redis_tenant(prefix = format!("t{}:", self.tenant_id))]
+    fn counters(&self, names: &[&str]) -> redis::Pipeline {
+        redis_pipe! {
+            for name in names {
+                INCR counter:{name}
+            }
+            PUBLISH counters {names.len()}
+        }
+    }
+
+    #[redis_tenant(prefix = format!("t{}:", self.tenant_id))]
+    #[redis_namespace("cache:")]
+    fn cached(&self, key: &str) -> (redis::Cmd, Route) {
+        redis_routed!(GET { key })
+    }
+}
+
+#[test]
+fn test_tenant_prefix() {
+    let store = Store { tenant_id: 7 };
+    assert_eq!(
+        store.copy("a", "b").get_packed_command(),
+        redis::cmd("COPY")
+            .arg("t7:a")
+            .arg("t7:b")
+            .arg("REPLACE")
+            .get_packed_command()
+    );
+    let mut expected = redis::pipe();
+    expected
+        .cmd("INCR")
+        .arg("t7:counter:x")
+        .cmd("INCR")
+        .arg("t7:counter:y")
+        .cmd("PUBLISH")
+        .arg("counters")
+        .arg(2);
+    assert_eq!(
+        store.counters(&["x", "y"]).get_packed_pipeline(),
+        expected.get_packed_pipeline()
+    );
+}
+
+#[test]
+fn test_tenant_with_namespace() {
+    let store = Store { tenant_id: 7 };
+    let (cmd, route) = store.cached("k");
+    assert_eq!(
+        cmd.get_packed_command(),
+        redis::cmd("GET").arg("t7:cache:k").get_packed_command()
+    );
+    if !cfg!(feature = "key-prefix") {
+        assert_eq!(route, Route::for_key(b"t7:cache:k"));
+    }
+}
@@ -0,0 +1,56 @@
+use redis_rs_macro::{redis, redis_pipe, redis_readonly, redis_routed};
+
+#[redis_readonly]
+mod reads {
+    use redis_rs_macro::{redis, redis_pipe};
+
+    pub fn profile(id: u64) -> redis::Pipeline {
+        redis_pipe! {
+            HGETALL user:{id}
+            if id > 0 {
+                SMEMBERS user:{id}:roles
+            }
+        }
+    }
+
+    pub fn lag() -> redis::Cmd {
+        redis!(INFO replication)
+    }
+}
+
+#[redis_readonly]
+fn first_entry(stream: &str) -> (redis::Cmd, redis_rs_macro::Route) {
+    redis_routed!(XRANGE {stream} - + COUNT 1)
+}
+
+#[test]
+fn test_readonly_reads() {
+    let mut expected = redis::pipe();
+    expected
+        .cmd("HGETALL")
+        .arg("user:1")
+        .cmd("SMEMBERS")
+        .arg("user:1:roles");
+    assert_eq!(
+        reads::profile(1).get_packed_pipeline(),
+        expected.get_packed_pipeline()
+    );
+    assert_eq!(
+        reads::lag().get_packed_command(),
+        redis::cmd("INFO").arg("replication").get_packed_command()
+    );
+    let (cmd, _) = first_entry("events");
+    assert_eq!(
+        cmd.get_packed_command(),
+        redis::cmd("XRANGE")
+            .arg("events")
+            .arg("-")
+            .arg("+")
+            .arg("COUNT")
+            .arg(1)
+            .get_packed_command()
+    );
+    // Writes outside of the attribute are untouched
+    let _ = redis!(SET a 1);
+    let _ = redis_pipe!(DEL a);
+}
@@ -0,0 +1,81 @@
+use redis::Value;
+use redis_rs_macro::{redis_slowlog, SlowlogEntry};
+use redis_test::{MockCmd, MockRedisConnection};
+use std::time::{Duration, UNIX_EPOCH};
+
+mod common;
+
+use common::data;
+
+#[test]
+fn test_slowlog_query() {
+    let reply = Value::Bulk(vec![
+        Value::Bulk(vec![
+            Value::Int(14),
+            Value::Int(1700000000),
+            Value::Int(12500),
+            Value::Bulk(vec![data("KEYS"), data("*")]),
+            data("127.0.0.1:58217"),
+            data("worker"),
+        ]),
+        // Before Redis 4.0, entries have no client
+        Value::Bulk(vec![
+            Value::Int(13),
+            Value::Int(1699999999),
+            Value::Int(10),
+            Value::Bulk(vec![data("GET"), Value::Data(b"\xffa".to_vec())]),
+        ]),
+    ]);
+    let mut con = MockRedisConnection::new(vec![MockCmd::new(
+        redis::cmd("SLOWLOG").arg("GET").arg(2),
+        Ok(reply),
+    )]);
+    let count = 2;
+    let entries = redis_slowlog!(count).query(&mut con).unwrap();
+    assert_eq!(
+        entries,
+        [
+            SlowlogEntry {
+                id: 14,
+                timestamp: UNIX_EPOCH + Duration::from_secs(1700000000),
+                duration: Duration::from_micros(12500),
+                args: vec!["KEYS".to_string(), "*".to_string()],
+                client_addr: Some("127.0.0.1:58217".to_string()),
+                client_name: Some("worker".to_string()),
+            },
+            SlowlogEntry {
+                id: 13,
+                timestamp: UNIX_EPOCH + Duration::from_secs(1699999999),
+                duration: Duration::from_micros(10),
+                args: vec!["GET".to_string(), "\u{fffd}a".to_string()],
+                client_addr: None,
+                client_name: None,
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_slowlog_commands() {
+    assert_eq!(
+        redis_slowlog!().cmd().get_packed_command(),
+        redis::cmd("SLOWLOG").arg("GET").get_packed_command()
+    );
+    assert_eq!(
+        redis_slowlog!(-1).into_cmd().get_packed_command(),
+        redis::cmd("SLOWLOG")
+            .arg("GET")
+            .arg(-1)
+            .get_packed_command()
+    );
+}
+
+#[test]
+fn test_slowlog_errors() {
+    let mut con = MockRedisConnection::new(vec![MockCmd::new(
+        redis::cmd("SLOWLOG").arg("GET"),
+        Ok(Value::Bulk(vec![Value::Bulk(vec![Value::Int(1)])])),
+    )]);
+    let err = redis_slowlog!().query(&mut con).unwrap_err();
+    assert_eq!(err.kind(), redis::ErrorKind::TypeError);
+}
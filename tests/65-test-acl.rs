@@ -0,0 +1,25 @@
+use redis_rs_macro::redis;
+
+#[test]
+fn test_acl_setuser() {
+    let password = "s3cret";
+    let cmd = redis!(ACL SETUSER alice reset on >{password} ~cache:* %R~logs:* &news:* -@all +@read +client|setname);
+    assert_eq!(
+        cmd.get_packed_command(),
+        redis::cmd("ACL")
+            .arg("SETUSER")
+            .arg("alice")
+            .arg("reset")
+            .arg("on")
+            .arg(">s3cret")
+            .arg("~cache:*")
+            .arg("%R~logs:*")
+            .arg("&news:*")
+            .arg("-@all")
+            .arg("+@read")
+            .arg("+client|setname")
+            .get_packed_command()
+    );
+    let cmd = redis!(ACL SETUSER bob "(~app:* +get)" on "#5e884898da28047151d0e56f8dc6292773603d0d6aabbdd62a11ef721d1542d8");
+    assert_eq!(cmd.args_iter().count(), 6);
+}
@@ -0,0 +1,30 @@
+use redis_rs_macro::redis;
+
+#[test]
+fn test_client_kill_filters() {
+    let id = 42;
+    let cmd = redis!(CLIENT KILL ID {id} TYPE normal USER alice SKIPME yes MAXAGE 3600);
+    assert_eq!(
+        cmd.get_packed_command(),
+        redis::cmd("CLIENT")
+            .arg("KILL")
+            .arg("ID")
+            .arg(42)
+            .arg("TYPE")
+            .arg("normal")
+            .arg("USER")
+            .arg("alice")
+            .arg("SKIPME")
+            .arg("yes")
+            .arg("MAXAGE")
+            .arg(3600)
+            .get_packed_command()
+    );
+    assert_eq!(
+        redis!(CLIENT KILL "127.0.0.1:52340").get_packed_command(),
+        redis::cmd("CLIENT")
+            .arg("KILL")
+            .arg("127.0.0.1:52340")
+            .get_packed_command()
+    );
+}
@@ -0,0 +1,140 @@
+use redis::ErrorKind;
+use redis_rs_macro::{redis_sentinel, RetryPolicy, Sentinel};
+use std::collections::VecDeque;
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+/// A server that answers the commands it receives, over any number of connections, with `replies`
+/// in order, apart from the `CLIENT SETINFO` of new connections. It stops once they are used up, and returns the commands it received.
+fn serve(replies: &[&str]) -> (String, JoinHandle<Vec<Vec<String>>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let mut replies: VecDeque<String> = replies.iter().map(|reply| reply.to_string()).collect();
+    let handle = thread::spawn(move || {
+        let mut received = vec![];
+        while !replies.is_empty() {
+            let (stream, _) = listener.accept().unwrap();
+            let mut writer = stream.try_clone().unwrap();
+            let mut reader = BufReader::new(stream);
+            while let Some(command) = read_command(&mut reader) {
+                // The client names itself when it connects
+                if command[0] == "CLIENT" {
+                    writer.write_all(b"+OK\r\n").unwrap();
+                    continue;
+                }
+                received.push(command);
+                let reply = replies.pop_front().unwrap();
+                writer.write_all(reply.as_bytes()).unwrap();
+                if replies.is_empty() {
+                    break;
+                }
+            }
+        }
+        received
+    });
+    (format!("redis://127.0.0.1:{}", port), handle)
+}
+
+fn read_command(reader: &mut impl BufRead) -> Option<Vec<String>> {
+    let mut line = String::new();
+    if reader.read_line(&mut line).ok()? == 0 {
+        return None;
+    }
+    let count: usize = line.trim_end().strip_prefix('*')?.parse().ok()?;
+    let mut args = vec![];
+    for _ in 0..count {
+        line.clear();
+        reader.read_line(&mut line).ok()?;
+        let len: usize = line.trim_end().strip_prefix('$')?.parse().ok()?;
+        let mut arg = vec![0; len + 2];
+        reader.read_exact(&mut arg).ok()?;
+        arg.truncate(len);
+        args.push(String::from_utf8(arg).ok()?);
+    }
+    Some(args)
+}
+
+fn addr_reply(url: &str) -> String {
+    let port = url.rsplit(':').next().unwrap();
+    format!("*2\r\n$9\r\n127.0.0.1\r\n${}\r\n{}\r\n", port.len(), port)
+}
+
+/// Retries right away, so that the tests don't sleep
+fn no_delay(retries: u32) -> RetryPolicy {
+    RetryPolicy {
+        retries,
+        base_delay: Duration::ZERO,
+        ..RetryPolicy::default()
+    }
+}
+
+#[test]
+fn test_sentinel_failover() {
+    let (old_master, old_handle) =
+        serve(&["-READONLY You can't write against a read only replica.\r\n"]);
+    let (new_master, new_handle) = serve(&[":1\r\n", ":2\r\n"]);
+    let (sentinel_url, sentinel_handle) =
+        serve(&[&addr_reply(&old_master), &addr_reply(&new_master)]);
+    let mut sentinel = Sentinel::new([sentinel_url], "cache")
+        .unwrap()
+        .with_policy(no_delay(3));
+    let id = 42;
+    let visits: u64 = redis_sentinel!(&mut sentinel, INCR user:{id}:visits).unwrap();
+    assert_eq!(visits, 1);
+    // The connection to the new master is kept
+    let visits = redis_sentinel!(&mut sentinel, INCR user:{id}:visits -> u64).unwrap();
+    assert_eq!(visits, 2);
+
+    let lookup = vec!["SENTINEL", "GET-MASTER-ADDR-BY-NAME", "cache"];
+    assert_eq!(
+        sentinel_handle.join().unwrap(),
+        vec![lookup.clone(), lookup]
+    );
+    let incr = vec!["INCR", "user:42:visits"];
+    assert_eq!(old_handle.join().unwrap(), vec![incr.clone()]);
+    assert_eq!(new_handle.join().unwrap(), vec![incr.clone(), incr]);
+}
+
+#[test]
+fn test_sentinel_unreachable() {
+    // A port that nothing listens on
+    let down = {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        format!("redis://{}", listener.local_addr().unwrap())
+    };
+    let (master, master_handle) = serve(&["$5\r\nalice\r\n"]);
+    let (sentinel_url, sentinel_handle) = serve(&[&addr_reply(&master)]);
+    let mut sentinel = Sentinel::new([down.as_str(), sentinel_url.as_str()], "users").unwrap();
+    let name: Option<String> = redis_sentinel!(&mut sentinel, GET user:1:name).unwrap();
+    assert_eq!(name.as_deref(), Some("alice"));
+    assert_eq!(sentinel_handle.join().unwrap().len(), 1);
+    assert_eq!(
+        master_handle.join().unwrap(),
+        vec![vec!["GET", "user:1:name"]]
+    );
+}
+
+#[test]
+fn test_sentinel_errors() {
+    assert!(Sentinel::new(Vec::<String>::new(), "cache").is_err());
+
+    let (sentinel_url, _) = serve(&["*-1\r\n"]);
+    let mut sentinel = Sentinel::new([sentinel_url], "missing")
+        .unwrap()
+        .with_policy(no_delay(0));
+    let err = redis_sentinel!(&mut sentinel, GET k -> Option<String>).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::MasterDown);
+
+    // Errors that don't come from a failover are returned right away
+    let (master, _) =
+        serve(&["-WRONGTYPE Operation against a key holding the wrong kind of value\r\n"]);
+    let (sentinel_url, _) = serve(&[&addr_reply(&master)]);
+    let mut sentinel = Sentinel::new([sentinel_url], "cache").unwrap();
+    let err = redis_sentinel!(&mut sentinel, INCR name -> u64).unwrap_err();
+    assert_eq!(err.code(), Some("WRONGTYPE"));
+    assert!(Sentinel::is_failover(
+        &(ErrorKind::ReadOnly, "read only").into()
+    ));
+}
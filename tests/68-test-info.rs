@@ -0,0 +1,128 @@
+use redis::{ErrorKind, Value};
+use redis_rs_macro::{
+    redis_info, ClientsInfo, DbKeyspace, MemoryInfo, ReplicaInfo, ReplicationInfo,
+};
+use redis_test::{MockCmd, MockRedisConnection};
+use std::time::Duration;
+
+fn mock(section: &str, reply: &str) -> MockRedisConnection {
+    MockRedisConnection::new(vec![MockCmd::new(
+        redis::cmd("INFO").arg(section),
+        Ok(Value::Data(reply.as_bytes().to_vec())),
+    )])
+}
+
+#[test]
+fn test_info_memory() {
+    let reply = "# Memory\r\nused_memory:1048576\r\nused_memory_human:1.00M\r\n\
+                 used_memory_rss:2097152\r\nused_memory_peak:3145728\r\nused_memory_lua:31744\r\n\
+                 maxmemory:0\r\nmaxmemory_policy:noeviction\r\nmem_fragmentation_ratio:2.00\r\n";
+    let memory = redis_info!(memory)
+        .query(&mut mock("memory", reply))
+        .unwrap();
+    assert_eq!(
+        memory,
+        MemoryInfo {
+            used_memory: 1048576,
+            used_memory_rss: 2097152,
+            used_memory_peak: 3145728,
+            used_memory_lua: 31744,
+            maxmemory: 0,
+            maxmemory_policy: Some("noeviction".to_owned()),
+            mem_fragmentation_ratio: 2.0,
+        }
+    );
+}
+
+#[test]
+fn test_info_replication() {
+    let reply = "# Replication\r\nrole:master\r\nconnected_slaves:2\r\n\
+                 slave0:ip=10.0.0.2,port=6379,state=online,offset=4242,lag=0\r\n\
+                 slave1:ip=10.0.0.3,port=6380,state=wait_bgsave,offset=0,lag=1\r\n\
+                 master_repl_offset:4242\r\n";
+    let replication = redis_info!(replication)
+        .query(&mut mock("replication", reply))
+        .unwrap();
+    assert!(replication.is_master());
+    assert_eq!(replication.connected_replicas, 2);
+    assert_eq!(
+        replication.replicas,
+        [
+            ReplicaInfo {
+                ip: "10.0.0.2".to_owned(),
+                port: 6379,
+                state: "online".to_owned(),
+                offset: 4242,
+                lag: 0,
+            },
+            ReplicaInfo {
+                ip: "10.0.0.3".to_owned(),
+                port: 6380,
+                state: "wait_bgsave".to_owned(),
+                offset: 0,
+                lag: 1,
+            },
+        ]
+    );
+
+    let reply = "# Replication\r\nrole:slave\r\nmaster_host:10.0.0.1\r\nmaster_port:6379\r\n\
+                 master_link_status:down\r\nconnected_slaves:0\r\nmaster_repl_offset:17\r\n";
+    let replication = redis_info!(REPLICATION)
+        .query(&mut mock("replication", reply))
+        .unwrap();
+    assert_eq!(
+        replication,
+        ReplicationInfo {
+            role: "slave".to_owned(),
+            connected_replicas: 0,
+            master_repl_offset: 17,
+            master_host: Some("10.0.0.1".to_owned()),
+            master_port: Some(6379),
+            master_link_up: Some(false),
+            replicas: vec![],
+        }
+    );
+}
+
+#[test]
+fn test_info_clients_and_keyspace() {
+    // Servers before Redis 6.0 don't send tracking_clients
+    let reply = "# Clients\r\nconnected_clients:12\r\nblocked_clients:3\r\nmaxclients:10000\r\n";
+    let clients = redis_info!(clients)
+        .query(&mut mock("clients", reply))
+        .unwrap();
+    assert_eq!(
+        clients,
+        ClientsInfo {
+            connected_clients: 12,
+            blocked_clients: 3,
+            tracking_clients: 0,
+            maxclients: 10000,
+        }
+    );
+
+    let reply = "# Keyspace\r\ndb0:keys=120,expires=4,avg_ttl=60000\r\ndb3:keys=1,expires=0,\
+                 avg_ttl=0\r\n";
+    let keyspace = redis_info!(keyspace)
+        .query(&mut mock("keyspace", reply))
+        .unwrap();
+    assert_eq!(keyspace.dbs.keys().copied().collect::<Vec<_>>(), [0, 3]);
+    assert_eq!(
+        keyspace.dbs[&0],
+        DbKeyspace {
+            keys: 120,
+            expires: 4,
+            avg_ttl: Duration::from_secs(60),
+        }
+    );
+}
+
+#[test]
+fn test_info_errors() {
+    let reply = "# Memory\r\nused_memory:lots\r\n";
+    let err = redis_info!(memory)
+        .query(&mut mock("memory", reply))
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::TypeError);
+    assert!(err.to_string().contains("used_memory:lots"), "{}", err);
+}
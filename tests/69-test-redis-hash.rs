@@ -0,0 +1,102 @@
+use redis::{ErrorKind, FromRedisValue, Value};
+use redis_rs_macro::{redis, RedisHash};
+use redis_test::{MockCmd, MockRedisConnection};
+
+mod common;
+
+use common::data;
+
+#[derive(RedisHash, Debug, PartialEq)]
+struct User {
+    name: String,
+    #[redis(rename = "mail")]
+    email: Option<String>,
+    #[redis(default)]
+    visits: u64,
+    #[redis(skip)]
+    session: Option<String>,
+}
+
+#[test]
+fn test_redis_hash_write() {
+    let id = 42;
+    let mut con = MockRedisConnection::new(vec![
+        MockCmd::new(
+            redis::cmd("HSET")
+                .arg("user:42")
+                .arg("name")
+                .arg("alice")
+                .arg("mail")
+                .arg("alice@example.com")
+                .arg("visits")
+                .arg(3),
+            Ok(3),
+        ),
+        MockCmd::new(
+            redis::cmd("HSET")
+                .arg("user:42")
+                .arg("name")
+                .arg("alice")
+                .arg("visits")
+                .arg(3),
+            Ok(0),
+        ),
+    ]);
+    let user = User {
+        name: "alice".to_owned(),
+        email: Some("alice@example.com".to_owned()),
+        visits: 3,
+        session: Some("abc".to_owned()),
+    };
+    let added: u64 = redis!(HSET user:{id} {&user}).query(&mut con).unwrap();
+    assert_eq!(added, 3);
+    let user = User {
+        email: None,
+        ..user
+    };
+    let added: u64 = redis!(HSET user:{id} {*user}).query(&mut con).unwrap();
+    assert_eq!(added, 0);
+}
+
+#[test]
+fn test_redis_hash_read() {
+    let reply = Value::Bulk(vec![
+        data("visits"),
+        data("7"),
+        data("name"),
+        data("bob"),
+        data("mail"),
+        data("bob@example.com"),
+        data("session"),
+        data("ignored"),
+    ]);
+    let mut con = MockRedisConnection::new(vec![MockCmd::new(
+        redis::cmd("HGETALL").arg("user:7"),
+        Ok(reply),
+    )]);
+    let user: User = redis!(HGETALL user:7).query(&mut con).unwrap();
+    assert_eq!(
+        user,
+        User {
+            name: "bob".to_owned(),
+            email: Some("bob@example.com".to_owned()),
+            visits: 7,
+            session: None,
+        }
+    );
+
+    // Hashes written before `visits` was added
+    let user = User::from_redis_value(&Value::Bulk(vec![data("name"), data("carol")])).unwrap();
+    assert_eq!(user.visits, 0);
+    assert_eq!(user.email, None);
+}
+
+#[test]
+fn test_redis_hash_errors() {
+    // HGETALL replies with no fields for a missing key
+    let err = User::from_redis_value(&Value::Bulk(vec![])).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::TypeError);
+    assert!(err.to_string().contains("name"), "{}", err);
+    let err = User::from_redis_value(&Value::Bulk(vec![data("name")])).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::TypeError);
+}
@@ -0,0 +1,66 @@
+use redis_rs_macro::{redis, RedisArg};
+use redis_test::{MockCmd, MockRedisConnection};
+
+#[derive(RedisArg, Clone, Copy)]
+#[redis(rename_all = "UPPERCASE")]
+enum OrderStatus {
+    Pending,
+    Shipped,
+    #[redis(rename = "cancelled")]
+    Canceled,
+}
+
+#[derive(RedisArg)]
+#[redis(rename_all = "kebab-case")]
+enum Region {
+    EuWest,
+    UsEast,
+}
+
+#[derive(RedisArg)]
+enum Side {
+    Left,
+}
+
+#[test]
+fn test_redis_arg() {
+    let id = 42;
+    let statuses = [OrderStatus::Shipped, OrderStatus::Canceled];
+    let region = Region::EuWest;
+    let mut con = MockRedisConnection::new(vec![
+        MockCmd::new(
+            redis::cmd("HSET")
+                .arg("order:42")
+                .arg("status")
+                .arg("PENDING")
+                .arg("region")
+                .arg("eu-west"),
+            Ok(2),
+        ),
+        MockCmd::new(
+            redis::cmd("SADD")
+                .arg("statuses")
+                .arg("SHIPPED")
+                .arg("cancelled"),
+            Ok(2),
+        ),
+        MockCmd::new(
+            redis::cmd("LMOVE")
+                .arg("a:us-east")
+                .arg("b")
+                .arg("Left")
+                .arg("Left"),
+            Ok(""),
+        ),
+    ]);
+    let status = OrderStatus::Pending;
+    redis!(HSET order:{id} status {status} region {region})
+        .query::<u64>(&mut con)
+        .unwrap();
+    redis!(SADD statuses {..statuses})
+        .query::<u64>(&mut con)
+        .unwrap();
+    redis!(LMOVE a:{Region::UsEast} b {Side::Left} {Side::Left})
+        .query::<String>(&mut con)
+        .unwrap();
+}
@@ -0,0 +1,132 @@
+use redis::{ErrorKind, FromRedisValue, Value};
+use redis_rs_macro::{redis, RedisReply};
+use redis_test::{MockCmd, MockRedisConnection};
+
+mod common;
+
+use common::data;
+
+#[derive(RedisReply, Debug, PartialEq)]
+struct Limits {
+    maxmemory: u64,
+    #[redis(rename = "maxmemory-policy")]
+    policy: String,
+    #[redis(default)]
+    maxclients: u64,
+    timeout: Option<u64>,
+}
+
+#[derive(RedisReply, Debug, PartialEq)]
+struct Entry(String, Vec<(String, String)>);
+
+#[derive(RedisReply, Debug, PartialEq)]
+#[redis(positional)]
+struct Sample {
+    timestamp: u64,
+    value: f64,
+    #[redis(default)]
+    label: String,
+}
+
+#[test]
+fn test_redis_reply_by_name() {
+    let reply = Value::Bulk(vec![
+        data("maxmemory-policy"),
+        data("allkeys-lru"),
+        data("maxmemory"),
+        data("1048576"),
+        data("appendonly"),
+        data("no"),
+    ]);
+    let mut con = MockRedisConnection::new(vec![MockCmd::new(
+        redis::cmd("CONFIG")
+            .arg("GET")
+            .arg("maxmemory")
+            .arg("maxmemory-policy")
+            .arg("appendonly"),
+        Ok(reply),
+    )]);
+    let limits: Limits = redis!(CONFIG GET maxmemory maxmemory-policy appendonly)
+        .query(&mut con)
+        .unwrap();
+    assert_eq!(
+        limits,
+        Limits {
+            maxmemory: 1048576,
+            policy: "allkeys-lru".to_owned(),
+            maxclients: 0,
+            timeout: None,
+        }
+    );
+}
+
+#[test]
+fn test_redis_reply_positional() {
+    let reply = Value::Bulk(vec![Value::Bulk(vec![
+        data("1700000000000-0"),
+        Value::Bulk(vec![data("kind"), data("login")]),
+    ])]);
+    let mut con = MockRedisConnection::new(vec![MockCmd::new(
+        redis::cmd("XRANGE").arg("events").arg("-").arg("+"),
+        Ok(reply),
+    )]);
+    let entries: Vec<Entry> = redis!(XRANGE events - +).query(&mut con).unwrap();
+    assert_eq!(
+        entries,
+        [Entry(
+            "1700000000000-0".to_owned(),
+            vec![("kind".to_owned(), "login".to_owned())]
+        )]
+    );
+
+    let sample = Sample::from_redis_value(&Value::Bulk(vec![
+        Value::Int(1700000000),
+        Value::Status("21.5".to_owned()),
+    ]))
+    .unwrap();
+    assert_eq!(
+        sample,
+        Sample {
+            timestamp: 1700000000,
+            value: 21.5,
+            label: String::new(),
+        }
+    );
+}
+
+#[test]
+fn test_redis_reply_errors() {
+    let err = Limits::from_redis_value(&Value::Bulk(vec![
+        data("maxmemory"),
+        data("lots"),
+        data("maxmemory-policy"),
+        data("noeviction"),
+    ]))
+    .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::TypeError);
+    assert!(err.to_string().contains("`maxmemory`"), "{}", err);
+
+    let err =
+        Limits::from_redis_value(&Value::Bulk(vec![data("maxmemory"), data("1")])).unwrap_err();
+    assert!(err.to_string().contains("maxmemory-policy"), "{}", err);
+
+    let err = Sample::from_redis_value(&Value::Bulk(vec![Value::Int(1)])).unwrap_err();
+    assert!(err.to_string().contains("missing field"), "{}", err);
+    assert!(err.to_string().contains("value"), "{}", err);
+
+    let err = Sample::from_redis_value(&Value::Bulk(vec![Value::Int(1); 4])).unwrap_err();
+    assert!(
+        err.to_string().contains("expected at most 3, got 4"),
+        "{}",
+        err
+    );
+
+    let err = Entry::from_redis_value(&Value::Bulk(vec![data("1-0"), Value::Int(3)])).unwrap_err();
+    assert!(err.to_string().contains("`1`"), "{}", err);
+    let err = Entry::from_redis_value(&Value::Int(3)).unwrap_err();
+    assert!(
+        err.to_string().contains("expected an array reply"),
+        "{}",
+        err
+    );
+}
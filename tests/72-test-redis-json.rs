@@ -0,0 +1,98 @@
+#![cfg(feature = "json")]
+
+use redis::{ErrorKind, Value};
+use redis_rs_macro::RedisJson;
+use redis_test::{MockCmd, MockRedisConnection};
+use serde::{Deserialize, Serialize};
+
+mod common;
+
+use common::data;
+
+#[derive(RedisJson, Serialize, Deserialize, Debug, PartialEq)]
+struct Cart {
+    items: Vec<String>,
+    total: f64,
+}
+
+#[derive(RedisJson, Serialize, Deserialize, Debug, PartialEq)]
+struct Tagged<T> {
+    tag: T,
+}
+
+#[test]
+fn test_redis_json_set() {
+    let mut con = MockRedisConnection::new(vec![
+        MockCmd::new(
+            redis::cmd("JSON.SET")
+                .arg("cart:42")
+                .arg("$")
+                .arg(r#"{"items":["book"],"total":12.5}"#),
+            Ok("OK"),
+        ),
+        MockCmd::new(
+            redis::cmd("JSON.SET")
+                .arg("doc")
+                .arg("$.meta")
+                .arg(r#"{"tag":7}"#),
+            Ok("OK"),
+        ),
+    ]);
+    let cart = Cart {
+        items: vec!["book".to_owned()],
+        total: 12.5,
+    };
+    let id = 42;
+    cart.json_set(&mut con, format!("cart:{}", id), "$")
+        .unwrap();
+    Tagged { tag: 7 }
+        .json_set(&mut con, "doc", "$.meta")
+        .unwrap();
+}
+
+#[test]
+fn test_redis_json_get() {
+    let mut con = MockRedisConnection::new(vec![
+        MockCmd::new(
+            redis::cmd("JSON.GET").arg("cart:1").arg("$"),
+            Ok(data(r#"[{"items":[],"total":0.0}]"#)),
+        ),
+        MockCmd::new(
+            redis::cmd("JSON.GET").arg("cart:1").arg("."),
+            Ok(data(r#"{"items":["pen"],"total":1.5}"#)),
+        ),
+        MockCmd::new(
+            redis::cmd("JSON.GET").arg("cart:2").arg("$"),
+            Ok(Value::Nil),
+        ),
+        MockCmd::new(
+            redis::cmd("JSON.GET").arg("doc").arg("$.missing"),
+            Ok(data("[]")),
+        ),
+        MockCmd::new(
+            redis::cmd("JSON.GET").arg("doc").arg("$"),
+            Ok(data(r#"[{"tag":"x"}]"#)),
+        ),
+    ]);
+    assert_eq!(
+        Cart::json_get(&mut con, "cart:1", "$").unwrap(),
+        Some(Cart {
+            items: vec![],
+            total: 0.0,
+        })
+    );
+    assert_eq!(
+        Cart::json_get(&mut con, "cart:1", ".").unwrap(),
+        Some(Cart {
+            items: vec!["pen".to_owned()],
+            total: 1.5,
+        })
+    );
+    assert_eq!(Cart::json_get(&mut con, "cart:2", "$").unwrap(), None);
+    assert_eq!(
+        Tagged::<u8>::json_get(&mut con, "doc", "$.missing").unwrap(),
+        None
+    );
+    let err = Tagged::<u8>::json_get(&mut con, "doc", "$").unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::TypeError);
+}
@@ -0,0 +1,34 @@
+use redis_rs_macro::redis;
+
+#[test]
+fn test_json_paths() {
+    let id = 42;
+    let cmd = redis!(JSON.GET cart:{id} INDENT "  " $.items[0].name $..price "$['unit price']");
+    assert_eq!(
+        cmd.get_packed_command(),
+        redis::cmd("JSON.GET")
+            .arg("cart:42")
+            .arg("INDENT")
+            .arg("  ")
+            .arg("$.items[0].name")
+            .arg("$..price")
+            .arg("$['unit price']")
+            .get_packed_command()
+    );
+    let path = "$.items";
+    assert_eq!(
+        redis!(JSON.ARRAPPEND cart:{id} {path} "\"pen\"").get_packed_command(),
+        redis::cmd("JSON.ARRAPPEND")
+            .arg("cart:42")
+            .arg("$.items")
+            .arg("\"pen\"")
+            .get_packed_command()
+    );
+    assert_eq!(
+        redis!(JSON.DEL cart:{id} $.items[?(@.qty==0)]).get_packed_command(),
+        redis::cmd("JSON.DEL")
+            .arg("cart:42")
+            .arg("$.items[?(@.qty==0)]")
+            .get_packed_command()
+    );
+}
@@ -0,0 +1,90 @@
+use redis_rs_macro::{RedisHash, RedisSearch};
+
+#[allow(dead_code)]
+#[derive(RedisHash, RedisSearch)]
+#[search(prefix = "product:", prefix = "item:")]
+struct Product {
+    #[search(weight = 2.5, sortable)]
+    name: String,
+    #[redis(rename = "desc", default)]
+    #[search(nostem)]
+    description: String,
+    price: f64,
+    stock: Option<u32>,
+    #[search(tag, separator = "|")]
+    tags: String,
+    #[search(geo)]
+    location: String,
+    #[redis(skip)]
+    cached: Vec<u8>,
+    #[search(skip)]
+    image: Vec<u8>,
+}
+
+#[allow(dead_code)]
+#[derive(RedisSearch)]
+#[search(on = "json")]
+struct User {
+    #[search(tag, sortable)]
+    name: String,
+    #[search(numeric, sortable, noindex)]
+    age: u8,
+}
+
+#[test]
+fn test_redis_search_hash() {
+    let expected = redis::cmd("FT.CREATE")
+        .arg("idx:products")
+        .arg("ON")
+        .arg("HASH")
+        .arg("PREFIX")
+        .arg(2)
+        .arg("product:")
+        .arg("item:")
+        .arg("SCHEMA")
+        .arg("name")
+        .arg("TEXT")
+        .arg("WEIGHT")
+        .arg("2.5")
+        .arg("SORTABLE")
+        .arg("desc")
+        .arg("TEXT")
+        .arg("NOSTEM")
+        .arg("price")
+        .arg("NUMERIC")
+        .arg("stock")
+        .arg("NUMERIC")
+        .arg("tags")
+        .arg("TAG")
+        .arg("SEPARATOR")
+        .arg("|")
+        .arg("location")
+        .arg("GEO")
+        .get_packed_command();
+    assert_eq!(
+        Product::ft_create("idx:products").get_packed_command(),
+        expected
+    );
+}
+
+#[test]
+fn test_redis_search_json() {
+    let expected = redis::cmd("FT.CREATE")
+        .arg("idx:users")
+        .arg("ON")
+        .arg("JSON")
+        .arg("SCHEMA")
+        .arg("$.name")
+        .arg("AS")
+        .arg("name")
+        .arg("TAG")
+        .arg("SORTABLE")
+        .arg("$.age")
+        .arg("AS")
+        .arg("age")
+        .arg("NUMERIC")
+        .arg("SORTABLE")
+        .arg("NOINDEX")
+        .get_packed_command();
+    assert_eq!(User::ft_create("idx:users").get_packed_command(), expected);
+}
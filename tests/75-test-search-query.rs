@@ -0,0 +1,218 @@
+use redis::{ErrorKind, Value};
+use redis_rs_macro::{redis_search, AggregateResults, RedisReply, SearchDoc, SearchResults};
+use redis_test::{MockCmd, MockRedisConnection};
+
+mod common;
+
+use common::data;
+
+#[derive(RedisReply, Debug, PartialEq)]
+struct Product {
+    name: String,
+    price: f64,
+}
+
+#[derive(RedisReply, Debug, PartialEq)]
+struct Brand {
+    brand: String,
+    total: f64,
+}
+
+fn fields(pairs: &[&str]) -> Value {
+    Value::Bulk(pairs.iter().map(data).collect())
+}
+
+#[test]
+fn test_redis_search_params() {
+    let name = "laptop".to_string();
+    let max = 1000;
+    let mut con = MockRedisConnection::new(vec![MockCmd::new(
+        redis::cmd("FT.SEARCH")
+            .arg("idx:products")
+            .arg("@name:$name @price:[0 $max]")
+            .arg("WITHSCORES")
+            .arg("RETURN")
+            .arg(2)
+            .arg("name")
+            .arg("price")
+            .arg("SORTBY")
+            .arg("price")
+            .arg("ASC")
+            .arg("LIMIT")
+            .arg(0)
+            .arg(10)
+            .arg("PARAMS")
+            .arg(4)
+            .arg("name")
+            .arg("laptop")
+            .arg("max")
+            .arg(1000)
+            .arg("DIALECT")
+            .arg(2),
+        Ok(Value::Bulk(vec![
+            Value::Int(2),
+            data("product:1"),
+            data("1.5"),
+            fields(&["name", "laptop pro", "price", "999.5"]),
+            data("product:2"),
+            data("0.5"),
+            fields(&["price", "450", "name", "laptop air"]),
+        ])),
+    )]);
+    let results = redis_search!(
+        FT.SEARCH idx:products "@name:$name @price:[0 $max]"
+        WITHSCORES RETURN 2 name price SORTBY price ASC LIMIT 0 10 -> Product
+    )
+    .query(&mut con)
+    .unwrap();
+    assert_eq!(
+        results,
+        SearchResults {
+            total: 2,
+            docs: vec![
+                SearchDoc {
+                    id: "product:1".into(),
+                    score: Some(1.5),
+                    payload: None,
+                    sort_key: None,
+                    value: Product {
+                        name: "laptop pro".into(),
+                        price: 999.5,
+                    },
+                },
+                SearchDoc {
+                    id: "product:2".into(),
+                    score: Some(0.5),
+                    payload: None,
+                    sort_key: None,
+                    value: Product {
+                        name: "laptop air".into(),
+                        price: 450.0,
+                    },
+                },
+            ],
+        }
+    );
+    // The parameters are borrowed
+    assert_eq!(name, "laptop");
+}
+
+#[test]
+fn test_redis_search_layout() {
+    let mut con = MockRedisConnection::new(vec![
+        MockCmd::new(
+            redis::cmd("FT.SEARCH")
+                .arg("idx")
+                .arg("@tags:{sale}")
+                .arg("NOCONTENT")
+                .arg("WITHSORTKEYS")
+                .arg("SORTBY")
+                .arg("price"),
+            Ok(Value::Bulk(vec![
+                Value::Int(3),
+                data("product:1"),
+                data("#12"),
+                data("product:2"),
+                Value::Nil,
+            ])),
+        ),
+        MockCmd::new(
+            redis::cmd("FT.SEARCH")
+                .arg("idx")
+                .arg("*")
+                .arg("WITHSCORES"),
+            Ok(Value::Bulk(vec![Value::Int(1), data("product:1")])),
+        ),
+    ]);
+    let results = redis_search!(
+        FT.SEARCH idx "@tags:{sale}" NOCONTENT WITHSORTKEYS SORTBY price -> ()
+    )
+    .query(&mut con)
+    .unwrap();
+    assert_eq!(results.total, 3);
+    let docs: Vec<_> = results
+        .docs
+        .iter()
+        .map(|doc| (doc.id.as_str(), doc.sort_key.as_deref()))
+        .collect();
+    assert_eq!(docs, [("product:1", Some("#12")), ("product:2", None)]);
+
+    let err = redis_search!(FT.SEARCH idx "*" WITHSCORES -> Product)
+        .query(&mut con)
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::TypeError);
+    assert!(err.to_string().contains("missing the score"), "{}", err);
+}
+
+#[test]
+fn test_redis_search_aggregate() {
+    let mut con = MockRedisConnection::new(vec![
+        MockCmd::new(
+            redis::cmd("FT.AGGREGATE")
+                .arg("idx:products")
+                .arg("@name:$name")
+                .arg("GROUPBY")
+                .arg(1)
+                .arg("@brand")
+                .arg("REDUCE")
+                .arg("SUM")
+                .arg(1)
+                .arg("@price")
+                .arg("AS")
+                .arg("total")
+                .arg("PARAMS")
+                .arg(2)
+                .arg("name")
+                .arg("laptop")
+                .arg("DIALECT")
+                .arg(3),
+            Ok(Value::Bulk(vec![
+                Value::Int(1),
+                fields(&["brand", "acme", "total", "1449.5"]),
+            ])),
+        ),
+        MockCmd::new(
+            redis::cmd("FT.AGGREGATE")
+                .arg("idx:products")
+                .arg("*")
+                .arg("LOAD")
+                .arg("*")
+                .arg("WITHCURSOR")
+                .arg("COUNT")
+                .arg(1),
+            Ok(Value::Bulk(vec![
+                Value::Bulk(vec![
+                    Value::Int(2),
+                    fields(&["brand", "acme", "total", "2"]),
+                ]),
+                Value::Int(42),
+            ])),
+        ),
+    ]);
+    let name = "laptop";
+    let brands = redis_search!(
+        FT.AGGREGATE idx:products "@name:$name"
+        GROUPBY 1 @brand REDUCE SUM 1 @price AS total DIALECT 3 -> Brand
+    )
+    .query(&mut con)
+    .unwrap();
+    assert_eq!(
+        brands,
+        AggregateResults {
+            total: 1,
+            rows: vec![Brand {
+                brand: "acme".into(),
+                total: 1449.5,
+            }],
+            cursor: None,
+        }
+    );
+
+    let brands = redis_search!(
+        FT.AGGREGATE idx:products "*" LOAD "*" WITHCURSOR COUNT 1 -> Brand
+    )
+    .query(&mut con)
+    .unwrap();
+    assert_eq!(brands.cursor, Some(42));
+    assert_eq!(brands.rows.len(), 1);
+}
@@ -0,0 +1,141 @@
+use redis::{FromRedisValue, Value};
+use redis_rs_macro::{redis, Samples, Series};
+use redis_test::{MockCmd, MockRedisConnection};
+use std::collections::BTreeMap;
+use std::time::{Duration, SystemTime};
+
+mod common;
+
+use common::data;
+
+fn sample(timestamp: i64, value: &str) -> Value {
+    Value::Bulk(vec![Value::Int(timestamp), data(value)])
+}
+
+fn at(millis: u64) -> SystemTime {
+    SystemTime::UNIX_EPOCH + Duration::from_millis(millis)
+}
+
+#[test]
+fn test_timeseries_times() {
+    let labels = BTreeMap::from([("room", "kitchen"), ("sensor", "temp")]);
+    let retention = Duration::from_secs(86400);
+    assert_eq!(
+        redis!(TS.CREATE temp:1 RETENTION {retention} DUPLICATE_POLICY last LABELS {*labels})
+            .get_packed_command(),
+        redis::cmd("TS.CREATE")
+            .arg("temp:1")
+            .arg("RETENTION")
+            .arg(86400000)
+            .arg("DUPLICATE_POLICY")
+            .arg("last")
+            .arg("LABELS")
+            .arg("room")
+            .arg("kitchen")
+            .arg("sensor")
+            .arg("temp")
+            .get_packed_command()
+    );
+    assert_eq!(
+        redis!(TS.ADD temp:1 {at(1_700_000_000_123)} 21.5).get_packed_command(),
+        redis::cmd("TS.ADD")
+            .arg("temp:1")
+            .arg(1_700_000_000_123u64)
+            .arg("21.5")
+            .get_packed_command()
+    );
+    // Other timestamps are passed through
+    assert_eq!(
+        redis!(TS.ADD temp:1 * 21.5).get_packed_command(),
+        redis::cmd("TS.ADD")
+            .arg("temp:1")
+            .arg("*")
+            .arg("21.5")
+            .get_packed_command()
+    );
+    assert_eq!(
+        redis!(TS.MADD a {at(1000)} 1 b {2000} 2).get_packed_command(),
+        redis::cmd("TS.MADD")
+            .arg("a")
+            .arg(1000)
+            .arg(1)
+            .arg("b")
+            .arg(2000)
+            .arg(2)
+            .get_packed_command()
+    );
+    let bucket = Duration::from_secs(60);
+    assert_eq!(
+        redis!(TS.MRANGE {at(1000)} + AGGREGATION max {bucket} FILTER sensor=temp room!=(hall,attic))
+            .get_packed_command(),
+        redis::cmd("TS.MRANGE")
+            .arg(1000)
+            .arg("+")
+            .arg("AGGREGATION")
+            .arg("max")
+            .arg(60000)
+            .arg("FILTER")
+            .arg("sensor=temp")
+            .arg("room!=(hall,attic)")
+            .get_packed_command()
+    );
+}
+
+#[test]
+fn test_timeseries_range() {
+    let mut con = MockRedisConnection::new(vec![MockCmd::new(
+        redis::cmd("TS.RANGE")
+            .arg("temp:1")
+            .arg(1000)
+            .arg(3000)
+            .arg("AGGREGATION")
+            .arg("avg")
+            .arg(1000),
+        Ok(Value::Bulk(vec![sample(1000, "20.5"), sample(2000, "21")])),
+    )]);
+    let samples = redis!(TS.RANGE temp:1 {at(1000)} {at(3000)} AGGREGATION avg {Duration::from_secs(1)} -> Samples)
+        .query(&mut con)
+        .unwrap();
+    assert_eq!(samples, Samples(vec![(at(1000), 20.5), (at(2000), 21.0)]));
+    let values: Vec<f64> = samples.into_iter().map(|(_, value)| value).collect();
+    assert_eq!(values, [20.5, 21.0]);
+
+    let err = Samples::from_redis_value(&Value::Bulk(vec![sample(1000, "warm")])).unwrap_err();
+    assert_eq!(err.kind(), redis::ErrorKind::TypeError);
+}
+
+#[test]
+fn test_timeseries_mrange() {
+    let reply = Value::Bulk(vec![
+        Value::Bulk(vec![
+            data("temp:1"),
+            Value::Bulk(vec![
+                Value::Bulk(vec![data("sensor"), data("temp")]),
+                Value::Bulk(vec![data("room"), Value::Nil]),
+            ]),
+            Value::Bulk(vec![sample(1000, "20.5")]),
+        ]),
+        Value::Bulk(vec![
+            data("temp:2"),
+            Value::Bulk(vec![]),
+            Value::Bulk(vec![]),
+        ]),
+    ]);
+    let series: Vec<Series> = FromRedisValue::from_redis_value(&reply).unwrap();
+    assert_eq!(
+        series,
+        [
+            Series {
+                key: "temp:1".into(),
+                labels: vec![("sensor".into(), "temp".into()), ("room".into(), "".into())],
+                samples: Samples(vec![(at(1000), 20.5)]),
+            },
+            Series {
+                key: "temp:2".into(),
+                labels: vec![],
+                samples: Samples(vec![]),
+            },
+        ]
+    );
+    assert!(Series::from_redis_value(&Value::Bulk(vec![data("temp:1")])).is_err());
+}
@@ -0,0 +1,115 @@
+use redis::Value;
+use redis_rs_macro::redis_bloom;
+use redis_test::{MockCmd, MockRedisConnection};
+
+mod common;
+
+use common::data;
+
+#[test]
+fn test_redis_bloom_filter() {
+    let capacity: u64 = 1000;
+    let names = vec!["alice", "bob"];
+    let mut con = MockRedisConnection::new(vec![
+        MockCmd::new(
+            redis::cmd("BF.RESERVE")
+                .arg("seen")
+                .arg("0.001")
+                .arg(1000)
+                .arg("EXPANSION")
+                .arg("2"),
+            Ok("OK"),
+        ),
+        MockCmd::new(
+            redis::cmd("BF.MADD").arg("seen").arg("alice").arg("bob"),
+            Ok(Value::Bulk(vec![Value::Int(1), Value::Int(0)])),
+        ),
+        MockCmd::new(
+            redis::cmd("BF.MEXISTS")
+                .arg("seen")
+                .arg("alice")
+                .arg("bob")
+                .arg("carol"),
+            Ok(Value::Bulk(vec![
+                Value::Int(1),
+                Value::Int(1),
+                Value::Int(0),
+            ])),
+        ),
+        MockCmd::new(
+            redis::cmd("BF.INSERT")
+                .arg("seen")
+                .arg("ERROR")
+                .arg(0.01)
+                .arg("ITEMS")
+                .arg("dave"),
+            Ok(Value::Bulk(vec![Value::Int(1)])),
+        ),
+        MockCmd::new(redis::cmd("CF.EXISTS").arg("cuckoo").arg("alice"), Ok(1)),
+    ]);
+    redis_bloom!(BF.RESERVE seen 0.001 {capacity} EXPANSION 2)
+        .query(&mut con)
+        .unwrap();
+    let added = redis_bloom!(BF.MADD seen {..&names})
+        .query(&mut con)
+        .unwrap();
+    assert_eq!(added, [true, false]);
+    let seen = redis_bloom!(BF.MEXISTS seen {..names} carol)
+        .query(&mut con)
+        .unwrap();
+    assert_eq!(seen, [true, true, false]);
+    let rate = 0.01;
+    let inserted = redis_bloom!(BF.INSERT seen ERROR {rate} ITEMS dave)
+        .query(&mut con)
+        .unwrap();
+    assert_eq!(inserted, [true]);
+    assert!(redis_bloom!(CF.EXISTS cuckoo alice)
+        .query(&mut con)
+        .unwrap());
+}
+
+#[test]
+fn test_redis_bloom_sketches() {
+    let mut con = MockRedisConnection::new(vec![
+        MockCmd::new(
+            redis::cmd("CMS.INCRBY")
+                .arg("views")
+                .arg("page:1")
+                .arg(3)
+                .arg("page:2")
+                .arg("1"),
+            Ok(Value::Bulk(vec![Value::Int(3), Value::Int(1)])),
+        ),
+        MockCmd::new(
+            redis::cmd("TOPK.ADD").arg("top").arg("a").arg("b"),
+            Ok(Value::Bulk(vec![Value::Nil, data("c")])),
+        ),
+        MockCmd::new(
+            redis::cmd("TDIGEST.QUANTILE")
+                .arg("latency")
+                .arg("0.5")
+                .arg(0.99),
+            Ok(Value::Bulk(vec![data("12.5"), data("inf")])),
+        ),
+        MockCmd::new(
+            redis::cmd("BF.INFO").arg("seen").arg("CAPACITY"),
+            Ok(Value::Bulk(vec![Value::Int(1000)])),
+        ),
+    ]);
+    let views: i64 = 3;
+    let counts = redis_bloom!(CMS.INCRBY views page:1 {views} page:2 1)
+        .query(&mut con)
+        .unwrap();
+    assert_eq!(counts, [3, 1]);
+    let dropped = redis_bloom!(TOPK.ADD top a b).query(&mut con).unwrap();
+    assert_eq!(dropped, [None, Some("c".to_string())]);
+    let q = 0.99;
+    let quantiles = redis_bloom!(TDIGEST.QUANTILE latency 0.5 {q})
+        .query(&mut con)
+        .unwrap();
+    assert_eq!(quantiles, [12.5, f64::INFINITY]);
+    let capacity = redis_bloom!(BF.INFO seen CAPACITY -> Vec<u64>)
+        .query(&mut con)
+        .unwrap();
+    assert_eq!(capacity, [1000]);
+}
@@ -0,0 +1,85 @@
+#![cfg(feature = "generator")]
+
+use redis::Value;
+use redis_rs_macro::{redis_meta, CommandGen, ReplyShape};
+use redis_test::{MockCmd, MockRedisConnection};
+
+#[test]
+fn test_generator_valid() {
+    for generated in CommandGen::new(7).take(1000) {
+        let meta = generated.meta;
+        let args: Vec<Vec<u8>> = generated
+            .cmd
+            .args_iter()
+            .map(|arg| match arg {
+                redis::Arg::Simple(arg) => arg.to_vec(),
+                redis::Arg::Cursor => panic!("unexpected cursor"),
+            })
+            .collect();
+        let name = meta.name.replace(' ', "");
+        assert_eq!(
+            args[..meta.name.split(' ').count()].concat(),
+            name.as_bytes()
+        );
+        if meta.arity > 0 {
+            assert_eq!(args.len(), meta.arity as usize, "{}", meta.name);
+        } else {
+            assert!(
+                args.len() >= meta.arity.unsigned_abs() as usize,
+                "{}",
+                meta.name
+            );
+        }
+        if meta.first_key > 0 {
+            assert!(args[meta.first_key].starts_with(b"key:"), "{}", meta.name);
+        }
+        assert_eq!(generated.reply, ReplyShape::of(meta.name));
+    }
+}
+
+#[test]
+fn test_generator_seeded() {
+    let packed = |seed| {
+        CommandGen::new(seed)
+            .take(20)
+            .map(|generated| generated.cmd.get_packed_command())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(packed(1), packed(1));
+    assert_ne!(packed(1), packed(2));
+    assert!(CommandGen::commands().any(|meta| *meta == redis_meta!(MSET)));
+    assert!(CommandGen::commands().all(|meta| !meta.movable_keys));
+}
+
+#[test]
+fn test_generator_mset_pairs() {
+    let mset = CommandGen::new(3)
+        .take(10_000)
+        .filter(|generated| generated.meta.name == "MSET")
+        .take(10)
+        .collect::<Vec<_>>();
+    assert!(!mset.is_empty());
+    for generated in mset {
+        assert_eq!(generated.cmd.args_iter().count() % 2, 1);
+    }
+}
+
+#[test]
+fn test_generator_reply_shapes() {
+    assert_eq!(ReplyShape::of("INCR"), ReplyShape::Int);
+    assert_eq!(ReplyShape::of("XGROUP CREATE"), ReplyShape::Status);
+    assert_eq!(ReplyShape::of("SET"), ReplyShape::Any);
+    assert!(ReplyShape::Bulk.matches(&Value::Nil));
+    assert!(!ReplyShape::Int.matches(&Value::Okay));
+
+    let commands: Vec<_> = CommandGen::new(11).take(50).collect();
+    let mut con = MockRedisConnection::new(
+        commands
+            .iter()
+            .map(|generated| MockCmd::new(generated.cmd.clone(), Ok(generated.reply.example()))),
+    );
+    for generated in &commands {
+        let reply: Value = generated.cmd.query(&mut con).unwrap();
+        assert!(generated.reply.matches(&reply), "{}", generated.meta.name);
+    }
+}
@@ -0,0 +1,116 @@
+//! Helpers shared by the integration tests. Each test crate uses only some of them.
+#![allow(dead_code, unused_imports)]
+
+use redis::{ConnectionLike, RedisResult, Value};
+use std::collections::VecDeque;
+
+#[cfg(feature = "async")]
+pub use self::aio::AsyncMock;
+
+/// A bulk string reply
+pub fn data<T: AsRef<[u8]>>(value: T) -> Value {
+    Value::Data(value.as_ref().to_vec())
+}
+
+/// Records the commands it receives, and replies with the given replies in order, or with `OK`
+/// to everything when made with `default`
+#[derive(Default)]
+pub struct StubConnection {
+    pub sent: Vec<Vec<u8>>,
+    replies: Option<VecDeque<Value>>,
+}
+
+impl StubConnection {
+    pub fn new(replies: Vec<Value>) -> StubConnection {
+        StubConnection {
+            sent: vec![],
+            replies: Some(replies.into()),
+        }
+    }
+
+    /// The names of the sent commands
+    pub fn names(&self) -> Vec<String> {
+        self.sent
+            .iter()
+            .map(|cmd| {
+                let parts: Vec<_> = cmd.split(|b| *b == b'\n').collect();
+                String::from_utf8_lossy(parts[2]).trim_end().to_string()
+            })
+            .collect()
+    }
+
+    /// Whether the command at `index` contains `bytes`
+    pub fn sent_contains(&self, index: usize, bytes: &[u8]) -> bool {
+        self.sent[index].windows(bytes.len()).any(|w| w == bytes)
+    }
+
+    fn reply(&mut self) -> Value {
+        match &mut self.replies {
+            Some(replies) => replies.pop_front().expect("no reply left"),
+            None => Value::Okay,
+        }
+    }
+}
+
+impl ConnectionLike for StubConnection {
+    fn req_packed_command(&mut self, cmd: &[u8]) -> RedisResult<Value> {
+        self.sent.push(cmd.to_vec());
+        Ok(self.reply())
+    }
+
+    fn req_packed_commands(&mut self, cmd: &[u8], _: usize, n: usize) -> RedisResult<Vec<Value>> {
+        self.sent.push(cmd.to_vec());
+        Ok((0..n).map(|_| self.reply()).collect())
+    }
+
+    fn get_db(&self) -> i64 {
+        0
+    }
+
+    fn check_connection(&mut self) -> bool {
+        true
+    }
+
+    fn is_open(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(feature = "async")]
+mod aio {
+    use redis::{Cmd, ConnectionLike, Pipeline, RedisFuture, Value};
+    use redis_test::{MockCmd, MockRedisConnection};
+
+    /// A `MockRedisConnection`, or another blocking connection, for async connections, whose
+    /// futures are ready right away
+    pub struct AsyncMock<C = MockRedisConnection>(pub C);
+
+    impl AsyncMock {
+        pub fn new(commands: Vec<MockCmd>) -> AsyncMock {
+            AsyncMock(MockRedisConnection::new(commands))
+        }
+    }
+
+    impl<C: ConnectionLike + Send> redis::aio::ConnectionLike for AsyncMock<C> {
+        fn req_packed_command<'a>(&'a mut self, cmd: &'a Cmd) -> RedisFuture<'a, Value> {
+            let reply = self.0.req_packed_command(&cmd.get_packed_command());
+            Box::pin(std::future::ready(reply))
+        }
+
+        fn req_packed_commands<'a>(
+            &'a mut self,
+            pipe: &'a Pipeline,
+            offset: usize,
+            count: usize,
+        ) -> RedisFuture<'a, Vec<Value>> {
+            let replies = self
+                .0
+                .req_packed_commands(&pipe.get_packed_pipeline(), offset, count);
+            Box::pin(std::future::ready(replies))
+        }
+
+        fn get_db(&self) -> i64 {
+            0
+        }
+    }
+}
@@ -0,0 +1,13 @@
+#!lua name=orders
+
+local function add(keys, args)
+    return redis.call('INCRBY', keys[1], args[1])
+end
+
+redis.register_function('add', add)
+
+redis.register_function{
+    function_name = 'total',
+    callback = function(keys) return tonumber(redis.call('GET', keys[1])) or 0 end,
+    flags = { 'no-writes' },
+}